@@ -19,7 +19,7 @@ where
             .await
             .map_err(|_| StatusCode::UNAUTHORIZED)?;
 
-        if user.role != "platform_admin" {
+        if !user.role().is_platform_level() {
             return Err(StatusCode::FORBIDDEN);
         }
 
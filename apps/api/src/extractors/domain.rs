@@ -14,12 +14,12 @@ pub struct RequireDomainEditor(pub RequireDomainRole);
 pub struct RequireDomainAdmin(pub RequireDomainRole);
 
 // Helper function for permission checking
-fn check_domain_permission(
+pub(crate) fn check_domain_permission(
     user: &UserContext,
     domain_id: i32,
     required_role: &str,
 ) -> Result<(), StatusCode> {
-    if user.role == "platform_admin" {
+    if user.role().is_platform_level() {
         return Ok(());
     }
 
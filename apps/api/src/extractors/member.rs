@@ -0,0 +1,58 @@
+use crate::handlers::auth::validate_member_token_with;
+use crate::services::{members, post_visibility::Reader};
+use crate::{AppState, DomainContext};
+use axum::{
+    extract::{Extension, FromRequestParts},
+    http::{StatusCode, header, request::Parts},
+};
+use std::sync::Arc;
+
+/// The reader of a public, domain-scoped request, from the member token in
+/// `Authorization: Bearer`. Never rejects the request: a missing token is an
+/// anonymous reader and a bad one a [`Reader::Rejected`], so the handler
+/// decides what they may see.
+pub struct MemberReader(pub Reader);
+
+impl FromRequestParts<Arc<AppState>> for MemberReader {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::to_owned);
+        let Some(token) = token else {
+            return Ok(MemberReader(Reader::Anonymous));
+        };
+
+        let Extension(domain) = Extension::<DomainContext>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let claims = match validate_member_token_with(&token, &state.jwt_config()) {
+            Ok(claims) if claims.domain_id == domain.id => claims,
+            Ok(_) => return Ok(MemberReader(Reader::Rejected)),
+            Err(e) => {
+                tracing::debug!(error = %e, "Member token rejected");
+                return Ok(MemberReader(Reader::Rejected));
+            }
+        };
+
+        // Removed members lose access before their tokens expire
+        let current = members::is_member(&state.db, claims.member_id, domain.id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        Ok(MemberReader(if current {
+            Reader::Member {
+                member_id: claims.member_id,
+            }
+        } else {
+            Reader::Rejected
+        }))
+    }
+}
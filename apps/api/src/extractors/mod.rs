@@ -1,5 +1,9 @@
 pub mod auth;
 pub mod domain;
+pub mod member;
+pub mod pagination;
 
 pub use auth::*;
 pub use domain::*;
+pub use member::*;
+pub use pagination::*;
@@ -0,0 +1,155 @@
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::{StatusCode, request::Parts},
+};
+use serde::Deserialize;
+
+/// Page size defaults and caps shared by paginated endpoints
+#[derive(Debug, Clone, Copy)]
+pub struct PaginationConfig {
+    pub default_per_page: i64,
+    pub max_per_page: i64,
+}
+
+impl Default for PaginationConfig {
+    /// `PAGINATION_DEFAULT_PER_PAGE` (default 20) and
+    /// `PAGINATION_MAX_PER_PAGE` (default 100)
+    fn default() -> Self {
+        let env_limit = |var: &str, default: i64| {
+            std::env::var(var)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|limit: &i64| *limit > 0)
+                .unwrap_or(default)
+        };
+        let max_per_page = env_limit("PAGINATION_MAX_PER_PAGE", 100);
+        Self {
+            default_per_page: env_limit("PAGINATION_DEFAULT_PER_PAGE", 20).min(max_per_page),
+            max_per_page,
+        }
+    }
+}
+
+/// Validated `page`/`per_page` query parameters
+///
+/// Missing values use the configured defaults and a `per_page` above the cap
+/// is clamped to it; anything else that isn't a positive integer is a 400.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pagination {
+    /// 1-based page number
+    pub page: i64,
+    pub per_page: i64,
+}
+
+#[derive(Deserialize)]
+struct PaginationParams {
+    page: Option<String>,
+    per_page: Option<String>,
+}
+
+fn positive(value: &str) -> Option<i64> {
+    value.trim().parse().ok().filter(|n: &i64| *n > 0)
+}
+
+impl Pagination {
+    pub fn parse(
+        page: Option<&str>,
+        per_page: Option<&str>,
+        config: &PaginationConfig,
+    ) -> Result<Self, &'static str> {
+        let page = match page {
+            Some(value) => positive(value).ok_or("page must be a positive integer")?,
+            None => 1,
+        };
+        let per_page = match per_page {
+            Some(value) => positive(value)
+                .ok_or("per_page must be a positive integer")?
+                .min(config.max_per_page),
+            None => config.default_per_page,
+        };
+
+        let pagination = Self { page, per_page };
+        (page - 1)
+            .checked_mul(per_page)
+            .map(|_| pagination)
+            .ok_or("page is out of range")
+    }
+
+    /// Rows to skip before this page
+    pub fn offset(&self) -> i64 {
+        (self.page - 1) * self.per_page
+    }
+}
+
+impl<S> FromRequestParts<S> for Pagination
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<PaginationParams>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| (StatusCode::BAD_REQUEST, "invalid query string"))?;
+
+        Pagination::parse(
+            params.page.as_deref(),
+            params.per_page.as_deref(),
+            &PaginationConfig::default(),
+        )
+        .map_err(|message| (StatusCode::BAD_REQUEST, message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: PaginationConfig = PaginationConfig {
+        default_per_page: 20,
+        max_per_page: 100,
+    };
+
+    #[test]
+    fn test_defaults() {
+        let pagination = Pagination::parse(None, None, &CONFIG).unwrap();
+        assert_eq!(
+            pagination,
+            Pagination {
+                page: 1,
+                per_page: 20
+            }
+        );
+        assert_eq!(pagination.offset(), 0);
+    }
+
+    #[test]
+    fn test_per_page_above_cap_is_clamped() {
+        let pagination = Pagination::parse(Some("2"), Some("500"), &CONFIG).unwrap();
+        assert_eq!(pagination.per_page, 100);
+        assert_eq!(pagination.offset(), 100);
+    }
+
+    #[test]
+    fn test_nonsense_is_rejected() {
+        for (page, per_page) in [
+            (Some("0"), None),
+            (Some("-3"), None),
+            (Some("abc"), None),
+            (None, Some("0")),
+            (None, Some("ten")),
+            (Some("9223372036854775807"), Some("50")),
+        ] {
+            assert!(
+                Pagination::parse(page, per_page, &CONFIG).is_err(),
+                "accepted page={page:?} per_page={per_page:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_offset() {
+        let pagination = Pagination::parse(Some("3"), Some("25"), &CONFIG).unwrap();
+        assert_eq!(pagination.offset(), 50);
+    }
+}
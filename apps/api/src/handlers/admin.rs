@@ -24,23 +24,66 @@
 // ============================================================================
 
 use crate::extractors::{
-    RequireDomainAdmin, RequireDomainEditor, RequireDomainViewer, RequirePlatformAdmin,
+    Pagination, RequireDomainAdmin, RequireDomainEditor, RequireDomainViewer, RequirePlatformAdmin,
+    check_domain_permission,
 };
-use crate::services::session_tracking::SessionTracker;
+use crate::handlers::analytics;
+use crate::middleware::{RateLimitSettings, cors};
+use crate::services::abuse::BanInfo;
+use crate::services::alerts::{self, Alert, AlertSettings};
+use crate::services::audit::{self, AuditEntry};
+use crate::services::calendar::{self, Calendar};
+use crate::services::clock::Clock;
+use crate::services::credentials;
+use crate::services::daily_stats;
+use crate::services::digest::{Mailer, SmtpMailer};
+use crate::services::domain_clone::{self, CloneReport};
+use crate::services::domain_export;
+use crate::services::domain_verification::{self, DnsError, ExpectedRecords, VerificationConfig};
+use crate::services::email_templates::{self, EmailKind, EmailTemplate, TemplateSource};
+use crate::services::event_types;
+use crate::services::excerpt;
+use crate::services::maintenance;
+use crate::services::media::{self, UploadError};
+use crate::services::members::{self, Member};
+use crate::services::menu::{self, MenuItem};
+use crate::services::parquet_export::ExportField;
+use crate::services::password_policy;
+use crate::services::permalinks;
+use crate::services::post_authors::{self, AuthorRole, PostAuthor};
+use crate::services::post_status::PostStatus;
+use crate::services::quota::{self, Quota, Usage};
+use crate::services::rate_limits;
+use crate::services::reading_time;
+use crate::services::referrers::{self, ReferrerConfig, ReferrerTypeBreakdown};
+use crate::services::retention::{self, PruneReport, RetentionConfig};
+use crate::services::sanitize::{self, ContentFormat};
+use crate::services::search::{self, SearchSettings};
+use crate::services::session_tracking::{SessionConfig, SessionTracker};
+use crate::services::slug;
+use crate::services::slug_redirects;
+use crate::services::subscribers::{self, Subscriber, SubscriberStatus};
+use crate::services::timezones;
+use crate::services::translations::{self, Translation};
+use crate::services::view_counts;
 use crate::utils::{AnalyticsSpan, DatabaseSpan, PerformanceSpan};
 use crate::validation::{extractors::ValidatedJson, rules::*};
-use crate::{AppState, UserContext};
+use crate::{AppState, DOMAIN_FEATURES, DomainContext, Role, UserContext};
 use axum::{
     Extension, Router,
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
-    routing::get,
+    body::Body,
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Json, Response},
+    routing::{delete, get, post, put},
 };
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
+use std::net::IpAddr;
 use std::sync::Arc;
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
 use validator::Validate;
 
 // ============================================================================
@@ -62,10 +105,38 @@ impl super::HandlerModule for AdminModule {
             // Post management: CRUD operations for blog posts
             // Permissions: domain_viewer (read), domain_editor (write), domain_admin (delete)
             .route("/posts", get(list_admin_posts).post(create_post))
+            .route("/posts/import", post(import_posts))
+            .route("/posts/view-counts/sync", post(sync_view_counts))
+            .route("/posts/calendar", get(get_post_calendar))
             .route(
                 "/posts/{id}",
                 get(get_admin_post).put(update_post).delete(delete_post),
             )
+            .route("/posts/{id}/clone", post(clone_post))
+            .route("/posts/{id}/autosave", get(get_autosave).put(autosave_post))
+            .route("/posts/{id}/translations", get(list_post_translations))
+            .route(
+                "/posts/{id}/translations/{locale}",
+                put(upsert_post_translation).delete(delete_post_translation),
+            )
+            .route("/posts/{id}/authors", get(list_post_authors))
+            .route(
+                "/posts/{id}/authors/{user_id}",
+                put(put_post_author).delete(delete_post_author),
+            )
+            
+            // ===========================================
+            // MEDIA MANAGEMENT ROUTES
+            // ===========================================
+            // Image uploads stored via the configured storage backend
+            // Permissions: domain_viewer (list), domain_editor (upload, delete)
+            .route(
+                "/media",
+                get(list_media)
+                    .post(upload_media)
+                    .layer(DefaultBodyLimit::max(media::max_upload_bytes() + 64 * 1024)),
+            )
+            .route("/media/{id}", delete(delete_media))
             
             // ===========================================
             // ANALYTICS & REPORTING ROUTES  
@@ -78,6 +149,8 @@ impl super::HandlerModule for AdminModule {
             .route("/analytics/posts", get(get_admin_post_analytics))
             .route("/analytics/search-terms", get(get_admin_search_analytics))
             .route("/analytics/referrers", get(get_admin_referrer_stats))
+            .route("/analytics/prune", post(prune_analytics_events))
+            .route("/analytics/rollup", post(backfill_daily_stats))
             
             // ===========================================
             // DOMAIN CONFIGURATION ROUTES
@@ -88,11 +161,55 @@ impl super::HandlerModule for AdminModule {
                 "/domain/settings",
                 get(get_domain_settings).put(update_domain_settings),
             )
+            .route(
+                "/domain/features",
+                get(get_domain_features).put(update_domain_features),
+            )
+            .route("/domain/menu", get(get_domain_menu).put(update_domain_menu))
+            .route(
+                "/domain/search",
+                get(get_search_settings).put(update_search_settings),
+            )
+            .route("/domain/usage", get(get_domain_usage))
+            .route("/domain/email-templates", get(list_domain_email_templates))
+            .route(
+                "/domain/email-templates/{kind}",
+                put(update_domain_email_template).delete(delete_domain_email_template),
+            )
+            .route("/domain/members", get(list_members).post(create_member))
+            .route("/domain/members/{id}", delete(delete_member))
+            .route("/subscribers", get(list_subscribers))
+            .route("/subscribers/export", get(export_subscribers))
+            .route("/alerts", get(list_alerts).post(create_alert))
+            .route(
+                "/alerts/{id}",
+                get(get_alert).put(update_alert).delete(delete_alert),
+            )
             .route("/domains", get(list_domains).post(create_domain))
             .route(
                 "/domains/{id}",
                 get(get_domain).put(update_domain).delete(delete_domain),
             )
+            .route("/domains/{id}/export", get(export_domain))
+            .route("/domains/{id}/clone", post(clone_domain))
+            .route("/domains/{id}/verify", post(verify_domain))
+            .route(
+                "/domains/{id}/quota",
+                get(get_domain_quota).put(update_domain_quota),
+            )
+            .route(
+                "/domains/{id}/permissions/bulk",
+                post(bulk_update_domain_permissions),
+            )
+            .route("/email-templates", get(list_platform_email_templates))
+            .route(
+                "/email-templates/{kind}",
+                put(update_platform_email_template).delete(delete_platform_email_template),
+            )
+            .route("/maintenance", get(get_maintenance).put(update_maintenance))
+            .route("/rate-limits", get(get_rate_limits).put(update_rate_limits))
+            .route("/bans", get(list_bans))
+            .route("/bans/{ip}", delete(clear_ban))
             
             // ===========================================
             // USER MANAGEMENT ROUTES
@@ -104,6 +221,10 @@ impl super::HandlerModule for AdminModule {
                 "/users/{id}",
                 get(get_user).put(update_user).delete(delete_user),
             )
+            .route("/users/{id}/restore", post(restore_user))
+            .route("/users/{id}/reset-password", post(reset_user_password))
+            .route("/users/{id}/revoke-sessions", post(revoke_user_sessions))
+            .route("/impersonate/{user_id}", post(impersonate_user))
             
             // ===========================================
             // USER PROFILE & PREFERENCES ROUTES
@@ -137,17 +258,62 @@ struct CreatePostRequest {
     category: String,           // Post category (required)
     slug: Option<String>,       // URL slug (auto-generated if not provided)
     status: Option<String>,     // Publication status: "draft" or "published" (defaults to "draft")
+    excerpt: Option<String>,    // Listing summary (generated from content if not provided)
+    image_url: Option<String>,  // Social sharing image (domain default if not provided)
+    publish_at: Option<DateTime<Utc>>, // When a scheduled post goes out (required for "scheduled")
+    visibility: Option<String>, // "public" (default), "members" or "private"; kept on update
+    pinned: Option<bool>,       // Listed before other posts (defaults to false); kept on update
+    meta_title: Option<String>, // Search and sharing title (post title if unset); kept on update, "" clears
+    meta_description: Option<String>, // Search and sharing description (excerpt if unset); kept on update, "" clears
+    noindex: Option<bool>, // Hidden from search engines and the sitemap (defaults to false); kept on update
 }
 
 impl Validate for CreatePostRequest {
     fn validate(&self) -> Result<(), validator::ValidationErrors> {
-        crate::validation::custom::validate_create_post_request(
-            &self.title,
-            &self.content,
-            &self.category,
-            &self.slug,
-            &self.status,
-        )
+        let result = with_visibility_errors(
+            crate::validation::custom::validate_create_post_request(
+                &self.title,
+                &self.content,
+                &self.category,
+                &self.slug,
+                &self.status,
+                &self.excerpt,
+                &self.image_url,
+            ),
+            &self.visibility,
+        );
+        let mut errors = result.err().unwrap_or_default();
+        if let Some(Err(error)) = self.meta_title.as_deref().map(validate_post_meta_title) {
+            errors.add("meta_title", error);
+        }
+        if let Some(Err(error)) = self
+            .meta_description
+            .as_deref()
+            .map(validate_post_meta_description)
+        {
+            errors.add("meta_description", error);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Add a `visibility` error, if any, to the other post field errors
+fn with_visibility_errors(
+    result: Result<(), validator::ValidationErrors>,
+    visibility: &Option<String>,
+) -> Result<(), validator::ValidationErrors> {
+    let mut errors = result.err().unwrap_or_default();
+    if let Some(Err(error)) = visibility.as_deref().map(validate_post_visibility) {
+        errors.add("visibility", error);
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
     }
 }
 
@@ -161,13 +327,45 @@ struct AdminPostResponse {
     author: Option<String>,                             // Post author name
     category: Option<String>,                           // Post category
     slug: String,                                       // URL-friendly slug
+    excerpt: String,                                    // Listing summary, written or generated
+    image_url: Option<String>,                          // Social sharing image
     status: Option<String>,                             // Publication status
+    publish_at: Option<chrono::DateTime<chrono::Utc>>, // When a scheduled post goes out
+    visibility: String,                                 // public, members or private
+    pinned: bool,                                       // Listed first on the public blog
+    meta_title: Option<String>,                         // Search and sharing title override
+    meta_description: Option<String>,                   // Search and sharing description override
+    noindex: bool,                                      // Hidden from search engines and the sitemap
     domain_id: i32,                                     // Associated domain ID
     domain_name: Option<String>,                        // Domain name for context
+    word_count: i32,                                    // Words in content, computed on save
+    reading_time_minutes: i32,                          // Estimated reading time, computed on save
     created_at: Option<chrono::DateTime<chrono::Utc>>, // Creation timestamp
     updated_at: Option<chrono::DateTime<chrono::Utc>>, // Last modification timestamp
 }
 
+/// Admin post with the statuses `update_post` will accept next
+#[derive(Serialize)]
+struct AdminPost {
+    #[serde(flatten)]
+    post: AdminPostResponse,
+    next_statuses: Vec<PostStatus>, // Allowed moves from the current status
+}
+
+impl From<AdminPostResponse> for AdminPost {
+    fn from(post: AdminPostResponse) -> Self {
+        let status = post
+            .status
+            .as_deref()
+            .and_then(PostStatus::parse)
+            .unwrap_or_default();
+        Self {
+            next_statuses: status.next().to_vec(),
+            post,
+        }
+    }
+}
+
 // ============================================================================
 // USER PREFERENCES DATA STRUCTURES  
 // ============================================================================
@@ -206,7 +404,7 @@ async fn list_admin_posts(
     RequireDomainViewer(auth): RequireDomainViewer,
     State(state): State<Arc<AppState>>,
     Query(query): Query<AdminPostsQuery>,
-) -> Result<Json<Vec<AdminPostResponse>>, StatusCode> {
+) -> Result<Json<Vec<AdminPost>>, StatusCode> {
     // Set pagination defaults
     let page = query.page.unwrap_or(1).max(1);
     let limit = query.limit.unwrap_or(10).clamp(1, 100); // Max 100 posts per page
@@ -214,36 +412,8 @@ async fn list_admin_posts(
 
     // Handle cross-domain listing for users with proper permissions
     let posts = if query.domain.as_deref() == Some("all") {
-        // Helper struct for domain ID queries
-        #[derive(sqlx::FromRow)]
-        struct DomainId {
-            id: i32,
-        }
-
-        // Determine which domains the user can access
-        let domain_ids: Vec<i32> = if auth.user.role == "platform_admin" {
-            // Platform admins can see all domains
-            sqlx::query_as!(DomainId, "SELECT id as id FROM domains")
-                .fetch_all(&state.db)
-                .await
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-                .into_iter()
-                .map(|d| d.id)
-                .collect()
-        } else {
-            // Domain users can only see domains they have permissions for
-            sqlx::query_as!(
-                DomainId,
-                "SELECT domain_id as \"id!\" FROM user_domain_permissions WHERE user_id = $1",
-                auth.user.id
-            )
-            .fetch_all(&state.db)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-                .into_iter()
-                .map(|d| d.id)
-                .collect()
-        };
+        // Same domains the analytics routes cover
+        let domain_ids = analytics::accessible_domain_ids(&auth.user, &state.db).await?;
 
         // Return empty list if user has no domain access
         if domain_ids.is_empty() {
@@ -254,8 +424,10 @@ async fn list_admin_posts(
         let placeholders: Vec<String> = (1..=domain_ids.len()).map(|i| format!("${i}")).collect();
         let query_str = format!(
             r#"
-            SELECT p.id, p.title, p.content, p.author, p.category, p.slug, p.status, 
-                   p.domain_id as "domain_id!", d.name as "domain_name?", p.created_at, p.updated_at
+            SELECT p.id, p.title, p.content, p.author, p.category, p.slug, p.excerpt, p.image_url, p.status, p.publish_at, p.visibility, p.pinned,
+                   p.meta_title, p.meta_description, p.noindex,
+                   p.domain_id, d.name AS domain_name,
+                   p.word_count, p.reading_time_minutes, p.created_at, p.updated_at
             FROM posts p
             JOIN domains d ON p.domain_id = d.id
             WHERE p.domain_id IN ({})
@@ -283,8 +455,10 @@ async fn list_admin_posts(
         sqlx::query_as!(
             AdminPostResponse,
             r#"
-            SELECT p.id, p.title, p.content, p.author, p.category, p.slug, p.status, 
-                   p.domain_id as "domain_id!", d.name as "domain_name?", p.created_at, p.updated_at
+            SELECT p.id, p.title, p.content, p.author, p.category, p.slug, p.excerpt, p.image_url, p.status, p.publish_at, p.visibility, p.pinned,
+                   p.meta_title, p.meta_description, p.noindex,
+                   p.domain_id as "domain_id!", d.name as "domain_name?",
+                   p.word_count, p.reading_time_minutes, p.created_at, p.updated_at
             FROM posts p
             JOIN domains d ON p.domain_id = d.id
             WHERE p.domain_id = $1
@@ -300,71 +474,132 @@ async fn list_admin_posts(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
     };
 
-    Ok(Json(posts))
+    Ok(Json(posts.into_iter().map(AdminPost::from).collect()))
+}
+
+/// URL-friendly slug derived from a post title, dated by `clock` when
+/// nothing in the title transliterates
+fn slug_from_title(title: &str, clock: &dyn Clock) -> String {
+    slug::from_title(title, clock.now().date_naive())
+}
+
+/// `409` with the reason when `new_posts` more posts would take the domain
+/// past its quota
+async fn check_post_quota(
+    db: &sqlx::PgPool,
+    domain_id: i32,
+    new_posts: i64,
+) -> Result<(), Response> {
+    let limits = quota::load(db, domain_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?
+        .unwrap_or_default();
+    let usage = quota::usage(db, domain_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+    limits
+        .check_posts(&usage, new_posts)
+        .map_err(IntoResponse::into_response)
 }
 
 /// Create a new blog post
 /// Requires domain editor permissions or higher
 /// Auto-generates slug from title if not provided
+/// Returns 409 when the domain is at its post quota
 async fn create_post(
     RequireDomainEditor(auth): RequireDomainEditor,
     State(state): State<Arc<AppState>>,
     ValidatedJson(payload): ValidatedJson<CreatePostRequest>,
-) -> Result<Json<AdminPostResponse>, StatusCode> {
+) -> Result<Json<AdminPost>, Response> {
+    check_post_quota(&state.db, auth.domain.id, 1).await?;
+
     DatabaseSpan::execute("create_post", "posts", async {
         // Generate URL-friendly slug if not provided
-        let slug = payload.slug.unwrap_or_else(|| {
-            payload
-                .title
-                .to_lowercase()
-                .replace(" ", "-")
-                .chars()
-                .filter(|c| c.is_alphanumeric() || *c == '-')
-                .collect()
-        });
+        let slug = payload.slug.unwrap_or_else(|| slug_from_title(&payload.title, state.clock.as_ref()));
 
         // Default to draft status if not specified
         let status = payload.status.unwrap_or_else(|| "draft".to_string());
+        // Scheduled posts need a time to go out
+        if status == PostStatus::Scheduled.as_str() && payload.publish_at.is_none() {
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        // HTML content is cleaned before anything is derived from it
+        let content = sanitize::for_storage(payload.content, ContentFormat::of(&auth.domain));
+        let (word_count, reading_time_minutes) = reading_time::estimate(&content);
+        let excerpt = excerpt::resolve(payload.excerpt.as_deref(), &content);
+
+        let mut tx = state
+            .db
+            .begin()
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         // Insert new post with author attribution
-        let post = sqlx::query_as!(
+        let mut post = sqlx::query_as!(
             AdminPostResponse,
             r#"
-            INSERT INTO posts (domain_id, title, content, author, category, slug, status)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            RETURNING id, title, content, author, category, slug, status, 
-                      domain_id as "domain_id!", NULL as "domain_name?", created_at, updated_at
+            INSERT INTO posts (domain_id, title, content, author, category, slug, status, word_count, reading_time_minutes, excerpt, image_url, publish_at, visibility, pinned,
+                               meta_title, meta_description, noindex)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, COALESCE($13, 'public'), COALESCE($14, FALSE),
+                    NULLIF(BTRIM($15), ''), NULLIF(BTRIM($16), ''), COALESCE($17, FALSE))
+            RETURNING id, title, content, author, category, slug, excerpt, image_url, status, publish_at, visibility, pinned,
+                      meta_title, meta_description, noindex,
+                      domain_id as "domain_id!", NULL as "domain_name?",
+                      word_count, reading_time_minutes, created_at, updated_at
             "#,
             auth.domain.id,    // Post belongs to user's current domain
             payload.title,
-            payload.content,
+            content,
             auth.user.name,    // Set author to current user's name
             payload.category,
             slug,
-            status
+            status,
+            word_count,
+            reading_time_minutes,
+            excerpt,
+            payload.image_url,
+            payload.publish_at,
+            payload.visibility,
+            payload.pinned,
+            payload.meta_title,
+            payload.meta_description,
+            payload.noindex
         )
-        .fetch_one(&state.db)
+        .fetch_one(&mut *tx)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        // The byline follows the creator's name from now on
+        post_authors::add(&mut tx, post.id, auth.user.id, AuthorRole::Author)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        tx.commit()
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        // Same shape as listing and fetching: the post is in the current domain
+        post.domain_name = Some(auth.domain.name.clone());
 
-        Ok(Json(post))
+        Ok(Json(post.into()))
     })
     .await
+    .map_err(IntoResponse::into_response)
 }
 
 /// Get a single post with admin details
 /// Requires domain viewer permissions or higher
-/// Returns 404 if post doesn't exist or user lacks access
+/// Returns 403 if the post belongs to another domain the user can access, and
+/// 404 if it doesn't exist or belongs to a domain the user can't see
 async fn get_admin_post(
     RequireDomainViewer(auth): RequireDomainViewer,
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
-) -> Result<Json<AdminPostResponse>, StatusCode> {
+) -> Result<Json<AdminPost>, StatusCode> {
     let post = sqlx::query_as!(
         AdminPostResponse,
         r#"
-        SELECT p.id, p.title, p.content, p.author, p.category, p.slug, p.status, 
-               p.domain_id as "domain_id!", d.name as "domain_name?", p.created_at, p.updated_at
+        SELECT p.id, p.title, p.content, p.author, p.category, p.slug, p.excerpt, p.image_url, p.status, p.publish_at, p.visibility, p.pinned,
+               p.meta_title, p.meta_description, p.noindex,
+               p.domain_id as "domain_id!", d.name as "domain_name?",
+               p.word_count, p.reading_time_minutes, p.created_at, p.updated_at
         FROM posts p
         JOIN domains d ON p.domain_id = d.id
         WHERE p.id = $1 AND p.domain_id = $2
@@ -374,58 +609,398 @@ async fn get_admin_post(
     )
     .fetch_optional(&state.db)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-    .ok_or(StatusCode::NOT_FOUND)?;
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    match post {
+        Some(post) => Ok(Json(post.into())),
+        None => Err(post_outside_domain_status(&state.db, &auth.user, id).await),
+    }
+}
 
-    Ok(Json(post))
+/// Status for a post id that isn't in the current domain: 403 when it lives
+/// in another domain the user has access to, so they know to switch domains,
+/// and 404 otherwise, so other domains' posts can't be discovered by id
+async fn post_outside_domain_status(db: &sqlx::PgPool, user: &UserContext, id: i32) -> StatusCode {
+    let owner = sqlx::query_scalar::<_, i32>("SELECT domain_id FROM posts WHERE id = $1")
+        .bind(id)
+        .fetch_optional(db)
+        .await;
+    let domain_id = match owner {
+        Ok(domain_id) => domain_id,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
+    };
+
+    let visible = domain_id.is_some_and(|domain_id| {
+        user.role().is_platform_level()
+            || user
+                .domain_permissions
+                .iter()
+                .any(|permission| permission.domain_id == domain_id)
+    });
+    if visible {
+        StatusCode::FORBIDDEN
+    } else {
+        StatusCode::NOT_FOUND
+    }
 }
 
+/// Update a post, moving its status only along the workflow in
+/// [`PostStatus::next`] (422 otherwise); an omitted status keeps the current one.
+/// A changed slug leaves a redirect behind, see [`slug_redirects`].
 async fn update_post(
     RequireDomainEditor(auth): RequireDomainEditor,
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
     ValidatedJson(payload): ValidatedJson<CreatePostRequest>,
-) -> Result<Json<AdminPostResponse>, StatusCode> {
+) -> Result<Json<AdminPost>, StatusCode> {
     DatabaseSpan::execute("update_post", "posts", async {
-        let slug = payload.slug.unwrap_or_else(|| {
-            payload
-                .title
-                .to_lowercase()
-                .replace(" ", "-")
-                .chars()
-                .filter(|c| c.is_alphanumeric() || *c == '-')
-                .collect()
-        });
+        let slug = payload
+            .slug
+            .unwrap_or_else(|| slug_from_title(&payload.title, state.clock.as_ref()));
 
-        let status = payload.status.unwrap_or_else(|| "draft".to_string());
+        let mut tx = state
+            .db
+            .begin()
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let (current, old_slug): (Option<String>, String) = sqlx::query_as(
+            "SELECT status, slug FROM posts WHERE id = $1 AND domain_id = $2 FOR UPDATE",
+        )
+        .bind(id)
+        .bind(auth.domain.id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+        let current = current
+            .as_deref()
+            .and_then(PostStatus::parse)
+            .unwrap_or_default();
+        // Already validated, so only an omitted status falls back
+        let status = payload
+            .status
+            .as_deref()
+            .and_then(PostStatus::parse)
+            .unwrap_or(current);
+        if !current.can_become(status) {
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        // Scheduled posts need a time to go out, also when staying scheduled
+        if status == PostStatus::Scheduled && payload.publish_at.is_none() {
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
 
-        let post = sqlx::query_as!(
+        let content = sanitize::for_storage(payload.content, ContentFormat::of(&auth.domain));
+        let (word_count, reading_time_minutes) = reading_time::estimate(&content);
+        let excerpt = excerpt::resolve(payload.excerpt.as_deref(), &content);
+
+        let mut post = sqlx::query_as!(
             AdminPostResponse,
             r#"
         UPDATE posts 
-        SET title = $3, content = $4, category = $5, slug = $6, status = $7, updated_at = NOW()
+        SET title = $3, content = $4, category = $5, slug = $6, status = $7,
+            word_count = $8, reading_time_minutes = $9, excerpt = $10, image_url = $11,
+            publish_at = $12, visibility = COALESCE($13, visibility),
+            pinned = COALESCE($14, pinned),
+            meta_title = CASE WHEN $15::text IS NULL THEN meta_title ELSE NULLIF(BTRIM($15), '') END,
+            meta_description = CASE WHEN $16::text IS NULL THEN meta_description ELSE NULLIF(BTRIM($16), '') END,
+            noindex = COALESCE($17, noindex), updated_at = NOW()
         WHERE id = $1 AND domain_id = $2
-        RETURNING id, title, content, author, category, slug, status, 
-                  domain_id as "domain_id!", NULL as "domain_name?", created_at, updated_at
+        RETURNING id, title, content, author, category, slug, excerpt, image_url, status, publish_at, visibility, pinned,
+                  meta_title, meta_description, noindex,
+                  domain_id as "domain_id!", NULL as "domain_name?",
+                  word_count, reading_time_minutes, created_at, updated_at
         "#,
             id,
             auth.domain.id,
             payload.title,
-            payload.content,
+            content,
             payload.category,
             slug,
-            status
+            status.as_str(),
+            word_count,
+            reading_time_minutes,
+            excerpt,
+            payload.image_url,
+            payload.publish_at,
+            payload.visibility,
+            payload.pinned,
+            payload.meta_title,
+            payload.meta_description,
+            payload.noindex
         )
-        .fetch_optional(&state.db)
+        .fetch_optional(&mut *tx)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
+        post.domain_name = Some(auth.domain.name.clone());
+
+        slug_redirects::record(&mut tx, auth.domain.id, id, &old_slug, &post.slug)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        tx.commit()
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-        Ok(Json(post))
+        Ok(Json(post.into()))
     })
     .await
 }
 
+#[derive(Deserialize)]
+struct CalendarQuery {
+    month: Option<String>, // YYYY-MM, defaults to the current month
+}
+
+/// Scheduled and published posts of a month, grouped by day in the domain's
+/// time zone with a count per day
+/// Requires domain viewer permissions or higher
+async fn get_post_calendar(
+    RequireDomainViewer(auth): RequireDomainViewer,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CalendarQuery>,
+) -> Result<Json<Calendar>, StatusCode> {
+    let first = match query.month.as_deref() {
+        Some(month) => calendar::parse_month(month).ok_or(StatusCode::BAD_REQUEST)?,
+        None => calendar::month_start(state.clock.now().date_naive()),
+    };
+
+    let calendar = calendar::load(&state.db, auth.domain.id, &auth.domain.timezone, first)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(calendar))
+}
+
+/// Most posts accepted by one import request
+const MAX_IMPORT_POSTS: usize = 500;
+
+/// One post in an import payload, e.g. exported from another platform
+#[derive(Deserialize)]
+struct ImportPostRequest {
+    title: String,
+    content: String,
+    category: String,
+    slug: Option<String>,
+    status: Option<String>,
+    excerpt: Option<String>,
+    image_url: Option<String>,
+    created_at: Option<DateTime<Utc>>, // Original publication time (defaults to now)
+    visibility: Option<String>,        // Defaults to "public"
+}
+
+impl Validate for ImportPostRequest {
+    fn validate(&self) -> Result<(), validator::ValidationErrors> {
+        with_visibility_errors(
+            crate::validation::custom::validate_create_post_request(
+                &self.title,
+                &self.content,
+                &self.category,
+                &self.slug,
+                &self.status,
+                &self.excerpt,
+                &self.image_url,
+            ),
+            &self.visibility,
+        )
+    }
+}
+
+/// Outcome of importing one post, by position in the payload
+#[derive(Serialize)]
+struct ImportPostResult {
+    index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slug: Option<String>, // Final slug, suffixed with -2, -3, ... on collision
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ImportPostsResponse {
+    imported: usize,
+    failed: usize,
+    results: Vec<ImportPostResult>,
+}
+
+/// First of `slug`, `slug-2`, `slug-3`, ... not yet used in the domain
+async fn unique_slug(
+    conn: &mut sqlx::PgConnection,
+    domain_id: i32,
+    slug: &str,
+) -> Result<String, sqlx::Error> {
+    let mut candidate = slug.to_string();
+    for suffix in 2.. {
+        let taken: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM posts WHERE domain_id = $1 AND slug = $2)",
+        )
+        .bind(domain_id)
+        .bind(&candidate)
+        .fetch_one(&mut *conn)
+        .await?;
+        if !taken {
+            break;
+        }
+        candidate = format!("{slug}-{suffix}");
+    }
+    Ok(candidate)
+}
+
+async fn insert_imported_post(
+    conn: &mut sqlx::PgConnection,
+    clock: &dyn Clock,
+    domain_id: i32,
+    author: &str,
+    post: &ImportPostRequest,
+) -> Result<(i32, String), sqlx::Error> {
+    let base_slug = post
+        .slug
+        .clone()
+        .unwrap_or_else(|| slug_from_title(&post.title, clock));
+    let slug = unique_slug(conn, domain_id, &base_slug).await?;
+    let status = post.status.as_deref().unwrap_or("draft");
+    let (word_count, reading_time_minutes) = reading_time::estimate(&post.content);
+    let excerpt = excerpt::resolve(post.excerpt.as_deref(), &post.content);
+
+    let id: i32 = sqlx::query_scalar(
+        r#"
+        INSERT INTO posts (domain_id, title, content, author, category, slug, status,
+                           word_count, reading_time_minutes, excerpt, image_url, created_at, updated_at,
+                           visibility)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, COALESCE($12, NOW()), COALESCE($12, NOW()),
+                COALESCE($13, 'public'))
+        RETURNING id
+        "#,
+    )
+    .bind(domain_id)
+    .bind(&post.title)
+    .bind(&post.content)
+    .bind(author)
+    .bind(&post.category)
+    .bind(&slug)
+    .bind(status)
+    .bind(word_count)
+    .bind(reading_time_minutes)
+    .bind(&excerpt)
+    .bind(&post.image_url)
+    .bind(post.created_at)
+    .bind(&post.visibility)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok((id, slug))
+}
+
+/// Import posts into the current domain in one transaction
+/// Requires domain admin permissions
+/// Invalid rows, and rows past the domain's post quota, are reported and
+/// skipped; colliding slugs get a numeric suffix
+async fn import_posts(
+    RequireDomainAdmin(auth): RequireDomainAdmin,
+    State(state): State<Arc<AppState>>,
+    Json(raw_posts): Json<Vec<serde_json::Value>>,
+) -> Result<Json<ImportPostsResponse>, StatusCode> {
+    if raw_posts.len() > MAX_IMPORT_POSTS {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut results = Vec::with_capacity(raw_posts.len());
+    let format = ContentFormat::of(&auth.domain);
+    let limits = quota::load(&state.db, auth.domain.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .unwrap_or_default();
+    let usage = quota::usage(&state.db, auth.domain.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut stored = 0;
+
+    for (index, raw) in raw_posts.into_iter().enumerate() {
+        let mut post = match serde_json::from_value::<ImportPostRequest>(raw)
+            .map_err(|e| e.to_string())
+            .and_then(|post| post.validate().map(|_| post).map_err(|e| e.to_string()))
+        {
+            Ok(post) => post,
+            Err(error) => {
+                results.push(ImportPostResult {
+                    index,
+                    id: None,
+                    slug: None,
+                    error: Some(error),
+                });
+                continue;
+            }
+        };
+
+        if let Err(exceeded) = limits.check_posts(&usage, stored + 1) {
+            results.push(ImportPostResult {
+                index,
+                id: None,
+                slug: None,
+                error: Some(exceeded.to_string()),
+            });
+            continue;
+        }
+
+        post.content = sanitize::for_storage(post.content, format);
+
+        // A savepoint per post keeps one bad row from aborting the rest
+        let mut savepoint = sqlx::Acquire::begin(&mut *tx)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        match insert_imported_post(
+            &mut savepoint,
+            state.clock.as_ref(),
+            auth.domain.id,
+            &auth.user.name,
+            &post,
+        )
+        .await
+        {
+            Ok((id, slug)) => {
+                savepoint
+                    .commit()
+                    .await
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                stored += 1;
+                results.push(ImportPostResult {
+                    index,
+                    id: Some(id),
+                    slug: Some(slug),
+                    error: None,
+                });
+            }
+            Err(e) => {
+                let _ = savepoint.rollback().await;
+                tracing::warn!(error = %e, index, "Failed to import post");
+                results.push(ImportPostResult {
+                    index,
+                    id: None,
+                    slug: None,
+                    error: Some("failed to store post".to_string()),
+                });
+            }
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let imported = results.iter().filter(|r| r.id.is_some()).count();
+    Ok(Json(ImportPostsResponse {
+        imported,
+        failed: results.len() - imported,
+        results,
+    }))
+}
+
 async fn delete_post(
     RequireDomainAdmin(auth): RequireDomainAdmin,
     State(state): State<Arc<AppState>>,
@@ -448,90 +1023,718 @@ async fn delete_post(
     }
 }
 
-// ============================================================================
-// ANALYTICS & REPORTING HANDLERS
-// ============================================================================
-// Admin-level analytics providing dashboard metrics and reporting.
-// Supports both domain-scoped and platform-wide analytics depending on user permissions.
+/// Longest title `validate_create_post_request` accepts, in bytes
+const MAX_TITLE_LENGTH: usize = 200;
 
-/// Get analytics summary for admin dashboard
-/// Returns comprehensive metrics for the last 30 days
-/// Domain users see their domain stats, platform admins see aggregated stats
-async fn get_analytics_summary(
-    RequireDomainViewer(auth): RequireDomainViewer,
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // Get domain-specific analytics for the dashboard
-    let summary = sqlx::query!(
-        r#"
-        SELECT 
-            COUNT(*) FILTER (WHERE event_type = 'page_view') as page_views,
-            COUNT(*) FILTER (WHERE event_type = 'post_view') as post_views,
-            COUNT(DISTINCT ip_address) as unique_visitors,
-            COUNT(*) FILTER (WHERE event_type = 'search') as searches
-        FROM analytics_events 
-        WHERE domain_id = $1 AND created_at >= NOW() - INTERVAL '30 days'
-        "#,
-        auth.domain.id
-    )
-    .fetch_one(&state.db)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+/// `Copy of <title>`, cut to the title limit on a character boundary
+fn copy_title(title: &str) -> String {
+    let mut copy = format!("Copy of {title}");
+    if copy.len() > MAX_TITLE_LENGTH {
+        let mut end = MAX_TITLE_LENGTH;
+        while !copy.is_char_boundary(end) {
+            end -= 1;
+        }
+        copy.truncate(end);
+    }
+    copy
+}
 
-    // Get total posts count for this domain
-    let posts_count = sqlx::query!(
-        "SELECT COUNT(*) as total FROM posts WHERE domain_id = $1",
-        auth.domain.id
-    )
-    .fetch_one(&state.db)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-    .total
-    .unwrap_or(0);
+/// Start a new draft from an existing post in the current domain
+/// Requires domain editor permissions or higher
+/// Copies the title (as "Copy of ..."), content, category and excerpt under
+/// a fresh slug; views, analytics, autosaves and translations stay with the
+/// original. Counts against the domain's post quota like `create_post`.
+async fn clone_post(
+    RequireDomainEditor(auth): RequireDomainEditor,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<Json<AdminPost>, Response> {
+    check_post_quota(&state.db, auth.domain.id, 1).await?;
 
-    // Get posts created this month for this domain
-    let posts_this_month = sqlx::query!(
-        "SELECT COUNT(*) as total FROM posts WHERE domain_id = $1 AND created_at >= DATE_TRUNC('month', NOW())",
-        auth.domain.id
-    )
-    .fetch_one(&state.db)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-    .total
-    .unwrap_or(0);
+    DatabaseSpan::execute::<_, _, StatusCode>("clone_post", "posts", async {
+        let mut tx = state
+            .db
+            .begin()
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    // Get total across all domains for comparison
-    let all_domains_posts = sqlx::query!("SELECT COUNT(*) as total FROM posts")
-        .fetch_one(&state.db)
+        let (title, content, category, excerpt, image_url): (
+            String,
+            String,
+            String,
+            String,
+            Option<String>,
+        ) = sqlx::query_as(
+            "SELECT title, content, category, excerpt, image_url FROM posts WHERE id = $1 AND domain_id = $2",
+        )
+        .bind(id)
+        .bind(auth.domain.id)
+        .fetch_optional(&mut *tx)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .total
-        .unwrap_or(0);
+        .ok_or(StatusCode::NOT_FOUND)?;
 
-    let all_domains_analytics = sqlx::query!(
-        r#"
-        SELECT 
-            COUNT(*) FILTER (WHERE event_type = 'page_view') as page_views,
-            COUNT(*) FILTER (WHERE event_type = 'post_view') as post_views,
-            COUNT(DISTINCT ip_address) as unique_visitors
-        FROM analytics_events 
-        WHERE created_at >= NOW() - INTERVAL '30 days'
-        "#
-    )
-    .fetch_one(&state.db)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let title = copy_title(&title);
+        let slug = unique_slug(&mut tx, auth.domain.id, &slug_from_title(&title, state.clock.as_ref()))
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let (word_count, reading_time_minutes) = reading_time::estimate(&content);
 
-    // Count active domains
-    let active_domains = sqlx::query!("SELECT COUNT(DISTINCT id) as total FROM domains")
-        .fetch_one(&state.db)
+        let mut post = sqlx::query_as::<_, AdminPostResponse>(
+            r#"
+            INSERT INTO posts (domain_id, title, content, author, category, slug, status,
+                               word_count, reading_time_minutes, excerpt, image_url)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            RETURNING id, title, content, author, category, slug, excerpt, image_url, status, publish_at, visibility, pinned,
+                      meta_title, meta_description, noindex,
+                      domain_id, NULL::text AS domain_name,
+                      word_count, reading_time_minutes, created_at, updated_at
+            "#,
+        )
+        .bind(auth.domain.id)
+        .bind(&title)
+        .bind(&content)
+        .bind(&auth.user.name)
+        .bind(&category)
+        .bind(&slug)
+        .bind(PostStatus::Draft.as_str())
+        .bind(word_count)
+        .bind(reading_time_minutes)
+        .bind(&excerpt)
+        .bind(&image_url)
+        .fetch_one(&mut *tx)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .total
-        .unwrap_or(0);
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        post_authors::add(&mut tx, post.id, auth.user.id, AuthorRole::Author)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    // Return aggregated data for dashboard
-    let total_views = all_domains_analytics.page_views.unwrap_or(0)
+        tx.commit()
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        post.domain_name = Some(auth.domain.name.clone());
+        Ok(Json(post.into()))
+    })
+    .await
+    .map_err(IntoResponse::into_response)
+}
+
+#[derive(Serialize)]
+struct ViewCountSyncResponse {
+    corrected: u64, // Posts whose view_count disagreed with their events
+}
+
+// Recount every post's views in the domain from analytics_events
+async fn sync_view_counts(
+    RequireDomainAdmin(auth): RequireDomainAdmin,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ViewCountSyncResponse>, StatusCode> {
+    let corrected = view_counts::resync(&state.db, auth.domain.id)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to resync post view counts");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(ViewCountSyncResponse { corrected }))
+}
+
+/// Autosaves untouched for this long are discarded
+const AUTOSAVE_RETENTION_DAYS: i64 = 7;
+
+/// Editor state captured while a post is being edited
+/// Not validated like a post: work in progress may have an empty title
+#[derive(Deserialize)]
+struct AutosaveRequest {
+    title: String,
+    content: String,
+    category: Option<String>,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+struct AutosaveResponse {
+    post_id: i32,
+    title: String,
+    content: String,
+    category: Option<String>,
+    saved_at: DateTime<Utc>,
+}
+
+/// Store the caller's in-progress edit of a post in `post_drafts`
+/// Leaves the live post untouched; each save replaces the caller's previous one
+async fn autosave_post(
+    RequireDomainEditor(auth): RequireDomainEditor,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    Json(payload): Json<AutosaveRequest>,
+) -> Result<Json<AutosaveResponse>, StatusCode> {
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let now = state.clock.now();
+    sqlx::query("DELETE FROM post_drafts WHERE saved_at < $1")
+        .bind(now - Duration::days(AUTOSAVE_RETENTION_DAYS))
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Only posts in the caller's domain can be autosaved
+    let draft = sqlx::query_as::<_, AutosaveResponse>(
+        r#"
+        INSERT INTO post_drafts (post_id, user_id, title, content, category, saved_at)
+        SELECT p.id, $3, $4, $5, $6, $7
+        FROM posts p
+        WHERE p.id = $1 AND p.domain_id = $2
+        ON CONFLICT (post_id, user_id) DO UPDATE
+        SET title = EXCLUDED.title, content = EXCLUDED.content,
+            category = EXCLUDED.category, saved_at = EXCLUDED.saved_at
+        RETURNING post_id, title, content, category, saved_at
+        "#,
+    )
+    .bind(id)
+    .bind(auth.domain.id)
+    .bind(auth.user.id)
+    .bind(&payload.title)
+    .bind(&payload.content)
+    .bind(&payload.category)
+    .bind(now)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(draft))
+}
+
+/// The caller's latest autosave of a post, if younger than the retention window
+async fn get_autosave(
+    RequireDomainEditor(auth): RequireDomainEditor,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<Json<AutosaveResponse>, StatusCode> {
+    let draft = sqlx::query_as::<_, AutosaveResponse>(
+        r#"
+        SELECT pd.post_id, pd.title, pd.content, pd.category, pd.saved_at
+        FROM post_drafts pd
+        JOIN posts p ON p.id = pd.post_id
+        WHERE pd.post_id = $1 AND p.domain_id = $2 AND pd.user_id = $3
+          AND pd.saved_at >= $4
+        "#,
+    )
+    .bind(id)
+    .bind(auth.domain.id)
+    .bind(auth.user.id)
+    .bind(state.clock.now() - Duration::days(AUTOSAVE_RETENTION_DAYS))
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(draft))
+}
+
+/// Title and content of a post in one locale
+#[derive(Deserialize, Validate)]
+struct TranslationRequest {
+    #[validate(length(
+        min = 1,
+        max = 200,
+        message = "Title must be between 1 and 200 characters"
+    ))]
+    title: String,
+    #[validate(custom(function = "validate_post_content"))]
+    content: String,
+}
+
+/// Whether post `id` belongs to the caller's domain
+async fn post_in_domain(db: &sqlx::PgPool, id: i32, domain_id: i32) -> Result<bool, StatusCode> {
+    sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM posts WHERE id = $1 AND domain_id = $2)")
+        .bind(id)
+        .bind(domain_id)
+        .fetch_one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// All translations of a post
+async fn list_post_translations(
+    RequireDomainViewer(auth): RequireDomainViewer,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<Translation>>, StatusCode> {
+    if !post_in_domain(&state.db, id, auth.domain.id).await? {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let rows = sqlx::query_as::<_, Translation>(
+        r#"
+        SELECT post_id, locale, title, content, updated_at
+        FROM post_translations
+        WHERE post_id = $1
+        ORDER BY locale
+        "#,
+    )
+    .bind(id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(rows))
+}
+
+/// Add or replace the translation of a post for `locale` (e.g. `fr`, `pt-BR`)
+async fn upsert_post_translation(
+    RequireDomainEditor(auth): RequireDomainEditor,
+    State(state): State<Arc<AppState>>,
+    Path((id, locale)): Path<(i32, String)>,
+    ValidatedJson(payload): ValidatedJson<TranslationRequest>,
+) -> Result<Json<Translation>, StatusCode> {
+    let locale = translations::normalize_locale(&locale).ok_or(StatusCode::BAD_REQUEST)?;
+    let content = sanitize::for_storage(payload.content, ContentFormat::of(&auth.domain));
+
+    let translation = sqlx::query_as::<_, Translation>(
+        r#"
+        INSERT INTO post_translations (post_id, locale, title, content)
+        SELECT p.id, $3, $4, $5
+        FROM posts p
+        WHERE p.id = $1 AND p.domain_id = $2
+        ON CONFLICT (post_id, locale) DO UPDATE
+        SET title = EXCLUDED.title, content = EXCLUDED.content, updated_at = NOW()
+        RETURNING post_id, locale, title, content, updated_at
+        "#,
+    )
+    .bind(id)
+    .bind(auth.domain.id)
+    .bind(&locale)
+    .bind(&payload.title)
+    .bind(&content)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(translation))
+}
+
+async fn delete_post_translation(
+    RequireDomainEditor(auth): RequireDomainEditor,
+    State(state): State<Arc<AppState>>,
+    Path((id, locale)): Path<(i32, String)>,
+) -> Result<StatusCode, StatusCode> {
+    let locale = translations::normalize_locale(&locale).ok_or(StatusCode::BAD_REQUEST)?;
+
+    let rows_affected = sqlx::query(
+        r#"
+        DELETE FROM post_translations t
+        USING posts p
+        WHERE t.post_id = p.id AND t.post_id = $1 AND p.domain_id = $2 AND t.locale = $3
+        "#,
+    )
+    .bind(id)
+    .bind(auth.domain.id)
+    .bind(&locale)
+    .execute(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .rows_affected();
+
+    if rows_affected > 0 {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+#[derive(Deserialize)]
+struct PostAuthorRequest {
+    /// `author` (default) or `contributor`
+    #[serde(default)]
+    role: AuthorRole,
+}
+
+/// Users credited on a post, authors before contributors
+async fn list_post_authors(
+    RequireDomainViewer(auth): RequireDomainViewer,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<Json<Vec<PostAuthor>>, StatusCode> {
+    if !post_in_domain(&state.db, id, auth.domain.id).await? {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    post_authors::list(&state.db, id)
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Credit a user on a post, or change the role they are credited with.
+/// Only users with access to the domain (or platform admins) can be
+/// credited; anyone else is a 422.
+async fn put_post_author(
+    RequireDomainEditor(auth): RequireDomainEditor,
+    State(state): State<Arc<AppState>>,
+    Path((id, user_id)): Path<(i32, i32)>,
+    Json(payload): Json<PostAuthorRequest>,
+) -> Result<Json<Vec<PostAuthor>>, StatusCode> {
+    if !post_in_domain(&state.db, id, auth.domain.id).await? {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let eligible: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM users u
+            WHERE u.id = $1 AND u.deleted_at IS NULL
+              AND (u.role IN ('platform_admin', 'super_admin')
+                   OR EXISTS (SELECT 1 FROM user_domain_permissions p
+                              WHERE p.user_id = u.id AND p.domain_id = $2))
+        )
+        "#,
+    )
+    .bind(user_id)
+    .bind(auth.domain.id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !eligible {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let mut conn = state
+        .db
+        .acquire()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    post_authors::add(&mut conn, id, user_id, payload.role)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    post_authors::list(&state.db, id)
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn delete_post_author(
+    RequireDomainEditor(auth): RequireDomainEditor,
+    State(state): State<Arc<AppState>>,
+    Path((id, user_id)): Path<(i32, i32)>,
+) -> Result<StatusCode, StatusCode> {
+    if !post_in_domain(&state.db, id, auth.domain.id).await? {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    if post_authors::remove(&state.db, id, user_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+// ============================================================================
+// MEDIA MANAGEMENT HANDLERS
+// ============================================================================
+// Image uploads for posts. Files go to the storage backend in AppState,
+// metadata to the `media` table scoped by domain.
+
+/// Stored media metadata
+#[derive(sqlx::FromRow)]
+struct MediaRow {
+    id: i32,
+    storage_key: String,
+    filename: String,
+    content_type: String,
+    size_bytes: i64,
+    created_at: DateTime<Utc>,
+}
+
+/// Response structure for media operations
+#[derive(Serialize)]
+struct MediaResponse {
+    id: i32,
+    filename: String,
+    content_type: String,
+    size_bytes: i64,
+    url: String, // Public URL served by the storage backend
+    created_at: DateTime<Utc>,
+}
+
+impl MediaResponse {
+    fn from_row(row: MediaRow, state: &AppState) -> Self {
+        Self {
+            url: state.storage.public_url(&row.storage_key),
+            id: row.id,
+            filename: row.filename,
+            content_type: row.content_type,
+            size_bytes: row.size_bytes,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// Query parameters for listing media
+#[derive(Deserialize)]
+struct MediaQuery {
+    page: Option<i64>,  // Page number (1-based)
+    limit: Option<i64>, // Number of items per page
+}
+
+/// Upload an image as multipart form data (field name `file`)
+/// Requires domain editor permissions or higher
+/// Returns 415 for unsupported types, 413 for oversized files and 409 when the
+/// file would take the domain past its media quota
+async fn upload_media(
+    RequireDomainEditor(auth): RequireDomainEditor,
+    State(state): State<Arc<AppState>>,
+    mut multipart: Multipart,
+) -> Result<Json<MediaResponse>, Response> {
+    // Pick the `file` field out of the form
+    let mut upload = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| e.status().into_response())?
+    {
+        if field.name() == Some("file") {
+            let filename: String = field
+                .file_name()
+                .unwrap_or("upload")
+                .chars()
+                .take(255)
+                .collect();
+            let content_type = field.content_type().unwrap_or_default().to_string();
+            let data = field
+                .bytes()
+                .await
+                .map_err(|e| e.status().into_response())?;
+            upload = Some((filename, content_type, data));
+            break;
+        }
+    }
+    let (filename, content_type, data) =
+        upload.ok_or_else(|| StatusCode::BAD_REQUEST.into_response())?;
+
+    let extension = media::validate_upload(&content_type, &data, media::max_upload_bytes())
+        .map_err(|e| {
+            tracing::warn!(error = %e, domain_id = auth.domain.id, "Rejected media upload");
+            let status = match e {
+                UploadError::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+                UploadError::Empty => StatusCode::BAD_REQUEST,
+                UploadError::UnsupportedType(_) | UploadError::ContentMismatch => {
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE
+                }
+            };
+            status.into_response()
+        })?;
+
+    // Keys are namespaced by domain; the random name avoids collisions and guessing
+    let storage_key = format!("{}/{}.{}", auth.domain.id, Uuid::new_v4(), extension);
+    let size_bytes = data.len() as i64;
+
+    let limits = quota::load(&state.db, auth.domain.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?
+        .unwrap_or_default();
+    let usage = quota::usage(&state.db, auth.domain.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+    limits
+        .check_media(&usage, size_bytes)
+        .map_err(IntoResponse::into_response)?;
+
+    state
+        .storage
+        .put(&storage_key, data.to_vec())
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to store media");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?;
+
+    let row = sqlx::query_as::<_, MediaRow>(
+        r#"
+        INSERT INTO media (domain_id, storage_key, filename, content_type, size_bytes, uploaded_by)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, storage_key, filename, content_type, size_bytes, created_at
+        "#,
+    )
+    .bind(auth.domain.id)
+    .bind(&storage_key)
+    .bind(&filename)
+    .bind(&content_type)
+    .bind(size_bytes)
+    .bind(auth.user.id)
+    .fetch_one(&state.db)
+    .await;
+
+    match row {
+        Ok(row) => Ok(Json(MediaResponse::from_row(row, &state))),
+        Err(e) => {
+            // Don't leave an orphaned file behind
+            tracing::error!(error = %e, "Failed to record media metadata");
+            let _ = state.storage.delete(&storage_key).await;
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+/// List media for the current domain, newest first
+/// Requires domain viewer permissions or higher
+async fn list_media(
+    RequireDomainViewer(auth): RequireDomainViewer,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<MediaQuery>,
+) -> Result<Json<Vec<MediaResponse>>, StatusCode> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let offset = (page - 1) * limit;
+
+    let rows = sqlx::query_as::<_, MediaRow>(
+        r#"
+        SELECT id, storage_key, filename, content_type, size_bytes, created_at
+        FROM media
+        WHERE domain_id = $1
+        ORDER BY created_at DESC, id DESC
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(auth.domain.id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(
+        rows.into_iter()
+            .map(|row| MediaResponse::from_row(row, &state))
+            .collect(),
+    ))
+}
+
+/// Delete a media item and its stored file
+/// Requires domain editor permissions or higher
+async fn delete_media(
+    RequireDomainEditor(auth): RequireDomainEditor,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, StatusCode> {
+    let storage_key: String = sqlx::query_scalar(
+        "DELETE FROM media WHERE id = $1 AND domain_id = $2 RETURNING storage_key",
+    )
+    .bind(id)
+    .bind(auth.domain.id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    // The row is gone either way; a missing file only needs a log line
+    if let Err(e) = state.storage.delete(&storage_key).await {
+        tracing::warn!(error = %e, storage_key = %storage_key, "Failed to delete stored media");
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ============================================================================
+// ANALYTICS & REPORTING HANDLERS
+// ============================================================================
+// Admin-level analytics providing dashboard metrics and reporting.
+// Supports both domain-scoped and platform-wide analytics depending on user permissions.
+
+/// Get analytics summary for admin dashboard
+/// Returns comprehensive metrics for the last 30 days
+/// Domain users see their domain stats, platform admins see aggregated stats
+async fn get_analytics_summary(
+    RequireDomainViewer(auth): RequireDomainViewer,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    // Get domain-specific analytics for the dashboard
+    let summary = sqlx::query!(
+        r#"
+        SELECT 
+            ROUND(COALESCE(SUM(sample_weight) FILTER (WHERE event_type = 'page_view'), 0))::BIGINT as page_views,
+            COUNT(*) FILTER (WHERE event_type = 'post_view') as post_views,
+            COUNT(DISTINCT ip_address) as unique_visitors,
+            COUNT(*) FILTER (WHERE event_type = 'search') as searches
+        FROM analytics_events 
+        WHERE domain_id = $1 AND created_at >= NOW() - INTERVAL '30 days'
+        "#,
+        auth.domain.id
+    )
+    .fetch_one(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Get total posts count for this domain
+    let posts_count = sqlx::query!(
+        "SELECT COUNT(*) as total FROM posts WHERE domain_id = $1",
+        auth.domain.id
+    )
+    .fetch_one(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .total
+    .unwrap_or(0);
+
+    // Get posts created this month for this domain
+    let posts_this_month = sqlx::query!(
+        "SELECT COUNT(*) as total FROM posts WHERE domain_id = $1 AND created_at >= DATE_TRUNC('month', NOW())",
+        auth.domain.id
+    )
+    .fetch_one(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .total
+    .unwrap_or(0);
+
+    // Totals cover only the domains this user can see; every domain for
+    // platform admins
+    let domain_ids = analytics::accessible_domain_ids(&auth.user, &state.db).await?;
+
+    let all_domains_posts = sqlx::query!(
+        "SELECT COUNT(*) as total FROM posts WHERE domain_id = ANY($1)",
+        &domain_ids
+    )
+    .fetch_one(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .total
+    .unwrap_or(0);
+
+    let all_domains_analytics = sqlx::query!(
+        r#"
+        SELECT 
+            ROUND(COALESCE(SUM(sample_weight) FILTER (WHERE event_type = 'page_view'), 0))::BIGINT as page_views,
+            COUNT(*) FILTER (WHERE event_type = 'post_view') as post_views,
+            COUNT(DISTINCT ip_address) as unique_visitors
+        FROM analytics_events 
+        WHERE domain_id = ANY($1) AND created_at >= NOW() - INTERVAL '30 days'
+        "#,
+        &domain_ids
+    )
+    .fetch_one(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Count active domains
+    let active_domains = domain_ids.len() as i64;
+
+    // Return aggregated data for dashboard
+    let total_views = all_domains_analytics.page_views.unwrap_or(0)
         + all_domains_analytics.post_views.unwrap_or(0);
 
     Ok(Json(serde_json::json!({
@@ -559,6 +1762,8 @@ async fn get_domain_settings(
         "name": auth.domain.name,
         "theme_config": auth.domain.theme_config,
         "categories": auth.domain.categories,
+        "timezone": auth.domain.timezone,
+        "cors_origins": auth.domain.cors_origins,
         "seo_config": auth.domain.theme_config.get("seo_config").unwrap_or(&serde_json::json!({})),
         "analytics_config": auth.domain.theme_config.get("analytics_config").unwrap_or(&serde_json::json!({})),
         "content_config": auth.domain.theme_config.get("content_config").unwrap_or(&serde_json::json!({})),
@@ -586,10 +1791,36 @@ async fn update_domain_settings(
         .get("seo_config")
         .cloned()
         .unwrap_or_else(|| serde_json::json!({}));
+    // Canonical URLs have to identify the post
+    if let Some(format) = seo_config.get("permalink_format") {
+        let format = format.as_str().ok_or(StatusCode::BAD_REQUEST)?;
+        permalinks::validate(format).map_err(|_| StatusCode::BAD_REQUEST)?;
+    }
     let analytics_config = payload
         .get("analytics_config")
         .cloned()
         .unwrap_or_else(|| serde_json::json!({}));
+    // Custom event names share the event_type namespace with built-in ones
+    if let Some(names) = analytics_config.get("custom_events") {
+        let names: Vec<String> =
+            serde_json::from_value(names.clone()).map_err(|_| StatusCode::BAD_REQUEST)?;
+        if !names
+            .iter()
+            .all(|name| event_types::is_valid_custom_name(name))
+        {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+    // Only user agent and referrer can be kept out of analytics exports
+    if let Some(names) = analytics_config.get("export_exclude") {
+        let names: Vec<String> =
+            serde_json::from_value(names.clone()).map_err(|_| StatusCode::BAD_REQUEST)?;
+        if !names.iter().all(|name| {
+            ExportField::parse(name).is_some_and(|field| ExportField::PRIVATE.contains(&field))
+        }) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
     let content_config = payload
         .get("content_config")
         .cloned()
@@ -598,6 +1829,33 @@ async fn update_domain_settings(
         .get("social_config")
         .cloned()
         .unwrap_or_else(|| serde_json::json!({}));
+    // Time zone for analytics buckets; kept unless a new one is sent
+    let timezone = match payload.get("timezone") {
+        None => auth.domain.timezone.clone(),
+        Some(tz) => {
+            let tz = tz.as_str().ok_or(StatusCode::BAD_REQUEST)?;
+            if !timezones::is_known(&state.db, tz)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            tz.to_string()
+        }
+    };
+    // Origins the blog and session routes accept cross-origin; kept unless
+    // new ones are sent
+    let cors_origins = match payload.get("cors_origins") {
+        None => auth.domain.cors_origins.clone(),
+        Some(origins) => {
+            let origins: Vec<String> =
+                serde_json::from_value(origins.clone()).map_err(|_| StatusCode::BAD_REQUEST)?;
+            if !origins.iter().all(|origin| cors::is_valid_origin(origin)) {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            origins
+        }
+    };
 
     // Create comprehensive settings object
     let comprehensive_settings = serde_json::json!({
@@ -612,17 +1870,495 @@ async fn update_domain_settings(
 
     // Update the domain with all settings
     sqlx::query!(
-        "UPDATE domains SET theme_config = $2, categories = $3, updated_at = NOW() WHERE id = $1",
+        "UPDATE domains SET theme_config = $2, categories = $3, timezone = $4, cors_origins = $5, updated_at = NOW() WHERE id = $1",
         auth.domain.id,
         &comprehensive_settings,
-        categories
+        categories,
+        timezone,
+        serde_json::json!(cors_origins)
     )
     .execute(&state.db)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     // Return the comprehensive settings
-    Ok(Json(comprehensive_settings))
+    let mut settings = comprehensive_settings;
+    settings["timezone"] = serde_json::json!(timezone);
+    settings["cors_origins"] = serde_json::json!(cors_origins);
+    Ok(Json(settings))
+}
+
+/// Every known feature with its effective on/off state
+fn feature_states(domain: &DomainContext) -> std::collections::BTreeMap<&'static str, bool> {
+    DOMAIN_FEATURES
+        .iter()
+        .map(|name| (*name, domain.feature_enabled(name)))
+        .collect()
+}
+
+async fn get_domain_features(
+    RequireDomainViewer(auth): RequireDomainViewer,
+) -> Json<std::collections::BTreeMap<&'static str, bool>> {
+    Json(feature_states(&auth.domain))
+}
+
+/// Toggle features for the current domain, e.g. `{"search": false}`.
+/// Features not mentioned keep their current state.
+async fn update_domain_features(
+    RequireDomainAdmin(auth): RequireDomainAdmin,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<std::collections::HashMap<String, bool>>,
+) -> Result<Json<std::collections::BTreeMap<&'static str, bool>>, StatusCode> {
+    if payload
+        .keys()
+        .any(|name| !DOMAIN_FEATURES.contains(&name.as_str()))
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let features: serde_json::Value = sqlx::query_scalar(
+        "UPDATE domains SET features = features || $2, updated_at = NOW() WHERE id = $1 RETURNING features",
+    )
+    .bind(auth.domain.id)
+    .bind(serde_json::json!(payload))
+    .fetch_one(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let domain = DomainContext {
+        features,
+        ..auth.domain
+    };
+    Ok(Json(feature_states(&domain)))
+}
+
+/// A domain's navigation menu, as read and replaced through `/domain/menu`
+#[derive(Serialize, Deserialize)]
+pub struct MenuPayload {
+    items: Vec<MenuItem>,
+}
+
+impl Validate for MenuPayload {
+    fn validate(&self) -> Result<(), validator::ValidationErrors> {
+        menu::validate(&self.items).map_err(|e| {
+            let mut errors = validator::ValidationErrors::new();
+            let mut error = validator::ValidationError::new("menu");
+            error.message = Some(e.to_string().into());
+            errors.add("items", error);
+            errors
+        })
+    }
+}
+
+async fn get_domain_menu(
+    RequireDomainViewer(auth): RequireDomainViewer,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<MenuPayload>, StatusCode> {
+    let items = menu::load(&state.db, auth.domain.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(MenuPayload { items }))
+}
+
+/// Replace the current domain's menu with `{"items": [...]}`
+async fn update_domain_menu(
+    RequireDomainAdmin(auth): RequireDomainAdmin,
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(payload): ValidatedJson<MenuPayload>,
+) -> Result<Json<MenuPayload>, StatusCode> {
+    menu::save(&state.db, auth.domain.id, &payload.items)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(payload))
+}
+
+impl Validate for SearchSettings {
+    fn validate(&self) -> Result<(), validator::ValidationErrors> {
+        self.check().map_err(|e| {
+            let mut errors = validator::ValidationErrors::new();
+            let mut error = validator::ValidationError::new("search");
+            error.message = Some(e.to_string().into());
+            errors.add(e.field(), error);
+            errors
+        })
+    }
+}
+
+/// The current domain's search weights, synonyms and stopwords
+async fn get_search_settings(
+    RequireDomainViewer(auth): RequireDomainViewer,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<SearchSettings>, StatusCode> {
+    let settings = search::load(&state.db, auth.domain.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(settings))
+}
+
+/// Replace the current domain's search settings; omitted fields reset to
+/// their defaults
+async fn update_search_settings(
+    RequireDomainAdmin(auth): RequireDomainAdmin,
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(settings): ValidatedJson<SearchSettings>,
+) -> Result<Json<SearchSettings>, StatusCode> {
+    search::save(&state.db, auth.domain.id, &settings)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(settings))
+}
+
+#[derive(Deserialize, Validate)]
+struct EmailTemplateRequest {
+    #[validate(length(
+        min = 1,
+        max = 200,
+        message = "Subject must be between 1 and 200 characters"
+    ))]
+    subject: String,
+    #[validate(length(
+        min = 1,
+        max = 20000,
+        message = "Body must be between 1 and 20000 characters"
+    ))]
+    body: String,
+}
+
+/// An email template in effect, with the placeholders it can use
+#[derive(Serialize)]
+struct EmailTemplateResponse {
+    kind: &'static str,
+    source: TemplateSource,
+    subject: String,
+    body: String,
+    placeholders: &'static [&'static str],
+}
+
+/// The templates in effect for `domain_id`, or platform-wide for `None`
+async fn email_templates_in_effect(
+    db: &sqlx::PgPool,
+    domain_id: Option<i32>,
+) -> Result<Json<Vec<EmailTemplateResponse>>, StatusCode> {
+    let mut templates = Vec::new();
+    for kind in EmailKind::ALL {
+        if domain_id.is_some() && !kind.per_domain() {
+            continue;
+        }
+        let (template, source) = email_templates::resolve(db, domain_id, kind)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        templates.push(EmailTemplateResponse {
+            kind: kind.as_str(),
+            source,
+            subject: template.subject,
+            body: template.body,
+            placeholders: kind.placeholders(),
+        });
+    }
+    Ok(Json(templates))
+}
+
+/// The email kind named in the path; `404` for unknown kinds, and on domain
+/// routes for kinds only the platform can customize
+fn email_kind(kind: &str, for_domain: bool) -> Result<EmailKind, StatusCode> {
+    EmailKind::parse(kind)
+        .filter(|kind| !for_domain || kind.per_domain())
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// The current domain's email templates, falling back to the platform's
+async fn list_domain_email_templates(
+    RequireDomainViewer(auth): RequireDomainViewer,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<EmailTemplateResponse>>, StatusCode> {
+    email_templates_in_effect(&state.db, Some(auth.domain.id)).await
+}
+
+/// Replace the current domain's template for one kind of email
+async fn update_domain_email_template(
+    RequireDomainAdmin(auth): RequireDomainAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(kind): Path<String>,
+    ValidatedJson(payload): ValidatedJson<EmailTemplateRequest>,
+) -> Result<Json<Vec<EmailTemplateResponse>>, StatusCode> {
+    let kind = email_kind(&kind, true)?;
+    let template = EmailTemplate {
+        subject: payload.subject,
+        body: payload.body,
+    };
+    email_templates::save(&state.db, Some(auth.domain.id), kind, &template)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    email_templates_in_effect(&state.db, Some(auth.domain.id)).await
+}
+
+/// Go back to the platform's template for one kind of email
+async fn delete_domain_email_template(
+    RequireDomainAdmin(auth): RequireDomainAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(kind): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let kind = email_kind(&kind, true)?;
+    if email_templates::delete(&state.db, Some(auth.domain.id), kind)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+// ============================================================================
+// MEMBERS
+// ============================================================================
+// Readers allowed into the current domain's members-only posts (domain admin
+// only); they sign in through /members/login, not /auth
+
+#[derive(Deserialize)]
+pub struct CreateMemberRequest {
+    email: String,
+    name: String,
+    password: String, // Validated for strength like user passwords
+}
+
+impl Validate for CreateMemberRequest {
+    fn validate(&self) -> Result<(), validator::ValidationErrors> {
+        crate::validation::custom::validate_create_member_request(
+            &self.email,
+            &self.name,
+            &self.password,
+        )
+    }
+}
+
+async fn list_members(
+    RequireDomainAdmin(auth): RequireDomainAdmin,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<Member>>, StatusCode> {
+    let members = members::list(&state.db, auth.domain.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(members))
+}
+
+#[derive(Deserialize)]
+pub struct SubscribersQuery {
+    /// `pending` or `confirmed`; both when omitted
+    status: Option<SubscriberStatus>,
+}
+
+#[derive(Serialize)]
+pub struct SubscribersResponse {
+    subscribers: Vec<Subscriber>,
+    total: i64,
+    page: i64,
+    per_page: i64,
+}
+
+/// Newsletter subscribers of the current domain, newest first
+async fn list_subscribers(
+    RequireDomainViewer(auth): RequireDomainViewer,
+    pagination: Pagination,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SubscribersQuery>,
+) -> Result<Json<SubscribersResponse>, StatusCode> {
+    let (subscribers, total) = subscribers::list(
+        &state.db,
+        auth.domain.id,
+        query.status,
+        pagination.per_page,
+        pagination.offset(),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(SubscribersResponse {
+        subscribers,
+        total,
+        page: pagination.page,
+        per_page: pagination.per_page,
+    }))
+}
+
+/// Every subscriber of the current domain as CSV
+async fn export_subscribers(
+    RequireDomainViewer(auth): RequireDomainViewer,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SubscribersQuery>,
+) -> Result<Response, StatusCode> {
+    let (subscribers, _) = subscribers::list(&state.db, auth.domain.id, query.status, i64::MAX, 0)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let timestamp = |at: Option<DateTime<Utc>>| {
+        at.map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default()
+    };
+    let mut csv = String::from("email,status,created_at,confirmed_at\n");
+    for subscriber in subscribers {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            subscriber.email.replace(",", ";"),
+            subscriber.status,
+            timestamp(Some(subscriber.created_at)),
+            timestamp(subscriber.confirmed_at),
+        ));
+    }
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"subscribers.csv\"",
+            ),
+        ],
+        csv,
+    )
+        .into_response())
+}
+
+/// Add a member to the current domain; 409 if the email already is one
+async fn create_member(
+    RequireDomainAdmin(auth): RequireDomainAdmin,
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(payload): ValidatedJson<CreateMemberRequest>,
+) -> Result<(StatusCode, Json<Member>), StatusCode> {
+    use bcrypt::{DEFAULT_COST, hash};
+    let password_hash =
+        hash(&payload.password, DEFAULT_COST).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let member = members::create(
+        &state.db,
+        auth.domain.id,
+        payload.email.trim(),
+        payload.name.trim(),
+        &password_hash,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::CONFLICT)?;
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(auth.user.id),
+            impersonator_id: auth.user.impersonator_id,
+            action: "member.create".to_string(),
+            details: serde_json::json!({ "domain_id": auth.domain.id, "member_id": member.id }),
+        },
+    )
+    .await;
+
+    Ok((StatusCode::CREATED, Json(member)))
+}
+
+/// Remove a member; their member tokens stop working right away
+async fn delete_member(
+    RequireDomainAdmin(auth): RequireDomainAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = members::delete(&state.db, auth.domain.id, id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(auth.user.id),
+            impersonator_id: auth.user.impersonator_id,
+            action: "member.delete".to_string(),
+            details: serde_json::json!({ "domain_id": auth.domain.id, "member_id": id }),
+        },
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ============================================================================
+// ANALYTICS ALERTS
+// ============================================================================
+// Traffic spike/drop alerts for the current domain (domain admin only);
+// checked in the background by services::alerts
+
+impl Validate for AlertSettings {
+    fn validate(&self) -> Result<(), validator::ValidationErrors> {
+        self.check().map_err(|e| {
+            let mut errors = validator::ValidationErrors::new();
+            let mut error = validator::ValidationError::new("alert");
+            error.message = Some(e.to_string().into());
+            errors.add(e.field(), error);
+            errors
+        })
+    }
+}
+
+async fn list_alerts(
+    RequireDomainAdmin(auth): RequireDomainAdmin,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<Alert>>, StatusCode> {
+    let alerts = alerts::list(&state.db, auth.domain.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(alerts))
+}
+
+async fn create_alert(
+    RequireDomainAdmin(auth): RequireDomainAdmin,
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(payload): ValidatedJson<AlertSettings>,
+) -> Result<Json<Alert>, StatusCode> {
+    let alert = alerts::create(&state.db, auth.domain.id, &payload)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(alert))
+}
+
+async fn get_alert(
+    RequireDomainAdmin(auth): RequireDomainAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<Json<Alert>, StatusCode> {
+    alerts::get(&state.db, auth.domain.id, id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Replace an alert's settings; the new rule starts out as not firing
+async fn update_alert(
+    RequireDomainAdmin(auth): RequireDomainAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    ValidatedJson(payload): ValidatedJson<AlertSettings>,
+) -> Result<Json<Alert>, StatusCode> {
+    alerts::update(&state.db, auth.domain.id, id, &payload)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn delete_alert(
+    RequireDomainAdmin(auth): RequireDomainAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, StatusCode> {
+    if alerts::delete(&state.db, auth.domain.id, id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
 }
 
 // ============================================================================
@@ -646,6 +2382,27 @@ struct CreateDomainRequest {
     categories: Option<Vec<String>>,          // Optional default categories for the domain
 }
 
+/// Request structure for starting a domain from another one
+#[derive(Deserialize, Validate)]
+struct CloneDomainRequest {
+    #[validate(custom(function = "validate_hostname", message = "Invalid hostname format"))]
+    hostname: String, // Hostname of the new domain - must be unique
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Name must be between 1 and 100 characters"
+    ))]
+    name: String, // Human-readable name of the new domain
+    #[serde(default)]
+    include_posts: bool, // Also copy the source's posts, as drafts
+}
+
+#[derive(Serialize)]
+struct CloneDomainResponse {
+    domain: DomainResponse,
+    cloned: CloneReport, // What was copied from the source
+}
+
 /// Response structure for domain operations
 /// Includes aggregated statistics for admin overview
 #[derive(Serialize, sqlx::FromRow)]
@@ -695,7 +2452,7 @@ async fn list_domains(
             d.updated_at,
             COUNT(p.id) as posts_count,
             COUNT(DISTINCT ae.ip_address) FILTER (WHERE ae.created_at >= NOW() - INTERVAL '30 days') as active_users,
-            COUNT(ae.id) FILTER (WHERE ae.created_at >= NOW() - INTERVAL '30 days' AND ae.event_type IN ('page_view', 'post_view')) as monthly_views
+            ROUND(COALESCE(SUM(ae.sample_weight) FILTER (WHERE ae.created_at >= NOW() - INTERVAL '30 days' AND ae.event_type IN ('page_view', 'post_view')), 0))::BIGINT as monthly_views
         FROM domains d
         LEFT JOIN posts p ON d.id = p.domain_id
         LEFT JOIN analytics_events ae ON d.id = ae.domain_id
@@ -730,7 +2487,7 @@ async fn get_domain(
             d.updated_at,
             COUNT(p.id) as posts_count,
             COUNT(DISTINCT ae.ip_address) FILTER (WHERE ae.created_at >= NOW() - INTERVAL '30 days') as active_users,
-            COUNT(ae.id) FILTER (WHERE ae.created_at >= NOW() - INTERVAL '30 days' AND ae.event_type IN ('page_view', 'post_view')) as monthly_views
+            ROUND(COALESCE(SUM(ae.sample_weight) FILTER (WHERE ae.created_at >= NOW() - INTERVAL '30 days' AND ae.event_type IN ('page_view', 'post_view')), 0))::BIGINT as monthly_views
         FROM domains d
         LEFT JOIN posts p ON d.id = p.domain_id
         LEFT JOIN analytics_events ae ON d.id = ae.domain_id
@@ -752,14 +2509,13 @@ async fn create_domain(
     State(state): State<Arc<AppState>>,
     ValidatedJson(payload): ValidatedJson<CreateDomainRequest>,
 ) -> Result<Json<DomainResponse>, StatusCode> {
+    let hostname = normalize_hostname(&payload.hostname).map_err(|_| StatusCode::BAD_REQUEST)?;
+
     // Validate hostname uniqueness
-    let existing = sqlx::query!(
-        "SELECT id FROM domains WHERE hostname = $1",
-        payload.hostname
-    )
-    .fetch_optional(&state.db)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let existing = sqlx::query!("SELECT id FROM domains WHERE hostname = $1", hostname)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     if existing.is_some() {
         return Err(StatusCode::CONFLICT);
@@ -789,7 +2545,7 @@ async fn create_domain(
             0::bigint as active_users,
             0::bigint as monthly_views
         "#,
-        payload.hostname,
+        hostname,
         payload.name,
         theme_config,
         categories_json
@@ -801,12 +2557,88 @@ async fn create_domain(
     Ok(Json(domain))
 }
 
+/// Create a domain from another one used as a template, see
+/// [`domain_clone`] (platform_admin only). The new domain is unverified and
+/// has no analytics; 404 for an unknown source, 409 for a taken hostname.
+async fn clone_domain(
+    RequirePlatformAdmin { user }: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    ValidatedJson(payload): ValidatedJson<CloneDomainRequest>,
+) -> Result<Json<CloneDomainResponse>, StatusCode> {
+    let hostname = normalize_hostname(&payload.hostname).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let taken: Option<i32> = sqlx::query_scalar("SELECT id FROM domains WHERE hostname = $1")
+        .bind(&hostname)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if taken.is_some() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let cloned = domain_clone::clone_domain(
+        &state.db,
+        id,
+        &hostname,
+        &payload.name,
+        payload.include_posts,
+    )
+    .await
+    .map_err(|e| match e.as_database_error() {
+        // Taken by a concurrent request since the check above
+        Some(db_err) if db_err.is_unique_violation() => StatusCode::CONFLICT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let domain = sqlx::query_as::<_, DomainResponse>(
+        r#"
+        SELECT d.id, d.hostname, d.name,
+               COALESCE(d.theme_config, '{}'::jsonb) AS theme_config,
+               COALESCE(d.categories, '[]'::jsonb) AS categories,
+               d.created_at, d.updated_at,
+               (SELECT COUNT(*) FROM posts p WHERE p.domain_id = d.id) AS posts_count,
+               0::bigint AS active_users,
+               0::bigint AS monthly_views
+        FROM domains d
+        WHERE d.id = $1
+        "#,
+    )
+    .bind(cloned.domain_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(user.id),
+            impersonator_id: user.impersonator_id,
+            action: "domain.clone".to_string(),
+            details: serde_json::json!({
+                "source_id": id,
+                "domain_id": cloned.domain_id,
+                "hostname": domain.hostname,
+                "posts": cloned.posts,
+            }),
+        },
+    )
+    .await;
+
+    Ok(Json(CloneDomainResponse { domain, cloned }))
+}
+
 async fn update_domain(
     _auth: RequirePlatformAdmin,
     State(state): State<Arc<AppState>>,
     Path(id): Path<i32>,
-    ValidatedJson(payload): ValidatedJson<UpdateDomainRequest>,
+    ValidatedJson(mut payload): ValidatedJson<UpdateDomainRequest>,
 ) -> Result<Json<DomainResponse>, StatusCode> {
+    if let Some(hostname) = &payload.hostname {
+        payload.hostname = Some(normalize_hostname(hostname).map_err(|_| StatusCode::BAD_REQUEST)?);
+    }
+
     // Check if domain exists
     let existing = sqlx::query!("SELECT hostname FROM domains WHERE id = $1", id)
         .fetch_optional(&state.db)
@@ -892,7 +2724,7 @@ async fn update_domain(
             d.updated_at,
             COUNT(p.id) as posts_count,
             COUNT(DISTINCT ae.ip_address) FILTER (WHERE ae.created_at >= NOW() - INTERVAL '30 days') as active_users,
-            COUNT(ae.id) FILTER (WHERE ae.created_at >= NOW() - INTERVAL '30 days' AND ae.event_type IN ('page_view', 'post_view')) as monthly_views
+            ROUND(COALESCE(SUM(ae.sample_weight) FILTER (WHERE ae.created_at >= NOW() - INTERVAL '30 days' AND ae.event_type IN ('page_view', 'post_view')), 0))::BIGINT as monthly_views
         FROM domains d
         LEFT JOIN posts p ON d.id = p.domain_id
         LEFT JOIN analytics_events ae ON d.id = ae.domain_id
@@ -908,6 +2740,173 @@ async fn update_domain(
     Ok(Json(domain))
 }
 
+#[derive(Serialize)]
+struct DomainUsageResponse {
+    usage: Usage,
+    quota: Quota,
+}
+
+/// Posts and media bytes the current domain stores, next to its quota
+async fn get_domain_usage(
+    RequireDomainViewer(auth): RequireDomainViewer,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<DomainUsageResponse>, StatusCode> {
+    let quota = quota::load(&state.db, auth.domain.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .unwrap_or_default();
+    let usage = quota::usage(&state.db, auth.domain.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(DomainUsageResponse { usage, quota }))
+}
+
+// Storage limits of a domain (platform_admin only)
+async fn get_domain_quota(
+    _auth: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<Json<Quota>, StatusCode> {
+    quota::load(&state.db, id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+// Replace a domain's storage limits (platform_admin only). Lowering a limit
+// below current usage keeps existing content and blocks further writes.
+async fn update_domain_quota(
+    RequirePlatformAdmin { user }: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+    ValidatedJson(payload): ValidatedJson<Quota>,
+) -> Result<Json<Quota>, StatusCode> {
+    let previous = quota::load(&state.db, id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    quota::save(&state.db, id, &payload)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(user.id),
+            impersonator_id: user.impersonator_id,
+            action: "domain.quota_update".to_string(),
+            details: serde_json::json!({ "domain_id": id, "from": previous, "to": payload }),
+        },
+    )
+    .await;
+
+    Ok(Json(payload))
+}
+
+#[derive(Serialize)]
+struct DomainVerification {
+    verified: bool,
+    verified_at: Option<DateTime<Utc>>,
+    records_found: bool,       // Whether this check found one of the records
+    expected: ExpectedRecords, // What to publish for the domain to verify
+}
+
+/// Check a domain's DNS and mark it verified when it points at the platform
+/// (platform_admin only). A failed check leaves an earlier verification in
+/// place; 504 when the lookups time out, 502 when they fail.
+async fn verify_domain(
+    RequirePlatformAdmin { user }: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<Json<DomainVerification>, Response> {
+    let domain = domain_verification::load(&state.db, id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+    let config = VerificationConfig::default();
+    let expected = ExpectedRecords::new(&domain.hostname, &domain.verification_token, &config);
+
+    let checked = domain_verification::check(
+        state.dns.as_ref(),
+        &domain.hostname,
+        &expected,
+        config.timeout,
+    )
+    .await;
+    let records_found = match checked {
+        Ok(found) => found,
+        Err(e) => {
+            tracing::warn!(error = %e, domain_id = id, "Domain verification lookup failed");
+            let (status, error) = match e {
+                DnsError::Timeout => (StatusCode::GATEWAY_TIMEOUT, "dns_timeout"),
+                DnsError::Lookup(_) => (StatusCode::BAD_GATEWAY, "dns_lookup_failed"),
+            };
+            return Err((
+                status,
+                Json(serde_json::json!({ "error": error, "message": e.to_string() })),
+            )
+                .into_response());
+        }
+    };
+
+    let mut verified_at = domain.verified_at;
+    if records_found {
+        verified_at = Some(
+            domain_verification::mark_verified(&state.db, id)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?,
+        );
+        audit::record(
+            &state.db,
+            AuditEntry {
+                actor_id: Some(user.id),
+                impersonator_id: user.impersonator_id,
+                action: "domain.verify".to_string(),
+                details: serde_json::json!({ "domain_id": id, "hostname": domain.hostname }),
+            },
+        )
+        .await;
+    }
+
+    Ok(Json(DomainVerification {
+        verified: verified_at.is_some(),
+        verified_at,
+        records_found,
+        expected,
+    }))
+}
+
+/// Download a zip of a domain's posts, analytics events, settings and user
+/// permissions, plus a manifest with counts (platform_admin only)
+async fn export_domain(
+    _auth: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i32>,
+) -> Result<Response, StatusCode> {
+    let hostname: String = sqlx::query_scalar("SELECT hostname FROM domains WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let archive = domain_export::export_domain(state.db.clone(), id);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{hostname}-export.zip\""),
+            ),
+        ],
+        Body::from_stream(ReaderStream::new(archive)),
+    )
+        .into_response())
+}
+
 async fn delete_domain(
     _auth: RequirePlatformAdmin,
     State(state): State<Arc<AppState>>,
@@ -948,6 +2947,7 @@ struct AdminAnalyticsOverview {
     current_period: AdminPeriodStats,
     previous_period: AdminPeriodStats,
     change_percent: AdminChangePercent,
+    session_data_available: bool, // Whether the current period has session durations
     top_posts: Vec<AdminPostStats>,
     top_categories: Vec<AdminCategoryStats>,
 }
@@ -958,7 +2958,7 @@ struct AdminPeriodStats {
     unique_visitors: i64,
     post_views: i64,
     searches: i64,
-    avg_session_duration: f64,
+    avg_session_duration: Option<f64>, // null without session data
 }
 
 #[derive(Serialize)]
@@ -1038,7 +3038,7 @@ struct AdminSearchVolumeDay {
 #[derive(Serialize)]
 struct AdminReferrerResponse {
     top_referrers: Vec<AdminReferrerStats>,
-    referrer_types: AdminReferrerTypeBreakdown,
+    referrer_types: ReferrerTypeBreakdown,
 }
 
 #[derive(Serialize)]
@@ -1048,36 +3048,48 @@ struct AdminReferrerStats {
     unique_visitors: i64,
 }
 
-#[derive(Serialize)]
-struct AdminReferrerTypeBreakdown {
-    direct: i64,
-    search_engines: i64,
-    social_media: i64,
-    other_websites: i64,
-}
-
 #[derive(Deserialize)]
 struct AdminAnalyticsQuery {
     days: Option<i32>, // Default 30
     start_date: Option<String>,
     end_date: Option<String>,
+    tz: Option<String>, // Zone for day/hour buckets, default UTC
+}
+
+/// Zone for platform-wide day and hour buckets; unknown zones are a bad request
+async fn admin_bucket_timezone(
+    query: &AdminAnalyticsQuery,
+    db: &sqlx::PgPool,
+) -> Result<String, StatusCode> {
+    let Some(tz) = &query.tz else {
+        return Ok(timezones::default_timezone());
+    };
+    if timezones::is_known(db, tz)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    {
+        Ok(tz.clone())
+    } else {
+        Err(StatusCode::BAD_REQUEST)
+    }
 }
 
 // Helper to parse date range
-fn parse_admin_date_range(query: &AdminAnalyticsQuery) -> (DateTime<Utc>, DateTime<Utc>) {
+fn parse_admin_date_range(
+    query: &AdminAnalyticsQuery,
+    now: DateTime<Utc>,
+) -> (DateTime<Utc>, DateTime<Utc>) {
     // If explicit dates are provided, use them
     if let (Some(start_str), Some(end_str)) = (&query.start_date, &query.end_date) {
         let start_date = start_str
             .parse::<DateTime<Utc>>()
-            .unwrap_or_else(|_| Utc::now() - Duration::days(30));
-        let end_date = end_str
-            .parse::<DateTime<Utc>>()
-            .unwrap_or_else(|_| Utc::now());
+            .unwrap_or_else(|_| now - Duration::days(30));
+        let end_date = end_str.parse::<DateTime<Utc>>().unwrap_or(now);
         return (start_date, end_date);
     }
 
     // Otherwise, use the days parameter (default behavior)
-    let end_date = Utc::now();
+    let end_date = now;
     let days = query.days.unwrap_or(30).clamp(1, 365);
     let start_date = end_date - Duration::days(days as i64);
     (start_date, end_date)
@@ -1090,29 +3102,36 @@ async fn get_admin_analytics_overview(
     Query(query): Query<AdminAnalyticsQuery>,
 ) -> Result<Json<AdminAnalyticsOverview>, StatusCode> {
     PerformanceSpan::monitor("admin_analytics_overview", async {
-        let (start_date, end_date) = parse_admin_date_range(&query);
+        let (start_date, end_date) = parse_admin_date_range(&query, state.clock.now());
         let previous_start = start_date - (end_date - start_date);
 
-        // Get real session duration data (fallback to mock while migration is pending)
-        let current_avg_session_duration =
-            SessionTracker::get_average_session_duration(&state.db, start_date, end_date, None)
-                .await
-                .unwrap_or(3.5);
+        // Session durations, absent when there are no sessions to average
+        let session_config = SessionConfig::default();
+        let current_avg_session_duration = SessionTracker::get_average_session_duration(
+            &state.db,
+            start_date,
+            end_date,
+            None,
+            &session_config,
+        )
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         let previous_avg_session_duration = SessionTracker::get_average_session_duration(
             &state.db,
             previous_start,
             start_date,
             None,
+            &session_config,
         )
         .await
-        .unwrap_or(3.2);
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         // Current period stats across all domains
         let current_stats = sqlx::query!(
             r#"
         SELECT 
-            COUNT(*) FILTER (WHERE event_type = 'page_view') as page_views,
+            ROUND(COALESCE(SUM(sample_weight) FILTER (WHERE event_type = 'page_view'), 0))::BIGINT as page_views,
             COUNT(*) FILTER (WHERE event_type = 'post_view') as post_views,
             COUNT(DISTINCT ip_address) as unique_visitors,
             COUNT(*) FILTER (WHERE event_type = 'search') as searches
@@ -1130,7 +3149,7 @@ async fn get_admin_analytics_overview(
         let previous_stats = sqlx::query!(
             r#"
         SELECT 
-            COUNT(*) FILTER (WHERE event_type = 'page_view') as page_views,
+            ROUND(COALESCE(SUM(sample_weight) FILTER (WHERE event_type = 'page_view'), 0))::BIGINT as page_views,
             COUNT(*) FILTER (WHERE event_type = 'post_view') as post_views,
             COUNT(DISTINCT ip_address) as unique_visitors,
             COUNT(*) FILTER (WHERE event_type = 'search') as searches
@@ -1266,6 +3285,7 @@ async fn get_admin_analytics_overview(
                 post_views: post_views_change,
                 searches: searches_change,
             },
+            session_data_available: current_avg_session_duration.is_some(),
             top_posts,
             top_categories,
         }))
@@ -1279,23 +3299,25 @@ async fn get_admin_traffic_stats(
     State(state): State<Arc<AppState>>,
     Query(query): Query<AdminAnalyticsQuery>,
 ) -> Result<Json<AdminTrafficResponse>, StatusCode> {
-    let (start_date, end_date) = parse_admin_date_range(&query);
+    let (start_date, end_date) = parse_admin_date_range(&query, state.clock.now());
+    let tz = admin_bucket_timezone(&query, &state.db).await?;
 
     // Daily stats
     let daily_data = sqlx::query!(
         r#"
         SELECT 
-            DATE(created_at) as date,
-            COUNT(*) FILTER (WHERE event_type = 'page_view') as page_views,
+            (created_at AT TIME ZONE $3)::date as date,
+            ROUND(COALESCE(SUM(sample_weight) FILTER (WHERE event_type = 'page_view'), 0))::BIGINT as page_views,
             COUNT(*) FILTER (WHERE event_type = 'post_view') as post_views,
             COUNT(DISTINCT ip_address) as unique_visitors
         FROM analytics_events 
         WHERE created_at BETWEEN $1 AND $2
-        GROUP BY DATE(created_at)
+        GROUP BY 1
         ORDER BY date
         "#,
         start_date,
-        end_date
+        end_date,
+        tz
     )
     .fetch_all(&state.db)
     .await
@@ -1315,16 +3337,17 @@ async fn get_admin_traffic_stats(
     let hourly_data = sqlx::query!(
         r#"
         SELECT 
-            EXTRACT(HOUR FROM created_at) as hour,
-            COUNT(*) FILTER (WHERE event_type = 'page_view') as page_views,
+            CAST(EXTRACT(HOUR FROM created_at AT TIME ZONE $3) AS INTEGER) as hour,
+            ROUND(COALESCE(SUM(sample_weight) FILTER (WHERE event_type = 'page_view'), 0))::BIGINT as page_views,
             COUNT(DISTINCT ip_address) as unique_visitors
         FROM analytics_events 
         WHERE created_at BETWEEN $1 AND $2
-        GROUP BY EXTRACT(HOUR FROM created_at)
+        GROUP BY hour
         ORDER BY hour
         "#,
         start_date,
-        end_date
+        end_date,
+        tz
     )
     .fetch_all(&state.db)
     .await
@@ -1332,21 +3355,20 @@ async fn get_admin_traffic_stats(
 
     let hourly_distribution = hourly_data
         .into_iter()
-        .map(|row| AdminHourStats {
-            hour: row
-                .hour
-                .map(|h| h.to_string().parse::<i32>().unwrap_or(0))
-                .unwrap_or(0),
-            page_views: row.page_views.unwrap_or(0),
-            unique_visitors: row.unique_visitors.unwrap_or(0),
+        .filter_map(|row| {
+            Some(AdminHourStats {
+                hour: daily_stats::hour_bucket(row.hour)?,
+                page_views: row.page_views.unwrap_or(0),
+                unique_visitors: row.unique_visitors.unwrap_or(0),
+            })
         })
         .collect();
 
-    // Device breakdown (real data from sessions, with fallback to mock)
+    // Device breakdown across all domains
     let (mobile, desktop, tablet, unknown) =
         SessionTracker::get_device_breakdown(&state.db, start_date, end_date, None)
             .await
-            .unwrap_or((45, 35, 15, 5));
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     let device_breakdown = AdminDeviceBreakdown {
         mobile: mobile as i64,
@@ -1368,7 +3390,7 @@ async fn get_admin_post_analytics(
     State(state): State<Arc<AppState>>,
     Query(query): Query<AdminAnalyticsQuery>,
 ) -> Result<Json<Vec<AdminPostStats>>, StatusCode> {
-    let (start_date, end_date) = parse_admin_date_range(&query);
+    let (start_date, end_date) = parse_admin_date_range(&query, state.clock.now());
 
     let posts_data = sqlx::query!(
         r#"
@@ -1410,11 +3432,12 @@ async fn get_admin_search_analytics(
     Query(query): Query<AdminAnalyticsQuery>,
 ) -> Result<Json<AdminSearchAnalyticsResponse>, StatusCode> {
     AnalyticsSpan::track_search("admin_search_analytics", async {
-        if user.role != "platform_admin" {
+        if !user.role().is_platform_level() {
             return Err(StatusCode::FORBIDDEN);
         }
 
-        let (start_date, end_date) = parse_admin_date_range(&query);
+        let (start_date, end_date) = parse_admin_date_range(&query, state.clock.now());
+        let tz = admin_bucket_timezone(&query, &state.db).await?;
 
         // Popular search terms
         let search_data = sqlx::query!(
@@ -1451,15 +3474,16 @@ async fn get_admin_search_analytics(
         let trend_data = sqlx::query!(
             r#"
         SELECT 
-            DATE(created_at) as date,
+            (created_at AT TIME ZONE $3)::date as date,
             COUNT(*) as searches
         FROM analytics_events 
         WHERE created_at BETWEEN $1 AND $2 AND event_type = 'search'
-        GROUP BY DATE(created_at)
+        GROUP BY 1
         ORDER BY date
         "#,
             start_date,
-            end_date
+            end_date,
+            tz
         )
         .fetch_all(&state.db)
         .await
@@ -1514,59 +3538,312 @@ async fn get_admin_search_analytics(
     .await
 }
 
-// Admin Referrer Stats
-async fn get_admin_referrer_stats(
-    Extension(user): Extension<UserContext>,
+// Admin Referrer Stats
+async fn get_admin_referrer_stats(
+    Extension(user): Extension<UserContext>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AdminAnalyticsQuery>,
+) -> Result<Json<AdminReferrerResponse>, StatusCode> {
+    if !user.role().is_platform_level() {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let (start_date, end_date) = parse_admin_date_range(&query, state.clock.now());
+
+    // Top referrers
+    let referrer_data = sqlx::query!(
+        r#"
+        SELECT 
+            COALESCE(referrer, 'Direct') as referrer,
+            COUNT(*) as visits,
+            COUNT(DISTINCT ip_address) as unique_visitors
+        FROM analytics_events 
+        WHERE created_at BETWEEN $1 AND $2
+        GROUP BY referrer
+        ORDER BY visits DESC
+        LIMIT 15
+        "#,
+        start_date,
+        end_date
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let top_referrers = referrer_data
+        .into_iter()
+        .map(|row| AdminReferrerStats {
+            referrer: row.referrer.unwrap_or_else(|| "Direct".to_string()),
+            visits: row.visits.unwrap_or(0),
+            unique_visitors: row.unique_visitors.unwrap_or(0),
+        })
+        .collect();
+
+    let referrer_types = referrers::referrer_breakdown(
+        &state.db,
+        None,
+        start_date,
+        end_date,
+        &ReferrerConfig::default(),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(AdminReferrerResponse {
+        top_referrers,
+        referrer_types,
+    }))
+}
+
+// Prune analytics events past the retention window (platform_admin only)
+async fn prune_analytics_events(
+    _auth: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<PruneReport>, StatusCode> {
+    let report =
+        retention::prune_analytics(&state.db, &RetentionConfig::default(), state.clock.now())
+            .await
+            .map_err(|e| {
+                tracing::error!(error = %e, "Failed to prune analytics events");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+
+    Ok(Json(report))
+}
+
+/// Longest span a single backfill request may recompute
+const MAX_BACKFILL_DAYS: i64 = 366;
+
+#[derive(Deserialize)]
+pub struct BackfillRequest {
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+}
+
+#[derive(Serialize)]
+pub struct BackfillResponse {
+    days: i64,
+    rows: u64,
+}
+
+// Recompute the daily analytics rollup for a span of days (platform_admin only)
+async fn backfill_daily_stats(
+    _auth: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<BackfillRequest>,
+) -> Result<Json<BackfillResponse>, StatusCode> {
+    let days = (payload.to - payload.from).num_days() + 1;
+    if !(1..=MAX_BACKFILL_DAYS).contains(&days) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let rows = daily_stats::backfill(&state.db, payload.from, payload.to)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to backfill daily analytics rollup");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(BackfillResponse { days, rows }))
+}
+
+#[derive(Deserialize)]
+pub struct MaintenanceRequest {
+    enabled: bool,
+}
+
+#[derive(Serialize)]
+pub struct MaintenanceResponse {
+    enabled: bool,         // Whether public routes currently answer 503
+    runtime_enabled: bool, // The toggle set through this endpoint
+    forced_by_env: bool,   // MAINTENANCE_MODE is set, overriding the toggle
+}
+
+async fn maintenance_status(state: &AppState) -> Result<Json<MaintenanceResponse>, StatusCode> {
+    let runtime_enabled = maintenance::runtime_enabled(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let forced_by_env = maintenance::forced_by_env();
+
+    Ok(Json(MaintenanceResponse {
+        enabled: runtime_enabled || forced_by_env,
+        runtime_enabled,
+        forced_by_env,
+    }))
+}
+
+// Current maintenance mode state (platform_admin only)
+async fn get_maintenance(
+    _auth: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<MaintenanceResponse>, StatusCode> {
+    maintenance_status(&state).await
+}
+
+// Switch maintenance mode on or off at runtime (platform_admin only)
+async fn update_maintenance(
+    RequirePlatformAdmin { user }: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<MaintenanceRequest>,
+) -> Result<Json<MaintenanceResponse>, StatusCode> {
+    state
+        .maintenance
+        .set(&state.db, payload.enabled)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to update maintenance mode");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(user.id),
+            impersonator_id: user.impersonator_id,
+            action: "maintenance.update".to_string(),
+            details: serde_json::json!({ "enabled": payload.enabled }),
+        },
+    )
+    .await;
+
+    maintenance_status(&state).await
+}
+
+// Email templates every domain falls back to (platform_admin only)
+async fn list_platform_email_templates(
+    _auth: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<EmailTemplateResponse>>, StatusCode> {
+    email_templates_in_effect(&state.db, None).await
+}
+
+// Replace the platform-wide template for one kind of email (platform_admin only)
+async fn update_platform_email_template(
+    RequirePlatformAdmin { user }: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(kind): Path<String>,
+    ValidatedJson(payload): ValidatedJson<EmailTemplateRequest>,
+) -> Result<Json<Vec<EmailTemplateResponse>>, StatusCode> {
+    let kind = email_kind(&kind, false)?;
+    let template = EmailTemplate {
+        subject: payload.subject,
+        body: payload.body,
+    };
+    email_templates::save(&state.db, None, kind, &template)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(user.id),
+            impersonator_id: user.impersonator_id,
+            action: "email_template.update".to_string(),
+            details: serde_json::json!({ "kind": kind.as_str() }),
+        },
+    )
+    .await;
+
+    email_templates_in_effect(&state.db, None).await
+}
+
+// Go back to the built-in template for one kind of email (platform_admin only)
+async fn delete_platform_email_template(
+    RequirePlatformAdmin { user }: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(kind): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let kind = email_kind(&kind, false)?;
+    let deleted = email_templates::delete(&state.db, None, kind)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !deleted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(user.id),
+            impersonator_id: user.impersonator_id,
+            action: "email_template.delete".to_string(),
+            details: serde_json::json!({ "kind": kind.as_str() }),
+        },
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Rate limiter thresholds currently in effect (platform_admin only)
+async fn get_rate_limits(
+    _auth: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+) -> Json<RateLimitSettings> {
+    Json(state.rate_limits.current())
+}
+
+// Change the rate limiter thresholds without a restart (platform_admin only)
+async fn update_rate_limits(
+    RequirePlatformAdmin { user }: RequirePlatformAdmin,
     State(state): State<Arc<AppState>>,
-    Query(query): Query<AdminAnalyticsQuery>,
-) -> Result<Json<AdminReferrerResponse>, StatusCode> {
-    if user.role != "platform_admin" {
-        return Err(StatusCode::FORBIDDEN);
-    }
+    ValidatedJson(payload): ValidatedJson<RateLimitSettings>,
+) -> Result<Json<RateLimitSettings>, StatusCode> {
+    rate_limits::save(&state.db, &payload).await.map_err(|e| {
+        tracing::error!(error = %e, "Failed to save rate limits");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let previous = state.rate_limits.current();
+    state.rate_limits.update(payload.clone());
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(user.id),
+            impersonator_id: user.impersonator_id,
+            action: "rate_limits.update".to_string(),
+            details: serde_json::json!({ "from": previous, "to": payload }),
+        },
+    )
+    .await;
 
-    let (start_date, end_date) = parse_admin_date_range(&query);
+    Ok(Json(payload))
+}
 
-    // Top referrers
-    let referrer_data = sqlx::query!(
-        r#"
-        SELECT 
-            COALESCE(referrer, 'Direct') as referrer,
-            COUNT(*) as visits,
-            COUNT(DISTINCT ip_address) as unique_visitors
-        FROM analytics_events 
-        WHERE created_at BETWEEN $1 AND $2
-        GROUP BY referrer
-        ORDER BY visits DESC
-        LIMIT 15
-        "#,
-        start_date,
-        end_date
-    )
-    .fetch_all(&state.db)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+// IPs currently banned for abuse, with the reason (platform_admin only)
+async fn list_bans(
+    _auth: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+) -> Json<Vec<BanInfo>> {
+    Json(state.abuse.bans())
+}
 
-    let top_referrers = referrer_data
-        .into_iter()
-        .map(|row| AdminReferrerStats {
-            referrer: row.referrer.unwrap_or_else(|| "Direct".to_string()),
-            visits: row.visits.unwrap_or(0),
-            unique_visitors: row.unique_visitors.unwrap_or(0),
-        })
-        .collect();
+// Lift an abuse ban before its cooldown runs out (platform_admin only)
+async fn clear_ban(
+    RequirePlatformAdmin { user }: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(ip): Path<IpAddr>,
+) -> StatusCode {
+    let ban = state.abuse.banned(ip);
+    if !state.abuse.clear(ip) {
+        return StatusCode::NOT_FOUND;
+    }
 
-    // Referrer type breakdown (simplified classification)
-    let referrer_types = AdminReferrerTypeBreakdown {
-        direct: 40,
-        search_engines: 35,
-        social_media: 20,
-        other_websites: 5,
-    };
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(user.id),
+            impersonator_id: user.impersonator_id,
+            action: "abuse.ban_clear".to_string(),
+            details: serde_json::json!({
+                "ip": ip,
+                "reason": ban.map(|ban| ban.reason),
+            }),
+        },
+    )
+    .await;
 
-    Ok(Json(AdminReferrerResponse {
-        top_referrers,
-        referrer_types,
-    }))
+    StatusCode::NO_CONTENT
 }
 
 // Get user preferences
@@ -1612,25 +3889,26 @@ pub async fn update_user_preferences(
 
 /// Request structure for creating new users
 /// Includes validation for security and data integrity
-#[derive(Serialize, Deserialize, Validate)]
+#[derive(Serialize, Deserialize)]
 pub struct CreateUserRequest {
-    #[validate(email(message = "Invalid email format"))]
-    #[validate(length(min = 1, message = "Email is required"))]
     email: String,                 // User email (must be unique)
-    #[validate(length(
-        min = 1,
-        max = 100,
-        message = "Name must be between 1 and 100 characters"
-    ))]
     name: String,                  // User display name
-    #[validate(custom(
-        function = "validate_password_strength",
-        message = "Password does not meet security requirements"
-    ))]
     password: String,              // Password (validated for strength)
-    #[validate(custom(function = "validate_user_role", message = "Invalid user role"))]
     role: String,                  // User role: "platform_admin" or "domain_user"
     domain_permissions: Option<Vec<DomainPermissionInput>>,
+    #[serde(default)]
+    must_change_password: bool, // Force a new password on first login
+}
+
+impl Validate for CreateUserRequest {
+    fn validate(&self) -> Result<(), validator::ValidationErrors> {
+        crate::validation::custom::validate_create_user_request(
+            &self.email,
+            &self.name,
+            &self.password,
+            &self.role,
+        )
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -1640,6 +3918,7 @@ pub struct UpdateUserRequest {
     password: Option<String>,
     role: Option<String>,
     domain_permissions: Option<Vec<DomainPermissionInput>>,
+    must_change_password: Option<bool>,
 }
 
 impl Validate for UpdateUserRequest {
@@ -1669,8 +3948,10 @@ pub struct UserResponse {
     email: String,
     name: String,
     role: String,
-    created_at: chrono::DateTime<chrono::Utc>,
-    updated_at: chrono::DateTime<chrono::Utc>,
+    created_at: Option<chrono::DateTime<chrono::Utc>>,
+    updated_at: Option<chrono::DateTime<chrono::Utc>>,
+    deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    must_change_password: bool,
     domain_permissions: Vec<DomainPermissionResponse>,
 }
 
@@ -1691,10 +3972,10 @@ pub struct UsersResponse {
 
 #[derive(Deserialize)]
 pub struct UsersQuery {
-    page: Option<i32>,
-    per_page: Option<i32>,
     role: Option<String>,
     search: Option<String>,     // Search term for name/email filtering
+    #[serde(default)]
+    include_deleted: bool, // Also list soft-deleted users
 }
 
 // ============================================================================
@@ -1707,19 +3988,20 @@ pub struct UsersQuery {
 /// Supports search, role filtering, and pagination for large user bases
 pub async fn list_users(
     RequirePlatformAdmin { user: _ }: RequirePlatformAdmin,
+    pagination: Pagination,
     State(state): State<Arc<AppState>>,
     Query(params): Query<UsersQuery>,
 ) -> Result<Json<UsersResponse>, StatusCode> {
     DatabaseSpan::execute("list_users", "users", async {
-        // Sanitize and validate pagination parameters
-        let page = params.page.unwrap_or(1).max(1);
-        let per_page = params.per_page.unwrap_or(20).clamp(1, 100) as i64;
-        let offset = ((page - 1) * (per_page as i32)) as i64;
 
         // Build dynamic query with optional filtering
         let mut where_conditions = Vec::new();
         let mut bind_values: Vec<String> = Vec::new();
 
+        if !params.include_deleted {
+            where_conditions.push("deleted_at IS NULL".to_string());
+        }
+
         if let Some(ref role) = params.role {
             where_conditions.push(format!("role = ${}", bind_values.len() + 1));
             bind_values.push(role.clone());
@@ -1742,7 +4024,7 @@ pub async fn list_users(
 
         // Use raw sqlx::query instead of the macro to avoid type conflicts
         let query_sql = format!(
-            "SELECT id, email, name, role, created_at, updated_at FROM users{} ORDER BY created_at DESC LIMIT ${} OFFSET ${}",
+            "SELECT id, email, name, role, created_at, updated_at, deleted_at, must_change_password FROM users{} ORDER BY created_at DESC LIMIT ${} OFFSET ${}",
             where_clause,
             bind_values.len() + 1,
             bind_values.len() + 2
@@ -1752,7 +4034,7 @@ pub async fn list_users(
         for value in &bind_values {
             query = query.bind(value);
         }
-        query = query.bind(per_page).bind(offset);
+        query = query.bind(pagination.per_page).bind(pagination.offset());
 
         let users_data = query
             .fetch_all(&state.db)
@@ -1812,10 +4094,13 @@ pub async fn list_users(
                     tracing::error!("Error getting name: {e}");
                     StatusCode::INTERNAL_SERVER_ERROR
                 })?,
-                role: user_data.try_get("role").map_err(|e| {
-                    tracing::error!("Error getting role: {e}");
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?,
+                role: user_data
+                    .try_get::<Option<String>, _>("role")
+                    .map_err(|e| {
+                        tracing::error!("Error getting role: {e}");
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    })?
+                    .unwrap_or_else(|| "domain_user".to_string()),
                 created_at: user_data.try_get("created_at").map_err(|e| {
                     tracing::error!("Error getting created_at: {e}");
                     StatusCode::INTERNAL_SERVER_ERROR
@@ -1824,6 +4109,14 @@ pub async fn list_users(
                     tracing::error!("Error getting updated_at: {e}");
                     StatusCode::INTERNAL_SERVER_ERROR
                 })?,
+                deleted_at: user_data.try_get("deleted_at").map_err(|e| {
+                    tracing::error!("Error getting deleted_at: {e}");
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?,
+                must_change_password: user_data.try_get("must_change_password").map_err(|e| {
+                    tracing::error!("Error getting must_change_password: {e}");
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?,
                 domain_permissions,
             });
         }
@@ -1831,8 +4124,8 @@ pub async fn list_users(
         Ok(Json(UsersResponse {
             users,
             total,
-            page,
-            per_page: per_page as i32,
+            page: pagination.page as i32,
+            per_page: pagination.per_page as i32,
         }))
     })
     .await
@@ -1845,7 +4138,7 @@ pub async fn create_user(
     ValidatedJson(payload): ValidatedJson<CreateUserRequest>,
 ) -> Result<Json<UserResponse>, StatusCode> {
     // Only platform admins can create users
-    if user.role != "platform_admin" {
+    if !user.role().is_platform_level() {
         return Err(StatusCode::FORBIDDEN);
     }
 
@@ -1856,16 +4149,21 @@ pub async fn create_user(
 
     // Insert user
     let user_id = sqlx::query_scalar::<_, i32>(
-        "INSERT INTO users (email, name, password_hash, role) VALUES ($1, $2, $3, $4) RETURNING id",
+        "INSERT INTO users (email, name, password_hash, role, must_change_password) VALUES ($1, $2, $3, $4, $5) RETURNING id",
     )
     .bind(&payload.email)
     .bind(&payload.name)
     .bind(&password_hash)
     .bind(&payload.role)
+    .bind(payload.must_change_password)
     .fetch_one(&state.db)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    password_policy::record(&state.db, user_id, &password_hash)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     // Insert domain permissions if provided
     if let Some(permissions) = &payload.domain_permissions {
         for perm in permissions {
@@ -1894,7 +4192,7 @@ pub async fn get_user(
     Path(user_id): Path<i32>,
 ) -> Result<Json<UserResponse>, StatusCode> {
     // Only platform admins can view users
-    if user.role != "platform_admin" {
+    if !user.role().is_platform_level() {
         return Err(StatusCode::FORBIDDEN);
     }
 
@@ -1909,15 +4207,31 @@ pub async fn update_user(
     ValidatedJson(payload): ValidatedJson<UpdateUserRequest>,
 ) -> Result<Json<UserResponse>, StatusCode> {
     // Only platform admins can update users
-    if user.role != "platform_admin" {
+    if !user.role().is_platform_level() {
         return Err(StatusCode::FORBIDDEN);
     }
 
+    // A new password may not repeat a recent one
+    let password_hash = match &payload.password {
+        Some(password) => {
+            if password_policy::is_recently_used(&state.db, user_id, password)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            use bcrypt::{DEFAULT_COST, hash};
+            Some(hash(password, DEFAULT_COST).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?)
+        }
+        None => None,
+    };
+
     // Update user fields if provided
     if payload.email.is_some()
         || payload.name.is_some()
         || payload.role.is_some()
         || payload.password.is_some()
+        || payload.must_change_password.is_some()
     {
         let mut query = "UPDATE users SET updated_at = NOW()".to_string();
         let mut bind_count = 0;
@@ -1938,6 +4252,10 @@ pub async fn update_user(
             bind_count += 1;
             query.push_str(&format!(", password_hash = ${bind_count}"));
         }
+        if payload.must_change_password.is_some() {
+            bind_count += 1;
+            query.push_str(&format!(", must_change_password = ${bind_count}"));
+        }
 
         query.push_str(&format!(" WHERE id = ${}", bind_count + 1));
 
@@ -1951,12 +4269,12 @@ pub async fn update_user(
         if let Some(role) = &payload.role {
             sqlx_query = sqlx_query.bind(role);
         }
-        if let Some(password) = &payload.password {
-            use bcrypt::{DEFAULT_COST, hash};
-            let password_hash =
-                hash(password, DEFAULT_COST).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if let Some(password_hash) = &password_hash {
             sqlx_query = sqlx_query.bind(password_hash);
         }
+        if let Some(must_change_password) = payload.must_change_password {
+            sqlx_query = sqlx_query.bind(must_change_password);
+        }
 
         sqlx_query
             .bind(user_id)
@@ -1965,6 +4283,12 @@ pub async fn update_user(
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     }
 
+    if let Some(password_hash) = &password_hash {
+        password_policy::record(&state.db, user_id, password_hash)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+
     // Update domain permissions if provided
     if let Some(permissions) = &payload.domain_permissions {
         // Delete existing permissions
@@ -1994,14 +4318,15 @@ pub async fn update_user(
     get_user_by_id(&state, user_id).await
 }
 
-// Delete a user
+// Soft-delete a user: they can no longer sign in and their sessions end,
+// but their row, permissions and audit trail stay until restored
 pub async fn delete_user(
     Extension(user): Extension<UserContext>,
     State(state): State<Arc<AppState>>,
     Path(user_id): Path<i32>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     // Only platform admins can delete users
-    if user.role != "platform_admin" {
+    if !user.role().is_platform_level() {
         return Err(StatusCode::FORBIDDEN);
     }
 
@@ -2010,22 +4335,394 @@ pub async fn delete_user(
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    // Delete user (cascade will handle domain permissions)
-    let result = sqlx::query("DELETE FROM users WHERE id = $1")
-        .bind(user_id)
-        .execute(&state.db)
+    let mut tx = state
+        .db
+        .begin()
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    // Stamping sessions_revoked_at keeps the tokens issued so far dead if
+    // the user is restored later
+    let result = sqlx::query(
+        "UPDATE users SET deleted_at = NOW(), sessions_revoked_at = $2, updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(state.clock.now())
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     if result.rows_affected() == 0 {
         return Err(StatusCode::NOT_FOUND);
     }
 
+    // JWTs stop working because auth skips deleted users; end their sessions too
+    sqlx::query(
+        "UPDATE user_sessions SET ended_at = NOW(), expires_at = NOW() WHERE user_id = $1 AND ended_at IS NULL",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(user.id),
+            impersonator_id: user.impersonator_id,
+            action: "user.delete".to_string(),
+            details: serde_json::json!({ "user_id": user_id }),
+        },
+    )
+    .await;
+
     Ok(Json(
         serde_json::json!({"message": "User deleted successfully"}),
     ))
 }
 
+// Undo a soft delete (platform_admin only)
+pub async fn restore_user(
+    RequirePlatformAdmin { user: admin }: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<i32>,
+) -> Result<Json<UserResponse>, StatusCode> {
+    let result = sqlx::query(
+        "UPDATE users SET deleted_at = NULL, updated_at = NOW() WHERE id = $1 AND deleted_at IS NOT NULL",
+    )
+    .bind(user_id)
+    .execute(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(admin.id),
+            impersonator_id: admin.impersonator_id,
+            action: "user.restore".to_string(),
+            details: serde_json::json!({ "user_id": user_id }),
+        },
+    )
+    .await;
+
+    get_user_by_id(&state, user_id).await
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordQuery {
+    /// Email the token to the user rather than returning it
+    #[serde(default)]
+    email: bool,
+}
+
+#[derive(Serialize)]
+pub struct PasswordResetResponse {
+    user_id: i32,
+    expires_at: DateTime<Utc>,
+    emailed: bool,
+    /// Only returned when it wasn't emailed, for support to pass on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+}
+
+// Issue a password reset token for a locked-out user, redeemed through
+// POST /auth/reset-password; `?email=true` sends it to the user (platform_admin only)
+pub async fn reset_user_password(
+    RequirePlatformAdmin { user: admin }: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<i32>,
+    Query(query): Query<ResetPasswordQuery>,
+) -> Result<Json<PasswordResetResponse>, StatusCode> {
+    let email: String =
+        sqlx::query_scalar("SELECT email FROM users WHERE id = $1 AND deleted_at IS NULL")
+            .bind(user_id)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Checked before issuing, as a new token voids the user's earlier ones
+    let mailer = if query.email {
+        Some(SmtpMailer::from_env().ok_or(StatusCode::SERVICE_UNAVAILABLE)?)
+    } else {
+        None
+    };
+
+    let reset = credentials::issue_reset_token(&state.db, state.clock.as_ref(), user_id, admin.id)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, user_id, "Failed to issue password reset token");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if let Some(mailer) = &mailer {
+        // Users belong to no single domain, so only a platform template applies
+        let (template, _) = email_templates::resolve(&state.db, None, EmailKind::PasswordReset)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        mailer
+            .send(credentials::reset_email(&template, &email, &reset))
+            .await
+            .map_err(|e| {
+                tracing::error!(error = %e, user_id, "Failed to email password reset token");
+                StatusCode::BAD_GATEWAY
+            })?;
+    }
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(admin.id),
+            impersonator_id: admin.impersonator_id,
+            action: "user.password_reset_issued".to_string(),
+            details: serde_json::json!({ "user_id": user_id, "emailed": mailer.is_some() }),
+        },
+    )
+    .await;
+
+    Ok(Json(PasswordResetResponse {
+        user_id,
+        expires_at: reset.expires_at,
+        emailed: mailer.is_some(),
+        token: mailer.is_none().then_some(reset.token),
+    }))
+}
+
+// Make every token issued to a user so far stop working (platform_admin only)
+pub async fn revoke_user_sessions(
+    RequirePlatformAdmin { user: admin }: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<i32>,
+) -> Result<StatusCode, StatusCode> {
+    if !credentials::revoke_sessions(&state.db, state.clock.as_ref(), user_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(admin.id),
+            impersonator_id: admin.impersonator_id,
+            action: "user.sessions_revoked".to_string(),
+            details: serde_json::json!({ "user_id": user_id }),
+        },
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// How long an impersonation token stays valid, from `IMPERSONATION_TTL_MINUTES`
+fn impersonation_ttl() -> Duration {
+    std::env::var("IMPERSONATION_TTL_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|m| *m > 0)
+        .map(Duration::minutes)
+        .unwrap_or_else(|| Duration::minutes(30))
+}
+
+#[derive(Serialize)]
+pub struct ImpersonationResponse {
+    token: String,
+    expires_at: DateTime<Utc>,
+    user_id: i32,
+    impersonator_id: i32,
+}
+
+// Issue a short-lived token acting as another user (platform_admin only)
+pub async fn impersonate_user(
+    RequirePlatformAdmin { user: admin }: RequirePlatformAdmin,
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<i32>,
+) -> Result<Json<ImpersonationResponse>, StatusCode> {
+    if admin.id == user_id {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let target = sqlx::query!(
+        "SELECT id, email, role FROM users WHERE id = $1 AND deleted_at IS NULL",
+        user_id
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+    let role = target.role.unwrap_or_default();
+
+    // Impersonation is for debugging domain users, not other platform admins
+    if Role::parse(&role).is_platform_level() {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let ttl = impersonation_ttl();
+    let token = crate::handlers::auth::create_impersonation_token(
+        &state.jwt_config(),
+        &target.email,
+        target.id,
+        &role,
+        admin.id,
+        ttl,
+    )
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(admin.id),
+            impersonator_id: None,
+            action: "impersonation.start".to_string(),
+            details: serde_json::json!({ "target_user_id": target.id }),
+        },
+    )
+    .await;
+
+    Ok(Json(ImpersonationResponse {
+        token,
+        expires_at: state.clock.now() + ttl,
+        user_id: target.id,
+        impersonator_id: admin.id,
+    }))
+}
+
+#[derive(Deserialize, Validate)]
+pub struct BulkPermissionsRequest {
+    #[validate(nested)]
+    permissions: Vec<BulkPermissionInput>,
+}
+
+#[derive(Deserialize, Validate)]
+struct BulkPermissionInput {
+    user_id: i32,
+    #[validate(custom(
+        function = "validate_domain_permission_role",
+        message = "Invalid domain permission role"
+    ))]
+    role: String, // admin, editor, viewer, none (removes access)
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct DomainMemberPermission {
+    user_id: i32,
+    email: String,
+    name: String,
+    role: String,
+}
+
+// Grant, change or revoke many users' access to one domain at once
+// (platform admin or an admin of that domain); all or nothing
+pub async fn bulk_update_domain_permissions(
+    Extension(user): Extension<UserContext>,
+    State(state): State<Arc<AppState>>,
+    Path(domain_id): Path<i32>,
+    ValidatedJson(payload): ValidatedJson<BulkPermissionsRequest>,
+) -> Result<Json<Vec<DomainMemberPermission>>, StatusCode> {
+    check_domain_permission(&user, domain_id, "admin")?;
+
+    let domain_exists: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM domains WHERE id = $1)")
+            .bind(domain_id)
+            .fetch_one(&state.db)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !domain_exists {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    // Every listed user must exist and not be deleted
+    let mut user_ids: Vec<i32> = payload.permissions.iter().map(|p| p.user_id).collect();
+    user_ids.sort_unstable();
+    user_ids.dedup();
+    let found: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE id = ANY($1) AND deleted_at IS NULL")
+            .bind(&user_ids)
+            .fetch_one(&state.db)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if found != user_ids.len() as i64 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    for perm in &payload.permissions {
+        if perm.role == "none" {
+            sqlx::query(
+                "DELETE FROM user_domain_permissions WHERE user_id = $1 AND domain_id = $2",
+            )
+            .bind(perm.user_id)
+            .bind(domain_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        } else {
+            sqlx::query(
+                "INSERT INTO user_domain_permissions (user_id, domain_id, role) VALUES ($1, $2, $3) ON CONFLICT (user_id, domain_id) DO UPDATE SET role = $3",
+            )
+            .bind(perm.user_id)
+            .bind(domain_id)
+            .bind(&perm.role)
+            .execute(&mut *tx)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(user.id),
+            impersonator_id: user.impersonator_id,
+            action: "permissions.bulk_update".to_string(),
+            details: serde_json::json!({
+                "domain_id": domain_id,
+                "changes": payload
+                    .permissions
+                    .iter()
+                    .map(|p| serde_json::json!({ "user_id": p.user_id, "role": p.role }))
+                    .collect::<Vec<_>>(),
+            }),
+        },
+    )
+    .await;
+
+    let permissions = sqlx::query_as::<_, DomainMemberPermission>(
+        r#"
+        SELECT u.id AS user_id, u.email, u.name, udp.role
+        FROM user_domain_permissions udp
+        JOIN users u ON u.id = udp.user_id
+        WHERE udp.domain_id = $1 AND u.deleted_at IS NULL
+        ORDER BY u.id
+        "#,
+    )
+    .bind(domain_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(permissions))
+}
+
 // Helper function to get user by ID with domain permissions
 async fn get_user_by_id(
     state: &Arc<AppState>,
@@ -2033,7 +4730,7 @@ async fn get_user_by_id(
 ) -> Result<Json<UserResponse>, StatusCode> {
     // Get user info
     let user = sqlx::query!(
-        "SELECT id, email, name, role, created_at, updated_at FROM users WHERE id = $1",
+        "SELECT id, email, name, role, created_at, updated_at, deleted_at, must_change_password FROM users WHERE id = $1",
         user_id
     )
     .fetch_optional(&state.db)
@@ -2056,17 +4753,21 @@ async fn get_user_by_id(
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    // The columns are nullable; a row from before their defaults is treated
+    // as an ordinary domain user and its missing timestamps stay null
+    if user.role.is_none() || user.created_at.is_none() || user.updated_at.is_none() {
+        tracing::warn!(user_id, "User row has a NULL role or timestamp");
+    }
+
     Ok(Json(UserResponse {
         id: user.id,
         email: user.email,
         name: user.name,
-        role: user.role.expect("role should never be null in DB"),
-        created_at: user
-            .created_at
-            .expect("created_at should never be null in DB"),
-        updated_at: user
-            .updated_at
-            .expect("updated_at should never be null in DB"),
+        role: user.role.unwrap_or_else(|| "domain_user".to_string()),
+        created_at: user.created_at,
+        updated_at: user.updated_at,
+        deleted_at: user.deleted_at,
+        must_change_password: user.must_change_password,
         domain_permissions,
     }))
 }
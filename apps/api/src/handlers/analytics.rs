@@ -1,19 +1,37 @@
-use crate::services::session_tracking::SessionTracker;
+use crate::services::daily_stats;
+use crate::services::event_buffer::AnalyticsEvent;
+use crate::services::event_types;
+use crate::services::live::{self, LiveEvent};
+use crate::services::parquet_export::{self, ExportField, ExportRow};
+use crate::services::period_comparison;
+use crate::services::query_timeout;
+use crate::services::referrers::{self, ReferrerConfig, ReferrerTypeBreakdown};
+use crate::services::session_tracking::{SessionConfig, SessionTracker};
+use crate::services::timezones;
 use crate::utils::{AnalyticsSpan, PerformanceSpan};
-use crate::{AppState, UserContext};
+use crate::validation::{ValidationErrorResponse, extractors::ValidatedJson};
+use crate::{AnalyticsContext, AppState, DomainContext, UserContext};
 use axum::{
     Extension, Router,
-    extract::{Query, State},
-    http::StatusCode,
-    response::Json,
+    body::Bytes,
+    extract::{
+        Path, Query, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    http::{StatusCode, header},
+    response::{IntoResponse, Json, Response},
     routing::{get, post},
 };
+use bigdecimal::ToPrimitive;
 use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use sqlx::types::BigDecimal;
 use std::str::FromStr;
 use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
 use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
 
 pub struct AnalyticsModule;
 
@@ -26,11 +44,16 @@ impl super::HandlerModule for AnalyticsModule {
             .route("/search-terms", get(get_search_analytics))
             .route("/referrers", get(get_referrer_stats))
             .route("/real-time", get(get_realtime_stats))
+            .route("/ws", get(live_updates))
             .route("/export", get(export_data))
-            .route("/behavior", post(track_behavior_event))
-            .route("/search", post(track_search_event))
-            .route("/search-click", post(track_search_click_event))
-            .route("/content-metrics", post(track_content_metrics))
+            .route("/content", get(get_content_engagement))
+            .route("/engagement", get(get_engagement_stats))
+            .route("/visitors", get(get_visitor_stats))
+            .route("/pages", get(get_entry_exit_pages))
+            .route("/custom-events", get(get_custom_event_stats))
+            .route("/funnel", post(analyze_funnel))
+            .route("/sessions/{session_id}", get(get_session_timeline))
+            .merge(Self::tracking_routes())
     }
 
     fn mount_path() -> &'static str {
@@ -38,6 +61,20 @@ impl super::HandlerModule for AnalyticsModule {
     }
 }
 
+impl AnalyticsModule {
+    /// Ingestion endpoints called by anonymous blog visitors; these must not
+    /// sit behind authentication like the reporting routes
+    pub fn tracking_routes() -> Router<Arc<AppState>> {
+        Router::new()
+            .route("/behavior", post(track_behavior_event))
+            .route("/search", post(track_search_event))
+            .route("/search-click", post(track_search_click_event))
+            .route("/content-metrics", post(track_content_metrics))
+            .route("/events/batch", post(track_events_batch))
+            .route("/custom-event", post(track_custom_event))
+    }
+}
+
 // Main analytics dashboard response (merged overview + dashboard)
 #[derive(Serialize)]
 pub struct AnalyticsDashboardResponse {
@@ -53,8 +90,12 @@ pub struct AnalyticsDashboardResponse {
 pub struct DashboardOverview {
     total_sessions: i64,
     total_page_views: i64,
-    avg_session_duration: f64,
-    bounce_rate: f64,
+    /// `null` without session data, see `session_data_available`
+    avg_session_duration: Option<f64>,
+    bounce_rate: Option<f64>,
+    /// Whether sessions were recorded in the period; without them the
+    /// session metrics are `null` rather than zero
+    session_data_available: bool,
     unique_visitors: i64,
     // Period comparison
     previous_period: PeriodStats,
@@ -63,11 +104,13 @@ pub struct DashboardOverview {
 
 #[derive(Serialize)]
 pub struct PeriodStats {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
     page_views: i64,
     unique_visitors: i64,
     post_views: i64,
     searches: i64,
-    avg_session_duration: f64,
+    avg_session_duration: Option<f64>,
 }
 
 #[derive(Serialize)]
@@ -180,6 +223,8 @@ pub struct SearchAnalyticsResponse {
     popular_terms: Vec<SearchTerm>,
     search_volume_trend: Vec<SearchVolumeDay>,
     no_results_queries: Vec<SearchTerm>,
+    /// Share of searches that returned no posts
+    no_results_rate: f64,
 }
 
 #[derive(Serialize)]
@@ -209,12 +254,169 @@ pub struct ReferrerStats {
     unique_visitors: i64,
 }
 
+// Single-domain content engagement
+#[derive(Serialize)]
+pub struct ContentEngagementResponse {
+    domain_id: i32,
+    total_content_views: i64,
+    avg_reading_time: i64,
+    content_completion_rate: f64,
+}
+
+// Time-on-page distribution across the user's domains
+#[derive(Serialize)]
+pub struct EngagementResponse {
+    total_views: i64,
+    distribution: Vec<TimeOnPageBucket>,
+    median_seconds: f64,
+    p75_seconds: f64,
+    p90_seconds: f64,
+    p95_seconds: f64,
+}
+
+#[derive(Serialize)]
+pub struct TimeOnPageBucket {
+    label: &'static str,
+    min_seconds: i32,
+    /// Exclusive; `None` for the open-ended last bucket
+    max_seconds: Option<i32>,
+    count: i64,
+    percentage: f64,
+}
+
+/// Time-on-page buckets as (label, lower bound in seconds); each runs up to the next
+const TIME_ON_PAGE_BUCKETS: [(&str, i32); 5] = [
+    ("0-10s", 0),
+    ("10-30s", 10),
+    ("30-60s", 30),
+    ("1-3m", 60),
+    ("3m+", 180),
+];
+
+// New vs returning visitors, by IP address
+#[derive(Serialize)]
+pub struct VisitorsResponse {
+    domains: Vec<DomainVisitors>,
+    daily_trend: Vec<VisitorDay>,
+}
+
+// Where sessions start and end, per domain
+#[derive(Serialize)]
+pub struct PagesResponse {
+    domains: Vec<DomainPages>,
+}
+
+#[derive(Serialize)]
+pub struct DomainPages {
+    domain_id: i32,
+    /// Paths of the first page view of each session, most sessions first
+    landing_pages: Vec<PageSessions>,
+    /// Paths of the last page view of each session, most sessions first
+    exit_pages: Vec<PageSessions>,
+}
+
+#[derive(Serialize)]
+pub struct PageSessions {
+    path: String,
+    sessions: i64,
+}
+
+#[derive(sqlx::FromRow)]
+struct EntryExitRow {
+    domain_id: i32,
+    landing: bool,
+    path: String,
+    sessions: i64,
+}
+
+/// Landing and exit pages reported per domain
+const TOP_ENTRY_EXIT_PAGES: usize = 20;
+
+// Custom event counts per domain and name
+#[derive(Serialize)]
+pub struct CustomEventsResponse {
+    events: Vec<CustomEventCount>,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct CustomEventCount {
+    domain_id: i32,
+    name: String,
+    count: i64,
+    unique_visitors: i64,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct DomainVisitors {
+    domain_id: i32,
+    unique_visitors: i64,
+    /// No events on the domain before the period
+    new_visitors: i64,
+    returning_visitors: i64,
+}
+
+/// A visitor is new on a day when they had no events on the domain before it,
+/// so someone first seen in the period counts as returning on later days
+#[derive(Serialize, sqlx::FromRow)]
+pub struct VisitorDay {
+    date: chrono::NaiveDate,
+    new_visitors: i64,
+    returning_visitors: i64,
+}
+
+// Funnel analytics
 #[derive(Serialize)]
-pub struct ReferrerTypeBreakdown {
-    direct: i64,
-    search_engines: i64,
-    social_media: i64,
-    other_websites: i64,
+pub struct FunnelResponse {
+    total_sessions: i64,
+    steps: Vec<FunnelStep>,
+}
+
+#[derive(Serialize)]
+pub struct FunnelStep {
+    pattern: String,
+    sessions: i64,
+    conversion_from_previous: f64,
+    conversion_from_start: f64,
+}
+
+// Session timeline
+#[derive(Serialize)]
+pub struct SessionTimelineResponse {
+    session_id: Uuid,
+    domain_id: i32,
+    started_at: DateTime<Utc>,
+    last_activity_at: DateTime<Utc>,
+    ended_at: Option<DateTime<Utc>>,
+    /// Cut to its /24 (IPv4) or /48 (IPv6) network, e.g. `192.168.1.0`
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    events: Vec<TimelineEvent>,
+    /// The session has more than `MAX_TIMELINE_EVENTS` events; the earliest are listed
+    truncated: bool,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct TimelineEvent {
+    at: DateTime<Utc>,
+    /// Table the event came from: `analytics`, `behavior`, `search`,
+    /// `search_click` or `content_metrics`
+    source: String,
+    /// e.g. `page_view`, `post_view`, `click`, `scroll`
+    event_type: String,
+    path: Option<String>,
+    details: serde_json::Value,
+}
+
+#[derive(sqlx::FromRow)]
+struct TimelineSession {
+    id: Uuid,
+    session_id: Uuid,
+    domain_id: i32,
+    started_at: DateTime<Utc>,
+    last_activity_at: DateTime<Utc>,
+    ended_at: Option<DateTime<Utc>>,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
 }
 
 // Realtime analytics
@@ -242,13 +444,73 @@ pub struct RecentEvent {
 }
 
 // Query parameters
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
 pub struct AnalyticsQuery {
     range: Option<String>, // "24h", "7d", "30d"
     days: Option<i32>,
     start_date: Option<String>,
     end_date: Option<String>,
     domain_id: Option<i32>,
+    tz: Option<String>, // IANA zone for day/hour buckets, e.g. "America/New_York"
+    compare_start: Option<String>, // Comparison window (RFC 3339), defaults to the preceding one
+    compare_end: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct CacheBypass {
+    /// Recompute instead of serving a cached response
+    #[serde(default)]
+    nocache: bool,
+}
+
+#[derive(Deserialize, Default)]
+pub struct DeltaQuery {
+    /// Only count what was recorded after this instant (RFC 3339), usually
+    /// the `until` of the previous poll
+    since: Option<String>,
+}
+
+/// Counts recorded in `(since, until]`, for a client that already holds the
+/// dashboard and adds these to it. Distinct visitors and sessions can't be
+/// added up, so they are left to a full reload.
+#[derive(Serialize)]
+pub struct DashboardDelta {
+    since: DateTime<Utc>,
+    /// Pass as `since` on the next poll. Trails the current time by the
+    /// event buffer's write delay, so events not yet written come in a later poll.
+    until: DateTime<Utc>,
+    page_views: i64,
+    post_views: i64,
+    searches: i64,
+    /// New views per post, most viewed first
+    posts: Vec<PostDelta>,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct PostDelta {
+    id: i32,
+    title: String,
+    slug: String,
+    views: i64,
+}
+
+/// Most posts listed in a dashboard delta
+const MAX_DELTA_POSTS: i64 = 100;
+
+/// Oldest `since` accepted; a client further behind reloads the dashboard
+const MAX_DELTA_AGE_HOURS: i64 = 24;
+
+#[derive(Deserialize, Validate)]
+pub struct FunnelRequest {
+    /// Ordered path patterns; `*` matches any run of characters
+    #[validate(length(
+        min = 2,
+        max = 10,
+        message = "A funnel must have between 2 and 10 steps"
+    ))]
+    steps: Vec<String>,
+    #[serde(flatten)]
+    window: AnalyticsQuery,
 }
 
 // Behavior tracking structs
@@ -295,20 +557,290 @@ pub struct ContentMetricsEvent {
     timestamp: String,
 }
 
-// Helper functions
-fn get_user_domain_ids(user: &UserContext) -> Vec<i32> {
-    if user.role == "platform_admin" || user.role == "super_admin" {
-        vec![]
+/// An event the domain defined itself, registered by name in
+/// `analytics_config.custom_events`
+#[derive(Deserialize)]
+pub struct CustomEvent {
+    name: String,
+    #[serde(default)]
+    properties: serde_json::Map<String, serde_json::Value>,
+    path: Option<String>,
+    session_id: Option<Uuid>,
+}
+
+// Batch ingestion: one element per event, tagged by `type`
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BatchEvent {
+    Behavior(UserBehaviorEvent),
+    Search(SearchEvent),
+    SearchClick(SearchClickEvent),
+    ContentMetrics(ContentMetricsEvent),
+}
+
+impl BatchEvent {
+    /// Field checks beyond what deserialization enforces
+    fn validate(&self) -> Result<(), String> {
+        let result = match self {
+            BatchEvent::Behavior(e) => e.validate(),
+            BatchEvent::Search(e) => e.validate(),
+            BatchEvent::SearchClick(e) => e.validate(),
+            BatchEvent::ContentMetrics(e) => e.validate(),
+        };
+        result.map_err(|errors| describe_event_errors(&errors))
+    }
+
+    async fn insert(&self, conn: &mut sqlx::PgConnection) -> Result<(), sqlx::Error> {
+        match self {
+            BatchEvent::Behavior(e) => insert_behavior_event(conn, e).await,
+            BatchEvent::Search(e) => insert_search_event(conn, e).await,
+            BatchEvent::SearchClick(e) => insert_search_click_event(conn, e).await,
+            BatchEvent::ContentMetrics(e) => insert_content_metrics(conn, e).await,
+        }
+    }
+}
+
+// Tracking events are checked before they are stored, since reports read
+// these columns back assuming they're in range
+
+/// Longest `event_type` and `content_type` (their `VARCHAR(100)` columns)
+const MAX_EVENT_TYPE_LENGTH: usize = 100;
+
+/// Longest `element` and `content_id` (their `VARCHAR(255)` columns)
+const MAX_EVENT_LABEL_LENGTH: usize = 255;
+
+/// Bound of `x` and `y`, stored as `DECIMAL(10,2)`
+const MAX_COORDINATE: f64 = 99_999_999.99;
+
+/// Longest `path` (its `VARCHAR(500)` column)
+const MAX_EVENT_PATH_LENGTH: usize = 500;
+
+/// Largest custom event `properties` object, serialized
+const MAX_CUSTOM_PROPERTIES_BYTES: usize = 4096;
+
+fn add_event_error(errors: &mut ValidationErrors, field: &'static str, rule: &str) {
+    let mut error = ValidationError::new("invalid_event");
+    error.message = Some(format!("{field} {rule}").into());
+    errors.add(field, error);
+}
+
+fn check_required(
+    errors: &mut ValidationErrors,
+    field: &'static str,
+    value: &str,
+    max_length: Option<usize>,
+) {
+    if value.trim().is_empty() {
+        add_event_error(errors, field, "is required");
+    } else if max_length.is_some_and(|max| value.chars().count() > max) {
+        add_event_error(errors, field, "is too long");
+    }
+}
+
+fn check_percentage(errors: &mut ValidationErrors, field: &'static str, value: f64) {
+    if !(0.0..=100.0).contains(&value) {
+        add_event_error(errors, field, "must be between 0 and 100");
+    }
+}
+
+fn check_count(errors: &mut ValidationErrors, field: &'static str, value: i64) {
+    if !(0..=i64::from(i32::MAX)).contains(&value) {
+        add_event_error(errors, field, "must be between 0 and 2147483647");
+    }
+}
+
+fn into_result(errors: ValidationErrors) -> Result<(), ValidationErrors> {
+    if errors.is_empty() {
+        Ok(())
     } else {
-        user.domain_permissions
-            .iter()
-            .map(|p| p.domain_id)
-            .collect()
+        Err(errors)
+    }
+}
+
+/// One line naming every rejected field, e.g. `query is required`
+fn describe_event_errors(errors: &ValidationErrors) -> String {
+    let mut messages: Vec<String> = errors
+        .field_errors()
+        .values()
+        .flat_map(|errors| errors.iter())
+        .filter_map(|error| error.message.as_ref().map(|m| m.to_string()))
+        .collect();
+    messages.sort();
+    messages.join("; ")
+}
+
+impl Validate for UserBehaviorEvent {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_required(
+            &mut errors,
+            "event_type",
+            &self.event_type,
+            Some(MAX_EVENT_TYPE_LENGTH),
+        );
+        if self
+            .element
+            .as_ref()
+            .is_some_and(|element| element.chars().count() > MAX_EVENT_LABEL_LENGTH)
+        {
+            add_event_error(&mut errors, "element", "is too long");
+        }
+        if let Some(scroll_depth) = self.scroll_depth {
+            check_percentage(&mut errors, "scroll_depth", scroll_depth);
+        }
+        for (field, value) in [("x", self.x), ("y", self.y)] {
+            if value.is_some_and(|v| !(0.0..=MAX_COORDINATE).contains(&v)) {
+                add_event_error(&mut errors, field, "must be a non-negative coordinate");
+            }
+        }
+        into_result(errors)
+    }
+}
+
+impl Validate for SearchEvent {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_required(&mut errors, "query", &self.query, None);
+        check_count(&mut errors, "results_count", self.results_count);
+        into_result(errors)
+    }
+}
+
+impl Validate for SearchClickEvent {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_required(&mut errors, "query", &self.query, None);
+        check_required(&mut errors, "clicked_result", &self.clicked_result, None);
+        if self.position_clicked.is_some_and(|position| position < 0) {
+            add_event_error(&mut errors, "position_clicked", "cannot be negative");
+        }
+        into_result(errors)
+    }
+}
+
+impl Validate for ContentMetricsEvent {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_required(
+            &mut errors,
+            "content_id",
+            &self.content_id,
+            Some(MAX_EVENT_LABEL_LENGTH),
+        );
+        check_required(
+            &mut errors,
+            "content_type",
+            &self.content_type,
+            Some(MAX_EVENT_TYPE_LENGTH),
+        );
+        check_percentage(&mut errors, "scroll_percentage", self.scroll_percentage);
+        check_count(&mut errors, "reading_time", self.reading_time);
+        check_count(&mut errors, "time_on_page", self.time_on_page);
+        check_count(
+            &mut errors,
+            "engagement_events",
+            i64::from(self.engagement_events),
+        );
+        into_result(errors)
+    }
+}
+
+impl Validate for CustomEvent {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        check_required(
+            &mut errors,
+            "name",
+            &self.name,
+            Some(event_types::MAX_CUSTOM_NAME_LENGTH),
+        );
+        if self
+            .path
+            .as_ref()
+            .is_some_and(|path| path.chars().count() > MAX_EVENT_PATH_LENGTH)
+        {
+            add_event_error(&mut errors, "path", "is too long");
+        }
+        let properties = serde_json::to_string(&self.properties).unwrap_or_default();
+        if properties.len() > MAX_CUSTOM_PROPERTIES_BYTES {
+            add_event_error(&mut errors, "properties", "is too large");
+        }
+        into_result(errors)
+    }
+}
+
+/// A tracking event that failed validation; `422` with the rejected fields
+struct InvalidEvent(validator::ValidationErrors);
+
+impl IntoResponse for InvalidEvent {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(ValidationErrorResponse::from_validation_errors(self.0)),
+        )
+            .into_response()
+    }
+}
+
+impl From<InvalidEvent> for Response {
+    fn from(invalid: InvalidEvent) -> Self {
+        invalid.into_response()
     }
 }
 
+fn reject_invalid_event(event: &impl Validate) -> Result<(), InvalidEvent> {
+    event.validate().map_err(InvalidEvent)
+}
+
+#[derive(Deserialize)]
+pub struct BatchQuery {
+    /// Reject the whole batch if any event fails
+    #[serde(default)]
+    atomic: bool,
+}
+
+#[derive(Serialize)]
+pub struct BatchIngestResponse {
+    accepted: usize,
+    failed: Vec<BatchEventError>,
+}
+
+#[derive(Serialize)]
+pub struct BatchEventError {
+    index: usize,
+    error: String,
+}
+
+// Helper functions
+
+/// Share of searches (that recorded a result count) which found nothing
+async fn search_no_results_rate(
+    db: &sqlx::PgPool,
+    domain_ids: &[i32],
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+) -> Result<f64, StatusCode> {
+    let rate: Option<f64> = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FILTER (WHERE (metadata->>'results_count')::int = 0)::float8
+               / NULLIF(COUNT(*) FILTER (WHERE metadata ? 'results_count'), 0)
+        FROM analytics_events
+        WHERE domain_id = ANY($1) AND event_type = 'search'
+        AND created_at BETWEEN $2 AND $3
+        "#,
+    )
+    .bind(domain_ids)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_one(db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(rate.unwrap_or(0.0))
+}
+
 fn check_analytics_permission(user: &UserContext, domain_id: i32) -> Result<(), StatusCode> {
-    if user.role == "super_admin" || user.role == "platform_admin" {
+    if user.role().is_platform_level() {
         return Ok(());
     }
 
@@ -320,35 +852,76 @@ fn check_analytics_permission(user: &UserContext, domain_id: i32) -> Result<(),
     Ok(())
 }
 
-/// Get domain IDs that the user has access to for analytics
-async fn get_user_accessible_domains(
+/// Every domain the user can see: all of them for platform admins, otherwise
+/// the ones they hold a permission on (possibly none). Only platform admins
+/// cost a query, as the auth middleware already loaded the permissions.
+pub(crate) async fn accessible_domain_ids(
+    user: &UserContext,
+    db: &sqlx::PgPool,
+) -> Result<Vec<i32>, StatusCode> {
+    if user.role().is_platform_level() {
+        sqlx::query_scalar("SELECT id FROM domains ORDER BY id")
+            .fetch_all(db)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+    } else {
+        Ok(user
+            .domain_permissions
+            .iter()
+            .map(|p| p.domain_id)
+            .collect())
+    }
+}
+
+/// Get domain IDs that the user has access to for analytics: `domain_id`
+/// when given and permitted, otherwise every accessible domain. Users with
+/// no domain at all are forbidden.
+pub(crate) async fn get_user_accessible_domains(
     user: &UserContext,
     query: &AnalyticsQuery,
     db: &sqlx::PgPool,
 ) -> Result<Vec<i32>, StatusCode> {
     if let Some(specific_domain) = query.domain_id {
         check_analytics_permission(user, specific_domain)?;
-        Ok(vec![specific_domain])
-    } else if user.role == "super_admin" || user.role == "platform_admin" {
-        let all_domains = sqlx::query!("SELECT id FROM domains")
-            .fetch_all(db)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        Ok(all_domains.into_iter().map(|d| d.id).collect())
+        return Ok(vec![specific_domain]);
+    }
+
+    let domain_ids = accessible_domain_ids(user, db).await?;
+    if domain_ids.is_empty() && !user.role().is_platform_level() {
+        Err(StatusCode::FORBIDDEN)
     } else {
-        let domain_ids = get_user_domain_ids(user);
-        if domain_ids.is_empty() {
-            Err(StatusCode::FORBIDDEN)
-        } else {
-            Ok(domain_ids)
+        Ok(domain_ids)
+    }
+}
+
+/// Time zone for day and hour buckets: `tz` when given, otherwise the zone the
+/// domains share (UTC when they differ). Unknown zones are a bad request.
+async fn bucket_timezone(
+    query: &AnalyticsQuery,
+    domain_ids: &[i32],
+    db: &sqlx::PgPool,
+) -> Result<String, StatusCode> {
+    match &query.tz {
+        Some(tz) => {
+            if timezones::is_known(db, tz)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            {
+                Ok(tz.clone())
+            } else {
+                Err(StatusCode::BAD_REQUEST)
+            }
         }
+        None => timezones::shared_by(db, domain_ids)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
 
-fn parse_date_range(query: &AnalyticsQuery) -> (DateTime<Utc>, DateTime<Utc>) {
+fn parse_date_range(query: &AnalyticsQuery, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
     // Handle range parameter first
     if let Some(range) = &query.range {
-        let end_date = Utc::now();
+        let end_date = now;
         let days = match range.as_str() {
             "24h" => 1,
             "7d" => 7,
@@ -363,15 +936,13 @@ fn parse_date_range(query: &AnalyticsQuery) -> (DateTime<Utc>, DateTime<Utc>) {
     if let (Some(start_str), Some(end_str)) = (&query.start_date, &query.end_date) {
         let start_date = start_str
             .parse::<DateTime<Utc>>()
-            .unwrap_or_else(|_| Utc::now() - Duration::days(30));
-        let end_date = end_str
-            .parse::<DateTime<Utc>>()
-            .unwrap_or_else(|_| Utc::now());
+            .unwrap_or_else(|_| now - Duration::days(30));
+        let end_date = end_str.parse::<DateTime<Utc>>().unwrap_or(now);
         return (start_date, end_date);
     }
 
     // Default to days parameter or 7 days
-    let end_date = Utc::now();
+    let end_date = now;
     let days = query.days.unwrap_or(7).clamp(1, 365);
     let start_date = end_date - Duration::days(days as i64);
     (start_date, end_date)
@@ -382,52 +953,67 @@ pub async fn get_analytics_dashboard(
     Extension(user): Extension<UserContext>,
     State(state): State<Arc<AppState>>,
     Query(query): Query<AnalyticsQuery>,
-) -> Result<Json<AnalyticsDashboardResponse>, StatusCode> {
+    Query(bypass): Query<CacheBypass>,
+    Query(delta): Query<DeltaQuery>,
+) -> Result<Response, StatusCode> {
     PerformanceSpan::monitor("analytics_dashboard", async {
-        let (start_date, end_date) = parse_date_range(&query);
-        let previous_start = start_date - (end_date - start_date);
+        let now = state.clock.now();
+        if let Some(since) = delta.since.as_deref() {
+            let since = since
+                .parse::<DateTime<Utc>>()
+                .map_err(|_| StatusCode::BAD_REQUEST)?;
+            if since < now - Duration::hours(MAX_DELTA_AGE_HOURS) {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            // Events still in the write buffer would be skipped by the next
+            // poll, so stop short of them
+            let write_delay = Duration::from_std(state.events.write_delay())
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            let until = (now - write_delay).max(since);
+            let domain_ids = get_user_accessible_domains(&user, &query, &state.db).await?;
+            let delta = dashboard_delta(&state.db, &domain_ids, since, until)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            return Ok(Json(delta).into_response());
+        }
+        let (start_date, end_date) = parse_date_range(&query, now);
+        let (previous_start, previous_end) = period_comparison::comparison_window(
+            query.compare_start.as_deref(),
+            query.compare_end.as_deref(),
+            start_date,
+            end_date,
+            now,
+        )
+        .map_err(|e| {
+            tracing::warn!(error = %e, "Invalid comparison window");
+            StatusCode::BAD_REQUEST
+        })?;
 
         // Get domain IDs user has access to
         let domain_ids = get_user_accessible_domains(&user, &query, &state.db).await?;
 
-        // Current period stats - aggregate across all permitted domains
-        let current_stats = sqlx::query!(
-            r#"
-        SELECT 
-            COUNT(*) FILTER (WHERE event_type = 'page_view') as page_views,
-            COUNT(*) FILTER (WHERE event_type = 'post_view') as post_views,
-            COUNT(DISTINCT ip_address) as unique_visitors,
-            COUNT(*) FILTER (WHERE event_type = 'search') as searches,
-            COUNT(DISTINCT session_id) as total_sessions
-        FROM analytics_events 
-        WHERE domain_id = ANY($1) AND created_at BETWEEN $2 AND $3
-        "#,
-            &domain_ids,
-            start_date,
-            end_date
-        )
-        .fetch_one(&state.db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        // Users who can see the same domains share cached responses
+        let cache = &state.dashboard_cache;
+        let cache_key = dashboard_cache_key(&domain_ids, &query);
+        let cached = if bypass.nocache {
+            None
+        } else {
+            cache.get(&cache_key)
+        };
+        if let Some(cached) = cached {
+            return Ok(cached_json(cached.body, cache.ttl(), cached.age));
+        }
 
-        // Previous period stats for comparison
-        let previous_stats = sqlx::query!(
-            r#"
-        SELECT 
-            COUNT(*) FILTER (WHERE event_type = 'page_view') as page_views,
-            COUNT(*) FILTER (WHERE event_type = 'post_view') as post_views,
-            COUNT(DISTINCT ip_address) as unique_visitors,
-            COUNT(*) FILTER (WHERE event_type = 'search') as searches
-        FROM analytics_events 
-        WHERE domain_id = ANY($1) AND created_at BETWEEN $2 AND $3
-        "#,
-            &domain_ids,
-            previous_start,
-            start_date
-        )
-        .fetch_one(&state.db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        // Current and previous period totals - whole past days come from the
+        // daily rollup, only partial days are scanned from raw events
+        let current_stats =
+            daily_stats::period_totals(&state.db, &domain_ids, start_date, end_date, now)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let previous_stats =
+            daily_stats::period_totals(&state.db, &domain_ids, previous_start, previous_end, now)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         // Top posts across all permitted domains
         let top_posts = sqlx::query!(
@@ -489,30 +1075,33 @@ pub async fn get_analytics_dashboard(
         })
         .collect();
 
-        // Calculate percentage changes
-        let calc_change = |current: Option<i64>, previous: Option<i64>| -> f64 {
-            let curr = current.unwrap_or(0) as f64;
-            let prev = previous.unwrap_or(0) as f64;
-            if prev == 0.0 {
-                0.0
-            } else {
-                ((curr - prev) / prev) * 100.0
-            }
+        // Calculate percentage changes against the comparison window
+        let calc_change = |current: i64, previous: i64| -> f64 {
+            period_comparison::change_percent(
+                current,
+                previous,
+                (start_date, end_date),
+                (previous_start, previous_end),
+            )
         };
 
+        let session_config = SessionConfig::default();
         let previous_period = PeriodStats {
-            page_views: previous_stats.page_views.unwrap_or(0),
-            unique_visitors: previous_stats.unique_visitors.unwrap_or(0),
-            post_views: previous_stats.post_views.unwrap_or(0),
-            searches: previous_stats.searches.unwrap_or(0),
+            start: previous_start,
+            end: previous_end,
+            page_views: previous_stats.page_views,
+            unique_visitors: previous_stats.unique_visitors,
+            post_views: previous_stats.post_views,
+            searches: previous_stats.searches,
             avg_session_duration: SessionTracker::get_average_session_duration(
                 &state.db,
                 previous_start,
-                start_date,
+                previous_end,
                 None,
+                &session_config,
             )
             .await
-            .unwrap_or(0.0),
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
         };
 
         let change_percent = ChangePercent {
@@ -528,12 +1117,14 @@ pub async fn get_analytics_dashboard(
         // Get search analytics
         let search_queries = sqlx::query!(
             r#"
-        SELECT path as query,
-               COUNT(*) as count
+        SELECT metadata->>'query' as query,
+               COUNT(*) as count,
+               AVG((metadata->>'results_count')::float8) as results_avg
         FROM analytics_events
         WHERE domain_id = ANY($1) AND event_type = 'search'
         AND created_at BETWEEN $2 AND $3
-        GROUP BY path
+        AND metadata ? 'query'
+        GROUP BY metadata->>'query'
         ORDER BY count DESC
         LIMIT 5
         "#,
@@ -548,10 +1139,13 @@ pub async fn get_analytics_dashboard(
         .map(|row| SearchQuery {
             query: row.query.unwrap_or_default(),
             count: row.count.unwrap_or(0),
-            results_avg: 8.5,
+            results_avg: row.results_avg.unwrap_or(0.0),
         })
         .collect();
 
+        let no_results_rate =
+            search_no_results_rate(&state.db, &domain_ids, start_date, end_date).await?;
+
         // Get real content performance data from posts analytics
         let content_performance: Vec<ContentPerformance> = sqlx::query!(
             r#"
@@ -695,7 +1289,7 @@ pub async fn get_analytics_dashboard(
         let engagement_score_avg = match engagement_score {
             Ok(record) => record
                 .avg_engagement
-                .and_then(|d| d.to_string().parse::<f64>().ok())
+                .map(|d| decimal_to_f64(&d))
                 .unwrap_or(0.0),
             Err(_) => 0.0,
         };
@@ -717,7 +1311,7 @@ pub async fn get_analytics_dashboard(
         let avg_reading_time_val = match avg_reading_time {
             Ok(record) => record
                 .avg_time
-                .and_then(|d| d.to_string().parse::<i64>().ok())
+                .map(|d| decimal_to_f64(&d).round() as i64)
                 .unwrap_or(0),
             Err(_) => 0,
         };
@@ -739,37 +1333,43 @@ pub async fn get_analytics_dashboard(
         .await;
 
         let completion_rate = match completion_stats {
-            Ok(stats) => {
-                let total = stats.total_content_views.unwrap_or(0);
-                if total > 0 {
-                    (stats.completed_views.unwrap_or(0) as f64 / total as f64) * 100.0
-                } else {
-                    0.0
-                }
-            }
+            Ok(stats) => content_completion_rate(
+                stats.total_content_views.unwrap_or(0),
+                stats.completed_views.unwrap_or(0),
+            ),
             Err(_) => 0.0,
         };
 
         // Get real session metrics
         let avg_session_duration = SessionTracker::get_average_session_duration(
-            &state.db, start_date, end_date, None, // Cross-domain analytics
+            &state.db,
+            start_date,
+            end_date,
+            None, // Cross-domain analytics
+            &session_config,
         )
         .await
-        .unwrap_or(0.0);
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         let bounce_rate = SessionTracker::get_bounce_rate(
-            &state.db, start_date, end_date, None, // Cross-domain analytics
+            &state.db,
+            start_date,
+            end_date,
+            None, // Cross-domain analytics
+            &session_config,
         )
         .await
-        .unwrap_or(0.0);
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
         let response = AnalyticsDashboardResponse {
             overview: DashboardOverview {
-                total_sessions: current_stats.total_sessions.unwrap_or(0),
-                total_page_views: current_stats.page_views.unwrap_or(0),
+                total_sessions: current_stats.sessions,
+                total_page_views: current_stats.page_views,
                 avg_session_duration,
                 bounce_rate,
-                unique_visitors: current_stats.unique_visitors.unwrap_or(0),
+                // Any session in the period gives a bounce rate
+                session_data_available: bounce_rate.is_some(),
+                unique_visitors: current_stats.unique_visitors,
                 previous_period,
                 change_percent,
             },
@@ -781,7 +1381,7 @@ pub async fn get_analytics_dashboard(
             },
             search: SearchAnalytics {
                 top_queries: search_queries,
-                no_results_rate: 0.12,
+                no_results_rate,
                 search_to_click_rate: 0.68,
             },
             content: ContentAnalytics {
@@ -793,89 +1393,520 @@ pub async fn get_analytics_dashboard(
             top_categories,
         };
 
-        Ok(Json(response))
+        let body = Bytes::from(
+            serde_json::to_vec(&response).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        );
+        cache.insert(cache_key, body.clone());
+        Ok(cached_json(body, cache.ttl(), std::time::Duration::ZERO))
     })
     .await
 }
 
-// Traffic analytics - keep the existing working implementation
-pub async fn get_traffic_stats(
+/// What `domain_ids` recorded after `since`, up to `until`, counted from raw
+/// events like the partial days of a full dashboard
+async fn dashboard_delta(
+    db: &sqlx::PgPool,
+    domain_ids: &[i32],
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<DashboardDelta, sqlx::Error> {
+    let (page_views, post_views, searches): (i64, i64, i64) = sqlx::query_as(
+        r#"
+        SELECT
+            ROUND(COALESCE(SUM(sample_weight) FILTER (WHERE event_type = 'page_view'), 0))::BIGINT,
+            COUNT(*) FILTER (WHERE event_type = 'post_view'),
+            COUNT(*) FILTER (WHERE event_type = 'search')
+        FROM analytics_events
+        WHERE domain_id = ANY($1) AND created_at > $2 AND created_at <= $3
+        "#,
+    )
+    .bind(domain_ids)
+    .bind(since)
+    .bind(until)
+    .fetch_one(db)
+    .await?;
+
+    let posts = sqlx::query_as::<_, PostDelta>(
+        r#"
+        SELECT p.id, p.title, p.slug, COUNT(*) AS views
+        FROM analytics_events ae
+        JOIN posts p ON ae.post_id = p.id
+        WHERE ae.domain_id = ANY($1) AND ae.event_type = 'post_view'
+          AND ae.created_at > $2 AND ae.created_at <= $3
+        GROUP BY p.id, p.title, p.slug
+        ORDER BY views DESC, p.id
+        LIMIT $4
+        "#,
+    )
+    .bind(domain_ids)
+    .bind(since)
+    .bind(until)
+    .bind(MAX_DELTA_POSTS)
+    .fetch_all(db)
+    .await?;
+
+    Ok(DashboardDelta {
+        since,
+        until,
+        page_views,
+        post_views,
+        searches,
+        posts,
+    })
+}
+
+/// Cache key for a dashboard request: the domains it covers and the window
+/// parameters as given, so relative ranges share an entry until it expires
+fn dashboard_cache_key(domain_ids: &[i32], query: &AnalyticsQuery) -> String {
+    let mut domain_ids = domain_ids.to_vec();
+    domain_ids.sort_unstable();
+    let params = [
+        &query.range,
+        &query.start_date,
+        &query.end_date,
+        &query.compare_start,
+        &query.compare_end,
+    ];
+    format!(
+        "{domain_ids:?}|{}|{}",
+        query.days.map(|d| d.to_string()).unwrap_or_default(),
+        params
+            .iter()
+            .map(|p| p.as_deref().unwrap_or(""))
+            .collect::<Vec<_>>()
+            .join("|"),
+    )
+}
+
+/// A JSON body with headers telling clients how fresh it is
+fn cached_json(body: Bytes, ttl: std::time::Duration, age: std::time::Duration) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (
+                header::CACHE_CONTROL,
+                format!("private, max-age={}", ttl.saturating_sub(age).as_secs()),
+            ),
+            (header::AGE, age.as_secs().to_string()),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// Share of content views that scrolled to at least 90%, as a percentage
+fn content_completion_rate(total_views: i64, completed_views: i64) -> f64 {
+    if total_views > 0 {
+        (completed_views as f64 / total_views as f64) * 100.0
+    } else {
+        0.0
+    }
+}
+
+// Content engagement for a single domain (reading time + completion)
+pub async fn get_content_engagement(
     Extension(user): Extension<UserContext>,
     State(state): State<Arc<AppState>>,
     Query(query): Query<AnalyticsQuery>,
-) -> Result<Json<TrafficResponse>, StatusCode> {
-    PerformanceSpan::monitor("get_traffic_stats", async {
-        let (start_date, end_date) = parse_date_range(&query);
+) -> Result<Json<ContentEngagementResponse>, StatusCode> {
+    let domain_id = query.domain_id.ok_or(StatusCode::BAD_REQUEST)?;
+    check_analytics_permission(&user, domain_id)?;
 
-        // Get domain IDs user has access to
-        let domain_ids = get_user_accessible_domains(&user, &query, &state.db).await?;
+    let (start_date, end_date) = parse_date_range(&query, state.clock.now());
 
-        // Daily stats aggregated across domains
-        let daily_stats = sqlx::query!(
-            r#"
+    // content_metrics has no domain column, so scope through the session's domain
+    let stats = sqlx::query!(
+        r#"
         SELECT 
-            DATE(created_at) as date,
-            COUNT(*) FILTER (WHERE event_type = 'page_view') as page_views,
-            COUNT(*) FILTER (WHERE event_type = 'post_view') as post_views,
-            COUNT(DISTINCT ip_address) as unique_visitors
-        FROM analytics_events
-        WHERE domain_id = ANY($1) AND created_at BETWEEN $2 AND $3
-        GROUP BY DATE(created_at)
-        ORDER BY date
+            COUNT(*) as total_content_views,
+            COUNT(*) FILTER (WHERE cm.scroll_percentage >= 90) as completed_views,
+            AVG(cm.reading_time)::float8 as avg_time
+        FROM content_metrics cm
+        JOIN user_sessions us ON cm.session_id = us.session_id
+        JOIN domains d ON d.hostname = us.domain_name
+        WHERE d.id = $1 AND cm.created_at BETWEEN $2 AND $3
         "#,
-            &domain_ids,
-            start_date,
-            end_date
+        domain_id,
+        start_date,
+        end_date
+    )
+    .fetch_one(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let total = stats.total_content_views.unwrap_or(0);
+
+    Ok(Json(ContentEngagementResponse {
+        domain_id,
+        total_content_views: total,
+        avg_reading_time: stats.avg_time.map(|t| t.round() as i64).unwrap_or(0),
+        content_completion_rate: content_completion_rate(total, stats.completed_views.unwrap_or(0)),
+    }))
+}
+
+// Time-on-page distribution and percentiles across the user's domains
+pub async fn get_engagement_stats(
+    Extension(user): Extension<UserContext>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<EngagementResponse>, StatusCode> {
+    let (start_date, end_date) = parse_date_range(&query, state.clock.now());
+    let domain_ids = get_user_accessible_domains(&user, &query, &state.db).await?;
+    let lower_bounds: Vec<i32> = TIME_ON_PAGE_BUCKETS.iter().map(|(_, min)| *min).collect();
+
+    // width_bucket returns the 1-based index of the last bound <= time_on_page
+    let bucket_counts: Vec<(i32, i64)> = sqlx::query_as(
+        r#"
+        SELECT width_bucket(cm.time_on_page, $4::int[]) AS bucket, COUNT(*)
+        FROM content_metrics cm
+        JOIN user_sessions us ON cm.session_id = us.session_id
+        JOIN domains d ON d.hostname = us.domain_name
+        WHERE d.id = ANY($1) AND cm.created_at BETWEEN $2 AND $3
+        GROUP BY bucket
+        "#,
+    )
+    .bind(&domain_ids)
+    .bind(start_date)
+    .bind(end_date)
+    .bind(&lower_bounds)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let (median, p75, p90, p95): (Option<f64>, Option<f64>, Option<f64>, Option<f64>) =
+        sqlx::query_as(
+            r#"
+            SELECT
+                percentile_cont(0.5) WITHIN GROUP (ORDER BY cm.time_on_page),
+                percentile_cont(0.75) WITHIN GROUP (ORDER BY cm.time_on_page),
+                percentile_cont(0.9) WITHIN GROUP (ORDER BY cm.time_on_page),
+                percentile_cont(0.95) WITHIN GROUP (ORDER BY cm.time_on_page)
+            FROM content_metrics cm
+            JOIN user_sessions us ON cm.session_id = us.session_id
+            JOIN domains d ON d.hostname = us.domain_name
+            WHERE d.id = ANY($1) AND cm.created_at BETWEEN $2 AND $3
+            "#,
         )
-        .fetch_all(&state.db)
+        .bind(&domain_ids)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_one(&state.db)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let total_views: i64 = bucket_counts.iter().map(|(_, count)| count).sum();
+    let distribution = TIME_ON_PAGE_BUCKETS
+        .iter()
+        .enumerate()
+        .map(|(i, (label, min_seconds))| {
+            let count = bucket_counts
+                .iter()
+                .find(|(bucket, _)| *bucket as usize == i + 1)
+                .map(|(_, count)| *count)
+                .unwrap_or(0);
+            TimeOnPageBucket {
+                label,
+                min_seconds: *min_seconds,
+                max_seconds: TIME_ON_PAGE_BUCKETS.get(i + 1).map(|(_, max)| *max),
+                count,
+                percentage: percentage(count, total_views),
+            }
+        })
+        .collect();
+
+    Ok(Json(EngagementResponse {
+        total_views,
+        distribution,
+        median_seconds: median.unwrap_or(0.0),
+        p75_seconds: p75.unwrap_or(0.0),
+        p90_seconds: p90.unwrap_or(0.0),
+        p95_seconds: p95.unwrap_or(0.0),
+    }))
+}
+
+/// Convert a funnel step pattern into a SQL LIKE pattern
+fn funnel_pattern_to_like(pattern: &str) -> String {
+    pattern
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+        .replace('*', "%")
+}
+
+/// Convert a funnel step pattern into an anchored regex
+fn funnel_pattern_to_regex(pattern: &str) -> Regex {
+    let escaped: Vec<String> = pattern.split('*').map(regex::escape).collect();
+    Regex::new(&format!("^{}$", escaped.join(".*"))).expect("escaped pattern is valid")
+}
+
+/// Walk each session's events in order and count how many sessions reached each step.
+/// `events` must be sorted by session, then by time.
+fn count_funnel_sessions(steps: &[Regex], events: &[(Uuid, String)]) -> Vec<i64> {
+    let mut counts = vec![0i64; steps.len()];
+
+    for session_events in events.chunk_by(|a, b| a.0 == b.0) {
+        let mut reached = 0;
+        for (_, path) in session_events {
+            if reached < steps.len() && steps[reached].is_match(path) {
+                reached += 1;
+            }
+        }
+        for count in counts.iter_mut().take(reached) {
+            *count += 1;
+        }
+    }
+
+    counts
+}
+
+fn percentage(part: i64, whole: i64) -> f64 {
+    if whole > 0 {
+        (part as f64 / whole as f64) * 100.0
+    } else {
+        0.0
+    }
+}
+
+// Funnel analytics - drop-off across an ordered sequence of paths
+pub async fn analyze_funnel(
+    Extension(user): Extension<UserContext>,
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(request): ValidatedJson<FunnelRequest>,
+) -> Result<Json<FunnelResponse>, StatusCode> {
+    let (start_date, end_date) = parse_date_range(&request.window, state.clock.now());
+    let domain_ids = get_user_accessible_domains(&user, &request.window, &state.db).await?;
+
+    let like_patterns: Vec<String> = request
+        .steps
+        .iter()
+        .map(|step| funnel_pattern_to_like(step))
+        .collect();
+
+    // Only events that match some step matter; ordering drives the funnel walk
+    let events: Vec<(Uuid, String)> = sqlx::query!(
+        r#"
+        SELECT session_id as "session_id!", path as "path!"
+        FROM analytics_events
+        WHERE domain_id = ANY($1)
+        AND session_id IS NOT NULL AND path IS NOT NULL
+        AND created_at BETWEEN $2 AND $3
+        AND path LIKE ANY($4)
+        ORDER BY session_id, created_at
+        "#,
+        &domain_ids,
+        start_date,
+        end_date,
+        &like_patterns
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .into_iter()
+    .map(|row| (row.session_id, row.path))
+    .collect();
+
+    let step_regexes: Vec<Regex> = request
+        .steps
+        .iter()
+        .map(|step| funnel_pattern_to_regex(step))
+        .collect();
+    let counts = count_funnel_sessions(&step_regexes, &events);
+    let total_sessions = counts.first().copied().unwrap_or(0);
+
+    let steps = request
+        .steps
         .into_iter()
-        .map(|row| DayStats {
-            date: row.date.unwrap_or_default().to_string(),
-            page_views: row.page_views.unwrap_or(0),
-            unique_visitors: row.unique_visitors.unwrap_or(0),
-            post_views: row.post_views.unwrap_or(0),
+        .enumerate()
+        .map(|(i, pattern)| {
+            let previous = if i == 0 { counts[0] } else { counts[i - 1] };
+            FunnelStep {
+                pattern,
+                sessions: counts[i],
+                conversion_from_previous: percentage(counts[i], previous),
+                conversion_from_start: percentage(counts[i], total_sessions),
+            }
         })
         .collect();
 
+    Ok(Json(FunnelResponse {
+        total_sessions,
+        steps,
+    }))
+}
+
+/// Most events returned for one session timeline
+const MAX_TIMELINE_EVENTS: usize = 2000;
+
+// Every event recorded for one visitor session, oldest first, for tracing a
+// reported issue. The id may be the session's `id` or its client-side
+// `session_id`; sessions on domains the user can't view are reported as
+// missing rather than forbidden.
+pub async fn get_session_timeline(
+    Extension(user): Extension<UserContext>,
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<SessionTimelineResponse>, StatusCode> {
+    let session = sqlx::query_as::<_, TimelineSession>(
+        r#"
+        SELECT us.id, us.session_id, d.id AS domain_id,
+               us.started_at, us.last_activity_at, us.ended_at,
+               host(network(set_masklen(
+                   us.ip_address, CASE family(us.ip_address) WHEN 4 THEN 24 ELSE 48 END
+               ))) AS ip_address,
+               us.user_agent
+        FROM user_sessions us
+        JOIN domains d ON d.hostname = us.domain_name
+        WHERE us.id = $1 OR us.session_id = $1
+        LIMIT 1
+        "#,
+    )
+    .bind(session_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    check_analytics_permission(&user, session.domain_id).map_err(|_| StatusCode::NOT_FOUND)?;
+
+    // Event tables reference the session by its `id`
+    let mut events = sqlx::query_as::<_, TimelineEvent>(
+        r#"
+        SELECT at, source, event_type, path, details
+        FROM (
+            SELECT created_at AS at, 'analytics' AS source, event_type, path,
+                   jsonb_strip_nulls(jsonb_build_object(
+                       'post_id', post_id, 'referrer', referrer, 'metadata', metadata
+                   )) AS details, id
+            FROM analytics_events
+            WHERE session_id = $1 AND created_at IS NOT NULL
+            UNION ALL
+            SELECT created_at, 'behavior', event_type, NULL,
+                   jsonb_strip_nulls(jsonb_build_object(
+                       'element', element, 'x', x, 'y', y, 'scroll_depth', scroll_depth
+                   )), id
+            FROM behavior_events
+            WHERE session_id = $1
+            UNION ALL
+            SELECT created_at, 'search', 'search', NULL,
+                   jsonb_build_object(
+                       'query', query, 'results_count', results_count, 'no_results', no_results
+                   ), id
+            FROM search_events
+            WHERE session_id = $1
+            UNION ALL
+            SELECT created_at, 'search_click', 'search_click', NULL,
+                   jsonb_strip_nulls(jsonb_build_object(
+                       'query', query, 'clicked_result', clicked_result,
+                       'position_clicked', position_clicked
+                   )), id
+            FROM search_click_events
+            WHERE session_id = $1
+            UNION ALL
+            SELECT created_at, 'content_metrics', content_type, NULL,
+                   jsonb_build_object(
+                       'content_id', content_id, 'title', title, 'reading_time', reading_time,
+                       'scroll_percentage', scroll_percentage, 'time_on_page', time_on_page
+                   ), id
+            FROM content_metrics
+            WHERE session_id = $1
+        ) timeline
+        ORDER BY at, source, id
+        LIMIT $2
+        "#,
+    )
+    .bind(session.id)
+    .bind(MAX_TIMELINE_EVENTS as i64 + 1)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "Failed to load session timeline");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let truncated = events.len() > MAX_TIMELINE_EVENTS;
+    events.truncate(MAX_TIMELINE_EVENTS);
+
+    Ok(Json(SessionTimelineResponse {
+        session_id: session.session_id,
+        domain_id: session.domain_id,
+        started_at: session.started_at,
+        last_activity_at: session.last_activity_at,
+        ended_at: session.ended_at,
+        ip_address: session.ip_address,
+        user_agent: session.user_agent,
+        events,
+        truncated,
+    }))
+}
+
+// Traffic analytics - keep the existing working implementation
+pub async fn get_traffic_stats(
+    Extension(user): Extension<UserContext>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<TrafficResponse>, StatusCode> {
+    PerformanceSpan::monitor("get_traffic_stats", async {
+        let now = state.clock.now();
+        let (start_date, end_date) = parse_date_range(&query, now);
+
+        // Get domain IDs user has access to
+        let domain_ids = get_user_accessible_domains(&user, &query, &state.db).await?;
+        let tz = bucket_timezone(&query, &domain_ids, &state.db).await?;
+
+        // Daily stats aggregated across domains, from the rollup for past days
+        let daily_stats =
+            daily_stats::daily_totals(&state.db, &domain_ids, start_date, end_date, now, &tz)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                .into_iter()
+                .map(|row| DayStats {
+                    date: row.day.to_string(),
+                    page_views: row.totals.page_views,
+                    unique_visitors: row.totals.unique_visitors,
+                    post_views: row.totals.post_views,
+                })
+                .collect();
+
         // Hourly distribution aggregated across domains
         let hourly_distribution = sqlx::query!(
             r#"
         SELECT 
-            EXTRACT(HOUR FROM created_at) as hour,
-            COUNT(*) FILTER (WHERE event_type = 'page_view') as page_views,
+            CAST(EXTRACT(HOUR FROM created_at AT TIME ZONE $4) AS INTEGER) as hour,
+            ROUND(COALESCE(SUM(sample_weight) FILTER (WHERE event_type = 'page_view'), 0))::BIGINT as page_views,
             COUNT(DISTINCT ip_address) as unique_visitors
         FROM analytics_events
         WHERE domain_id = ANY($1) AND created_at BETWEEN $2 AND $3
-        GROUP BY EXTRACT(HOUR FROM created_at)
+        GROUP BY hour
         ORDER BY hour
         "#,
             &domain_ids,
             start_date,
-            end_date
+            end_date,
+            tz
         )
         .fetch_all(&state.db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .into_iter()
-        .map(|row| HourStats {
-            hour: row
-                .hour
-                .map(|h| h.to_string().parse().unwrap_or(0))
-                .unwrap_or(0),
-            page_views: row.page_views.unwrap_or(0),
-            unique_visitors: row.unique_visitors.unwrap_or(0),
+        .filter_map(|row| {
+            Some(HourStats {
+                hour: daily_stats::hour_bucket(row.hour)?,
+                page_views: row.page_views.unwrap_or(0),
+                unique_visitors: row.unique_visitors.unwrap_or(0),
+            })
         })
         .collect();
 
-        // Get real device breakdown from session data
+        // Device breakdown over the same domain set, from session data
         let device_breakdown = {
             let (mobile, desktop, tablet, unknown) = SessionTracker::get_device_breakdown(
-                &state.db, start_date, end_date, None, // Cross-domain analytics
+                &state.db,
+                start_date,
+                end_date,
+                Some(domain_ids.as_slice()),
             )
             .await
-            .unwrap_or((0, 0, 0, 0));
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
             DeviceBreakdown {
                 mobile,
@@ -896,12 +1927,212 @@ pub async fn get_traffic_stats(
     .await
 }
 
+/// Unique visitors per accessible domain split into first-time and returning,
+/// with a daily trend
+pub async fn get_visitor_stats(
+    Extension(user): Extension<UserContext>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<VisitorsResponse>, StatusCode> {
+    PerformanceSpan::monitor("get_visitor_stats", async {
+        let (start_date, end_date) = parse_date_range(&query, state.clock.now());
+        let domain_ids = get_user_accessible_domains(&user, &query, &state.db).await?;
+        let tz = bucket_timezone(&query, &domain_ids, &state.db).await?;
+
+        let domains = sqlx::query_as::<_, DomainVisitors>(
+            r#"
+            WITH visitors AS (
+                SELECT e.domain_id,
+                       EXISTS (
+                           SELECT 1 FROM analytics_events prior
+                           WHERE prior.domain_id = e.domain_id
+                             AND prior.ip_address = e.ip_address
+                             AND prior.created_at < $2
+                       ) AS is_returning
+                FROM analytics_events e
+                WHERE e.domain_id = ANY($1) AND e.created_at BETWEEN $2 AND $3
+                  AND e.ip_address IS NOT NULL
+                GROUP BY e.domain_id, e.ip_address
+            )
+            SELECT domain_id,
+                   COUNT(*) AS unique_visitors,
+                   COUNT(*) FILTER (WHERE NOT is_returning) AS new_visitors,
+                   COUNT(*) FILTER (WHERE is_returning) AS returning_visitors
+            FROM visitors
+            GROUP BY domain_id
+            ORDER BY domain_id
+            "#,
+        )
+        .bind(&domain_ids)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let daily_trend = sqlx::query_as::<_, VisitorDay>(
+            r#"
+            WITH daily AS (
+                SELECT e.domain_id, e.ip_address,
+                       DATE(e.created_at AT TIME ZONE $4) AS day,
+                       MIN(e.created_at) AS first_seen
+                FROM analytics_events e
+                WHERE e.domain_id = ANY($1) AND e.created_at BETWEEN $2 AND $3
+                  AND e.ip_address IS NOT NULL
+                GROUP BY e.domain_id, e.ip_address, day
+            ),
+            classified AS (
+                SELECT d.day,
+                       EXISTS (
+                           SELECT 1 FROM analytics_events prior
+                           WHERE prior.domain_id = d.domain_id
+                             AND prior.ip_address = d.ip_address
+                             AND prior.created_at < d.first_seen
+                       ) AS is_returning
+                FROM daily d
+            )
+            SELECT day AS date,
+                   COUNT(*) FILTER (WHERE NOT is_returning) AS new_visitors,
+                   COUNT(*) FILTER (WHERE is_returning) AS returning_visitors
+            FROM classified
+            GROUP BY day
+            ORDER BY day
+            "#,
+        )
+        .bind(&domain_ids)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(&tz)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        Ok(Json(VisitorsResponse {
+            domains,
+            daily_trend,
+        }))
+    })
+    .await
+}
+
+/// Top landing and exit pages per accessible domain. A session's landing
+/// page is its first page view in the period and its exit page the last, so
+/// a single-page session counts as both.
+pub async fn get_entry_exit_pages(
+    Extension(user): Extension<UserContext>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<PagesResponse>, StatusCode> {
+    PerformanceSpan::monitor("get_entry_exit_pages", async {
+        let (start_date, end_date) = parse_date_range(&query, state.clock.now());
+        let domain_ids = get_user_accessible_domains(&user, &query, &state.db).await?;
+
+        let rows = sqlx::query_as::<_, EntryExitRow>(
+            r#"
+            WITH views AS (
+                SELECT domain_id, path,
+                       ROW_NUMBER() OVER (
+                           PARTITION BY domain_id, session_id ORDER BY created_at, id
+                       ) AS from_start,
+                       ROW_NUMBER() OVER (
+                           PARTITION BY domain_id, session_id ORDER BY created_at DESC, id DESC
+                       ) AS from_end
+                FROM analytics_events
+                WHERE domain_id = ANY($1) AND created_at BETWEEN $2 AND $3
+                  AND event_type = $4
+                  AND session_id IS NOT NULL AND path IS NOT NULL
+            )
+            SELECT domain_id, TRUE AS landing, path, COUNT(*) AS sessions
+            FROM views WHERE from_start = 1
+            GROUP BY domain_id, path
+            UNION ALL
+            SELECT domain_id, FALSE, path, COUNT(*)
+            FROM views WHERE from_end = 1
+            GROUP BY domain_id, path
+            ORDER BY domain_id, landing, sessions DESC, path
+            "#,
+        )
+        .bind(&domain_ids)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(event_types::PAGE_VIEW)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let mut domains: Vec<DomainPages> = domain_ids
+            .iter()
+            .map(|&domain_id| DomainPages {
+                domain_id,
+                landing_pages: Vec::new(),
+                exit_pages: Vec::new(),
+            })
+            .collect();
+        for row in rows {
+            let Some(domain) = domains.iter_mut().find(|d| d.domain_id == row.domain_id) else {
+                continue;
+            };
+            let pages = if row.landing {
+                &mut domain.landing_pages
+            } else {
+                &mut domain.exit_pages
+            };
+            if pages.len() < TOP_ENTRY_EXIT_PAGES {
+                pages.push(PageSessions {
+                    path: row.path,
+                    sessions: row.sessions,
+                });
+            }
+        }
+
+        Ok(Json(PagesResponse { domains }))
+    })
+    .await
+}
+
+/// Custom event counts per accessible domain and event name
+pub async fn get_custom_event_stats(
+    Extension(user): Extension<UserContext>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<CustomEventsResponse>, StatusCode> {
+    PerformanceSpan::monitor("get_custom_event_stats", async {
+        let (start_date, end_date) = parse_date_range(&query, state.clock.now());
+        let domain_ids = get_user_accessible_domains(&user, &query, &state.db).await?;
+
+        let events = sqlx::query_as::<_, CustomEventCount>(
+            r#"
+            SELECT domain_id,
+                   metadata->>'name' AS name,
+                   COUNT(*) AS count,
+                   COUNT(DISTINCT ip_address) AS unique_visitors
+            FROM analytics_events
+            WHERE domain_id = ANY($1) AND event_type = $4
+              AND created_at BETWEEN $2 AND $3
+              AND metadata->>'name' IS NOT NULL
+            GROUP BY domain_id, metadata->>'name'
+            ORDER BY domain_id, count DESC, name
+            "#,
+        )
+        .bind(&domain_ids)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(event_types::CUSTOM)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        Ok(Json(CustomEventsResponse { events }))
+    })
+    .await
+}
+
 pub async fn get_post_analytics(
     Extension(user): Extension<UserContext>,
     State(state): State<Arc<AppState>>,
     Query(query): Query<AnalyticsQuery>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    let (start_date, end_date) = parse_date_range(&query);
+    let (start_date, end_date) = parse_date_range(&query, state.clock.now());
 
     // Get domain IDs user has access to
     let domain_ids = get_user_accessible_domains(&user, &query, &state.db).await?;
@@ -937,7 +2168,7 @@ pub async fn get_post_analytics(
                 "category": row.category,
                 "views": row.views.unwrap_or(0),
                 "unique_views": row.unique_views.unwrap_or(0),
-                "avg_days_to_view": row.avg_days_to_view.map(|d| d.to_string().parse::<f64>().unwrap_or(0.0)).unwrap_or(0.0)
+                "avg_days_to_view": row.avg_days_to_view.map(|d| decimal_to_f64(&d)).unwrap_or(0.0)
             })
         }).collect::<Vec<_>>()
     })))
@@ -948,20 +2179,23 @@ pub async fn get_search_analytics(
     State(state): State<Arc<AppState>>,
     Query(query): Query<AnalyticsQuery>,
 ) -> Result<Json<SearchAnalyticsResponse>, StatusCode> {
-    let (start_date, end_date) = parse_date_range(&query);
+    let (start_date, end_date) = parse_date_range(&query, state.clock.now());
 
     // Get domain IDs user has access to
     let domain_ids = get_user_accessible_domains(&user, &query, &state.db).await?;
+    let tz = bucket_timezone(&query, &domain_ids, &state.db).await?;
 
-    // Popular search terms
+    // Popular search terms; a query counts as found if any search for it had results
     let popular_terms = sqlx::query!(
         r#"
-        SELECT path as query,
-               COUNT(*) as count
+        SELECT metadata->>'query' as query,
+               COUNT(*) as count,
+               BOOL_OR((metadata->>'results_count')::int > 0) as results_found
         FROM analytics_events
         WHERE domain_id = ANY($1) AND event_type = 'search'
         AND created_at BETWEEN $2 AND $3
-        GROUP BY path
+        AND metadata ? 'query'
+        GROUP BY metadata->>'query'
         ORDER BY count DESC
         LIMIT 20
         "#,
@@ -976,24 +2210,58 @@ pub async fn get_search_analytics(
     .map(|row| SearchTerm {
         query: row.query.unwrap_or_default(),
         count: row.count.unwrap_or(0),
-        results_found: true, // TODO: Track actual results
+        // Searches logged before results were tracked count as found
+        results_found: row.results_found.unwrap_or(true),
     })
     .collect();
 
+    // Queries that returned nothing
+    let no_results_queries = sqlx::query!(
+        r#"
+        SELECT metadata->>'query' as query,
+               COUNT(*) as count
+        FROM analytics_events
+        WHERE domain_id = ANY($1) AND event_type = 'search'
+        AND created_at BETWEEN $2 AND $3
+        AND metadata ? 'query'
+        AND (metadata->>'results_count')::int = 0
+        GROUP BY metadata->>'query'
+        ORDER BY count DESC
+        LIMIT 10
+        "#,
+        &domain_ids,
+        start_date,
+        end_date
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .into_iter()
+    .map(|row| SearchTerm {
+        query: row.query.unwrap_or_default(),
+        count: row.count.unwrap_or(0),
+        results_found: false,
+    })
+    .collect();
+
+    let no_results_rate =
+        search_no_results_rate(&state.db, &domain_ids, start_date, end_date).await?;
+
     // Search volume trend
     let search_volume_trend = sqlx::query!(
         r#"
-        SELECT DATE(created_at) as date,
+        SELECT (created_at AT TIME ZONE $4)::date as date,
                COUNT(*) as searches
         FROM analytics_events
         WHERE domain_id = ANY($1) AND event_type = 'search'
         AND created_at BETWEEN $2 AND $3
-        GROUP BY DATE(created_at)
+        GROUP BY 1
         ORDER BY date
         "#,
         &domain_ids,
         start_date,
-        end_date
+        end_date,
+        tz
     )
     .fetch_all(&state.db)
     .await
@@ -1008,7 +2276,8 @@ pub async fn get_search_analytics(
     let response = SearchAnalyticsResponse {
         popular_terms,
         search_volume_trend,
-        no_results_queries: vec![], // TODO: Implement
+        no_results_queries,
+        no_results_rate,
     };
 
     Ok(Json(response))
@@ -1019,7 +2288,7 @@ pub async fn get_referrer_stats(
     State(state): State<Arc<AppState>>,
     Query(query): Query<AnalyticsQuery>,
 ) -> Result<Json<ReferrerResponse>, StatusCode> {
-    let (start_date, end_date) = parse_date_range(&query);
+    let (start_date, end_date) = parse_date_range(&query, state.clock.now());
 
     // Get domain IDs user has access to
     let domain_ids = get_user_accessible_domains(&user, &query, &state.db).await?;
@@ -1050,60 +2319,16 @@ pub async fn get_referrer_stats(
     })
     .collect();
 
-    // Calculate real referrer type breakdown from analytics data
-    let referrer_type_stats = sqlx::query!(
-        r#"
-        SELECT 
-            COALESCE(referrer, '') as referrer,
-            COUNT(*) as visits
-        FROM analytics_events
-        WHERE domain_id = ANY($1) AND created_at BETWEEN $2 AND $3
-        GROUP BY referrer
-        "#,
-        &domain_ids,
+    let referrer_types = referrers::referrer_breakdown(
+        &state.db,
+        Some(domain_ids.as_slice()),
         start_date,
-        end_date
+        end_date,
+        &ReferrerConfig::default(),
     )
-    .fetch_all(&state.db)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let mut direct = 0i64;
-    let mut search_engines = 0i64;
-    let mut social_media = 0i64;
-    let mut other_websites = 0i64;
-
-    for stat in referrer_type_stats {
-        let referrer = stat.referrer.unwrap_or_default();
-        let visits = stat.visits.unwrap_or(0);
-
-        if referrer.is_empty() || referrer == "Direct" {
-            direct += visits;
-        } else if referrer.contains("google.com")
-            || referrer.contains("bing.com")
-            || referrer.contains("duckduckgo.com")
-            || referrer.contains("yahoo.com")
-        {
-            search_engines += visits;
-        } else if referrer.contains("facebook.com")
-            || referrer.contains("twitter.com")
-            || referrer.contains("linkedin.com")
-            || referrer.contains("instagram.com")
-            || referrer.contains("tiktok.com")
-        {
-            social_media += visits;
-        } else {
-            other_websites += visits;
-        }
-    }
-
-    let referrer_types = ReferrerTypeBreakdown {
-        direct,
-        search_engines,
-        social_media,
-        other_websites,
-    };
-
     let response = ReferrerResponse {
         top_referrers,
         referrer_types,
@@ -1120,8 +2345,9 @@ pub async fn get_realtime_stats(
     // Get domain IDs user has access to
     let domain_ids = get_user_accessible_domains(&user, &query, &state.db).await?;
 
-    let one_hour_ago = Utc::now() - Duration::hours(1);
-    let five_minutes_ago = Utc::now() - Duration::minutes(5);
+    let now = state.clock.now();
+    let one_hour_ago = now - Duration::hours(1);
+    let five_minutes_ago = now - Duration::minutes(5);
 
     // Active visitors (last 5 minutes)
     let active_visitors = sqlx::query!(
@@ -1140,7 +2366,7 @@ pub async fn get_realtime_stats(
     // Page views in last hour
     let page_views_last_hour = sqlx::query!(
         r#"
-        SELECT COUNT(*) as views
+        SELECT ROUND(COALESCE(SUM(sample_weight), 0))::BIGINT as views
         FROM analytics_events
         WHERE domain_id = ANY($1) AND event_type = 'page_view' AND created_at > $2
         "#,
@@ -1212,16 +2438,125 @@ pub async fn get_realtime_stats(
     Ok(Json(response))
 }
 
+/// Push live page view and session deltas for the user's domains over a
+/// WebSocket. Clients that fall behind get periodic snapshots instead.
+pub async fn live_updates(
+    ws: WebSocketUpgrade,
+    Extension(user): Extension<UserContext>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Response, StatusCode> {
+    let domain_ids = get_user_accessible_domains(&user, &query, &state.db).await?;
+
+    // Subscribe before upgrading so nothing published during the handshake is lost
+    let events = live::subscribe();
+    Ok(ws.on_upgrade(move |socket| stream_live_updates(socket, state, domain_ids, events)))
+}
+
+async fn send_json<T: Serialize>(socket: &mut WebSocket, value: &T) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(value).map_err(axum::Error::new)?;
+    socket.send(Message::Text(text.into())).await
+}
+
+async fn stream_live_updates(
+    mut socket: WebSocket,
+    state: Arc<AppState>,
+    domain_ids: Vec<i32>,
+    mut events: broadcast::Receiver<LiveEvent>,
+) {
+    let mut snapshots = tokio::time::interval(live::SNAPSHOT_INTERVAL);
+    snapshots.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // Set once the client has missed deltas; cleared by the next snapshot
+    let mut lagging = false;
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) if lagging || !domain_ids.contains(&event.domain_id()) => {}
+                Ok(event) => {
+                    if send_json(&mut socket, &event).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::debug!(
+                        skipped,
+                        "Live analytics client lagging, switching to snapshots"
+                    );
+                    lagging = true;
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = snapshots.tick(), if lagging => {
+                let snapshot = match live::snapshot(&state.db, &domain_ids).await {
+                    Ok(snapshot) => snapshot,
+                    Err(e) => {
+                        tracing::error!(error = %e, "Failed to build live analytics snapshot");
+                        break;
+                    }
+                };
+                if send_json(&mut socket, &snapshot).await.is_err() {
+                    break;
+                }
+                lagging = false;
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    /// `csv` (default) or `parquet`
+    format: Option<String>,
+    /// Comma-separated columns to export, e.g. `domain,event_type,created_at`
+    fields: Option<String>,
+}
+
 pub async fn export_data(
     Extension(user): Extension<UserContext>,
     State(state): State<Arc<AppState>>,
     Query(query): Query<AnalyticsQuery>,
-) -> Result<String, StatusCode> {
-    let (start_date, end_date) = parse_date_range(&query);
+    Query(export): Query<ExportQuery>,
+) -> Result<Response, StatusCode> {
+    let (start_date, end_date) = parse_date_range(&query, state.clock.now());
+
+    let mut fields = parquet_export::parse_fields(export.fields.as_deref()).map_err(|field| {
+        tracing::debug!(field, "Unknown export field");
+        StatusCode::BAD_REQUEST
+    })?;
 
     // Get domain IDs user has access to
     let domain_ids = get_user_accessible_domains(&user, &query, &state.db).await?;
 
+    // A column any exported domain keeps private is left out for all of them
+    let theme_configs: Vec<serde_json::Value> =
+        sqlx::query_scalar("SELECT theme_config FROM domains WHERE id = ANY($1)")
+            .bind(&domain_ids)
+            .fetch_all(&state.db)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    for theme_config in &theme_configs {
+        let excluded = parquet_export::excluded_fields(theme_config);
+        fields.retain(|field| !excluded.contains(field));
+    }
+    if fields.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // Read-only, so the transaction only scopes the longer export timeout
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    query_timeout::extend_for_export(&mut tx)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     let events = sqlx::query!(
         r#"
         SELECT ae.event_type, ae.path, ae.user_agent, ae.referrer, ae.created_at,
@@ -1236,37 +2571,288 @@ pub async fn export_data(
         start_date,
         end_date
     )
-    .fetch_all(&state.db)
+    .fetch_all(&mut *tx)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .map_err(|e| {
+        if query_timeout::is_timeout(&e) {
+            StatusCode::GATEWAY_TIMEOUT
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    })?;
+
+    let rows = events.into_iter().map(|event| ExportRow {
+        domain: event.domain_name,
+        event_type: event.event_type,
+        path: event.path,
+        ip_address: event.ip_address,
+        user_agent: event.user_agent,
+        referrer: event.referrer,
+        created_at: event.created_at.unwrap_or_else(Utc::now),
+    });
+
+    match export.format.as_deref().unwrap_or("csv") {
+        "csv" => Ok(export_csv(rows, &fields).into_response()),
+        "parquet" => {
+            let rows = rows.collect::<Vec<_>>();
+            let parquet = parquet_export::write_events(&rows, &fields).map_err(|e| {
+                tracing::error!(error = %e, "Failed to write Parquet export");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+            Ok((
+                [
+                    (header::CONTENT_TYPE, parquet_export::CONTENT_TYPE),
+                    (
+                        header::CONTENT_DISPOSITION,
+                        "attachment; filename=\"analytics.parquet\"",
+                    ),
+                ],
+                parquet,
+            )
+                .into_response())
+        }
+        _ => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
+fn export_csv(rows: impl Iterator<Item = ExportRow>, fields: &[ExportField]) -> String {
+    // Generate CSV with the selected columns
+    let headers: Vec<&str> = fields.iter().map(|field| field.header()).collect();
+    let mut csv = headers.join(",");
+    csv.push('\n');
 
-    // Generate CSV with domain information
-    let mut csv = "Domain,Event Type,Path,IP Address,User Agent,Referrer,Timestamp\n".to_string();
-
-    for event in events {
-        csv.push_str(&format!(
-            "{},{},{},{},{},{},{}\n",
-            event.domain_name.replace(",", ";"),
-            event.event_type,
-            event.path.unwrap_or_default().replace(",", ";"),
-            event.ip_address.unwrap_or_default(),
-            event.user_agent.unwrap_or_default().replace(",", ";"),
-            event.referrer.unwrap_or_default().replace(",", ";"),
-            event
-                .created_at
-                .unwrap_or_else(Utc::now)
-                .format("%Y-%m-%d %H:%M:%S")
-        ));
+    for event in rows {
+        let values: Vec<String> = fields
+            .iter()
+            .map(|field| match field {
+                ExportField::Domain => event.domain.replace(",", ";"),
+                ExportField::EventType => event.event_type.clone(),
+                ExportField::Path => event.path.as_deref().unwrap_or_default().replace(",", ";"),
+                ExportField::IpAddress => event.ip_address.clone().unwrap_or_default(),
+                ExportField::UserAgent => {
+                    let user_agent = event.user_agent.as_deref().unwrap_or_default();
+                    user_agent.replace(",", ";")
+                }
+                ExportField::Referrer => {
+                    let referrer = event.referrer.as_deref().unwrap_or_default();
+                    referrer.replace(",", ";")
+                }
+                ExportField::CreatedAt => event.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            })
+            .collect();
+        csv.push_str(&values.join(","));
+        csv.push('\n');
     }
 
-    Ok(csv)
+    csv
 }
 
 // Behavior tracking endpoints
+// Event inserts shared by the single-event routes and batch ingestion
+
+fn to_decimal(value: f64) -> BigDecimal {
+    BigDecimal::from_str(&value.to_string()).unwrap_or_default()
+}
+
+/// Nearest `f64` to a `NUMERIC` value; 0 if it is out of range
+fn decimal_to_f64(value: &BigDecimal) -> f64 {
+    value.to_f64().filter(|v| v.is_finite()).unwrap_or(0.0)
+}
+
+async fn insert_behavior_event<'e, E: sqlx::PgExecutor<'e>>(
+    executor: E,
+    event: &UserBehaviorEvent,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO behavior_events (
+            session_id, event_type, element, x, y, scroll_depth
+        ) VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        event.session_id,
+        event.event_type,
+        event.element,
+        event.x.map(to_decimal),
+        event.y.map(to_decimal),
+        event.scroll_depth.map(to_decimal)
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+async fn insert_search_event<'e, E: sqlx::PgExecutor<'e>>(
+    executor: E,
+    event: &SearchEvent,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO search_events (
+            session_id, query, results_count, no_results
+        ) VALUES ($1, $2, $3, $4)
+        "#,
+        event.session_id,
+        event.query,
+        event.results_count as i32,
+        event.no_results.unwrap_or(event.results_count == 0)
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+async fn insert_search_click_event<'e, E: sqlx::PgExecutor<'e>>(
+    executor: E,
+    event: &SearchClickEvent,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO search_click_events (
+            session_id, query, clicked_result, position_clicked
+        ) VALUES ($1, $2, $3, $4)
+        "#,
+        event.session_id,
+        event.query,
+        event.clicked_result,
+        event.position_clicked
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+async fn insert_content_metrics<'e, E: sqlx::PgExecutor<'e>>(
+    executor: E,
+    event: &ContentMetricsEvent,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO content_metrics (
+            session_id, content_id, content_type, title, reading_time, 
+            scroll_percentage, time_on_page, bounce, engagement_events
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+        event.session_id,
+        event.content_id,
+        event.content_type,
+        event.title,
+        event.reading_time as i32,
+        to_decimal(event.scroll_percentage),
+        event.time_on_page as i32,
+        event.bounce,
+        event.engagement_events
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Largest batch accepted by `POST /analytics/events/batch`
+const MAX_BATCH_EVENTS: usize = 500;
+
+// Ingest many tracking events in one request and one transaction.
+// Failing events are reported by index; with `atomic=true` any failure
+// rolls back the whole batch.
+pub async fn track_events_batch(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<BatchQuery>,
+    Json(raw_events): Json<Vec<serde_json::Value>>,
+) -> Result<(StatusCode, Json<BatchIngestResponse>), StatusCode> {
+    if raw_events.len() > MAX_BATCH_EVENTS {
+        return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    // Parse and validate up front so malformed events never reach the database
+    let mut failed = Vec::new();
+    let mut events = Vec::with_capacity(raw_events.len());
+    for (index, raw) in raw_events.into_iter().enumerate() {
+        match serde_json::from_value::<BatchEvent>(raw)
+            .map_err(|e| e.to_string())
+            .and_then(|event| event.validate().map(|_| event))
+        {
+            Ok(event) => events.push((index, event)),
+            Err(error) => failed.push(BatchEventError { index, error }),
+        }
+    }
+
+    let rejected = |failed: Vec<BatchEventError>| {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(BatchIngestResponse {
+                accepted: 0,
+                failed,
+            }),
+        )
+    };
+
+    if params.atomic && !failed.is_empty() {
+        return Ok(rejected(failed));
+    }
+
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut accepted = 0;
+
+    for (index, event) in &events {
+        let result = if params.atomic {
+            event.insert(&mut tx).await
+        } else {
+            // A savepoint per event keeps one bad row from aborting the rest
+            let mut savepoint = sqlx::Acquire::begin(&mut *tx)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            match event.insert(&mut savepoint).await {
+                Ok(()) => savepoint.commit().await,
+                Err(e) => {
+                    let _ = savepoint.rollback().await;
+                    Err(e)
+                }
+            }
+        };
+
+        match result {
+            Ok(()) => accepted += 1,
+            Err(e) => {
+                tracing::warn!(error = %e, index, "Failed to store batched analytics event");
+                failed.push(BatchEventError {
+                    index: *index,
+                    error: "failed to store event".to_string(),
+                });
+                if params.atomic {
+                    let _ = tx.rollback().await;
+                    return Ok(rejected(failed));
+                }
+            }
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    failed.sort_by_key(|f| f.index);
+    crate::telemetry::record_analytics_event("batch_events");
+    tracing::info!(
+        accepted,
+        failed = failed.len(),
+        "Analytics event batch stored"
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(BatchIngestResponse { accepted, failed }),
+    ))
+}
+
 pub async fn track_behavior_event(
     State(state): State<Arc<AppState>>,
     Json(event): Json<UserBehaviorEvent>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<StatusCode, Response> {
+    reject_invalid_event(&event)?;
+
     PerformanceSpan::monitor("track_behavior_event", async {
         let span = tracing::info_span!(
             "track_behavior_event",
@@ -1278,27 +2864,7 @@ pub async fn track_behavior_event(
         let _guard = span.enter();
 
         // Store behavior event in database
-        let result = sqlx::query!(
-            r#"
-        INSERT INTO behavior_events (
-            session_id, event_type, element, x, y, scroll_depth
-        ) VALUES ($1, $2, $3, $4, $5, $6)
-        "#,
-            event.session_id,
-            event.event_type,
-            event.element,
-            event
-                .x
-                .map(|v| BigDecimal::from_str(&v.to_string()).unwrap_or_default()),
-            event
-                .y
-                .map(|v| BigDecimal::from_str(&v.to_string()).unwrap_or_default()),
-            event
-                .scroll_depth
-                .map(|v| BigDecimal::from_str(&v.to_string()).unwrap_or_default())
-        )
-        .execute(&state.db)
-        .await;
+        let result = insert_behavior_event(&state.db, &event).await;
 
         match result {
             Ok(_) => {
@@ -1308,7 +2874,7 @@ pub async fn track_behavior_event(
             }
             Err(e) => {
                 tracing::error!(error = %e, "Failed to store behavior event");
-                Err(StatusCode::INTERNAL_SERVER_ERROR)
+                Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
             }
         }
     })
@@ -1318,22 +2884,12 @@ pub async fn track_behavior_event(
 pub async fn track_search_event(
     State(state): State<Arc<AppState>>,
     Json(event): Json<SearchEvent>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<StatusCode, Response> {
+    reject_invalid_event(&event)?;
+
     AnalyticsSpan::track_search("track_search_event", async {
         // Store search event in database
-        let result = sqlx::query!(
-            r#"
-        INSERT INTO search_events (
-            session_id, query, results_count, no_results
-        ) VALUES ($1, $2, $3, $4)
-        "#,
-            event.session_id,
-            event.query,
-            event.results_count as i32,
-            event.no_results.unwrap_or_else(|| event.results_count == 0)
-        )
-        .execute(&state.db)
-        .await;
+        let result = insert_search_event(&state.db, &event).await;
 
         match result {
             Ok(_) => {
@@ -1347,7 +2903,7 @@ pub async fn track_search_event(
             }
             Err(e) => {
                 tracing::error!(error = %e, "Failed to store search event");
-                Err(StatusCode::INTERNAL_SERVER_ERROR)
+                Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
             }
         }
     })
@@ -1357,21 +2913,11 @@ pub async fn track_search_event(
 pub async fn track_search_click_event(
     State(state): State<Arc<AppState>>,
     Json(event): Json<SearchClickEvent>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<StatusCode, Response> {
+    reject_invalid_event(&event)?;
+
     // Store search click event in database
-    let result = sqlx::query!(
-        r#"
-        INSERT INTO search_click_events (
-            session_id, query, clicked_result, position_clicked
-        ) VALUES ($1, $2, $3, $4)
-        "#,
-        event.session_id,
-        event.query,
-        event.clicked_result,
-        event.position_clicked
-    )
-    .execute(&state.db)
-    .await;
+    let result = insert_search_click_event(&state.db, &event).await;
 
     match result {
         Ok(_) => {
@@ -1386,7 +2932,7 @@ pub async fn track_search_click_event(
         }
         Err(e) => {
             tracing::error!(error = %e, "Failed to store search click event");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
         }
     }
 }
@@ -1394,27 +2940,11 @@ pub async fn track_search_click_event(
 pub async fn track_content_metrics(
     State(state): State<Arc<AppState>>,
     Json(event): Json<ContentMetricsEvent>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<StatusCode, Response> {
+    reject_invalid_event(&event)?;
+
     // Store content metrics in database
-    let result = sqlx::query!(
-        r#"
-        INSERT INTO content_metrics (
-            session_id, content_id, content_type, title, reading_time, 
-            scroll_percentage, time_on_page, bounce, engagement_events
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-        "#,
-        event.session_id,
-        event.content_id,
-        event.content_type,
-        event.title,
-        event.reading_time as i32,
-        BigDecimal::from_str(&event.scroll_percentage.to_string()).unwrap_or_default(),
-        event.time_on_page as i32,
-        event.bounce,
-        event.engagement_events
-    )
-    .execute(&state.db)
-    .await;
+    let result = insert_content_metrics(&state.db, &event).await;
 
     match result {
         Ok(_) => {
@@ -1432,7 +2962,135 @@ pub async fn track_content_metrics(
         }
         Err(e) => {
             tracing::error!(error = %e, "Failed to store content metrics");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+/// Store a custom event; `422` unless its name is registered for the domain
+pub async fn track_custom_event(
+    State(state): State<Arc<AppState>>,
+    Extension(domain): Extension<DomainContext>,
+    analytics: Option<Extension<AnalyticsContext>>,
+    Json(event): Json<CustomEvent>,
+) -> Result<StatusCode, Response> {
+    reject_invalid_event(&event)?;
+    if !event_types::is_registered(&domain, &event.name) {
+        let mut errors = ValidationErrors::new();
+        add_event_error(&mut errors, "name", "is not a registered custom event");
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(ValidationErrorResponse::from_validation_errors(errors)),
+        )
+            .into_response());
+    }
+
+    let analytics = analytics.map(|Extension(analytics)| analytics);
+    let ip_address: Option<std::net::IpAddr> = analytics
+        .as_ref()
+        .and_then(|analytics| analytics.ip_address.parse().ok());
+
+    let result = state
+        .events
+        .record(AnalyticsEvent {
+            session_id: event.session_id,
+            path: event.path.clone(),
+            user_agent: analytics.as_ref().map(|a| a.user_agent.clone()),
+            ip_address,
+            referrer: analytics.as_ref().and_then(|a| a.referrer.clone()),
+            metadata: serde_json::json!({
+                "name": event.name,
+                "properties": event.properties,
+            }),
+            ..AnalyticsEvent::new(domain.id, event_types::CUSTOM, state.clock.now())
+        })
+        .await;
+
+    match result {
+        Ok(_) => {
+            crate::telemetry::record_analytics_event("custom_event");
+            Ok(StatusCode::OK)
+        }
+        Err(e) => {
+            tracing::error!(error = %e, name = %event.name, "Failed to store custom event");
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn test_decimal_to_f64() {
+        assert_eq!(decimal_to_f64(&decimal("0")), 0.0);
+        assert_eq!(decimal_to_f64(&decimal("1.5")), 1.5);
+        assert_eq!(decimal_to_f64(&decimal("-42.25")), -42.25);
+        assert_eq!(decimal_to_f64(&decimal("3.0000000000000000")), 3.0);
+        // AVG() over integers yields 20 significant digits
+        assert!((decimal_to_f64(&decimal("0.33333333333333333333")) - 1.0 / 3.0).abs() < 1e-15);
+        assert_eq!(decimal_to_f64(&decimal("123456789012.75")), 123456789012.75);
+        assert_eq!(decimal_to_f64(&decimal("1e400")), 0.0);
+    }
+
+    fn behavior(scroll_depth: Option<f64>) -> UserBehaviorEvent {
+        UserBehaviorEvent {
+            event_type: "scroll".to_string(),
+            element: None,
+            x: None,
+            y: None,
+            scroll_depth,
+            timestamp: Utc::now().to_rfc3339(),
+            session_id: Uuid::new_v4(),
+        }
+    }
+
+    fn search(query: &str, results_count: i64) -> SearchEvent {
+        SearchEvent {
+            query: query.to_string(),
+            results_count,
+            no_results: None,
+            timestamp: Utc::now().to_rfc3339(),
+            session_id: Uuid::new_v4(),
         }
     }
+
+    #[test]
+    fn test_behavior_scroll_depth_range() {
+        assert!(behavior(None).validate().is_ok());
+        assert!(behavior(Some(0.0)).validate().is_ok());
+        assert!(behavior(Some(100.0)).validate().is_ok());
+
+        let errors = behavior(Some(140.0)).validate().unwrap_err();
+        assert!(errors.field_errors().contains_key("scroll_depth"));
+        assert!(behavior(Some(-1.0)).validate().is_err());
+    }
+
+    #[test]
+    fn test_search_event_requires_query_and_count() {
+        assert!(search("rust", 0).validate().is_ok());
+
+        let errors = search("  ", -1).validate().unwrap_err();
+        assert_eq!(
+            describe_event_errors(&errors),
+            "query is required; results_count must be between 0 and 2147483647"
+        );
+        // Would wrap when stored in the INTEGER column
+        assert!(search("rust", i64::from(i32::MAX) + 1).validate().is_err());
+    }
+
+    #[test]
+    fn test_fractional_reading_time_rounds() {
+        // Reading time used to parse straight to i64, which failed on any fraction
+        assert_eq!(
+            decimal_to_f64(&decimal("94.6000000000000000")).round() as i64,
+            95
+        );
+        assert_eq!(decimal_to_f64(&decimal("12.4")).round() as i64, 12);
+    }
 }
@@ -1,6 +1,11 @@
+use crate::services::audit::{self, AuditEntry};
+use crate::services::clock::{Clock, SystemClock};
+use crate::services::credentials;
+use crate::services::password_policy;
 use crate::utils::{ErrorSpan, PerformanceSpan};
 use crate::validation::extractors::ValidatedJson;
-use crate::{AppState, DomainPermission};
+use crate::validation::rules::password_strength_errors;
+use crate::{AppState, DomainPermission, UserContext, load_domain_permissions};
 use axum::{
     Router,
     extract::State,
@@ -8,12 +13,15 @@ use axum::{
     response::Json,
     routing::{get, post},
 };
-use bcrypt::verify;
-use chrono::{Duration, Utc};
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
-use serde::{Deserialize, Serialize};
-use std::{env, sync::Arc};
-use validator::Validate;
+use bcrypt::{DEFAULT_COST, hash, verify};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode,
+    errors::ErrorKind,
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{collections::HashMap, env, fs, path::Path, sync::Arc};
+use validator::{Validate, ValidationErrors};
 
 // JWT Claims
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,11 +31,436 @@ pub struct Claims {
     pub role: String, // user role
     pub exp: usize,   // expiry
     pub iat: usize,   // issued at
+    pub iss: String,  // issuer
+    pub aud: String,  // audience
+    /// Platform admin acting as `user_id`, set only on impersonation tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator_id: Option<i32>,
+}
+
+/// Claims of a reader's member token. Signed like access tokens but for
+/// [`JwtConfig::member_audience`], so neither kind passes for the other.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MemberClaims {
+    pub sub: String,    // member email
+    pub member_id: i32, // member id
+    pub domain_id: i32, // the only domain the token reads
+    pub exp: usize,
+    pub iat: usize,
+    pub iss: String,
+    pub aud: String,
+}
+
+/// Missing or unusable JWT settings, reported once at startup
+#[derive(Debug)]
+pub struct JwtConfigError(String);
+
+impl std::fmt::Display for JwtConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for JwtConfigError {}
+
+fn required_env(name: &str, purpose: &str) -> Result<String, JwtConfigError> {
+    env::var(name).map_err(|_| JwtConfigError(format!("{name} must be set {purpose}")))
+}
+
+/// RS256 keys: one private key for signing, every still-published public key for
+/// verification. Retired keys stay in `public_keys` until their tokens expire.
+#[derive(Clone)]
+pub struct RsaKeys {
+    pub current_kid: String,
+    pub private_key: EncodingKey,
+    pub public_keys: HashMap<String, DecodingKey>,
+}
+
+impl std::fmt::Debug for RsaKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RsaKeys")
+            .field("current_kid", &self.current_kid)
+            .field("public_kids", &self.public_keys.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl RsaKeys {
+    /// Load keys from PEM data; `public_keys` maps each `kid` to its public key PEM
+    pub fn from_pem(
+        current_kid: &str,
+        private_key_pem: &[u8],
+        public_keys: &[(String, Vec<u8>)],
+    ) -> Result<Self, jsonwebtoken::errors::Error> {
+        let public_keys = public_keys
+            .iter()
+            .map(|(kid, pem)| Ok((kid.clone(), DecodingKey::from_rsa_pem(pem)?)))
+            .collect::<Result<HashMap<_, _>, jsonwebtoken::errors::Error>>()?;
+
+        Ok(Self {
+            current_kid: current_kid.to_string(),
+            private_key: EncodingKey::from_rsa_pem(private_key_pem)?,
+            public_keys,
+        })
+    }
+
+    /// Read `JWT_RSA_PRIVATE_KEY_PATH`, `JWT_RSA_KID` and `JWT_RSA_PUBLIC_KEYS_DIR`
+    /// (a directory of `<kid>.pem` files). Returns `None` when RS256 is not configured.
+    pub fn from_env() -> Result<Option<Self>, JwtConfigError> {
+        let Ok(private_key_path) = env::var("JWT_RSA_PRIVATE_KEY_PATH") else {
+            return Ok(None);
+        };
+        let current_kid = required_env("JWT_RSA_KID", "for RS256")?;
+        let public_keys_dir = required_env("JWT_RSA_PUBLIC_KEYS_DIR", "for RS256")?;
+
+        let private_key_pem = fs::read(&private_key_path).map_err(|e| {
+            JwtConfigError(format!(
+                "Failed to read JWT private key {private_key_path}: {e}"
+            ))
+        })?;
+        let public_keys = read_public_keys(Path::new(&public_keys_dir))?;
+
+        let keys = Self::from_pem(&current_kid, &private_key_pem, &public_keys)
+            .map_err(|e| JwtConfigError(format!("Invalid RS256 key material: {e}")))?;
+        if !keys.public_keys.contains_key(&current_kid) {
+            return Err(JwtConfigError(format!(
+                "Public key for current kid '{current_kid}' must be published"
+            )));
+        }
+        Ok(Some(keys))
+    }
+}
+
+fn read_public_keys(dir: &Path) -> Result<Vec<(String, Vec<u8>)>, JwtConfigError> {
+    let entries = fs::read_dir(dir).map_err(|e| {
+        JwtConfigError(format!(
+            "Failed to read JWT public keys directory {}: {e}",
+            dir.display()
+        ))
+    })?;
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pem"))
+        .filter_map(|path| {
+            let kid = path.file_stem()?.to_str()?.to_string();
+            Some((kid, fs::read(&path).ok()?))
+        })
+        .collect())
+}
+
+/// Clock drift tolerated on `exp` and `nbf` unless `JWT_LEEWAY_SECS` is set
+pub const DEFAULT_JWT_LEEWAY_SECS: u64 = 30;
+
+/// JWT signing and validation settings, shared by login and the auth middleware
+#[derive(Debug, Clone)]
+pub struct JwtConfig {
+    /// HS256 secret, used when `rsa` is not configured
+    pub secret: String,
+    pub access_ttl: Duration,
+    /// Lifetime of refresh tokens from `/auth/login` and `/auth/refresh`
+    pub refresh_ttl: Duration,
+    pub issuer: String,
+    pub audience: String,
+    /// Seconds of clock drift tolerated when checking `exp` and `nbf`
+    pub leeway: u64,
+    pub rsa: Option<Arc<RsaKeys>>,
+    /// Stamps `iat`/`exp` on new tokens and decides when tokens expire
+    pub clock: Arc<dyn Clock>,
+}
+
+impl JwtConfig {
+    /// Read from `JWT_SECRET`, `JWT_ACCESS_TTL_SECONDS`, `JWT_REFRESH_TTL_DAYS`, `JWT_ISSUER`,
+    /// `JWT_AUDIENCE` and `JWT_LEEWAY_SECS`, switching to RS256 when
+    /// `JWT_RSA_PRIVATE_KEY_PATH` is set. Key files are read and checked here,
+    /// so this runs once at startup; see [`AppState::jwt_config`].
+    pub fn from_env() -> Result<Self, JwtConfigError> {
+        let rsa = RsaKeys::from_env()?.map(Arc::new);
+        Ok(Self {
+            secret: if rsa.is_some() {
+                env::var("JWT_SECRET").unwrap_or_default()
+            } else {
+                required_env("JWT_SECRET", "in environment")?
+            },
+            access_ttl: env::var("JWT_ACCESS_TTL_SECONDS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::seconds)
+                .unwrap_or_else(|| Duration::hours(24)),
+            refresh_ttl: env::var("JWT_REFRESH_TTL_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&days| days > 0)
+                .map(Duration::days)
+                .unwrap_or_else(|| Duration::days(30)),
+            issuer: env::var("JWT_ISSUER").unwrap_or_else(|_| "multi-blog-api".to_string()),
+            audience: env::var("JWT_AUDIENCE").unwrap_or_else(|_| "multi-blog".to_string()),
+            leeway: env::var("JWT_LEEWAY_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_JWT_LEEWAY_SECS),
+            rsa,
+            clock: Arc::new(SystemClock),
+        })
+    }
+
+    /// The same settings, telling time by `clock`
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    fn algorithm(&self) -> Algorithm {
+        if self.rsa.is_some() {
+            Algorithm::RS256
+        } else {
+            Algorithm::HS256
+        }
+    }
+
+    /// Audience of member tokens, kept apart from the admin `audience`
+    pub fn member_audience(&self) -> String {
+        format!("{}-members", self.audience)
+    }
+
+    /// Audience of refresh tokens, which only `/auth/refresh` accepts
+    pub fn refresh_audience(&self) -> String {
+        format!("{}-refresh", self.audience)
+    }
+
+    fn validation(&self, audience: &str) -> Validation {
+        let mut validation = Validation::new(self.algorithm());
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[audience]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+        // `exp` and `nbf` are checked against `clock` in `check_times`
+        validation.validate_exp = false;
+        validation.validate_nbf = false;
+        validation
+    }
+
+    fn check_times(&self, times: &TimeClaims) -> Result<(), TokenError> {
+        let now = self.clock.now().timestamp();
+        let leeway = self.leeway as i64;
+        if times.exp < now - leeway {
+            return Err(TokenError::Expired);
+        }
+        if times.nbf.is_some_and(|nbf| nbf > now + leeway) {
+            return Err(TokenError::Malformed);
+        }
+        Ok(())
+    }
+}
+
+/// The claims of any token that decide when it is valid
+#[derive(Deserialize)]
+struct TimeClaims {
+    exp: i64,
+    #[serde(default)]
+    nbf: Option<i64>,
+}
+
+/// Why a token was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenError {
+    Expired,
+    Malformed,
+    InvalidSignature,
+    WrongIssuer,
+    WrongAudience,
+    UnknownKey,
+}
+
+impl std::fmt::Display for TokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            TokenError::Expired => "token has expired",
+            TokenError::Malformed => "token is malformed",
+            TokenError::InvalidSignature => "token signature is invalid",
+            TokenError::WrongIssuer => "token was issued by an unexpected issuer",
+            TokenError::WrongAudience => "token is not intended for this audience",
+            TokenError::UnknownKey => "token was signed with an unknown key",
+        };
+        f.write_str(reason)
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+impl From<jsonwebtoken::errors::Error> for TokenError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        match err.kind() {
+            ErrorKind::ExpiredSignature => TokenError::Expired,
+            ErrorKind::InvalidSignature | ErrorKind::InvalidAlgorithm => {
+                TokenError::InvalidSignature
+            }
+            ErrorKind::InvalidIssuer => TokenError::WrongIssuer,
+            ErrorKind::InvalidAudience => TokenError::WrongAudience,
+            _ => TokenError::Malformed,
+        }
+    }
+}
+
+impl TokenError {
+    /// Machine-readable code used in error responses
+    pub fn code(&self) -> &'static str {
+        match self {
+            TokenError::Expired => "token_expired",
+            _ => "invalid_token",
+        }
+    }
+}
+
+/// Mint an access token for a user
+pub fn create_access_token(
+    config: &JwtConfig,
+    email: &str,
+    user_id: i32,
+    role: &str,
+) -> Result<String, TokenError> {
+    let now = config.clock.now();
+    sign_claims(
+        config,
+        &Claims {
+            sub: email.to_string(),
+            user_id,
+            role: role.to_string(),
+            exp: (now + config.access_ttl).timestamp() as usize,
+            iat: now.timestamp() as usize,
+            iss: config.issuer.clone(),
+            aud: config.audience.clone(),
+            impersonator_id: None,
+        },
+    )
+}
+
+/// Mint a refresh token, traded for a new token pair at `/auth/refresh`
+pub fn create_refresh_token(
+    config: &JwtConfig,
+    email: &str,
+    user_id: i32,
+    role: &str,
+) -> Result<String, TokenError> {
+    let now = config.clock.now();
+    sign_claims(
+        config,
+        &Claims {
+            sub: email.to_string(),
+            user_id,
+            role: role.to_string(),
+            exp: (now + config.refresh_ttl).timestamp() as usize,
+            iat: now.timestamp() as usize,
+            iss: config.issuer.clone(),
+            aud: config.refresh_audience(),
+            impersonator_id: None,
+        },
+    )
+}
+
+/// Mint a short-lived token that acts as `user_id` on behalf of `impersonator_id`
+pub fn create_impersonation_token(
+    config: &JwtConfig,
+    email: &str,
+    user_id: i32,
+    role: &str,
+    impersonator_id: i32,
+    ttl: Duration,
+) -> Result<String, TokenError> {
+    let now = config.clock.now();
+    sign_claims(
+        config,
+        &Claims {
+            sub: email.to_string(),
+            user_id,
+            role: role.to_string(),
+            exp: (now + ttl).timestamp() as usize,
+            iat: now.timestamp() as usize,
+            iss: config.issuer.clone(),
+            aud: config.audience.clone(),
+            impersonator_id: Some(impersonator_id),
+        },
+    )
+}
+
+/// Mint a member token letting a reader into `domain_id`'s members-only posts
+pub fn create_member_token(
+    config: &JwtConfig,
+    email: &str,
+    member_id: i32,
+    domain_id: i32,
+    ttl: Duration,
+) -> Result<String, TokenError> {
+    let now = config.clock.now();
+    sign_claims(
+        config,
+        &MemberClaims {
+            sub: email.to_string(),
+            member_id,
+            domain_id,
+            exp: (now + ttl).timestamp() as usize,
+            iat: now.timestamp() as usize,
+            iss: config.issuer.clone(),
+            aud: config.member_audience(),
+        },
+    )
+}
+
+fn sign_claims<T: Serialize>(config: &JwtConfig, claims: &T) -> Result<String, TokenError> {
+    let result = match &config.rsa {
+        Some(rsa) => {
+            let mut header = Header::new(Algorithm::RS256);
+            header.kid = Some(rsa.current_kid.clone());
+            encode(&header, claims, &rsa.private_key)
+        }
+        None => encode(
+            &Header::new(Algorithm::HS256),
+            claims,
+            &EncodingKey::from_secret(config.secret.as_bytes()),
+        ),
+    };
+
+    result.map_err(|_| TokenError::Malformed)
+}
+
+/// Validate a token against an explicit config
+pub fn validate_jwt_token_with(token: &str, config: &JwtConfig) -> Result<Claims, TokenError> {
+    decode_claims(token, config, &config.audience)
 }
 
-// Get JWT secret from environment variable
-fn get_jwt_secret() -> String {
-    env::var("JWT_SECRET").expect("JWT_SECRET must be set in environment")
+/// Validate a refresh token against an explicit config
+pub fn validate_refresh_token_with(token: &str, config: &JwtConfig) -> Result<Claims, TokenError> {
+    decode_claims(token, config, &config.refresh_audience())
+}
+
+/// Validate a member token against an explicit config
+pub fn validate_member_token_with(
+    token: &str,
+    config: &JwtConfig,
+) -> Result<MemberClaims, TokenError> {
+    decode_claims(token, config, &config.member_audience())
+}
+
+fn decode_claims<T: DeserializeOwned>(
+    token: &str,
+    config: &JwtConfig,
+    audience: &str,
+) -> Result<T, TokenError> {
+    let token_data = match &config.rsa {
+        Some(rsa) => {
+            // Pick the verification key by the header's kid so rotated keys keep working
+            let kid = decode_header(token)?.kid.ok_or(TokenError::UnknownKey)?;
+            let key = rsa.public_keys.get(&kid).ok_or(TokenError::UnknownKey)?;
+            decode::<serde_json::Value>(token, key, &config.validation(audience))?
+        }
+        None => decode::<serde_json::Value>(
+            token,
+            &DecodingKey::from_secret(config.secret.as_bytes()),
+            &config.validation(audience),
+        )?,
+    };
+
+    let times = TimeClaims::deserialize(&token_data.claims).map_err(|_| TokenError::Malformed)?;
+    config.check_times(&times)?;
+    serde_json::from_value(token_data.claims).map_err(|_| TokenError::Malformed)
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
@@ -38,19 +471,70 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+/// Tokens and user returned by `/auth/login` and `/auth/refresh`
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoginResponse {
-    pub user: UserInfo,
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Same as `access_token`, for clients that read `token`
     pub token: String,
+    pub user: LoginUser,
 }
 
+/// The context `auth_middleware` builds for the user's requests
 #[derive(Debug, Serialize, Deserialize)]
-pub struct UserInfo {
-    pub id: i32,
-    pub email: String,
-    pub name: String,
-    pub role: String,
-    pub domain_permissions: Vec<DomainPermission>,
+pub struct LoginUser {
+    #[serde(flatten)]
+    pub context: UserContext,
+    /// Admin routes refuse this user until they call `/auth/change-password`
+    #[serde(default)]
+    pub must_change_password: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+/// A fresh access and refresh token pair for `user`
+fn session_tokens(
+    config: &JwtConfig,
+    user: UserContext,
+    must_change_password: bool,
+) -> Result<LoginResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token_error = |_: TokenError| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(
+                "token_error",
+                "Failed to generate token",
+            )),
+        )
+    };
+    let access_token =
+        create_access_token(config, &user.email, user.id, &user.role).map_err(token_error)?;
+    let refresh_token =
+        create_refresh_token(config, &user.email, user.id, &user.role).map_err(token_error)?;
+
+    Ok(LoginResponse {
+        token: access_token.clone(),
+        access_token,
+        refresh_token,
+        user: LoginUser {
+            context: user,
+            must_change_password,
+        },
+    })
+}
+
+fn permissions_error(_: sqlx::Error) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse::new(
+            "database_error",
+            "Failed to query permissions",
+        )),
+    )
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -60,6 +544,53 @@ pub struct VerifyResponse {
     pub name: String,
     pub role: String,
     pub domain_permissions: Vec<DomainPermission>,
+    #[serde(default)]
+    pub must_change_password: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+impl Validate for ChangePasswordRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        for error in password_strength_errors(&self.new_password) {
+            errors.add("new_password", error);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResetPasswordRequest {
+    /// Token issued by `POST /admin/users/{id}/reset-password`
+    pub token: String,
+    pub new_password: String,
+}
+
+impl Validate for ResetPasswordRequest {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        for error in password_strength_errors(&self.new_password) {
+            errors.add("new_password", error);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -86,7 +617,7 @@ pub async fn login(
         // Input is already validated by ValidatedJson extractor
         // Look up user in database
         let user = sqlx::query!(
-            "SELECT id, email, name, password_hash, role FROM users WHERE email = $1",
+            "SELECT id, email, name, password_hash, role, must_change_password FROM users WHERE email = $1 AND deleted_at IS NULL",
             payload.email
         )
         .fetch_optional(&state.db)
@@ -149,79 +680,102 @@ pub async fn login(
             ));
         }
 
-        // Get domain permissions for this user
-        let permissions_rows = sqlx::query!(
-            "SELECT domain_id, role FROM user_domain_permissions WHERE user_id = $1",
-            user.id
-        )
-        .fetch_all(&state.db)
-        .await
-        .map_err(|_| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "database_error",
-                    "Failed to query permissions",
-                )),
-            )
-        })?;
-
-        let domain_permissions = permissions_rows
-            .into_iter()
-            .map(|row| DomainPermission {
-                domain_id: row.domain_id.unwrap_or(0),
-                role: row.role,
-            })
-            .collect();
+        // Same permissions auth_middleware loads, so the client needs no second call
+        let domain_permissions = load_domain_permissions(&state.db, user.id)
+            .await
+            .map_err(permissions_error)?;
 
-        // Create JWT token
-        let now = Utc::now();
-        let exp = now + Duration::hours(24); // Token valid for 24 hours
-
-        let claims = Claims {
-            sub: user.email.clone(),
-            user_id: user.id,
-            role: user.role.clone().unwrap_or_default(),
-            exp: exp.timestamp() as usize,
-            iat: now.timestamp() as usize,
-        };
-
-        let token = encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(get_jwt_secret().as_bytes()),
-        )
-        .map_err(|_| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse::new(
-                    "token_error",
-                    "Failed to generate token",
-                )),
-            )
-        })?;
-
-        let user_info = UserInfo {
+        let context = UserContext {
             id: user.id,
             email: user.email,
             name: user.name,
             role: user.role.unwrap_or_default(),
             domain_permissions,
+            impersonator_id: None,
         };
 
-        Ok(Json(LoginResponse {
-            user: user_info,
-            token,
-        }))
+        session_tokens(&state.jwt_config(), context, user.must_change_password).map(Json)
     })
     .await
 }
 
-/// Verify token endpoint
-pub async fn verify_token(
+#[derive(sqlx::FromRow)]
+struct RefreshingUser {
+    id: i32,
+    email: String,
+    name: String,
+    role: Option<String>,
+    must_change_password: bool,
+    sessions_revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Trade a refresh token for a new access and refresh token pair. Refused
+/// once the user is deleted or their sessions are revoked.
+pub async fn refresh(
     State(state): State<Arc<AppState>>,
-    headers: axum::http::HeaderMap,
-) -> Result<Json<VerifyResponse>, (StatusCode, Json<ErrorResponse>)> {
+    Json(payload): Json<RefreshRequest>,
+) -> Result<Json<LoginResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let config = state.jwt_config();
+    let claims = validate_refresh_token_with(&payload.refresh_token, &config).map_err(|e| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new(e.code(), &e.to_string())),
+        )
+    })?;
+
+    let user = sqlx::query_as::<_, RefreshingUser>(
+        "SELECT id, email, name, role, must_change_password, sessions_revoked_at FROM users WHERE id = $1 AND email = $2 AND deleted_at IS NULL",
+    )
+    .bind(claims.user_id)
+    .bind(&claims.sub)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new("database_error", "Failed to query user")),
+        )
+    })?
+    .ok_or_else(|| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new(
+                "user_not_found",
+                "User no longer exists",
+            )),
+        )
+    })?;
+
+    if credentials::is_revoked(claims.iat, user.sessions_revoked_at) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new(
+                "token_revoked",
+                "Token has been revoked",
+            )),
+        ));
+    }
+
+    let domain_permissions = load_domain_permissions(&state.db, user.id)
+        .await
+        .map_err(permissions_error)?;
+    let context = UserContext {
+        id: user.id,
+        email: user.email,
+        name: user.name,
+        role: user.role.unwrap_or_default(),
+        domain_permissions,
+        impersonator_id: None,
+    };
+
+    session_tokens(&config, context, user.must_change_password).map(Json)
+}
+
+/// Validate the bearer token of a request to an `/auth` route
+fn bearer_claims(
+    headers: &axum::http::HeaderMap,
+    config: &JwtConfig,
+) -> Result<Claims, (StatusCode, Json<ErrorResponse>)> {
     let token = headers
         .get("authorization")
         .and_then(|v| v.to_str().ok())
@@ -241,26 +795,24 @@ pub async fn verify_token(
     };
 
     // Decode and validate JWT
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(get_jwt_secret().as_bytes()),
-        &Validation::default(),
-    )
-    .map_err(|_| {
+    validate_jwt_token_with(token, config).map_err(|e| {
         (
             StatusCode::UNAUTHORIZED,
-            Json(ErrorResponse::new(
-                "invalid_token",
-                "Token is invalid or expired",
-            )),
+            Json(ErrorResponse::new(e.code(), &e.to_string())),
         )
-    })?;
+    })
+}
 
-    let claims = token_data.claims;
+/// Verify token endpoint
+pub async fn verify_token(
+    State(state): State<Arc<AppState>>,
+    headers: axum::http::HeaderMap,
+) -> Result<Json<VerifyResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = bearer_claims(&headers, &state.jwt_config())?;
 
     // Get user from database to ensure they still exist
     let user = sqlx::query!(
-        "SELECT id, email, name, role FROM users WHERE id = $1 AND email = $2",
+        "SELECT id, email, name, role, must_change_password, sessions_revoked_at FROM users WHERE id = $1 AND email = $2 AND deleted_at IS NULL",
         claims.user_id,
         claims.sub
     )
@@ -289,38 +841,221 @@ pub async fn verify_token(
         }
     };
 
+    if credentials::is_revoked(claims.iat, user.sessions_revoked_at) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new(
+                "token_revoked",
+                "Token has been revoked",
+            )),
+        ));
+    }
+
     // Get domain permissions for this user
-    let permissions_rows = sqlx::query!(
-        "SELECT domain_id, role FROM user_domain_permissions WHERE user_id = $1",
-        user.id
+    let domain_permissions = load_domain_permissions(&state.db, user.id)
+        .await
+        .map_err(permissions_error)?;
+
+    Ok(Json(VerifyResponse {
+        id: user.id,
+        email: user.email,
+        name: user.name,
+        role: user.role.unwrap_or_default(),
+        domain_permissions,
+        must_change_password: user.must_change_password,
+        impersonator_id: claims.impersonator_id,
+    }))
+}
+
+/// Change the caller's own password. This is the one route left open to
+/// users who must change their password, and it clears that requirement.
+pub async fn change_password(
+    State(state): State<Arc<AppState>>,
+    headers: axum::http::HeaderMap,
+    ValidatedJson(payload): ValidatedJson<ChangePasswordRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let claims = bearer_claims(&headers, &state.jwt_config())?;
+    let database_error = |_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(
+                "database_error",
+                "Failed to change password",
+            )),
+        )
+    };
+
+    if claims.impersonator_id.is_some() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::new(
+                "impersonation_forbidden",
+                "Passwords cannot be changed while impersonating",
+            )),
+        ));
+    }
+
+    let current: Option<(String, Option<DateTime<Utc>>)> = sqlx::query_as(
+        "SELECT password_hash, sessions_revoked_at FROM users WHERE id = $1 AND email = $2 AND deleted_at IS NULL",
     )
-    .fetch_all(&state.db)
+    .bind(claims.user_id)
+    .bind(&claims.sub)
+    .fetch_optional(&state.db)
     .await
-    .map_err(|_| {
+    .map_err(database_error)?;
+
+    let (current_hash, sessions_revoked_at) = current.ok_or((
+        StatusCode::UNAUTHORIZED,
+        Json(ErrorResponse::new(
+            "user_not_found",
+            "User no longer exists",
+        )),
+    ))?;
+
+    if credentials::is_revoked(claims.iat, sessions_revoked_at) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new(
+                "token_revoked",
+                "Token has been revoked",
+            )),
+        ));
+    }
+
+    if !verify(&payload.current_password, &current_hash).unwrap_or(false) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new(
+                "invalid_credentials",
+                "Current password is incorrect",
+            )),
+        ));
+    }
+
+    if password_policy::is_recently_used(&state.db, claims.user_id, &payload.new_password)
+        .await
+        .map_err(database_error)?
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "password_reused",
+                "New password must differ from your recent passwords",
+            )),
+        ));
+    }
+
+    let new_hash = hash(&payload.new_password, DEFAULT_COST).map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new("auth_error", "Password hashing failed")),
+        )
+    })?;
+
+    sqlx::query(
+        "UPDATE users SET password_hash = $1, must_change_password = FALSE, updated_at = NOW() WHERE id = $2",
+    )
+    .bind(&new_hash)
+    .bind(claims.user_id)
+    .execute(&state.db)
+    .await
+    .map_err(database_error)?;
+
+    password_policy::record(&state.db, claims.user_id, &new_hash)
+        .await
+        .map_err(database_error)?;
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(claims.user_id),
+            impersonator_id: None,
+            action: "user.password_change".to_string(),
+            details: serde_json::json!({}),
+        },
+    )
+    .await;
+
+    Ok(Json(
+        serde_json::json!({ "message": "Password changed successfully" }),
+    ))
+}
+
+/// Set a new password with a reset token issued by an admin; works without
+/// being logged in
+pub async fn reset_password(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(payload): ValidatedJson<ResetPasswordRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
+    let database_error = |_| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse::new(
                 "database_error",
-                "Failed to query permissions",
+                "Failed to reset password",
+            )),
+        )
+    };
+    let invalid_token = || {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "invalid_reset_token",
+                "Reset token is invalid, expired or already used",
+            )),
+        )
+    };
+
+    let reset = credentials::find_reset_token(&state.db, state.clock.as_ref(), &payload.token)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(invalid_token)?;
+
+    if password_policy::is_recently_used(&state.db, reset.user_id, &payload.new_password)
+        .await
+        .map_err(database_error)?
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "password_reused",
+                "New password must differ from your recent passwords",
             )),
+        ));
+    }
+
+    let new_hash = hash(&payload.new_password, DEFAULT_COST).map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new("auth_error", "Password hashing failed")),
         )
     })?;
 
-    let domain_permissions = permissions_rows
-        .into_iter()
-        .map(|row| DomainPermission {
-            domain_id: row.domain_id.unwrap_or(0),
-            role: row.role,
-        })
-        .collect();
+    if !credentials::complete_reset(&state.db, state.clock.as_ref(), reset, &new_hash)
+        .await
+        .map_err(database_error)?
+    {
+        return Err(invalid_token());
+    }
 
-    Ok(Json(VerifyResponse {
-        id: user.id,
-        email: user.email,
-        name: user.name,
-        role: user.role.unwrap_or_default(),
-        domain_permissions,
-    }))
+    password_policy::record(&state.db, reset.user_id, &new_hash)
+        .await
+        .map_err(database_error)?;
+
+    audit::record(
+        &state.db,
+        AuditEntry {
+            actor_id: Some(reset.user_id),
+            impersonator_id: None,
+            action: "user.password_reset".to_string(),
+            details: serde_json::json!({}),
+        },
+    )
+    .await;
+
+    Ok(Json(
+        serde_json::json!({ "message": "Password reset successfully" }),
+    ))
 }
 
 /// Logout endpoint (for now just returns success)
@@ -330,21 +1065,375 @@ pub async fn logout() -> Result<Json<serde_json::Value>, StatusCode> {
     ))
 }
 
-/// JWT validation function for middleware
-pub fn validate_jwt_token(token: &str) -> Result<Claims, Box<dyn std::error::Error>> {
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(get_jwt_secret().as_bytes()),
-        &Validation::default(),
-    )?;
-
-    Ok(token_data.claims)
-}
-
 /// Create auth router
 pub fn auth_router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/login", post(login))
+        .route("/refresh", post(refresh))
         .route("/verify", get(verify_token))
+        .route("/change-password", post(change_password))
+        .route("/reset-password", post(reset_password))
         .route("/logout", post(logout))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> JwtConfig {
+        JwtConfig {
+            secret: "test-secret".to_string(),
+            access_ttl: Duration::hours(1),
+            refresh_ttl: Duration::days(30),
+            issuer: "multi-blog-api".to_string(),
+            audience: "multi-blog".to_string(),
+            leeway: DEFAULT_JWT_LEEWAY_SECS,
+            rsa: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    const CURRENT_PRIVATE: &[u8] = include_bytes!("../../tests/fixtures/jwt/current_private.pem");
+    const CURRENT_PUBLIC: &[u8] = include_bytes!("../../tests/fixtures/jwt/current_public.pem");
+    const RETIRED_PRIVATE: &[u8] = include_bytes!("../../tests/fixtures/jwt/retired_private.pem");
+    const RETIRED_PUBLIC: &[u8] = include_bytes!("../../tests/fixtures/jwt/retired_public.pem");
+
+    /// RS256 config signing with "current" and still publishing "retired"
+    fn rsa_config() -> JwtConfig {
+        let public_keys = vec![
+            ("current".to_string(), CURRENT_PUBLIC.to_vec()),
+            ("retired".to_string(), RETIRED_PUBLIC.to_vec()),
+        ];
+        JwtConfig {
+            rsa: Some(Arc::new(
+                RsaKeys::from_pem("current", CURRENT_PRIVATE, &public_keys).unwrap(),
+            )),
+            ..test_config()
+        }
+    }
+
+    fn sign_rs256(claims: &Claims, kid: &str, private_pem: &[u8]) -> String {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(kid.to_string());
+        encode(
+            &header,
+            claims,
+            &EncodingKey::from_rsa_pem(private_pem).unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn sign(claims: &Claims, secret: &str) -> String {
+        encode(
+            &Header::new(Algorithm::HS256),
+            claims,
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    fn claims_for(config: &JwtConfig) -> Claims {
+        let now = Utc::now();
+        Claims {
+            sub: "user@test.com".to_string(),
+            user_id: 1,
+            role: "user".to_string(),
+            exp: (now + Duration::hours(1)).timestamp() as usize,
+            iat: now.timestamp() as usize,
+            iss: config.issuer.clone(),
+            aud: config.audience.clone(),
+            impersonator_id: None,
+        }
+    }
+
+    #[test]
+    fn test_valid_token_round_trip() {
+        let config = test_config();
+        let token = create_access_token(&config, "user@test.com", 1, "user").unwrap();
+
+        let claims = validate_jwt_token_with(&token, &config).unwrap();
+        assert_eq!(claims.sub, "user@test.com");
+        assert_eq!(claims.user_id, 1);
+        assert_eq!(claims.iss, "multi-blog-api");
+        assert_eq!(claims.aud, "multi-blog");
+        assert_eq!(claims.exp - claims.iat, 3600);
+    }
+
+    #[test]
+    fn test_impersonation_token_carries_impersonator() {
+        let config = test_config();
+        let token = create_impersonation_token(
+            &config,
+            "user@test.com",
+            7,
+            "user",
+            1,
+            Duration::minutes(30),
+        )
+        .unwrap();
+
+        let claims = validate_jwt_token_with(&token, &config).unwrap();
+        assert_eq!(claims.user_id, 7);
+        assert_eq!(claims.impersonator_id, Some(1));
+        assert_eq!(claims.exp - claims.iat, 1800);
+
+        // Regular tokens don't carry the claim at all
+        let token = create_access_token(&config, "user@test.com", 7, "user").unwrap();
+        let claims = validate_jwt_token_with(&token, &config).unwrap();
+        assert_eq!(claims.impersonator_id, None);
+    }
+
+    #[test]
+    fn test_member_and_access_tokens_do_not_mix() {
+        let config = test_config();
+        let token =
+            create_member_token(&config, "reader@test.com", 3, 9, Duration::days(30)).unwrap();
+
+        let claims = validate_member_token_with(&token, &config).unwrap();
+        assert_eq!(claims.member_id, 3);
+        assert_eq!(claims.domain_id, 9);
+        assert_eq!(claims.aud, "multi-blog-members");
+        assert_eq!(
+            validate_jwt_token_with(&token, &config).unwrap_err(),
+            TokenError::WrongAudience
+        );
+
+        let token = create_access_token(&config, "user@test.com", 1, "user").unwrap();
+        assert_eq!(
+            validate_member_token_with(&token, &config).unwrap_err(),
+            TokenError::WrongAudience
+        );
+    }
+
+    #[test]
+    fn test_refresh_and_access_tokens_do_not_mix() {
+        let config = test_config();
+        let token = create_refresh_token(&config, "user@test.com", 1, "user").unwrap();
+
+        let claims = validate_refresh_token_with(&token, &config).unwrap();
+        assert_eq!(claims.user_id, 1);
+        assert_eq!(claims.aud, "multi-blog-refresh");
+        assert!(claims.exp > (Utc::now() + Duration::days(29)).timestamp() as usize);
+        assert_eq!(
+            validate_jwt_token_with(&token, &config).unwrap_err(),
+            TokenError::WrongAudience
+        );
+
+        let token = create_access_token(&config, "user@test.com", 1, "user").unwrap();
+        assert_eq!(
+            validate_refresh_token_with(&token, &config).unwrap_err(),
+            TokenError::WrongAudience
+        );
+    }
+
+    #[test]
+    fn test_expired_token_rejected() {
+        let config = test_config();
+        let mut claims = claims_for(&config);
+        claims.exp = (Utc::now() - Duration::hours(2)).timestamp() as usize;
+
+        let token = sign(&claims, &config.secret);
+        assert_eq!(
+            validate_jwt_token_with(&token, &config).unwrap_err(),
+            TokenError::Expired
+        );
+    }
+
+    #[test]
+    fn test_expiry_within_leeway_accepted() {
+        let config = test_config();
+        let mut claims = claims_for(&config);
+        claims.exp = (Utc::now() - Duration::seconds(10)).timestamp() as usize;
+
+        let token = sign(&claims, &config.secret);
+        assert!(validate_jwt_token_with(&token, &config).is_ok());
+
+        let strict = JwtConfig {
+            leeway: 0,
+            ..test_config()
+        };
+        assert_eq!(
+            validate_jwt_token_with(&token, &strict).unwrap_err(),
+            TokenError::Expired
+        );
+    }
+
+    #[test]
+    fn test_expiry_beyond_leeway_rejected() {
+        let config = test_config();
+        let mut claims = claims_for(&config);
+        claims.exp = (Utc::now() - Duration::seconds(DEFAULT_JWT_LEEWAY_SECS as i64 + 10))
+            .timestamp() as usize;
+
+        let token = sign(&claims, &config.secret);
+        assert_eq!(
+            validate_jwt_token_with(&token, &config).unwrap_err(),
+            TokenError::Expired
+        );
+    }
+
+    #[test]
+    fn test_token_expires_at_exact_instant() {
+        use crate::services::clock::FrozenClock;
+        use chrono::TimeZone;
+
+        let issued_at = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let clock = FrozenClock::new(issued_at);
+        let config = JwtConfig {
+            leeway: 0,
+            ..test_config()
+        }
+        .with_clock(Arc::new(clock.clone()));
+
+        let token = create_access_token(&config, "user@test.com", 1, "user").unwrap();
+        let claims = validate_jwt_token_with(&token, &config).unwrap();
+        assert_eq!(claims.iat, issued_at.timestamp() as usize);
+
+        // Valid through the second of `exp`, expired the second after
+        clock.set(issued_at + Duration::hours(1));
+        assert!(validate_jwt_token_with(&token, &config).is_ok());
+        clock.advance(Duration::seconds(1));
+        assert_eq!(
+            validate_jwt_token_with(&token, &config).unwrap_err(),
+            TokenError::Expired
+        );
+
+        // Leeway pushes the cut-off back by as many seconds
+        let lenient = JwtConfig {
+            leeway: 30,
+            ..config.clone()
+        };
+        clock.set(issued_at + Duration::hours(1) + Duration::seconds(30));
+        assert!(validate_jwt_token_with(&token, &lenient).is_ok());
+        clock.advance(Duration::seconds(1));
+        assert_eq!(
+            validate_jwt_token_with(&token, &lenient).unwrap_err(),
+            TokenError::Expired
+        );
+    }
+
+    #[test]
+    fn test_wrong_issuer_rejected() {
+        let config = test_config();
+        let mut claims = claims_for(&config);
+        claims.iss = "someone-else".to_string();
+
+        let token = sign(&claims, &config.secret);
+        assert_eq!(
+            validate_jwt_token_with(&token, &config).unwrap_err(),
+            TokenError::WrongIssuer
+        );
+    }
+
+    #[test]
+    fn test_wrong_audience_rejected() {
+        let config = test_config();
+        let mut claims = claims_for(&config);
+        claims.aud = "another-app".to_string();
+
+        let token = sign(&claims, &config.secret);
+        assert_eq!(
+            validate_jwt_token_with(&token, &config).unwrap_err(),
+            TokenError::WrongAudience
+        );
+    }
+
+    #[test]
+    fn test_wrong_secret_rejected() {
+        let config = test_config();
+        let token = sign(&claims_for(&config), "not-the-secret");
+
+        assert_eq!(
+            validate_jwt_token_with(&token, &config).unwrap_err(),
+            TokenError::InvalidSignature
+        );
+    }
+
+    #[test]
+    fn test_malformed_token_rejected() {
+        let config = test_config();
+
+        assert_eq!(
+            validate_jwt_token_with("not.a.jwt", &config).unwrap_err(),
+            TokenError::Malformed
+        );
+        assert_eq!(
+            validate_jwt_token_with("", &config).unwrap_err(),
+            TokenError::Malformed
+        );
+    }
+
+    #[test]
+    fn test_rs256_current_key_round_trip() {
+        let config = rsa_config();
+        let token = create_access_token(&config, "user@test.com", 1, "user").unwrap();
+
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.alg, Algorithm::RS256);
+        assert_eq!(header.kid.as_deref(), Some("current"));
+
+        let claims = validate_jwt_token_with(&token, &config).unwrap();
+        assert_eq!(claims.sub, "user@test.com");
+    }
+
+    #[test]
+    fn test_rs256_retired_key_still_validates() {
+        let config = rsa_config();
+        let token = sign_rs256(&claims_for(&config), "retired", RETIRED_PRIVATE);
+
+        let claims = validate_jwt_token_with(&token, &config).unwrap();
+        assert_eq!(claims.user_id, 1);
+    }
+
+    #[test]
+    fn test_rs256_unknown_kid_rejected() {
+        let config = rsa_config();
+        let token = sign_rs256(&claims_for(&config), "removed", RETIRED_PRIVATE);
+
+        assert_eq!(
+            validate_jwt_token_with(&token, &config).unwrap_err(),
+            TokenError::UnknownKey
+        );
+    }
+
+    #[test]
+    fn test_rs256_kid_signed_with_other_key_rejected() {
+        let config = rsa_config();
+        let token = sign_rs256(&claims_for(&config), "current", RETIRED_PRIVATE);
+
+        assert_eq!(
+            validate_jwt_token_with(&token, &config).unwrap_err(),
+            TokenError::InvalidSignature
+        );
+    }
+
+    #[test]
+    fn test_hs256_token_rejected_when_rs256_configured() {
+        let config = rsa_config();
+        let token = sign(&claims_for(&config), &config.secret);
+
+        assert!(validate_jwt_token_with(&token, &config).is_err());
+    }
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(TokenError::Expired.code(), "token_expired");
+        assert_eq!(TokenError::WrongIssuer.code(), "invalid_token");
+    }
+
+    #[test]
+    fn test_change_password_request_checks_new_password_strength() {
+        let weak = ChangePasswordRequest {
+            current_password: "anything".to_string(),
+            new_password: "short".to_string(),
+        };
+        let errors = weak.validate().unwrap_err();
+        assert!(errors.field_errors().contains_key("new_password"));
+
+        let strong = ChangePasswordRequest {
+            current_password: "anything".to_string(),
+            new_password: "Str0ng!Passphrase".to_string(),
+        };
+        assert!(strong.validate().is_ok());
+    }
+}
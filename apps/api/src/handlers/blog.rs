@@ -1,18 +1,105 @@
 // src/handlers/blog.rs
+use crate::services::event_buffer::AnalyticsEvent;
+use crate::services::live::{self, LiveEvent};
+use crate::services::permalinks::{self, PermalinkPost};
+use crate::services::post_authors::{self, PostAuthor};
+use crate::services::post_visibility::{self, Access, PostVisibility};
+use crate::services::{
+    event_types, reading_time, sampling, search, slug_redirects, social_meta, translations,
+};
 use crate::utils::{AnalyticsSpan, BusinessSpan, DatabaseSpan};
-use crate::{AnalyticsContext, AppState, DomainContext};
+use crate::{AnalyticsContext, AppState, DomainContext, MemberReader};
 use axum::{
     Extension, Router,
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    extract::{FromRequestParts, Path, Query, State},
+    http::{
+        HeaderMap, HeaderValue, StatusCode, Uri,
+        header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH, LOCATION, VARY},
+    },
+    response::{IntoResponse, Response},
     routing::get,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::Row;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::sync::Arc;
 use tracing::{info, instrument, warn};
 use utoipa::{IntoParams, OpenApi, ToSchema};
+use uuid::Uuid;
+
+/// Header carrying the client's analytics session id (from `POST /session`)
+pub const SESSION_HEADER: &str = "x-session-id";
+
+/// Repeat views of a post by the same reader within this window count once.
+/// Configurable via `POST_VIEW_DEDUP_MINUTES` (default 30).
+fn post_view_dedup_window() -> chrono::Duration {
+    let minutes = std::env::var("POST_VIEW_DEDUP_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30);
+    chrono::Duration::minutes(minutes)
+}
+
+/// `Cache-Control: max-age` for public blog responses.
+/// Configurable via `BLOG_CACHE_MAX_AGE_SECONDS` (default 60).
+fn cache_max_age() -> u64 {
+    std::env::var("BLOG_CACHE_MAX_AGE_SECONDS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60)
+}
+
+/// ETag for a response body, used for listings that have no single `updated_at`
+fn body_etag(body: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// Whether the client's `If-None-Match` already names `etag`
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// Send `body` with caching headers, or a bare 304 if the client is up to date
+fn conditional_response(
+    headers: &HeaderMap,
+    etag: &str,
+    content_type: &'static str,
+    body: Vec<u8>,
+) -> Response {
+    let mut response = if etag_matches(headers, etag) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        ([(CONTENT_TYPE, content_type)], body).into_response()
+    };
+
+    let response_headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        response_headers.insert(ETAG, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&format!("public, max-age={}", cache_max_age())) {
+        response_headers.insert(CACHE_CONTROL, value);
+    }
+    response
+}
+
+/// Serialize `value` as JSON behind an ETag derived from the body
+fn cached_json<T: Serialize>(headers: &HeaderMap, value: &T) -> Result<Response, StatusCode> {
+    let body = serde_json::to_vec(value).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(conditional_response(
+        headers,
+        &body_etag(&body),
+        "application/json",
+        body,
+    ))
+}
 
 pub struct BlogModule;
 
@@ -22,9 +109,17 @@ impl super::HandlerModule for BlogModule {
             .route("/", get(home))
             .route("/posts", get(list_posts))
             .route("/posts/{slug}", get(get_post))
+            .route("/posts/{slug}/meta", get(post_meta))
+            .route("/posts/id/{id}", get(post_by_id))
             .route("/category/{category}", get(get_category_posts))
             .route("/search", get(search_posts))
             .route("/feed.xml", get(rss_feed))
+            .route("/feed/category/{file}", get(category_feed))
+            .route("/sitemap.xml", get(sitemap))
+            .route("/theme", get(theme))
+            .route("/theme.css", get(theme_css))
+            .route("/menu", get(menu))
+            .route("/permalinks/resolve", get(resolve_permalink))
     }
 
     fn mount_path() -> &'static str {
@@ -40,6 +135,9 @@ impl super::HandlerModule for BlogModule {
     "author": "John Doe",
     "category": "Technology",
     "slug": "sample-blog-post",
+    "word_count": 1250,
+    "reading_time_minutes": 7,
+    "visibility": "public",
     "created_at": "2025-07-20T04:00:00Z"
 }))]
 struct PostResponse {
@@ -49,14 +147,93 @@ struct PostResponse {
     title: String,
     /// Full content of the blog post
     content: String,
-    /// Author of the post
+    /// Byline: the linked authors' current names, or the name stored with the post
     author: String,
+    /// Users credited on the post, authors before contributors
+    #[sqlx(skip)]
+    authors: Vec<PostAuthor>,
     /// Category the post belongs to
     category: String,
     /// URL-friendly slug for the post
     slug: String,
+    /// Words in the post content
+    word_count: i32,
+    /// Estimated minutes to read the post
+    reading_time_minutes: i32,
+    /// `public`, or `members` when reading it takes a member token
+    visibility: String,
     /// When the post was created
     created_at: chrono::DateTime<chrono::Utc>,
+    /// Shown in place of the content to readers who can't open the post
+    #[serde(skip)]
+    excerpt: String,
+    /// Locale of the translation served, absent for the default language
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[sqlx(skip)]
+    locale: Option<String>,
+    /// Drives the response ETag
+    #[serde(skip)]
+    updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl PostResponse {
+    fn etag(&self) -> String {
+        let version = self.updated_at.unwrap_or(self.created_at);
+        // Renaming a credited user changes the post without touching it
+        let mut hasher = DefaultHasher::new();
+        for author in &self.authors {
+            (&author.name, &author.role).hash(&mut hasher);
+        }
+        let credits = hasher.finish() as u32;
+        match &self.locale {
+            Some(locale) => format!(
+                "\"post-{}-{}-{}-{:08x}\"",
+                self.id,
+                locale,
+                version.timestamp_micros(),
+                credits
+            ),
+            None => format!(
+                "\"post-{}-{}-{:08x}\"",
+                self.id,
+                version.timestamp_micros(),
+                credits
+            ),
+        }
+    }
+
+    fn credit(&mut self, authors: Vec<PostAuthor>) {
+        if let Some(byline) = post_authors::byline(&authors) {
+            self.author = byline;
+        }
+        self.authors = authors;
+    }
+
+    /// Serve `translation` in place of the default title and content
+    fn translate(&mut self, translation: translations::Translation) {
+        let (word_count, reading_time_minutes) = reading_time::estimate(&translation.content);
+        self.title = translation.title;
+        self.content = translation.content;
+        self.word_count = word_count;
+        self.reading_time_minutes = reading_time_minutes;
+        self.locale = Some(translation.locale);
+        self.updated_at = Some(translation.updated_at);
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+struct PostQuery {
+    /// Preferred translation, e.g. `fr` or `pt-BR`; the post's default
+    /// language is served when no matching translation exists
+    locale: Option<String>,
+}
+
+/// The post route's query, parsed and as sent; a redirect to a moved post
+/// passes it on unchanged
+#[derive(FromRequestParts)]
+struct PostParams {
+    query: Query<PostQuery>,
+    uri: Uri,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -68,6 +245,7 @@ struct PostResponse {
             "author": "John Doe",
             "category": "Technology",
             "slug": "sample-post",
+            "excerpt": "A short introduction to the post…",
             "created_at": "2025-07-20T04:00:00Z"
         }
     ],
@@ -93,6 +271,9 @@ struct PostListResponse {
     "author": "John Doe",
     "category": "Technology",
     "slug": "sample-blog-post",
+    "excerpt": "A short introduction to the post…",
+    "visibility": "public",
+    "pinned": false,
     "created_at": "2025-07-20T04:00:00Z"
 }))]
 struct PostSummary {
@@ -100,14 +281,49 @@ struct PostSummary {
     id: i32,
     /// Title of the blog post
     title: String,
-    /// Author of the post
+    /// Byline: the linked authors' current names, or the name stored with the post
     author: String,
+    /// Users credited on the post, authors before contributors
+    #[sqlx(skip)]
+    authors: Vec<PostAuthor>,
     /// Category the post belongs to
     category: String,
     /// URL-friendly slug for the post
     slug: String,
+    /// Plain-text summary, written by the editor or generated from the content
+    excerpt: String,
+    /// `public`, or `members` when reading it takes a member token
+    visibility: String,
+    /// Listed before other posts in `/posts`, whatever the sort
+    pinned: bool,
     /// When the post was created
     created_at: chrono::DateTime<chrono::Utc>,
+    /// Full content of public posts, only with `view=full`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[sqlx(default)]
+    content: Option<String>,
+}
+
+impl PostSummary {
+    fn credit(&mut self, authors: Vec<PostAuthor>) {
+        if let Some(byline) = post_authors::byline(&authors) {
+            self.author = byline;
+        }
+        self.authors = authors;
+    }
+}
+
+/// Resolve the credited users of every listed post
+async fn credit_summaries(db: &sqlx::PgPool, posts: &mut [PostSummary]) -> Result<(), StatusCode> {
+    let ids: Vec<i32> = posts.iter().map(|post| post.id).collect();
+    let mut authors = post_authors::for_posts(db, &ids).await.map_err(|e| {
+        warn!("Database error retrieving post authors: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    for post in posts {
+        post.credit(authors.remove(&post.id).unwrap_or_default());
+    }
+    Ok(())
 }
 
 #[derive(Deserialize, ToSchema, IntoParams)]
@@ -121,6 +337,13 @@ struct ListQuery {
     /// Filter posts by category
     #[schema(example = "Technology")]
     category: Option<String>,
+    /// `recent` (default, also `newest`), `popular` (most viewed first) or
+    /// `oldest`; pinned posts come first in every order
+    #[schema(example = "popular")]
+    sort: Option<String>,
+    /// `summary` (default) or `full`, which adds the `content` of public posts
+    #[schema(example = "full")]
+    view: Option<String>,
 }
 
 #[derive(Deserialize, ToSchema, IntoParams)]
@@ -131,6 +354,29 @@ struct SearchQuery {
     /// Page number (default: 1)
     #[schema(example = 1, minimum = 1)]
     page: Option<i32>,
+    /// Include a `highlight` snippet of the content with matches in `<mark>`
+    #[schema(example = true)]
+    highlight: Option<bool>,
+}
+
+#[derive(Serialize, sqlx::FromRow, ToSchema)]
+struct SearchResult {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    post: PostSummary,
+    /// Matching passages of the content, when `highlight=true`; never for
+    /// members-only posts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    highlight: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct SearchResponse {
+    /// Matching posts, best match first
+    posts: Vec<SearchResult>,
+    total: i64,
+    page: i32,
+    per_page: i32,
 }
 
 #[utoipa::path(
@@ -145,16 +391,17 @@ async fn home(
     Extension(domain): Extension<DomainContext>,
     Extension(analytics): Extension<AnalyticsContext>,
     State(state): State<Arc<AppState>>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
     // Log the page view
     log_page_view(&state, &domain, &analytics, "/").await?;
 
     // Get recent posts for homepage
-    let posts = sqlx::query_as::<_, PostSummary>(
+    let mut posts = sqlx::query_as::<_, PostSummary>(
         r#"
-        SELECT id, title, author, category, slug, created_at
+        SELECT id, title, author, category, slug, excerpt, visibility, pinned, created_at
         FROM posts 
-        WHERE domain_id = $1 AND status = 'published'
+        WHERE domain_id = $1 AND status = 'published' AND visibility <> 'private'
         ORDER BY created_at DESC 
         LIMIT 5
         "#,
@@ -163,12 +410,16 @@ async fn home(
     .fetch_all(&state.db)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    credit_summaries(&state.db, &mut posts).await?;
 
-    Ok(Json(serde_json::json!({
-        "domain": domain.name,
-        "recent_posts": posts,
-        "categories": domain.categories
-    })))
+    cached_json(
+        &headers,
+        &serde_json::json!({
+            "domain": domain.name,
+            "recent_posts": posts,
+            "categories": domain.categories
+        }),
+    )
 }
 
 #[utoipa::path(
@@ -186,14 +437,29 @@ async fn list_posts(
     Extension(analytics): Extension<AnalyticsContext>,
     State(state): State<Arc<AppState>>,
     Query(params): Query<ListQuery>,
-) -> Result<Json<PostListResponse>, StatusCode> {
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
     let page = params.page.unwrap_or(1).max(1);
     let per_page = params.per_page.unwrap_or(10).clamp(1, 50);
     let offset = (page - 1) * per_page;
+    let order_by = match params.sort.as_deref() {
+        None | Some("recent") | Some("newest") => "pinned DESC, created_at DESC",
+        Some("popular") => "pinned DESC, view_count DESC, created_at DESC",
+        Some("oldest") => "pinned DESC, created_at ASC",
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+    };
+    // Members-only content stays behind GET /posts/{slug}
+    let content = match params.view.as_deref() {
+        None | Some("summary") => "",
+        Some("full") => ", CASE WHEN visibility = 'public' THEN content END AS content",
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+    };
 
     log_page_view(&state, &domain, &analytics, "/posts").await?;
 
-    let mut query = "SELECT id, title, author, category, slug, created_at FROM posts WHERE domain_id = $1 AND status = 'published'".to_string();
+    let mut query = format!(
+        "SELECT id, title, author, category, slug, excerpt, visibility, pinned, created_at{content} FROM posts WHERE domain_id = $1 AND status = 'published' AND visibility <> 'private'"
+    );
     let mut bind_count = 1;
 
     if let Some(_category) = &params.category {
@@ -202,7 +468,7 @@ async fn list_posts(
     }
 
     query.push_str(&format!(
-        " ORDER BY created_at DESC LIMIT ${} OFFSET ${}",
+        " ORDER BY {order_by} LIMIT ${} OFFSET ${}",
         bind_count + 1,
         bind_count + 2
     ));
@@ -213,18 +479,19 @@ async fn list_posts(
         sqlx_query = sqlx_query.bind(category);
     }
 
-    let posts = sqlx_query
+    let mut posts = sqlx_query
         .bind(per_page)
         .bind(offset)
         .fetch_all(&state.db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    credit_summaries(&state.db, &mut posts).await?;
 
     // Get total count
     let total_query = if params.category.is_some() {
-        "SELECT COUNT(*) as count FROM posts WHERE domain_id = $1 AND status = 'published' AND category = $2"
+        "SELECT COUNT(*) as count FROM posts WHERE domain_id = $1 AND status = 'published' AND visibility <> 'private' AND category = $2"
     } else {
-        "SELECT COUNT(*) as count FROM posts WHERE domain_id = $1 AND status = 'published'"
+        "SELECT COUNT(*) as count FROM posts WHERE domain_id = $1 AND status = 'published' AND visibility <> 'private'"
     };
 
     let mut count_query = sqlx::query_scalar::<_, i64>(total_query).bind(domain.id);
@@ -237,28 +504,35 @@ async fn list_posts(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(Json(PostListResponse {
-        posts,
-        total,
-        page,
-        per_page,
-    }))
+    cached_json(
+        &headers,
+        &PostListResponse {
+            posts,
+            total,
+            page,
+            per_page,
+        },
+    )
 }
 
 #[utoipa::path(
     get,
     path = "/posts/{slug}",
     params(
-        ("slug" = String, Path, description = "Post slug")
+        ("slug" = String, Path, description = "Post slug"),
+        PostQuery
     ),
     responses(
         (status = 200, description = "Single blog post", body = PostResponse),
+        (status = 301, description = "The post was renamed; `Location` has its current slug"),
+        (status = 402, description = "Members-only post and no member token; carries a teaser", body = MembersOnlyResponse),
+        (status = 403, description = "Members-only post and a member token that doesn't open it; carries a teaser", body = MembersOnlyResponse),
         (status = 404, description = "Post not found")
     ),
     tag = "blog"
 )]
 #[instrument(
-    skip(state, domain, analytics),
+    skip(state, domain, analytics, reader, params),
     fields(
         blog.slug = %slug,
         blog.domain = %domain.name,
@@ -275,7 +549,14 @@ async fn get_post(
     Extension(analytics): Extension<AnalyticsContext>,
     State(state): State<Arc<AppState>>,
     Path(slug): Path<String>,
-) -> Result<Json<PostResponse>, StatusCode> {
+    params: PostParams,
+    MemberReader(reader): MemberReader,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let PostParams {
+        query: Query(query),
+        uri,
+    } = params;
     // Add request context to span
     BusinessSpan::add_request_context("", "GET", &format!("/posts/{slug}"));
 
@@ -288,7 +569,8 @@ async fn get_post(
     let post = DatabaseSpan::execute("SELECT", "posts", async {
         sqlx::query_as::<_, PostResponse>(
             r#"
-                SELECT id, title, content, author, category, slug, created_at
+                SELECT id, title, content, author, category, slug, excerpt, visibility,
+                       word_count, reading_time_minutes, created_at, updated_at
                 FROM posts 
                 WHERE domain_id = $1 AND slug = $2 AND status = 'published'
                 "#,
@@ -304,7 +586,7 @@ async fn get_post(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    let post = match post {
+    let mut post = match post {
         Some(p) => {
             // Record successful retrieval in span
             BusinessSpan::add_attribute("blog.post_found", "true");
@@ -315,11 +597,69 @@ async fn get_post(
             p
         }
         None => {
+            // Old links to a renamed post follow it to its current slug
+            let moved = slug_redirects::resolve(&state.db, domain.id, &slug)
+                .await
+                .map_err(|e| {
+                    warn!("Database error resolving slug redirect: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+            if let Some(current) = moved {
+                info!("Post slug {} moved to {}", slug, current);
+                let location = match uri.query() {
+                    Some(query) => format!("/posts/{current}?{query}"),
+                    None => format!("/posts/{current}"),
+                };
+                return Ok((StatusCode::MOVED_PERMANENTLY, [(LOCATION, location)]).into_response());
+            }
             warn!("Post not found for slug: {}", slug);
             return Err(StatusCode::NOT_FOUND);
         }
     };
 
+    let authors = post_authors::list(&state.db, post.id).await.map_err(|e| {
+        warn!("Database error retrieving post authors: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    post.credit(authors);
+
+    let visibility = PostVisibility::parse(&post.visibility).unwrap_or_default();
+    match post_visibility::access(visibility, reader) {
+        Access::Full => {}
+        Access::Hidden => {
+            warn!("Post {} is private", post.id);
+            return Err(StatusCode::NOT_FOUND);
+        }
+        Access::MembersOnly => {
+            return Ok(members_only_response(
+                StatusCode::PAYMENT_REQUIRED,
+                "members_only",
+                "Sign in as a member to read this post",
+                post,
+            ));
+        }
+        Access::Forbidden => {
+            return Ok(members_only_response(
+                StatusCode::FORBIDDEN,
+                "member_access_denied",
+                "This member token does not give access to this post",
+                post,
+            ));
+        }
+    }
+
+    if let Some(locale) = &query.locale {
+        let translation = translations::find(&state.db, post.id, locale)
+            .await
+            .map_err(|e| {
+                warn!("Database error retrieving translation: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        if let Some(translation) = translation {
+            post.translate(translation);
+        }
+    }
+
     // Track page view with analytics tracing
     BusinessSpan::execute("log_page_view", async {
         log_page_view(&state, &domain, &analytics, &format!("/posts/{slug}")).await
@@ -329,6 +669,15 @@ async fn get_post(
         warn!("Analytics logging failed: {:?}", e);
     });
 
+    // Record the post view, deduplicated per reader within the window
+    let session_id = headers
+        .get(SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<Uuid>().ok());
+    if let Err(e) = log_post_view(&state, &domain, &analytics, post.id, &slug, session_id).await {
+        warn!("Post view logging failed: {:?}", e);
+    }
+
     // Track the analytics event with detailed context
     let event_data = serde_json::json!({
         "post_id": post.id,
@@ -338,10 +687,71 @@ async fn get_post(
         "category": post.category
     });
 
-    AnalyticsSpan::track_event("post_view", None, event_data);
+    AnalyticsSpan::track_event(event_types::POST_VIEW, None, event_data);
 
     info!("Successfully retrieved and returning post: {}", post.title);
-    Ok(Json(post))
+    let body = serde_json::to_vec(&post).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut response = conditional_response(&headers, &post.etag(), "application/json", body);
+    // Members-only content must not end up in shared caches
+    if visibility == PostVisibility::Members {
+        let response_headers = response.headers_mut();
+        response_headers.insert(CACHE_CONTROL, HeaderValue::from_static("private, no-cache"));
+        response_headers.insert(VARY, HeaderValue::from_static("authorization"));
+    }
+    Ok(response)
+}
+
+/// What readers who can't open a members-only post get to see
+#[derive(Serialize, ToSchema)]
+struct PostTeaser {
+    id: i32,
+    title: String,
+    author: String,
+    authors: Vec<PostAuthor>,
+    category: String,
+    slug: String,
+    /// Plain-text summary of the post
+    excerpt: String,
+    reading_time_minutes: i32,
+    visibility: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Error body for a members-only post, carrying its teaser
+#[derive(Serialize, ToSchema)]
+struct MembersOnlyResponse {
+    error: &'static str,
+    message: &'static str,
+    teaser: PostTeaser,
+}
+
+fn members_only_response(
+    status: StatusCode,
+    error: &'static str,
+    message: &'static str,
+    post: PostResponse,
+) -> Response {
+    let teaser = PostTeaser {
+        id: post.id,
+        title: post.title,
+        author: post.author,
+        authors: post.authors,
+        category: post.category,
+        slug: post.slug,
+        excerpt: post.excerpt,
+        reading_time_minutes: post.reading_time_minutes,
+        visibility: post.visibility,
+        created_at: post.created_at,
+    };
+    (
+        status,
+        axum::Json(MembersOnlyResponse {
+            error,
+            message,
+            teaser,
+        }),
+    )
+        .into_response()
 }
 
 async fn get_category_posts(
@@ -349,7 +759,8 @@ async fn get_category_posts(
     Extension(analytics): Extension<AnalyticsContext>,
     State(state): State<Arc<AppState>>,
     Path(category): Path<String>,
-) -> Result<Json<PostListResponse>, StatusCode> {
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
     log_page_view(
         &state,
         &domain,
@@ -358,11 +769,11 @@ async fn get_category_posts(
     )
     .await?;
 
-    let posts = sqlx::query_as::<_, PostSummary>(
+    let mut posts = sqlx::query_as::<_, PostSummary>(
         r#"
-        SELECT id, title, author, category, slug, created_at
+        SELECT id, title, author, category, slug, excerpt, visibility, pinned, created_at
         FROM posts 
-        WHERE domain_id = $1 AND category = $2 AND status = 'published'
+        WHERE domain_id = $1 AND category = $2 AND status = 'published' AND visibility <> 'private'
         ORDER BY created_at DESC
         LIMIT 20
         "#,
@@ -372,15 +783,19 @@ async fn get_category_posts(
     .fetch_all(&state.db)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    credit_summaries(&state.db, &mut posts).await?;
 
     let total = posts.len() as i64;
 
-    Ok(Json(PostListResponse {
-        posts,
-        total,
-        page: 1,
-        per_page: 20,
-    }))
+    cached_json(
+        &headers,
+        &PostListResponse {
+            posts,
+            total,
+            page: 1,
+            per_page: 20,
+        },
+    )
 }
 
 #[utoipa::path(
@@ -388,7 +803,7 @@ async fn get_category_posts(
     path = "/search",
     params(SearchQuery),
     responses(
-        (status = 200, description = "Search results", body = PostListResponse)
+        (status = 200, description = "Search results", body = SearchResponse)
     ),
     tag = "blog"
 )]
@@ -397,107 +812,492 @@ async fn search_posts(
     Extension(analytics): Extension<AnalyticsContext>,
     State(state): State<Arc<AppState>>,
     Query(params): Query<SearchQuery>,
-) -> Result<Json<PostListResponse>, StatusCode> {
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    if !domain.feature_enabled("search") {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
     log_page_view(&state, &domain, &analytics, "/search").await?;
 
-    // Log search event with query
-    sqlx::query(
-        r#"
-        INSERT INTO analytics_events (domain_id, event_type, path, user_agent, ip_address, referrer, metadata)
-        VALUES ($1, 'search', '/search', $2, $3, $4, $5)
-        "#
-    )
-    .bind(domain.id)
-    .bind(&analytics.user_agent)
-    .bind(&analytics.ip_address)
-    .bind(&analytics.referrer)
-    .bind(serde_json::json!({"query": params.q}))
-    .execute(&state.db)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let settings = search::load(&state.db, domain.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let posts = sqlx::query_as::<_, PostSummary>(
-        r#"
-        SELECT id, title, author, category, slug, created_at
-        FROM posts 
-        WHERE domain_id = $1 AND status = 'published' 
-        AND (title ILIKE $2 OR content ILIKE $2)
-        ORDER BY created_at DESC
-        LIMIT 20
-        "#,
-    )
-    .bind(domain.id)
-    .bind(format!("%{}%", params.q))
-    .fetch_all(&state.db)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // Nothing but stopwords and punctuation matches nothing
+    let posts = match search::build_tsquery(&params.q, &settings) {
+        Some(tsquery) => sqlx::query_as::<_, SearchResult>(&format!(
+            r#"
+            SELECT id, title, author, category, slug, excerpt, visibility, pinned, created_at,
+                CASE WHEN $4 AND visibility = 'public'
+                    THEN ts_headline('english', content, query, $5) END AS highlight
+            FROM posts, to_tsquery('english', $2) query
+            WHERE domain_id = $1 AND status = 'published' AND visibility <> 'private'
+            AND {document} @@ query
+            ORDER BY ts_rank($3, {document}, query) DESC, created_at DESC
+            LIMIT 20
+            "#,
+            document = search::SEARCH_DOCUMENT,
+        ))
+        .bind(domain.id)
+        .bind(tsquery)
+        .bind(settings.weights.rank_weights())
+        .bind(params.highlight.unwrap_or(false))
+        .bind(search::HEADLINE_OPTIONS)
+        .fetch_all(&state.db)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Search query error");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?,
+        None => Vec::new(),
+    };
 
     let total = posts.len() as i64;
 
-    Ok(Json(PostListResponse {
-        posts,
-        total,
-        page: params.page.unwrap_or(1),
-        per_page: 20,
-    }))
+    // Log search event with query and how many posts it found
+    state
+        .events
+        .record(AnalyticsEvent {
+            path: Some("/search".to_string()),
+            user_agent: Some(analytics.user_agent.clone()),
+            ip_address: analytics.ip_address.parse().ok(),
+            referrer: analytics.referrer.clone(),
+            metadata: serde_json::json!({
+                "query": params.q,
+                "results_count": total,
+                "no_results": total == 0
+            }),
+            ..AnalyticsEvent::new(domain.id, event_types::SEARCH, state.clock.now())
+        })
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    cached_json(
+        &headers,
+        &SearchResponse {
+            posts,
+            total,
+            page: params.page.unwrap_or(1),
+            per_page: 20,
+        },
+    )
 }
 
 async fn rss_feed(
     Extension(domain): Extension<DomainContext>,
     State(state): State<Arc<AppState>>,
-) -> Result<String, StatusCode> {
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    build_feed(&state, &domain, &headers, None).await
+}
+
+// Feed of one category's posts, at /feed/category/{category}.xml
+async fn category_feed(
+    Extension(domain): Extension<DomainContext>,
+    State(state): State<Arc<AppState>>,
+    Path(file): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let category = file.strip_suffix(".xml").ok_or(StatusCode::NOT_FOUND)?;
+    build_feed(&state, &domain, &headers, Some(category)).await
+}
+
+/// The latest 20 published posts as RSS, optionally only those in
+/// `category`. A category the domain doesn't list and no post uses is `404`.
+async fn build_feed(
+    state: &AppState,
+    domain: &DomainContext,
+    headers: &HeaderMap,
+    category: Option<&str>,
+) -> Result<Response, StatusCode> {
+    if !domain.feature_enabled("rss") {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
     let posts = sqlx::query(
         r#"
-        SELECT title, content, author, slug, created_at
+        SELECT id, title, excerpt, author, slug, category, created_at
         FROM posts 
-        WHERE domain_id = $1 AND status = 'published'
+        WHERE domain_id = $1 AND status = 'published' AND visibility <> 'private'
+          AND ($2::text IS NULL OR category = $2)
         ORDER BY created_at DESC
         LIMIT 20
         "#,
     )
     .bind(domain.id)
+    .bind(category)
     .fetch_all(&state.db)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    let unlisted =
+        category.is_some_and(|category| !domain.categories.iter().any(|c| c == category));
+    if posts.is_empty() && unlisted {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    // Translated locales per post, linked as hreflang alternates
+    let alternates = if domain.feature_enabled("hreflang") {
+        let post_ids: Vec<i32> = posts.iter().map(|post| post.get("id")).collect();
+        translations::locales_for_posts(&state.db, &post_ids)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    } else {
+        Vec::new()
+    };
+
+    let (title, description) = match category {
+        Some(category) => (
+            format!("{} - {}", domain.name, category),
+            format!("Latest {} posts from {}", category, domain.name),
+        ),
+        None => (
+            domain.name.clone(),
+            format!("Latest posts from {}", domain.name),
+        ),
+    };
     let mut rss = format!(
         r#"<?xml version="1.0" encoding="UTF-8"?>
-<rss version="2.0">
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
 <channel>
 <title>{}</title>
 <link>https://{}</link>
-<description>Latest posts from {}</description>
+<description>{}</description>
 "#,
-        domain.name, domain.hostname, domain.name
+        xml_escape(&title),
+        xml_escape(&domain.hostname),
+        xml_escape(&description)
     );
 
     for post in posts {
+        let id: i32 = post.get("id");
         let title: String = post.get("title");
-        let content: String = post.get("content");
+        let excerpt: String = post.get("excerpt");
         let author: String = post.get("author");
         let slug: String = post.get("slug");
+        let category: String = post.get("category");
         let created_at: chrono::DateTime<chrono::Utc> = post.get("created_at");
+        let link = permalinks::url(
+            domain,
+            &PermalinkPost {
+                id,
+                slug: &slug,
+                category: &category,
+                created_at,
+            },
+        );
 
         rss.push_str(&format!(
             r#"<item>
 <title>{}</title>
-<link>https://{}/posts/{}</link>
+<link>{}</link>
 <description>{}</description>
 <author>{}</author>
 <pubDate>{}</pubDate>
-</item>
+{}</item>
 "#,
-            title,
-            domain.hostname,
-            slug,
-            content.chars().take(200).collect::<String>(),
-            author,
-            created_at.format("%a, %d %b %Y %H:%M:%S GMT")
+            xml_escape(&title),
+            xml_escape(&link),
+            xml_escape(&excerpt),
+            xml_escape(&author),
+            created_at.format("%a, %d %b %Y %H:%M:%S GMT"),
+            hreflang_links("atom:link", &link, id, &alternates)
         ));
     }
 
     rss.push_str("</channel></rss>");
-    Ok(rss)
+    Ok(conditional_response(
+        headers,
+        &body_etag(rss.as_bytes()),
+        "application/rss+xml; charset=utf-8",
+        rss.into_bytes(),
+    ))
+}
+
+/// Most posts listed in the sitemap, the limit of one sitemap file
+const MAX_SITEMAP_URLS: i64 = 50_000;
+
+/// Canonical URLs of the published posts search engines may index, newest
+/// first. Private and `noindex` posts are left out.
+async fn sitemap(
+    Extension(domain): Extension<DomainContext>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let posts = sqlx::query(
+        r#"
+        SELECT id, slug, category, created_at, COALESCE(updated_at, created_at) AS updated_at
+        FROM posts
+        WHERE domain_id = $1 AND status = 'published' AND visibility <> 'private' AND NOT noindex
+        ORDER BY created_at DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(domain.id)
+    .bind(MAX_SITEMAP_URLS)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Translated locales per post, listed as hreflang alternates
+    let alternates = if domain.feature_enabled("hreflang") {
+        let post_ids: Vec<i32> = posts.iter().map(|post| post.get("id")).collect();
+        translations::locales_for_posts(&state.db, &post_ids)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    } else {
+        Vec::new()
+    };
+
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9" xmlns:xhtml="http://www.w3.org/1999/xhtml">
+"#,
+    );
+    for post in posts {
+        let id: i32 = post.get("id");
+        let slug: String = post.get("slug");
+        let category: String = post.get("category");
+        let updated_at: chrono::DateTime<chrono::Utc> = post.get("updated_at");
+        let loc = permalinks::url(
+            &domain,
+            &PermalinkPost {
+                id,
+                slug: &slug,
+                category: &category,
+                created_at: post.get("created_at"),
+            },
+        );
+        xml.push_str(&format!(
+            "<url><loc>{}</loc><lastmod>{}</lastmod>{}</url>\n",
+            xml_escape(&loc),
+            updated_at.format("%Y-%m-%d"),
+            hreflang_links("xhtml:link", &loc, id, &alternates)
+        ));
+    }
+    xml.push_str("</urlset>");
+
+    Ok(conditional_response(
+        &headers,
+        &body_etag(xml.as_bytes()),
+        "application/xml; charset=utf-8",
+        xml.into_bytes(),
+    ))
+}
+
+/// `element` alternates (`atom:link` in feeds, `xhtml:link` in the sitemap)
+/// for a post's translations, plus `x-default` for the untranslated post at
+/// `link`; empty when it has no translations
+fn hreflang_links(element: &str, link: &str, post_id: i32, alternates: &[(i32, String)]) -> String {
+    let locales: Vec<&str> = alternates
+        .iter()
+        .filter(|(id, _)| *id == post_id)
+        .map(|(_, locale)| locale.as_str())
+        .collect();
+    if locales.is_empty() {
+        return String::new();
+    }
+
+    let mut links = format!(
+        "<{element} rel=\"alternate\" hreflang=\"x-default\" href=\"{}\"/>\n",
+        xml_escape(link)
+    );
+    for locale in locales {
+        links.push_str(&format!(
+            "<{element} rel=\"alternate\" hreflang=\"{}\" href=\"{}\"/>\n",
+            xml_escape(locale),
+            xml_escape(&format!("{link}?locale={locale}"))
+        ));
+    }
+    links
+}
+
+/// `text` with the characters XML gives meaning to replaced by entities, safe
+/// inside both elements and quoted attributes
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// The domain's theme merged over the platform default, with every value set
+async fn theme(
+    Extension(domain): Extension<DomainContext>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let default = crate::services::theme::platform_default();
+    cached_json(
+        &headers,
+        &crate::services::theme::resolve(&default, &domain.theme_config),
+    )
+}
+
+// Domain theme as CSS custom properties
+async fn theme_css(Extension(domain): Extension<DomainContext>, headers: HeaderMap) -> Response {
+    let default = crate::services::theme::platform_default();
+    let css = crate::services::theme::render_layered_css(&[&domain.theme_config, &default]);
+    conditional_response(
+        &headers,
+        &body_etag(css.as_bytes()),
+        "text/css; charset=utf-8",
+        css.into_bytes(),
+    )
+}
+
+/// The domain's navigation menu for the frontend, `{"items": [...]}`
+async fn menu(
+    State(state): State<Arc<AppState>>,
+    Extension(domain): Extension<DomainContext>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let items = crate::services::menu::load(&state.db, domain.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    cached_json(&headers, &serde_json::json!({ "items": items }))
+}
+
+/// Open Graph and Twitter Card fields for sharing a published post
+async fn post_meta(
+    State(state): State<Arc<AppState>>,
+    Extension(domain): Extension<DomainContext>,
+    Path(slug): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let post = sqlx::query(
+        r#"
+        SELECT id, title, slug, category, author, excerpt, image_url, created_at,
+               meta_title, meta_description, noindex
+        FROM posts
+        WHERE domain_id = $1 AND slug = $2 AND status = 'published' AND visibility <> 'private'
+        "#,
+    )
+    .bind(domain.id)
+    .bind(&slug)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        warn!("Database error retrieving post meta: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let title: String = post.get("title");
+    let slug: String = post.get("slug");
+    let category: String = post.get("category");
+    let author: String = post.get("author");
+    let excerpt: String = post.get("excerpt");
+    let image_url: Option<String> = post.get("image_url");
+    let meta_title: Option<String> = post.get("meta_title");
+    let meta_description: Option<String> = post.get("meta_description");
+    let meta = social_meta::post_meta(
+        &domain,
+        &social_meta::PostMetaSource {
+            id: post.get("id"),
+            title: &title,
+            slug: &slug,
+            category: &category,
+            author: &author,
+            excerpt: &excerpt,
+            image_url: image_url.as_deref(),
+            created_at: post.get("created_at"),
+            meta_title: meta_title.as_deref(),
+            meta_description: meta_description.as_deref(),
+            noindex: post.get("noindex"),
+        },
+    );
+    cached_json(&headers, &meta)
+}
+
+/// Send integrations that only know a post's id to its slug URL, so the
+/// post is only ever served from one address
+async fn post_by_id(
+    State(state): State<Arc<AppState>>,
+    Extension(domain): Extension<DomainContext>,
+    Path(id): Path<i32>,
+    uri: Uri,
+) -> Result<Response, StatusCode> {
+    let slug: String = sqlx::query_scalar(
+        r#"
+        SELECT slug FROM posts
+        WHERE id = $1 AND domain_id = $2 AND status = 'published' AND visibility <> 'private'
+        "#,
+    )
+    .bind(id)
+    .bind(domain.id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        warn!("Database error looking up post by id: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let location = match uri.query() {
+        Some(query) => format!("/posts/{slug}?{query}"),
+        None => format!("/posts/{slug}"),
+    };
+    Ok((StatusCode::MOVED_PERMANENTLY, [(LOCATION, location)]).into_response())
+}
+
+#[derive(Deserialize)]
+struct ResolvePermalinkQuery {
+    path: String,
+}
+
+/// The published post a path under the domain's permalink format points at,
+/// as `{"id", "slug", "url"}`
+async fn resolve_permalink(
+    State(state): State<Arc<AppState>>,
+    Extension(domain): Extension<DomainContext>,
+    Query(query): Query<ResolvePermalinkQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let found = permalinks::parse(permalinks::format_for(&domain), &query.path)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let post = sqlx::query(
+        r#"
+        SELECT id, slug, category, created_at
+        FROM posts
+        WHERE domain_id = $1 AND status = 'published' AND visibility <> 'private'
+          AND ($2::int IS NULL OR id = $2) AND ($3::text IS NULL OR slug = $3)
+        "#,
+    )
+    .bind(domain.id)
+    .bind(found.id)
+    .bind(found.slug.as_deref())
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        warn!("Database error resolving permalink: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let slug: String = post.get("slug");
+    let category: String = post.get("category");
+    let post = PermalinkPost {
+        id: post.get("id"),
+        slug: &slug,
+        category: &category,
+        created_at: post.get("created_at"),
+    };
+    // A path whose date or category disagrees with the post names nothing
+    if !found.matches(&post) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    cached_json(
+        &headers,
+        &serde_json::json!({
+            "id": post.id,
+            "slug": post.slug,
+            "url": permalinks::url(&domain, &post),
+        }),
+    )
 }
 
 // Helper function to log page views
@@ -513,25 +1313,103 @@ async fn log_page_view(
         .parse()
         .unwrap_or_else(|_| "127.0.0.1".parse().unwrap());
 
-    sqlx::query(
+    // Busy domains may store only a sample of page views, each weighted up
+    let rate = sampling::page_view_sample_rate(domain);
+    if let Some(weight) = sampling::sample_weight(rate, rand::random::<f64>()) {
+        state
+            .events
+            .record(AnalyticsEvent {
+                path: Some(path.to_string()),
+                user_agent: Some(analytics.user_agent.clone()),
+                ip_address: Some(ip_addr),
+                referrer: analytics.referrer.clone(),
+                sample_weight: weight,
+                ..AnalyticsEvent::new(domain.id, event_types::PAGE_VIEW, state.clock.now())
+            })
+            .await
+            .map_err(|e| {
+                tracing::error!(error = %e, "Analytics logging error");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    }
+
+    // Live dashboards see every page view, sampled or not
+    live::publish(LiveEvent::PageView {
+        domain_id: domain.id,
+        path: path.to_string(),
+        at: state.clock.now(),
+    });
+
+    Ok(())
+}
+
+/// Record a `post_view` event unless the same reader viewed this post within
+/// the dedup window. Readers are identified by their analytics session when the
+/// client sends one, otherwise by IP address and user agent. A recorded view
+/// also bumps `posts.view_count`.
+/// Returns whether a new view was recorded.
+async fn log_post_view(
+    state: &Arc<AppState>,
+    domain: &DomainContext,
+    analytics: &AnalyticsContext,
+    post_id: i32,
+    slug: &str,
+    session_id: Option<Uuid>,
+) -> Result<bool, StatusCode> {
+    let ip_addr: std::net::IpAddr = analytics
+        .ip_address
+        .parse()
+        .unwrap_or_else(|_| "127.0.0.1".parse().unwrap());
+
+    // analytics_events.session_id references user_sessions.id, not the client-facing id
+    let session_ref: Option<Uuid> = match session_id {
+        Some(client_id) => sqlx::query_scalar("SELECT id FROM user_sessions WHERE session_id = $1")
+            .bind(client_id)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        None => None,
+    };
+
+    let window_seconds = post_view_dedup_window().num_seconds() as f64;
+
+    let result = sqlx::query(
         r#"
-        INSERT INTO analytics_events (domain_id, event_type, path, user_agent, ip_address, referrer)
-        VALUES ($1, 'page_view', $2, $3, $4, $5)
+        WITH recorded AS (
+            INSERT INTO analytics_events (domain_id, post_id, session_id, event_type, path, user_agent, ip_address, referrer)
+            SELECT $1, $2, $3, $9, $4, $5, $6, $7
+            WHERE NOT EXISTS (
+                SELECT 1 FROM analytics_events
+                WHERE event_type = $9 AND post_id = $2
+                AND created_at > NOW() - make_interval(secs => $8)
+                AND CASE
+                    WHEN $3::uuid IS NOT NULL THEN session_id = $3
+                    ELSE session_id IS NULL AND ip_address = $6 AND user_agent = $5
+                END
+            )
+            RETURNING post_id
+        )
+        UPDATE posts SET view_count = view_count + 1
+        WHERE id IN (SELECT post_id FROM recorded)
         "#,
     )
     .bind(domain.id)
-    .bind(path)
+    .bind(post_id)
+    .bind(session_ref)
+    .bind(format!("/posts/{slug}"))
     .bind(&analytics.user_agent)
     .bind(ip_addr)
     .bind(&analytics.referrer)
+    .bind(window_seconds)
+    .bind(event_types::POST_VIEW)
     .execute(&state.db)
     .await
     .map_err(|e| {
-        tracing::error!(error = %e, "Analytics logging error");
+        tracing::error!(error = %e, "Post view logging error");
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    Ok(())
+    Ok(result.rows_affected() > 0)
 }
 
 #[derive(OpenApi)]
@@ -543,7 +1421,7 @@ async fn log_page_view(
         search_posts,
     ),
     components(
-        schemas(PostResponse, PostListResponse, PostSummary, ListQuery, SearchQuery)
+        schemas(PostResponse, PostTeaser, MembersOnlyResponse, PostListResponse, PostSummary, PostAuthor, ListQuery, SearchQuery, SearchResult, SearchResponse)
     ),
     tags(
         (name = "blog", description = "Blog API endpoints")
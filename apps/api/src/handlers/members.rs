@@ -0,0 +1,87 @@
+// src/handlers/members.rs
+//! Reader sign-in for members-only posts
+//!
+//! Domain-scoped like the blog routes and separate from `/auth`: the token
+//! handed out here only opens the domain's `members` posts.
+use super::auth::{ErrorResponse, create_member_token};
+use crate::services::members::{self, Member};
+use crate::validation::extractors::ValidatedJson;
+use crate::{AppState, DomainContext};
+use axum::{Extension, Router, extract::State, http::StatusCode, response::Json, routing::post};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct MemberLoginRequest {
+    #[validate(email(message = "Invalid email format"))]
+    pub email: String,
+    #[validate(length(min = 1, message = "Password is required"))]
+    pub password: String,
+}
+
+#[derive(Serialize)]
+pub struct MemberLoginResponse {
+    /// Sent back as `Authorization: Bearer` to read members-only posts
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+    pub member: Member,
+}
+
+/// Exchange a member's email and password for a member token
+pub async fn member_login(
+    Extension(domain): Extension<DomainContext>,
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(payload): ValidatedJson<MemberLoginRequest>,
+) -> Result<Json<MemberLoginResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let member = members::authenticate(&state.db, domain.id, &payload.email, &payload.password)
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(
+                    "database_error",
+                    "Failed to query member",
+                )),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse::new(
+                    "invalid_credentials",
+                    "Invalid email or password",
+                )),
+            )
+        })?;
+
+    let ttl = members::member_token_ttl();
+    let token = create_member_token(
+        &state.jwt_config(),
+        &member.email,
+        member.id,
+        domain.id,
+        ttl,
+    )
+    .map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(
+                "token_error",
+                "Failed to create member token",
+            )),
+        )
+    })?;
+
+    Ok(Json(MemberLoginResponse {
+        token,
+        expires_at: state.clock.now() + ttl,
+        member,
+    }))
+}
+
+/// Create members router
+pub fn members_router() -> Router<Arc<AppState>> {
+    Router::new().route("/login", post(member_login))
+}
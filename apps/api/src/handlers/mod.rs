@@ -3,7 +3,9 @@ pub mod admin;
 pub mod analytics;
 pub mod auth;
 pub mod blog;
+pub mod members;
 pub mod session;
+pub mod subscribers;
 
 use crate::AppState;
 use axum::Router;
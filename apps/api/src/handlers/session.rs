@@ -1,5 +1,6 @@
 // src/handlers/session.rs
 use crate::{AppState, DomainContext, AnalyticsContext};
+use crate::services::live::{self, LiveEvent};
 use crate::services::session_tracking::SessionTracker;
 use crate::validation::extractors::ValidatedJson;
 use axum::{
@@ -72,7 +73,13 @@ pub async fn create_session(
     };
     
     match SessionTracker::get_or_create_session(&state.db, session_id, session_info).await {
-        Ok(_) => Ok(Json(CreateSessionResponse { session_id })),
+        Ok(_) => {
+            live::publish(LiveEvent::SessionStart {
+                domain_id: domain.id,
+                at: state.clock.now(),
+            });
+            Ok(Json(CreateSessionResponse { session_id }))
+        }
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
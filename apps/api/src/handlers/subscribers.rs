@@ -0,0 +1,163 @@
+// src/handlers/subscribers.rs
+//! Newsletter sign-up with double opt-in
+//!
+//! Domain-scoped like the blog routes. `POST /subscribe` answers the same way
+//! whether or not the address was already subscribed, so it can't be used to
+//! find out who reads a blog.
+use super::auth::ErrorResponse;
+use crate::services::email_templates::{self, EmailKind};
+use crate::services::subscribers::{self, Subscription};
+use crate::validation::extractors::ValidatedJson;
+use crate::{AppState, DomainContext};
+use axum::{
+    Extension, Router,
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SubscribeRequest {
+    #[validate(
+        email(message = "Invalid email format"),
+        length(max = 255, message = "Email must be at most 255 characters")
+    )]
+    pub email: String,
+}
+
+#[derive(Serialize)]
+pub struct SubscribeResponse {
+    pub message: &'static str,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmQuery {
+    pub token: String,
+}
+
+#[derive(Serialize)]
+pub struct ConfirmResponse {
+    pub email: String,
+    pub confirmed_at: Option<DateTime<Utc>>,
+}
+
+/// Subscribe an email to the domain and send it a confirmation link
+pub async fn subscribe(
+    Extension(domain): Extension<DomainContext>,
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(payload): ValidatedJson<SubscribeRequest>,
+) -> Result<(StatusCode, Json<SubscribeResponse>), (StatusCode, Json<ErrorResponse>)> {
+    // Without email there is no way to confirm, so don't store anything
+    let Some(mailer) = state.mailer.clone() else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new(
+                "email_unavailable",
+                "Subscriptions are not available right now",
+            )),
+        ));
+    };
+
+    let email = payload.email.trim().to_lowercase();
+    let subscription = subscribers::subscribe(&state.db, state.clock.as_ref(), domain.id, &email)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, domain_id = domain.id, "Failed to store subscriber");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(
+                    "database_error",
+                    "Failed to store subscription",
+                )),
+            )
+        })?;
+
+    if let Subscription::Pending { token, expires_at } = subscription {
+        let (template, _) = email_templates::resolve(
+            &state.db,
+            Some(domain.id),
+            EmailKind::SubscriptionConfirmation,
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, domain_id = domain.id, "Failed to load email template");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(
+                    "database_error",
+                    "Failed to send the confirmation email",
+                )),
+            )
+        })?;
+        let message = subscribers::confirmation_email(
+            &template,
+            &email,
+            &domain.name,
+            &domain.hostname,
+            &token,
+            expires_at,
+        );
+        mailer.send_boxed(message).await.map_err(|e| {
+            tracing::error!(error = %e, domain_id = domain.id, "Failed to send confirmation email");
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(ErrorResponse::new(
+                    "email_failed",
+                    "Failed to send the confirmation email",
+                )),
+            )
+        })?;
+    }
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(SubscribeResponse {
+            message: "Check your inbox to confirm your subscription",
+        }),
+    ))
+}
+
+/// Confirm a subscription from the link in its confirmation email
+pub async fn confirm(
+    Extension(domain): Extension<DomainContext>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ConfirmQuery>,
+) -> Result<Json<ConfirmResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let subscriber = subscribers::confirm(&state.db, state.clock.as_ref(), domain.id, &query.token)
+        .await
+        .map_err(|_| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(
+                    "database_error",
+                    "Failed to confirm subscription",
+                )),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::new(
+                    "invalid_token",
+                    "This confirmation link is invalid or has expired",
+                )),
+            )
+        })?;
+
+    Ok(Json(ConfirmResponse {
+        email: subscriber.email,
+        confirmed_at: subscriber.confirmed_at,
+    }))
+}
+
+/// Create subscribers router
+pub fn subscribers_router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", post(subscribe))
+        .route("/confirm", get(confirm))
+}
@@ -1,8 +1,9 @@
 use axum::{
+    Json,
     extract::{Request, State},
     http::{HeaderMap, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
@@ -17,7 +18,7 @@ pub mod telemetry;
 pub mod utils;
 pub mod validation;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "test-utils"))]
 pub mod test_utils;
 
 // Re-export commonly used types
@@ -35,6 +36,56 @@ pub struct DomainContext {
     pub name: String,
     pub theme_config: serde_json::Value,
     pub categories: Vec<String>,
+    /// Feature flags from `domains.features`; see [`DomainContext::feature_enabled`]
+    #[serde(default)]
+    pub features: serde_json::Value,
+    /// Time zone analytics days and hours are bucketed in; see [`services::timezones`]
+    #[serde(default = "services::timezones::default_timezone")]
+    pub timezone: String,
+    /// Origins allowed to call the blog and session routes cross-origin;
+    /// empty means the global `CORS_ORIGINS`. See [`middleware::cors`]
+    #[serde(default)]
+    pub cors_origins: Vec<String>,
+}
+
+/// Features a domain can switch off through `/admin/domain/features`
+pub const DOMAIN_FEATURES: &[&str] = &["comments", "search", "rss", "hreflang"];
+
+impl DomainContext {
+    /// Whether a feature is on for this domain. Features are on unless the
+    /// domain explicitly sets them to `false`.
+    pub fn feature_enabled(&self, name: &str) -> bool {
+        self.features
+            .get(name)
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(true)
+    }
+}
+
+/// Platform-wide role stored in `users.role`. Per-domain access lives in
+/// `user_domain_permissions` instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    PlatformAdmin,
+    /// Legacy name for a platform admin; granted exactly the same access
+    SuperAdmin,
+    /// Anyone else, whose access comes from their domain permissions
+    DomainUser,
+}
+
+impl Role {
+    pub fn parse(role: &str) -> Self {
+        match role {
+            "platform_admin" => Role::PlatformAdmin,
+            "super_admin" => Role::SuperAdmin,
+            _ => Role::DomainUser,
+        }
+    }
+
+    /// Whether the role can act on every domain and manage the platform
+    pub fn is_platform_level(self) -> bool {
+        matches!(self, Role::PlatformAdmin | Role::SuperAdmin)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,6 +95,15 @@ pub struct UserContext {
     pub name: String,
     pub role: String,
     pub domain_permissions: Vec<DomainPermission>,
+    /// Platform admin acting as this user via `/admin/impersonate/{user_id}`
+    #[serde(default)]
+    pub impersonator_id: Option<i32>,
+}
+
+impl UserContext {
+    pub fn role(&self) -> Role {
+        Role::parse(&self.role)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +121,67 @@ pub struct AnalyticsContext {
 
 pub struct AppState {
     pub db: PgPool,
+    pub storage: Arc<dyn services::media::Storage>,
+    /// Live rate limiter thresholds, changed through `PUT /admin/rate-limits`
+    pub rate_limits: middleware::RateLimits,
+    /// Where page views, searches and custom events are recorded
+    pub events: services::event_buffer::EventBuffer,
+    /// DNS lookups for domain verification
+    pub dns: Arc<dyn services::domain_verification::DnsResolver>,
+    /// Strike counts and temporary bans for abusive clients
+    pub abuse: services::abuse::AbuseTracker,
+    /// Outgoing email for public flows; `None` without `SMTP_HOST`
+    pub mailer: Option<Arc<dyn services::digest::SharedMailer>>,
+    /// Assembled `/analytics/dashboard` responses, kept for a short while
+    pub dashboard_cache: services::response_cache::ResponseCache,
+    /// Current time for token expiry, analytics windows and calendars
+    pub clock: Arc<dyn services::clock::Clock>,
+    /// Probes of the database and optional services for `/health/ready`
+    pub health: services::health::HealthRegistry,
+    /// JWT settings and parsed keys, loaded once; read through [`AppState::jwt_config`]
+    pub jwt: handlers::auth::JwtConfig,
+    /// The maintenance mode setting, re-read every few seconds
+    pub maintenance: services::maintenance::MaintenanceFlag,
+}
+
+impl AppState {
+    /// Build application state, selecting backends from the environment.
+    /// Events are written as they are recorded; the server swaps in a
+    /// buffered writer.
+    ///
+    /// Panics when the JWT settings are missing or the RS256 keys can't be
+    /// loaded, so a misconfigured server fails at startup.
+    pub fn new(db: PgPool) -> Self {
+        let smtp = services::digest::SmtpMailer::from_env().map(Arc::new);
+        let mut health = services::health::HealthRegistry::default()
+            .with(services::health::DatabaseCheck(db.clone()));
+        if let Some(smtp) = &smtp {
+            health = health.with(services::health::SmtpCheck(smtp.clone()));
+        }
+        let jwt = handlers::auth::JwtConfig::from_env()
+            .unwrap_or_else(|e| panic!("Invalid JWT configuration: {e}"));
+        Self {
+            events: services::event_buffer::EventBuffer::unbuffered(db.clone()),
+            db,
+            storage: services::media::storage_from_env(),
+            rate_limits: middleware::RateLimits::default(),
+            dns: Arc::new(services::domain_verification::SystemResolver),
+            abuse: services::abuse::AbuseTracker::default(),
+            mailer: smtp.map(|mailer| mailer as Arc<dyn services::digest::SharedMailer>),
+            dashboard_cache: services::response_cache::ResponseCache::new(
+                services::response_cache::dashboard_ttl(),
+            ),
+            clock: Arc::new(services::clock::SystemClock),
+            health,
+            jwt,
+            maintenance: services::maintenance::MaintenanceFlag::default(),
+        }
+    }
+
+    /// The JWT settings loaded at startup, telling time by `clock`
+    pub fn jwt_config(&self) -> handlers::auth::JwtConfig {
+        self.jwt.clone().with_clock(self.clock.clone())
+    }
 }
 
 // Helper struct for database operations
@@ -71,6 +192,63 @@ struct DomainContextDb {
     pub name: String,
     pub theme_config: serde_json::Value,
     pub categories: serde_json::Value,
+    pub features: serde_json::Value,
+    pub timezone: String,
+    pub cors_origins: serde_json::Value,
+    pub verified: bool,
+}
+
+/// How `domain_middleware` picks the hostname of a request
+#[derive(Debug, Clone)]
+pub struct DomainResolutionConfig {
+    /// Honor the `x-domain` header over `host`. Only meant for local
+    /// development, where every blog is served from `localhost`.
+    pub allow_header_override: bool,
+    /// Hostname used when the request carries no `host` header
+    pub default_domain: String,
+    /// Where requests for hostnames without a blog are redirected; see
+    /// [`middleware::unknown_domain`]
+    pub unknown_domain_redirect: Option<String>,
+    /// Treat domains whose DNS hasn't been verified as unknown; see
+    /// [`services::domain_verification`]
+    pub require_verification: bool,
+}
+
+impl Default for DomainResolutionConfig {
+    /// `ALLOW_DOMAIN_HEADER_OVERRIDE` defaults to true except when
+    /// `ENVIRONMENT=production`; `DEFAULT_DOMAIN` defaults to `localhost`;
+    /// `UNKNOWN_DOMAIN_REDIRECT` is unset by default;
+    /// `REQUIRE_DOMAIN_VERIFICATION` defaults to false.
+    fn default() -> Self {
+        let production = std::env::var("ENVIRONMENT").is_ok_and(|e| e == "production");
+        Self {
+            allow_header_override: std::env::var("ALLOW_DOMAIN_HEADER_OVERRIDE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(!production),
+            default_domain: std::env::var("DEFAULT_DOMAIN")
+                .ok()
+                .filter(|d| !d.trim().is_empty())
+                .unwrap_or_else(|| "localhost".to_string()),
+            unknown_domain_redirect: middleware::unknown_domain::fallback_url(),
+            require_verification: services::domain_verification::required(),
+        }
+    }
+}
+
+/// Hostname a request is addressed to, without any port
+pub fn request_hostname(headers: &HeaderMap, config: &DomainResolutionConfig) -> String {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    config
+        .allow_header_override
+        .then(|| header("x-domain"))
+        .flatten()
+        .or_else(|| header("host"))
+        .and_then(|h| h.split(':').next())
+        .filter(|h| !h.is_empty())
+        .unwrap_or(config.default_domain.as_str())
+        .to_string()
 }
 
 // Middleware to resolve domain from hostname
@@ -79,20 +257,8 @@ pub async fn domain_middleware(
     mut request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    // Extract hostname from headers
-    let hostname = request
-        .headers()
-        .get("x-domain")
-        .and_then(|v| v.to_str().ok())
-        .or_else(|| {
-            request
-                .headers()
-                .get("host")
-                .and_then(|v| v.to_str().ok())
-                .and_then(|h| h.split(':').next()) // Remove port if present
-        })
-        .unwrap_or("localhost")
-        .to_string();
+    let config = DomainResolutionConfig::default();
+    let hostname = request_hostname(request.headers(), &config);
 
     let span = tracing::info_span!(
         "domain_middleware",
@@ -108,7 +274,8 @@ pub async fn domain_middleware(
     let domain_db = sqlx::query_as::<_, DomainContextDb>(
         r#"
         SELECT id, hostname, name, theme_config, 
-               COALESCE(categories, '[]'::jsonb) as categories
+               COALESCE(categories, '[]'::jsonb) as categories,
+               features, timezone, cors_origins, verified_at IS NOT NULL AS verified
         FROM domains 
         WHERE hostname = $1
         "#,
@@ -122,7 +289,7 @@ pub async fn domain_middleware(
     })?;
 
     let domain = match domain_db {
-        Some(d) => {
+        Some(d) if d.verified || !config.require_verification => {
             span.record("domain_id", d.id);
             span.record("domain_name", &d.name);
             tracing::info!(domain_id = d.id, domain_name = %d.name, "Domain found");
@@ -135,6 +302,7 @@ pub async fn domain_middleware(
                 .iter()
                 .filter_map(|v| v.as_str().map(String::from))
                 .collect::<Vec<_>>();
+            let cors_origins = serde_json::from_value(d.cors_origins).unwrap_or_default();
 
             DomainContext {
                 id: d.id,
@@ -142,11 +310,18 @@ pub async fn domain_middleware(
                 name: d.name,
                 theme_config: d.theme_config,
                 categories,
+                features: d.features,
+                timezone: d.timezone,
+                cors_origins,
             }
         }
-        None => {
-            tracing::warn!("Domain not found for hostname");
-            return Err(StatusCode::NOT_FOUND);
+        _ => {
+            tracing::warn!("Domain not found or not verified for hostname");
+            return Ok(middleware::unknown_domain::unknown_domain_response(
+                &request,
+                &hostname,
+                config.unknown_domain_redirect.as_deref(),
+            ));
         }
     };
 
@@ -177,10 +352,13 @@ pub async fn analytics_middleware(
         .unwrap_or("unknown")
         .to_string();
 
+    // Stored as the privacy setting allows, never with its query string
+    // unless REFERRER_PRIVACY=full
+    let privacy = services::referrers::ReferrerPrivacy::from_env();
     let referrer = headers
         .get("referer")
         .and_then(|v| v.to_str().ok())
-        .map(String::from);
+        .and_then(|v| services::referrers::anonymize_referrer(v, privacy));
 
     // In production, you'd want to handle X-Forwarded-For, X-Real-IP, etc.
     let ip_address = headers
@@ -214,6 +392,27 @@ pub async fn analytics_middleware(
     response
 }
 
+/// A user's roles per domain, as carried in [`UserContext::domain_permissions`]
+pub async fn load_domain_permissions(
+    db: &PgPool,
+    user_id: i32,
+) -> Result<Vec<DomainPermission>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (Option<i32>, String)>(
+        "SELECT domain_id, role FROM user_domain_permissions WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(domain_id, role)| DomainPermission {
+            domain_id: domain_id.unwrap_or(0),
+            role,
+        })
+        .collect())
+}
+
 // Middleware for admin authentication (JWT or session-based)
 pub async fn auth_middleware(
     State(state): State<Arc<AppState>>,
@@ -252,7 +451,7 @@ pub async fn auth_middleware(
     };
 
     // Validate JWT and get user claims
-    let claims = match crate::handlers::auth::validate_jwt_token(token) {
+    let claims = match crate::handlers::auth::validate_jwt_token_with(token, &state.jwt_config()) {
         Ok(claims) => {
             span.record("user_email", &claims.sub);
             tracing::info!(user_email = %claims.sub, "Token validation successful");
@@ -268,7 +467,7 @@ pub async fn auth_middleware(
 
     // Get user and domain permissions from database
     let user = sqlx::query!(
-        "SELECT id, email, name, role FROM users WHERE id = $1 AND email = $2",
+        "SELECT id, email, name, role, must_change_password, sessions_revoked_at FROM users WHERE id = $1 AND email = $2 AND deleted_at IS NULL",
         claims.user_id,
         claims.sub
     )
@@ -291,25 +490,38 @@ pub async fn auth_middleware(
         }
     };
 
-    // Get domain permissions
-    let permissions_rows = sqlx::query!(
-        "SELECT domain_id, role FROM user_domain_permissions WHERE user_id = $1",
-        user.id
-    )
-    .fetch_all(&state.db)
-    .await
-    .map_err(|e| {
-        tracing::error!(error = %e, user_id = user.id, "Error fetching user permissions");
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    // Tokens issued before the user's sessions were revoked no longer count
+    if crate::services::credentials::is_revoked(claims.iat, user.sessions_revoked_at) {
+        tracing::warn!(
+            user_id = user.id,
+            "Token issued before sessions were revoked"
+        );
+        crate::telemetry::record_auth_metrics("token_revoked", false);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    // Until a forced password change is done, only /auth/change-password
+    // (outside this middleware) works. Impersonating admins are let through.
+    if user.must_change_password && claims.impersonator_id.is_none() {
+        tracing::info!(user_id = user.id, "Password change required");
+        crate::telemetry::record_auth_metrics("password_change_required", false);
+        return Ok((
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": "password_change_required",
+                "message": "Change your password via POST /auth/change-password to continue"
+            })),
+        )
+            .into_response());
+    }
 
-    let domain_permissions = permissions_rows
-        .into_iter()
-        .map(|row| DomainPermission {
-            domain_id: row.domain_id.unwrap_or(0),
-            role: row.role,
-        })
-        .collect::<Vec<_>>();
+    // Get domain permissions
+    let domain_permissions = load_domain_permissions(&state.db, user.id)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, user_id = user.id, "Error fetching user permissions");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
 
     span.record("permissions_count", domain_permissions.len());
 
@@ -320,6 +532,7 @@ pub async fn auth_middleware(
         name: user.name,
         role: user.role.unwrap_or_default(),
         domain_permissions,
+        impersonator_id: claims.impersonator_id,
     };
 
     tracing::info!(
@@ -330,7 +543,106 @@ pub async fn auth_middleware(
     );
 
     crate::telemetry::record_auth_metrics("authentication", true);
+
+    // Impersonation tokens stop working once the impersonator loses platform admin
+    if let Some(impersonator_id) = user_context.impersonator_id {
+        let impersonator_role: Option<String> =
+            sqlx::query_scalar("SELECT role FROM users WHERE id = $1 AND deleted_at IS NULL")
+                .bind(impersonator_id)
+                .fetch_optional(&state.db)
+                .await
+                .map_err(|e| {
+                    tracing::error!(error = %e, "Database error while fetching impersonator");
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?
+                .flatten();
+        if !impersonator_role.is_some_and(|role| Role::parse(&role).is_platform_level()) {
+            tracing::warn!(
+                impersonator_id,
+                "Impersonator is no longer a platform admin"
+            );
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    let impersonation = user_context
+        .impersonator_id
+        .map(|impersonator_id| (impersonator_id, user_context.id));
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+
     request.extensions_mut().insert(user_context);
+    let response = next.run(request).await;
 
-    Ok(next.run(request).await)
+    // Flag everything done under impersonation in the audit log
+    if let Some((impersonator_id, user_id)) = impersonation {
+        services::audit::record(
+            &state.db,
+            services::audit::AuditEntry {
+                actor_id: Some(user_id),
+                impersonator_id: Some(impersonator_id),
+                action: format!("{} {}", method, path),
+                details: serde_json::json!({ "status": response.status().as_u16() }),
+            },
+        )
+        .await;
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    fn config(allow_header_override: bool) -> DomainResolutionConfig {
+        DomainResolutionConfig {
+            allow_header_override,
+            default_domain: "default.example".to_string(),
+            unknown_domain_redirect: None,
+            require_verification: false,
+        }
+    }
+
+    #[test]
+    fn test_x_domain_only_honored_when_allowed() {
+        let spoofed = headers(&[
+            ("host", "real.example:8080"),
+            ("x-domain", "victim.example"),
+        ]);
+        assert_eq!(request_hostname(&spoofed, &config(true)), "victim.example");
+        assert_eq!(request_hostname(&spoofed, &config(false)), "real.example");
+    }
+
+    #[test]
+    fn test_super_admin_is_platform_level() {
+        assert_eq!(Role::parse("platform_admin"), Role::PlatformAdmin);
+        assert_eq!(Role::parse("super_admin"), Role::SuperAdmin);
+        assert_eq!(Role::parse("domain_user"), Role::DomainUser);
+        assert_eq!(Role::parse(""), Role::DomainUser);
+        assert!(Role::PlatformAdmin.is_platform_level());
+        assert!(Role::SuperAdmin.is_platform_level());
+        assert!(!Role::DomainUser.is_platform_level());
+    }
+
+    #[test]
+    fn test_missing_host_uses_default_domain() {
+        assert_eq!(
+            request_hostname(&HeaderMap::new(), &config(false)),
+            "default.example"
+        );
+        assert_eq!(
+            request_hostname(&headers(&[("x-domain", "a.example")]), &config(false)),
+            "default.example"
+        );
+    }
 }
@@ -1,17 +1,33 @@
 use api::{
     AppState, analytics_middleware, auth_middleware, domain_middleware,
-    handlers::{HandlerModule, admin::AdminModule, analytics, auth, blog::BlogModule, session},
+    handlers::{
+        HandlerModule, admin::AdminModule, analytics, auth, blog::BlogModule, members, session,
+        subscribers,
+    },
     middleware::{
-        ClientIp, RateLimitConfig, create_rate_limiter, error_tracking_middleware,
-        http_tracing_middleware, performance_monitoring_middleware,
+        ClientIp, ConcurrencyLimit, RateLimitMiddleware, abuse_middleware,
+        domain_cors_middleware, error_tracking_middleware, global_cors_layer,
+        http_tracing_middleware, load_shed_middleware, maintenance_middleware,
+        performance_monitoring_middleware, query_timeout_middleware, request_id_middleware,
+    },
+    services::{
+        alerts::{self, AlertDelivery, start_alert_task},
+        clock::{Clock, SystemClock},
+        daily_stats::start_daily_stats_task,
+        digest::{DigestConfig, SmtpMailer, start_digest_task},
+        event_buffer::{EventBuffer, EventBufferConfig},
+        health::readiness_handler,
+        media::media_root,
+        query_timeout, rate_limits,
+        retention::{RetentionConfig, start_retention_task},
     },
-    telemetry::{TelemetryConfig, init_telemetry},
+    telemetry::{TelemetryConfig, init_telemetry, metrics_handler},
 };
 
 use axum::{Router, extract::ConnectInfo, middleware, response::Html};
 use std::{env, net::SocketAddr, sync::Arc};
 use tokio::net::TcpListener;
-use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::services::ServeDir;
 use tracing::{info, error};
 use utoipa::OpenApi;
 
@@ -65,24 +81,6 @@ async fn health_check(state: Arc<AppState>) -> axum::Json<serde_json::Value> {
     }))
 }
 
-async fn metrics_handler() -> Result<axum::response::Response, axum::http::StatusCode> {
-    match std::env::var("ENABLE_METRICS") {
-        Ok(_) => {
-            let metrics_text = api::telemetry::get_metrics();
-            Ok(axum::response::Response::builder()
-                .status(200)
-                .header("Content-Type", "text/plain; version=0.0.4; charset=utf-8")
-                .body(metrics_text.into())
-                .unwrap())
-        }
-        _ => Ok(axum::response::Response::builder()
-            .status(200)
-            .header("Content-Type", "text/plain; version=0.0.4; charset=utf-8")
-            .body("# Metrics collection disabled\n".into())
-            .unwrap()),
-    }
-}
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Load environment variables
@@ -100,14 +98,65 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Connect to database
     let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
 
-    let pool = sqlx::PgPool::connect(&database_url).await?;
+    // Every connection carries the default statement timeout
+    let pool = sqlx::PgPool::connect_with(query_timeout::connect_options(&database_url)?).await?;
     info!("Database connection established");
 
     // Run migrations
     sqlx::migrate!("../../services/database/migrations").run(&pool).await?;
     info!("Database migrations completed");
 
-    let state = Arc::new(AppState { db: pool });
+    // Background jobs and request handlers tell time by the same clock
+    let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+
+    // Weekly analytics digest emails (opt-in per user)
+    let digest_config = DigestConfig::default();
+    if digest_config.enabled {
+        match SmtpMailer::from_env() {
+            Some(mailer) => {
+                start_digest_task(pool.clone(), mailer, digest_config);
+                info!("Analytics digest job scheduled");
+            }
+            None => error!("DIGEST_ENABLED is set but SMTP_HOST is not configured"),
+        }
+    }
+
+    // Daily pruning of analytics events past the retention window
+    let retention_config = RetentionConfig::default();
+    info!(
+        retention_days = retention_config.retention_days,
+        "Analytics retention job scheduled"
+    );
+    start_retention_task(pool.clone(), clock.clone(), retention_config);
+
+    // Nightly rollup into daily_domain_stats for the dashboards
+    start_daily_stats_task(pool.clone());
+
+    // Traffic alerts; email targets need SMTP_HOST
+    start_alert_task(
+        pool.clone(),
+        clock.clone(),
+        AlertDelivery::new(SmtpMailer::from_env()),
+        alerts::check_interval(),
+    );
+
+    // Page views, searches and custom events are written in batches
+    let events = EventBuffer::spawn(pool.clone(), EventBufferConfig::default());
+    let state = Arc::new(AppState {
+        events: events.clone(),
+        clock,
+        ..AppState::new(pool)
+    });
+
+    // Thresholds saved through PUT /admin/rate-limits outlive restarts
+    match rate_limits::load(&state.db).await {
+        Ok(Some(settings)) => {
+            info!("Loaded saved rate limits");
+            state.rate_limits.update(settings);
+        }
+        Ok(None) => {}
+        Err(e) => error!("Failed to load saved rate limits: {e}"),
+    }
     let app = create_app(state);
 
     let port = env::var("PORT").unwrap_or_else(|_| "8000".to_string());
@@ -126,17 +175,59 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         listener,
         app.into_make_service_with_connect_info::<SocketAddr>(),
     )
+    .with_graceful_shutdown(shutdown_signal())
     .await?;
+
+    // Nothing recorded before shutdown is lost
+    events.shutdown().await;
+    info!("Analytics event buffer flushed");
     Ok(())
 }
 
+/// Resolves on Ctrl+C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl+C: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    info!("Shutdown signal received");
+}
+
 pub fn create_app(state: Arc<AppState>) -> Router {
     // Create rate limiting middleware instances for different route groups
-    // Each rate limiter has different thresholds based on the sensitivity of the routes
-    let default_rate_limiter = create_rate_limiter(RateLimitConfig::default());
-    let auth_rate_limiter = create_rate_limiter(RateLimitConfig::auth());
-    let admin_rate_limiter = create_rate_limiter(RateLimitConfig::admin());
-    let read_only_rate_limiter = create_rate_limiter(RateLimitConfig::read_only());
+    // Each rate limiter has different thresholds based on the sensitivity of the routes,
+    // read from state.rate_limits on every request so admins can change them at runtime
+    let limits = &state.rate_limits;
+    let default_rate_limiter = RateLimitMiddleware::shared(limits.default.clone());
+    let auth_rate_limiter = RateLimitMiddleware::shared(limits.auth.clone());
+    let member_rate_limiter = RateLimitMiddleware::shared(limits.auth.clone());
+    let subscribe_rate_limiter = RateLimitMiddleware::shared(limits.auth.clone());
+    let admin_rate_limiter = RateLimitMiddleware::shared(limits.admin.clone());
+    let read_only_rate_limiter = RateLimitMiddleware::shared(limits.read_only.clone());
+    let tracking_rate_limiter = RateLimitMiddleware::shared(limits.tracking.clone());
 
     Router::new()
         // ===========================================
@@ -159,6 +250,13 @@ pub fn create_app(state: Arc<AppState>) -> Router {
             }),
         )
         
+        // Readiness check - probes the database and optional services such as
+        // SMTP; 503 while a critical one is down
+        .route(
+            "/health/ready",
+            axum::routing::get(readiness_handler).with_state(state.health.clone()),
+        )
+        
         // Test route for domain middleware functionality (development only)
         .route(
             "/test-domain",
@@ -181,6 +279,9 @@ pub fn create_app(state: Arc<AppState>) -> Router {
         // Prometheus metrics endpoint for monitoring and observability
         .route("/metrics", axum::routing::get(metrics_handler))
         
+        // Uploaded media served from the local storage backend
+        .nest_service("/media", ServeDir::new(media_root()))
+        
         // ===========================================
         // AUTHENTICATION ROUTES
         // ===========================================
@@ -206,74 +307,6 @@ pub fn create_app(state: Arc<AppState>) -> Router {
             )),
         )
         
-        // ===========================================
-        // PUBLIC BLOG CONTENT ROUTES (Domain-scoped)
-        // ===========================================
-        // Public-facing blog content: posts, categories, search, etc.
-        // Requires domain context (extracted from subdomain or x-domain header)
-        // Includes analytics tracking for visitor behavior
-        // Read-only rate limiting (more permissive than admin routes)
-        .merge(
-            BlogModule::routes()
-                .layer(middleware::from_fn_with_state(
-                    state.clone(),
-                    domain_middleware,
-                ))
-                .layer(middleware::from_fn(analytics_middleware))
-                .layer(middleware::from_fn(
-                    move |ConnectInfo(addr): ConnectInfo<SocketAddr>, req, next| {
-                        let rate_limiter = read_only_rate_limiter.clone();
-                        async move {
-                            rate_limiter
-                                .apply(ClientIp(addr.ip()), req, next)
-                                .await
-                                .unwrap_or_else(|status| {
-                                    axum::response::Response::builder()
-                                        .status(status)
-                                        .body("Rate limit exceeded".into())
-                                        .unwrap()
-                                })
-                        }
-                    },
-                )),
-        )
-        
-        // ===========================================
-        // USER SESSION TRACKING ROUTES (Domain-scoped)
-        // ===========================================
-        // Session lifecycle management: create, update, end sessions
-        // Used for analytics and user behavior tracking
-        // Requires domain context for proper attribution
-        // Default rate limiting (moderate protection)
-        .nest(
-            "/session",
-            Router::new()
-                .route("/create", axum::routing::post(session::create_session))
-                .route("/update", axum::routing::post(session::update_session))
-                .route("/end", axum::routing::post(session::end_session))
-                .layer(middleware::from_fn_with_state(
-                    state.clone(),
-                    domain_middleware,
-                ))
-                .layer(middleware::from_fn(analytics_middleware))
-                .layer(middleware::from_fn(
-                    move |ConnectInfo(addr): ConnectInfo<SocketAddr>, req, next| {
-                        let rate_limiter = default_rate_limiter.clone();
-                        async move {
-                            rate_limiter
-                                .apply(ClientIp(addr.ip()), req, next)
-                                .await
-                                .unwrap_or_else(|status| {
-                                    axum::response::Response::builder()
-                                        .status(status)
-                                        .body("Rate limit exceeded".into())
-                                        .unwrap()
-                                })
-                        }
-                    },
-                )),
-        )
-        
         // ===========================================
         // ADMIN PANEL ROUTES (Authentication required)
         // ===========================================
@@ -315,7 +348,7 @@ pub fn create_app(state: Arc<AppState>) -> Router {
         )
         
         // ===========================================
-        // ANALYTICS ROUTES (Authentication required)
+        // ANALYTICS ROUTES (Reporting requires authentication)
         // ===========================================
         // Analytics and reporting endpoints:
         // - Dashboard: overview metrics and charts
@@ -327,6 +360,7 @@ pub fn create_app(state: Arc<AppState>) -> Router {
         // 
         // Cross-domain analytics (aggregates data across all user's domains)
         // User permissions determine which domains they can view analytics for
+        // Tracking endpoints are public and domain-scoped instead
         .nest(
             "/analytics",
             Router::new()
@@ -349,27 +383,230 @@ pub fn create_app(state: Arc<AppState>) -> Router {
                     "/real-time",
                     axum::routing::get(analytics::get_realtime_stats),
                 )
+                .route("/ws", axum::routing::get(analytics::live_updates))
                 .route("/export", axum::routing::get(analytics::export_data))
-                // Behavior tracking endpoints
                 .route(
-                    "/behavior",
-                    axum::routing::post(analytics::track_behavior_event),
+                    "/content",
+                    axum::routing::get(analytics::get_content_engagement),
+                )
+                .route(
+                    "/engagement",
+                    axum::routing::get(analytics::get_engagement_stats),
+                )
+                .route(
+                    "/sessions/{session_id}",
+                    axum::routing::get(analytics::get_session_timeline),
                 )
                 .route(
-                    "/search",
-                    axum::routing::post(analytics::track_search_event),
+                    "/visitors",
+                    axum::routing::get(analytics::get_visitor_stats),
                 )
                 .route(
-                    "/search-click",
-                    axum::routing::post(analytics::track_search_click_event),
+                    "/pages",
+                    axum::routing::get(analytics::get_entry_exit_pages),
                 )
                 .route(
-                    "/content-metrics",
-                    axum::routing::post(analytics::track_content_metrics),
+                    "/custom-events",
+                    axum::routing::get(analytics::get_custom_event_stats),
                 )
+                .route("/funnel", axum::routing::post(analytics::analyze_funnel))
                 .layer(middleware::from_fn_with_state(
                     state.clone(),
                     auth_middleware,
+                ))
+                // Behavior tracking endpoints: called by anonymous visitors,
+                // so domain-scoped instead of authenticated, with a
+                // high-volume rate limit of their own. Custom events record
+                // the visitor's IP and user agent like page views do
+                .merge(
+                    analytics::AnalyticsModule::tracking_routes()
+                        .layer(middleware::from_fn_with_state(
+                            state.clone(),
+                            domain_middleware,
+                        ))
+                        .layer(middleware::from_fn(analytics_middleware))
+                        .layer(middleware::from_fn(
+                            move |ConnectInfo(addr): ConnectInfo<SocketAddr>, req, next| {
+                                let rate_limiter = tracking_rate_limiter.clone();
+                                async move {
+                                    rate_limiter
+                                        .apply(ClientIp(addr.ip()), req, next)
+                                        .await
+                                        .unwrap_or_else(|status| {
+                                            axum::response::Response::builder()
+                                                .status(status)
+                                                .body("Rate limit exceeded".into())
+                                                .unwrap()
+                                        })
+                                }
+                            },
+                        ))
+                        .layer(middleware::from_fn_with_state(
+                            state.clone(),
+                            maintenance_middleware,
+                        )),
+                ),
+        )
+        
+        // CORS for everything above: the global CORS_ORIGINS list. The
+        // domain-scoped routes below answer their domain's own origins instead
+        .layer(global_cors_layer())
+        
+        // ===========================================
+        // PUBLIC BLOG CONTENT ROUTES (Domain-scoped)
+        // ===========================================
+        // Public-facing blog content: posts, categories, search, etc.
+        // Requires domain context (extracted from subdomain or x-domain header)
+        // Includes analytics tracking for visitor behavior
+        // Read-only rate limiting (more permissive than admin routes)
+        // Cross-origin access limited to the domain's own origins
+        // Answers 503 while maintenance mode is on
+        .merge(
+            BlogModule::routes()
+                .layer(middleware::from_fn(domain_cors_middleware))
+                .layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    domain_middleware,
+                ))
+                .layer(middleware::from_fn(analytics_middleware))
+                .layer(middleware::from_fn(
+                    move |ConnectInfo(addr): ConnectInfo<SocketAddr>, req, next| {
+                        let rate_limiter = read_only_rate_limiter.clone();
+                        async move {
+                            rate_limiter
+                                .apply(ClientIp(addr.ip()), req, next)
+                                .await
+                                .unwrap_or_else(|status| {
+                                    axum::response::Response::builder()
+                                        .status(status)
+                                        .body("Rate limit exceeded".into())
+                                        .unwrap()
+                                })
+                        }
+                    },
+                ))
+                .layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    maintenance_middleware,
+                )),
+        )
+        
+        // ===========================================
+        // USER SESSION TRACKING ROUTES (Domain-scoped)
+        // ===========================================
+        // Session lifecycle management: create, update, end sessions
+        // Used for analytics and user behavior tracking
+        // Requires domain context for proper attribution
+        // Default rate limiting (moderate protection)
+        // Cross-origin access limited to the domain's own origins
+        // Answers 503 while maintenance mode is on
+        .nest(
+            "/session",
+            Router::new()
+                .route("/create", axum::routing::post(session::create_session))
+                .route("/update", axum::routing::post(session::update_session))
+                .route("/end", axum::routing::post(session::end_session))
+                .layer(middleware::from_fn(domain_cors_middleware))
+                .layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    domain_middleware,
+                ))
+                .layer(middleware::from_fn(analytics_middleware))
+                .layer(middleware::from_fn(
+                    move |ConnectInfo(addr): ConnectInfo<SocketAddr>, req, next| {
+                        let rate_limiter = default_rate_limiter.clone();
+                        async move {
+                            rate_limiter
+                                .apply(ClientIp(addr.ip()), req, next)
+                                .await
+                                .unwrap_or_else(|status| {
+                                    axum::response::Response::builder()
+                                        .status(status)
+                                        .body("Rate limit exceeded".into())
+                                        .unwrap()
+                                })
+                        }
+                    },
+                ))
+                .layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    maintenance_middleware,
+                )),
+        )
+        
+        // ===========================================
+        // MEMBER SIGN-IN ROUTES (Domain-scoped)
+        // ===========================================
+        // Readers sign in for members-only posts, separately from /auth
+        // Requires domain context: members belong to one domain
+        // Same rate limiting as /auth
+        // Cross-origin access limited to the domain's own origins
+        // Answers 503 while maintenance mode is on
+        .nest(
+            "/members",
+            members::members_router()
+                .layer(middleware::from_fn(domain_cors_middleware))
+                .layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    domain_middleware,
+                ))
+                .layer(middleware::from_fn(
+                    move |ConnectInfo(addr): ConnectInfo<SocketAddr>, req, next| {
+                        let rate_limiter = member_rate_limiter.clone();
+                        async move {
+                            rate_limiter
+                                .apply(ClientIp(addr.ip()), req, next)
+                                .await
+                                .unwrap_or_else(|status| {
+                                    axum::response::Response::builder()
+                                        .status(status)
+                                        .body("Rate limit exceeded".into())
+                                        .unwrap()
+                                })
+                        }
+                    },
+                ))
+                .layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    maintenance_middleware,
+                )),
+        )
+        
+        // ===========================================
+        // NEWSLETTER SUBSCRIPTION ROUTES (Domain-scoped)
+        // ===========================================
+        // Readers subscribe by email and confirm through the emailed link
+        // Requires domain context: subscribers belong to one domain
+        // Same rate limiting as /auth, as every subscribe sends an email
+        // Cross-origin access limited to the domain's own origins
+        // Answers 503 while maintenance mode is on
+        .nest(
+            "/subscribe",
+            subscribers::subscribers_router()
+                .layer(middleware::from_fn(domain_cors_middleware))
+                .layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    domain_middleware,
+                ))
+                .layer(middleware::from_fn(
+                    move |ConnectInfo(addr): ConnectInfo<SocketAddr>, req, next| {
+                        let rate_limiter = subscribe_rate_limiter.clone();
+                        async move {
+                            rate_limiter
+                                .apply(ClientIp(addr.ip()), req, next)
+                                .await
+                                .unwrap_or_else(|status| {
+                                    axum::response::Response::builder()
+                                        .status(status)
+                                        .body("Rate limit exceeded".into())
+                                        .unwrap()
+                                })
+                        }
+                    },
+                ))
+                .layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    maintenance_middleware,
                 )),
         )
         
@@ -378,6 +615,13 @@ pub fn create_app(state: Arc<AppState>) -> Router {
         // ===========================================
         // Applied to ALL routes in order of application:
         
+        // Query timeout: answers 504 once a request outlasts its database
+        // budget (longer for export routes)
+        .layer(middleware::from_fn_with_state(
+            query_timeout::RequestBudgets::from_env(),
+            query_timeout_middleware,
+        ))
+        
         // HTTP tracing: logs all requests/responses for debugging
         .layer(middleware::from_fn(http_tracing_middleware))
         
@@ -387,33 +631,22 @@ pub fn create_app(state: Arc<AppState>) -> Router {
         // Error tracking: captures and reports application errors
         .layer(middleware::from_fn(error_tracking_middleware))
         
-        // CORS configuration: enables cross-origin requests from frontend
-        .layer({
-            // Parse allowed origins from environment variable
-            // Default: local development URLs (localhost:3000, localhost:5173)
-            let cors_origins = env::var("CORS_ORIGINS")
-                .unwrap_or_else(|_| "http://localhost:3000,http://localhost:5173".to_string());
-
-            let origins: Vec<_> = cors_origins
-                .split(',')
-                .map(|s| s.trim().parse().expect("Invalid CORS origin"))
-                .collect();
-
-            CorsLayer::new()
-                .allow_origin(AllowOrigin::list(origins))
-                .allow_methods([
-                    axum::http::Method::GET,
-                    axum::http::Method::POST,
-                    axum::http::Method::PUT,
-                    axum::http::Method::DELETE,
-                    axum::http::Method::OPTIONS,
-                ])
-                .allow_headers([
-                    axum::http::header::CONTENT_TYPE,
-                    axum::http::header::AUTHORIZATION,
-                    axum::http::HeaderName::from_static("x-domain"),
-                ])
-                .allow_credentials(true)
-        })
+        // Abuse detection: refuses banned IPs with 403 before anything else
+        // runs, and counts 404s and failed logins towards a ban
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            abuse_middleware,
+        ))
+        
+        // Load shedding: answers 503 once MAX_IN_FLIGHT_REQUESTS are being
+        // handled, except for /health and /health/ready
+        .layer(middleware::from_fn_with_state(
+            ConcurrencyLimit::from_env(),
+            load_shed_middleware,
+        ))
+        
+        // Request ID: reads or generates X-Request-Id before tracing runs
+        // and echoes it on the response
+        .layer(middleware::from_fn(request_id_middleware))
         .with_state(state)
 }
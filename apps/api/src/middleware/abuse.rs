@@ -0,0 +1,58 @@
+// src/middleware/abuse.rs
+//! Refuse banned clients and count strikes against the rest
+//!
+//! Layered onto every route; see `services::abuse`. The client is identified
+//! by its socket address, like the rate limiters, so a forged
+//! `X-Forwarded-For` can't get somebody else banned.
+
+use crate::{AppState, services::abuse::AbuseReason};
+use axum::{
+    Json,
+    extract::{ConnectInfo, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{net::SocketAddr, sync::Arc};
+
+/// Answer 403 while the client is banned, and record 404s and failed logins
+pub async fn abuse_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(ip) = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+    else {
+        return next.run(request).await;
+    };
+
+    if let Some(ban) = state.abuse.banned(ip) {
+        return (
+            StatusCode::FORBIDDEN,
+            [(header::RETRY_AFTER, ban.retry_after_secs.to_string())],
+            Json(serde_json::json!({
+                "error": "ip_banned",
+                "message": "Too many suspicious requests from this address, please try again later",
+                "reason": ban.reason,
+            })),
+        )
+            .into_response();
+    }
+
+    let is_auth = request.uri().path().starts_with("/auth/");
+    let response = next.run(request).await;
+
+    match response.status() {
+        StatusCode::NOT_FOUND => {
+            state.abuse.record(ip, AbuseReason::NotFound);
+        }
+        StatusCode::UNAUTHORIZED if is_auth => {
+            state.abuse.record(ip, AbuseReason::AuthFailure);
+        }
+        _ => {}
+    }
+    response
+}
@@ -1,3 +1,4 @@
+use crate::middleware::RequestId;
 use crate::utils::{ErrorSpan, PerformanceSpan, SpanContext};
 use axum::{extract::Request, http::StatusCode, middleware::Next, response::Response};
 use std::time::Instant;
@@ -12,8 +13,12 @@ pub async fn http_tracing_middleware(request: Request, next: Next) -> Response {
         let uri = request.uri().clone();
         let path = uri.path().to_string();
 
-        // Create request context for correlation
-        let span_context = SpanContext::new(&operation_name);
+        // Create request context for correlation, reusing the id assigned by
+        // `request_id_middleware` so logs match the `X-Request-Id` header
+        let mut span_context = SpanContext::new(&operation_name);
+        if let Some(RequestId(id)) = request.extensions().get::<RequestId>() {
+            span_context = span_context.with_request_id(id.clone());
+        }
 
         // Create a span for the entire HTTP request
         let span = tracing::info_span!(
@@ -0,0 +1,222 @@
+// src/middleware/cors.rs
+//! Cross-origin access for the API
+//!
+//! Auth, admin and analytics routes share the `CORS_ORIGINS` list through
+//! [`global_cors_layer`]. The blog and session routes answer each domain's
+//! own `domains.cors_origins` instead, so one blog's frontend can't read
+//! another's: [`domain_cors_middleware`] runs after `domain_middleware` and
+//! turns away requests from any other origin. A domain that hasn't configured
+//! origins falls back to the global list.
+
+use super::request_id::REQUEST_ID_HEADER;
+use crate::{DomainContext, handlers::blog::SESSION_HEADER};
+use axum::{
+    Json,
+    extract::Request,
+    http::{HeaderName, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+const ALLOWED_METHODS: [Method; 5] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::DELETE,
+    Method::OPTIONS,
+];
+
+/// How long browsers may cache a preflight answer
+const PREFLIGHT_MAX_AGE_SECONDS: &str = "600";
+
+fn allowed_headers() -> [HeaderName; 5] {
+    [
+        header::CONTENT_TYPE,
+        header::AUTHORIZATION,
+        HeaderName::from_static("x-domain"),
+        HeaderName::from_static(SESSION_HEADER),
+        HeaderName::from_static(REQUEST_ID_HEADER),
+    ]
+}
+
+/// Origins allowed across the API.
+/// Configurable via `CORS_ORIGINS`, comma-separated (default the local
+/// frontends, `http://localhost:3000,http://localhost:5173`).
+pub fn global_origins() -> Vec<String> {
+    let origins = std::env::var("CORS_ORIGINS")
+        .unwrap_or_else(|_| "http://localhost:3000,http://localhost:5173".to_string());
+    parse_origins(&origins)
+}
+
+fn parse_origins(origins: &str) -> Vec<String> {
+    origins
+        .split(',')
+        .map(normalize_origin)
+        .filter(|origin| !origin.is_empty())
+        .collect()
+}
+
+fn normalize_origin(origin: &str) -> String {
+    origin.trim().trim_end_matches('/').to_string()
+}
+
+/// Whether `origin` is a `scheme://host[:port]` browsers could send
+pub fn is_valid_origin(origin: &str) -> bool {
+    let Some((scheme, host)) = origin.split_once("://") else {
+        return false;
+    };
+    matches!(scheme, "http" | "https")
+        && !host.is_empty()
+        && !host.contains(['/', '?', '#', '@'])
+        && HeaderValue::from_str(origin).is_ok()
+}
+
+/// CORS for the routes that aren't domain-scoped
+pub fn global_cors_layer() -> CorsLayer {
+    let origins: Vec<HeaderValue> = global_origins()
+        .iter()
+        .map(|origin| origin.parse().expect("Invalid CORS origin"))
+        .collect();
+
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods(ALLOWED_METHODS)
+        .allow_headers(allowed_headers())
+        .expose_headers([HeaderName::from_static(REQUEST_ID_HEADER)])
+        .allow_credentials(true)
+}
+
+/// Whether `domain` accepts requests from `origin`
+pub fn origin_allowed(domain: &DomainContext, origin: &str) -> bool {
+    let origin = normalize_origin(origin);
+    if domain.cors_origins.is_empty() {
+        global_origins().contains(&origin)
+    } else {
+        domain
+            .cors_origins
+            .iter()
+            .any(|allowed| normalize_origin(allowed) == origin)
+    }
+}
+
+/// Per-domain CORS, layered inside `domain_middleware`. Requests without an
+/// `Origin` are not cross-origin and pass untouched; requests from an origin
+/// the domain doesn't allow get `403`; preflights from one it does are
+/// answered here.
+pub async fn domain_cors_middleware(request: Request, next: Next) -> Response {
+    let Some(origin) = request.headers().get(header::ORIGIN).cloned() else {
+        return next.run(request).await;
+    };
+    let Some(domain) = request.extensions().get::<DomainContext>() else {
+        return next.run(request).await;
+    };
+
+    let allowed = origin
+        .to_str()
+        .is_ok_and(|origin| origin_allowed(domain, origin));
+    if !allowed {
+        tracing::warn!(
+            domain_id = domain.id,
+            origin = ?origin,
+            "Cross-origin request from an origin the domain doesn't allow"
+        );
+        return (
+            StatusCode::FORBIDDEN,
+            [(header::VARY, HeaderValue::from_static("origin"))],
+            Json(serde_json::json!({
+                "error": "origin_not_allowed",
+                "message": "Cross-origin requests from this origin are not allowed",
+            })),
+        )
+            .into_response();
+    }
+
+    let preflight = request.method() == Method::OPTIONS
+        && request
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+
+    let mut response = if preflight {
+        let mut response = StatusCode::NO_CONTENT.into_response();
+        let headers = response.headers_mut();
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            join_header(ALLOWED_METHODS.iter().map(Method::as_str)),
+        );
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_HEADERS,
+            join_header(allowed_headers().iter().map(HeaderName::as_str)),
+        );
+        headers.insert(
+            header::ACCESS_CONTROL_MAX_AGE,
+            HeaderValue::from_static(PREFLIGHT_MAX_AGE_SECONDS),
+        );
+        response
+    } else {
+        let mut response = next.run(request).await;
+        response.headers_mut().insert(
+            header::ACCESS_CONTROL_EXPOSE_HEADERS,
+            HeaderValue::from_static(REQUEST_ID_HEADER),
+        );
+        response
+    };
+
+    let headers = response.headers_mut();
+    headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    headers.insert(
+        header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+        HeaderValue::from_static("true"),
+    );
+    headers.append(header::VARY, HeaderValue::from_static("origin"));
+    response
+}
+
+fn join_header<'a>(values: impl Iterator<Item = &'a str>) -> HeaderValue {
+    HeaderValue::from_str(&values.collect::<Vec<_>>().join(","))
+        .expect("method and header names are valid header values")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn domain(cors_origins: &[&str]) -> DomainContext {
+        DomainContext {
+            id: 1,
+            hostname: "example.com".to_string(),
+            name: "Example".to_string(),
+            theme_config: serde_json::json!({}),
+            categories: vec![],
+            features: serde_json::json!({}),
+            timezone: "UTC".to_string(),
+            cors_origins: cors_origins.iter().map(|o| o.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_origins() {
+        assert_eq!(
+            parse_origins(" https://a.com/, ,http://localhost:3000"),
+            vec!["https://a.com", "http://localhost:3000"]
+        );
+    }
+
+    #[test]
+    fn test_is_valid_origin() {
+        assert!(is_valid_origin("https://blog.example.com"));
+        assert!(is_valid_origin("http://localhost:5173"));
+        assert!(!is_valid_origin("blog.example.com"));
+        assert!(!is_valid_origin("ftp://example.com"));
+        assert!(!is_valid_origin("https://example.com/path"));
+        assert!(!is_valid_origin("https://"));
+    }
+
+    #[test]
+    fn test_domain_origins_are_matched_exactly() {
+        let domain = domain(&["https://blog.example.com/"]);
+        assert!(origin_allowed(&domain, "https://blog.example.com"));
+        assert!(!origin_allowed(&domain, "http://blog.example.com"));
+        assert!(!origin_allowed(&domain, "https://evil.example.com"));
+    }
+}
@@ -0,0 +1,84 @@
+// src/middleware/load_shed.rs
+//! Shed requests beyond a fixed number in flight
+//!
+//! Without a cap a traffic spike queues everything on the database pool and
+//! every request slows down until it times out. Past the limit new requests
+//! are answered straight away with 503 instead, and the ones already running
+//! finish normally. `/health` and `/health/ready` are never shed so load
+//! balancers can still tell a busy instance from a dead one.
+
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Seconds clients are told to wait before retrying
+const RETRY_AFTER_SECONDS: &str = "5";
+
+/// Paths that are let through however busy the server is
+const EXEMPT_PATHS: &[&str] = &["/health", "/health/ready"];
+
+/// How many requests may be handled at once.
+/// Configurable via `MAX_IN_FLIGHT_REQUESTS` (default 512, 0 turns it off).
+pub fn max_in_flight() -> usize {
+    std::env::var("MAX_IN_FLIGHT_REQUESTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(512)
+}
+
+/// Slots for requests in flight, shared by every clone
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimit {
+    slots: Option<Arc<Semaphore>>,
+}
+
+impl ConcurrencyLimit {
+    /// At most `max` requests at once; `0` means no limit
+    pub fn new(max: usize) -> Self {
+        Self {
+            slots: (max > 0).then(|| Arc::new(Semaphore::new(max))),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(max_in_flight())
+    }
+}
+
+/// Answer 503 with a JSON body while every slot is taken
+pub async fn load_shed_middleware(
+    State(limit): State<ConcurrencyLimit>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(slots) = limit.slots else {
+        return next.run(request).await;
+    };
+    if EXEMPT_PATHS.contains(&request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    // Held until the handler has produced its response
+    let Ok(_permit) = slots.try_acquire_owned() else {
+        tracing::warn!(
+            path = request.uri().path(),
+            "Request shed, server at capacity"
+        );
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, RETRY_AFTER_SECONDS)],
+            Json(serde_json::json!({
+                "error": "overloaded",
+                "message": "The server is busy, please try again shortly",
+            })),
+        )
+            .into_response();
+    };
+    next.run(request).await
+}
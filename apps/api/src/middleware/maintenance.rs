@@ -0,0 +1,48 @@
+// src/middleware/maintenance.rs
+//! Refuse public traffic while maintenance mode is on
+//!
+//! Layered onto the public route groups only; see `services::maintenance`.
+
+use crate::{AppState, services::maintenance};
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+/// Seconds clients are told to wait before retrying
+const RETRY_AFTER_SECONDS: &str = "120";
+
+/// Answer 503 with a JSON body while maintenance mode is on
+pub async fn maintenance_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    // Fail open: a settings lookup error shouldn't take the blog down by itself
+    let enabled = state
+        .maintenance
+        .is_enabled(&state.db)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!(error = %e, "Failed to read maintenance mode setting");
+            maintenance::forced_by_env()
+        });
+
+    if !enabled {
+        return next.run(request).await;
+    }
+
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(header::RETRY_AFTER, RETRY_AFTER_SECONDS)],
+        Json(serde_json::json!({
+            "error": "maintenance",
+            "message": "The site is down for maintenance, please try again shortly",
+        })),
+    )
+        .into_response()
+}
@@ -1,7 +1,23 @@
+pub mod abuse;
 pub mod common;
+pub mod cors;
+pub mod load_shed;
+pub mod maintenance;
+pub mod query_timeout;
 pub mod rate_limit;
+pub mod request_id;
+pub mod unknown_domain;
 
-pub use rate_limit::{ClientIp, RateLimitConfig, RateLimitMiddleware, create_rate_limiter};
+pub use abuse::abuse_middleware;
+pub use cors::{domain_cors_middleware, global_cors_layer};
+pub use load_shed::{ConcurrencyLimit, load_shed_middleware};
+pub use maintenance::maintenance_middleware;
+pub use query_timeout::query_timeout_middleware;
+pub use rate_limit::{
+    ClientIp, RateLimitConfig, RateLimitMiddleware, RateLimitSettings, RateLimits,
+    create_rate_limiter,
+};
+pub use request_id::{REQUEST_ID_HEADER, RequestId, request_id_middleware};
 
 pub use common::{
     error_tracking_middleware, http_tracing_middleware, performance_monitoring_middleware,
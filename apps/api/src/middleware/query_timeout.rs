@@ -0,0 +1,47 @@
+// src/middleware/query_timeout.rs
+//! Give up on requests that run past their database budget
+//!
+//! The budget comes from `services::query_timeout`; the request's queries are
+//! cancelled by Postgres' `statement_timeout` on the same budget, so the pool
+//! gets the connection back.
+
+use crate::services::query_timeout::RequestBudgets;
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+/// Answer 504 with a JSON body when the handler outlasts the budget
+pub async fn query_timeout_middleware(
+    State(budgets): State<RequestBudgets>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let budget = budgets.budget_for(request.uri().path());
+    if budget.is_zero() {
+        return next.run(request).await;
+    }
+
+    let path = request.uri().path().to_string();
+    match tokio::time::timeout(budget, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            tracing::warn!(
+                path,
+                budget_ms = budget.as_millis() as u64,
+                "Request timed out"
+            );
+            (
+                StatusCode::GATEWAY_TIMEOUT,
+                Json(serde_json::json!({
+                    "error": "timeout",
+                    "message": "The request took too long, try a narrower query",
+                })),
+            )
+                .into_response()
+        }
+    }
+}
@@ -10,14 +10,15 @@ use governor::{
     clock::DefaultClock,
     state::{InMemoryState, NotKeyed},
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{
     net::{IpAddr, SocketAddr},
     num::NonZeroU32,
-    sync::Arc,
+    sync::{Arc, PoisonError, RwLock},
     time::{Duration, Instant},
 };
 use tracing::{info, warn};
+use validator::{Validate, ValidationError, ValidationErrors};
 
 /// Represents the real client IP address, extracted from headers or socket info.
 #[derive(Debug, Clone)]
@@ -65,9 +66,8 @@ where
 }
 
 /// Configuration for different rate limiting scenarios.
-/// Can be customized via environment variables.
-// TODO: Implement dynamic configuration loading
-#[derive(Debug, Clone, Deserialize)]
+/// Platform admins can change it at runtime through `PUT /admin/rate-limits`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RateLimitConfig {
     /// Maximum requests per time window
     pub max_requests: NonZeroU32,
@@ -86,7 +86,7 @@ impl RateLimitConfig {
     }
 
     /// Admin endpoints - strict rate limiting
-    /// 10 requests per minute
+    /// 60 requests per minute
     pub fn admin() -> Self {
         Self {
             max_requests: NonZeroU32::new(60).unwrap(),
@@ -103,6 +103,15 @@ impl RateLimitConfig {
         }
     }
 
+    /// Public analytics tracking endpoints - high volume
+    /// 300 requests per minute, since one page view can send several events
+    pub fn tracking() -> Self {
+        Self {
+            max_requests: NonZeroU32::new(300).unwrap(),
+            window_seconds: 60,
+        }
+    }
+
     /// Default rate limiting for general API endpoints
     /// 30 requests per minute
     pub fn default() -> Self {
@@ -122,16 +131,140 @@ impl RateLimitConfig {
     }
 }
 
+/// Longest window an admin can configure: one day
+pub const MAX_WINDOW_SECONDS: u64 = 86_400;
+
+/// A limiter configuration that can be replaced while the server runs
+pub type SharedRateLimitConfig = Arc<RwLock<RateLimitConfig>>;
+
+/// Thresholds for every group of rate-limited routes, as stored in the
+/// `rate_limits` setting and accepted by `PUT /admin/rate-limits`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitSettings {
+    /// `/auth` and `/members/login`
+    pub auth: RateLimitConfig,
+    /// `/admin`
+    pub admin: RateLimitConfig,
+    /// Public blog routes
+    pub read_only: RateLimitConfig,
+    /// Public analytics tracking routes
+    pub tracking: RateLimitConfig,
+    /// Session and other general API routes
+    pub default: RateLimitConfig,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            auth: RateLimitConfig::auth(),
+            admin: RateLimitConfig::admin(),
+            read_only: RateLimitConfig::read_only(),
+            tracking: RateLimitConfig::tracking(),
+            default: RateLimitConfig::default(),
+        }
+    }
+}
+
+impl Validate for RateLimitSettings {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        for (field, config) in [
+            ("auth", &self.auth),
+            ("admin", &self.admin),
+            ("read_only", &self.read_only),
+            ("tracking", &self.tracking),
+            ("default", &self.default),
+        ] {
+            if !(1..=MAX_WINDOW_SECONDS).contains(&config.window_seconds) {
+                let mut error = ValidationError::new("window_seconds");
+                error.message = Some(
+                    format!("window_seconds must be between 1 and {MAX_WINDOW_SECONDS}").into(),
+                );
+                errors.add(field, error);
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// The live configuration of each limiter group, shared between the
+/// middleware built in `create_app` and the admin endpoint that changes it
+#[derive(Debug, Clone)]
+pub struct RateLimits {
+    pub auth: SharedRateLimitConfig,
+    pub admin: SharedRateLimitConfig,
+    pub read_only: SharedRateLimitConfig,
+    pub tracking: SharedRateLimitConfig,
+    pub default: SharedRateLimitConfig,
+}
+
+impl RateLimits {
+    pub fn new(settings: RateLimitSettings) -> Self {
+        let shared = |config| Arc::new(RwLock::new(config));
+        Self {
+            auth: shared(settings.auth),
+            admin: shared(settings.admin),
+            read_only: shared(settings.read_only),
+            tracking: shared(settings.tracking),
+            default: shared(settings.default),
+        }
+    }
+
+    /// Thresholds currently in effect
+    pub fn current(&self) -> RateLimitSettings {
+        RateLimitSettings {
+            auth: read_config(&self.auth),
+            admin: read_config(&self.admin),
+            read_only: read_config(&self.read_only),
+            tracking: read_config(&self.tracking),
+            default: read_config(&self.default),
+        }
+    }
+
+    /// Replace the thresholds; limiters pick them up on their next request
+    pub fn update(&self, settings: RateLimitSettings) {
+        for (shared, config) in [
+            (&self.auth, settings.auth),
+            (&self.admin, settings.admin),
+            (&self.read_only, settings.read_only),
+            (&self.tracking, settings.tracking),
+            (&self.default, settings.default),
+        ] {
+            *shared.write().unwrap_or_else(PoisonError::into_inner) = config;
+        }
+    }
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self::new(RateLimitSettings::default())
+    }
+}
+
+fn read_config(shared: &SharedRateLimitConfig) -> RateLimitConfig {
+    shared
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
 /// Wrapper for the rate limiter to include last access time.
 struct LimiterState {
     limiter: IpRateLimiter,
+    /// Configuration the limiter's quota was built from
+    config: RateLimitConfig,
     last_accessed: Instant,
 }
 
 impl LimiterState {
-    fn new(limiter: IpRateLimiter) -> Self {
+    fn new(limiter: IpRateLimiter, config: RateLimitConfig) -> Self {
         Self {
             limiter,
+            config,
             last_accessed: Instant::now(),
         }
     }
@@ -154,13 +287,18 @@ type IpRateLimiter = Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>;
 #[derive(Clone)]
 pub struct RateLimitMiddleware {
     limiters: Arc<DashMap<IpAddr, LimiterState>>,
-    config: RateLimitConfig,
+    config: SharedRateLimitConfig,
     _cleanup_handle: Arc<tokio::task::JoinHandle<()>>,
 }
 
 impl RateLimitMiddleware {
     /// Create a new rate limiting middleware with the given configuration
     pub fn new(config: RateLimitConfig) -> Self {
+        Self::shared(Arc::new(RwLock::new(config)))
+    }
+
+    /// Create a rate limiting middleware that follows changes to `config`
+    pub fn shared(config: SharedRateLimitConfig) -> Self {
         let limiters = Arc::new(DashMap::new());
 
         // Start cleanup task
@@ -210,17 +348,22 @@ impl RateLimitMiddleware {
 
     /// Get or create a rate limiter for the given IP
     fn get_limiter(&self, ip: IpAddr) -> IpRateLimiter {
+        let config = read_config(&self.config);
         if let Some(mut entry) = self.limiters.get_mut(&ip) {
-            entry.touch();
-            return entry.limiter.clone();
+            // A limiter built before the configuration changed is replaced
+            if entry.config == config {
+                entry.touch();
+                return entry.limiter.clone();
+            }
         }
 
-        let quota = Quota::with_period(Duration::from_secs(self.config.window_seconds))
+        let quota = Quota::with_period(Duration::from_secs(config.window_seconds))
             .unwrap()
-            .allow_burst(self.config.max_requests);
+            .allow_burst(config.max_requests);
         let limiter = Arc::new(RateLimiter::direct(quota));
 
-        self.limiters.insert(ip, LimiterState::new(limiter.clone()));
+        self.limiters
+            .insert(ip, LimiterState::new(limiter.clone(), config));
         limiter
     }
 
@@ -246,10 +389,11 @@ impl RateLimitMiddleware {
             }
             Err(_) => {
                 // Rate limit exceeded
+                let config = read_config(&self.config);
                 warn!(
                     ip = %ip,
-                    max_requests = %self.config.max_requests,
-                    window_seconds = self.config.window_seconds,
+                    max_requests = %config.max_requests,
+                    window_seconds = config.window_seconds,
                     "Rate limit exceeded"
                 );
 
@@ -273,25 +417,25 @@ mod tests {
     use axum::http::HeaderMap;
     use std::net::{Ipv4Addr, Ipv6Addr};
 
+    fn parts_with(headers: HeaderMap) -> Parts {
+        let (mut parts, ()) = axum::http::Request::new(()).into_parts();
+        parts.headers = headers;
+        parts
+    }
+
     #[tokio::test]
     async fn test_client_ip_extractor() {
         // Test X-Forwarded-For
         let mut headers = HeaderMap::new();
         headers.insert("X-Forwarded-For", "192.168.0.1, 10.0.0.1".parse().unwrap());
-        let mut parts = Parts {
-            headers,
-            ..Default::default()
-        };
+        let mut parts = parts_with(headers);
         let ip = ClientIp::from_request_parts(&mut parts, &()).await.unwrap();
         assert_eq!(ip.0, IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1)));
 
         // Test X-Real-IP
         let mut headers = HeaderMap::new();
         headers.insert("X-Real-IP", "2001:db8::1".parse().unwrap());
-        let mut parts = Parts {
-            headers,
-            ..Default::default()
-        };
+        let mut parts = parts_with(headers);
         let ip = ClientIp::from_request_parts(&mut parts, &()).await.unwrap();
         assert_eq!(
             ip.0,
@@ -299,9 +443,7 @@ mod tests {
         );
 
         // Test fallback to ConnectInfo
-        let mut parts = Parts {
-            ..Default::default()
-        };
+        let mut parts = parts_with(HeaderMap::new());
         let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
         parts.extensions.insert(ConnectInfo(addr));
         let ip = ClientIp::from_request_parts(&mut parts, &()).await.unwrap();
@@ -315,11 +457,14 @@ mod tests {
         assert_eq!(auth_config.window_seconds, 60);
 
         let admin_config = RateLimitConfig::admin();
-        assert_eq!(admin_config.max_requests.get(), 10);
+        assert_eq!(admin_config.max_requests.get(), 60);
 
         let read_only_config = RateLimitConfig::read_only();
         assert_eq!(read_only_config.max_requests.get(), 100);
 
+        let tracking_config = RateLimitConfig::tracking();
+        assert_eq!(tracking_config.max_requests.get(), 300);
+
         let default_config = RateLimitConfig::default();
         assert_eq!(default_config.max_requests.get(), 30);
 
@@ -360,6 +505,51 @@ mod tests {
         assert!(limiter.check().is_err());
     }
 
+    #[tokio::test]
+    async fn test_config_update_applies_to_next_request() {
+        let limits = RateLimits::default();
+        let middleware = RateLimitMiddleware::shared(limits.read_only.clone());
+        let ip = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+
+        // Two requests against the default read-only limit of 100
+        assert!(middleware.get_limiter(ip).check().is_ok());
+        assert!(middleware.get_limiter(ip).check().is_ok());
+
+        limits.update(RateLimitSettings {
+            read_only: RateLimitConfig {
+                max_requests: NonZeroU32::new(1).unwrap(),
+                window_seconds: 60,
+            },
+            ..limits.current()
+        });
+
+        assert!(middleware.get_limiter(ip).check().is_ok());
+        assert!(middleware.get_limiter(ip).check().is_err());
+        assert_eq!(limits.current().read_only.max_requests.get(), 1);
+    }
+
+    #[test]
+    fn test_settings_reject_out_of_range_windows() {
+        assert!(RateLimitSettings::default().validate().is_ok());
+
+        let settings = RateLimitSettings {
+            auth: RateLimitConfig {
+                max_requests: NonZeroU32::new(5).unwrap(),
+                window_seconds: 0,
+            },
+            tracking: RateLimitConfig {
+                max_requests: NonZeroU32::new(5).unwrap(),
+                window_seconds: MAX_WINDOW_SECONDS + 1,
+            },
+            ..RateLimitSettings::default()
+        };
+        let errors = settings.validate().unwrap_err();
+        let fields = errors.field_errors();
+        assert!(fields.contains_key("auth"));
+        assert!(fields.contains_key("tracking"));
+        assert!(!fields.contains_key("admin"));
+    }
+
     #[tokio::test]
     async fn test_cleanup_task() {
         let limiters = Arc::new(DashMap::new());
@@ -369,15 +559,14 @@ mod tests {
         let quota = Quota::with_period(Duration::from_secs(60))
             .unwrap()
             .allow_burst(NonZeroU32::new(10).unwrap());
-        let limiter1 = Arc::new(RateLimiter::direct(quota.clone()));
+        let limiter1 = Arc::new(RateLimiter::direct(quota));
         let limiter2 = Arc::new(RateLimiter::direct(quota));
 
-        limiters.insert(ip1, LimiterState::new(limiter1));
-        limiters.insert(ip2, LimiterState::new(limiter2));
+        limiters.insert(ip1, LimiterState::new(limiter1, RateLimitConfig::default()));
+        limiters.insert(ip2, LimiterState::new(limiter2, RateLimitConfig::default()));
 
-        // Manually create a stale entry
-        let mut stale_entry = limiters.get_mut(&ip1).unwrap();
-        stale_entry.last_accessed = Instant::now() - Duration::from_secs(4000);
+        // Manually create a stale entry; the guard must drop before `retain`
+        limiters.get_mut(&ip1).unwrap().last_accessed = Instant::now() - Duration::from_secs(4000);
 
         assert_eq!(limiters.len(), 2);
 
@@ -0,0 +1,172 @@
+// src/middleware/unknown_domain.rs
+//! What `domain_middleware` answers for a hostname with no blog
+//!
+//! By default a `404` "blog not found" page: HTML for browsers, JSON for
+//! everything else. With `UNKNOWN_DOMAIN_REDIRECT` set, `GET` and `HEAD`
+//! requests are sent to that fallback site instead, keeping their path, so a
+//! wildcard-DNS setup can point subdomains that aren't provisioned yet at a
+//! landing page.
+
+use super::cors::is_valid_origin;
+use axum::{
+    Json,
+    extract::Request,
+    http::{HeaderValue, Method, StatusCode, header},
+    response::{Html, IntoResponse, Response},
+};
+
+/// Site requests for unknown hostnames are redirected to, as
+/// `scheme://host[:port]`.
+/// Configurable via `UNKNOWN_DOMAIN_REDIRECT` (default unset: answer `404`).
+pub fn fallback_url() -> Option<String> {
+    let url = std::env::var("UNKNOWN_DOMAIN_REDIRECT").ok()?;
+    let url = url.trim().trim_end_matches('/');
+    if url.is_empty() {
+        return None;
+    }
+    if !is_valid_origin(url) {
+        tracing::warn!(
+            url,
+            "Ignoring UNKNOWN_DOMAIN_REDIRECT, not an http(s) origin"
+        );
+        return None;
+    }
+    Some(url.to_string())
+}
+
+/// Response for a request to `hostname`, which matches no domain
+pub fn unknown_domain_response(
+    request: &Request,
+    hostname: &str,
+    fallback: Option<&str>,
+) -> Response {
+    let redirectable = matches!(*request.method(), Method::GET | Method::HEAD);
+    // Never bounce a request back to the host it came in on
+    let loops_back =
+        |url: &str| origin_host(url).is_some_and(|host| host.eq_ignore_ascii_case(hostname));
+    let fallback = fallback.filter(|url| redirectable && !loops_back(url));
+
+    if let Some(fallback) = fallback {
+        let path = request
+            .uri()
+            .path_and_query()
+            .map_or("/", |path| path.as_str());
+        if let Ok(location) = HeaderValue::from_str(&format!("{fallback}{path}")) {
+            return (StatusCode::FOUND, [(header::LOCATION, location)]).into_response();
+        }
+    }
+
+    let wants_html = request
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"));
+
+    if wants_html {
+        (StatusCode::NOT_FOUND, Html(not_found_page(hostname))).into_response()
+    } else {
+        (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": "domain_not_found",
+                "message": "No blog is configured for this domain",
+                "hostname": hostname,
+            })),
+        )
+            .into_response()
+    }
+}
+
+/// `host` of a `scheme://host[:port]` origin
+fn origin_host(origin: &str) -> Option<&str> {
+    let (_, authority) = origin.split_once("://")?;
+    authority.split(':').next()
+}
+
+fn not_found_page(hostname: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>Blog not found</title>
+    <style>
+        body {{ font-family: system-ui, sans-serif; color: #1f2937; background: #f9fafb; display: flex; align-items: center; justify-content: center; min-height: 100vh; margin: 0; }}
+        main {{ text-align: center; padding: 2rem; }}
+        h1 {{ font-size: 1.75rem; margin-bottom: 0.5rem; }}
+        p {{ color: #6b7280; }}
+    </style>
+</head>
+<body>
+    <main>
+        <h1>Blog not found</h1>
+        <p>There is no blog at <strong>{}</strong> yet.</p>
+    </main>
+</body>
+</html>
+"#,
+        escape_html(hostname)
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    fn request(method: Method, uri: &str, accept: Option<&str>) -> Request {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(accept) = accept {
+            builder = builder.header(header::ACCEPT, accept);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn test_browsers_get_an_html_page() {
+        let response = unknown_domain_response(
+            &request(Method::GET, "/", Some("text/html,application/xhtml+xml")),
+            "new.example.com",
+            None,
+        );
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(
+            response.headers()[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/html")
+        );
+    }
+
+    #[test]
+    fn test_redirect_only_for_reads_and_other_hosts() {
+        let fallback = Some("https://blog.example.com");
+
+        let response = unknown_domain_response(
+            &request(Method::POST, "/analytics/behavior", None),
+            "new.example.com",
+            fallback,
+        );
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = unknown_domain_response(
+            &request(Method::GET, "/posts", None),
+            "blog.example.com",
+            fallback,
+        );
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_hostname_is_escaped() {
+        assert!(!not_found_page("<script>").contains("<script>"));
+    }
+}
@@ -0,0 +1,327 @@
+// src/services/abuse.rs
+//! Temporary bans for abusive clients
+//!
+//! Every 404 a client gets, and every 401 from `/auth`, counts as a strike
+//! against its IP. A client that collects `not_found_threshold` 404s or
+//! `auth_failure_threshold` auth failures within `window` lands in the
+//! banned set, and `middleware::abuse` answers its requests with 403 until
+//! `ban_duration` has passed. Platform admins can list and lift bans through
+//! `/admin/bans`. State is kept in memory, so bans don't survive a restart
+//! and aren't shared between instances.
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+use std::env;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Strike records kept before stale ones are swept
+const MAX_TRACKED_IPS: usize = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct AbuseConfig {
+    /// How long strikes count towards a ban
+    pub window: Duration,
+    /// 404s within `window` that get a client banned
+    pub not_found_threshold: u32,
+    /// Failed `/auth` requests within `window` that get a client banned
+    pub auth_failure_threshold: u32,
+    /// How long a ban lasts
+    pub ban_duration: Duration,
+}
+
+impl Default for AbuseConfig {
+    fn default() -> Self {
+        let number = |name: &str, default: u64| {
+            env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or(default)
+        };
+        Self {
+            window: Duration::from_secs(number("ABUSE_WINDOW_SECS", 60)),
+            not_found_threshold: number("ABUSE_NOT_FOUND_THRESHOLD", 50) as u32,
+            auth_failure_threshold: number("ABUSE_AUTH_FAILURE_THRESHOLD", 10) as u32,
+            ban_duration: Duration::from_secs(number("ABUSE_BAN_SECS", 900)),
+        }
+    }
+}
+
+/// What a strike was for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AbuseReason {
+    NotFound,
+    AuthFailure,
+}
+
+impl AbuseReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AbuseReason::NotFound => "not_found",
+            AbuseReason::AuthFailure => "auth_failure",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Strikes {
+    window_start: Instant,
+    not_found: u32,
+    auth_failures: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Ban {
+    reason: AbuseReason,
+    strikes: u32,
+    banned_at: DateTime<Utc>,
+    until: Instant,
+}
+
+/// A ban currently in effect
+#[derive(Debug, Clone, Serialize)]
+pub struct BanInfo {
+    pub ip: IpAddr,
+    pub reason: AbuseReason,
+    /// Strikes collected when the ban was imposed
+    pub strikes: u32,
+    pub banned_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Seconds left, rounded up
+    pub retry_after_secs: u64,
+}
+
+/// Per-IP strike counts and the set of banned IPs
+#[derive(Debug, Clone)]
+pub struct AbuseTracker {
+    config: Arc<AbuseConfig>,
+    strikes: Arc<DashMap<IpAddr, Strikes>>,
+    banned_ips: Arc<DashMap<IpAddr, Ban>>,
+}
+
+impl Default for AbuseTracker {
+    fn default() -> Self {
+        Self::new(AbuseConfig::default())
+    }
+}
+
+impl AbuseTracker {
+    pub fn new(config: AbuseConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            strikes: Arc::new(DashMap::new()),
+            banned_ips: Arc::new(DashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &AbuseConfig {
+        &self.config
+    }
+
+    /// Count a strike against `ip`; returns whether it got the client banned
+    pub fn record(&self, ip: IpAddr, reason: AbuseReason) -> bool {
+        self.record_at(ip, reason, Instant::now())
+    }
+
+    /// The ban on `ip`, if one is in effect
+    pub fn banned(&self, ip: IpAddr) -> Option<BanInfo> {
+        self.banned_at(ip, Instant::now())
+    }
+
+    /// Every ban in effect, longest remaining first
+    pub fn bans(&self) -> Vec<BanInfo> {
+        let now = Instant::now();
+        self.banned_ips.retain(|_, ban| ban.until > now);
+        let mut bans: Vec<_> = self
+            .banned_ips
+            .iter()
+            .map(|entry| ban_info(*entry.key(), entry.value(), now))
+            .collect();
+        bans.sort_by_key(|b| std::cmp::Reverse(b.expires_at));
+        bans
+    }
+
+    /// Lift the ban on `ip` and forget its strikes; returns whether it was banned
+    pub fn clear(&self, ip: IpAddr) -> bool {
+        self.strikes.remove(&ip);
+        self.banned_ips
+            .remove(&ip)
+            .is_some_and(|(_, ban)| ban.until > Instant::now())
+    }
+
+    fn record_at(&self, ip: IpAddr, reason: AbuseReason, now: Instant) -> bool {
+        if self.banned_at(ip, now).is_some() {
+            return false;
+        }
+        if self.strikes.len() >= MAX_TRACKED_IPS {
+            let window = self.config.window;
+            self.strikes
+                .retain(|_, s| now.duration_since(s.window_start) < window);
+        }
+
+        let mut strikes = self.strikes.entry(ip).or_insert_with(|| Strikes {
+            window_start: now,
+            not_found: 0,
+            auth_failures: 0,
+        });
+        if now.duration_since(strikes.window_start) >= self.config.window {
+            *strikes = Strikes {
+                window_start: now,
+                not_found: 0,
+                auth_failures: 0,
+            };
+        }
+        let (count, threshold) = match reason {
+            AbuseReason::NotFound => {
+                strikes.not_found += 1;
+                (strikes.not_found, self.config.not_found_threshold)
+            }
+            AbuseReason::AuthFailure => {
+                strikes.auth_failures += 1;
+                (strikes.auth_failures, self.config.auth_failure_threshold)
+            }
+        };
+        if count < threshold {
+            return false;
+        }
+        drop(strikes);
+        self.strikes.remove(&ip);
+
+        warn!(
+            ip = %ip,
+            reason = reason.as_str(),
+            strikes = count,
+            ban_secs = self.config.ban_duration.as_secs(),
+            "Banning abusive client"
+        );
+        self.banned_ips.insert(
+            ip,
+            Ban {
+                reason,
+                strikes: count,
+                banned_at: Utc::now(),
+                until: now + self.config.ban_duration,
+            },
+        );
+        true
+    }
+
+    fn banned_at(&self, ip: IpAddr, now: Instant) -> Option<BanInfo> {
+        let info = {
+            let ban = self.banned_ips.get(&ip)?;
+            (ban.until > now).then(|| ban_info(ip, &ban, now))
+        };
+        if info.is_none() {
+            // The cooldown is over
+            self.banned_ips.remove_if(&ip, |_, ban| ban.until <= now);
+        }
+        info
+    }
+}
+
+fn ban_info(ip: IpAddr, ban: &Ban, now: Instant) -> BanInfo {
+    let remaining = ban.until.saturating_duration_since(now);
+    BanInfo {
+        ip,
+        reason: ban.reason,
+        strikes: ban.strikes,
+        banned_at: ban.banned_at,
+        expires_at: Utc::now() + chrono::Duration::from_std(remaining).unwrap_or_default(),
+        retry_after_secs: remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7));
+
+    fn tracker() -> AbuseTracker {
+        AbuseTracker::new(AbuseConfig {
+            window: Duration::from_secs(60),
+            not_found_threshold: 5,
+            auth_failure_threshold: 3,
+            ban_duration: Duration::from_secs(300),
+        })
+    }
+
+    #[test]
+    fn crossing_the_threshold_bans() {
+        let tracker = tracker();
+        let now = Instant::now();
+
+        assert!(!tracker.record_at(IP, AbuseReason::AuthFailure, now));
+        assert!(!tracker.record_at(IP, AbuseReason::AuthFailure, now));
+        assert!(tracker.banned_at(IP, now).is_none());
+
+        assert!(tracker.record_at(IP, AbuseReason::AuthFailure, now));
+        let ban = tracker.banned_at(IP, now).unwrap();
+        assert_eq!(ban.reason, AbuseReason::AuthFailure);
+        assert_eq!(ban.strikes, 3);
+        assert_eq!(ban.retry_after_secs, 300);
+    }
+
+    #[test]
+    fn ban_lifts_after_cooldown() {
+        let tracker = tracker();
+        let now = Instant::now();
+        for _ in 0..5 {
+            tracker.record_at(IP, AbuseReason::NotFound, now);
+        }
+
+        let later = now + Duration::from_secs(299);
+        assert!(tracker.banned_at(IP, later).is_some());
+
+        let later = now + Duration::from_secs(300);
+        assert!(tracker.banned_at(IP, later).is_none());
+        // Strikes start over once the ban is lifted
+        assert!(!tracker.record_at(IP, AbuseReason::NotFound, later));
+    }
+
+    #[test]
+    fn strikes_expire_with_the_window() {
+        let tracker = tracker();
+        let now = Instant::now();
+        for _ in 0..4 {
+            tracker.record_at(IP, AbuseReason::NotFound, now);
+        }
+
+        let later = now + Duration::from_secs(61);
+        assert!(!tracker.record_at(IP, AbuseReason::NotFound, later));
+        assert!(tracker.banned_at(IP, later).is_none());
+    }
+
+    #[test]
+    fn reasons_are_counted_separately() {
+        let tracker = tracker();
+        let now = Instant::now();
+        for _ in 0..2 {
+            tracker.record_at(IP, AbuseReason::AuthFailure, now);
+        }
+        for _ in 0..4 {
+            tracker.record_at(IP, AbuseReason::NotFound, now);
+        }
+        assert!(tracker.banned_at(IP, now).is_none());
+    }
+
+    #[test]
+    fn clear_lifts_a_ban() {
+        let tracker = tracker();
+        for _ in 0..3 {
+            tracker.record(IP, AbuseReason::AuthFailure);
+        }
+        assert_eq!(tracker.bans().len(), 1);
+
+        assert!(tracker.clear(IP));
+        assert!(tracker.banned(IP).is_none());
+        assert!(tracker.bans().is_empty());
+        assert!(!tracker.clear(IP));
+    }
+}
@@ -0,0 +1,699 @@
+// src/services/alerts.rs
+//! Traffic alerts for domain owners
+//!
+//! An alert watches one metric over a trailing window (say, page views in the
+//! last 60 minutes) and notifies a webhook and/or an email address when the
+//! value goes above or below a threshold. A background job checks every alert
+//! each `ALERT_CHECK_INTERVAL_MINUTES`.
+//!
+//! Alerts fire when the threshold is crossed, not on every check while it
+//! stays crossed, and at most once per `cooldown_minutes`, so a metric
+//! flapping around the threshold can't set off a storm of notifications.
+
+use super::clock::Clock;
+use super::daily_stats::{self, StatsTotals};
+use super::digest::{EmailMessage, Mailer, SmtpMailer};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::future::Future;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+use validator::ValidateEmail;
+
+/// Longest trailing window an alert may watch, one week
+pub const MAX_WINDOW_MINUTES: i32 = 7 * 24 * 60;
+
+/// Longest cooldown between notifications, one week
+pub const MAX_COOLDOWN_MINUTES: i32 = 7 * 24 * 60;
+
+const WEBHOOK_TIMEOUT_SECONDS: u64 = 10;
+
+/// How often alerts are checked.
+/// Configurable via `ALERT_CHECK_INTERVAL_MINUTES` (default 5).
+pub fn check_interval() -> std::time::Duration {
+    let minutes = std::env::var("ALERT_CHECK_INTERVAL_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|m| *m > 0)
+        .unwrap_or(5);
+    std::time::Duration::from_secs(minutes * 60)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    PageViews,
+    PostViews,
+    UniqueVisitors,
+    Searches,
+    Sessions,
+}
+
+impl Metric {
+    pub fn parse(metric: &str) -> Option<Self> {
+        match metric {
+            "page_views" => Some(Metric::PageViews),
+            "post_views" => Some(Metric::PostViews),
+            "unique_visitors" => Some(Metric::UniqueVisitors),
+            "searches" => Some(Metric::Searches),
+            "sessions" => Some(Metric::Sessions),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Metric::PageViews => "page_views",
+            Metric::PostViews => "post_views",
+            Metric::UniqueVisitors => "unique_visitors",
+            Metric::Searches => "searches",
+            Metric::Sessions => "sessions",
+        }
+    }
+
+    pub fn value(self, totals: &StatsTotals) -> i64 {
+        match self {
+            Metric::PageViews => totals.page_views,
+            Metric::PostViews => totals.post_views,
+            Metric::UniqueVisitors => totals.unique_visitors,
+            Metric::Searches => totals.searches,
+            Metric::Sessions => totals.sessions,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    /// A spike: the value rose above the threshold
+    Above,
+    /// A drop: the value fell below the threshold
+    Below,
+}
+
+impl Comparison {
+    pub fn parse(comparison: &str) -> Option<Self> {
+        match comparison {
+            "above" => Some(Comparison::Above),
+            "below" => Some(Comparison::Below),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Comparison::Above => "above",
+            Comparison::Below => "below",
+        }
+    }
+
+    pub fn is_crossed(self, value: i64, threshold: i64) -> bool {
+        match self {
+            Comparison::Above => value > threshold,
+            Comparison::Below => value < threshold,
+        }
+    }
+}
+
+/// A stored alert
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Alert {
+    pub id: i32,
+    pub domain_id: i32,
+    pub metric: String,
+    pub comparison: String,
+    pub threshold: i64,
+    pub window_minutes: i32,
+    pub cooldown_minutes: i32,
+    pub webhook_url: Option<String>,
+    pub email: Option<String>,
+    pub enabled: bool,
+    pub is_firing: bool,
+    pub last_triggered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The settings an admin chooses for an alert
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlertSettings {
+    pub metric: String,
+    pub comparison: String,
+    pub threshold: i64,
+    #[serde(default = "default_minutes")]
+    pub window_minutes: i32,
+    #[serde(default = "default_minutes")]
+    pub cooldown_minutes: i32,
+    pub webhook_url: Option<String>,
+    pub email: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_minutes() -> i32 {
+    60
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Why alert settings were rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertError {
+    UnknownMetric(String),
+    UnknownComparison(String),
+    NegativeThreshold,
+    InvalidWindow,
+    InvalidCooldown,
+    NoTarget,
+    InvalidWebhook,
+    InvalidEmail,
+}
+
+impl AlertError {
+    /// The settings field at fault
+    pub fn field(&self) -> &'static str {
+        match self {
+            AlertError::UnknownMetric(_) => "metric",
+            AlertError::UnknownComparison(_) => "comparison",
+            AlertError::NegativeThreshold => "threshold",
+            AlertError::InvalidWindow => "window_minutes",
+            AlertError::InvalidCooldown => "cooldown_minutes",
+            AlertError::NoTarget | AlertError::InvalidWebhook => "webhook_url",
+            AlertError::InvalidEmail => "email",
+        }
+    }
+}
+
+impl std::fmt::Display for AlertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlertError::UnknownMetric(metric) => write!(
+                f,
+                "Unknown metric '{metric}'; use page_views, post_views, unique_visitors, searches or sessions"
+            ),
+            AlertError::UnknownComparison(comparison) => {
+                write!(f, "Unknown comparison '{comparison}'; use above or below")
+            }
+            AlertError::NegativeThreshold => write!(f, "Threshold cannot be negative"),
+            AlertError::InvalidWindow => write!(
+                f,
+                "Window must be between 1 and {MAX_WINDOW_MINUTES} minutes"
+            ),
+            AlertError::InvalidCooldown => write!(
+                f,
+                "Cooldown must be between 0 and {MAX_COOLDOWN_MINUTES} minutes"
+            ),
+            AlertError::NoTarget => write!(f, "Alerts need a webhook_url, an email or both"),
+            AlertError::InvalidWebhook => write!(f, "Webhook must be an http(s) URL"),
+            AlertError::InvalidEmail => write!(f, "Invalid email address"),
+        }
+    }
+}
+
+impl AlertSettings {
+    pub fn check(&self) -> Result<(), AlertError> {
+        if Metric::parse(&self.metric).is_none() {
+            return Err(AlertError::UnknownMetric(self.metric.clone()));
+        }
+        if Comparison::parse(&self.comparison).is_none() {
+            return Err(AlertError::UnknownComparison(self.comparison.clone()));
+        }
+        if self.threshold < 0 {
+            return Err(AlertError::NegativeThreshold);
+        }
+        if !(1..=MAX_WINDOW_MINUTES).contains(&self.window_minutes) {
+            return Err(AlertError::InvalidWindow);
+        }
+        if !(0..=MAX_COOLDOWN_MINUTES).contains(&self.cooldown_minutes) {
+            return Err(AlertError::InvalidCooldown);
+        }
+        if self.webhook_url.is_none() && self.email.is_none() {
+            return Err(AlertError::NoTarget);
+        }
+        if let Some(url) = &self.webhook_url {
+            let is_http = url.starts_with("https://") || url.starts_with("http://");
+            if !is_http || url.len() > 2048 || url.chars().any(char::is_whitespace) {
+                return Err(AlertError::InvalidWebhook);
+            }
+        }
+        if self
+            .email
+            .as_ref()
+            .is_some_and(|email| !email.validate_email())
+        {
+            return Err(AlertError::InvalidEmail);
+        }
+        Ok(())
+    }
+}
+
+/// What one check of an alert decided
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision {
+    /// Whether the threshold is crossed now; stored as `is_firing`
+    pub firing: bool,
+    /// Whether to send a notification
+    pub notify: bool,
+}
+
+/// Check `value` against an alert: notify only when the threshold has just
+/// been crossed and the last notification is older than the cooldown
+pub fn decide(
+    comparison: Comparison,
+    threshold: i64,
+    cooldown: Duration,
+    was_firing: bool,
+    last_triggered_at: Option<DateTime<Utc>>,
+    value: i64,
+    now: DateTime<Utc>,
+) -> Decision {
+    let firing = comparison.is_crossed(value, threshold);
+    let cooled_down = last_triggered_at.is_none_or(|at| now - at >= cooldown);
+    Decision {
+        firing,
+        notify: firing && !was_firing && cooled_down,
+    }
+}
+
+/// What a notification reports, sent as the webhook's JSON body
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AlertNotification {
+    pub alert_id: i32,
+    pub domain_id: i32,
+    pub hostname: String,
+    pub metric: String,
+    pub comparison: String,
+    pub threshold: i64,
+    pub value: i64,
+    pub window_minutes: i32,
+    pub triggered_at: DateTime<Utc>,
+}
+
+impl AlertNotification {
+    fn email_body(&self) -> String {
+        format!(
+            "{} on {} was {} in the last {} minutes, {} the alert threshold of {}.\n",
+            self.metric,
+            self.hostname,
+            self.value,
+            self.window_minutes,
+            self.comparison,
+            self.threshold,
+        )
+    }
+}
+
+/// Sends alert notifications; implemented by [`AlertDelivery`] and by test doubles
+pub trait AlertNotifier: Send + Sync {
+    fn notify(
+        &self,
+        alert: &Alert,
+        notification: &AlertNotification,
+    ) -> impl Future<Output = Result<(), String>> + Send;
+}
+
+/// Delivers to an alert's webhook over HTTP and to its email through SMTP
+pub struct AlertDelivery {
+    http: reqwest::Client,
+    mailer: Option<SmtpMailer>,
+}
+
+impl AlertDelivery {
+    /// Email targets are skipped when `mailer` is `None`
+    pub fn new(mailer: Option<SmtpMailer>) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(WEBHOOK_TIMEOUT_SECONDS))
+                .build()
+                .unwrap_or_default(),
+            mailer,
+        }
+    }
+}
+
+impl AlertNotifier for AlertDelivery {
+    async fn notify(&self, alert: &Alert, notification: &AlertNotification) -> Result<(), String> {
+        let mut failures = Vec::new();
+
+        if let Some(url) = &alert.webhook_url {
+            let sent = self
+                .http
+                .post(url)
+                .json(notification)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(e) = sent {
+                failures.push(format!("webhook: {e}"));
+            }
+        }
+
+        if let Some(to) = &alert.email {
+            match &self.mailer {
+                Some(mailer) => {
+                    let message = EmailMessage {
+                        to: to.clone(),
+                        subject: format!("Traffic alert for {}", notification.hostname),
+                        body: notification.email_body(),
+                    };
+                    if let Err(e) = mailer.send(message).await {
+                        failures.push(format!("email: {e}"));
+                    }
+                }
+                None => failures.push("email: SMTP_HOST is not configured".to_string()),
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures.join("; "))
+        }
+    }
+}
+
+const ALERT_COLUMNS: &str = "id, domain_id, metric, comparison, threshold, window_minutes, \
+     cooldown_minutes, webhook_url, email, enabled, is_firing, last_triggered_at, created_at, \
+     updated_at";
+
+pub async fn list(db: &PgPool, domain_id: i32) -> Result<Vec<Alert>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {ALERT_COLUMNS} FROM analytics_alerts WHERE domain_id = $1 ORDER BY id"
+    ))
+    .bind(domain_id)
+    .fetch_all(db)
+    .await
+}
+
+pub async fn get(db: &PgPool, domain_id: i32, id: i32) -> Result<Option<Alert>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {ALERT_COLUMNS} FROM analytics_alerts WHERE id = $1 AND domain_id = $2"
+    ))
+    .bind(id)
+    .bind(domain_id)
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn create(
+    db: &PgPool,
+    domain_id: i32,
+    settings: &AlertSettings,
+) -> Result<Alert, sqlx::Error> {
+    sqlx::query_as(&format!(
+        r#"
+        INSERT INTO analytics_alerts (domain_id, metric, comparison, threshold, window_minutes,
+                                      cooldown_minutes, webhook_url, email, enabled)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING {ALERT_COLUMNS}
+        "#
+    ))
+    .bind(domain_id)
+    .bind(&settings.metric)
+    .bind(&settings.comparison)
+    .bind(settings.threshold)
+    .bind(settings.window_minutes)
+    .bind(settings.cooldown_minutes)
+    .bind(&settings.webhook_url)
+    .bind(&settings.email)
+    .bind(settings.enabled)
+    .fetch_one(db)
+    .await
+}
+
+/// Replace an alert's settings; a changed rule starts over as not firing
+pub async fn update(
+    db: &PgPool,
+    domain_id: i32,
+    id: i32,
+    settings: &AlertSettings,
+) -> Result<Option<Alert>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        r#"
+        UPDATE analytics_alerts
+        SET metric = $3, comparison = $4, threshold = $5, window_minutes = $6,
+            cooldown_minutes = $7, webhook_url = $8, email = $9, enabled = $10,
+            is_firing = FALSE, updated_at = NOW()
+        WHERE id = $1 AND domain_id = $2
+        RETURNING {ALERT_COLUMNS}
+        "#
+    ))
+    .bind(id)
+    .bind(domain_id)
+    .bind(&settings.metric)
+    .bind(&settings.comparison)
+    .bind(settings.threshold)
+    .bind(settings.window_minutes)
+    .bind(settings.cooldown_minutes)
+    .bind(&settings.webhook_url)
+    .bind(&settings.email)
+    .bind(settings.enabled)
+    .fetch_optional(db)
+    .await
+}
+
+pub async fn delete(db: &PgPool, domain_id: i32, id: i32) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM analytics_alerts WHERE id = $1 AND domain_id = $2")
+        .bind(id)
+        .bind(domain_id)
+        .execute(db)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Check every enabled alert at `now`; returns how many notifications were sent
+pub async fn run_alerts<N: AlertNotifier>(
+    db: &PgPool,
+    notifier: &N,
+    now: DateTime<Utc>,
+) -> Result<usize, sqlx::Error> {
+    #[derive(sqlx::FromRow)]
+    struct AlertWithHost {
+        #[sqlx(flatten)]
+        alert: Alert,
+        hostname: String,
+    }
+
+    let alerts: Vec<AlertWithHost> = sqlx::query_as(
+        r#"
+        SELECT a.id, a.domain_id, a.metric, a.comparison, a.threshold, a.window_minutes,
+               a.cooldown_minutes, a.webhook_url, a.email, a.enabled, a.is_firing,
+               a.last_triggered_at, a.created_at, a.updated_at, d.hostname
+        FROM analytics_alerts a
+        JOIN domains d ON d.id = a.domain_id
+        WHERE a.enabled
+        ORDER BY a.id
+        "#,
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut sent = 0;
+    for AlertWithHost { alert, hostname } in alerts {
+        let (Some(metric), Some(comparison)) = (
+            Metric::parse(&alert.metric),
+            Comparison::parse(&alert.comparison),
+        ) else {
+            warn!(
+                alert_id = alert.id,
+                "Skipping alert with an unknown metric or comparison"
+            );
+            continue;
+        };
+
+        let window_start = now - Duration::minutes(alert.window_minutes.into());
+        let totals =
+            daily_stats::period_totals(db, &[alert.domain_id], window_start, now, now).await?;
+        let value = metric.value(&totals);
+
+        let decision = decide(
+            comparison,
+            alert.threshold,
+            Duration::minutes(alert.cooldown_minutes.into()),
+            alert.is_firing,
+            alert.last_triggered_at,
+            value,
+            now,
+        );
+
+        if decision.notify {
+            let notification = AlertNotification {
+                alert_id: alert.id,
+                domain_id: alert.domain_id,
+                hostname,
+                metric: alert.metric.clone(),
+                comparison: alert.comparison.clone(),
+                threshold: alert.threshold,
+                value,
+                window_minutes: alert.window_minutes,
+                triggered_at: now,
+            };
+            // The cooldown starts even when delivery fails, so a broken
+            // target isn't retried on every check
+            match notifier.notify(&alert, &notification).await {
+                Ok(()) => sent += 1,
+                Err(e) => warn!(alert_id = alert.id, error = %e, "Failed to deliver alert"),
+            }
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE analytics_alerts
+            SET is_firing = $2,
+                last_triggered_at = CASE WHEN $3 THEN $4 ELSE last_triggered_at END
+            WHERE id = $1
+            "#,
+        )
+        .bind(alert.id)
+        .bind(decision.firing)
+        .bind(decision.notify)
+        .bind(now)
+        .execute(db)
+        .await?;
+    }
+
+    Ok(sent)
+}
+
+/// Start the periodic alert check, judging windows by `clock`
+pub fn start_alert_task<N: AlertNotifier + 'static>(
+    db: PgPool,
+    clock: Arc<dyn Clock>,
+    notifier: N,
+    every: std::time::Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(every);
+        loop {
+            interval.tick().await;
+
+            match run_alerts(&db, &notifier, clock.now()).await {
+                Ok(sent) if sent > 0 => info!(sent, "Analytics alerts sent"),
+                Ok(_) => {}
+                Err(e) => error!(error = %e, "Failed to check analytics alerts"),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, 1, 12, minute, 0).unwrap()
+    }
+
+    fn settings() -> AlertSettings {
+        AlertSettings {
+            metric: "page_views".to_string(),
+            comparison: "above".to_string(),
+            threshold: 100,
+            window_minutes: 60,
+            cooldown_minutes: 30,
+            webhook_url: Some("https://hooks.example.com/alerts".to_string()),
+            email: None,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_fires_when_threshold_is_crossed() {
+        let cooldown = Duration::minutes(30);
+        let spike = |value| decide(Comparison::Above, 100, cooldown, false, None, value, at(0));
+        assert_eq!(
+            spike(101),
+            Decision {
+                firing: true,
+                notify: true
+            }
+        );
+        // Reaching the threshold is not crossing it
+        assert_eq!(
+            spike(100),
+            Decision {
+                firing: false,
+                notify: false
+            }
+        );
+
+        let drop = decide(Comparison::Below, 10, cooldown, false, None, 3, at(0));
+        assert!(drop.notify);
+        let steady = decide(Comparison::Below, 10, cooldown, false, None, 50, at(0));
+        assert!(!steady.firing && !steady.notify);
+    }
+
+    #[test]
+    fn test_stays_quiet_while_threshold_stays_crossed() {
+        let decision = decide(
+            Comparison::Above,
+            100,
+            Duration::minutes(30),
+            true,
+            Some(at(0)),
+            500,
+            at(50),
+        );
+        assert!(decision.firing);
+        assert!(!decision.notify);
+    }
+
+    #[test]
+    fn test_cooldown_suppresses_flapping() {
+        let cooldown = Duration::minutes(30);
+        // Crossed at :00 and notified, dropped back, crossed again at :10
+        let again = decide(
+            Comparison::Above,
+            100,
+            cooldown,
+            false,
+            Some(at(0)),
+            150,
+            at(10),
+        );
+        assert_eq!(
+            again,
+            Decision {
+                firing: true,
+                notify: false
+            }
+        );
+
+        // Once the cooldown has passed a new crossing notifies again
+        let later = decide(
+            Comparison::Above,
+            100,
+            cooldown,
+            false,
+            Some(at(0)),
+            150,
+            at(30),
+        );
+        assert!(later.notify);
+    }
+
+    #[test]
+    fn test_validates_settings() {
+        assert_eq!(settings().check(), Ok(()));
+
+        let mut bad = settings();
+        bad.metric = "bounces".to_string();
+        assert!(matches!(bad.check(), Err(AlertError::UnknownMetric(_))));
+
+        let mut bad = settings();
+        bad.webhook_url = None;
+        assert_eq!(bad.check(), Err(AlertError::NoTarget));
+
+        let mut bad = settings();
+        bad.webhook_url = Some("ftp://hooks.example.com".to_string());
+        assert_eq!(bad.check(), Err(AlertError::InvalidWebhook));
+
+        let mut bad = settings();
+        bad.window_minutes = 0;
+        assert_eq!(bad.check(), Err(AlertError::InvalidWindow));
+    }
+}
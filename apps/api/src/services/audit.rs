@@ -0,0 +1,37 @@
+// src/services/audit.rs
+//! Audit log of sensitive actions
+//!
+//! Writes are best-effort: a failed insert is logged but never fails the
+//! request that triggered it.
+
+use sqlx::PgPool;
+use tracing::error;
+
+/// One row to append to `audit_log`
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    /// User the action ran as
+    pub actor_id: Option<i32>,
+    /// Platform admin impersonating `actor_id`, if any
+    pub impersonator_id: Option<i32>,
+    /// What happened, e.g. `impersonation.start` or `PUT /admin/posts/3`
+    pub action: String,
+    pub details: serde_json::Value,
+}
+
+/// Append an entry to the audit log
+pub async fn record(db: &PgPool, entry: AuditEntry) {
+    let result = sqlx::query(
+        "INSERT INTO audit_log (actor_id, impersonator_id, action, details) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(entry.actor_id)
+    .bind(entry.impersonator_id)
+    .bind(&entry.action)
+    .bind(&entry.details)
+    .execute(db)
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, action = %entry.action, "Failed to write audit log entry");
+    }
+}
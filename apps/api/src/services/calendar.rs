@@ -0,0 +1,156 @@
+// src/services/calendar.rs
+//! Month view of a domain's content schedule
+//!
+//! Scheduled posts land on the day of their `publish_at` and published posts
+//! on the day they were created. Days are the domain's own, split at
+//! midnight in its `timezone` (see [`super::timezones`]), and every day of
+//! the month is listed, empty or not, so the counts can be drawn as a heatmap.
+
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+
+/// A post as it appears on the calendar
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct CalendarPost {
+    pub id: i32,
+    pub title: String,
+    pub slug: String,
+    pub status: String,
+    /// `publish_at` for scheduled posts, `created_at` for published ones
+    pub at: DateTime<Utc>,
+    #[serde(skip)]
+    pub day: NaiveDate,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CalendarDay {
+    pub date: NaiveDate,
+    pub count: usize,
+    pub posts: Vec<CalendarPost>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Calendar {
+    pub month: String,
+    pub timezone: String,
+    pub total: usize,
+    pub days: Vec<CalendarDay>,
+}
+
+/// First day of a `YYYY-MM` month
+pub fn parse_month(month: &str) -> Option<NaiveDate> {
+    let (year, month) = month.split_once('-')?;
+    if year.len() != 4 || month.len() != 2 {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, 1)
+}
+
+/// First day of the month `date` falls in
+pub fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+/// Every day of the month starting on `first`, with its posts in time order
+pub fn group_by_day(first: NaiveDate, mut posts: Vec<CalendarPost>) -> Vec<CalendarDay> {
+    posts.sort_by_key(|post| (post.day, post.at, post.id));
+    let mut posts = posts.into_iter().peekable();
+
+    first
+        .iter_days()
+        .take_while(|date| date.month() == first.month())
+        .map(|date| {
+            let mut day_posts = Vec::new();
+            while let Some(post) = posts.next_if(|post| post.day <= date) {
+                if post.day == date {
+                    day_posts.push(post);
+                }
+            }
+            CalendarDay {
+                date,
+                count: day_posts.len(),
+                posts: day_posts,
+            }
+        })
+        .collect()
+}
+
+/// The domain's scheduled and published posts in the month starting on
+/// `first`, by day in `timezone`
+pub async fn load(
+    db: &PgPool,
+    domain_id: i32,
+    timezone: &str,
+    first: NaiveDate,
+) -> Result<Calendar, sqlx::Error> {
+    let next = first + Months::new(1);
+    let posts = sqlx::query_as::<_, CalendarPost>(
+        r#"
+        SELECT id, title, slug, status, at, (at AT TIME ZONE $2)::date AS day
+        FROM (
+            SELECT id, title, slug, status,
+                   CASE WHEN status = 'scheduled' THEN publish_at ELSE created_at END AS at
+            FROM posts
+            WHERE domain_id = $1
+            AND (status = 'published' OR (status = 'scheduled' AND publish_at IS NOT NULL))
+        ) p
+        WHERE at >= ($3::date::timestamp AT TIME ZONE $2)
+        AND at < ($4::date::timestamp AT TIME ZONE $2)
+        "#,
+    )
+    .bind(domain_id)
+    .bind(timezone)
+    .bind(first)
+    .bind(next)
+    .fetch_all(db)
+    .await?;
+
+    let total = posts.len();
+    Ok(Calendar {
+        month: first.format("%Y-%m").to_string(),
+        timezone: timezone.to_string(),
+        total,
+        days: group_by_day(first, posts),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn post(id: i32, day: u32, hour: u32) -> CalendarPost {
+        CalendarPost {
+            id,
+            title: format!("Post {id}"),
+            slug: format!("post-{id}"),
+            status: "published".to_string(),
+            at: Utc.with_ymd_and_hms(2025, 2, day, hour, 0, 0).unwrap(),
+            day: NaiveDate::from_ymd_opt(2025, 2, day).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_parse_month() {
+        assert_eq!(parse_month("2025-02"), NaiveDate::from_ymd_opt(2025, 2, 1));
+        assert_eq!(parse_month("2025-13"), None);
+        assert_eq!(parse_month("2025-2"), None);
+        assert_eq!(parse_month("2025-02-01"), None);
+        assert_eq!(parse_month("february"), None);
+    }
+
+    #[test]
+    fn test_every_day_of_the_month_is_listed() {
+        let first = parse_month("2025-02").unwrap();
+        let days = group_by_day(first, vec![post(1, 10, 9), post(2, 3, 12), post(3, 10, 8)]);
+
+        assert_eq!(days.len(), 28);
+        assert_eq!(days[0].date, first);
+        assert_eq!(days[2].count, 1);
+        assert_eq!(days[9].count, 2);
+        let ids: Vec<i32> = days[9].posts.iter().map(|post| post.id).collect();
+        assert_eq!(ids, vec![3, 1]);
+        assert_eq!(days.iter().map(|day| day.count).sum::<usize>(), 3);
+    }
+}
@@ -0,0 +1,258 @@
+// src/services/credentials.rs
+//! Password reset tokens and session revocation
+//!
+//! A reset token reads `<id>.<secret>`: the id finds its row in
+//! `password_reset_tokens`, which keeps only a bcrypt hash of the secret.
+//! Tokens are single use, expire after [`reset_token_ttl`], and issuing a new
+//! one voids the user's unused ones.
+//!
+//! Access tokens are stateless JWTs, so sessions are revoked by stamping
+//! `users.sessions_revoked_at`, and the auth layer turns away tokens issued
+//! at or before it. `iat` has second precision, so a token issued in the
+//! same second as the revocation is refused as well. That is why a password
+//! reset leaves existing sessions alone: the user would often be logging in
+//! again within the second. Revoke them separately when the account may be
+//! compromised.
+
+use super::clock::Clock;
+use super::digest::EmailMessage;
+use super::email_templates::EmailTemplate;
+use bcrypt::{DEFAULT_COST, hash, verify};
+use chrono::{DateTime, Duration, Utc};
+use rand::{Rng, distr::Alphanumeric};
+use sqlx::PgPool;
+
+const SECRET_CHARS: usize = 32;
+
+/// How long a reset token stays usable.
+/// Configurable via `PASSWORD_RESET_TTL_MINUTES` (default 60).
+pub fn reset_token_ttl() -> Duration {
+    let minutes = std::env::var("PASSWORD_RESET_TTL_MINUTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|m| *m > 0)
+        .unwrap_or(60);
+    Duration::minutes(minutes)
+}
+
+#[derive(Debug)]
+pub enum CredentialsError {
+    Database(sqlx::Error),
+    Hashing,
+}
+
+impl From<sqlx::Error> for CredentialsError {
+    fn from(err: sqlx::Error) -> Self {
+        CredentialsError::Database(err)
+    }
+}
+
+impl std::fmt::Display for CredentialsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CredentialsError::Database(e) => write!(f, "database error: {e}"),
+            CredentialsError::Hashing => write!(f, "failed to hash reset token"),
+        }
+    }
+}
+
+/// A newly issued reset token; the plain token is not stored anywhere
+pub struct ResetToken {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A reset token that checked out and has not been used yet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PendingReset {
+    pub token_id: i32,
+    pub user_id: i32,
+}
+
+/// A random secret for a `<id>.<secret>` token
+pub(crate) fn generate_secret() -> String {
+    rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(SECRET_CHARS)
+        .map(char::from)
+        .collect()
+}
+
+/// Split `<id>.<secret>`
+pub(crate) fn parse_token(token: &str) -> Option<(i32, &str)> {
+    let (id, secret) = token.trim().split_once('.')?;
+    if secret.len() != SECRET_CHARS {
+        return None;
+    }
+    Some((id.parse().ok()?, secret))
+}
+
+/// Issue a reset token for `user_id`, voiding any unused earlier ones
+pub async fn issue_reset_token(
+    db: &PgPool,
+    clock: &dyn Clock,
+    user_id: i32,
+    issued_by: i32,
+) -> Result<ResetToken, CredentialsError> {
+    let secret = generate_secret();
+    let secret_hash = hash(&secret, DEFAULT_COST).map_err(|_| CredentialsError::Hashing)?;
+    let expires_at = clock.now() + reset_token_ttl();
+
+    let mut tx = db.begin().await?;
+    sqlx::query("DELETE FROM password_reset_tokens WHERE user_id = $1 AND used_at IS NULL")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    let token_id: i32 = sqlx::query_scalar(
+        r#"
+        INSERT INTO password_reset_tokens (user_id, token_hash, expires_at, issued_by)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id
+        "#,
+    )
+    .bind(user_id)
+    .bind(&secret_hash)
+    .bind(expires_at)
+    .bind(issued_by)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(ResetToken {
+        token: format!("{token_id}.{secret}"),
+        expires_at,
+    })
+}
+
+/// The unused, unexpired reset `token` belongs to a live user
+pub async fn find_reset_token(
+    db: &PgPool,
+    clock: &dyn Clock,
+    token: &str,
+) -> Result<Option<PendingReset>, sqlx::Error> {
+    let Some((token_id, secret)) = parse_token(token) else {
+        return Ok(None);
+    };
+
+    let row: Option<(i32, String)> = sqlx::query_as(
+        r#"
+        SELECT t.user_id, t.token_hash
+        FROM password_reset_tokens t
+        JOIN users u ON u.id = t.user_id
+        WHERE t.id = $1 AND t.used_at IS NULL AND t.expires_at > $2
+        AND u.deleted_at IS NULL
+        "#,
+    )
+    .bind(token_id)
+    .bind(clock.now())
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row
+        .filter(|(_, token_hash)| verify(secret, token_hash).unwrap_or(false))
+        .map(|(user_id, _)| PendingReset { token_id, user_id }))
+}
+
+/// Use up `reset` and set the new password, which also clears a pending
+/// forced change. Returns `false` when the token was used concurrently.
+pub async fn complete_reset(
+    db: &PgPool,
+    clock: &dyn Clock,
+    reset: PendingReset,
+    password_hash: &str,
+) -> Result<bool, sqlx::Error> {
+    let now = clock.now();
+    let mut tx = db.begin().await?;
+    let claimed = sqlx::query(
+        "UPDATE password_reset_tokens SET used_at = $1 WHERE id = $2 AND used_at IS NULL",
+    )
+    .bind(now)
+    .bind(reset.token_id)
+    .execute(&mut *tx)
+    .await?;
+    if claimed.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET password_hash = $1, must_change_password = FALSE, updated_at = $2
+        WHERE id = $3
+        "#,
+    )
+    .bind(password_hash)
+    .bind(now)
+    .bind(reset.user_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+/// Invalidate every token issued to `user_id` so far; `false` if there is
+/// no such user. The revocation is stamped with `clock`, the same time
+/// source tokens get their `iat` from.
+pub async fn revoke_sessions(
+    db: &PgPool,
+    clock: &dyn Clock,
+    user_id: i32,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE users SET sessions_revoked_at = $1, updated_at = $1 WHERE id = $2 AND deleted_at IS NULL",
+    )
+    .bind(clock.now())
+    .bind(user_id)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Whether a token issued at `iat` (seconds) predates the user's last revocation
+pub fn is_revoked(iat: usize, sessions_revoked_at: Option<DateTime<Utc>>) -> bool {
+    sessions_revoked_at.is_some_and(|revoked_at| iat as i64 <= revoked_at.timestamp())
+}
+
+/// The email carrying a reset token to its user, rendered from the
+/// `password_reset` template
+pub fn reset_email(template: &EmailTemplate, to: &str, reset: &ResetToken) -> EmailMessage {
+    let expires_at = reset.expires_at.format("%Y-%m-%d %H:%M UTC").to_string();
+    template.render(
+        to,
+        &[
+            ("email", to),
+            ("token", &reset.token),
+            ("expires_at", &expires_at),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_token() {
+        let secret = "a".repeat(SECRET_CHARS);
+        assert_eq!(
+            parse_token(&format!("42.{secret}")),
+            Some((42, secret.as_str()))
+        );
+        assert_eq!(parse_token(&secret), None);
+        assert_eq!(parse_token(&format!("x.{secret}")), None);
+        assert_eq!(parse_token("42.short"), None);
+    }
+
+    #[test]
+    fn test_tokens_issued_up_to_revocation_are_revoked() {
+        let revoked_at = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+        let at = |seconds: i64| (revoked_at.timestamp() + seconds) as usize;
+
+        assert!(!is_revoked(at(-60), None));
+        assert!(is_revoked(at(-60), Some(revoked_at)));
+        assert!(is_revoked(at(0), Some(revoked_at)));
+        assert!(!is_revoked(at(1), Some(revoked_at)));
+    }
+}
@@ -0,0 +1,396 @@
+// src/services/daily_stats.rs
+//! Daily per-domain rollup of `analytics_events`
+//!
+//! Dashboards read whole past days from `daily_domain_stats` and only scan raw
+//! events for partial days: the current day and a range's ragged first day.
+//! A nightly job rolls up the previous day and fills in any day that has
+//! events but no rollup yet; [`backfill`] recomputes an arbitrary span.
+//!
+//! Unique visitors and sessions are distinct per day, so over multi-day
+//! ranges they are the sum of the daily counts.
+//! Page views sum each row's `sample_weight`, so domains that sample page
+//! views report estimated totals.
+//!
+//! Rollup days are UTC days. Per-day figures in any other time zone are
+//! computed from raw events, so they only reach back as far as retention
+//! keeps them.
+
+use super::timezones;
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::ops::AddAssign;
+use tracing::{error, info};
+
+/// Minutes past midnight UTC the nightly rollup runs, leaving time for late events
+const ROLLUP_DELAY_MINUTES: i64 = 10;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, sqlx::FromRow)]
+pub struct StatsTotals {
+    pub page_views: i64,
+    pub post_views: i64,
+    pub searches: i64,
+    pub unique_visitors: i64,
+    pub sessions: i64,
+}
+
+impl AddAssign for StatsTotals {
+    fn add_assign(&mut self, other: Self) {
+        self.page_views += other.page_views;
+        self.post_views += other.post_views;
+        self.searches += other.searches;
+        self.unique_visitors += other.unique_visitors;
+        self.sessions += other.sessions;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct DailyTotals {
+    pub day: NaiveDate,
+    #[sqlx(flatten)]
+    pub totals: StatsTotals,
+}
+
+/// How a time range is split between the rollup table and raw events
+#[derive(Debug, PartialEq, Eq)]
+pub struct RangePlan {
+    /// Half-open spans read from `analytics_events`
+    pub raw: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+    /// Inclusive span of whole days read from `daily_domain_stats`
+    pub rollup_days: Option<(NaiveDate, NaiveDate)>,
+}
+
+fn midnight(day: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&day.and_hms_opt(0, 0, 0).unwrap())
+}
+
+/// Split `[start, end)` into whole days before today (served by the rollup)
+/// and the partial days around them (scanned raw)
+pub fn plan_range(start: DateTime<Utc>, end: DateTime<Utc>, now: DateTime<Utc>) -> RangePlan {
+    let first_full_day = if midnight(start.date_naive()) == start {
+        start.date_naive()
+    } else {
+        start.date_naive() + Duration::days(1)
+    };
+    let rollup_start = midnight(first_full_day);
+    let rollup_end = midnight(end.date_naive()).min(midnight(now.date_naive()));
+
+    if rollup_start >= rollup_end {
+        return RangePlan {
+            raw: vec![(start, end)],
+            rollup_days: None,
+        };
+    }
+
+    let mut raw = Vec::new();
+    if start < rollup_start {
+        raw.push((start, rollup_start));
+    }
+    if rollup_end < end {
+        raw.push((rollup_end, end));
+    }
+
+    RangePlan {
+        raw,
+        rollup_days: Some((first_full_day, rollup_end.date_naive() - Duration::days(1))),
+    }
+}
+
+/// Recompute the rollup rows for one UTC day. Returns the number of domains written.
+pub async fn rollup_day(db: &PgPool, day: NaiveDate) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO daily_domain_stats
+            (domain_id, day, page_views, post_views, searches, unique_visitors, sessions)
+        SELECT
+            domain_id,
+            $1,
+            ROUND(COALESCE(SUM(sample_weight) FILTER (WHERE event_type = 'page_view'), 0))::BIGINT,
+            COUNT(*) FILTER (WHERE event_type = 'post_view'),
+            COUNT(*) FILTER (WHERE event_type = 'search'),
+            COUNT(DISTINCT ip_address),
+            COUNT(DISTINCT session_id)
+        FROM analytics_events
+        WHERE domain_id IS NOT NULL AND created_at >= $2 AND created_at < $3
+        GROUP BY domain_id
+        ON CONFLICT (domain_id, day) DO UPDATE SET
+            page_views = EXCLUDED.page_views,
+            post_views = EXCLUDED.post_views,
+            searches = EXCLUDED.searches,
+            unique_visitors = EXCLUDED.unique_visitors,
+            sessions = EXCLUDED.sessions,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(day)
+    .bind(midnight(day))
+    .bind(midnight(day + Duration::days(1)))
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Recompute every day in `from..=to`. Returns the number of rows written.
+pub async fn backfill(db: &PgPool, from: NaiveDate, to: NaiveDate) -> Result<u64, sqlx::Error> {
+    let mut rows = 0;
+    for day in from.iter_days().take_while(|day| *day <= to) {
+        rows += rollup_day(db, day).await?;
+    }
+    Ok(rows)
+}
+
+/// Roll up past days that have raw events but no rollup row yet
+pub async fn backfill_missing(db: &PgPool, now: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+    let days: Vec<NaiveDate> = sqlx::query_scalar(
+        r#"
+        SELECT DISTINCT (ae.created_at AT TIME ZONE 'UTC')::date
+        FROM analytics_events ae
+        WHERE ae.domain_id IS NOT NULL AND ae.created_at < $1
+        AND NOT EXISTS (
+            SELECT 1 FROM daily_domain_stats s
+            WHERE s.domain_id = ae.domain_id
+            AND s.day = (ae.created_at AT TIME ZONE 'UTC')::date
+        )
+        "#,
+    )
+    .bind(midnight(now.date_naive()))
+    .fetch_all(db)
+    .await?;
+
+    let mut rows = 0;
+    for day in days {
+        rows += rollup_day(db, day).await?;
+    }
+    Ok(rows)
+}
+
+/// Totals straight from `analytics_events` over `[start, end)`
+pub async fn raw_totals(
+    db: &PgPool,
+    domain_ids: &[i32],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<StatsTotals, sqlx::Error> {
+    sqlx::query_as::<_, StatsTotals>(
+        r#"
+        SELECT
+            ROUND(COALESCE(SUM(sample_weight) FILTER (WHERE event_type = 'page_view'), 0))::BIGINT AS page_views,
+            COUNT(*) FILTER (WHERE event_type = 'post_view') AS post_views,
+            COUNT(*) FILTER (WHERE event_type = 'search') AS searches,
+            COUNT(DISTINCT ip_address) AS unique_visitors,
+            COUNT(DISTINCT session_id) AS sessions
+        FROM analytics_events
+        WHERE domain_id = ANY($1) AND created_at >= $2 AND created_at < $3
+        "#,
+    )
+    .bind(domain_ids)
+    .bind(start)
+    .bind(end)
+    .fetch_one(db)
+    .await
+}
+
+/// Totals over `[start, end)`, using the rollup for whole past days
+pub async fn period_totals(
+    db: &PgPool,
+    domain_ids: &[i32],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<StatsTotals, sqlx::Error> {
+    let plan = plan_range(start, end, now);
+    let mut totals = StatsTotals::default();
+
+    if let Some((first_day, last_day)) = plan.rollup_days {
+        totals += sqlx::query_as::<_, StatsTotals>(
+            r#"
+            SELECT
+                COALESCE(SUM(page_views), 0)::BIGINT AS page_views,
+                COALESCE(SUM(post_views), 0)::BIGINT AS post_views,
+                COALESCE(SUM(searches), 0)::BIGINT AS searches,
+                COALESCE(SUM(unique_visitors), 0)::BIGINT AS unique_visitors,
+                COALESCE(SUM(sessions), 0)::BIGINT AS sessions
+            FROM daily_domain_stats
+            WHERE domain_id = ANY($1) AND day BETWEEN $2 AND $3
+            "#,
+        )
+        .bind(domain_ids)
+        .bind(first_day)
+        .bind(last_day)
+        .fetch_one(db)
+        .await?;
+    }
+    for (raw_start, raw_end) in plan.raw {
+        totals += raw_totals(db, domain_ids, raw_start, raw_end).await?;
+    }
+
+    Ok(totals)
+}
+
+/// Per-day totals over `[start, end)` with days split at midnight in `tz`,
+/// using the rollup for whole past days when `tz` is UTC
+pub async fn daily_totals(
+    db: &PgPool,
+    domain_ids: &[i32],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    now: DateTime<Utc>,
+    tz: &str,
+) -> Result<Vec<DailyTotals>, sqlx::Error> {
+    let plan = if timezones::is_utc(tz) {
+        plan_range(start, end, now)
+    } else {
+        RangePlan {
+            raw: vec![(start, end)],
+            rollup_days: None,
+        }
+    };
+    let mut rows = Vec::new();
+
+    if let Some((first_day, last_day)) = plan.rollup_days {
+        rows.extend(
+            sqlx::query_as::<_, DailyTotals>(
+                r#"
+                SELECT
+                    day,
+                    SUM(page_views)::BIGINT AS page_views,
+                    SUM(post_views)::BIGINT AS post_views,
+                    SUM(searches)::BIGINT AS searches,
+                    SUM(unique_visitors)::BIGINT AS unique_visitors,
+                    SUM(sessions)::BIGINT AS sessions
+                FROM daily_domain_stats
+                WHERE domain_id = ANY($1) AND day BETWEEN $2 AND $3
+                GROUP BY day
+                "#,
+            )
+            .bind(domain_ids)
+            .bind(first_day)
+            .bind(last_day)
+            .fetch_all(db)
+            .await?,
+        );
+    }
+    for (raw_start, raw_end) in plan.raw {
+        rows.extend(
+            sqlx::query_as::<_, DailyTotals>(
+                r#"
+                SELECT
+                    (created_at AT TIME ZONE $4)::date AS day,
+                    ROUND(COALESCE(SUM(sample_weight) FILTER (WHERE event_type = 'page_view'), 0))::BIGINT AS page_views,
+                    COUNT(*) FILTER (WHERE event_type = 'post_view') AS post_views,
+                    COUNT(*) FILTER (WHERE event_type = 'search') AS searches,
+                    COUNT(DISTINCT ip_address) AS unique_visitors,
+                    COUNT(DISTINCT session_id) AS sessions
+                FROM analytics_events
+                WHERE domain_id = ANY($1) AND created_at >= $2 AND created_at < $3
+                GROUP BY 1
+                "#,
+            )
+            .bind(domain_ids)
+            .bind(raw_start)
+            .bind(raw_end)
+            .bind(tz)
+            .fetch_all(db)
+            .await?,
+        );
+    }
+
+    // A raw span and the rollup never cover the same day, but merge anyway
+    // so callers always get one row per day in order
+    let mut by_day: BTreeMap<NaiveDate, StatsTotals> = BTreeMap::new();
+    for row in rows {
+        *by_day.entry(row.day).or_default() += row.totals;
+    }
+    Ok(by_day
+        .into_iter()
+        .map(|(day, totals)| DailyTotals { day, totals })
+        .collect())
+}
+
+/// Validate an hour-of-day bucket from `CAST(EXTRACT(HOUR FROM ...) AS INTEGER)`.
+/// Returns `None` for NULL or anything outside 0-23 so a bad row is dropped
+/// instead of being folded into midnight.
+pub fn hour_bucket(hour: Option<i32>) -> Option<i32> {
+    hour.filter(|h| (0..24).contains(h))
+}
+
+/// Start the nightly rollup background task
+pub fn start_daily_stats_task(db: PgPool) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let now = Utc::now();
+            let yesterday = now.date_naive() - Duration::days(1);
+
+            // Refresh yesterday to pick up late events, then fill any gaps
+            let result = match rollup_day(&db, yesterday).await {
+                Ok(rows) => backfill_missing(&db, now).await.map(|more| rows + more),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(rows) => info!(rows, "Daily analytics rollup complete"),
+                Err(e) => error!(error = %e, "Daily analytics rollup failed"),
+            }
+
+            let next_run = midnight(now.date_naive() + Duration::days(1))
+                + Duration::minutes(ROLLUP_DELAY_MINUTES);
+            tokio::time::sleep((next_run - Utc::now()).to_std().unwrap_or_default()).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, m: u32, d: u32, h: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap()
+    }
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_hour_bucket_covers_every_hour() {
+        let buckets: Vec<i32> = (0..24).filter_map(|h| hour_bucket(Some(h))).collect();
+        assert_eq!(buckets, (0..24).collect::<Vec<_>>());
+
+        assert_eq!(hour_bucket(None), None);
+        assert_eq!(hour_bucket(Some(24)), None);
+        assert_eq!(hour_bucket(Some(-1)), None);
+    }
+
+    #[test]
+    fn test_plan_range_splits_partial_days() {
+        let now = at(2024, 3, 10, 15);
+        let plan = plan_range(at(2024, 3, 3, 15), now, now);
+
+        assert_eq!(plan.rollup_days, Some((day(2024, 3, 4), day(2024, 3, 9))));
+        assert_eq!(
+            plan.raw,
+            vec![
+                (at(2024, 3, 3, 15), at(2024, 3, 4, 0)),
+                (at(2024, 3, 10, 0), now)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_range_aligned_past_range() {
+        let now = at(2024, 3, 10, 15);
+        let plan = plan_range(at(2024, 3, 1, 0), at(2024, 3, 5, 0), now);
+
+        assert_eq!(plan.rollup_days, Some((day(2024, 3, 1), day(2024, 3, 4))));
+        assert!(plan.raw.is_empty());
+    }
+
+    #[test]
+    fn test_plan_range_within_today_is_raw() {
+        let now = at(2024, 3, 10, 15);
+        let plan = plan_range(at(2024, 3, 9, 18), now, now);
+
+        assert_eq!(plan.rollup_days, None);
+        assert_eq!(plan.raw, vec![(at(2024, 3, 9, 18), now)]);
+    }
+}
@@ -0,0 +1,529 @@
+// src/services/digest.rs
+//! Weekly analytics digest emails for domain owners
+//!
+//! A background job wakes up on a weekly schedule, computes last week's key
+//! metrics for every domain and emails them to domain admins who opted in via
+//! `users.preferences.weekly_digest`. Delivery goes through the [`Mailer`]
+//! trait so the job can run against SMTP in production and a recorder in tests.
+
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc, Weekday};
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    transport::smtp::authentication::Credentials,
+};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::env;
+use std::future::Future;
+use std::pin::Pin;
+use tracing::{error, info, warn};
+
+/// Preference key that opts a user into the weekly digest
+pub const DIGEST_PREFERENCE_KEY: &str = "weekly_digest";
+
+/// Digest job configuration, read from the environment
+#[derive(Debug, Clone)]
+pub struct DigestConfig {
+    pub enabled: bool,
+    pub weekday: Weekday,
+    /// Hour of day (UTC) the digest is sent
+    pub hour: u32,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: env::var("DIGEST_ENABLED")
+                .map(|v| v.parse().unwrap_or(false))
+                .unwrap_or(false),
+            weekday: env::var("DIGEST_WEEKDAY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(Weekday::Mon),
+            hour: env::var("DIGEST_HOUR")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|h| *h < 24)
+                .unwrap_or(8),
+        }
+    }
+}
+
+impl DigestConfig {
+    /// Next time the digest should run, strictly after `now`
+    pub fn next_run_after(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let days_ahead = (7 + self.weekday.num_days_from_monday() as i64
+            - now.weekday().num_days_from_monday() as i64)
+            % 7;
+        let date = now.date_naive() + Duration::days(days_ahead);
+        let candidate = Utc.from_utc_datetime(&date.and_hms_opt(self.hour, 0, 0).unwrap());
+
+        if candidate > now {
+            candidate
+        } else {
+            candidate + Duration::weeks(1)
+        }
+    }
+}
+
+/// An outgoing email
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug)]
+pub struct MailError(pub String);
+
+impl std::fmt::Display for MailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "mail delivery failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for MailError {}
+
+/// Delivers emails; implemented by [`SmtpMailer`] and by test doubles
+pub trait Mailer: Send + Sync {
+    fn send(&self, message: EmailMessage) -> impl Future<Output = Result<(), MailError>> + Send;
+}
+
+/// Boxed future returned by [`SharedMailer::send_boxed`]
+pub type MailFuture<'a> = Pin<Box<dyn Future<Output = Result<(), MailError>> + Send + 'a>>;
+
+/// A [`Mailer`] usable as a trait object, as kept in `AppState`
+pub trait SharedMailer: Send + Sync {
+    fn send_boxed(&self, message: EmailMessage) -> MailFuture<'_>;
+}
+
+impl<M: Mailer> SharedMailer for M {
+    fn send_boxed(&self, message: EmailMessage) -> MailFuture<'_> {
+        Box::pin(self.send(message))
+    }
+}
+
+/// SMTP mailer configured from `SMTP_*` environment variables
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: String,
+}
+
+impl SmtpMailer {
+    /// Build from env; returns `None` when `SMTP_HOST` is not set
+    pub fn from_env() -> Option<Self> {
+        let host = env::var("SMTP_HOST").ok()?;
+        let mut builder = match AsyncSmtpTransport::<Tokio1Executor>::relay(&host) {
+            Ok(builder) => builder,
+            Err(e) => {
+                error!(error = %e, host = %host, "Invalid SMTP relay configuration");
+                return None;
+            }
+        };
+
+        if let Some(port) = env::var("SMTP_PORT").ok().and_then(|p| p.parse().ok()) {
+            builder = builder.port(port);
+        }
+        if let (Ok(username), Ok(password)) = (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD"))
+        {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Some(Self {
+            transport: builder.build(),
+            from: env::var("SMTP_FROM").unwrap_or_else(|_| "no-reply@localhost".to_string()),
+        })
+    }
+
+    /// Open a connection to the relay without sending anything
+    pub async fn test_connection(&self) -> Result<(), String> {
+        match self.transport.test_connection().await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("SMTP relay refused the connection".to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+impl Mailer for SmtpMailer {
+    async fn send(&self, message: EmailMessage) -> Result<(), MailError> {
+        let email = Message::builder()
+            .from(self.from.parse().map_err(|e| MailError(format!("{e}")))?)
+            .to(message.to.parse().map_err(|e| MailError(format!("{e}")))?)
+            .subject(message.subject)
+            .body(message.body)
+            .map_err(|e| MailError(e.to_string()))?;
+
+        self.transport
+            .send(email)
+            .await
+            .map(|_| ())
+            .map_err(|e| MailError(e.to_string()))
+    }
+}
+
+/// Last week's metrics for a single domain
+#[derive(Debug, Clone)]
+pub struct DomainDigest {
+    pub domain_id: i32,
+    pub domain_name: String,
+    pub hostname: String,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub views: i64,
+    pub visitors: i64,
+    pub top_posts: Vec<(String, i64)>,
+}
+
+/// A domain admin who may receive a digest
+#[derive(Debug, Clone)]
+pub struct DigestRecipient {
+    pub email: String,
+    pub name: String,
+    pub preferences: serde_json::Value,
+    pub domain_id: i32,
+}
+
+/// Whether a user's preferences opt them into the weekly digest
+pub fn wants_digest(preferences: &serde_json::Value) -> bool {
+    preferences
+        .get(DIGEST_PREFERENCE_KEY)
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// Render one email per opted-in user, covering every domain they administer
+pub fn assemble_digests(
+    recipients: &[DigestRecipient],
+    digests: &BTreeMap<i32, DomainDigest>,
+) -> Vec<EmailMessage> {
+    let mut by_user: BTreeMap<&str, (&str, Vec<&DomainDigest>)> = BTreeMap::new();
+
+    for recipient in recipients.iter().filter(|r| wants_digest(&r.preferences)) {
+        if let Some(digest) = digests.get(&recipient.domain_id) {
+            by_user
+                .entry(recipient.email.as_str())
+                .or_insert_with(|| (recipient.name.as_str(), Vec::new()))
+                .1
+                .push(digest);
+        }
+    }
+
+    by_user
+        .into_iter()
+        .map(|(email, (name, domains))| EmailMessage {
+            to: email.to_string(),
+            subject: "Your weekly blog analytics digest".to_string(),
+            body: render_digest_body(name, &domains),
+        })
+        .collect()
+}
+
+fn render_digest_body(name: &str, domains: &[&DomainDigest]) -> String {
+    let mut body = format!("Hi {name},\n\nHere is how your blogs did last week.\n");
+
+    for digest in domains {
+        body.push_str(&format!(
+            "\n{} ({})\n{} to {}\n  Views: {}\n  Visitors: {}\n",
+            digest.domain_name,
+            digest.hostname,
+            digest.period_start.format("%Y-%m-%d"),
+            digest.period_end.format("%Y-%m-%d"),
+            digest.views,
+            digest.visitors,
+        ));
+
+        if digest.top_posts.is_empty() {
+            body.push_str("  No post views this week\n");
+        } else {
+            body.push_str("  Top posts:\n");
+            for (title, views) in &digest.top_posts {
+                body.push_str(&format!("    - {title} ({views} views)\n"));
+            }
+        }
+    }
+
+    body.push_str(&format!(
+        "\nTo stop these emails, set `{DIGEST_PREFERENCE_KEY}` to false in your preferences.\n"
+    ));
+    body
+}
+
+/// Compute last week's metrics for every domain
+async fn load_domain_digests(
+    db: &PgPool,
+    period_start: DateTime<Utc>,
+    period_end: DateTime<Utc>,
+) -> Result<BTreeMap<i32, DomainDigest>, sqlx::Error> {
+    let domains = sqlx::query!(
+        r#"
+        SELECT d.id, d.name, d.hostname,
+               ROUND(COALESCE(SUM(ae.sample_weight) FILTER (WHERE ae.event_type IN ('page_view', 'post_view')), 0))::BIGINT as views,
+               COUNT(DISTINCT ae.ip_address) as visitors
+        FROM domains d
+        LEFT JOIN analytics_events ae ON ae.domain_id = d.id
+            AND ae.created_at BETWEEN $1 AND $2
+        GROUP BY d.id, d.name, d.hostname
+        "#,
+        period_start,
+        period_end
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut digests = BTreeMap::new();
+    for domain in domains {
+        let top_posts = sqlx::query!(
+            r#"
+            SELECT p.title, COUNT(ae.id) as views
+            FROM posts p
+            JOIN analytics_events ae ON ae.post_id = p.id
+            WHERE p.domain_id = $1 AND ae.event_type = 'post_view'
+            AND ae.created_at BETWEEN $2 AND $3
+            GROUP BY p.id, p.title
+            ORDER BY views DESC
+            LIMIT 5
+            "#,
+            domain.id,
+            period_start,
+            period_end
+        )
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .map(|row| (row.title, row.views.unwrap_or(0)))
+        .collect();
+
+        digests.insert(
+            domain.id,
+            DomainDigest {
+                domain_id: domain.id,
+                domain_name: domain.name,
+                hostname: domain.hostname,
+                period_start,
+                period_end,
+                views: domain.views.unwrap_or(0),
+                visitors: domain.visitors.unwrap_or(0),
+                top_posts,
+            },
+        );
+    }
+
+    Ok(digests)
+}
+
+/// Domain admins, with their preferences, one row per administered domain
+async fn load_recipients(db: &PgPool) -> Result<Vec<DigestRecipient>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT u.email, u.name, u.preferences, udp.domain_id as "domain_id!"
+        FROM users u
+        JOIN user_domain_permissions udp ON udp.user_id = u.id
+        WHERE udp.role = 'admin' AND udp.domain_id IS NOT NULL AND u.deleted_at IS NULL
+        "#
+    )
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| DigestRecipient {
+            email: row.email,
+            name: row.name,
+            preferences: row.preferences.unwrap_or_default(),
+            domain_id: row.domain_id,
+        })
+        .collect())
+}
+
+/// Send digests to every opted-in recipient; returns the number delivered
+pub async fn send_digests<M: Mailer>(
+    mailer: &M,
+    recipients: &[DigestRecipient],
+    digests: &BTreeMap<i32, DomainDigest>,
+) -> usize {
+    let mut sent = 0;
+    for message in assemble_digests(recipients, digests) {
+        let to = message.to.clone();
+        match mailer.send(message).await {
+            Ok(()) => sent += 1,
+            Err(e) => warn!(error = %e, to = %to, "Failed to send analytics digest"),
+        }
+    }
+    sent
+}
+
+/// Run a single digest pass covering the week before `now`
+pub async fn run_digest<M: Mailer>(
+    db: &PgPool,
+    mailer: &M,
+    now: DateTime<Utc>,
+) -> Result<usize, sqlx::Error> {
+    let digests = load_domain_digests(db, now - Duration::weeks(1), now).await?;
+    let recipients = load_recipients(db).await?;
+    Ok(send_digests(mailer, &recipients, &digests).await)
+}
+
+/// Start the weekly digest background task
+pub fn start_digest_task<M: Mailer + 'static>(
+    db: PgPool,
+    mailer: M,
+    config: DigestConfig,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let now = Utc::now();
+            let next_run = config.next_run_after(now);
+            info!(next_run = %next_run, "Scheduled next analytics digest");
+
+            let wait = (next_run - now).to_std().unwrap_or_default();
+            tokio::time::sleep(wait).await;
+
+            match run_digest(&db, &mailer, Utc::now()).await {
+                Ok(sent) => info!(sent, "Analytics digests sent"),
+                Err(e) => error!(error = %e, "Failed to build analytics digests"),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingMailer {
+        sent: Mutex<Vec<EmailMessage>>,
+    }
+
+    impl Mailer for RecordingMailer {
+        async fn send(&self, message: EmailMessage) -> Result<(), MailError> {
+            self.sent.lock().unwrap().push(message);
+            Ok(())
+        }
+    }
+
+    fn digest(domain_id: i32, name: &str, top_posts: Vec<(&str, i64)>) -> DomainDigest {
+        DomainDigest {
+            domain_id,
+            domain_name: name.to_string(),
+            hostname: format!("{}.example.com", name.to_lowercase()),
+            period_start: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            period_end: Utc.with_ymd_and_hms(2024, 1, 8, 0, 0, 0).unwrap(),
+            views: 120,
+            visitors: 45,
+            top_posts: top_posts
+                .into_iter()
+                .map(|(t, v)| (t.to_string(), v))
+                .collect(),
+        }
+    }
+
+    fn recipient(email: &str, preferences: serde_json::Value, domain_id: i32) -> DigestRecipient {
+        DigestRecipient {
+            email: email.to_string(),
+            name: "Owner".to_string(),
+            preferences,
+            domain_id,
+        }
+    }
+
+    #[test]
+    fn test_wants_digest() {
+        assert!(wants_digest(&json!({ "weekly_digest": true })));
+        assert!(!wants_digest(&json!({ "weekly_digest": false })));
+        assert!(!wants_digest(&json!({})));
+        assert!(!wants_digest(&json!({ "weekly_digest": "yes" })));
+    }
+
+    #[test]
+    fn test_digest_content() {
+        let digests = BTreeMap::from([(1, digest(1, "Tech", vec![("Rust tips", 30)]))]);
+        let recipients = vec![recipient(
+            "owner@test.com",
+            json!({ "weekly_digest": true }),
+            1,
+        )];
+
+        let messages = assemble_digests(&recipients, &digests);
+        assert_eq!(messages.len(), 1);
+
+        let body = &messages[0].body;
+        assert!(body.contains("Tech (tech.example.com)"));
+        assert!(body.contains("2024-01-01 to 2024-01-08"));
+        assert!(body.contains("Views: 120"));
+        assert!(body.contains("Visitors: 45"));
+        assert!(body.contains("Rust tips (30 views)"));
+    }
+
+    #[test]
+    fn test_digest_groups_domains_per_user() {
+        let digests = BTreeMap::from([
+            (1, digest(1, "Tech", vec![])),
+            (2, digest(2, "Food", vec![])),
+        ]);
+        let prefs = json!({ "weekly_digest": true });
+        let recipients = vec![
+            recipient("owner@test.com", prefs.clone(), 1),
+            recipient("owner@test.com", prefs, 2),
+        ];
+
+        let messages = assemble_digests(&recipients, &digests);
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].body.contains("Tech"));
+        assert!(messages[0].body.contains("Food"));
+        assert!(messages[0].body.contains("No post views this week"));
+    }
+
+    #[tokio::test]
+    async fn test_only_opted_in_users_are_mailed() {
+        let digests = BTreeMap::from([(1, digest(1, "Tech", vec![]))]);
+        let recipients = vec![
+            recipient("in@test.com", json!({ "weekly_digest": true }), 1),
+            recipient("out@test.com", json!({ "weekly_digest": false }), 1),
+            recipient("default@test.com", json!({}), 1),
+            // Opted in, but the domain has no digest
+            recipient("other@test.com", json!({ "weekly_digest": true }), 99),
+        ];
+
+        let mailer = RecordingMailer::default();
+        let sent = send_digests(&mailer, &recipients, &digests).await;
+
+        assert_eq!(sent, 1);
+        let sent = mailer.sent.lock().unwrap();
+        assert_eq!(sent[0].to, "in@test.com");
+    }
+
+    #[test]
+    fn test_next_run_after() {
+        let config = DigestConfig {
+            enabled: true,
+            weekday: Weekday::Mon,
+            hour: 8,
+        };
+
+        // Wednesday 2024-01-03 -> Monday 2024-01-08 08:00
+        let wednesday = Utc.with_ymd_and_hms(2024, 1, 3, 12, 0, 0).unwrap();
+        assert_eq!(
+            config.next_run_after(wednesday),
+            Utc.with_ymd_and_hms(2024, 1, 8, 8, 0, 0).unwrap()
+        );
+
+        // Monday before the hour -> same day
+        let monday_early = Utc.with_ymd_and_hms(2024, 1, 8, 7, 0, 0).unwrap();
+        assert_eq!(
+            config.next_run_after(monday_early),
+            Utc.with_ymd_and_hms(2024, 1, 8, 8, 0, 0).unwrap()
+        );
+
+        // Exactly at the run time -> following week
+        let monday_at = Utc.with_ymd_and_hms(2024, 1, 8, 8, 0, 0).unwrap();
+        assert_eq!(
+            config.next_run_after(monday_at),
+            Utc.with_ymd_and_hms(2024, 1, 15, 8, 0, 0).unwrap()
+        );
+    }
+}
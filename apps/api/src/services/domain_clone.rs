@@ -0,0 +1,119 @@
+// src/services/domain_clone.rs
+//! Start a new domain from an existing one used as a template
+//!
+//! The copy gets the source's settings (theme, categories, features,
+//! timezone, quota), its menu, search settings and email templates, and
+//! optionally its posts as drafts, all in one transaction. Nothing tied to
+//! the source's audience or address comes along: analytics, members,
+//! subscribers, alerts, CORS origins and DNS verification start empty.
+
+use serde::Serialize;
+use sqlx::PgPool;
+
+/// What was copied into the new domain
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct CloneReport {
+    pub source_id: i32,
+    pub domain_id: i32,
+    pub categories: usize,
+    pub menu: bool,
+    pub search_settings: bool,
+    pub email_templates: u64,
+    pub posts: u64,
+}
+
+/// Copy domain `source_id` into a new domain at `hostname` named `name`;
+/// `None` when there is no such source. The hostname must be free.
+pub async fn clone_domain(
+    db: &PgPool,
+    source_id: i32,
+    hostname: &str,
+    name: &str,
+    include_posts: bool,
+) -> Result<Option<CloneReport>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+
+    let Some((domain_id, categories)): Option<(i32, Option<serde_json::Value>)> = sqlx::query_as(
+        r#"
+        INSERT INTO domains (hostname, name, theme_config, categories, features, timezone, quota)
+        SELECT $2, $3, theme_config, categories, features, timezone, quota
+        FROM domains WHERE id = $1
+        RETURNING id, categories
+        "#,
+    )
+    .bind(source_id)
+    .bind(hostname)
+    .bind(name)
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let menu = sqlx::query(
+        "INSERT INTO domain_menus (domain_id, items) SELECT $2, items FROM domain_menus WHERE domain_id = $1",
+    )
+    .bind(source_id)
+    .bind(domain_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    let search_settings = sqlx::query(
+        "INSERT INTO domain_search_settings (domain_id, settings) SELECT $2, settings FROM domain_search_settings WHERE domain_id = $1",
+    )
+    .bind(source_id)
+    .bind(domain_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    let email_templates = sqlx::query(
+        r#"
+        INSERT INTO email_templates (domain_id, kind, subject, body)
+        SELECT $2, kind, subject, body FROM email_templates WHERE domain_id = $1
+        "#,
+    )
+    .bind(source_id)
+    .bind(domain_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    // Drafts, so nothing goes live on the new address before it's reviewed;
+    // view counts start at zero like the rest of the analytics
+    let posts = if include_posts {
+        sqlx::query(
+            r#"
+            INSERT INTO posts (domain_id, title, content, author, category, slug, status,
+                               word_count, reading_time_minutes, excerpt, image_url, visibility,
+                               pinned, meta_title, meta_description, noindex)
+            SELECT $2, title, content, author, category, slug, 'draft',
+                   word_count, reading_time_minutes, excerpt, image_url, visibility,
+                   pinned, meta_title, meta_description, noindex
+            FROM posts WHERE domain_id = $1
+            ORDER BY id
+            "#,
+        )
+        .bind(source_id)
+        .bind(domain_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+    } else {
+        0
+    };
+
+    tx.commit().await?;
+
+    Ok(Some(CloneReport {
+        source_id,
+        domain_id,
+        categories: categories
+            .as_ref()
+            .and_then(serde_json::Value::as_array)
+            .map_or(0, Vec::len),
+        menu: menu > 0,
+        search_settings: search_settings > 0,
+        email_templates,
+        posts,
+    }))
+}
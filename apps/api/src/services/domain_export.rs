@@ -0,0 +1,249 @@
+// src/services/domain_export.rs
+//! Zip archive of everything belonging to one domain, for moving a tenant
+//!
+//! The archive is written on a blocking thread into an anonymous temp file
+//! (the zip writer needs to seek back to patch entry headers), then copied
+//! through a bounded pipe that the response body reads from. Posts and
+//! analytics events are fetched in pages, so memory stays flat however large
+//! the domain is. The manifest is written last, once the counts are known.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use tokio::io::DuplexStream;
+use tokio::runtime::Handle;
+use tokio_util::io::SyncIoBridge;
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+/// Bumped whenever a member's layout changes
+pub const SCHEMA_VERSION: u32 = 1;
+
+pub const DOMAIN_MEMBER: &str = "domain.json";
+pub const POSTS_MEMBER: &str = "posts.json";
+pub const EVENTS_MEMBER: &str = "analytics_events.csv";
+pub const PERMISSIONS_MEMBER: &str = "permissions.json";
+pub const MANIFEST_MEMBER: &str = "manifest.json";
+
+/// Bytes buffered between the archive writer and the response body
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+/// Rows fetched per query for posts and analytics events
+const PAGE_SIZE: i64 = 1000;
+
+const EVENTS_HEADER: &str =
+    "id,session_id,post_id,event_type,path,user_agent,ip_address,referrer,metadata,created_at\n";
+
+type ExportResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportCounts {
+    pub posts: usize,
+    pub analytics_events: usize,
+    pub permissions: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub schema_version: u32,
+    pub domain_id: i32,
+    pub exported_at: DateTime<Utc>,
+    pub counts: ExportCounts,
+}
+
+#[derive(sqlx::FromRow)]
+struct EventRow {
+    id: i32,
+    session_id: Option<uuid::Uuid>,
+    post_id: Option<i32>,
+    event_type: String,
+    path: Option<String>,
+    user_agent: Option<String>,
+    ip_address: Option<String>,
+    referrer: Option<String>,
+    metadata: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+}
+
+/// Quote a CSV field when it contains a separator, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn event_csv_line(event: &EventRow) -> String {
+    let fields = [
+        event.id.to_string(),
+        event
+            .session_id
+            .map(|id| id.to_string())
+            .unwrap_or_default(),
+        event.post_id.map(|id| id.to_string()).unwrap_or_default(),
+        event.event_type.clone(),
+        event.path.clone().unwrap_or_default(),
+        event.user_agent.clone().unwrap_or_default(),
+        event.ip_address.clone().unwrap_or_default(),
+        event.referrer.clone().unwrap_or_default(),
+        event.metadata.clone().unwrap_or_default(),
+        event.created_at.map(|t| t.to_rfc3339()).unwrap_or_default(),
+    ];
+    let mut line = fields
+        .iter()
+        .map(|f| csv_field(f))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push('\n');
+    line
+}
+
+/// Start writing the archive for `domain_id`; read the zip from the returned
+/// stream. Errors after the first byte can only be logged, so callers should
+/// check the domain exists first.
+pub fn export_domain(db: PgPool, domain_id: i32) -> DuplexStream {
+    let (reader, writer) = tokio::io::duplex(PIPE_CAPACITY);
+
+    tokio::task::spawn_blocking(move || {
+        let runtime = Handle::current();
+        let out = BufWriter::new(SyncIoBridge::new(writer));
+        if let Err(e) = write_archive(&runtime, &db, domain_id, out) {
+            tracing::error!(error = %e, domain_id, "Domain export failed");
+        }
+    });
+
+    reader
+}
+
+/// Build the archive in a temp file, then copy it to `out`
+fn write_archive<W: Write>(
+    runtime: &Handle,
+    db: &PgPool,
+    domain_id: i32,
+    mut out: W,
+) -> ExportResult<()> {
+    let mut file = build_archive(runtime, db, domain_id, tempfile::tempfile()?)?;
+    file.seek(SeekFrom::Start(0))?;
+    io::copy(&mut file, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn build_archive<W: Write + Seek>(
+    runtime: &Handle,
+    db: &PgPool,
+    domain_id: i32,
+    out: W,
+) -> ExportResult<W> {
+    let mut zip = ZipWriter::new(out);
+    let options = SimpleFileOptions::default();
+    let mut counts = ExportCounts::default();
+
+    let domain: serde_json::Value = runtime.block_on(
+        sqlx::query_scalar("SELECT to_jsonb(d) FROM domains d WHERE id = $1")
+            .bind(domain_id)
+            .fetch_one(db),
+    )?;
+    zip.start_file(DOMAIN_MEMBER, options)?;
+    serde_json::to_writer_pretty(&mut zip, &domain)?;
+
+    // Posts as one JSON array, written a page at a time
+    zip.start_file(POSTS_MEMBER, options)?;
+    zip.write_all(b"[")?;
+    let mut last_id = 0;
+    loop {
+        let page: Vec<(i32, serde_json::Value)> = runtime.block_on(
+            sqlx::query_as(
+                "SELECT id, to_jsonb(p) FROM posts p WHERE domain_id = $1 AND id > $2 ORDER BY id LIMIT $3",
+            )
+            .bind(domain_id)
+            .bind(last_id)
+            .bind(PAGE_SIZE)
+            .fetch_all(db),
+        )?;
+        let Some((id, _)) = page.last() else { break };
+        last_id = *id;
+
+        for (_, post) in &page {
+            if counts.posts > 0 {
+                zip.write_all(b",")?;
+            }
+            zip.write_all(b"\n")?;
+            serde_json::to_writer(&mut zip, post)?;
+            counts.posts += 1;
+        }
+    }
+    zip.write_all(b"\n]\n")?;
+
+    zip.start_file(EVENTS_MEMBER, options)?;
+    zip.write_all(EVENTS_HEADER.as_bytes())?;
+    let mut last_id = 0;
+    loop {
+        let page: Vec<EventRow> = runtime.block_on(
+            sqlx::query_as(
+                r#"
+                SELECT id, session_id, post_id, event_type, path, user_agent,
+                       host(ip_address) AS ip_address, referrer, metadata::text AS metadata, created_at
+                FROM analytics_events
+                WHERE domain_id = $1 AND id > $2
+                ORDER BY id
+                LIMIT $3
+                "#,
+            )
+            .bind(domain_id)
+            .bind(last_id)
+            .bind(PAGE_SIZE)
+            .fetch_all(db),
+        )?;
+        let Some(last) = page.last() else { break };
+        last_id = last.id;
+
+        for event in &page {
+            zip.write_all(event_csv_line(event).as_bytes())?;
+        }
+        counts.analytics_events += page.len();
+    }
+
+    let permissions: Vec<serde_json::Value> = runtime.block_on(
+        sqlx::query_scalar(
+            r#"
+            SELECT jsonb_build_object('user_id', p.user_id, 'email', u.email, 'name', u.name, 'role', p.role)
+            FROM user_domain_permissions p
+            JOIN users u ON u.id = p.user_id
+            WHERE p.domain_id = $1
+            ORDER BY p.user_id
+            "#,
+        )
+        .bind(domain_id)
+        .fetch_all(db),
+    )?;
+    counts.permissions = permissions.len();
+    zip.start_file(PERMISSIONS_MEMBER, options)?;
+    serde_json::to_writer_pretty(&mut zip, &permissions)?;
+
+    let manifest = Manifest {
+        schema_version: SCHEMA_VERSION,
+        domain_id,
+        exported_at: Utc::now(),
+        counts,
+    };
+    zip.start_file(MANIFEST_MEMBER, options)?;
+    serde_json::to_writer_pretty(&mut zip, &manifest)?;
+
+    Ok(zip.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+}
@@ -0,0 +1,309 @@
+// src/services/domain_verification.rs
+//! DNS checks that a custom domain points at the platform
+//!
+//! A domain is verified when its hostname is a CNAME for `DOMAIN_CNAME_TARGET`
+//! or `_multi-blog-verification.<hostname>` has a TXT record holding the
+//! domain's verification token. `POST /admin/domains/{id}/verify` runs the
+//! check; with `REQUIRE_DOMAIN_VERIFICATION=true`, `domain_middleware` only
+//! serves verified domains.
+
+use chrono::{DateTime, Utc};
+use hickory_resolver::TokioAsyncResolver;
+use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+use hickory_resolver::error::{ResolveError, ResolveErrorKind};
+use hickory_resolver::proto::rr::RecordType;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::env;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+/// Label under the hostname that carries the TXT record
+pub const TXT_LABEL: &str = "_multi-blog-verification";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DnsError {
+    Timeout,
+    Lookup(String),
+}
+
+impl std::fmt::Display for DnsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DnsError::Timeout => write!(f, "DNS lookup timed out"),
+            DnsError::Lookup(e) => write!(f, "DNS lookup failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DnsError {}
+
+pub type DnsFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, DnsError>> + Send + 'a>>;
+
+/// Looks up the records a verification needs
+pub trait DnsResolver: Send + Sync {
+    /// TXT strings published at `name`, empty when there are none
+    fn txt<'a>(&'a self, name: &'a str) -> DnsFuture<'a, Vec<String>>;
+    /// CNAME targets of `name`, empty when there are none
+    fn cname<'a>(&'a self, name: &'a str) -> DnsFuture<'a, Vec<String>>;
+}
+
+/// Resolves through the nameservers in the system configuration
+pub struct SystemResolver;
+
+impl SystemResolver {
+    fn resolver() -> TokioAsyncResolver {
+        TokioAsyncResolver::tokio_from_system_conf().unwrap_or_else(|_| {
+            TokioAsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default())
+        })
+    }
+}
+
+/// No records is an answer, not a failure
+fn lookup_error(e: ResolveError) -> Result<Vec<String>, DnsError> {
+    match e.kind() {
+        ResolveErrorKind::NoRecordsFound { .. } => Ok(Vec::new()),
+        ResolveErrorKind::Timeout => Err(DnsError::Timeout),
+        _ => Err(DnsError::Lookup(e.to_string())),
+    }
+}
+
+impl DnsResolver for SystemResolver {
+    fn txt<'a>(&'a self, name: &'a str) -> DnsFuture<'a, Vec<String>> {
+        Box::pin(async move {
+            match Self::resolver().txt_lookup(name).await {
+                Ok(lookup) => Ok(lookup
+                    .iter()
+                    .map(|txt| {
+                        txt.txt_data()
+                            .iter()
+                            .map(|part| String::from_utf8_lossy(part))
+                            .collect()
+                    })
+                    .collect()),
+                Err(e) => lookup_error(e),
+            }
+        })
+    }
+
+    fn cname<'a>(&'a self, name: &'a str) -> DnsFuture<'a, Vec<String>> {
+        Box::pin(async move {
+            match Self::resolver().lookup(name, RecordType::CNAME).await {
+                Ok(lookup) => Ok(lookup
+                    .iter()
+                    .filter_map(|rdata| rdata.as_cname())
+                    .map(|cname| cname.0.to_utf8())
+                    .collect()),
+                Err(e) => lookup_error(e),
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VerificationConfig {
+    /// Hostname custom domains may CNAME to; without it only TXT records count
+    pub cname_target: Option<String>,
+    /// How long the lookups of one check may take altogether
+    pub timeout: Duration,
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            cname_target: env::var("DOMAIN_CNAME_TARGET")
+                .ok()
+                .map(|target| target.trim().to_string())
+                .filter(|target| !target.is_empty()),
+            timeout: Duration::from_secs(
+                env::var("DOMAIN_VERIFICATION_TIMEOUT_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .filter(|&secs| secs > 0)
+                    .unwrap_or(5),
+            ),
+        }
+    }
+}
+
+/// Whether domain resolution serves only verified domains, from
+/// `REQUIRE_DOMAIN_VERIFICATION` (false by default)
+pub fn required() -> bool {
+    env::var("REQUIRE_DOMAIN_VERIFICATION")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
+
+/// Records an operator can publish, either of which verifies the domain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExpectedRecords {
+    pub txt_name: String,
+    pub txt_value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cname_target: Option<String>,
+}
+
+impl ExpectedRecords {
+    pub fn new(hostname: &str, token: &str, config: &VerificationConfig) -> Self {
+        Self {
+            txt_name: format!("{TXT_LABEL}.{hostname}"),
+            txt_value: token.to_string(),
+            cname_target: config.cname_target.clone(),
+        }
+    }
+}
+
+/// DNS names compare without case or the root label's trailing dot
+fn same_name(a: &str, b: &str) -> bool {
+    a.trim_end_matches('.')
+        .eq_ignore_ascii_case(b.trim_end_matches('.'))
+}
+
+/// Whether `hostname` publishes one of the `expected` records, giving up
+/// after `timeout`
+pub async fn check(
+    resolver: &dyn DnsResolver,
+    hostname: &str,
+    expected: &ExpectedRecords,
+    timeout: Duration,
+) -> Result<bool, DnsError> {
+    let lookups = async {
+        if let Some(target) = &expected.cname_target {
+            let targets = resolver.cname(hostname).await?;
+            if targets.iter().any(|name| same_name(name, target)) {
+                return Ok(true);
+            }
+        }
+        let values = resolver.txt(&expected.txt_name).await?;
+        Ok(values
+            .iter()
+            .any(|value| value.trim() == expected.txt_value))
+    };
+    tokio::time::timeout(timeout, lookups)
+        .await
+        .unwrap_or(Err(DnsError::Timeout))
+}
+
+/// What a check needs to know about a domain
+#[derive(Debug, sqlx::FromRow)]
+pub struct DomainRecord {
+    pub hostname: String,
+    pub verification_token: String,
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+pub async fn load(db: &PgPool, domain_id: i32) -> Result<Option<DomainRecord>, sqlx::Error> {
+    sqlx::query_as::<_, DomainRecord>(
+        "SELECT hostname, verification_token, verified_at FROM domains WHERE id = $1",
+    )
+    .bind(domain_id)
+    .fetch_optional(db)
+    .await
+}
+
+/// Mark the domain verified now
+pub async fn mark_verified(db: &PgPool, domain_id: i32) -> Result<DateTime<Utc>, sqlx::Error> {
+    sqlx::query_scalar("UPDATE domains SET verified_at = NOW() WHERE id = $1 RETURNING verified_at")
+        .bind(domain_id)
+        .fetch_one(db)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticResolver {
+        txt: Vec<String>,
+        cname: Vec<String>,
+    }
+
+    impl DnsResolver for StaticResolver {
+        fn txt<'a>(&'a self, _name: &'a str) -> DnsFuture<'a, Vec<String>> {
+            Box::pin(async move { Ok(self.txt.clone()) })
+        }
+
+        fn cname<'a>(&'a self, _name: &'a str) -> DnsFuture<'a, Vec<String>> {
+            Box::pin(async move { Ok(self.cname.clone()) })
+        }
+    }
+
+    struct SlowResolver;
+
+    impl DnsResolver for SlowResolver {
+        fn txt<'a>(&'a self, _name: &'a str) -> DnsFuture<'a, Vec<String>> {
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Ok(Vec::new())
+            })
+        }
+
+        fn cname<'a>(&'a self, name: &'a str) -> DnsFuture<'a, Vec<String>> {
+            self.txt(name)
+        }
+    }
+
+    fn expected(cname_target: Option<&str>) -> ExpectedRecords {
+        ExpectedRecords::new(
+            "blog.example.com",
+            "token123",
+            &VerificationConfig {
+                cname_target: cname_target.map(String::from),
+                timeout: Duration::from_secs(5),
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn txt_record_with_the_token_verifies() {
+        let resolver = StaticResolver {
+            txt: vec!["other".to_string(), "token123".to_string()],
+            cname: Vec::new(),
+        };
+        let expected = expected(None);
+        assert_eq!(
+            expected.txt_name,
+            "_multi-blog-verification.blog.example.com"
+        );
+        let verified = check(
+            &resolver,
+            "blog.example.com",
+            &expected,
+            Duration::from_secs(1),
+        );
+        assert_eq!(verified.await, Ok(true));
+    }
+
+    #[tokio::test]
+    async fn cname_counts_only_when_a_target_is_configured() {
+        let resolver = StaticResolver {
+            txt: Vec::new(),
+            cname: vec!["Blogs.Platform.test.".to_string()],
+        };
+        let timeout = Duration::from_secs(1);
+        let with_target = expected(Some("blogs.platform.test"));
+        let without_target = expected(None);
+        assert_eq!(
+            check(&resolver, "blog.example.com", &with_target, timeout).await,
+            Ok(true)
+        );
+        assert_eq!(
+            check(&resolver, "blog.example.com", &without_target, timeout).await,
+            Ok(false)
+        );
+    }
+
+    #[tokio::test]
+    async fn slow_lookups_time_out() {
+        let expected = expected(None);
+        let result = check(
+            &SlowResolver,
+            "blog.example.com",
+            &expected,
+            Duration::from_millis(10),
+        );
+        assert_eq!(result.await, Err(DnsError::Timeout));
+    }
+}
@@ -0,0 +1,254 @@
+// src/services/email_templates.rs
+//! Customizable subjects and bodies of outgoing emails
+//!
+//! Each kind of email has a built-in default. A platform admin can replace it
+//! for every domain, and a domain admin for their own domain; sending picks the
+//! domain's template, then the platform's, then the default. Templates are
+//! plain text with `{{placeholder}}` markers, filled in by [`render`].
+
+use super::digest::EmailMessage;
+use serde::Serialize;
+use sqlx::PgPool;
+
+/// The emails that can be customized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailKind {
+    PasswordReset,
+    SubscriptionConfirmation,
+}
+
+impl EmailKind {
+    pub const ALL: [EmailKind; 2] = [
+        EmailKind::PasswordReset,
+        EmailKind::SubscriptionConfirmation,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EmailKind::PasswordReset => "password_reset",
+            EmailKind::SubscriptionConfirmation => "subscription_confirmation",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == kind)
+    }
+
+    /// Whether domains can have their own template. Password resets go to
+    /// users, who belong to no single domain, so only the platform's applies.
+    pub fn per_domain(self) -> bool {
+        !matches!(self, EmailKind::PasswordReset)
+    }
+
+    /// Placeholders filled in when this kind of email is sent
+    pub fn placeholders(self) -> &'static [&'static str] {
+        match self {
+            EmailKind::PasswordReset => &["email", "token", "expires_at"],
+            EmailKind::SubscriptionConfirmation => &[
+                "email",
+                "blog",
+                "hostname",
+                "token",
+                "confirm_url",
+                "expires_at",
+            ],
+        }
+    }
+
+    /// The template used when neither the domain nor the platform has one
+    pub fn default_template(self) -> EmailTemplate {
+        let (subject, body) = match self {
+            EmailKind::PasswordReset => (
+                "Reset your password",
+                "A password reset was requested for your account.\n\n\
+                 Reset token: {{token}}\n\n\
+                 Send it with your new password to POST /auth/reset-password as\n\
+                 {\"token\": \"...\", \"new_password\": \"...\"}. The token works once and\n\
+                 expires at {{expires_at}}.\n",
+            ),
+            EmailKind::SubscriptionConfirmation => (
+                "Confirm your subscription to {{blog}}",
+                "Someone, hopefully you, subscribed this address to {{blog}}.\n\n\
+                 Confirm your subscription by opening:\n\
+                 {{confirm_url}}\n\n\
+                 The link expires at {{expires_at}}. If you didn't subscribe, ignore this\n\
+                 email and you won't hear from us again.\n",
+            ),
+        };
+        EmailTemplate {
+            subject: subject.to_string(),
+            body: body.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EmailTemplate {
+    pub subject: String,
+    pub body: String,
+}
+
+impl EmailTemplate {
+    /// The email to `to` with the placeholders in `values` filled in
+    pub fn render(&self, to: &str, values: &[(&str, &str)]) -> EmailMessage {
+        EmailMessage {
+            to: to.to_string(),
+            // A header can't span lines, whatever a value contains
+            subject: render(&self.subject, values).replace(['\r', '\n'], " "),
+            body: render(&self.body, values),
+        }
+    }
+}
+
+/// Where the template in effect comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateSource {
+    Domain,
+    Platform,
+    Default,
+}
+
+/// Replace each `{{name}}` (spaces inside the braces allowed) with its value.
+/// Placeholders without a value and unmatched braces are kept as written, so
+/// a typo in a template shows up in the email instead of failing the send.
+pub fn render(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest[2..].find("}}") else {
+            break;
+        };
+        let name = rest[2..2 + end].trim();
+        match values.iter().find(|(key, _)| *key == name) {
+            Some((_, value)) => out.push_str(value),
+            None => out.push_str(&rest[..end + 4]),
+        }
+        rest = &rest[end + 4..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The template in effect for `kind` on `domain_id` (`None` for emails that
+/// don't belong to a domain), and where it comes from
+pub async fn resolve(
+    db: &PgPool,
+    domain_id: Option<i32>,
+    kind: EmailKind,
+) -> Result<(EmailTemplate, TemplateSource), sqlx::Error> {
+    let stored: Option<(String, String, bool)> = sqlx::query_as(
+        r#"
+        SELECT subject, body, domain_id IS NOT NULL
+        FROM email_templates
+        WHERE kind = $1 AND (domain_id = $2 OR domain_id IS NULL)
+        ORDER BY domain_id NULLS LAST
+        LIMIT 1
+        "#,
+    )
+    .bind(kind.as_str())
+    .bind(domain_id)
+    .fetch_optional(db)
+    .await?;
+
+    Ok(match stored {
+        Some((subject, body, for_domain)) => (
+            EmailTemplate { subject, body },
+            if for_domain {
+                TemplateSource::Domain
+            } else {
+                TemplateSource::Platform
+            },
+        ),
+        None => (kind.default_template(), TemplateSource::Default),
+    })
+}
+
+/// Store the template for `kind` on `domain_id`, or platform-wide for `None`
+pub async fn save(
+    db: &PgPool,
+    domain_id: Option<i32>,
+    kind: EmailKind,
+    template: &EmailTemplate,
+) -> Result<(), sqlx::Error> {
+    // Each partial unique index needs its own conflict target
+    let conflict = if domain_id.is_some() {
+        "(domain_id, kind) WHERE domain_id IS NOT NULL"
+    } else {
+        "(kind) WHERE domain_id IS NULL"
+    };
+    sqlx::query(&format!(
+        r#"
+        INSERT INTO email_templates (domain_id, kind, subject, body)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT {conflict}
+        DO UPDATE SET subject = EXCLUDED.subject, body = EXCLUDED.body, updated_at = NOW()
+        "#
+    ))
+    .bind(domain_id)
+    .bind(kind.as_str())
+    .bind(&template.subject)
+    .bind(&template.body)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Drop the template for `kind` on `domain_id`, or platform-wide for `None`;
+/// returns whether there was one
+pub async fn delete(
+    db: &PgPool,
+    domain_id: Option<i32>,
+    kind: EmailKind,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "DELETE FROM email_templates WHERE kind = $1 AND domain_id IS NOT DISTINCT FROM $2",
+    )
+    .bind(kind.as_str())
+    .bind(domain_id)
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_fills_known_placeholders() {
+        let rendered = render(
+            "Hi {{ email }}, welcome to {{blog}}!",
+            &[("email", "a@b.com"), ("blog", "Test Blog")],
+        );
+        assert_eq!(rendered, "Hi a@b.com, welcome to Test Blog!");
+    }
+
+    #[test]
+    fn render_keeps_what_it_cannot_fill() {
+        let values = [("blog", "Test Blog")];
+        assert_eq!(
+            render("{{unknown}} {{blog}}", &values),
+            "{{unknown}} Test Blog"
+        );
+        assert_eq!(render("{{blog}} {{blog", &values), "Test Blog {{blog");
+        assert_eq!(render("{{}} }} {", &values), "{{}} }} {");
+        assert_eq!(render("ünïcode {{blog}}", &values), "ünïcode Test Blog");
+        assert_eq!(render("", &values), "");
+    }
+
+    #[test]
+    fn defaults_only_use_their_placeholders() {
+        for kind in EmailKind::ALL {
+            assert_eq!(EmailKind::parse(kind.as_str()), Some(kind));
+            let template = kind.default_template();
+            let values: Vec<(&str, &str)> = kind.placeholders().iter().map(|p| (*p, "x")).collect();
+            let email = template.render("to@example.com", &values);
+            assert!(!email.subject.contains("{{"), "{kind:?}");
+            assert!(!email.body.contains("{{"), "{kind:?}");
+        }
+        assert_eq!(EmailKind::parse("welcome"), None);
+    }
+}
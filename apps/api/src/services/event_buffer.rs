@@ -0,0 +1,277 @@
+// src/services/event_buffer.rs
+//! Buffered writes of `analytics_events`
+//!
+//! Page views, searches and custom events are queued on a bounded channel and
+//! written by one task in multi-row inserts, once `batch_size` events are
+//! waiting or every `flush_interval`, whichever comes first. A full queue makes
+//! the recording request wait for room rather than grow without limit.
+//! [`EventBuffer::shutdown`] writes whatever is still queued; events recorded
+//! after it are written one by one.
+
+use crate::services::event_types;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::env;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tracing::error;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct EventBufferConfig {
+    /// Events written per insert
+    pub batch_size: usize,
+    /// Longest an event waits in the buffer
+    pub flush_interval: Duration,
+    /// Events the queue holds before recording waits
+    pub capacity: usize,
+}
+
+impl Default for EventBufferConfig {
+    fn default() -> Self {
+        let batch_size = env::var("ANALYTICS_BUFFER_BATCH_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&size| size > 0)
+            .unwrap_or(200);
+        Self {
+            batch_size,
+            flush_interval: Duration::from_millis(
+                env::var("ANALYTICS_BUFFER_FLUSH_MS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .filter(|&ms| ms > 0)
+                    .unwrap_or(1000),
+            ),
+            capacity: env::var("ANALYTICS_BUFFER_CAPACITY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10_000)
+                .max(batch_size),
+        }
+    }
+}
+
+/// One `analytics_events` row waiting to be written
+#[derive(Debug, Clone)]
+pub struct AnalyticsEvent {
+    pub domain_id: i32,
+    /// Client-facing `user_sessions.session_id`, resolved to the row id on write
+    pub session_id: Option<Uuid>,
+    /// One of [`event_types::BUILT_IN`]
+    pub event_type: &'static str,
+    pub path: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<IpAddr>,
+    pub referrer: Option<String>,
+    pub metadata: serde_json::Value,
+    pub sample_weight: f64,
+    /// When the event happened, not when it was written
+    pub created_at: DateTime<Utc>,
+}
+
+impl AnalyticsEvent {
+    /// An event that happened at `created_at`, usually the state clock's now
+    pub fn new(domain_id: i32, event_type: &'static str, created_at: DateTime<Utc>) -> Self {
+        debug_assert!(event_types::BUILT_IN.contains(&event_type));
+        Self {
+            domain_id,
+            session_id: None,
+            event_type,
+            path: None,
+            user_agent: None,
+            ip_address: None,
+            referrer: None,
+            metadata: serde_json::json!({}),
+            sample_weight: 1.0,
+            created_at,
+        }
+    }
+}
+
+/// Write `events` in one insert
+pub async fn insert_batch(db: &PgPool, events: &[AnalyticsEvent]) -> Result<u64, sqlx::Error> {
+    if events.is_empty() {
+        return Ok(0);
+    }
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO analytics_events (domain_id, session_id, event_type, path, user_agent,
+                                      ip_address, referrer, metadata, sample_weight, created_at)
+        SELECT e.domain_id, s.id, e.event_type, e.path, e.user_agent,
+               e.ip_address, e.referrer, e.metadata, e.sample_weight, e.created_at
+        FROM UNNEST($1::int[], $2::uuid[], $3::text[], $4::text[], $5::text[],
+                    $6::inet[], $7::text[], $8::jsonb[], $9::float8[], $10::timestamptz[])
+             AS e(domain_id, session_id, event_type, path, user_agent,
+                  ip_address, referrer, metadata, sample_weight, created_at)
+        LEFT JOIN user_sessions s ON s.session_id = e.session_id
+        "#,
+    )
+    .bind(events.iter().map(|e| e.domain_id).collect::<Vec<_>>())
+    .bind(events.iter().map(|e| e.session_id).collect::<Vec<_>>())
+    .bind(events.iter().map(|e| e.event_type).collect::<Vec<_>>())
+    .bind(events.iter().map(|e| e.path.clone()).collect::<Vec<_>>())
+    .bind(
+        events
+            .iter()
+            .map(|e| e.user_agent.clone())
+            .collect::<Vec<_>>(),
+    )
+    .bind(events.iter().map(|e| e.ip_address).collect::<Vec<_>>())
+    .bind(
+        events
+            .iter()
+            .map(|e| e.referrer.clone())
+            .collect::<Vec<_>>(),
+    )
+    .bind(
+        events
+            .iter()
+            .map(|e| e.metadata.clone())
+            .collect::<Vec<_>>(),
+    )
+    .bind(events.iter().map(|e| e.sample_weight).collect::<Vec<_>>())
+    .bind(events.iter().map(|e| e.created_at).collect::<Vec<_>>())
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+enum Command {
+    Record(AnalyticsEvent),
+    Flush(oneshot::Sender<()>),
+    Shutdown(oneshot::Sender<()>),
+}
+
+/// Allowance for the insert itself on top of the time an event waits
+const WRITE_SLACK: Duration = Duration::from_secs(1);
+
+/// Where handlers record analytics events
+#[derive(Clone)]
+pub struct EventBuffer {
+    db: PgPool,
+    sender: Option<mpsc::Sender<Command>>,
+    flush_interval: Duration,
+}
+
+impl EventBuffer {
+    /// Write every event as it is recorded, without a background task
+    pub fn unbuffered(db: PgPool) -> Self {
+        Self {
+            db,
+            sender: None,
+            flush_interval: Duration::ZERO,
+        }
+    }
+
+    /// Start the task that batches recorded events
+    pub fn spawn(db: PgPool, config: EventBufferConfig) -> Self {
+        let (sender, receiver) = mpsc::channel(config.capacity);
+        let flush_interval = config.flush_interval;
+        tokio::spawn(run(db.clone(), config, receiver));
+        Self {
+            db,
+            sender: Some(sender),
+            flush_interval,
+        }
+    }
+
+    /// How long after its `created_at` an event may still be on its way to
+    /// `analytics_events`. Readers that poll by `created_at` stay this far
+    /// behind the current time so they don't step past unwritten events.
+    pub fn write_delay(&self) -> Duration {
+        self.flush_interval + WRITE_SLACK
+    }
+
+    /// Queue `event`, waiting for room when the queue is full
+    pub async fn record(&self, event: AnalyticsEvent) -> Result<(), sqlx::Error> {
+        let event = match &self.sender {
+            Some(sender) => match sender.send(Command::Record(event)).await {
+                Ok(()) => return Ok(()),
+                // The buffer was shut down; don't lose the event
+                Err(mpsc::error::SendError(Command::Record(event))) => event,
+                Err(_) => unreachable!("only events are sent here"),
+            },
+            None => event,
+        };
+        insert_batch(&self.db, std::slice::from_ref(&event)).await?;
+        Ok(())
+    }
+
+    /// Write everything queued so far
+    pub async fn flush(&self) {
+        self.send_and_wait(Command::Flush).await;
+    }
+
+    /// Write everything queued and stop the background task
+    pub async fn shutdown(&self) {
+        self.send_and_wait(Command::Shutdown).await;
+    }
+
+    async fn send_and_wait(&self, command: fn(oneshot::Sender<()>) -> Command) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        let (done, written) = oneshot::channel();
+        if sender.send(command(done)).await.is_ok() {
+            let _ = written.await;
+        }
+    }
+}
+
+async fn run(db: PgPool, config: EventBufferConfig, mut receiver: mpsc::Receiver<Command>) {
+    let mut pending = Vec::with_capacity(config.batch_size);
+    let mut ticker = tokio::time::interval(config.flush_interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            command = receiver.recv() => match command {
+                Some(Command::Record(event)) => {
+                    pending.push(event);
+                    if pending.len() >= config.batch_size {
+                        write(&db, &mut pending, config.batch_size).await;
+                    }
+                }
+                Some(Command::Flush(done)) => {
+                    write(&db, &mut pending, config.batch_size).await;
+                    let _ = done.send(());
+                }
+                Some(Command::Shutdown(done)) => {
+                    // Take what's already queued, then refuse more
+                    receiver.close();
+                    let mut waiting = vec![done];
+                    while let Some(command) = receiver.recv().await {
+                        match command {
+                            Command::Record(event) => pending.push(event),
+                            Command::Flush(done) | Command::Shutdown(done) => waiting.push(done),
+                        }
+                    }
+                    write(&db, &mut pending, config.batch_size).await;
+                    for done in waiting {
+                        let _ = done.send(());
+                    }
+                    return;
+                }
+                None => {
+                    write(&db, &mut pending, config.batch_size).await;
+                    return;
+                }
+            },
+            _ = ticker.tick() => write(&db, &mut pending, config.batch_size).await,
+        }
+    }
+}
+
+/// Write and clear `pending`, at most `batch_size` events per insert
+async fn write(db: &PgPool, pending: &mut Vec<AnalyticsEvent>, batch_size: usize) {
+    for batch in pending.chunks(batch_size) {
+        if let Err(e) = insert_batch(db, batch).await {
+            error!(error = %e, events = batch.len(), "Failed to write buffered analytics events");
+        }
+    }
+    pending.clear();
+}
@@ -0,0 +1,69 @@
+// src/services/event_types.rs
+//! Registered `analytics_events.event_type` values
+//!
+//! The API writes the built-in types below. Domains may also define custom
+//! events by listing their names in `analytics_config.custom_events`; those
+//! are stored as [`CUSTOM`] rows with the name and properties in `metadata`.
+
+use crate::DomainContext;
+
+pub const PAGE_VIEW: &str = "page_view";
+pub const POST_VIEW: &str = "post_view";
+pub const SEARCH: &str = "search";
+pub const CUSTOM: &str = "custom";
+
+/// Every event type the API stores
+pub const BUILT_IN: &[&str] = &[PAGE_VIEW, POST_VIEW, SEARCH, CUSTOM];
+
+/// Longest custom event name
+pub const MAX_CUSTOM_NAME_LENGTH: usize = 50;
+
+/// Whether `name` can be registered as a custom event: 1 to 50 lowercase
+/// letters, digits or underscores, and not a built-in type
+pub fn is_valid_custom_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_CUSTOM_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !BUILT_IN.contains(&name)
+}
+
+/// Custom event names registered for `domain`
+pub fn custom_events(domain: &DomainContext) -> Vec<&str> {
+    domain
+        .theme_config
+        .pointer("/analytics_config/custom_events")
+        .and_then(serde_json::Value::as_array)
+        .map(|names| names.iter().filter_map(serde_json::Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// Whether `domain` registered a custom event called `name`
+pub fn is_registered(domain: &DomainContext, name: &str) -> bool {
+    custom_events(domain).contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_names_are_snake_case() {
+        assert!(is_valid_custom_name("newsletter_signup"));
+        assert!(is_valid_custom_name("cta2"));
+        assert!(!is_valid_custom_name(""));
+        assert!(!is_valid_custom_name("Signup"));
+        assert!(!is_valid_custom_name("sign-up"));
+        assert!(!is_valid_custom_name(
+            &"a".repeat(MAX_CUSTOM_NAME_LENGTH + 1)
+        ));
+    }
+
+    #[test]
+    fn built_in_types_cannot_be_custom() {
+        for event_type in BUILT_IN {
+            assert!(!is_valid_custom_name(event_type));
+        }
+    }
+}
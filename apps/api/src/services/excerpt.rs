@@ -0,0 +1,138 @@
+// src/services/excerpt.rs
+//! Post excerpts for listings and feeds
+//!
+//! Editors may write an excerpt; when they don't, one is generated on save
+//! from the start of the content as plain text: HTML tags, markdown link
+//! targets, code fences and formatting characters are dropped, whitespace is
+//! collapsed, and the cut falls on a word boundary.
+
+use super::reading_time::{HTML_TAG, MARKDOWN_LINK};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Longest excerpt an editor may write
+pub const MAX_EXCERPT_CHARS: usize = 500;
+
+/// Fenced code blocks, markers and body alike
+static CODE_FENCE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)```.*?(```|$)").unwrap());
+
+/// Heading, blockquote and list markers at the start of a line
+static LINE_MARKER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^\s*(#{1,6}|>+|[-*+]|\d+\.)\s+").unwrap());
+
+/// Emphasis, strikethrough and inline code characters. Underscores are left
+/// alone since they show up inside identifiers far more than as emphasis.
+static INLINE_MARKUP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[*~`]+").unwrap());
+
+/// Whitespace left before punctuation once inline tags are gone
+static SPACE_BEFORE_PUNCTUATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\s+([.,;:!?])").unwrap());
+
+/// Length of generated excerpts in characters.
+/// Configurable via `EXCERPT_LENGTH` (default 200).
+pub fn excerpt_length() -> usize {
+    std::env::var("EXCERPT_LENGTH")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|n| (1..=MAX_EXCERPT_CHARS).contains(n))
+        .unwrap_or(200)
+}
+
+/// Markdown/HTML content as a single line of plain text
+pub fn plain_text(content: &str) -> String {
+    let text = CODE_FENCE.replace_all(content, " ");
+    let text = HTML_TAG.replace_all(&text, " ");
+    let text = MARKDOWN_LINK.replace_all(&text, "$1");
+    let text = LINE_MARKER.replace_all(&text, " ");
+    let text = INLINE_MARKUP.replace_all(&text, "");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    SPACE_BEFORE_PUNCTUATION
+        .replace_all(&text, "$1")
+        .into_owned()
+}
+
+/// At most `max_chars` characters of the content's plain text, cut at the
+/// last word boundary and followed by `…` when anything was left out
+pub fn generate(content: &str, max_chars: usize) -> String {
+    let text = plain_text(content);
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+
+    let cut = text
+        .char_indices()
+        .nth(max_chars)
+        .map_or(text.len(), |(i, _)| i);
+    let head = &text[..cut];
+    // The next character being a space means the cut already ends a word
+    let head = if text[cut..].starts_with(' ') {
+        head
+    } else {
+        head.rfind(' ').map_or(head, |space| &head[..space])
+    };
+
+    let head = head.trim_end_matches(|c: char| c.is_whitespace() || ",;:-".contains(c));
+    format!("{head}…")
+}
+
+/// The editor's excerpt when they gave a non-blank one, otherwise one
+/// generated from the content
+pub fn resolve(explicit: Option<&str>, content: &str) -> String {
+    match explicit.map(str::trim).filter(|e| !e.is_empty()) {
+        Some(excerpt) => excerpt.to_string(),
+        None => generate(content, excerpt_length()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_text_strips_markdown() {
+        let content = "# Getting Started\n\n\
+            Rust is **fast** and <em>safe</em>.\n\n\
+            - Read [the book](https://doc.rust-lang.org/book/)\n\
+            ```\nfn main() {}\n```\n\
+            > Quote `here`";
+        assert_eq!(
+            plain_text(content),
+            "Getting Started Rust is fast and safe. Read the book Quote here"
+        );
+    }
+
+    #[test]
+    fn test_short_content_kept_whole() {
+        assert_eq!(generate("A **short** post.", 200), "A short post.");
+        assert_eq!(generate("", 200), "");
+    }
+
+    #[test]
+    fn test_generated_excerpt_ends_on_word_boundary() {
+        let content = "The quick brown fox jumps over the lazy dog";
+        // "The quick brown fo|x" would split a word
+        assert_eq!(generate(content, 18), "The quick brown…");
+        // Cutting right before a space keeps the whole last word
+        assert_eq!(generate(content, 15), "The quick brown…");
+
+        let long = "word ".repeat(100);
+        let excerpt = generate(&long, 200);
+        assert!(excerpt.chars().count() <= 201);
+        assert!(excerpt.trim_end_matches('…').ends_with("word"));
+    }
+
+    #[test]
+    fn test_single_long_word_is_cut() {
+        assert_eq!(generate(&"a".repeat(10), 4), "aaaa…");
+    }
+
+    #[test]
+    fn test_explicit_excerpt_preserved() {
+        assert_eq!(
+            resolve(Some("  Hand-written summary  "), "Body text"),
+            "Hand-written summary"
+        );
+        assert_eq!(resolve(Some("   "), "Body text"), "Body text");
+        assert_eq!(resolve(None, "Body text"), "Body text");
+    }
+}
@@ -0,0 +1,308 @@
+// src/services/health.rs
+//! Readiness probes for the services the API depends on
+//!
+//! Each dependency registers a [`HealthCheck`] in the [`HealthRegistry`] kept
+//! in `AppState`, and `GET /health/ready` runs them all at once. A failing
+//! critical check (the database) makes the instance unready, so load
+//! balancers stop sending it traffic; a failing optional one (SMTP) is
+//! reported as degraded while requests keep being served. `/health` stays a
+//! plain liveness check.
+
+use super::digest::SmtpMailer;
+use axum::{
+    Json,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+/// How long a probe may take before it counts as failed.
+/// Configurable via `HEALTH_CHECK_TIMEOUT_MS` (default 2000).
+pub fn probe_timeout() -> Duration {
+    let millis = std::env::var("HEALTH_CHECK_TIMEOUT_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2000);
+    Duration::from_millis(millis)
+}
+
+/// Boxed future returned by [`HealthCheck::check`]; the error says what failed
+pub type ProbeFuture<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+
+/// A probe of one dependency
+pub trait HealthCheck: Send + Sync {
+    /// Key of the component in the readiness report
+    fn name(&self) -> &str;
+    /// Whether the API can't serve requests while this is down
+    fn critical(&self) -> bool {
+        true
+    }
+    fn check(&self) -> ProbeFuture<'_>;
+}
+
+/// Postgres answers a trivial query
+pub struct DatabaseCheck(pub PgPool);
+
+impl HealthCheck for DatabaseCheck {
+    fn name(&self) -> &str {
+        "database"
+    }
+
+    fn check(&self) -> ProbeFuture<'_> {
+        Box::pin(async move {
+            sqlx::query("SELECT 1")
+                .execute(&self.0)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+/// The SMTP relay accepts a connection; only emails depend on it
+pub struct SmtpCheck(pub Arc<SmtpMailer>);
+
+impl HealthCheck for SmtpCheck {
+    fn name(&self) -> &str {
+        "smtp"
+    }
+
+    fn critical(&self) -> bool {
+        false
+    }
+
+    fn check(&self) -> ProbeFuture<'_> {
+        Box::pin(self.0.test_connection())
+    }
+}
+
+/// The registered probes, shared by every clone
+#[derive(Clone)]
+pub struct HealthRegistry {
+    checks: Arc<Vec<Arc<dyn HealthCheck>>>,
+    timeout: Duration,
+}
+
+impl Default for HealthRegistry {
+    fn default() -> Self {
+        Self::new(probe_timeout())
+    }
+}
+
+impl HealthRegistry {
+    /// An empty registry whose probes fail after `timeout`
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            checks: Arc::new(Vec::new()),
+            timeout,
+        }
+    }
+
+    /// Add a probe; one registered under the same name is replaced
+    pub fn with(mut self, check: impl HealthCheck + 'static) -> Self {
+        let checks = Arc::make_mut(&mut self.checks);
+        checks.retain(|existing| existing.name() != check.name());
+        checks.push(Arc::new(check));
+        self
+    }
+
+    /// Run every probe concurrently
+    pub async fn report(&self) -> HealthReport {
+        let mut probes = JoinSet::new();
+        for check in self.checks.iter() {
+            let check = Arc::clone(check);
+            let timeout = self.timeout;
+            probes.spawn(async move {
+                let started = Instant::now();
+                let result = match tokio::time::timeout(timeout, check.check()).await {
+                    Ok(result) => result,
+                    Err(_) => Err(format!("timed out after {}ms", timeout.as_millis())),
+                };
+                let status = ComponentStatus {
+                    status: if result.is_ok() { "ok" } else { "error" },
+                    critical: check.critical(),
+                    latency_ms: started.elapsed().as_millis() as u64,
+                    error: result.err(),
+                };
+                (check.name().to_string(), status)
+            });
+        }
+
+        let mut components = BTreeMap::new();
+        while let Some(probe) = probes.join_next().await {
+            // A probe that panicked can't be told apart from a failed one
+            if let Ok((name, status)) = probe {
+                components.insert(name, status);
+            }
+        }
+        for check in self.checks.iter() {
+            components
+                .entry(check.name().to_string())
+                .or_insert_with(|| ComponentStatus {
+                    status: "error",
+                    critical: check.critical(),
+                    latency_ms: 0,
+                    error: Some("probe panicked".to_string()),
+                });
+        }
+
+        let failing = components.values().filter(|c| c.status != "ok");
+        let status = match failing.map(|c| c.critical).max() {
+            None => "ok",
+            Some(false) => "degraded",
+            Some(true) => "down",
+        };
+        HealthReport {
+            status,
+            components,
+            timestamp: chrono::Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComponentStatus {
+    pub status: &'static str, // ok or error
+    pub critical: bool,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    /// ok, degraded (an optional component is down) or down (a critical one is)
+    pub status: &'static str,
+    pub components: BTreeMap<String, ComponentStatus>,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl HealthReport {
+    pub fn ready(&self) -> bool {
+        self.status != "down"
+    }
+}
+
+/// `GET /health/ready`: the report, with 503 while a critical component is down
+pub async fn readiness_handler(State(registry): State<HealthRegistry>) -> Response {
+    let report = registry.report().await;
+    let status = if report.ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Probe {
+        name: &'static str,
+        critical: bool,
+        healthy: bool,
+    }
+
+    impl HealthCheck for Probe {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn critical(&self) -> bool {
+            self.critical
+        }
+
+        fn check(&self) -> ProbeFuture<'_> {
+            let healthy = self.healthy;
+            Box::pin(async move {
+                if healthy {
+                    Ok(())
+                } else {
+                    Err("connection refused".to_string())
+                }
+            })
+        }
+    }
+
+    struct Hangs;
+
+    impl HealthCheck for Hangs {
+        fn name(&self) -> &str {
+            "geoip"
+        }
+
+        fn check(&self) -> ProbeFuture<'_> {
+            Box::pin(std::future::pending())
+        }
+    }
+
+    fn probe(name: &'static str, critical: bool, healthy: bool) -> Probe {
+        Probe {
+            name,
+            critical,
+            healthy,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_no_probes_is_ready() {
+        let report = HealthRegistry::new(Duration::from_secs(1)).report().await;
+        assert_eq!(report.status, "ok");
+        assert!(report.components.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_optional_failure_is_degraded_but_ready() {
+        let report = HealthRegistry::new(Duration::from_secs(1))
+            .with(probe("database", true, true))
+            .with(probe("smtp", false, false))
+            .report()
+            .await;
+
+        assert_eq!(report.status, "degraded");
+        assert!(report.ready());
+        assert_eq!(report.components["database"].status, "ok");
+        assert_eq!(report.components["smtp"].status, "error");
+        assert_eq!(
+            report.components["smtp"].error.as_deref(),
+            Some("connection refused")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_critical_failure_or_timeout_is_down() {
+        let report = HealthRegistry::new(Duration::from_millis(50))
+            .with(probe("database", true, true))
+            .with(Hangs)
+            .report()
+            .await;
+
+        assert_eq!(report.status, "down");
+        assert!(!report.ready());
+        assert_eq!(
+            report.components["geoip"].error.as_deref(),
+            Some("timed out after 50ms")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_registering_a_name_again_replaces_the_probe() {
+        let report = HealthRegistry::new(Duration::from_secs(1))
+            .with(probe("database", true, false))
+            .with(probe("database", true, true))
+            .report()
+            .await;
+
+        assert_eq!(report.status, "ok");
+        assert_eq!(report.components.len(), 1);
+    }
+}
@@ -0,0 +1,127 @@
+// src/services/live.rs
+//! Live analytics feed for `/analytics/ws`
+//!
+//! Ingestion publishes small deltas to a process-wide broadcast channel and
+//! each socket subscribes to it. A subscriber that falls more than
+//! [`CHANNEL_CAPACITY`] events behind misses deltas, so it gets a snapshot
+//! instead (at most one per [`SNAPSHOT_INTERVAL`]).
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::LazyLock;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber before it counts as lagging
+pub const CHANNEL_CAPACITY: usize = 256;
+
+/// Minimum gap between snapshots sent to a lagging subscriber
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(5);
+
+static FEED: LazyLock<broadcast::Sender<LiveEvent>> =
+    LazyLock::new(|| broadcast::channel(CHANNEL_CAPACITY).0);
+
+/// One delta pushed to dashboard sockets
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    PageView {
+        domain_id: i32,
+        path: String,
+        at: DateTime<Utc>,
+    },
+    SessionStart {
+        domain_id: i32,
+        at: DateTime<Utc>,
+    },
+}
+
+impl LiveEvent {
+    pub fn domain_id(&self) -> i32 {
+        match self {
+            LiveEvent::PageView { domain_id, .. } | LiveEvent::SessionStart { domain_id, .. } => {
+                *domain_id
+            }
+        }
+    }
+}
+
+/// Publish an event to every connected dashboard. Dropped if nobody listens.
+pub fn publish(event: LiveEvent) {
+    let _ = FEED.send(event);
+}
+
+/// Subscribe to events published from now on
+pub fn subscribe() -> broadcast::Receiver<LiveEvent> {
+    FEED.subscribe()
+}
+
+/// Current totals sent in place of deltas to a lagging subscriber
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveSnapshot {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub active_visitors: i64,
+    pub page_views_last_hour: i64,
+    pub at: DateTime<Utc>,
+}
+
+pub async fn snapshot(db: &PgPool, domain_ids: &[i32]) -> Result<LiveSnapshot, sqlx::Error> {
+    let now = Utc::now();
+    let (active_visitors, page_views_last_hour): (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT
+            COUNT(DISTINCT ip_address) FILTER (WHERE created_at > $2),
+            ROUND(COALESCE(SUM(sample_weight) FILTER (WHERE event_type = 'page_view'), 0))::BIGINT
+        FROM analytics_events
+        WHERE domain_id = ANY($1) AND created_at > $3
+        "#,
+    )
+    .bind(domain_ids)
+    .bind(now - ChronoDuration::minutes(5))
+    .bind(now - ChronoDuration::hours(1))
+    .fetch_one(db)
+    .await?;
+
+    Ok(LiveSnapshot {
+        kind: "snapshot",
+        active_visitors,
+        page_views_last_hour,
+        at: now,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_serializes_as_tagged_delta() {
+        let at = Utc::now();
+        let event = LiveEvent::PageView {
+            domain_id: 3,
+            path: "/posts/hello".to_string(),
+            at,
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "page_view");
+        assert_eq!(json["domain_id"], 3);
+        assert_eq!(json["path"], "/posts/hello");
+        assert_eq!(event.domain_id(), 3);
+
+        let json = serde_json::to_value(LiveEvent::SessionStart { domain_id: 4, at }).unwrap();
+        assert_eq!(json["type"], "session_start");
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_published_events() {
+        let mut receiver = subscribe();
+        let event = LiveEvent::SessionStart {
+            domain_id: -1,
+            at: Utc::now(),
+        };
+        publish(event.clone());
+        assert_eq!(receiver.recv().await.unwrap(), event);
+    }
+}
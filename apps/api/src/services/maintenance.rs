@@ -0,0 +1,119 @@
+// src/services/maintenance.rs
+//! Maintenance mode
+//!
+//! While on, public blog, session and tracking routes answer 503 so deploys and
+//! migrations don't race visitor traffic. Health checks, `/auth` and the admin
+//! panel keep working so platform admins can check on things and switch it
+//! back off. It is on when `MAINTENANCE_MODE` is set, or when a platform admin
+//! turns on the `maintenance_mode` runtime setting.
+//!
+//! Public requests read the setting through [`MaintenanceFlag`], which keeps
+//! it for [`FLAG_TTL`] instead of querying `settings` every time. Toggling it
+//! through the flag takes effect on this instance at once; other instances
+//! pick it up when their copy expires.
+
+use sqlx::PgPool;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const SETTING_KEY: &str = "maintenance_mode";
+
+/// How long the runtime setting is trusted before it is read again
+pub const FLAG_TTL: Duration = Duration::from_secs(5);
+
+/// Whether `MAINTENANCE_MODE` forces maintenance on regardless of the setting
+pub fn forced_by_env() -> bool {
+    std::env::var("MAINTENANCE_MODE")
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "on"))
+        .unwrap_or(false)
+}
+
+/// The runtime toggle stored in `settings`
+pub async fn runtime_enabled(db: &PgPool) -> Result<bool, sqlx::Error> {
+    let value: Option<serde_json::Value> =
+        sqlx::query_scalar("SELECT value FROM settings WHERE key = $1")
+            .bind(SETTING_KEY)
+            .fetch_optional(db)
+            .await?;
+
+    Ok(value.and_then(|v| v.as_bool()).unwrap_or(false))
+}
+
+/// Turn the runtime toggle on or off
+pub async fn set_runtime_enabled(db: &PgPool, enabled: bool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO settings (key, value) VALUES ($1, $2)
+        ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value, updated_at = NOW()
+        "#,
+    )
+    .bind(SETTING_KEY)
+    .bind(serde_json::Value::Bool(enabled))
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// The runtime setting as last read, shared by clones
+#[derive(Debug, Clone, Default)]
+pub struct MaintenanceFlag {
+    cached: Arc<Mutex<Option<(Instant, bool)>>>,
+}
+
+impl MaintenanceFlag {
+    /// Whether public routes should currently be refused, reading the
+    /// setting only when the cached value has expired
+    pub async fn is_enabled(&self, db: &PgPool) -> Result<bool, sqlx::Error> {
+        if forced_by_env() {
+            return Ok(true);
+        }
+        if let Some(enabled) = self.get_at(Instant::now()) {
+            return Ok(enabled);
+        }
+        let enabled = runtime_enabled(db).await?;
+        self.store_at(enabled, Instant::now());
+        Ok(enabled)
+    }
+
+    /// Turn the runtime toggle on or off, updating the cached value too
+    pub async fn set(&self, db: &PgPool, enabled: bool) -> Result<(), sqlx::Error> {
+        set_runtime_enabled(db, enabled).await?;
+        self.store_at(enabled, Instant::now());
+        Ok(())
+    }
+
+    fn get_at(&self, now: Instant) -> Option<bool> {
+        let cached = *self.cached.lock().unwrap();
+        cached
+            .filter(|(read_at, _)| now.saturating_duration_since(*read_at) < FLAG_TTL)
+            .map(|(_, enabled)| enabled)
+    }
+
+    fn store_at(&self, enabled: bool, now: Instant) {
+        *self.cached.lock().unwrap() = Some((now, enabled));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_flag_expires_after_ttl() {
+        let flag = MaintenanceFlag::default();
+        let now = Instant::now();
+        assert_eq!(flag.get_at(now), None);
+
+        flag.store_at(true, now);
+        assert_eq!(
+            flag.get_at(now + FLAG_TTL - Duration::from_millis(1)),
+            Some(true)
+        );
+        assert_eq!(flag.get_at(now + FLAG_TTL), None);
+
+        let clone = flag.clone();
+        clone.store_at(false, now + FLAG_TTL);
+        assert_eq!(flag.get_at(now + FLAG_TTL), Some(false));
+    }
+}
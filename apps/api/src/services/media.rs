@@ -0,0 +1,296 @@
+// src/services/media.rs
+//! Media uploads: validation rules and the pluggable storage backend
+//!
+//! Files are written through the [`Storage`] trait. Only the local filesystem
+//! backend exists today; `STORAGE_BACKEND` selects the implementation so an
+//! S3-compatible backend can be added without touching the handlers.
+
+use std::env;
+use std::future::Future;
+use std::path::{Component, Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Content types accepted for upload, with the extension used when storing them
+const ALLOWED_CONTENT_TYPES: &[(&str, &str)] = &[
+    ("image/jpeg", "jpg"),
+    ("image/png", "png"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+];
+
+/// Default upload limit: 5 MiB
+const DEFAULT_MAX_UPLOAD_BYTES: usize = 5 * 1024 * 1024;
+
+/// Maximum accepted upload size, from `MEDIA_MAX_UPLOAD_BYTES`
+pub fn max_upload_bytes() -> usize {
+    env::var("MEDIA_MAX_UPLOAD_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_UPLOAD_BYTES)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum UploadError {
+    UnsupportedType(String),
+    /// Declared content type does not match the file contents
+    ContentMismatch,
+    TooLarge {
+        size: usize,
+        max: usize,
+    },
+    Empty,
+}
+
+impl std::fmt::Display for UploadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UploadError::UnsupportedType(t) => write!(f, "unsupported content type: {t}"),
+            UploadError::ContentMismatch => write!(f, "file contents do not match content type"),
+            UploadError::TooLarge { size, max } => {
+                write!(f, "file is {size} bytes, maximum is {max}")
+            }
+            UploadError::Empty => write!(f, "file is empty"),
+        }
+    }
+}
+
+impl std::error::Error for UploadError {}
+
+/// Check an upload's declared type, magic bytes and size.
+/// Returns the file extension to store it under.
+pub fn validate_upload(
+    content_type: &str,
+    data: &[u8],
+    max_bytes: usize,
+) -> Result<&'static str, UploadError> {
+    let extension = ALLOWED_CONTENT_TYPES
+        .iter()
+        .find(|(allowed, _)| *allowed == content_type)
+        .map(|(_, ext)| *ext)
+        .ok_or_else(|| UploadError::UnsupportedType(content_type.to_string()))?;
+
+    if data.is_empty() {
+        return Err(UploadError::Empty);
+    }
+    if data.len() > max_bytes {
+        return Err(UploadError::TooLarge {
+            size: data.len(),
+            max: max_bytes,
+        });
+    }
+
+    let matches = match content_type {
+        "image/jpeg" => data.starts_with(&[0xFF, 0xD8, 0xFF]),
+        "image/png" => data.starts_with(b"\x89PNG\r\n\x1a\n"),
+        "image/gif" => data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a"),
+        "image/webp" => data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP",
+        _ => false,
+    };
+    if !matches {
+        return Err(UploadError::ContentMismatch);
+    }
+
+    Ok(extension)
+}
+
+#[derive(Debug)]
+pub enum StorageError {
+    InvalidKey(String),
+    NotFound,
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::InvalidKey(key) => write!(f, "invalid storage key: {key}"),
+            StorageError::NotFound => write!(f, "object not found"),
+            StorageError::Io(e) => write!(f, "storage I/O error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<std::io::Error> for StorageError {
+    fn from(err: std::io::Error) -> Self {
+        if err.kind() == std::io::ErrorKind::NotFound {
+            StorageError::NotFound
+        } else {
+            StorageError::Io(err)
+        }
+    }
+}
+
+pub type StorageFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, StorageError>> + Send + 'a>>;
+
+/// Object storage backend for uploaded media
+pub trait Storage: Send + Sync {
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>) -> StorageFuture<'a, ()>;
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Vec<u8>>;
+    fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a, ()>;
+    /// URL clients use to fetch the object
+    fn public_url(&self, key: &str) -> String;
+}
+
+/// Stores media as files under a root directory, served at `base_url`
+pub struct LocalStorage {
+    root: PathBuf,
+    base_url: String,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>, base_url: &str) -> Self {
+        Self {
+            root: root.into(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolve a key to a path, refusing anything that could escape the root
+    fn path_for(&self, key: &str) -> Result<PathBuf, StorageError> {
+        let relative = Path::new(key);
+        let safe = !key.is_empty()
+            && relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)));
+        if !safe {
+            return Err(StorageError::InvalidKey(key.to_string()));
+        }
+        Ok(self.root.join(relative))
+    }
+}
+
+impl Storage for LocalStorage {
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            let path = self.path_for(key)?;
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(path, data).await?;
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Vec<u8>> {
+        Box::pin(async move { Ok(tokio::fs::read(self.path_for(key)?).await?) })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a, ()> {
+        Box::pin(async move { Ok(tokio::fs::remove_file(self.path_for(key)?).await?) })
+    }
+
+    fn public_url(&self, key: &str) -> String {
+        format!("{}/{}", self.base_url, key)
+    }
+}
+
+/// Local media directory, from `MEDIA_ROOT`
+pub fn media_root() -> PathBuf {
+    env::var("MEDIA_ROOT")
+        .unwrap_or_else(|_| "./uploads".to_string())
+        .into()
+}
+
+/// Build the storage backend selected by `STORAGE_BACKEND`
+pub fn storage_from_env() -> Arc<dyn Storage> {
+    let base_url = env::var("MEDIA_BASE_URL").unwrap_or_else(|_| "/media".to_string());
+
+    match env::var("STORAGE_BACKEND").as_deref() {
+        Ok("local") | Err(_) => Arc::new(LocalStorage::new(media_root(), &base_url)),
+        Ok(other) => {
+            tracing::warn!(
+                backend = %other,
+                "Unknown STORAGE_BACKEND, falling back to local filesystem storage"
+            );
+            Arc::new(LocalStorage::new(media_root(), &base_url))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    const JPEG: &[u8] = &[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+
+    #[test]
+    fn test_accepted_content_types() {
+        assert_eq!(validate_upload("image/png", PNG, 1024), Ok("png"));
+        assert_eq!(validate_upload("image/jpeg", JPEG, 1024), Ok("jpg"));
+        assert_eq!(validate_upload("image/gif", b"GIF89a....", 1024), Ok("gif"));
+        assert_eq!(
+            validate_upload("image/webp", b"RIFF\0\0\0\0WEBPVP8 ", 1024),
+            Ok("webp")
+        );
+    }
+
+    #[test]
+    fn test_rejected_content_types() {
+        assert_eq!(
+            validate_upload("image/svg+xml", b"<svg></svg>", 1024),
+            Err(UploadError::UnsupportedType("image/svg+xml".to_string()))
+        );
+        assert_eq!(
+            validate_upload("application/pdf", b"%PDF-1.4", 1024),
+            Err(UploadError::UnsupportedType("application/pdf".to_string()))
+        );
+        // Declared as PNG but actually HTML
+        assert_eq!(
+            validate_upload("image/png", b"<html></html>", 1024),
+            Err(UploadError::ContentMismatch)
+        );
+    }
+
+    #[test]
+    fn test_size_limits() {
+        assert_eq!(
+            validate_upload("image/png", PNG, 4),
+            Err(UploadError::TooLarge {
+                size: PNG.len(),
+                max: 4
+            })
+        );
+        assert_eq!(
+            validate_upload("image/png", b"", 1024),
+            Err(UploadError::Empty)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path(), "/media/");
+
+        storage.put("1/image.png", PNG.to_vec()).await.unwrap();
+        assert!(dir.path().join("1/image.png").exists());
+        assert_eq!(storage.get("1/image.png").await.unwrap(), PNG);
+        assert_eq!(storage.public_url("1/image.png"), "/media/1/image.png");
+
+        storage.delete("1/image.png").await.unwrap();
+        assert!(matches!(
+            storage.get("1/image.png").await,
+            Err(StorageError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_rejects_path_traversal() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path(), "/media");
+
+        for key in ["../escape.png", "/etc/passwd", "1/../../x", ""] {
+            assert!(matches!(
+                storage.put(key, PNG.to_vec()).await,
+                Err(StorageError::InvalidKey(_))
+            ));
+        }
+    }
+}
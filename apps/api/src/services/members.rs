@@ -0,0 +1,112 @@
+// src/services/members.rs
+//! Readers who may open a domain's members-only posts
+//!
+//! Members belong to one domain and sign in through `POST /members/login`,
+//! which hands out a member token (see `handlers::auth::create_member_token`).
+//! They have nothing to do with admin users: a member token opens no admin
+//! route, and an admin token opens no members-only post.
+
+use bcrypt::verify;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+
+/// How long a member token stays valid.
+/// Configurable via `MEMBER_TOKEN_TTL_DAYS` (default 30).
+pub fn member_token_ttl() -> Duration {
+    let days = std::env::var("MEMBER_TOKEN_TTL_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|d| *d > 0)
+        .unwrap_or(30);
+    Duration::days(days)
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Member {
+    pub id: i32,
+    pub email: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The domain's member with this email and password
+pub async fn authenticate(
+    db: &PgPool,
+    domain_id: i32,
+    email: &str,
+    password: &str,
+) -> Result<Option<Member>, sqlx::Error> {
+    let row: Option<(i32, String, String, DateTime<Utc>, String)> = sqlx::query_as(
+        r#"
+        SELECT id, email, name, created_at, password_hash
+        FROM members
+        WHERE domain_id = $1 AND lower(email) = lower($2)
+        "#,
+    )
+    .bind(domain_id)
+    .bind(email)
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row
+        .filter(|(.., password_hash)| verify(password, password_hash).unwrap_or(false))
+        .map(|(id, email, name, created_at, _)| Member {
+            id,
+            email,
+            name,
+            created_at,
+        }))
+}
+
+/// Whether `member_id` is still a member of `domain_id`
+pub async fn is_member(db: &PgPool, member_id: i32, domain_id: i32) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM members WHERE id = $1 AND domain_id = $2)")
+        .bind(member_id)
+        .bind(domain_id)
+        .fetch_one(db)
+        .await
+}
+
+pub async fn list(db: &PgPool, domain_id: i32) -> Result<Vec<Member>, sqlx::Error> {
+    sqlx::query_as::<_, Member>(
+        "SELECT id, email, name, created_at FROM members WHERE domain_id = $1 ORDER BY created_at DESC, id DESC",
+    )
+    .bind(domain_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Add a member; `None` when the email is already a member of the domain
+pub async fn create(
+    db: &PgPool,
+    domain_id: i32,
+    email: &str,
+    name: &str,
+    password_hash: &str,
+) -> Result<Option<Member>, sqlx::Error> {
+    sqlx::query_as::<_, Member>(
+        r#"
+        INSERT INTO members (domain_id, email, name, password_hash)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT DO NOTHING
+        RETURNING id, email, name, created_at
+        "#,
+    )
+    .bind(domain_id)
+    .bind(email)
+    .bind(name)
+    .bind(password_hash)
+    .fetch_optional(db)
+    .await
+}
+
+/// Remove a member, which also voids their tokens; `false` if there was none
+pub async fn delete(db: &PgPool, domain_id: i32, member_id: i32) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM members WHERE id = $1 AND domain_id = $2")
+        .bind(member_id)
+        .bind(domain_id)
+        .execute(db)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,268 @@
+// src/services/menu.rs
+//! Per-domain navigation menus
+//!
+//! A menu is an ordered tree of items stored as JSON in `domain_menus`. Each
+//! item links either to a `url` (absolute http(s), `mailto:` or a path starting
+//! with `/`) or to a post `slug`; items with children may leave both out and
+//! act as plain headings. Items may carry an `id` so clients can refer to them;
+//! an id repeated inside its own subtree is a cycle, and anywhere else a
+//! duplicate, and both are rejected.
+
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashSet;
+
+/// Deepest nesting allowed; top-level items are at depth 1
+pub const MAX_MENU_DEPTH: usize = 3;
+
+/// Most items a menu may hold, counting children
+pub const MAX_MENU_ITEMS: usize = 100;
+
+const MAX_LABEL_CHARS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MenuItem {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<MenuItem>,
+}
+
+/// Why a menu was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuError {
+    EmptyLabel,
+    LabelTooLong(String),
+    /// A leaf item without a `url` or `slug`
+    MissingTarget(String),
+    BothTargets(String),
+    InvalidUrl(String),
+    InvalidSlug(String),
+    TooDeep,
+    TooManyItems,
+    Cycle(String),
+    DuplicateId(String),
+}
+
+impl std::fmt::Display for MenuError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MenuError::EmptyLabel => write!(f, "Menu items need a label"),
+            MenuError::LabelTooLong(label) => write!(
+                f,
+                "Label '{label}' is longer than {MAX_LABEL_CHARS} characters"
+            ),
+            MenuError::MissingTarget(label) => {
+                write!(f, "Item '{label}' needs a url or slug")
+            }
+            MenuError::BothTargets(label) => {
+                write!(f, "Item '{label}' has both a url and a slug")
+            }
+            MenuError::InvalidUrl(url) => write!(
+                f,
+                "Url '{url}' must be http(s), mailto: or a path starting with /"
+            ),
+            MenuError::InvalidSlug(slug) => write!(f, "Invalid slug '{slug}'"),
+            MenuError::TooDeep => write!(f, "Menus nest at most {MAX_MENU_DEPTH} levels deep"),
+            MenuError::TooManyItems => write!(f, "Menus hold at most {MAX_MENU_ITEMS} items"),
+            MenuError::Cycle(id) => write!(f, "Item '{id}' is nested inside itself"),
+            MenuError::DuplicateId(id) => write!(f, "Item id '{id}' is used more than once"),
+        }
+    }
+}
+
+fn is_allowed_url(url: &str) -> bool {
+    let url = url.trim();
+    if url.is_empty() || url.chars().any(char::is_whitespace) {
+        return false;
+    }
+    (url.starts_with('/') && !url.starts_with("//"))
+        || url.starts_with("https://")
+        || url.starts_with("http://")
+        || url.starts_with("mailto:")
+}
+
+fn validate_item<'a>(
+    item: &'a MenuItem,
+    depth: usize,
+    ancestors: &mut Vec<&'a str>,
+    seen: &mut HashSet<&'a str>,
+    count: &mut usize,
+) -> Result<(), MenuError> {
+    if depth > MAX_MENU_DEPTH {
+        return Err(MenuError::TooDeep);
+    }
+    *count += 1;
+    if *count > MAX_MENU_ITEMS {
+        return Err(MenuError::TooManyItems);
+    }
+
+    let label = item.label.trim();
+    if label.is_empty() {
+        return Err(MenuError::EmptyLabel);
+    }
+    if label.chars().count() > MAX_LABEL_CHARS {
+        return Err(MenuError::LabelTooLong(label.to_string()));
+    }
+
+    match (&item.url, &item.slug) {
+        (Some(_), Some(_)) => return Err(MenuError::BothTargets(label.to_string())),
+        (Some(url), None) if !is_allowed_url(url) => {
+            return Err(MenuError::InvalidUrl(url.clone()));
+        }
+        (None, Some(slug)) if crate::validation::rules::validate_slug(slug).is_err() => {
+            return Err(MenuError::InvalidSlug(slug.clone()));
+        }
+        (None, None) if item.children.is_empty() => {
+            return Err(MenuError::MissingTarget(label.to_string()));
+        }
+        _ => {}
+    }
+
+    if let Some(id) = item.id.as_deref() {
+        if ancestors.contains(&id) {
+            return Err(MenuError::Cycle(id.to_string()));
+        }
+        if !seen.insert(id) {
+            return Err(MenuError::DuplicateId(id.to_string()));
+        }
+        ancestors.push(id);
+    }
+
+    for child in &item.children {
+        validate_item(child, depth + 1, ancestors, seen, count)?;
+    }
+
+    if item.id.is_some() {
+        ancestors.pop();
+    }
+    Ok(())
+}
+
+/// Check a whole menu before it is stored
+pub fn validate(items: &[MenuItem]) -> Result<(), MenuError> {
+    let mut ancestors = Vec::new();
+    let mut seen = HashSet::new();
+    let mut count = 0;
+    for item in items {
+        validate_item(item, 1, &mut ancestors, &mut seen, &mut count)?;
+    }
+    Ok(())
+}
+
+/// The domain's menu; empty when none has been saved
+pub async fn load(db: &PgPool, domain_id: i32) -> Result<Vec<MenuItem>, sqlx::Error> {
+    let items: Option<serde_json::Value> =
+        sqlx::query_scalar("SELECT items FROM domain_menus WHERE domain_id = $1")
+            .bind(domain_id)
+            .fetch_optional(db)
+            .await?;
+
+    Ok(items
+        .and_then(|items| serde_json::from_value(items).ok())
+        .unwrap_or_default())
+}
+
+/// Replace the domain's menu
+pub async fn save(db: &PgPool, domain_id: i32, items: &[MenuItem]) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO domain_menus (domain_id, items) VALUES ($1, $2)
+        ON CONFLICT (domain_id) DO UPDATE SET items = EXCLUDED.items, updated_at = NOW()
+        "#,
+    )
+    .bind(domain_id)
+    .bind(serde_json::json!(items))
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(id: &str, children: Vec<MenuItem>) -> MenuItem {
+        MenuItem {
+            id: Some(id.to_string()),
+            label: id.to_string(),
+            url: Some(format!("/{id}")),
+            slug: None,
+            children,
+        }
+    }
+
+    #[test]
+    fn test_accepts_nested_menu_within_depth() {
+        let menu = vec![
+            link("home", vec![]),
+            link("topics", vec![link("rust", vec![link("async", vec![])])]),
+        ];
+        assert_eq!(validate(&menu), Ok(()));
+    }
+
+    #[test]
+    fn test_rejects_menu_nested_too_deep() {
+        let menu = vec![link(
+            "a",
+            vec![link("b", vec![link("c", vec![link("d", vec![])])])],
+        )];
+        assert_eq!(validate(&menu), Err(MenuError::TooDeep));
+    }
+
+    #[test]
+    fn test_rejects_item_nested_inside_itself() {
+        let menu = vec![link(
+            "topics",
+            vec![link("rust", vec![link("topics", vec![])])],
+        )];
+        assert_eq!(validate(&menu), Err(MenuError::Cycle("topics".to_string())));
+    }
+
+    #[test]
+    fn test_rejects_duplicate_ids_across_branches() {
+        let menu = vec![
+            link("a", vec![link("x", vec![])]),
+            link("b", vec![link("x", vec![])]),
+        ];
+        assert_eq!(
+            validate(&menu),
+            Err(MenuError::DuplicateId("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_item_targets() {
+        let mut heading = link("heading", vec![]);
+        heading.url = None;
+        assert!(matches!(
+            validate(&[heading.clone()]),
+            Err(MenuError::MissingTarget(_))
+        ));
+
+        heading.children = vec![link("child", vec![])];
+        assert_eq!(validate(&[heading]), Ok(()));
+
+        let mut script = link("bad", vec![]);
+        script.url = Some("javascript:alert(1)".to_string());
+        assert!(matches!(validate(&[script]), Err(MenuError::InvalidUrl(_))));
+
+        let mut both = link("both", vec![]);
+        both.slug = Some("hello-world".to_string());
+        assert!(matches!(validate(&[both]), Err(MenuError::BothTargets(_))));
+    }
+
+    #[test]
+    fn test_rejects_too_many_items() {
+        let menu: Vec<_> = (0..=MAX_MENU_ITEMS)
+            .map(|i| link(&format!("item-{i}"), vec![]))
+            .collect();
+        assert_eq!(validate(&menu), Err(MenuError::TooManyItems));
+    }
+}
@@ -1,4 +1,50 @@
 // src/services/mod.rs
+pub mod abuse;
+pub mod alerts;
+pub mod audit;
+pub mod calendar;
+pub mod clock;
+pub mod credentials;
+pub mod daily_stats;
+pub mod digest;
+pub mod domain_clone;
+pub mod domain_export;
+pub mod domain_verification;
+pub mod email_templates;
+pub mod event_buffer;
+pub mod event_types;
+pub mod excerpt;
+pub mod health;
+pub mod live;
+pub mod maintenance;
+pub mod media;
+pub mod members;
+pub mod menu;
+pub mod parquet_export;
+pub mod permalinks;
+pub mod post_authors;
+pub mod password_policy;
+pub mod period_comparison;
+pub mod post_status;
+pub mod post_visibility;
+pub mod query_timeout;
+pub mod quota;
+pub mod rate_limits;
+pub mod reading_time;
+pub mod referrers;
+pub mod response_cache;
+pub mod retention;
+pub mod sanitize;
+pub mod sampling;
+pub mod search;
 pub mod session_tracking;
+pub mod slug;
+pub mod slug_redirects;
+pub mod subscribers;
+pub mod social_meta;
+pub mod theme;
+pub mod timezones;
+pub mod translations;
+pub mod view_counts;
 
 pub use session_tracking::*;
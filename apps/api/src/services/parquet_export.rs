@@ -0,0 +1,290 @@
+// src/services/parquet_export.rs
+//! Parquet encoding of analytics exports, and the columns exports can select
+//!
+//! Columns keep their types so DuckDB/pandas don't have to re-parse them:
+//! `created_at` is a UTC microsecond timestamp, everything else is a string.
+//! IP addresses are already anonymized by the export query.
+//!
+//! Callers pick columns with `fields=`; a domain can also list columns in
+//! `analytics_config.export_exclude` that no export of its events includes.
+
+use arrow::array::{ArrayRef, StringArray, TimestampMicrosecondArray};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::errors::Result;
+use std::sync::Arc;
+
+pub const CONTENT_TYPE: &str = "application/vnd.apache.parquet";
+
+/// Rows per record batch, so large exports aren't converted in one go
+const BATCH_SIZE: usize = 8192;
+
+/// One exported analytics event
+#[derive(Debug, Clone)]
+pub struct ExportRow {
+    pub domain: String,
+    pub event_type: String,
+    pub path: Option<String>,
+    /// Anonymized, e.g. `192.168.1.XXX`
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub referrer: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A column of the export, named as in `fields=` and the Parquet schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportField {
+    Domain,
+    EventType,
+    Path,
+    IpAddress,
+    UserAgent,
+    Referrer,
+    CreatedAt,
+}
+
+impl ExportField {
+    /// Every column, in the default order
+    pub const ALL: [ExportField; 7] = [
+        ExportField::Domain,
+        ExportField::EventType,
+        ExportField::Path,
+        ExportField::IpAddress,
+        ExportField::UserAgent,
+        ExportField::Referrer,
+        ExportField::CreatedAt,
+    ];
+
+    /// Columns a domain may keep out of exports of its events
+    pub const PRIVATE: [ExportField; 2] = [ExportField::UserAgent, ExportField::Referrer];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportField::Domain => "domain",
+            ExportField::EventType => "event_type",
+            ExportField::Path => "path",
+            ExportField::IpAddress => "ip_address",
+            ExportField::UserAgent => "user_agent",
+            ExportField::Referrer => "referrer",
+            ExportField::CreatedAt => "created_at",
+        }
+    }
+
+    /// Column title in CSV exports
+    pub fn header(self) -> &'static str {
+        match self {
+            ExportField::Domain => "Domain",
+            ExportField::EventType => "Event Type",
+            ExportField::Path => "Path",
+            ExportField::IpAddress => "IP Address",
+            ExportField::UserAgent => "User Agent",
+            ExportField::Referrer => "Referrer",
+            ExportField::CreatedAt => "Timestamp",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|field| field.name() == name)
+    }
+}
+
+/// Columns listed in a comma-separated `fields=` value, in the order given
+/// and without repeats; every column when it is absent. Fails with the first
+/// unknown name.
+pub fn parse_fields(fields: Option<&str>) -> std::result::Result<Vec<ExportField>, String> {
+    let Some(fields) = fields.filter(|fields| !fields.trim().is_empty()) else {
+        return Ok(ExportField::ALL.to_vec());
+    };
+    let mut selected = Vec::new();
+    for name in fields.split(',').map(str::trim) {
+        let field = ExportField::parse(name).ok_or_else(|| name.to_string())?;
+        if !selected.contains(&field) {
+            selected.push(field);
+        }
+    }
+    Ok(selected)
+}
+
+/// Columns a domain's `analytics_config.export_exclude` keeps out of exports
+pub fn excluded_fields(theme_config: &serde_json::Value) -> Vec<ExportField> {
+    theme_config
+        .pointer("/analytics_config/export_exclude")
+        .and_then(serde_json::Value::as_array)
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(serde_json::Value::as_str)
+                .filter_map(ExportField::parse)
+                .filter(|field| ExportField::PRIVATE.contains(field))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn schema(fields: &[ExportField]) -> Arc<Schema> {
+    Arc::new(Schema::new(
+        fields
+            .iter()
+            .map(|field| match field {
+                ExportField::Domain | ExportField::EventType => {
+                    Field::new(field.name(), DataType::Utf8, false)
+                }
+                ExportField::CreatedAt => Field::new(
+                    field.name(),
+                    DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+                    false,
+                ),
+                _ => Field::new(field.name(), DataType::Utf8, true),
+            })
+            .collect::<Vec<_>>(),
+    ))
+}
+
+fn column(field: ExportField, rows: &[ExportRow]) -> ArrayRef {
+    let optional = |value: fn(&ExportRow) -> Option<&str>| -> ArrayRef {
+        Arc::new(StringArray::from_iter(rows.iter().map(value)))
+    };
+    match field {
+        ExportField::Domain => Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|r| r.domain.as_str()),
+        )),
+        ExportField::EventType => Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|r| r.event_type.as_str()),
+        )),
+        ExportField::Path => optional(|r| r.path.as_deref()),
+        ExportField::IpAddress => optional(|r| r.ip_address.as_deref()),
+        ExportField::UserAgent => optional(|r| r.user_agent.as_deref()),
+        ExportField::Referrer => optional(|r| r.referrer.as_deref()),
+        ExportField::CreatedAt => Arc::new(
+            TimestampMicrosecondArray::from_iter_values(
+                rows.iter().map(|r| r.created_at.timestamp_micros()),
+            )
+            .with_timezone("UTC"),
+        ),
+    }
+}
+
+fn record_batch(
+    schema: &Arc<Schema>,
+    fields: &[ExportField],
+    rows: &[ExportRow],
+) -> Result<RecordBatch> {
+    let columns: Vec<ArrayRef> = fields.iter().map(|field| column(*field, rows)).collect();
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+/// Encode the `fields` columns of events as a Parquet file
+pub fn write_events(rows: &[ExportRow], fields: &[ExportField]) -> Result<Vec<u8>> {
+    let schema = schema(fields);
+    let mut buffer = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut buffer, schema.clone(), None)?;
+    for chunk in rows.chunks(BATCH_SIZE) {
+        writer.write(&record_batch(&schema, fields, chunk)?)?;
+    }
+    writer.close()?;
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use std::io::Write;
+
+    #[test]
+    fn test_parquet_round_trip() {
+        let created_at = Utc::now();
+        let rows = vec![
+            ExportRow {
+                domain: "Tech Blog".to_string(),
+                event_type: "page_view".to_string(),
+                path: Some("/".to_string()),
+                ip_address: Some("127.0.0.XXX".to_string()),
+                user_agent: Some("Mozilla/5.0".to_string()),
+                referrer: None,
+                created_at,
+            },
+            ExportRow {
+                domain: "Tech Blog".to_string(),
+                event_type: "search".to_string(),
+                path: None,
+                ip_address: None,
+                user_agent: None,
+                referrer: Some("https://google.com".to_string()),
+                created_at,
+            },
+        ];
+
+        let bytes = write_events(&rows, &ExportField::ALL).unwrap();
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&bytes).unwrap();
+
+        let reader = SerializedFileReader::new(file).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 2);
+
+        let columns = metadata
+            .schema_descr()
+            .columns()
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            columns,
+            [
+                "domain",
+                "event_type",
+                "path",
+                "ip_address",
+                "user_agent",
+                "referrer",
+                "created_at"
+            ]
+        );
+
+        let created_at_column = metadata.schema_descr().column(6);
+        assert_eq!(
+            created_at_column.physical_type(),
+            parquet::basic::Type::INT64
+        );
+        assert!(matches!(
+            created_at_column.logical_type(),
+            Some(parquet::basic::LogicalType::Timestamp {
+                is_adjusted_to_u_t_c: true,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_empty_export_is_valid_parquet() {
+        let bytes = write_events(&[], &ExportField::ALL).unwrap();
+        assert!(bytes.starts_with(b"PAR1"));
+        assert!(bytes.ends_with(b"PAR1"));
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        assert_eq!(parse_fields(None).unwrap(), ExportField::ALL);
+        assert_eq!(
+            parse_fields(Some("path, domain,path")).unwrap(),
+            [ExportField::Path, ExportField::Domain]
+        );
+        assert_eq!(
+            parse_fields(Some("domain,email")).unwrap_err(),
+            "email".to_string()
+        );
+    }
+
+    #[test]
+    fn test_only_private_fields_can_be_excluded() {
+        let theme_config = serde_json::json!({
+            "analytics_config": { "export_exclude": ["referrer", "domain", "bogus"] }
+        });
+        assert_eq!(excluded_fields(&theme_config), [ExportField::Referrer]);
+        assert!(excluded_fields(&serde_json::json!({})).is_empty());
+    }
+}
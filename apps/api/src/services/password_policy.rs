@@ -0,0 +1,79 @@
+// src/services/password_policy.rs
+//! Password reuse and forced changes
+//!
+//! Every password a user is given is kept (hashed) in `password_history`, and
+//! a new one may not match any of the last `PASSWORD_HISTORY_DEPTH`. Accounts
+//! with `users.must_change_password` set are held at the auth layer until the
+//! user picks a new password through `POST /auth/change-password`.
+
+use bcrypt::verify;
+use sqlx::PgPool;
+
+/// How many previous passwords a new one is checked against.
+/// Configurable via `PASSWORD_HISTORY_DEPTH` (default 5, 0 turns the check off).
+pub fn history_depth() -> i64 {
+    std::env::var("PASSWORD_HISTORY_DEPTH")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|n| *n >= 0)
+        .unwrap_or(5)
+}
+
+/// Whether `password` is the user's current password or one of their last
+/// `history_depth()` passwords
+pub async fn is_recently_used(
+    db: &PgPool,
+    user_id: i32,
+    password: &str,
+) -> Result<bool, sqlx::Error> {
+    let depth = history_depth();
+    if depth == 0 {
+        return Ok(false);
+    }
+
+    let hashes: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT password_hash FROM users WHERE id = $1
+        UNION ALL
+        (SELECT password_hash FROM password_history
+         WHERE user_id = $1
+         ORDER BY created_at DESC, id DESC
+         LIMIT $2)
+        "#,
+    )
+    .bind(user_id)
+    .bind(depth)
+    .fetch_all(db)
+    .await?;
+
+    Ok(hashes
+        .iter()
+        .any(|hash| verify(password, hash).unwrap_or(false)))
+}
+
+/// Remember a newly set password hash, dropping entries too old to matter
+pub async fn record(db: &PgPool, user_id: i32, password_hash: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO password_history (user_id, password_hash) VALUES ($1, $2)")
+        .bind(user_id)
+        .bind(password_hash)
+        .execute(db)
+        .await?;
+
+    sqlx::query(
+        r#"
+        DELETE FROM password_history
+        WHERE user_id = $1 AND id NOT IN (
+            SELECT id FROM password_history
+            WHERE user_id = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2
+        )
+        "#,
+    )
+    .bind(user_id)
+    .bind(history_depth().max(1))
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
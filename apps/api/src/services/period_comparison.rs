@@ -0,0 +1,157 @@
+// src/services/period_comparison.rs
+//! The window analytics are compared against, and the change between them
+//!
+//! By default a report is compared with the equal-length window just before
+//! it. `compare_start`/`compare_end` (RFC 3339, both or neither) pick any other
+//! window instead, such as the same week last year. When the two windows
+//! differ in length the comparison totals are scaled to the report's length,
+//! so a week set against a month compares daily rates rather than raw sums.
+
+use chrono::{DateTime, Utc};
+
+/// Why a comparison window was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComparisonError {
+    /// Only one of `compare_start` and `compare_end` was given
+    Incomplete,
+    Unparseable(String),
+    /// `compare_start` is not before `compare_end`
+    Empty,
+    InFuture,
+}
+
+impl std::fmt::Display for ComparisonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComparisonError::Incomplete => {
+                write!(f, "compare_start and compare_end must be given together")
+            }
+            ComparisonError::Unparseable(value) => {
+                write!(f, "'{value}' is not an RFC 3339 timestamp")
+            }
+            ComparisonError::Empty => write!(f, "compare_start must be before compare_end"),
+            ComparisonError::InFuture => {
+                write!(f, "The comparison window must not start in the future")
+            }
+        }
+    }
+}
+
+fn parse(value: &str) -> Result<DateTime<Utc>, ComparisonError> {
+    value
+        .parse::<DateTime<Utc>>()
+        .map_err(|_| ComparisonError::Unparseable(value.to_string()))
+}
+
+/// The window `[start, end)` is compared against
+pub fn comparison_window(
+    compare_start: Option<&str>,
+    compare_end: Option<&str>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Result<(DateTime<Utc>, DateTime<Utc>), ComparisonError> {
+    match (compare_start, compare_end) {
+        (None, None) => Ok((start - (end - start), start)),
+        (Some(compare_start), Some(compare_end)) => {
+            let compare_start = parse(compare_start)?;
+            let compare_end = parse(compare_end)?;
+            if compare_start >= compare_end {
+                return Err(ComparisonError::Empty);
+            }
+            if compare_start > now {
+                return Err(ComparisonError::InFuture);
+            }
+            Ok((compare_start, compare_end))
+        }
+        _ => Err(ComparisonError::Incomplete),
+    }
+}
+
+/// Percent change from `previous` (counted over `previous_window`) to
+/// `current` (over `current_window`); 0 when there is nothing to compare with
+pub fn change_percent(
+    current: i64,
+    previous: i64,
+    current_window: (DateTime<Utc>, DateTime<Utc>),
+    previous_window: (DateTime<Utc>, DateTime<Utc>),
+) -> f64 {
+    let current_len = (current_window.1 - current_window.0).num_seconds() as f64;
+    let previous_len = (previous_window.1 - previous_window.0).num_seconds() as f64;
+    if previous <= 0 || current_len <= 0.0 || previous_len <= 0.0 {
+        return 0.0;
+    }
+
+    let expected = previous as f64 * current_len / previous_len;
+    (current as f64 - expected) / expected * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, m: u32, d: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_defaults_to_preceding_window() {
+        let (start, end) = (at(2025, 3, 8), at(2025, 3, 15));
+        assert_eq!(
+            comparison_window(None, None, start, end, end),
+            Ok((at(2025, 3, 1), start))
+        );
+    }
+
+    #[test]
+    fn test_year_over_year() {
+        let current = (at(2025, 3, 8), at(2025, 3, 15));
+        let last_year = comparison_window(
+            Some("2024-03-08T00:00:00Z"),
+            Some("2024-03-15T00:00:00Z"),
+            current.0,
+            current.1,
+            current.1,
+        )
+        .unwrap();
+        assert_eq!(last_year, (at(2024, 3, 8), at(2024, 3, 15)));
+
+        assert_eq!(change_percent(150, 100, current, last_year), 50.0);
+        assert_eq!(change_percent(75, 100, current, last_year), -25.0);
+        assert_eq!(change_percent(10, 0, current, last_year), 0.0);
+    }
+
+    #[test]
+    fn test_scales_comparison_of_different_length() {
+        // A week against four weeks: 100 views a week is the same rate as 400 a month
+        let week = (at(2025, 3, 1), at(2025, 3, 8));
+        let four_weeks = (at(2024, 3, 1), at(2024, 3, 29));
+        assert_eq!(change_percent(100, 400, week, four_weeks), 0.0);
+        assert_eq!(change_percent(200, 400, week, four_weeks), 100.0);
+    }
+
+    #[test]
+    fn test_rejects_malformed_windows() {
+        let (start, end) = (at(2025, 3, 8), at(2025, 3, 15));
+        let window =
+            |from: Option<&str>, to: Option<&str>| comparison_window(from, to, start, end, end);
+
+        assert_eq!(
+            window(Some("2024-03-08T00:00:00Z"), None),
+            Err(ComparisonError::Incomplete)
+        );
+        assert!(matches!(
+            window(Some("last year"), Some("2024-03-15T00:00:00Z")),
+            Err(ComparisonError::Unparseable(_))
+        ));
+        assert_eq!(
+            window(Some("2024-03-15T00:00:00Z"), Some("2024-03-08T00:00:00Z")),
+            Err(ComparisonError::Empty)
+        );
+        assert_eq!(
+            window(Some("2026-01-01T00:00:00Z"), Some("2026-01-08T00:00:00Z")),
+            Err(ComparisonError::InFuture)
+        );
+    }
+}
@@ -0,0 +1,229 @@
+// src/services/permalinks.rs
+//! Per-domain permalink structure
+//!
+//! `theme_config.seo_config.permalink_format` sets the path that canonical
+//! URLs in the feed and sharing metadata point at, e.g.
+//! `/%year%/%month%/%slug%`. Each path segment is either literal text or
+//! exactly one token: `%year%`, `%month%` and `%day%` from the post's creation
+//! date (UTC), `%slug%`, `%id%`, or `%category%` as a slug. A format has to
+//! contain `%slug%` or `%id%` to pick out a post; domains without a valid one
+//! keep `/posts/%slug%`. [`parse`] maps a path back to the post it names.
+
+use crate::DomainContext;
+use crate::services::slug;
+use chrono::{DateTime, Datelike, Utc};
+
+/// Where posts live unless the domain says otherwise
+pub const DEFAULT_FORMAT: &str = "/posts/%slug%";
+
+const TOKENS: &[&str] = &["%year%", "%month%", "%day%", "%slug%", "%id%", "%category%"];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PermalinkFormatError {
+    /// The format must be an absolute path
+    NotAbsolute,
+    /// Neither `%slug%` nor `%id%` appears
+    NoIdentifier,
+    /// A segment mixes a token with other text, or uses an unknown token
+    InvalidSegment(String),
+}
+
+impl std::fmt::Display for PermalinkFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotAbsolute => write!(f, "permalink format must start with '/'"),
+            Self::NoIdentifier => write!(f, "permalink format must contain %slug% or %id%"),
+            Self::InvalidSegment(segment) => write!(
+                f,
+                "'{segment}' must be literal text or exactly one of {}",
+                TOKENS.join(", ")
+            ),
+        }
+    }
+}
+
+/// Check a format before it is saved
+pub fn validate(format: &str) -> Result<(), PermalinkFormatError> {
+    if !format.starts_with('/') {
+        return Err(PermalinkFormatError::NotAbsolute);
+    }
+    for segment in format.split('/') {
+        if segment.contains('%') && !TOKENS.contains(&segment) {
+            return Err(PermalinkFormatError::InvalidSegment(segment.to_string()));
+        }
+    }
+    if !format.split('/').any(|s| s == "%slug%" || s == "%id%") {
+        return Err(PermalinkFormatError::NoIdentifier);
+    }
+    Ok(())
+}
+
+/// The domain's permalink format, or [`DEFAULT_FORMAT`] when unset or invalid
+pub fn format_for(domain: &DomainContext) -> &str {
+    domain
+        .theme_config
+        .pointer("/seo_config/permalink_format")
+        .and_then(serde_json::Value::as_str)
+        .filter(|format| validate(format).is_ok())
+        .unwrap_or(DEFAULT_FORMAT)
+}
+
+/// The post fields a permalink can be built from
+pub struct PermalinkPost<'a> {
+    pub id: i32,
+    pub slug: &'a str,
+    pub category: &'a str,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Path of `post` under `format`
+pub fn build_path(format: &str, post: &PermalinkPost) -> String {
+    format
+        .split('/')
+        .map(|segment| match segment {
+            "%year%" => format!("{:04}", post.created_at.year()),
+            "%month%" => format!("{:02}", post.created_at.month()),
+            "%day%" => format!("{:02}", post.created_at.day()),
+            "%slug%" => post.slug.to_string(),
+            "%id%" => post.id.to_string(),
+            "%category%" => category_segment(post),
+            literal => literal.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Canonical URL of `post` on `domain`
+pub fn url(domain: &DomainContext, post: &PermalinkPost) -> String {
+    format!(
+        "https://{}{}",
+        domain.hostname,
+        build_path(format_for(domain), post)
+    )
+}
+
+fn category_segment(post: &PermalinkPost) -> String {
+    slug::from_title(post.category, post.created_at.date_naive())
+}
+
+/// What a path says about the post it names
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PermalinkMatch {
+    pub id: Option<i32>,
+    pub slug: Option<String>,
+    year: Option<i32>,
+    month: Option<u32>,
+    day: Option<u32>,
+    category: Option<String>,
+}
+
+impl PermalinkMatch {
+    /// Whether `post` is the one named, with the date and category in the
+    /// path agreeing as well
+    pub fn matches(&self, post: &PermalinkPost) -> bool {
+        self.id.is_none_or(|id| id == post.id)
+            && self.slug.as_deref().is_none_or(|slug| slug == post.slug)
+            && self.year.is_none_or(|year| year == post.created_at.year())
+            && self
+                .month
+                .is_none_or(|month| month == post.created_at.month())
+            && self.day.is_none_or(|day| day == post.created_at.day())
+            && self
+                .category
+                .as_deref()
+                .is_none_or(|category| category == category_segment(post))
+    }
+}
+
+/// Read `path` against `format`; `None` when its shape doesn't fit
+pub fn parse(format: &str, path: &str) -> Option<PermalinkMatch> {
+    let format_segments: Vec<&str> = format.split('/').collect();
+    let path_segments: Vec<&str> = path.split('/').collect();
+    if format_segments.len() != path_segments.len() {
+        return None;
+    }
+
+    let mut found = PermalinkMatch::default();
+    for (expected, segment) in format_segments.into_iter().zip(path_segments) {
+        if expected.starts_with('%') && segment.is_empty() {
+            return None;
+        }
+        match expected {
+            "%year%" => found.year = Some(segment.parse().ok()?),
+            "%month%" => found.month = Some(segment.parse().ok().filter(|m| (1..=12).contains(m))?),
+            "%day%" => found.day = Some(segment.parse().ok().filter(|d| (1..=31).contains(d))?),
+            "%slug%" => found.slug = Some(segment.to_string()),
+            "%id%" => found.id = Some(segment.parse().ok()?),
+            "%category%" => found.category = Some(segment.to_string()),
+            literal if literal == segment => {}
+            _ => return None,
+        }
+    }
+    Some(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const DATED: &str = "/%year%/%month%/%slug%";
+
+    fn post() -> PermalinkPost<'static> {
+        PermalinkPost {
+            id: 42,
+            slug: "my-post",
+            category: "Web Dev",
+            created_at: Utc.with_ymd_and_hms(2024, 3, 5, 23, 30, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(DEFAULT_FORMAT).is_ok());
+        assert!(validate("/%category%/%id%").is_ok());
+        assert_eq!(validate("%slug%"), Err(PermalinkFormatError::NotAbsolute));
+        assert_eq!(
+            validate("/%year%/%month%"),
+            Err(PermalinkFormatError::NoIdentifier)
+        );
+        assert!(matches!(
+            validate("/%year%-%month%/%slug%"),
+            Err(PermalinkFormatError::InvalidSegment(_))
+        ));
+        assert!(matches!(
+            validate("/%author%/%slug%"),
+            Err(PermalinkFormatError::InvalidSegment(_))
+        ));
+    }
+
+    #[test]
+    fn test_build_path() {
+        assert_eq!(build_path(DEFAULT_FORMAT, &post()), "/posts/my-post");
+        assert_eq!(build_path(DATED, &post()), "/2024/03/my-post");
+        assert_eq!(
+            build_path("/%category%/%day%/%id%", &post()),
+            "/web-dev/05/42"
+        );
+    }
+
+    #[test]
+    fn test_parse_round_trips_a_dated_format() {
+        let path = build_path(DATED, &post());
+        let found = parse(DATED, &path).unwrap();
+        assert_eq!(found.slug.as_deref(), Some("my-post"));
+        assert!(found.matches(&post()));
+
+        // Right slug, wrong month
+        assert!(!parse(DATED, "/2024/04/my-post").unwrap().matches(&post()));
+    }
+
+    #[test]
+    fn test_parse_rejects_paths_of_another_shape() {
+        assert_eq!(parse(DATED, "/posts/my-post"), None);
+        assert_eq!(parse(DATED, "/2024/13/my-post"), None);
+        assert_eq!(parse(DATED, "/2024/03/"), None);
+        assert_eq!(parse(DATED, "/2024/03/my-post/extra"), None);
+        assert_eq!(parse("/%id%", "/abc"), None);
+    }
+}
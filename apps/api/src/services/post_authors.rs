@@ -0,0 +1,144 @@
+// src/services/post_authors.rs
+//! Users credited on a post
+//!
+//! `post_authors` links posts to users as `author` or `contributor`. Names
+//! are read from `users` whenever a post is served, so renaming a user
+//! updates every byline. Posts nobody is linked to keep the `posts.author`
+//! string they were created with.
+
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthorRole {
+    #[default]
+    Author,
+    Contributor,
+}
+
+impl AuthorRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuthorRole::Author => "author",
+            AuthorRole::Contributor => "contributor",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct PostAuthor {
+    #[serde(skip)]
+    pub post_id: i32,
+    pub user_id: i32,
+    pub name: String,
+    /// `author` or `contributor`
+    pub role: String,
+}
+
+const AUTHOR_QUERY: &str = r#"
+    SELECT pa.post_id, pa.user_id, u.name, pa.role
+    FROM post_authors pa
+    JOIN users u ON u.id = pa.user_id
+    WHERE pa.post_id = ANY($1)
+    ORDER BY pa.post_id, pa.role = 'contributor', pa.created_at, pa.user_id
+"#;
+
+/// Credited users of each of `post_ids`, authors before contributors
+pub async fn for_posts(
+    db: &PgPool,
+    post_ids: &[i32],
+) -> Result<HashMap<i32, Vec<PostAuthor>>, sqlx::Error> {
+    if post_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows = sqlx::query_as::<_, PostAuthor>(AUTHOR_QUERY)
+        .bind(post_ids)
+        .fetch_all(db)
+        .await?;
+
+    let mut authors: HashMap<i32, Vec<PostAuthor>> = HashMap::new();
+    for row in rows {
+        authors.entry(row.post_id).or_default().push(row);
+    }
+    Ok(authors)
+}
+
+/// Credited users of one post, authors before contributors
+pub async fn list(db: &PgPool, post_id: i32) -> Result<Vec<PostAuthor>, sqlx::Error> {
+    sqlx::query_as::<_, PostAuthor>(AUTHOR_QUERY)
+        .bind([post_id].as_slice())
+        .fetch_all(db)
+        .await
+}
+
+/// Credit `user_id` on `post_id`, or change the role they are credited with
+pub async fn add(
+    conn: &mut PgConnection,
+    post_id: i32,
+    user_id: i32,
+    role: AuthorRole,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO post_authors (post_id, user_id, role)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (post_id, user_id) DO UPDATE SET role = EXCLUDED.role
+        "#,
+    )
+    .bind(post_id)
+    .bind(user_id)
+    .bind(role.as_str())
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Stop crediting `user_id` on `post_id`; returns whether they were
+pub async fn remove(db: &PgPool, post_id: i32, user_id: i32) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM post_authors WHERE post_id = $1 AND user_id = $2")
+        .bind(post_id)
+        .bind(user_id)
+        .execute(db)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// The names of a post's authors (not contributors) as one byline, `None`
+/// when no author is linked
+pub fn byline(authors: &[PostAuthor]) -> Option<String> {
+    let names: Vec<&str> = authors
+        .iter()
+        .filter(|a| a.role == AuthorRole::Author.as_str())
+        .map(|a| a.name.as_str())
+        .collect();
+    (!names.is_empty()).then(|| names.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credited(name: &str, role: AuthorRole) -> PostAuthor {
+        PostAuthor {
+            post_id: 1,
+            user_id: 1,
+            name: name.to_string(),
+            role: role.as_str().to_string(),
+        }
+    }
+
+    #[test]
+    fn byline_names_authors_only() {
+        let authors = vec![
+            credited("Ada", AuthorRole::Author),
+            credited("Grace", AuthorRole::Author),
+            credited("Linus", AuthorRole::Contributor),
+        ];
+        assert_eq!(byline(&authors).as_deref(), Some("Ada, Grace"));
+        assert_eq!(byline(&authors[2..]), None);
+        assert_eq!(byline(&[]), None);
+    }
+}
@@ -0,0 +1,96 @@
+// src/services/post_status.rs
+//! Post statuses and the editorial workflow between them
+//!
+//! Drafts go to review, get scheduled or are published directly; published
+//! posts can be archived and archived ones republished. Every status can go
+//! back to draft, and saving a post without changing its status is always
+//! allowed. Rows written before statuses were checked may hold anything, and
+//! are treated as drafts.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PostStatus {
+    #[default]
+    Draft,
+    Review,
+    Scheduled,
+    Published,
+    Archived,
+}
+
+impl PostStatus {
+    pub const ALL: [PostStatus; 5] = [
+        PostStatus::Draft,
+        PostStatus::Review,
+        PostStatus::Scheduled,
+        PostStatus::Published,
+        PostStatus::Archived,
+    ];
+
+    pub fn parse(status: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.as_str() == status)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PostStatus::Draft => "draft",
+            PostStatus::Review => "review",
+            PostStatus::Scheduled => "scheduled",
+            PostStatus::Published => "published",
+            PostStatus::Archived => "archived",
+        }
+    }
+
+    /// Statuses a post may move to from this one
+    pub fn next(self) -> &'static [PostStatus] {
+        use PostStatus::*;
+        match self {
+            Draft => &[Review, Scheduled, Published],
+            Review => &[Draft, Scheduled, Published],
+            Scheduled => &[Draft, Review, Published],
+            Published => &[Draft, Archived],
+            Archived => &[Draft, Published],
+        }
+    }
+
+    pub fn can_become(self, to: PostStatus) -> bool {
+        self == to || self.next().contains(&to)
+    }
+}
+
+impl std::fmt::Display for PostStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_round_trips() {
+        for status in PostStatus::ALL {
+            assert_eq!(PostStatus::parse(status.as_str()), Some(status));
+        }
+        assert_eq!(PostStatus::parse("banana"), None);
+        assert_eq!(PostStatus::parse("Published"), None);
+    }
+
+    #[test]
+    fn test_transitions() {
+        use PostStatus::*;
+        assert!(Draft.can_become(Review));
+        assert!(Draft.can_become(Published));
+        assert!(Published.can_become(Archived));
+        assert!(Archived.can_become(Draft));
+        assert!(Published.can_become(Published));
+
+        assert!(!Draft.can_become(Archived));
+        assert!(!Review.can_become(Archived));
+        assert!(!Archived.can_become(Scheduled));
+        assert!(!Published.can_become(Scheduled));
+    }
+}
@@ -0,0 +1,111 @@
+// src/services/post_visibility.rs
+//! Who may read a published post on the public routes
+//!
+//! `public` posts are open to everyone. `members` posts are listed like any
+//! other, but reading one takes a member token for the post's domain; other
+//! readers get a teaser and `402` (no token) or `403` (a token that doesn't
+//! let them in). `private` posts never appear publicly. Rows written before
+//! visibility existed default to `public`.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PostVisibility {
+    #[default]
+    Public,
+    Members,
+    Private,
+}
+
+impl PostVisibility {
+    pub const ALL: [PostVisibility; 3] = [
+        PostVisibility::Public,
+        PostVisibility::Members,
+        PostVisibility::Private,
+    ];
+
+    pub fn parse(visibility: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|v| v.as_str() == visibility)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PostVisibility::Public => "public",
+            PostVisibility::Members => "members",
+            PostVisibility::Private => "private",
+        }
+    }
+}
+
+impl std::fmt::Display for PostVisibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The reader behind a public request, as told by its member token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reader {
+    /// No member token
+    Anonymous,
+    /// A valid token of a current member of the domain
+    Member { member_id: i32 },
+    /// A token that is expired, malformed, for another domain or of a
+    /// removed member
+    Rejected,
+}
+
+/// What a reader gets when asking for a post
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Full,
+    /// Teaser only; the reader has to sign in as a member
+    MembersOnly,
+    /// Teaser only; the reader's token doesn't let them in
+    Forbidden,
+    /// Treated as if the post didn't exist
+    Hidden,
+}
+
+pub fn access(visibility: PostVisibility, reader: Reader) -> Access {
+    match (visibility, reader) {
+        (PostVisibility::Public, _) => Access::Full,
+        (PostVisibility::Members, Reader::Member { .. }) => Access::Full,
+        (PostVisibility::Members, Reader::Anonymous) => Access::MembersOnly,
+        (PostVisibility::Members, Reader::Rejected) => Access::Forbidden,
+        (PostVisibility::Private, _) => Access::Hidden,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_round_trips() {
+        for visibility in PostVisibility::ALL {
+            assert_eq!(PostVisibility::parse(visibility.as_str()), Some(visibility));
+        }
+        assert_eq!(PostVisibility::parse("Members"), None);
+        assert_eq!(PostVisibility::parse("secret"), None);
+    }
+
+    #[test]
+    fn test_access_by_visibility_and_reader() {
+        let member = Reader::Member { member_id: 1 };
+        for reader in [Reader::Anonymous, member, Reader::Rejected] {
+            assert_eq!(access(PostVisibility::Public, reader), Access::Full);
+            assert_eq!(access(PostVisibility::Private, reader), Access::Hidden);
+        }
+        assert_eq!(access(PostVisibility::Members, member), Access::Full);
+        assert_eq!(
+            access(PostVisibility::Members, Reader::Anonymous),
+            Access::MembersOnly
+        );
+        assert_eq!(
+            access(PostVisibility::Members, Reader::Rejected),
+            Access::Forbidden
+        );
+    }
+}
@@ -0,0 +1,173 @@
+// src/services/query_timeout.rs
+//! Time limits for database work done on behalf of a request
+//!
+//! Every pooled connection carries a Postgres `statement_timeout`, so a
+//! runaway query is cancelled server-side instead of holding a connection,
+//! and `middleware::query_timeout` gives up on the request after the same
+//! budget with a `504`. Export routes get a longer budget; their handlers run
+//! heavy queries in a transaction that raises the timeout with
+//! [`extend_for_export`], which lapses when the transaction ends. Other routes
+//! can be given their own request budget through `REQUEST_TIMEOUT_OVERRIDES`;
+//! that bounds the request as a whole, while each statement keeps the default
+//! `statement_timeout`.
+
+use sqlx::postgres::PgConnectOptions;
+use sqlx::{Postgres, Transaction};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Postgres' SQLSTATE for a statement cancelled by `statement_timeout`
+const QUERY_CANCELED: &str = "57014";
+
+fn millis_from_env(var: &str, default: u64) -> Duration {
+    Duration::from_millis(
+        std::env::var(var)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default),
+    )
+}
+
+/// Budget for an ordinary request and each of its statements.
+/// Configurable via `DB_STATEMENT_TIMEOUT_MS` (default 30000, 0 turns it off).
+pub fn statement_timeout() -> Duration {
+    millis_from_env("DB_STATEMENT_TIMEOUT_MS", 30_000)
+}
+
+/// Budget for export routes.
+/// Configurable via `DB_EXPORT_STATEMENT_TIMEOUT_MS` (default 600000, 0 turns it off).
+pub fn export_statement_timeout() -> Duration {
+    millis_from_env("DB_EXPORT_STATEMENT_TIMEOUT_MS", 600_000)
+}
+
+/// Whether the path is one of the bulk export routes
+pub fn is_export_path(path: &str) -> bool {
+    path.trim_end_matches('/').ends_with("/export")
+}
+
+/// Per-route request budgets from `REQUEST_TIMEOUT_OVERRIDES`, a
+/// comma-separated list of `path_prefix=milliseconds` such as
+/// `/admin/posts/import=120000`; malformed entries are skipped.
+fn route_overrides() -> Vec<(String, Duration)> {
+    std::env::var("REQUEST_TIMEOUT_OVERRIDES")
+        .map(|v| parse_overrides(&v))
+        .unwrap_or_default()
+}
+
+fn parse_overrides(value: &str) -> Vec<(String, Duration)> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (prefix, millis) = entry.split_once('=')?;
+            let prefix = prefix.trim().trim_end_matches('/');
+            if !prefix.starts_with('/') {
+                return None;
+            }
+            let millis = millis.trim().parse().ok()?;
+            Some((prefix.to_string(), Duration::from_millis(millis)))
+        })
+        .collect()
+}
+
+/// Budget of the longest override whose prefix covers `path` segment-wise
+fn override_for(path: &str, overrides: &[(String, Duration)]) -> Option<Duration> {
+    overrides
+        .iter()
+        .filter(|(prefix, _)| {
+            path.strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|&(_, budget)| budget)
+}
+
+/// Request budgets, read from the environment once at startup
+#[derive(Debug, Clone)]
+pub struct RequestBudgets {
+    default: Duration,
+    export: Duration,
+    overrides: Arc<[(String, Duration)]>,
+}
+
+impl RequestBudgets {
+    pub fn from_env() -> Self {
+        Self {
+            default: statement_timeout(),
+            export: export_statement_timeout(),
+            overrides: route_overrides().into(),
+        }
+    }
+
+    /// Budget for a request to `path`; zero means unlimited.
+    ///
+    /// An override only bounds the request as a whole: each of its statements
+    /// is still cancelled after `DB_STATEMENT_TIMEOUT_MS`, so raising a route
+    /// above that helps routes running many queries, not one long query.
+    pub fn budget_for(&self, path: &str) -> Duration {
+        if let Some(budget) = override_for(path, &self.overrides) {
+            return budget;
+        }
+        if is_export_path(path) {
+            self.export
+        } else {
+            self.default
+        }
+    }
+}
+
+/// Connection options for `database_url` with the default statement timeout
+pub fn connect_options(database_url: &str) -> Result<PgConnectOptions, sqlx::Error> {
+    let millis = statement_timeout().as_millis().to_string();
+    Ok(PgConnectOptions::from_str(database_url)?.options([("statement_timeout", millis)]))
+}
+
+/// Raise the statement timeout to the export budget until `tx` ends
+pub async fn extend_for_export(tx: &mut Transaction<'_, Postgres>) -> Result<(), sqlx::Error> {
+    let millis = export_statement_timeout().as_millis().to_string();
+    sqlx::query("SELECT set_config('statement_timeout', $1, true)")
+        .bind(millis)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Whether Postgres cancelled the query for running past `statement_timeout`
+pub fn is_timeout(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .and_then(|e| e.code())
+        .is_some_and(|code| code == QUERY_CANCELED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_paths_get_their_own_budget() {
+        assert!(is_export_path("/analytics/export"));
+        assert!(is_export_path("/admin/domains/3/export"));
+        assert!(is_export_path("/admin/domains/3/export/"));
+        assert!(!is_export_path("/admin/posts/import"));
+        assert!(!is_export_path("/posts/export-tips"));
+    }
+
+    #[test]
+    fn test_route_overrides_match_longest_prefix() {
+        let overrides = parse_overrides(
+            "/admin/posts=1000, /admin/posts/import/=120000,bad=5,/analytics/funnel=x,/slow=0",
+        );
+        assert_eq!(overrides.len(), 3);
+
+        let budget = |path| override_for(path, &overrides);
+        assert_eq!(
+            budget("/admin/posts/import"),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(budget("/admin/posts/7"), Some(Duration::from_secs(1)));
+        assert_eq!(budget("/admin/posts"), Some(Duration::from_secs(1)));
+        assert_eq!(budget("/slow"), Some(Duration::ZERO));
+        assert_eq!(budget("/admin/postsx"), None);
+        assert_eq!(budget("/analytics/funnel"), None);
+    }
+}
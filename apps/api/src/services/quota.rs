@@ -0,0 +1,192 @@
+// src/services/quota.rs
+//! Per-domain storage quotas
+//!
+//! Platform admins cap how many posts and how many bytes of media a domain
+//! may store, in `domains.quota`. Creating posts and uploading media are
+//! checked against current usage first; a limit left out is unlimited.
+
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quota {
+    pub max_posts: Option<i64>,
+    pub max_media_bytes: Option<i64>,
+}
+
+impl Validate for Quota {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        for (field, limit) in [
+            ("max_posts", self.max_posts),
+            ("max_media_bytes", self.max_media_bytes),
+        ] {
+            if limit.is_some_and(|limit| limit < 0) {
+                let mut error = ValidationError::new("range");
+                error.message = Some(format!("{field} cannot be negative").into());
+                errors.add(field, error);
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// What a domain currently stores
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::FromRow)]
+pub struct Usage {
+    pub posts: i64,
+    pub media_bytes: i64,
+}
+
+/// A write that would take the domain past one of its limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaExceeded {
+    Posts { limit: i64 },
+    MediaBytes { limit: i64 },
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuotaExceeded::Posts { limit } => {
+                write!(f, "This domain has reached its limit of {limit} posts")
+            }
+            QuotaExceeded::MediaBytes { limit } => write!(
+                f,
+                "This upload would take the domain past its media limit of {limit} bytes"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+impl IntoResponse for QuotaExceeded {
+    fn into_response(self) -> Response {
+        let (resource, limit) = match self {
+            QuotaExceeded::Posts { limit } => ("posts", limit),
+            QuotaExceeded::MediaBytes { limit } => ("media_bytes", limit),
+        };
+        (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": "quota_exceeded",
+                "message": self.to_string(),
+                "resource": resource,
+                "limit": limit,
+            })),
+        )
+            .into_response()
+    }
+}
+
+impl Quota {
+    /// Whether `new_posts` more posts fit
+    pub fn check_posts(&self, usage: &Usage, new_posts: i64) -> Result<(), QuotaExceeded> {
+        match self.max_posts {
+            Some(limit) if usage.posts + new_posts > limit => Err(QuotaExceeded::Posts { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether an upload of `bytes` fits
+    pub fn check_media(&self, usage: &Usage, bytes: i64) -> Result<(), QuotaExceeded> {
+        match self.max_media_bytes {
+            Some(limit) if usage.media_bytes + bytes > limit => {
+                Err(QuotaExceeded::MediaBytes { limit })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The quota of `domain_id`, unlimited when none is set; `None` when there
+/// is no such domain
+pub async fn load(db: &PgPool, domain_id: i32) -> Result<Option<Quota>, sqlx::Error> {
+    let value: Option<serde_json::Value> =
+        sqlx::query_scalar("SELECT quota FROM domains WHERE id = $1")
+            .bind(domain_id)
+            .fetch_optional(db)
+            .await?;
+
+    Ok(value.map(|v| serde_json::from_value(v).unwrap_or_default()))
+}
+
+/// Replace the quota of `domain_id`
+pub async fn save(db: &PgPool, domain_id: i32, quota: &Quota) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE domains SET quota = $2, updated_at = NOW() WHERE id = $1")
+        .bind(domain_id)
+        .bind(serde_json::json!(quota))
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Posts and media bytes `domain_id` stores now
+pub async fn usage(db: &PgPool, domain_id: i32) -> Result<Usage, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT (SELECT COUNT(*) FROM posts WHERE domain_id = $1) AS posts,
+               (SELECT COALESCE(SUM(size_bytes), 0)::BIGINT FROM media WHERE domain_id = $1) AS media_bytes
+        "#,
+    )
+    .bind(domain_id)
+    .fetch_one(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USAGE: Usage = Usage {
+        posts: 10,
+        media_bytes: 1_000,
+    };
+
+    #[test]
+    fn unlimited_by_default() {
+        let quota = Quota::default();
+        assert!(quota.check_posts(&USAGE, 1_000).is_ok());
+        assert!(quota.check_media(&USAGE, i64::from(i32::MAX)).is_ok());
+    }
+
+    #[test]
+    fn limits_are_inclusive() {
+        let quota = Quota {
+            max_posts: Some(11),
+            max_media_bytes: Some(1_500),
+        };
+        assert!(quota.check_posts(&USAGE, 1).is_ok());
+        assert_eq!(
+            quota.check_posts(&USAGE, 2),
+            Err(QuotaExceeded::Posts { limit: 11 })
+        );
+        assert!(quota.check_media(&USAGE, 500).is_ok());
+        assert_eq!(
+            quota.check_media(&USAGE, 501),
+            Err(QuotaExceeded::MediaBytes { limit: 1_500 })
+        );
+    }
+
+    #[test]
+    fn negative_limits_are_invalid() {
+        let quota = Quota {
+            max_posts: Some(-1),
+            max_media_bytes: None,
+        };
+        assert!(quota.validate().is_err());
+        assert!(Quota::default().validate().is_ok());
+    }
+}
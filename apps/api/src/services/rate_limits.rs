@@ -0,0 +1,46 @@
+// src/services/rate_limits.rs
+//! Persisted rate limiter thresholds
+//!
+//! Platform admins change the limits through `PUT /admin/rate-limits`. The new
+//! values take effect on the next request and are saved in the `rate_limits`
+//! runtime setting, which is loaded again at startup so they survive a restart.
+
+use crate::middleware::RateLimitSettings;
+use sqlx::PgPool;
+
+const SETTING_KEY: &str = "rate_limits";
+
+/// The saved thresholds, if an admin has ever changed them
+pub async fn load(db: &PgPool) -> Result<Option<RateLimitSettings>, sqlx::Error> {
+    let value: Option<serde_json::Value> =
+        sqlx::query_scalar("SELECT value FROM settings WHERE key = $1")
+            .bind(SETTING_KEY)
+            .fetch_optional(db)
+            .await?;
+
+    Ok(value.and_then(|value| match serde_json::from_value(value) {
+        Ok(settings) => Some(settings),
+        Err(e) => {
+            tracing::warn!(error = %e, "Ignoring unreadable rate_limits setting");
+            None
+        }
+    }))
+}
+
+/// Save the thresholds to be loaded on the next startup
+pub async fn save(db: &PgPool, settings: &RateLimitSettings) -> Result<(), sqlx::Error> {
+    let value = serde_json::to_value(settings).expect("rate limit settings serialize to JSON");
+
+    sqlx::query(
+        r#"
+        INSERT INTO settings (key, value) VALUES ($1, $2)
+        ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value, updated_at = NOW()
+        "#,
+    )
+    .bind(SETTING_KEY)
+    .bind(value)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+// src/services/reading_time.rs
+//! Word count and reading time of post content
+//!
+//! Computed when a post is saved so readers and dashboards get a stable
+//! estimate that doesn't depend on client-side tracking. HTML tags and
+//! markdown link targets are ignored, and tokens without any letter or digit
+//! (`#`, `-`, `>`, code fences, ...) aren't words.
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+pub(crate) static HTML_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").unwrap());
+
+/// `[text](url)` and `![alt](url)`, keeping only the text
+pub(crate) static MARKDOWN_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").unwrap());
+
+/// Reading speed used for estimates.
+/// Configurable via `READING_WORDS_PER_MINUTE` (default 200).
+pub fn words_per_minute() -> i32 {
+    std::env::var("READING_WORDS_PER_MINUTE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|wpm| *wpm > 0)
+        .unwrap_or(200)
+}
+
+/// Number of words in markdown/HTML content
+pub fn word_count(content: &str) -> i32 {
+    let text = HTML_TAG.replace_all(content, " ");
+    let text = MARKDOWN_LINK.replace_all(&text, " $1 ");
+    text.split_whitespace()
+        .filter(|token| token.chars().any(char::is_alphanumeric))
+        .count() as i32
+}
+
+/// Whole minutes needed to read `words`, rounded up; 0 for empty content
+pub fn reading_time_minutes(words: i32, words_per_minute: i32) -> i32 {
+    if words <= 0 {
+        return 0;
+    }
+    let words_per_minute = words_per_minute.max(1);
+    (words + words_per_minute - 1) / words_per_minute
+}
+
+/// `(word_count, reading_time_minutes)` of content at the configured speed
+pub fn estimate(content: &str) -> (i32, i32) {
+    let words = word_count(content);
+    (words, reading_time_minutes(words, words_per_minute()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_count_of_mixed_markdown() {
+        let content = "# Getting Started\n\n\
+            Rust is **fast** and <em>safe</em>.\n\n\
+            - Read [the book](https://doc.rust-lang.org/book/)\n\
+            - ![Ferris](ferris.png) says hi\n\n\
+            ```\nfn main() {}\n```\n\n\
+            > Quote -- here";
+        // Getting Started | Rust is fast and safe | Read the book | Ferris says hi
+        // | fn main() | Quote here
+        assert_eq!(word_count(content), 17);
+    }
+
+    #[test]
+    fn test_empty_content() {
+        assert_eq!(word_count(""), 0);
+        assert_eq!(word_count("<p></p>\n\n---\n"), 0);
+        assert_eq!(reading_time_minutes(0, 200), 0);
+    }
+
+    #[test]
+    fn test_reading_time_rounds_up() {
+        assert_eq!(reading_time_minutes(1, 200), 1);
+        assert_eq!(reading_time_minutes(200, 200), 1);
+        assert_eq!(reading_time_minutes(201, 200), 2);
+        assert_eq!(reading_time_minutes(900, 300), 3);
+    }
+}
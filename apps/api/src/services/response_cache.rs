@@ -0,0 +1,130 @@
+// src/services/response_cache.rs
+//! Short-lived in-memory cache of assembled responses
+//!
+//! The analytics dashboard runs a dozen heavy queries per request, and a team
+//! looking at the same domains keeps asking for the same numbers. Its
+//! serialized response is kept here for `ttl` and served to anyone with the
+//! same key. Nothing is invalidated: new events show up once the entry
+//! expires. The cache is per instance.
+
+use axum::body::Bytes;
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Entries kept before expired ones are swept
+const SWEEP_THRESHOLD: usize = 1_000;
+
+/// How long a dashboard response is served from the cache.
+/// Configurable via `ANALYTICS_DASHBOARD_CACHE_SECONDS` (default 60, 0 turns it off).
+pub fn dashboard_ttl() -> Duration {
+    Duration::from_secs(
+        std::env::var("ANALYTICS_DASHBOARD_CACHE_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60),
+    )
+}
+
+/// A response served from the cache
+#[derive(Debug, Clone)]
+pub struct Cached {
+    pub body: Bytes,
+    /// Time since it was computed
+    pub age: Duration,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    stored_at: Instant,
+    body: Bytes,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    ttl: Duration,
+    entries: Arc<DashMap<String, Entry>>,
+}
+
+impl ResponseCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::new(DashMap::new()),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// The response stored under `key`, unless it has expired
+    pub fn get(&self, key: &str) -> Option<Cached> {
+        self.get_at(key, Instant::now())
+    }
+
+    /// Store `body` under `key`, replacing what was there
+    pub fn insert(&self, key: String, body: Bytes) {
+        self.insert_at(key, body, Instant::now());
+    }
+
+    fn get_at(&self, key: &str, now: Instant) -> Option<Cached> {
+        let cached = {
+            let entry = self.entries.get(key)?;
+            let age = now.saturating_duration_since(entry.stored_at);
+            (age < self.ttl).then(|| Cached {
+                body: entry.body.clone(),
+                age,
+            })
+        };
+        if cached.is_none() {
+            self.entries.remove_if(key, |_, entry| {
+                now.duration_since(entry.stored_at) >= self.ttl
+            });
+        }
+        cached
+    }
+
+    fn insert_at(&self, key: String, body: Bytes, now: Instant) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        if self.entries.len() >= SWEEP_THRESHOLD {
+            self.entries
+                .retain(|_, entry| now.duration_since(entry.stored_at) < self.ttl);
+        }
+        self.entries.insert(
+            key,
+            Entry {
+                stored_at: now,
+                body,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_expire_after_ttl() {
+        let cache = ResponseCache::new(Duration::from_secs(60));
+        let now = Instant::now();
+        cache.insert_at("a".to_string(), Bytes::from_static(b"{}"), now);
+
+        let cached = cache.get_at("a", now + Duration::from_secs(59)).unwrap();
+        assert_eq!(cached.body, Bytes::from_static(b"{}"));
+        assert_eq!(cached.age, Duration::from_secs(59));
+
+        assert!(cache.get_at("a", now + Duration::from_secs(60)).is_none());
+        assert!(cache.get_at("b", now).is_none());
+    }
+
+    #[test]
+    fn zero_ttl_caches_nothing() {
+        let cache = ResponseCache::new(Duration::ZERO);
+        cache.insert("a".to_string(), Bytes::from_static(b"{}"));
+        assert!(cache.get("a").is_none());
+    }
+}
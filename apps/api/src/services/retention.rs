@@ -0,0 +1,182 @@
+// src/services/retention.rs
+//! Analytics event retention
+//!
+//! `analytics_events` is append-only, so a daily background job deletes rows
+//! older than `ANALYTICS_RETENTION_DAYS`. Deletes run in small batches to keep
+//! lock times short. Before deleting, per-day counts can be rolled up into
+//! `analytics_daily_summary` so long-term trends survive pruning.
+
+use super::clock::Clock;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::env;
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// How often the background job prunes
+const PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// Retention settings, read from the environment
+#[derive(Debug, Clone)]
+pub struct RetentionConfig {
+    pub retention_days: i64,
+    /// Rows deleted per statement
+    pub batch_size: i64,
+    /// Keep per-day event counts in `analytics_daily_summary` before deleting
+    pub preserve_rollups: bool,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: env::var("ANALYTICS_RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|d| *d > 0)
+                .unwrap_or(365),
+            batch_size: env::var("ANALYTICS_PRUNE_BATCH_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(5000),
+            preserve_rollups: env::var("ANALYTICS_RETENTION_ROLLUP")
+                .map(|v| v.parse().unwrap_or(true))
+                .unwrap_or(true),
+        }
+    }
+}
+
+impl RetentionConfig {
+    /// Events created before this instant are pruned. Aligned to midnight UTC
+    /// so a day is always rolled up and deleted as a whole.
+    pub fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let date = (now - Duration::days(self.retention_days)).date_naive();
+        Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PruneReport {
+    pub cutoff: DateTime<Utc>,
+    pub summary_rows: u64,
+    pub deleted_events: u64,
+}
+
+/// Store per-day, per-event-type counts for everything older than `cutoff`.
+/// Days already summarized are left untouched, so re-running after an
+/// interrupted prune doesn't count the same day twice.
+pub async fn rollup_daily_summary(db: &PgPool, cutoff: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO analytics_daily_summary (domain_id, day, event_type, events, unique_visitors)
+        SELECT domain_id, (created_at AT TIME ZONE 'UTC')::date, event_type,
+               COUNT(*), COUNT(DISTINCT ip_address)
+        FROM analytics_events
+        WHERE created_at < $1 AND domain_id IS NOT NULL
+        GROUP BY domain_id, (created_at AT TIME ZONE 'UTC')::date, event_type
+        ON CONFLICT (domain_id, day, event_type) DO NOTHING
+        "#,
+    )
+    .bind(cutoff)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Delete events older than `cutoff`, `batch_size` rows at a time
+pub async fn delete_events_before(
+    db: &PgPool,
+    cutoff: DateTime<Utc>,
+    batch_size: i64,
+) -> Result<u64, sqlx::Error> {
+    let mut deleted = 0;
+
+    loop {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM analytics_events
+            WHERE id IN (
+                SELECT id FROM analytics_events
+                WHERE created_at < $1
+                LIMIT $2
+            )
+            "#,
+        )
+        .bind(cutoff)
+        .bind(batch_size)
+        .execute(db)
+        .await?;
+
+        deleted += result.rows_affected();
+        if result.rows_affected() < batch_size as u64 {
+            return Ok(deleted);
+        }
+    }
+}
+
+/// Roll up (if enabled) and delete everything past the retention window
+pub async fn prune_analytics(
+    db: &PgPool,
+    config: &RetentionConfig,
+    now: DateTime<Utc>,
+) -> Result<PruneReport, sqlx::Error> {
+    let cutoff = config.cutoff(now);
+
+    let summary_rows = if config.preserve_rollups {
+        rollup_daily_summary(db, cutoff).await?
+    } else {
+        0
+    };
+    let deleted_events = delete_events_before(db, cutoff, config.batch_size).await?;
+
+    Ok(PruneReport {
+        cutoff,
+        summary_rows,
+        deleted_events,
+    })
+}
+
+/// Start the daily retention background task, dating the cutoff by `clock`
+pub fn start_retention_task(
+    db: PgPool,
+    clock: Arc<dyn Clock>,
+    config: RetentionConfig,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            match prune_analytics(&db, &config, clock.now()).await {
+                Ok(report) => info!(
+                    cutoff = %report.cutoff,
+                    deleted = report.deleted_events,
+                    summarized = report.summary_rows,
+                    "Pruned old analytics events"
+                ),
+                Err(e) => error!(error = %e, "Failed to prune analytics events"),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cutoff_aligned_to_midnight() {
+        let config = RetentionConfig {
+            retention_days: 30,
+            batch_size: 100,
+            preserve_rollups: true,
+        };
+        let now = Utc.with_ymd_and_hms(2024, 3, 31, 15, 42, 7).unwrap();
+        assert_eq!(
+            config.cutoff(now),
+            Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,84 @@
+// src/services/sampling.rs
+//! Page-view sampling for high-traffic domains
+//!
+//! A domain sets `analytics_config.page_view_sample_rate` in its settings to
+//! keep only that fraction of page views. Each stored row carries
+//! `sample_weight = 1 / rate`, so summing the weight estimates the true count.
+//! Other events are always stored with weight 1.
+
+use crate::DomainContext;
+
+/// Lowest accepted rate; smaller values are raised to it so weights stay bounded
+pub const MIN_SAMPLE_RATE: f64 = 0.001;
+
+/// Fraction of page views to store for `domain`, 1.0 unless configured
+pub fn page_view_sample_rate(domain: &DomainContext) -> f64 {
+    domain
+        .theme_config
+        .get("analytics_config")
+        .and_then(|config| config.get("page_view_sample_rate"))
+        .and_then(serde_json::Value::as_f64)
+        .filter(|rate| rate.is_finite() && *rate > 0.0)
+        .map(|rate| rate.clamp(MIN_SAMPLE_RATE, 1.0))
+        .unwrap_or(1.0)
+}
+
+/// Weight to store a page view with, or `None` to drop it. `roll` is a
+/// uniform random number in `[0, 1)`.
+pub fn sample_weight(rate: f64, roll: f64) -> Option<f64> {
+    if rate >= 1.0 {
+        Some(1.0)
+    } else if roll < rate {
+        Some(1.0 / rate)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn domain(theme_config: serde_json::Value) -> DomainContext {
+        DomainContext {
+            id: 1,
+            hostname: "example.com".to_string(),
+            name: "Example".to_string(),
+            theme_config,
+            categories: vec![],
+            features: serde_json::json!({}),
+            timezone: "UTC".to_string(),
+            cors_origins: vec![],
+        }
+    }
+
+    #[test]
+    fn test_sample_rate_from_domain_config() {
+        let rate = |config| page_view_sample_rate(&domain(config));
+        assert_eq!(rate(serde_json::json!({})), 1.0);
+        assert_eq!(
+            rate(serde_json::json!({"analytics_config": {"page_view_sample_rate": 0.25}})),
+            0.25
+        );
+        assert_eq!(
+            rate(serde_json::json!({"analytics_config": {"page_view_sample_rate": 3}})),
+            1.0
+        );
+        assert_eq!(
+            rate(serde_json::json!({"analytics_config": {"page_view_sample_rate": 0}})),
+            1.0
+        );
+        assert_eq!(
+            rate(serde_json::json!({"analytics_config": {"page_view_sample_rate": 0.00001}})),
+            MIN_SAMPLE_RATE
+        );
+    }
+
+    #[test]
+    fn test_sample_weight() {
+        assert_eq!(sample_weight(1.0, 0.999), Some(1.0));
+        assert_eq!(sample_weight(0.25, 0.1), Some(4.0));
+        assert_eq!(sample_weight(0.25, 0.25), None);
+        assert_eq!(sample_weight(0.25, 0.9), None);
+    }
+}
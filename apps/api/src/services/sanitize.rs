@@ -0,0 +1,96 @@
+// src/services/sanitize.rs
+//! Server-side sanitization of post content
+//!
+//! A domain whose `content_config` sets `"format": "html"` has its post
+//! content cleaned with ammonia when it is saved: `<script>` and `<style>`
+//! elements, event handler attributes and `javascript:` URLs are removed while
+//! formatting, links, images and tables are kept. Markdown, the default, is
+//! stored exactly as written, since escaping it would mangle `>` quotes and
+//! code blocks; it has to be sanitized when rendered instead.
+
+use crate::DomainContext;
+
+/// How a domain's post content is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContentFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl ContentFormat {
+    /// Format set in the domain's `content_config`, markdown when unset
+    pub fn of(domain: &DomainContext) -> Self {
+        match domain
+            .theme_config
+            .pointer("/content_config/format")
+            .and_then(serde_json::Value::as_str)
+        {
+            Some(format) if format.eq_ignore_ascii_case("html") => Self::Html,
+            _ => Self::Markdown,
+        }
+    }
+}
+
+/// `content` as it should be stored for a domain writing in `format`
+pub fn for_storage(content: String, format: ContentFormat) -> String {
+    match format {
+        ContentFormat::Markdown => content,
+        ContentFormat::Html => ammonia::clean(&content),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn domain(theme_config: serde_json::Value) -> DomainContext {
+        DomainContext {
+            id: 1,
+            hostname: "example.com".to_string(),
+            name: "Example".to_string(),
+            theme_config,
+            categories: vec![],
+            features: json!({}),
+            timezone: "UTC".to_string(),
+            cors_origins: vec![],
+        }
+    }
+
+    #[test]
+    fn test_html_loses_scripts_and_handlers() {
+        let cleaned = for_storage(
+            r#"<p onclick="steal()">Hi <strong>there</strong></p><script>alert(1)</script><a href="javascript:alert(1)">x</a>"#
+                .to_string(),
+            ContentFormat::Html,
+        );
+        assert!(!cleaned.contains("script"));
+        assert!(!cleaned.contains("onclick"));
+        assert!(!cleaned.contains("javascript:"));
+        assert!(cleaned.contains("<p>Hi <strong>there</strong></p>"));
+    }
+
+    #[test]
+    fn test_markdown_is_stored_as_written() {
+        let content = "> quoted\n\n```html\n<script>demo()</script>\n```\n".to_string();
+        assert_eq!(
+            for_storage(content.clone(), ContentFormat::Markdown),
+            content
+        );
+    }
+
+    #[test]
+    fn test_format_comes_from_content_config() {
+        let format = |config| ContentFormat::of(&domain(config));
+        assert_eq!(format(json!({})), ContentFormat::Markdown);
+        assert_eq!(
+            format(json!({"content_config": {"format": "markdown"}})),
+            ContentFormat::Markdown
+        );
+        assert_eq!(
+            format(json!({"content_config": {"format": "HTML"}})),
+            ContentFormat::Html
+        );
+    }
+}
@@ -0,0 +1,344 @@
+// src/services/search.rs
+//! Full-text post search, tuned per domain
+//!
+//! Posts are matched against a weighted document: the title is weight `A`,
+//! the excerpt `B` and the content `C`, and `ts_rank` scores each by the
+//! domain's [`FieldWeights`], so by default a title match outranks one in the
+//! body. Before the query reaches Postgres the domain's stopwords are dropped
+//! and each remaining word is widened to its synonym group, so with
+//! `["js", "javascript"]` configured a search for `js` finds JavaScript posts.
+
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashSet;
+
+/// The weighted document posts are searched in. Kept in step with the
+/// `idx_posts_search` expression index, which only applies to this exact text.
+pub const SEARCH_DOCUMENT: &str = "(setweight(to_tsvector('english', title), 'A') \
+     || setweight(to_tsvector('english', excerpt), 'B') \
+     || setweight(to_tsvector('english', content), 'C'))";
+
+/// `ts_headline` options for highlighted snippets
+pub const HEADLINE_OPTIONS: &str =
+    "StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MaxWords=30, MinWords=10";
+
+/// Most synonym groups plus stopwords a domain may configure
+pub const MAX_TERMS: usize = 500;
+
+const MAX_TERM_CHARS: usize = 50;
+
+/// How much a match in each field counts towards the rank, from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldWeights {
+    pub title: f32,
+    pub excerpt: f32,
+    pub content: f32,
+}
+
+impl Default for FieldWeights {
+    fn default() -> Self {
+        FieldWeights {
+            title: 1.0,
+            excerpt: 0.4,
+            content: 0.2,
+        }
+    }
+}
+
+impl FieldWeights {
+    /// The `{D, C, B, A}` array `ts_rank` takes; nothing is weighted `D`
+    pub fn rank_weights(&self) -> Vec<f32> {
+        vec![0.0, self.content, self.excerpt, self.title]
+    }
+}
+
+/// A domain's search tuning, as read and replaced through `/admin/domain/search`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchSettings {
+    pub weights: FieldWeights,
+    /// Groups of interchangeable words, e.g. `[["js", "javascript"]]`
+    pub synonyms: Vec<Vec<String>>,
+    /// Words left out of queries on top of the English ones
+    pub stopwords: Vec<String>,
+}
+
+/// Why search settings were rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchSettingsError {
+    WeightOutOfRange(&'static str),
+    /// Every weight is zero, so nothing would rank
+    NoWeight,
+    /// A synonym or stopword that isn't a single word
+    InvalidTerm(String),
+    /// A synonym group with fewer than two words
+    LoneSynonym(String),
+    /// A word in more than one synonym group
+    DuplicateSynonym(String),
+    TooManyTerms,
+}
+
+impl SearchSettingsError {
+    /// The settings field the error is about
+    pub fn field(&self) -> &'static str {
+        match self {
+            SearchSettingsError::WeightOutOfRange(_) | SearchSettingsError::NoWeight => "weights",
+            SearchSettingsError::InvalidTerm(_) | SearchSettingsError::TooManyTerms => "terms",
+            SearchSettingsError::LoneSynonym(_) | SearchSettingsError::DuplicateSynonym(_) => {
+                "synonyms"
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for SearchSettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchSettingsError::WeightOutOfRange(field) => {
+                write!(f, "The {field} weight must be between 0 and 1")
+            }
+            SearchSettingsError::NoWeight => write!(f, "At least one weight must be above 0"),
+            SearchSettingsError::InvalidTerm(term) => write!(
+                f,
+                "'{term}' must be a single word of at most {MAX_TERM_CHARS} letters or digits"
+            ),
+            SearchSettingsError::LoneSynonym(term) => {
+                write!(f, "Synonym group '{term}' needs at least two words")
+            }
+            SearchSettingsError::DuplicateSynonym(term) => {
+                write!(f, "'{term}' is in more than one synonym group")
+            }
+            SearchSettingsError::TooManyTerms => write!(
+                f,
+                "At most {MAX_TERMS} synonym groups and stopwords may be configured"
+            ),
+        }
+    }
+}
+
+fn is_word(term: &str) -> bool {
+    !term.is_empty()
+        && term.chars().count() <= MAX_TERM_CHARS
+        && term.chars().all(char::is_alphanumeric)
+}
+
+impl SearchSettings {
+    /// Check the settings before they are stored
+    pub fn check(&self) -> Result<(), SearchSettingsError> {
+        let weights = [
+            ("title", self.weights.title),
+            ("excerpt", self.weights.excerpt),
+            ("content", self.weights.content),
+        ];
+        for (field, weight) in weights {
+            if !(0.0..=1.0).contains(&weight) {
+                return Err(SearchSettingsError::WeightOutOfRange(field));
+            }
+        }
+        if weights.iter().all(|(_, weight)| *weight == 0.0) {
+            return Err(SearchSettingsError::NoWeight);
+        }
+
+        if self.synonyms.len() + self.stopwords.len() > MAX_TERMS {
+            return Err(SearchSettingsError::TooManyTerms);
+        }
+
+        let mut grouped = HashSet::new();
+        for group in &self.synonyms {
+            if group.len() < 2 {
+                return Err(SearchSettingsError::LoneSynonym(group.join(" ")));
+            }
+            for term in group {
+                if !is_word(term) {
+                    return Err(SearchSettingsError::InvalidTerm(term.clone()));
+                }
+                if !grouped.insert(term.to_lowercase()) {
+                    return Err(SearchSettingsError::DuplicateSynonym(term.clone()));
+                }
+            }
+        }
+
+        match self.stopwords.iter().find(|term| !is_word(term)) {
+            Some(term) => Err(SearchSettingsError::InvalidTerm(term.clone())),
+            None => Ok(()),
+        }
+    }
+
+    fn synonyms_of(&self, word: &str) -> Option<&Vec<String>> {
+        self.synonyms
+            .iter()
+            .find(|group| group.iter().any(|term| term.to_lowercase() == word))
+    }
+
+    fn is_stopword(&self, word: &str) -> bool {
+        self.stopwords
+            .iter()
+            .any(|term| term.to_lowercase() == word)
+    }
+}
+
+/// The `to_tsquery` text for a visitor's search: every word must match,
+/// either itself or one of its synonyms. `None` when no word is left to
+/// search for. Words are letters and digits only, so the visitor can't slip
+/// in tsquery operators.
+pub fn build_tsquery(query: &str, settings: &SearchSettings) -> Option<String> {
+    let mut seen = HashSet::new();
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .filter(|word| !settings.is_stopword(word) && seen.insert(word.clone()))
+        .map(|word| match settings.synonyms_of(&word) {
+            Some(group) => {
+                let alternatives: Vec<String> = group.iter().map(|t| t.to_lowercase()).collect();
+                format!("({})", alternatives.join(" | "))
+            }
+            None => word,
+        })
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" & "))
+    }
+}
+
+/// The domain's search settings; the defaults when none have been saved
+pub async fn load(db: &PgPool, domain_id: i32) -> Result<SearchSettings, sqlx::Error> {
+    let settings: Option<serde_json::Value> =
+        sqlx::query_scalar("SELECT settings FROM domain_search_settings WHERE domain_id = $1")
+            .bind(domain_id)
+            .fetch_optional(db)
+            .await?;
+
+    Ok(settings
+        .and_then(|settings| serde_json::from_value(settings).ok())
+        .unwrap_or_default())
+}
+
+/// Replace the domain's search settings
+pub async fn save(
+    db: &PgPool,
+    domain_id: i32,
+    settings: &SearchSettings,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO domain_search_settings (domain_id, settings) VALUES ($1, $2)
+        ON CONFLICT (domain_id) DO UPDATE SET settings = EXCLUDED.settings, updated_at = NOW()
+        "#,
+    )
+    .bind(domain_id)
+    .bind(serde_json::json!(settings))
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(terms: &[&str]) -> Vec<String> {
+        terms.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_every_word_must_match() {
+        let settings = SearchSettings::default();
+        assert_eq!(
+            build_tsquery("Rust  programming", &settings),
+            Some("rust & programming".to_string())
+        );
+        assert_eq!(
+            build_tsquery("rust rust", &settings),
+            Some("rust".to_string())
+        );
+        assert_eq!(build_tsquery("  !? ", &settings), None);
+    }
+
+    #[test]
+    fn test_operators_are_not_passed_through() {
+        let settings = SearchSettings::default();
+        assert_eq!(
+            build_tsquery("rust | !go & (c:*)", &settings),
+            Some("rust & go & c".to_string())
+        );
+    }
+
+    #[test]
+    fn test_synonyms_and_stopwords() {
+        let settings = SearchSettings {
+            synonyms: vec![words(&["JS", "javascript"]), words(&["pg", "postgres"])],
+            stopwords: words(&["howto"]),
+            ..Default::default()
+        };
+        assert_eq!(
+            build_tsquery("js howto", &settings),
+            Some("(js | javascript)".to_string())
+        );
+        assert_eq!(
+            build_tsquery("Postgres tuning", &settings),
+            Some("(pg | postgres) & tuning".to_string())
+        );
+        assert_eq!(build_tsquery("howto", &settings), None);
+    }
+
+    #[test]
+    fn test_rank_weights_order() {
+        let weights = FieldWeights {
+            title: 1.0,
+            excerpt: 0.5,
+            content: 0.25,
+        };
+        assert_eq!(weights.rank_weights(), vec![0.0, 0.25, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn test_check() {
+        assert_eq!(SearchSettings::default().check(), Ok(()));
+
+        let mut settings = SearchSettings::default();
+        settings.weights.title = 1.5;
+        assert_eq!(
+            settings.check(),
+            Err(SearchSettingsError::WeightOutOfRange("title"))
+        );
+        settings.weights = FieldWeights {
+            title: 0.0,
+            excerpt: 0.0,
+            content: 0.0,
+        };
+        assert_eq!(settings.check(), Err(SearchSettingsError::NoWeight));
+
+        let settings = SearchSettings {
+            synonyms: vec![words(&["js"])],
+            ..Default::default()
+        };
+        assert!(matches!(
+            settings.check(),
+            Err(SearchSettingsError::LoneSynonym(_))
+        ));
+
+        let settings = SearchSettings {
+            synonyms: vec![words(&["js", "javascript"]), words(&["JS", "ecmascript"])],
+            ..Default::default()
+        };
+        assert_eq!(
+            settings.check(),
+            Err(SearchSettingsError::DuplicateSynonym("JS".to_string()))
+        );
+
+        let settings = SearchSettings {
+            stopwords: words(&["a:*"]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            settings.check(),
+            Err(SearchSettingsError::InvalidTerm(_))
+        ));
+    }
+}
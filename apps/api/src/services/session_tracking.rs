@@ -6,6 +6,9 @@ use sqlx::{PgPool, types::ipnetwork::IpNetwork};
 use std::net::IpAddr;
 use uuid::Uuid;
 
+/// `(device_type, user_agent, count)` grouped for the device breakdown
+type DeviceCountRow = (Option<String>, Option<String>, i64);
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserSession {
     pub id: Uuid,
@@ -100,6 +103,75 @@ impl SessionInfo {
     }
 }
 
+/// Session timeout and bounce definition behind the session analytics
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    /// Minutes without activity after which an open session counts as ended
+    /// at its last activity
+    pub inactivity_timeout_minutes: i64,
+    /// Sessions with at most this many page views are bounces
+    pub bounce_max_page_views: i32,
+    /// Sessions shorter than this are bounces too, however many pages they saw
+    pub bounce_max_seconds: Option<i32>,
+}
+
+impl Default for SessionConfig {
+    /// `SESSION_TIMEOUT_MINUTES` (default 30), `BOUNCE_MAX_PAGE_VIEWS`
+    /// (default 1) and `BOUNCE_MAX_SECONDS` (unset by default, so bounces are
+    /// decided by page views alone)
+    fn default() -> Self {
+        let env_number = |var: &str| {
+            std::env::var(var)
+                .ok()
+                .and_then(|v| v.trim().parse::<i64>().ok())
+        };
+        Self {
+            inactivity_timeout_minutes: env_number("SESSION_TIMEOUT_MINUTES")
+                .filter(|minutes| *minutes > 0)
+                .unwrap_or(30),
+            bounce_max_page_views: env_number("BOUNCE_MAX_PAGE_VIEWS")
+                .filter(|views| *views >= 0)
+                .map(|views| views as i32)
+                .unwrap_or(1),
+            bounce_max_seconds: env_number("BOUNCE_MAX_SECONDS")
+                .filter(|seconds| *seconds > 0)
+                .map(|seconds| seconds as i32),
+        }
+    }
+}
+
+impl SessionConfig {
+    /// Open sessions last active before this have timed out
+    pub fn expired_before(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - chrono::Duration::minutes(self.inactivity_timeout_minutes)
+    }
+}
+
+/// Session length in seconds for analytics queries binding the timeout
+/// cutoff as `$4`: the recorded duration once a session has ended, the time
+/// up to its last activity once it has timed out, NULL while it is ongoing
+const EFFECTIVE_DURATION: &str = "COALESCE(duration_seconds, CASE WHEN last_activity_at < $4 \
+     THEN EXTRACT(EPOCH FROM (last_activity_at - started_at))::INTEGER END)";
+
+/// Whether `error` is Postgres reporting an undefined table, as it does
+/// while the session migrations haven't run
+pub fn is_missing_table(error: &sqlx::Error) -> bool {
+    error
+        .as_database_error()
+        .and_then(|e| e.code())
+        .is_some_and(|code| code == "42P01")
+}
+
+/// A session metric with nothing to compute it from, because the session
+/// tables are missing or hold no matching sessions, is `None` rather than a
+/// made-up number
+fn absent_if_missing<T>(result: Result<Option<T>, sqlx::Error>) -> Result<Option<T>, sqlx::Error> {
+    match result {
+        Err(e) if is_missing_table(&e) => Ok(None),
+        other => other,
+    }
+}
+
 pub struct SessionTracker;
 
 impl SessionTracker {
@@ -231,169 +303,149 @@ impl SessionTracker {
         }
     }
 
-    /// Get device breakdown for analytics
+    /// Get device breakdown for analytics as (mobile, desktop, tablet, unknown).
+    ///
+    /// Counts non-bot sessions started in the range on the given domains (all
+    /// domains when `domain_ids` is `None`). Sessions without a stored
+    /// `device_type` are classified from their user agent, and when there are
+    /// no sessions at all, distinct visitors in `analytics_events` are
+    /// classified by user agent instead.
     pub async fn get_device_breakdown(
         db: &PgPool,
         start_date: DateTime<Utc>,
         end_date: DateTime<Utc>,
-        domain_name: Option<&str>,
+        domain_ids: Option<&[i32]>,
     ) -> Result<(i64, i64, i64, i64), sqlx::Error> {
-        let (mobile, desktop, tablet, unknown) = if let Some(domain) = domain_name {
-            let result = sqlx::query!(
+        let session_rows: Result<Vec<DeviceCountRow>, _> = sqlx::query_as(
+            r#"
+            SELECT s.device_type, s.user_agent, COUNT(*)
+            FROM user_sessions s
+            LEFT JOIN domains d ON d.hostname = s.domain_name
+            WHERE s.started_at BETWEEN $1 AND $2
+              AND s.is_bot IS NOT TRUE
+              AND ($3::int[] IS NULL OR d.id = ANY($3))
+            GROUP BY s.device_type, s.user_agent
+            "#,
+        )
+        .bind(start_date)
+        .bind(end_date)
+        .bind(domain_ids)
+        .fetch_all(db)
+        .await;
+        let session_rows = match session_rows {
+            Err(e) if is_missing_table(&e) => Vec::new(),
+            rows => rows?,
+        };
+
+        let rows = if session_rows.is_empty() {
+            sqlx::query_as(
                 r#"
-                SELECT 
-                    COUNT(*) FILTER (WHERE device_type = 'mobile') as mobile,
-                    COUNT(*) FILTER (WHERE device_type = 'desktop') as desktop,
-                    COUNT(*) FILTER (WHERE device_type = 'tablet') as tablet,
-                    COUNT(*) FILTER (WHERE device_type = 'unknown') as unknown
-                FROM user_sessions 
-                WHERE started_at BETWEEN $1 AND $2 
-                AND domain_name = $3
-                AND is_bot = false
+                SELECT NULL::text, user_agent, COUNT(DISTINCT ip_address)
+                FROM analytics_events
+                WHERE created_at BETWEEN $1 AND $2
+                  AND ($3::int[] IS NULL OR domain_id = ANY($3))
+                GROUP BY user_agent
                 "#,
-                start_date,
-                end_date,
-                domain
-            )
-            .fetch_one(db)
-            .await?;
-            (
-                result.mobile.unwrap_or(0),
-                result.desktop.unwrap_or(0),
-                result.tablet.unwrap_or(0),
-                result.unknown.unwrap_or(0),
             )
+            .bind(start_date)
+            .bind(end_date)
+            .bind(domain_ids)
+            .fetch_all(db)
+            .await?
         } else {
-            let result = sqlx::query!(
-                r#"
-                SELECT 
-                    COUNT(*) FILTER (WHERE device_type = 'mobile') as mobile,
-                    COUNT(*) FILTER (WHERE device_type = 'desktop') as desktop,
-                    COUNT(*) FILTER (WHERE device_type = 'tablet') as tablet,
-                    COUNT(*) FILTER (WHERE device_type = 'unknown') as unknown
-                FROM user_sessions 
-                WHERE started_at BETWEEN $1 AND $2
-                AND is_bot = false
-                "#,
-                start_date,
-                end_date
-            )
-            .fetch_one(db)
-            .await?;
-            (
-                result.mobile.unwrap_or(0),
-                result.desktop.unwrap_or(0),
-                result.tablet.unwrap_or(0),
-                result.unknown.unwrap_or(0),
-            )
+            session_rows
         };
 
+        let (mut mobile, mut desktop, mut tablet, mut unknown) = (0, 0, 0, 0);
+        for (device_type, user_agent, count) in rows {
+            let device_type = match device_type.as_deref() {
+                Some("mobile") => DeviceType::Mobile,
+                Some("desktop") => DeviceType::Desktop,
+                Some("tablet") => DeviceType::Tablet,
+                Some("unknown") => DeviceType::Unknown,
+                _ => user_agent
+                    .as_deref()
+                    .map(DeviceType::from_user_agent)
+                    .unwrap_or(DeviceType::Unknown),
+            };
+            match device_type {
+                DeviceType::Mobile => mobile += count,
+                DeviceType::Desktop => desktop += count,
+                DeviceType::Tablet => tablet += count,
+                DeviceType::Unknown => unknown += count,
+            }
+        }
+
         Ok((mobile, desktop, tablet, unknown))
     }
 
     /// Get average session duration for analytics
+    ///
+    /// Ended sessions use their recorded duration; sessions idle for longer
+    /// than the configured timeout count as ended at their last activity, and
+    /// sessions still within it are left out. `None` when no session qualifies
+    /// or the session tables are missing.
     pub async fn get_average_session_duration(
         db: &PgPool,
         start_date: DateTime<Utc>,
         end_date: DateTime<Utc>,
         domain_name: Option<&str>,
-    ) -> Result<f64, sqlx::Error> {
-        let avg_duration = if let Some(domain) = domain_name {
-            let result = sqlx::query!(
-                r#"
-                SELECT AVG(duration_seconds) as avg_duration
-                FROM user_sessions 
-                WHERE started_at BETWEEN $1 AND $2 
-                AND domain_name = $3
-                AND is_bot = false
-                AND duration_seconds IS NOT NULL
-                "#,
-                start_date,
-                end_date,
-                domain
-            )
-            .fetch_one(db)
-            .await?;
-            result.avg_duration
-        } else {
-            let result = sqlx::query!(
-                r#"
-                SELECT AVG(duration_seconds) as avg_duration
-                FROM user_sessions 
-                WHERE started_at BETWEEN $1 AND $2
-                AND is_bot = false
-                AND duration_seconds IS NOT NULL
-                "#,
-                start_date,
-                end_date
-            )
-            .fetch_one(db)
-            .await?;
-            result.avg_duration
-        };
+        config: &SessionConfig,
+    ) -> Result<Option<f64>, sqlx::Error> {
+        let avg_duration = sqlx::query_scalar(&format!(
+            r#"
+            SELECT AVG({EFFECTIVE_DURATION})::float8
+            FROM user_sessions
+            WHERE started_at BETWEEN $1 AND $2
+            AND ($3::text IS NULL OR domain_name = $3)
+            AND is_bot = false
+            "#
+        ))
+        .bind(start_date)
+        .bind(end_date)
+        .bind(domain_name)
+        .bind(config.expired_before(Utc::now()))
+        .fetch_one(db)
+        .await;
 
-        Ok(avg_duration
-            .and_then(|d| d.to_string().parse::<f64>().ok())
-            .unwrap_or(0.0))
+        absent_if_missing(avg_duration)
     }
 
-    /// Get bounce rate for analytics
+    /// Get bounce rate for analytics, as defined by `config`; `None` when
+    /// there are no sessions or the session tables are missing
     pub async fn get_bounce_rate(
         db: &PgPool,
         start_date: DateTime<Utc>,
         end_date: DateTime<Utc>,
         domain_name: Option<&str>,
-    ) -> Result<f64, sqlx::Error> {
-        let (total, bounces) = if let Some(domain) = domain_name {
-            let result = sqlx::query!(
-                r#"
-                SELECT 
-                    COUNT(*) as total_sessions,
-                    COUNT(*) FILTER (WHERE page_views <= 1) as bounce_sessions
-                FROM user_sessions 
-                WHERE started_at BETWEEN $1 AND $2 
-                AND domain_name = $3
-                AND is_bot = false
-                "#,
-                start_date,
-                end_date,
-                domain
-            )
-            .fetch_one(db)
-            .await?;
-            (
-                result.total_sessions.unwrap_or(0),
-                result.bounce_sessions.unwrap_or(0),
-            )
-        } else {
-            let result = sqlx::query!(
-                r#"
-                SELECT 
-                    COUNT(*) as total_sessions,
-                    COUNT(*) FILTER (WHERE page_views <= 1) as bounce_sessions
-                FROM user_sessions 
-                WHERE started_at BETWEEN $1 AND $2
-                AND is_bot = false
-                "#,
-                start_date,
-                end_date
-            )
-            .fetch_one(db)
-            .await?;
-            (
-                result.total_sessions.unwrap_or(0),
-                result.bounce_sessions.unwrap_or(0),
-            )
-        };
-
-        let total_f = total as f64;
-        let bounces_f = bounces as f64;
+        config: &SessionConfig,
+    ) -> Result<Option<f64>, sqlx::Error> {
+        let counts: Result<(i64, i64), _> = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                COUNT(*) as total_sessions,
+                COUNT(*) FILTER (
+                    WHERE COALESCE(page_views, 0) <= $5
+                    OR {EFFECTIVE_DURATION} < $6
+                ) as bounce_sessions
+            FROM user_sessions
+            WHERE started_at BETWEEN $1 AND $2
+            AND ($3::text IS NULL OR domain_name = $3)
+            AND is_bot = false
+            "#
+        ))
+        .bind(start_date)
+        .bind(end_date)
+        .bind(domain_name)
+        .bind(config.expired_before(Utc::now()))
+        .bind(config.bounce_max_page_views)
+        .bind(config.bounce_max_seconds)
+        .fetch_one(db)
+        .await;
 
-        if total_f > 0.0 {
-            Ok(bounces_f / total_f)
-        } else {
-            Ok(0.0)
-        }
+        absent_if_missing(
+            counts.map(|(total, bounces)| (total > 0).then(|| bounces as f64 / total as f64)),
+        )
     }
 
     /// Get session count for analytics
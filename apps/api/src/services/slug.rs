@@ -0,0 +1,133 @@
+// src/services/slug.rs
+//! Post slugs generated from titles
+//!
+//! Used when an editor or an import leaves `slug` out. Latin, Greek and
+//! Cyrillic letters are transliterated to ASCII ("Café São Paulo" becomes
+//! `cafe-sao-paulo`), and every run of other characters becomes a single
+//! hyphen, so the result always passes `validate_slug`. CJK characters have
+//! no useful romanization for a URL and are dropped along with emoji and
+//! punctuation; a title left with nothing gets a dated slug such as
+//! `post-2026-10-16`.
+
+use crate::validation::custom::MAX_SLUG_LENGTH;
+use chrono::NaiveDate;
+use deunicode::deunicode_char;
+
+/// URL-friendly slug for `title`, falling back to one dated `today`
+pub fn from_title(title: &str, today: NaiveDate) -> String {
+    let mut slug = String::with_capacity(title.len());
+    for c in title.chars() {
+        // "What's new" reads better as `whats-new` than `what-s-new`
+        if matches!(c, '\'' | '\u{2019}') {
+            continue;
+        }
+        let mut buf = [0; 4];
+        let ascii = if c.is_ascii() {
+            &*c.encode_utf8(&mut buf)
+        } else if c.is_alphanumeric() && !is_cjk(c) {
+            deunicode_char(c).unwrap_or(" ")
+        } else {
+            " "
+        };
+        for c in ascii.chars() {
+            if c.is_ascii_alphanumeric() {
+                slug.push(c.to_ascii_lowercase());
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+    }
+
+    let slug = truncate(slug.trim_end_matches('-'));
+    if slug.is_empty() {
+        format!("post-{}", today.format("%Y-%m-%d"))
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Cut to the slug length limit, on a hyphen when there is one to cut on
+fn truncate(slug: &str) -> &str {
+    if slug.len() <= MAX_SLUG_LENGTH {
+        return slug;
+    }
+    let cut = &slug[..MAX_SLUG_LENGTH];
+    match cut.rfind('-') {
+        Some(hyphen) if !slug[MAX_SLUG_LENGTH..].starts_with('-') => &cut[..hyphen],
+        _ => cut.trim_end_matches('-'),
+    }
+}
+
+/// Han ideographs, kana and hangul
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{11FF}'
+        | '\u{3040}'..='\u{30FF}'
+        | '\u{3130}'..='\u{318F}'
+        | '\u{31F0}'..='\u{31FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FF66}'..='\u{FF9F}'
+        | '\u{20000}'..='\u{2FA1F}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::custom::validate_slug;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 16).unwrap()
+    }
+
+    #[test]
+    fn test_ascii_titles() {
+        assert_eq!(from_title("Hello World", today()), "hello-world");
+        assert_eq!(
+            from_title("  Rust 2024: what's new?  ", today()),
+            "rust-2024-whats-new"
+        );
+    }
+
+    #[test]
+    fn test_accented_titles_are_transliterated() {
+        assert_eq!(from_title("Café São Paulo", today()), "cafe-sao-paulo");
+        assert_eq!(
+            from_title("Ärger über Straße", today()),
+            "arger-uber-strasse"
+        );
+        assert_eq!(from_title("Привет мир", today()), "privet-mir");
+    }
+
+    #[test]
+    fn test_cjk_titles_fall_back_to_a_dated_slug() {
+        assert_eq!(from_title("東京の夏", today()), "post-2026-10-16");
+        assert_eq!(from_title("안녕하세요", today()), "post-2026-10-16");
+        assert_eq!(from_title("東京 2026 guide", today()), "2026-guide");
+    }
+
+    #[test]
+    fn test_emoji_only_titles_fall_back_to_a_dated_slug() {
+        assert_eq!(from_title("🎉🚀", today()), "post-2026-10-16");
+        assert_eq!(from_title("Launch 🚀 day", today()), "launch-day");
+        assert_eq!(from_title("", today()), "post-2026-10-16");
+    }
+
+    #[test]
+    fn test_generated_slugs_are_valid() {
+        let long = "word ".repeat(40);
+        for title in [
+            "Café São Paulo",
+            "--a--b--",
+            "東京の夏",
+            "🎉",
+            long.as_str(),
+        ] {
+            let slug = from_title(title, today());
+            assert!(validate_slug(&slug).is_ok(), "{title:?} gave {slug:?}");
+        }
+        assert!(from_title(&long, today()).len() <= MAX_SLUG_LENGTH);
+    }
+}
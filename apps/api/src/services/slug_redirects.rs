@@ -0,0 +1,67 @@
+// src/services/slug_redirects.rs
+//! Redirects from a post's former slugs
+//!
+//! When `update_post` renames a post, its old slug is kept in
+//! `post_slug_redirects` and `GET /posts/{slug}` answers requests for it with
+//! a `301` to the current slug. Redirects point at the post rather than at
+//! the slug it was renamed to, so a post renamed twice sends both old slugs
+//! straight to its current one. A live post always wins over a redirect.
+
+use sqlx::{PgConnection, PgPool};
+
+/// Remember that `post_id` was at `old_slug` before it moved to `new_slug`
+pub async fn record(
+    conn: &mut PgConnection,
+    domain_id: i32,
+    post_id: i32,
+    old_slug: &str,
+    new_slug: &str,
+) -> Result<(), sqlx::Error> {
+    if old_slug == new_slug {
+        return Ok(());
+    }
+
+    // The new slug is live again, whichever post it used to redirect to
+    sqlx::query("DELETE FROM post_slug_redirects WHERE domain_id = $1 AND old_slug = $2")
+        .bind(domain_id)
+        .bind(new_slug)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO post_slug_redirects (domain_id, old_slug, post_id)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (domain_id, old_slug) DO UPDATE
+        SET post_id = EXCLUDED.post_id, created_at = NOW()
+        "#,
+    )
+    .bind(domain_id)
+    .bind(old_slug)
+    .bind(post_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Current slug of the published post that used to be at `slug`, if any
+pub async fn resolve(
+    db: &PgPool,
+    domain_id: i32,
+    slug: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT p.slug
+        FROM post_slug_redirects r
+        JOIN posts p ON p.id = r.post_id
+        WHERE r.domain_id = $1 AND r.old_slug = $2
+          AND p.domain_id = $1 AND p.status = 'published' AND p.slug <> $2
+        "#,
+    )
+    .bind(domain_id)
+    .bind(slug)
+    .fetch_optional(db)
+    .await
+}
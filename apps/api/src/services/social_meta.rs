@@ -0,0 +1,253 @@
+// src/services/social_meta.rs
+//! Open Graph and Twitter Card fields for sharing a post
+//!
+//! What the post sets wins, and its `meta_title` / `meta_description`
+//! overrides win over its title and excerpt. Anything else comes from the
+//! domain settings:
+//! `theme_config.seo_config` (`site_name`, `meta_description`, `social_image`)
+//! and `theme_config.social_config.twitter_handle`. Canonical URLs follow the
+//! domain's [`permalinks`] format. They and relative image paths are made
+//! absolute against the domain's hostname, since crawlers won't resolve them.
+
+use crate::DomainContext;
+use crate::services::permalinks::{self, PermalinkPost};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// The post fields sharing tags are built from
+pub struct PostMetaSource<'a> {
+    pub id: i32,
+    pub title: &'a str,
+    pub slug: &'a str,
+    pub category: &'a str,
+    pub author: &'a str,
+    pub excerpt: &'a str,
+    pub image_url: Option<&'a str>,
+    pub created_at: DateTime<Utc>,
+    pub meta_title: Option<&'a str>,
+    pub meta_description: Option<&'a str>,
+    pub noindex: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostMeta {
+    pub title: String,
+    pub description: String,
+    pub canonical_url: String,
+    pub image: Option<String>,
+    pub author: String,
+    pub site_name: String,
+    /// `robots` meta content, set for posts kept out of search engines
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub robots: Option<&'static str>,
+    /// `og:*` and `article:*` properties
+    pub open_graph: BTreeMap<&'static str, String>,
+    /// `twitter:*` names
+    pub twitter: BTreeMap<&'static str, String>,
+}
+
+/// A non-empty string setting under `theme_config.<group>.<key>`
+fn setting<'a>(domain: &'a DomainContext, group: &str, key: &str) -> Option<&'a str> {
+    domain
+        .theme_config
+        .get(group)
+        .and_then(|g| g.get(key))
+        .and_then(serde_json::Value::as_str)
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+fn absolute_url(domain: &DomainContext, url: &str) -> String {
+    if url.starts_with('/') {
+        format!("https://{}{}", domain.hostname, url)
+    } else {
+        url.to_string()
+    }
+}
+
+pub fn post_meta(domain: &DomainContext, post: &PostMetaSource) -> PostMeta {
+    let site_name = setting(domain, "seo_config", "site_name").unwrap_or(&domain.name);
+    let title = post
+        .meta_title
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .unwrap_or(post.title);
+    let description = post
+        .meta_description
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .or_else(|| Some(post.excerpt.trim()).filter(|e| !e.is_empty()))
+        .or_else(|| setting(domain, "seo_config", "meta_description"))
+        .unwrap_or_default();
+    let image = post
+        .image_url
+        .filter(|i| !i.is_empty())
+        .or_else(|| setting(domain, "seo_config", "social_image"))
+        .map(|i| absolute_url(domain, i));
+    let canonical_url = permalinks::url(
+        domain,
+        &PermalinkPost {
+            id: post.id,
+            slug: post.slug,
+            category: post.category,
+            created_at: post.created_at,
+        },
+    );
+
+    let mut open_graph = BTreeMap::from([
+        ("og:type", "article".to_string()),
+        ("og:title", title.to_string()),
+        ("og:description", description.to_string()),
+        ("og:url", canonical_url.clone()),
+        ("og:site_name", site_name.to_string()),
+        ("article:author", post.author.to_string()),
+        ("article:published_time", post.created_at.to_rfc3339()),
+    ]);
+    let card = if image.is_some() {
+        "summary_large_image"
+    } else {
+        "summary"
+    };
+    let mut twitter = BTreeMap::from([
+        ("twitter:card", card.to_string()),
+        ("twitter:title", title.to_string()),
+        ("twitter:description", description.to_string()),
+    ]);
+    if let Some(image) = &image {
+        open_graph.insert("og:image", image.clone());
+        twitter.insert("twitter:image", image.clone());
+    }
+    if let Some(handle) = setting(domain, "social_config", "twitter_handle") {
+        twitter.insert(
+            "twitter:site",
+            format!("@{}", handle.trim_start_matches('@')),
+        );
+    }
+
+    PostMeta {
+        title: title.to_string(),
+        description: description.to_string(),
+        canonical_url,
+        image,
+        author: post.author.to_string(),
+        site_name: site_name.to_string(),
+        robots: post.noindex.then_some("noindex"),
+        open_graph,
+        twitter,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn domain(theme_config: serde_json::Value) -> DomainContext {
+        DomainContext {
+            id: 1,
+            hostname: "example.com".to_string(),
+            name: "Example".to_string(),
+            theme_config,
+            categories: vec![],
+            features: json!({}),
+            timezone: "UTC".to_string(),
+            cors_origins: vec![],
+        }
+    }
+
+    fn post<'a>(excerpt: &'a str, image_url: Option<&'a str>) -> PostMetaSource<'a> {
+        PostMetaSource {
+            id: 1,
+            title: "Hello",
+            slug: "hello",
+            category: "News",
+            author: "Ann",
+            excerpt,
+            image_url,
+            created_at: Utc::now(),
+            meta_title: None,
+            meta_description: None,
+            noindex: false,
+        }
+    }
+
+    #[test]
+    fn test_post_fields_win_over_domain_defaults() {
+        let domain = domain(json!({
+            "seo_config": { "meta_description": "Default", "social_image": "/default.png" }
+        }));
+        let meta = post_meta(&domain, &post("Post summary", Some("/cover.png")));
+
+        assert_eq!(meta.description, "Post summary");
+        assert_eq!(meta.image.as_deref(), Some("https://example.com/cover.png"));
+        assert_eq!(meta.canonical_url, "https://example.com/posts/hello");
+        assert_eq!(meta.twitter["twitter:card"], "summary_large_image");
+    }
+
+    #[test]
+    fn test_meta_overrides_win_over_post_and_domain() {
+        let domain = domain(json!({ "seo_config": { "meta_description": "Default" } }));
+        let meta = post_meta(
+            &domain,
+            &PostMetaSource {
+                meta_title: Some("Search title"),
+                meta_description: Some("Search description"),
+                noindex: true,
+                ..post("Post summary", None)
+            },
+        );
+
+        assert_eq!(meta.title, "Search title");
+        assert_eq!(meta.description, "Search description");
+        assert_eq!(meta.open_graph["og:title"], "Search title");
+        assert_eq!(meta.twitter["twitter:description"], "Search description");
+        assert_eq!(meta.robots, Some("noindex"));
+
+        let meta = post_meta(&domain, &post("", None));
+        assert_eq!(meta.title, "Hello");
+        assert_eq!(meta.robots, None);
+    }
+
+    #[test]
+    fn test_falls_back_to_domain_seo_config() {
+        let domain = domain(json!({
+            "seo_config": {
+                "site_name": "Example Blog",
+                "meta_description": "Default",
+                "social_image": "https://cdn.example.com/og.png"
+            },
+            "social_config": { "twitter_handle": "example" }
+        }));
+        let meta = post_meta(&domain, &post("", None));
+
+        assert_eq!(meta.description, "Default");
+        assert_eq!(meta.site_name, "Example Blog");
+        assert_eq!(
+            meta.open_graph["og:image"],
+            "https://cdn.example.com/og.png"
+        );
+        assert_eq!(meta.twitter["twitter:site"], "@example");
+    }
+
+    #[test]
+    fn test_canonical_url_follows_permalink_format() {
+        let domain = domain(json!({
+            "seo_config": { "permalink_format": "/%category%/%id%" }
+        }));
+        let meta = post_meta(&domain, &post("", None));
+
+        assert_eq!(meta.canonical_url, "https://example.com/news/1");
+        assert_eq!(meta.open_graph["og:url"], meta.canonical_url);
+    }
+
+    #[test]
+    fn test_no_image_anywhere() {
+        let meta = post_meta(&domain(json!({})), &post("", None));
+
+        assert_eq!(meta.image, None);
+        assert_eq!(meta.site_name, "Example");
+        assert_eq!(meta.twitter["twitter:card"], "summary");
+        assert!(!meta.open_graph.contains_key("og:image"));
+    }
+}
@@ -0,0 +1,235 @@
+// src/services/subscribers.rs
+//! Newsletter subscribers
+//!
+//! Readers subscribe to a domain through `POST /subscribe` and are only
+//! counted once they follow the link in the confirmation email (double
+//! opt-in). The link carries a `<id>.<secret>` token, as password resets do;
+//! only a bcrypt hash of the secret is stored. Subscribing again while
+//! pending issues a fresh token and voids the earlier one; subscribing again
+//! once confirmed changes nothing.
+
+use super::clock::Clock;
+use super::credentials::{self, CredentialsError};
+use super::digest::EmailMessage;
+use super::email_templates::EmailTemplate;
+use bcrypt::{DEFAULT_COST, hash, verify};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+/// How long a confirmation link stays usable.
+/// Configurable via `SUBSCRIBER_CONFIRM_TTL_HOURS` (default 48).
+pub fn confirmation_ttl() -> Duration {
+    let hours = std::env::var("SUBSCRIBER_CONFIRM_TTL_HOURS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|h| *h > 0)
+        .unwrap_or(48);
+    Duration::hours(hours)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubscriberStatus {
+    Pending,
+    Confirmed,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Subscriber {
+    pub id: i32,
+    pub email: String,
+    /// `pending` until the confirmation link is followed, then `confirmed`
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub confirmation_sent_at: Option<DateTime<Utc>>,
+    pub confirmed_at: Option<DateTime<Utc>>,
+}
+
+const SUBSCRIBER_COLUMNS: &str = r#"
+    id, email,
+    CASE WHEN confirmed_at IS NULL THEN 'pending' ELSE 'confirmed' END AS status,
+    created_at, confirmation_sent_at, confirmed_at
+"#;
+
+/// What `subscribe` did
+pub enum Subscription {
+    /// The email was already confirmed; there is nothing to send
+    AlreadyConfirmed,
+    /// The email is pending; `token` goes out in the confirmation email
+    Pending {
+        token: String,
+        expires_at: DateTime<Utc>,
+    },
+}
+
+/// Subscribe `email` to `domain_id`, or re-issue its confirmation token
+/// while it is still pending. The token is dated by `clock`.
+pub async fn subscribe(
+    db: &PgPool,
+    clock: &dyn Clock,
+    domain_id: i32,
+    email: &str,
+) -> Result<Subscription, CredentialsError> {
+    let secret = credentials::generate_secret();
+    let secret_hash = hash(&secret, DEFAULT_COST).map_err(|_| CredentialsError::Hashing)?;
+    let now = clock.now();
+
+    let id: Option<i32> = sqlx::query_scalar(
+        r#"
+        INSERT INTO subscribers (domain_id, email, token_hash, confirmation_sent_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (domain_id, lower(email)) DO UPDATE
+        SET token_hash = EXCLUDED.token_hash, confirmation_sent_at = EXCLUDED.confirmation_sent_at
+        WHERE subscribers.confirmed_at IS NULL
+        RETURNING id
+        "#,
+    )
+    .bind(domain_id)
+    .bind(email)
+    .bind(&secret_hash)
+    .bind(now)
+    .fetch_optional(db)
+    .await?;
+
+    Ok(match id {
+        Some(id) => Subscription::Pending {
+            token: format!("{id}.{secret}"),
+            expires_at: now + confirmation_ttl(),
+        },
+        None => Subscription::AlreadyConfirmed,
+    })
+}
+
+/// Confirm the subscription `token` was issued for. Following the link again
+/// after confirming returns the subscriber unchanged; `None` when the token
+/// is wrong, voided by a newer one, expired by `clock`, or for another domain.
+pub async fn confirm(
+    db: &PgPool,
+    clock: &dyn Clock,
+    domain_id: i32,
+    token: &str,
+) -> Result<Option<Subscriber>, sqlx::Error> {
+    let Some((id, secret)) = credentials::parse_token(token) else {
+        return Ok(None);
+    };
+    let now = clock.now();
+
+    let token_hash: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT token_hash FROM subscribers
+        WHERE id = $1 AND domain_id = $2 AND token_hash IS NOT NULL
+        AND (confirmed_at IS NOT NULL OR confirmation_sent_at > $3)
+        "#,
+    )
+    .bind(id)
+    .bind(domain_id)
+    .bind(now - confirmation_ttl())
+    .fetch_optional(db)
+    .await?;
+    if !token_hash.is_some_and(|token_hash| verify(secret, &token_hash).unwrap_or(false)) {
+        return Ok(None);
+    }
+
+    sqlx::query_as::<_, Subscriber>(&format!(
+        r#"
+        UPDATE subscribers SET confirmed_at = COALESCE(confirmed_at, $2)
+        WHERE id = $1
+        RETURNING {SUBSCRIBER_COLUMNS}
+        "#
+    ))
+    .bind(id)
+    .bind(now)
+    .fetch_optional(db)
+    .await
+}
+
+/// One page of the domain's subscribers, newest first, with the total
+pub async fn list(
+    db: &PgPool,
+    domain_id: i32,
+    status: Option<SubscriberStatus>,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<Subscriber>, i64), sqlx::Error> {
+    let confirmed = status.map(|status| status == SubscriberStatus::Confirmed);
+    let filter = "domain_id = $1 AND ($2::boolean IS NULL OR (confirmed_at IS NOT NULL) = $2)";
+
+    let subscribers = sqlx::query_as::<_, Subscriber>(&format!(
+        r#"
+        SELECT {SUBSCRIBER_COLUMNS}
+        FROM subscribers
+        WHERE {filter}
+        ORDER BY created_at DESC, id DESC
+        LIMIT $3 OFFSET $4
+        "#
+    ))
+    .bind(domain_id)
+    .bind(confirmed)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(db)
+    .await?;
+
+    let total: i64 =
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM subscribers WHERE {filter}"))
+            .bind(domain_id)
+            .bind(confirmed)
+            .fetch_one(db)
+            .await?;
+
+    Ok((subscribers, total))
+}
+
+/// The email asking `to` to confirm their subscription to `blog`, rendered
+/// from the domain's `subscription_confirmation` template
+pub fn confirmation_email(
+    template: &EmailTemplate,
+    to: &str,
+    blog: &str,
+    hostname: &str,
+    token: &str,
+    expires_at: DateTime<Utc>,
+) -> EmailMessage {
+    let confirm_url = format!("https://{hostname}/subscribe/confirm?token={token}");
+    let expires_at = expires_at.format("%Y-%m-%d %H:%M UTC").to_string();
+    template.render(
+        to,
+        &[
+            ("email", to),
+            ("blog", blog),
+            ("hostname", hostname),
+            ("token", token),
+            ("confirm_url", &confirm_url),
+            ("expires_at", &expires_at),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::email_templates::EmailKind;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_confirmation_email_links_to_the_domain() {
+        let expires_at = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let email = confirmation_email(
+            &EmailKind::SubscriptionConfirmation.default_template(),
+            "reader@example.com",
+            "Test Blog",
+            "testblog.com",
+            "7.secret",
+            expires_at,
+        );
+        assert_eq!(email.to, "reader@example.com");
+        assert_eq!(email.subject, "Confirm your subscription to Test Blog");
+        assert!(
+            email
+                .body
+                .contains("https://testblog.com/subscribe/confirm?token=7.secret")
+        );
+        assert!(email.body.contains("2024-03-01 12:00 UTC"));
+    }
+}
@@ -0,0 +1,300 @@
+// src/services/theme.rs
+//! Domain themes: inheritance from the platform default, and rendering as a
+//! CSS custom-properties stylesheet
+//!
+//! A domain's `theme_config` is layered over the platform default theme
+//! (`DEFAULT_THEME`), which is layered over the built-in defaults below, so a
+//! domain that sets one color inherits everything else. Objects merge key by
+//! key; any other value replaces the one beneath it. The settings kept in
+//! `theme_config` (`seo_config`, `analytics_config`, ...) are not part of the
+//! theme and are neither inherited nor returned.
+//!
+//! Recognised keys, either at the top level or under `colors` / `fonts`:
+//!
+//! - `primary` → `--color-primary` (default `#2563eb`)
+//! - `secondary` → `--color-secondary` (default `#7c3aed`)
+//! - `accent` → `--color-accent` (default `#3b82f6`)
+//! - `background` → `--color-background` (default `#ffffff`)
+//! - `text` → `--color-text` (default `#111827`)
+//! - `font_body` / `fonts.body` → `--font-body` (default: system UI font stack)
+//! - `font_heading` / `fonts.heading` → `--font-heading` (default: the body font)
+//!
+//! In the stylesheet, values that aren't a plain CSS color or font list (such
+//! as Tailwind class strings) fall back to the next layer down, which also
+//! keeps arbitrary JSON from injecting CSS.
+
+use serde_json::{Map, Value, json};
+
+const DEFAULT_FONT: &str =
+    "system-ui, -apple-system, \"Segoe UI\", Roboto, \"Helvetica Neue\", Arial, sans-serif";
+
+/// (config key, CSS variable, default)
+const COLORS: &[(&str, &str, &str)] = &[
+    ("primary", "--color-primary", "#2563eb"),
+    ("secondary", "--color-secondary", "#7c3aed"),
+    ("accent", "--color-accent", "#3b82f6"),
+    ("background", "--color-background", "#ffffff"),
+    ("text", "--color-text", "#111827"),
+];
+
+/// Domain settings stored in `theme_config` that aren't theme values
+const SETTINGS_KEYS: &[&str] = &[
+    "seo_config",
+    "analytics_config",
+    "content_config",
+    "social_config",
+];
+
+/// Theme every domain inherits from, below its own `theme_config`.
+/// Configurable via `DEFAULT_THEME`, a JSON object such as
+/// `{"colors": {"primary": "#0f766e"}}` (default `{}`: the built-in theme).
+pub fn platform_default() -> Value {
+    let Ok(raw) = std::env::var("DEFAULT_THEME") else {
+        return json!({});
+    };
+    match serde_json::from_str::<Value>(&raw) {
+        Ok(theme) if theme.is_object() => theme,
+        _ => {
+            tracing::warn!("Ignoring DEFAULT_THEME, not a JSON object");
+            json!({})
+        }
+    }
+}
+
+fn built_in() -> Value {
+    let colors: Map<String, Value> = COLORS
+        .iter()
+        .map(|(key, _, default)| (key.to_string(), json!(default)))
+        .collect();
+    json!({ "colors": colors, "fonts": { "body": DEFAULT_FONT } })
+}
+
+/// `config` with its theme keys in the nested `colors` / `fonts` form and the
+/// domain settings left out, so layers written either way merge key by key
+fn normalize(config: &Value) -> Map<String, Value> {
+    let mut theme = config.as_object().cloned().unwrap_or_default();
+    theme.retain(|key, _| !SETTINGS_KEYS.contains(&key.as_str()));
+
+    let mut colors = take_object(&mut theme, "colors");
+    for (key, _, _) in COLORS {
+        if let Some(value) = theme.remove(*key) {
+            // The nested value wins, as it does in the stylesheet
+            colors.entry(key.to_string()).or_insert(value);
+        }
+    }
+
+    let mut fonts = take_object(&mut theme, "fonts");
+    for (flat, nested) in [("font_body", "body"), ("font_heading", "heading")] {
+        let value = fonts.remove(flat).or_else(|| theme.remove(flat));
+        if let Some(value) = value {
+            fonts.insert(nested.to_string(), value);
+        }
+    }
+
+    if !colors.is_empty() {
+        theme.insert("colors".to_string(), Value::Object(colors));
+    }
+    if !fonts.is_empty() {
+        theme.insert("fonts".to_string(), Value::Object(fonts));
+    }
+    theme
+}
+
+fn take_object(theme: &mut Map<String, Value>, key: &str) -> Map<String, Value> {
+    match theme.remove(key) {
+        Some(Value::Object(object)) => object,
+        Some(other) => {
+            theme.insert(key.to_string(), other);
+            Map::new()
+        }
+        None => Map::new(),
+    }
+}
+
+/// Merge `overlay` into `base`: objects key by key, anything else replaced
+fn merge(base: &mut Map<String, Value>, overlay: Map<String, Value>) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Object(existing)), Value::Object(nested)) => merge(existing, nested),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// The full theme a domain renders with: the built-in defaults, then the
+/// platform `default`, then the domain's own `theme_config`
+pub fn resolve(default: &Value, domain: &Value) -> Value {
+    let mut theme = normalize(&built_in());
+    merge(&mut theme, normalize(default));
+    merge(&mut theme, normalize(domain));
+
+    // Headings use the body font unless some layer picks one
+    if let Some(Value::Object(fonts)) = theme.get_mut("fonts") {
+        let body = fonts.get("body").cloned().unwrap_or(json!(DEFAULT_FONT));
+        fonts.entry("heading").or_insert(body);
+    }
+    Value::Object(theme)
+}
+
+fn lookup<'a>(config: &'a Value, group: &str, key: &str) -> Option<&'a str> {
+    config
+        .get(group)
+        .and_then(|g| g.get(key))
+        .or_else(|| config.get(key))
+        .and_then(Value::as_str)
+        .map(str::trim)
+}
+
+/// Hex, `rgb()`/`rgba()`/`hsl()`/`hsla()` or a named color
+fn is_css_color(value: &str) -> bool {
+    if let Some(hex) = value.strip_prefix('#') {
+        return matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+
+    let functional = ["rgb(", "rgba(", "hsl(", "hsla("]
+        .iter()
+        .find_map(|prefix| value.strip_prefix(prefix))
+        .and_then(|rest| rest.strip_suffix(')'));
+    if let Some(args) = functional {
+        return args
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || " .,%/-".contains(c));
+    }
+
+    !value.is_empty() && value.len() <= 30 && value.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Comma-separated font families, optionally quoted
+fn is_font_list(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 200
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || " ,-_\"'".contains(c))
+}
+
+/// Build the stylesheet for a domain's theme config
+pub fn render_theme_css(config: &Value) -> String {
+    render_layered_css(&[config])
+}
+
+/// Build the stylesheet from theme layers, most specific first; each value
+/// comes from the first layer where it is usable
+pub fn render_layered_css(layers: &[&Value]) -> String {
+    let mut css = String::from(":root {\n");
+
+    for (key, variable, default) in COLORS {
+        let value = first_usable(layers, |layer| {
+            lookup(layer, "colors", key).filter(|v| is_css_color(v))
+        })
+        .unwrap_or(*default);
+        css.push_str(&format!("  {variable}: {value};\n"));
+    }
+
+    let font = |key: &'static str, alias: &'static str| {
+        first_usable(layers, move |layer| {
+            lookup(layer, "fonts", key)
+                .or_else(|| lookup(layer, "fonts", alias))
+                .filter(|v| is_font_list(v))
+        })
+    };
+    let body_font = font("font_body", "body").unwrap_or(DEFAULT_FONT);
+    let heading_font = font("font_heading", "heading").unwrap_or(body_font);
+    css.push_str(&format!("  --font-body: {body_font};\n"));
+    css.push_str(&format!("  --font-heading: {heading_font};\n"));
+
+    css.push_str("}\n");
+    css
+}
+
+fn first_usable<'a>(
+    layers: &[&'a Value],
+    find: impl Fn(&'a Value) -> Option<&'a str>,
+) -> Option<&'a str> {
+    layers.iter().copied().find_map(find)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_for_empty_config() {
+        let css = render_theme_css(&json!({}));
+        assert!(css.contains("--color-primary: #2563eb;"));
+        assert!(css.contains("--color-text: #111827;"));
+        assert!(css.contains(&format!("--font-heading: {DEFAULT_FONT};")));
+    }
+
+    #[test]
+    fn test_custom_values_flat_and_nested() {
+        let css = render_theme_css(&json!({
+            "accent": "#ec4899",
+            "colors": { "background": "rgb(250, 250, 250)" },
+            "fonts": { "body": "Inter, sans-serif", "heading": "\"Playfair Display\", serif" }
+        }));
+        assert!(css.contains("--color-accent: #ec4899;"));
+        assert!(css.contains("--color-background: rgb(250, 250, 250);"));
+        assert!(css.contains("--font-body: Inter, sans-serif;"));
+        assert!(css.contains("--font-heading: \"Playfair Display\", serif;"));
+    }
+
+    #[test]
+    fn test_domain_override_inherits_the_rest_from_default() {
+        let platform = json!({
+            "colors": { "primary": "#0f766e", "accent": "#f59e0b" },
+            "fonts": { "heading": "Georgia, serif" },
+            "logo": "/media/platform-logo.svg"
+        });
+        // Flat keys, as the seed data writes them
+        let domain = json!({ "accent": "#ec4899", "seo_config": { "meta_description": "x" } });
+
+        let theme = resolve(&platform, &domain);
+        assert_eq!(theme["colors"]["accent"], "#ec4899");
+        assert_eq!(theme["colors"]["primary"], "#0f766e");
+        assert_eq!(theme["colors"]["background"], "#ffffff");
+        assert_eq!(theme["fonts"]["body"], DEFAULT_FONT);
+        assert_eq!(theme["fonts"]["heading"], "Georgia, serif");
+        assert_eq!(theme["logo"], "/media/platform-logo.svg");
+        assert!(theme.get("accent").is_none());
+        assert!(theme.get("seo_config").is_none());
+
+        let css = render_layered_css(&[&domain, &platform]);
+        assert!(css.contains("--color-accent: #ec4899;"));
+        assert!(css.contains("--color-primary: #0f766e;"));
+        assert!(css.contains("--font-heading: Georgia, serif;"));
+    }
+
+    #[test]
+    fn test_heading_follows_resolved_body_font() {
+        let theme = resolve(&json!({}), &json!({ "font_body": "Inter, sans-serif" }));
+        assert_eq!(theme["fonts"]["body"], "Inter, sans-serif");
+        assert_eq!(theme["fonts"]["heading"], "Inter, sans-serif");
+        assert_eq!(
+            resolve(&json!({}), &json!({})),
+            resolve(&json!({}), &json!(null))
+        );
+    }
+
+    #[test]
+    fn test_unusable_values_fall_back() {
+        let css = render_theme_css(&json!({
+            // Tailwind classes from the seed data aren't CSS colors
+            "primary": "from-blue-600 to-purple-600",
+            "accent": "red; } body { display: none",
+            "font_body": "x</style>"
+        }));
+        assert!(css.contains("--color-primary: #2563eb;"));
+        assert!(css.contains("--color-accent: #3b82f6;"));
+        assert!(css.contains(&format!("--font-body: {DEFAULT_FONT};")));
+
+        // ... to the platform default before the built-in one
+        let css = render_layered_css(&[
+            &json!({ "primary": "from-blue-600 to-purple-600" }),
+            &json!({ "primary": "#0f766e" }),
+        ]);
+        assert!(css.contains("--color-primary: #0f766e;"));
+    }
+}
@@ -0,0 +1,62 @@
+// src/services/timezones.rs
+//! Time zones for analytics buckets
+//!
+//! Each domain has an IANA `timezone` (default `UTC`) that analytics use to
+//! split events into days and hours, so a New York blog's day ends at its own
+//! midnight. Reports can override it with `?tz=`. Names are checked against
+//! Postgres' `pg_timezone_names`, since that is where `AT TIME ZONE` looks
+//! them up.
+
+use sqlx::PgPool;
+
+pub const DEFAULT_TIMEZONE: &str = "UTC";
+
+pub fn default_timezone() -> String {
+    DEFAULT_TIMEZONE.to_string()
+}
+
+/// Whether days in `tz` line up with UTC days, so the UTC rollup can serve them
+pub fn is_utc(tz: &str) -> bool {
+    matches!(
+        tz.to_ascii_uppercase().as_str(),
+        "UTC" | "ETC/UTC" | "UCT" | "ETC/UCT" | "ZULU" | "ETC/ZULU" | "GMT" | "ETC/GMT"
+    )
+}
+
+/// Whether Postgres knows the zone name
+pub async fn is_known(db: &PgPool, tz: &str) -> Result<bool, sqlx::Error> {
+    if tz.is_empty() || tz.len() > 64 {
+        return Ok(false);
+    }
+    sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)")
+        .bind(tz)
+        .fetch_one(db)
+        .await
+}
+
+/// The zone every domain in `domain_ids` uses, or UTC when they differ
+pub async fn shared_by(db: &PgPool, domain_ids: &[i32]) -> Result<String, sqlx::Error> {
+    let zones: Vec<String> =
+        sqlx::query_scalar("SELECT DISTINCT timezone FROM domains WHERE id = ANY($1)")
+            .bind(domain_ids)
+            .fetch_all(db)
+            .await?;
+
+    Ok(match zones.as_slice() {
+        [tz] => tz.clone(),
+        _ => default_timezone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_utc() {
+        assert!(is_utc("UTC"));
+        assert!(is_utc("Etc/UTC"));
+        assert!(!is_utc("America/New_York"));
+        assert!(!is_utc("Europe/London"));
+    }
+}
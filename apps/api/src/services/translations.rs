@@ -0,0 +1,128 @@
+// src/services/translations.rs
+//! Translated variants of posts
+//!
+//! A post's own title and content are its default language; each row in
+//! `post_translations` overrides them for one locale. Locales are BCP 47 tags
+//! stored lowercase, and a request for a regional variant falls back to the
+//! bare language (`pt-br` → `pt`) before the default.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+
+/// Longest locale tag accepted, matching the column width
+pub const MAX_LOCALE_LEN: usize = 35;
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Translation {
+    pub post_id: i32,
+    pub locale: String,
+    pub title: String,
+    pub content: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Canonical form of a locale tag (`pt_BR` → `pt-br`), or `None` if it isn't
+/// a language subtag of 2-3 letters followed by 1-8 character subtags
+pub fn normalize_locale(locale: &str) -> Option<String> {
+    let locale = locale.trim().replace('_', "-").to_ascii_lowercase();
+    if locale.len() > MAX_LOCALE_LEN {
+        return None;
+    }
+
+    let mut subtags = locale.split('-');
+    let language = subtags.next()?;
+    let language_ok =
+        (2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_lowercase());
+    let rest_ok = subtags.all(|subtag| {
+        (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+    });
+
+    (language_ok && rest_ok).then_some(locale)
+}
+
+/// Locales to try for a normalized `locale`, most specific first
+pub fn fallback_chain(locale: &str) -> Vec<String> {
+    let subtags: Vec<&str> = locale.split('-').collect();
+    (1..=subtags.len())
+        .rev()
+        .map(|n| subtags[..n].join("-"))
+        .collect()
+}
+
+/// Best translation of a post for `locale`, if any; `None` means the
+/// default language should be served
+pub async fn find(
+    db: &PgPool,
+    post_id: i32,
+    locale: &str,
+) -> Result<Option<Translation>, sqlx::Error> {
+    let Some(locale) = normalize_locale(locale) else {
+        return Ok(None);
+    };
+    let candidates = fallback_chain(&locale);
+
+    sqlx::query_as(
+        r#"
+        SELECT post_id, locale, title, content, updated_at
+        FROM post_translations
+        WHERE post_id = $1 AND locale = ANY($2)
+        ORDER BY array_position($2, locale::text)
+        LIMIT 1
+        "#,
+    )
+    .bind(post_id)
+    .bind(&candidates)
+    .fetch_optional(db)
+    .await
+}
+
+/// Translated locales of each post, as (post_id, locale) pairs
+pub async fn locales_for_posts(
+    db: &PgPool,
+    post_ids: &[i32],
+) -> Result<Vec<(i32, String)>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT post_id, locale FROM post_translations WHERE post_id = ANY($1) ORDER BY post_id, locale",
+    )
+    .bind(post_ids)
+    .fetch_all(db)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_locale() {
+        assert_eq!(normalize_locale("fr").as_deref(), Some("fr"));
+        assert_eq!(normalize_locale(" pt_BR ").as_deref(), Some("pt-br"));
+        assert_eq!(
+            normalize_locale("zh-Hant-TW").as_deref(),
+            Some("zh-hant-tw")
+        );
+
+        for invalid in [
+            "",
+            "f",
+            "french",
+            "fr-",
+            "fr--ca",
+            "fr-abcdefghi",
+            "1a",
+            "en-<b>",
+        ] {
+            assert_eq!(normalize_locale(invalid), None, "accepted {invalid:?}");
+        }
+    }
+
+    #[test]
+    fn test_fallback_chain() {
+        assert_eq!(fallback_chain("fr"), ["fr"]);
+        assert_eq!(
+            fallback_chain("zh-hant-tw"),
+            ["zh-hant-tw", "zh-hant", "zh"]
+        );
+    }
+}
@@ -0,0 +1,35 @@
+// src/services/view_counts.rs
+//! Denormalized post view counts
+//!
+//! `posts.view_count` is bumped in the same statement that records a
+//! `post_view` event, so listings can sort by popularity without counting
+//! `analytics_events`. `resync` recomputes the counters from the events table
+//! for when they drift (manual edits, failed deploys, restored backups).
+//! Views whose events were pruned by retention are no longer counted after a
+//! resync.
+
+use sqlx::PgPool;
+
+/// Recount `post_view` events for every post in `domain_id` and fix counters
+/// that disagree. Returns how many posts were corrected.
+pub async fn resync(db: &PgPool, domain_id: i32) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE posts p SET view_count = counted.views
+        FROM (
+            SELECT p2.id, COUNT(ae.id) AS views
+            FROM posts p2
+            LEFT JOIN analytics_events ae
+                ON ae.post_id = p2.id AND ae.event_type = 'post_view'
+            WHERE p2.domain_id = $1
+            GROUP BY p2.id
+        ) counted
+        WHERE p.id = counted.id AND p.view_count <> counted.views
+        "#,
+    )
+    .bind(domain_id)
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -1,4 +1,8 @@
-use metrics_exporter_prometheus::PrometheusBuilder;
+use axum::{
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 use opentelemetry::KeyValue;
 use opentelemetry_otlp::WithExportConfig;
 use opentelemetry_sdk::{
@@ -6,16 +10,21 @@ use opentelemetry_sdk::{
     trace::{self, RandomIdGenerator, Sampler},
 };
 use std::env;
-use tracing::info;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use tracing::{Subscriber, info};
 use tracing_subscriber::{
     EnvFilter, Layer, Registry,
-    fmt::{self, format::FmtSpan},
+    filter::ParseError,
+    fmt::{self, MakeWriter, format::FmtSpan},
     layer::SubscriberExt,
+    registry::LookupSpan,
     util::SubscriberInitExt,
 };
 
 #[derive(Debug, Clone)]
 pub struct TelemetryConfig {
+    /// `RUST_LOG`-style filter directive, e.g. `info,sqlx=warn`
     pub log_level: String,
     pub log_format: LogFormat,
     pub enable_opentelemetry: bool,
@@ -25,22 +34,41 @@ pub struct TelemetryConfig {
     pub environment: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum LogFormat {
+    /// Multi-line, human-readable output for local development
+    #[default]
     Pretty,
+    /// One JSON object per line, for log ingestion
     Json,
     Compact,
 }
 
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            "compact" => Ok(LogFormat::Compact),
+            other => Err(format!("unknown log format: {other}")),
+        }
+    }
+}
+
 impl Default for TelemetryConfig {
     fn default() -> Self {
         Self {
-            log_level: env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string()),
-            log_format: match env::var("LOG_FORMAT").as_deref() {
-                Ok("json") => LogFormat::Json,
-                Ok("compact") => LogFormat::Compact,
-                _ => LogFormat::Pretty,
-            },
+            // LOG_FILTER takes precedence so the API's filter can differ from
+            // other tools sharing RUST_LOG
+            log_level: env::var("LOG_FILTER")
+                .or_else(|_| env::var("RUST_LOG"))
+                .unwrap_or_else(|_| "info".to_string()),
+            log_format: env::var("LOG_FORMAT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or_default(),
             enable_opentelemetry: env::var("ENABLE_OPENTELEMETRY")
                 .map(|v| v.parse().unwrap_or(true))
                 .unwrap_or(true),
@@ -54,21 +82,21 @@ impl Default for TelemetryConfig {
     }
 }
 
-/// Initialize telemetry for the application
-pub fn init_telemetry(
-    config: TelemetryConfig,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    // Create env filter
-    let env_filter =
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.log_level));
-
-    // Create base registry
-    let registry = Registry::default().with(env_filter);
+/// Level filter built from the configured directive
+pub fn env_filter(config: &TelemetryConfig) -> Result<EnvFilter, ParseError> {
+    EnvFilter::try_new(&config.log_level)
+}
 
-    // Add console/file logging layer
-    let fmt_layer = match config.log_format {
+/// Console layer for `format`, writing to `writer`
+pub fn fmt_layer<S, W>(format: LogFormat, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
         LogFormat::Pretty => fmt::layer()
             .pretty()
+            .with_writer(writer)
             .with_span_events(FmtSpan::CLOSE)
             .with_target(true)
             .with_thread_ids(true)
@@ -76,6 +104,7 @@ pub fn init_telemetry(
             .boxed(),
         LogFormat::Json => fmt::layer()
             .json()
+            .with_writer(writer)
             .with_span_events(FmtSpan::CLOSE)
             .with_target(true)
             .with_thread_ids(true)
@@ -83,11 +112,22 @@ pub fn init_telemetry(
             .boxed(),
         LogFormat::Compact => fmt::layer()
             .compact()
+            .with_writer(writer)
             .with_span_events(FmtSpan::CLOSE)
             .with_target(true)
             .boxed(),
-    };
+    }
+}
+
+/// Initialize telemetry for the application
+pub fn init_telemetry(
+    config: TelemetryConfig,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Create base registry
+    let registry = Registry::default().with(env_filter(&config)?);
 
+    // Add console logging layer
+    let fmt_layer = fmt_layer(config.log_format, std::io::stdout);
     let registry = registry.with(fmt_layer);
 
     // Conditionally add OpenTelemetry layer
@@ -155,27 +195,95 @@ fn init_opentelemetry_tracer(
     Ok(tracer)
 }
 
-fn init_metrics(config: &TelemetryConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let builder = PrometheusBuilder::new().with_http_listener(([0, 0, 0, 0], 9001)); // Serve metrics on port 9001
+/// Recorder handle rendering metrics for `/metrics`, set when the exporter
+/// runs without its own listener
+static PROMETHEUS_HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Bearer token Prometheus must send to scrape `/metrics`.
+/// Configurable via `METRICS_TOKEN` (default unset, leaving `/metrics` open).
+pub fn metrics_token() -> Option<String> {
+    env::var("METRICS_TOKEN")
+        .ok()
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+}
 
+fn init_metrics(config: &TelemetryConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Add custom labels
-    let builder = builder
+    let builder = PrometheusBuilder::new()
         .add_global_label("service_name", &config.service_name)
         .add_global_label("service_version", &config.service_version)
         .add_global_label("environment", &config.environment);
 
-    builder.install()?;
-
-    info!("Metrics exporter initialized on port 9001");
+    // The port 9001 listener can't check a token, so with one configured the
+    // metrics are only served through the protected `/metrics` route
+    if metrics_token().is_some() {
+        let handle = builder.install_recorder()?;
+        let _ = PROMETHEUS_HANDLE.set(handle);
+        info!("Metrics exporter initialized behind METRICS_TOKEN on /metrics");
+    } else {
+        builder
+            .with_http_listener(([0, 0, 0, 0], 9001)) // Serve metrics on port 9001
+            .install()?;
+        info!("Metrics exporter initialized on port 9001");
+    }
 
     Ok(())
 }
 
 /// Get current metrics in Prometheus format
 pub fn get_metrics() -> String {
-    // Return a simple redirect message since the actual metrics
-    // are served by the dedicated metrics server on port 9001
-    "# Metrics are served by the dedicated metrics server on port 9001\n# Please configure Prometheus to scrape from localhost:9001/metrics\n".to_string()
+    match PROMETHEUS_HANDLE.get() {
+        Some(handle) => handle.render(),
+        // Return a simple redirect message since the actual metrics
+        // are served by the dedicated metrics server on port 9001
+        None => "# Metrics are served by the dedicated metrics server on port 9001\n# Please configure Prometheus to scrape from localhost:9001/metrics\n".to_string(),
+    }
+}
+
+/// Whether a scrape carrying `headers` may read metrics guarded by `token`
+fn scrape_authorized(token: Option<&str>, headers: &HeaderMap) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+    let presented = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    // Compare every byte so the time taken doesn't reveal a matching prefix
+    presented.len() == token.len()
+        && presented
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// `GET /metrics` in Prometheus text format. With `METRICS_TOKEN` set,
+/// scrapes without `Authorization: Bearer <token>` get `401`.
+pub async fn metrics_handler(headers: HeaderMap) -> Response {
+    if !scrape_authorized(metrics_token().as_deref(), &headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"))],
+            "# Unauthorized\n",
+        )
+            .into_response();
+    }
+
+    let body = match env::var("ENABLE_METRICS") {
+        Ok(_) => get_metrics(),
+        _ => "# Metrics collection disabled\n".to_string(),
+    };
+    (
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"),
+        )],
+        body,
+    )
+        .into_response()
 }
 
 fn get_fallback_metrics() -> String {
@@ -266,3 +374,96 @@ pub fn record_auth_metrics(_action: &str, success: bool) {
         metrics::increment_counter!("auth_failures_total");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct CaptureWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CaptureWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'w> MakeWriter<'w> for CaptureWriter {
+        type Writer = CaptureWriter;
+
+        fn make_writer(&'w self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    fn config(log_level: &str, log_format: LogFormat) -> TelemetryConfig {
+        TelemetryConfig {
+            log_level: log_level.to_string(),
+            log_format,
+            enable_opentelemetry: false,
+            enable_metrics: false,
+            service_name: "test".to_string(),
+            service_version: "0.0.0".to_string(),
+            environment: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_log_format_parsing() {
+        assert_eq!("json".parse(), Ok(LogFormat::Json));
+        assert_eq!(" Compact ".parse(), Ok(LogFormat::Compact));
+        assert_eq!("pretty".parse(), Ok(LogFormat::Pretty));
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn test_json_format_emits_json_lines() {
+        let config = config("info,noisy=error", LogFormat::Json);
+        let writer = CaptureWriter::default();
+        let subscriber = Registry::default()
+            .with(env_filter(&config).unwrap())
+            .with(fmt_layer(config.log_format, writer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(post_id = 7, "post published");
+            tracing::debug!("below the level filter");
+            tracing::warn!(target: "noisy", "filtered by directive");
+        });
+
+        let output = String::from_utf8(writer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("each line is JSON"))
+            .collect();
+
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["fields"]["message"], "post published");
+        assert_eq!(lines[0]["fields"]["post_id"], 7);
+    }
+
+    #[test]
+    fn test_invalid_directive_is_rejected() {
+        assert!(env_filter(&config("info,sqlx=loud", LogFormat::Pretty)).is_err());
+    }
+
+    #[test]
+    fn test_scrape_authorization() {
+        let bearer = |value: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::AUTHORIZATION, HeaderValue::from_static(value));
+            headers
+        };
+        assert!(scrape_authorized(None, &HeaderMap::new()));
+        assert!(scrape_authorized(Some("s3cret"), &bearer("Bearer s3cret")));
+        assert!(!scrape_authorized(Some("s3cret"), &HeaderMap::new()));
+        assert!(!scrape_authorized(Some("s3cret"), &bearer("Bearer s3cre")));
+        assert!(!scrape_authorized(Some("s3cret"), &bearer("Basic s3cret")));
+    }
+}
@@ -2,7 +2,50 @@
 //! Custom validation implementations for complex structures
 
 use crate::validation::rules::*;
-use validator::{ValidationError, ValidationErrors};
+use validator::{ValidateEmail, ValidationError, ValidationErrors};
+
+/// Longest slug accepted for posts
+pub const MAX_SLUG_LENGTH: usize = 120;
+
+fn slug_error(code: &'static str, message: &'static str) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(message.into());
+    error
+}
+
+/// Validate a user-supplied post slug: 1-120 lowercase letters, digits and
+/// hyphens, with no leading, trailing or consecutive hyphens
+pub fn validate_slug(slug: &str) -> Result<(), ValidationError> {
+    if slug.is_empty() {
+        return Err(slug_error("slug_empty", "Slug cannot be empty"));
+    }
+
+    if slug.len() > MAX_SLUG_LENGTH {
+        return Err(slug_error(
+            "slug_length",
+            "Slug must be at most 120 characters",
+        ));
+    }
+
+    if !slug
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        return Err(slug_error(
+            "slug_characters",
+            "Slug can only contain lowercase letters, numbers, and hyphens",
+        ));
+    }
+
+    if slug.starts_with('-') || slug.ends_with('-') || slug.contains("--") {
+        return Err(slug_error(
+            "slug_hyphens",
+            "Slug cannot start or end with a hyphen or contain consecutive hyphens",
+        ));
+    }
+
+    Ok(())
+}
 
 /// Manual validation implementation for CreatePostRequest
 pub fn validate_create_post_request(
@@ -11,6 +54,8 @@ pub fn validate_create_post_request(
     category: &str,
     slug: &Option<String>,
     status: &Option<String>,
+    excerpt: &Option<String>,
+    image_url: &Option<String>,
 ) -> Result<(), ValidationErrors> {
     let mut errors = ValidationErrors::new();
 
@@ -45,6 +90,20 @@ pub fn validate_create_post_request(
         }
     }
 
+    // Validate excerpt if provided
+    if let Some(excerpt_value) = excerpt {
+        if let Err(error) = validate_post_excerpt(excerpt_value) {
+            errors.add("excerpt", error);
+        }
+    }
+
+    // Validate image if provided
+    if let Some(image_value) = image_url {
+        if let Err(error) = validate_post_image_url(image_value) {
+            errors.add("image_url", error);
+        }
+    }
+
     if errors.is_empty() {
         Ok(())
     } else {
@@ -82,6 +141,82 @@ pub fn validate_update_domain_request(
     }
 }
 
+/// Report every broken password rule so the client can show all of them
+fn add_password_errors(errors: &mut ValidationErrors, password: &str) {
+    for error in password_strength_errors(password) {
+        errors.add("password", error);
+    }
+}
+
+/// Manual validation implementation for CreateUserRequest
+pub fn validate_create_user_request(
+    email: &str,
+    name: &str,
+    password: &str,
+    role: &str,
+) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+
+    if email.trim().is_empty() {
+        let mut error = ValidationError::new("length");
+        error.message = Some("Email is required".into());
+        errors.add("email", error);
+    } else if !email.validate_email() {
+        let mut error = ValidationError::new("email");
+        error.message = Some("Invalid email format".into());
+        errors.add("email", error);
+    }
+
+    if name.trim().is_empty() || name.len() > 100 {
+        let mut error = ValidationError::new("length");
+        error.message = Some("Name must be between 1 and 100 characters".into());
+        errors.add("name", error);
+    }
+
+    add_password_errors(&mut errors, password);
+
+    if validate_user_role(role).is_err() {
+        let mut error = ValidationError::new("role");
+        error.message = Some("Invalid user role".into());
+        errors.add("role", error);
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Manual validation implementation for CreateMemberRequest
+pub fn validate_create_member_request(
+    email: &str,
+    name: &str,
+    password: &str,
+) -> Result<(), ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+
+    if !email.validate_email() {
+        let mut error = ValidationError::new("email");
+        error.message = Some("Invalid email format".into());
+        errors.add("email", error);
+    }
+
+    if name.trim().is_empty() || name.len() > 100 {
+        let mut error = ValidationError::new("length");
+        error.message = Some("Name must be between 1 and 100 characters".into());
+        errors.add("name", error);
+    }
+
+    add_password_errors(&mut errors, password);
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
 /// Manual validation implementation for UpdateUserRequest
 pub fn validate_update_user_request(
     email: &Option<String>,
@@ -111,9 +246,7 @@ pub fn validate_update_user_request(
 
     // Validate password if provided
     if let Some(password_value) = password {
-        if let Err(error) = validate_password_strength(password_value) {
-            errors.add("password", error);
-        }
+        add_password_errors(&mut errors, password_value);
     }
 
     // Validate role if provided
@@ -129,3 +262,105 @@ pub fn validate_update_user_request(
         Err(errors)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slug_code(slug: &str) -> String {
+        validate_slug(slug).unwrap_err().code.to_string()
+    }
+
+    #[test]
+    fn test_validate_slug_accepts_valid_slugs() {
+        assert!(validate_slug("hello-world").is_ok());
+        assert!(validate_slug("rust-2024-edition").is_ok());
+        assert!(validate_slug("a").is_ok());
+        assert!(validate_slug(&"a".repeat(MAX_SLUG_LENGTH)).is_ok());
+    }
+
+    #[test]
+    fn test_validate_slug_rejects_each_invalid_class() {
+        assert_eq!(slug_code(""), "slug_empty");
+        assert_eq!(slug_code(&"a".repeat(MAX_SLUG_LENGTH + 1)), "slug_length");
+        assert_eq!(slug_code("My-Post"), "slug_characters");
+        assert_eq!(slug_code("my post!"), "slug_characters");
+        assert_eq!(slug_code("caf\u{e9}"), "slug_characters");
+        assert_eq!(slug_code("-leading"), "slug_hyphens");
+        assert_eq!(slug_code("trailing-"), "slug_hyphens");
+        assert_eq!(slug_code("double--hyphen"), "slug_hyphens");
+    }
+
+    #[test]
+    fn test_create_user_request_lists_every_password_problem() {
+        let errors =
+            validate_create_user_request("new@example.com", "New User", "abc", "domain_user")
+                .unwrap_err();
+        let password_errors = &errors.field_errors()["password"];
+        assert_eq!(password_errors.len(), 4);
+        assert!(password_errors.iter().all(|e| e.message.is_some()));
+        assert!(!errors.field_errors().contains_key("email"));
+    }
+
+    #[test]
+    fn test_create_post_request_reports_slug_field() {
+        let errors = validate_create_post_request(
+            "Title",
+            "Content",
+            "Technology",
+            &Some("Bad Slug".to_string()),
+            &None,
+            &None,
+            &None,
+        )
+        .unwrap_err();
+        assert!(errors.field_errors().contains_key("slug"));
+    }
+
+    #[test]
+    fn test_create_post_request_limits_excerpt() {
+        let excerpt = Some("x".repeat(501));
+        let errors = validate_create_post_request(
+            "Title",
+            "Content",
+            "Technology",
+            &None,
+            &None,
+            &excerpt,
+            &None,
+        )
+        .unwrap_err();
+        assert!(errors.field_errors().contains_key("excerpt"));
+        assert!(
+            validate_create_post_request(
+                "Title",
+                "Content",
+                "Technology",
+                &None,
+                &None,
+                &Some("x".repeat(500)),
+                &None,
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_create_post_request_checks_image_url() {
+        let validate = |image: &str| {
+            validate_create_post_request(
+                "Title",
+                "Content",
+                "Technology",
+                &None,
+                &None,
+                &None,
+                &Some(image.to_string()),
+            )
+        };
+        assert!(validate("https://cdn.example.com/cover.png").is_ok());
+        assert!(validate("/media/cover.png").is_ok());
+        let errors = validate("javascript:alert(1)").unwrap_err();
+        assert!(errors.field_errors().contains_key("image_url"));
+    }
+}
@@ -1,6 +1,7 @@
 // src/validation/extractors.rs
 //! Axum extractors for validated request types
 
+use crate::middleware::RequestId;
 use crate::validation::{ValidationErrorResponse};
 use axum::{
     extract::{FromRequest, Request},
@@ -22,15 +23,24 @@ where
     type Rejection = ValidationRejection;
 
     async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
-        let Json(data) = Json::<T>::from_request(req, state)
-            .await
-            .map_err(|err| ValidationRejection::JsonError(err.to_string()))?;
+        let request_id = req
+            .extensions()
+            .get::<RequestId>()
+            .map(|RequestId(id)| id.clone());
+
+        let Json(data) = Json::<T>::from_request(req, state).await.map_err(|err| {
+            ValidationRejection::JsonError(
+                ValidationErrorResponse::new(&format!("Invalid JSON: {}", err))
+                    .with_request_id(request_id.clone()),
+            )
+        })?;
 
         // Validate the deserialized data
-        data.validate()
-            .map_err(|errors| ValidationRejection::ValidationError(
-                ValidationErrorResponse::from_validation_errors(errors)
-            ))?;
+        data.validate().map_err(|errors| {
+            ValidationRejection::ValidationError(
+                ValidationErrorResponse::from_validation_errors(errors).with_request_id(request_id),
+            )
+        })?;
 
         Ok(ValidatedJson(data))
     }
@@ -38,15 +48,14 @@ where
 
 /// Rejection type for validation errors
 pub enum ValidationRejection {
-    JsonError(String),
+    JsonError(ValidationErrorResponse),
     ValidationError(ValidationErrorResponse),
 }
 
 impl IntoResponse for ValidationRejection {
     fn into_response(self) -> Response {
         match self {
-            ValidationRejection::JsonError(msg) => {
-                let error = ValidationErrorResponse::new(&format!("Invalid JSON: {}", msg));
+            ValidationRejection::JsonError(error) => {
                 (StatusCode::BAD_REQUEST, Json(error)).into_response()
             }
             ValidationRejection::ValidationError(error) => {
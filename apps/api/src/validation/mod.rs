@@ -18,6 +18,9 @@ pub struct ValidationErrorResponse {
     pub error: String,
     pub message: String,
     pub field_errors: HashMap<String, Vec<String>>,
+    /// Correlation id from `X-Request-Id`, for bug reports
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl ValidationErrorResponse {
@@ -26,6 +29,7 @@ impl ValidationErrorResponse {
             error: "validation_error".to_string(),
             message: message.to_string(),
             field_errors: HashMap::new(),
+            request_id: None,
         }
     }
 
@@ -50,8 +54,14 @@ impl ValidationErrorResponse {
             error: "validation_error".to_string(),
             message: "Request validation failed".to_string(),
             field_errors,
+            request_id: None,
         }
     }
+
+    pub fn with_request_id(mut self, request_id: Option<String>) -> Self {
+        self.request_id = request_id;
+        self
+    }
 }
 
 /// Trait for validating request structures
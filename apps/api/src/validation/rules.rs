@@ -1,36 +1,14 @@
 // src/validation/rules.rs
 //! Custom validation rules for the multi-blog API
 
+use crate::services::post_status::PostStatus;
+use crate::services::post_visibility::PostVisibility;
 use regex::Regex;
 use validator::ValidationError;
 
-/// Validate that a slug contains only alphanumeric characters and hyphens
+/// Validate a post slug; see [`crate::validation::custom::validate_slug`]
 pub fn validate_slug(slug: &str) -> Result<(), ValidationError> {
-    let slug_regex = Regex::new(r"^[a-zA-Z0-9\-]+$").unwrap();
-
-    if slug.is_empty() {
-        return Err(ValidationError::new("Slug cannot be empty"));
-    }
-
-    if !slug_regex.is_match(slug) {
-        return Err(ValidationError::new(
-            "Slug can only contain letters, numbers, and hyphens",
-        ));
-    }
-
-    if slug.starts_with('-') || slug.ends_with('-') {
-        return Err(ValidationError::new(
-            "Slug cannot start or end with a hyphen",
-        ));
-    }
-
-    if slug.contains("--") {
-        return Err(ValidationError::new(
-            "Slug cannot contain consecutive hyphens",
-        ));
-    }
-
-    Ok(())
+    crate::validation::custom::validate_slug(slug)
 }
 
 /// Validate optional slug - used for Option<String> fields
@@ -42,25 +20,85 @@ pub fn validate_slug_option(slug_opt: &Option<String>) -> Result<(), ValidationE
     }
 }
 
-/// Validate hostname format (basic domain validation)
-pub fn validate_hostname(hostname: &str) -> Result<(), ValidationError> {
-    let hostname_regex = Regex::new(r"^[a-zA-Z0-9]([a-zA-Z0-9\-]{0,61}[a-zA-Z0-9])?(\.[a-zA-Z0-9]([a-zA-Z0-9\-]{0,61}[a-zA-Z0-9])?)*$").unwrap();
+/// Whether domain hostnames may carry a `:port`, e.g. `localhost:3000` in
+/// development. Configurable via `HOSTNAME_ALLOW_PORT` (default false).
+fn hostname_port_allowed() -> bool {
+    std::env::var("HOSTNAME_ALLOW_PORT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(false)
+}
 
+/// Normalize a hostname for storage and lookup
+///
+/// IDN labels are converted to punycode (`bücher.example` becomes
+/// `xn--bcher-kva.example`) and everything is lowercased. The result must
+/// follow RFC 1123: at most 253 characters, labels of 1-63 letters, digits
+/// and hyphens that don't start or end with a hyphen. A port is only accepted
+/// when `HOSTNAME_ALLOW_PORT=true`, and is dropped since domains are matched
+/// on the bare host.
+pub fn normalize_hostname(hostname: &str) -> Result<String, ValidationError> {
+    normalize_hostname_with(hostname, hostname_port_allowed())
+}
+
+fn normalize_hostname_with(hostname: &str, allow_port: bool) -> Result<String, ValidationError> {
     if hostname.is_empty() {
         return Err(ValidationError::new("Hostname cannot be empty"));
     }
 
-    if hostname.len() > 253 {
+    if hostname.chars().any(char::is_whitespace) {
+        return Err(ValidationError::new("Hostname cannot contain whitespace"));
+    }
+
+    let host = match hostname.rsplit_once(':') {
+        Some(_) if !allow_port => {
+            return Err(ValidationError::new("Hostname cannot include a port"));
+        }
+        Some((host, port)) => {
+            if !matches!(port.parse::<u16>(), Ok(p) if p > 0) {
+                return Err(ValidationError::new("Invalid port"));
+            }
+            host
+        }
+        None => hostname,
+    };
+
+    let ascii = idna::domain_to_ascii(host)
+        .map_err(|_| ValidationError::new("Invalid internationalized hostname"))?;
+
+    if ascii.len() > 253 {
         return Err(ValidationError::new(
             "Hostname is too long (max 253 characters)",
         ));
     }
 
-    if !hostname_regex.is_match(hostname) {
-        return Err(ValidationError::new("Invalid hostname format"));
+    for label in ascii.split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(ValidationError::new(
+                "Hostname labels must be between 1 and 63 characters",
+            ));
+        }
+        if !label
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        {
+            return Err(ValidationError::new(
+                "Hostname labels can only contain letters, numbers and hyphens",
+            ));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(ValidationError::new(
+                "Hostname labels cannot start or end with a hyphen",
+            ));
+        }
     }
 
-    Ok(())
+    Ok(ascii)
+}
+
+/// Validate hostname format; see [`normalize_hostname`]
+pub fn validate_hostname(hostname: &str) -> Result<(), ValidationError> {
+    normalize_hostname(hostname).map(|_| ())
 }
 
 /// Validate optional hostname - used for Option<String> fields
@@ -103,10 +141,10 @@ pub fn validate_domain_permission_role(role: &str) -> Result<(), ValidationError
 
 /// Validate post status
 pub fn validate_post_status(status: &str) -> Result<(), ValidationError> {
-    match status {
-        "draft" | "published" | "archived" => Ok(()),
-        _ => Err(ValidationError::new(
-            "Status must be 'draft', 'published', or 'archived'",
+    match PostStatus::parse(status) {
+        Some(_) => Ok(()),
+        None => Err(ValidationError::new(
+            "Status must be 'draft', 'review', 'scheduled', 'published', or 'archived'",
         )),
     }
 }
@@ -120,39 +158,126 @@ pub fn validate_post_status_option(status_opt: &Option<String>) -> Result<(), Va
     }
 }
 
-/// Validate password strength
-pub fn validate_password_strength(password: &str) -> Result<(), ValidationError> {
-    if password.len() < 8 {
-        return Err(ValidationError::new(
+/// Validate post visibility
+pub fn validate_post_visibility(visibility: &str) -> Result<(), ValidationError> {
+    match PostVisibility::parse(visibility) {
+        Some(_) => Ok(()),
+        None => Err(ValidationError::new(
+            "Visibility must be 'public', 'members', or 'private'",
+        )),
+    }
+}
+
+/// Passwords rejected outright (exact match), however many character classes they use
+const COMMON_PASSWORDS: &[&str] = &[
+    "password",
+    "password1",
+    "password123",
+    "passw0rd",
+    "p@ssw0rd",
+    "P@ssw0rd",
+    "Passw0rd!",
+    "12345678",
+    "123456789",
+    "1234567890",
+    "11111111",
+    "00000000",
+    "abc12345",
+    "qwerty123",
+    "Qwerty123!",
+    "qwertyuiop",
+    "iloveyou",
+    "admin123",
+    "Admin123!",
+    "welcome1",
+    "Welcome1!",
+    "letmein1",
+    "sunshine",
+    "football",
+    "baseball",
+    "monkey123",
+    "dragon123",
+    "changeme",
+];
+
+fn password_error(code: &'static str, message: &'static str) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(message.into());
+    error
+}
+
+/// Every password rule the value breaks, each with its own code and message.
+/// Passwords need 8-128 characters and at least 3 of lowercase, uppercase,
+/// number and symbol, and must not be a well-known password.
+pub fn password_strength_errors(password: &str) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let length = password.chars().count();
+
+    if length < 8 {
+        errors.push(password_error(
+            "password_too_short",
             "Password must be at least 8 characters long",
         ));
     }
 
-    if password.len() > 128 {
-        return Err(ValidationError::new(
+    if length > 128 {
+        errors.push(password_error(
+            "password_too_long",
             "Password is too long (max 128 characters)",
         ));
     }
 
-    let has_lowercase = password.chars().any(|c| c.is_lowercase());
-    let has_uppercase = password.chars().any(|c| c.is_uppercase());
-    let has_digit = password.chars().any(|c| c.is_ascii_digit());
-    let has_special = password
-        .chars()
-        .any(|c| "!@#$%^&*()_+-=[]{}|;:,.<>?".contains(c));
-
-    let strength_count = [has_lowercase, has_uppercase, has_digit, has_special]
-        .iter()
-        .filter(|&&x| x)
-        .count();
-
-    if strength_count < 3 {
-        return Err(ValidationError::new(
-            "Password must contain at least 3 of: lowercase letter, uppercase letter, number, special character",
+    if COMMON_PASSWORDS.contains(&password) {
+        errors.push(password_error(
+            "password_common",
+            "Password is too common, choose something less predictable",
         ));
     }
 
-    Ok(())
+    let classes = [
+        (
+            password.chars().any(|c| c.is_lowercase()),
+            "password_missing_lowercase",
+            "Add a lowercase letter (use at least 3 of: lowercase, uppercase, number, symbol)",
+        ),
+        (
+            password.chars().any(|c| c.is_uppercase()),
+            "password_missing_uppercase",
+            "Add an uppercase letter (use at least 3 of: lowercase, uppercase, number, symbol)",
+        ),
+        (
+            password.chars().any(|c| c.is_ascii_digit()),
+            "password_missing_digit",
+            "Add a number (use at least 3 of: lowercase, uppercase, number, symbol)",
+        ),
+        (
+            password
+                .chars()
+                .any(|c| "!@#$%^&*()_+-=[]{}|;:,.<>?".contains(c)),
+            "password_missing_symbol",
+            "Add a symbol such as !@#$% (use at least 3 of: lowercase, uppercase, number, symbol)",
+        ),
+    ];
+
+    if classes.iter().filter(|(present, _, _)| *present).count() < 3 {
+        errors.extend(
+            classes
+                .iter()
+                .filter(|(present, _, _)| !present)
+                .map(|(_, code, message)| password_error(code, message)),
+        );
+    }
+
+    errors
+}
+
+/// Validate password strength, reporting the first rule broken.
+/// Use [`password_strength_errors`] to report all of them.
+pub fn validate_password_strength(password: &str) -> Result<(), ValidationError> {
+    match password_strength_errors(password).into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
 }
 
 /// Validate optional password strength - used for Option<String> fields
@@ -181,6 +306,54 @@ pub fn validate_post_content(content: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
+/// Validate a hand-written post excerpt
+pub fn validate_post_excerpt(excerpt: &str) -> Result<(), ValidationError> {
+    if excerpt.chars().count() > crate::services::excerpt::MAX_EXCERPT_CHARS {
+        return Err(ValidationError::new(
+            "Excerpt is too long (max 500 characters)",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validate a post's search title override (empty clears it)
+pub fn validate_post_meta_title(meta_title: &str) -> Result<(), ValidationError> {
+    if meta_title.chars().count() > 200 {
+        return Err(ValidationError::new(
+            "Meta title is too long (max 200 characters)",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validate a post's search description override (empty clears it)
+pub fn validate_post_meta_description(meta_description: &str) -> Result<(), ValidationError> {
+    if meta_description.chars().count() > 500 {
+        return Err(ValidationError::new(
+            "Meta description is too long (max 500 characters)",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validate a post's social sharing image: an absolute http(s) URL or a
+/// path on the blog's own host
+pub fn validate_post_image_url(url: &str) -> Result<(), ValidationError> {
+    let allowed = url.starts_with("https://")
+        || url.starts_with("http://")
+        || (url.starts_with('/') && !url.starts_with("//"));
+    if !allowed || url.len() > 2048 || url.chars().any(char::is_whitespace) {
+        return Err(ValidationError::new(
+            "Image must be an http(s) URL or a path starting with /",
+        ));
+    }
+
+    Ok(())
+}
+
 /// Validate category name
 pub fn validate_category(category: &str) -> Result<(), ValidationError> {
     if category.trim().is_empty() {
@@ -225,6 +398,46 @@ mod tests {
         assert!(validate_hostname("invalid..com").is_err());
     }
 
+    #[test]
+    fn test_hostname_idn_is_normalized_to_punycode() {
+        assert_eq!(
+            normalize_hostname("Bücher.Example").unwrap(),
+            "xn--bcher-kva.example"
+        );
+        assert_eq!(
+            normalize_hostname("xn--bcher-kva.example").unwrap(),
+            "xn--bcher-kva.example"
+        );
+    }
+
+    #[test]
+    fn test_hostname_label_rules() {
+        let label = "a".repeat(63);
+        assert!(validate_hostname(&format!("{label}.com")).is_ok());
+        assert!(validate_hostname(&format!("{label}a.com")).is_err());
+        assert!(validate_hostname(&vec!["abc"; 64].join(".")).is_err()); // 255 characters
+        assert!(validate_hostname("-example.com").is_err());
+        assert!(validate_hostname("example-.com").is_err());
+        assert!(validate_hostname("exam_ple.com").is_err());
+    }
+
+    #[test]
+    fn test_hostname_rejects_embedded_space() {
+        assert!(validate_hostname("exa mple.com").is_err());
+        assert!(validate_hostname(" example.com").is_err());
+    }
+
+    #[test]
+    fn test_hostname_port_only_when_allowed() {
+        assert!(normalize_hostname_with("localhost:3000", false).is_err());
+        assert_eq!(
+            normalize_hostname_with("localhost:3000", true).unwrap(),
+            "localhost"
+        );
+        assert!(normalize_hostname_with("localhost:0", true).is_err());
+        assert!(normalize_hostname_with("localhost:http", true).is_err());
+    }
+
     #[test]
     fn test_validate_password_strength() {
         assert!(validate_password_strength("Password123!").is_ok());
@@ -234,6 +447,56 @@ mod tests {
         assert!(validate_password_strength("Password123").is_ok()); // 3 character types
     }
 
+    fn password_codes(password: &str) -> Vec<String> {
+        password_strength_errors(password)
+            .into_iter()
+            .map(|e| e.code.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_password_strength_errors_are_specific() {
+        assert_eq!(
+            password_codes("Ab1!"),
+            vec!["password_too_short".to_string()]
+        );
+        assert_eq!(
+            password_codes(&format!("Aa1!{}", "x".repeat(130))),
+            vec!["password_too_long".to_string()]
+        );
+        assert_eq!(
+            password_codes("P@ssw0rd"),
+            vec!["password_common".to_string()]
+        );
+        assert_eq!(
+            password_codes("lowercase!"),
+            vec![
+                "password_missing_uppercase".to_string(),
+                "password_missing_digit".to_string()
+            ]
+        );
+        assert_eq!(
+            password_codes("UPPERCASE1"),
+            vec![
+                "password_missing_lowercase".to_string(),
+                "password_missing_symbol".to_string()
+            ]
+        );
+
+        // Each rule carries its own message for the frontend
+        let messages: Vec<String> = password_strength_errors("abc")
+            .into_iter()
+            .map(|e| e.message.unwrap().to_string())
+            .collect();
+        assert_eq!(messages.len(), 4);
+        assert!(messages[0].contains("at least 8 characters"));
+        assert!(messages[1].contains("uppercase letter"));
+        assert!(messages[2].contains("number"));
+        assert!(messages[3].contains("symbol"));
+
+        assert!(password_strength_errors("Correct-Horse-42").is_empty());
+    }
+
     #[test]
     fn test_validate_user_role() {
         assert!(validate_user_role("platform_admin").is_ok());
@@ -1,11 +1,18 @@
 // tests/admin_tests.rs
-use api::{AppState, DomainContext, UserContext, handlers::admin::AdminModule, test_utils::*};
+use api::{
+    AppState, auth_middleware,
+    handlers::{HandlerModule, admin::AdminModule},
+    test_utils::*,
+};
 use axum::{
     Extension, Router,
-    body::Body,
-    http::{HeaderValue, Request, StatusCode},
+    http::{HeaderValue, StatusCode},
+    middleware,
+};
+use axum_test::{
+    TestServer,
+    multipart::{MultipartForm, Part},
 };
-use axum_test::TestServer;
 use serde_json::{Value, json};
 use serial_test::serial;
 use std::sync::Arc;
@@ -18,7 +25,7 @@ fn create_admin_app(state: Arc<AppState>) -> Router {
 #[serial]
 async fn test_list_admin_posts() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
     let user = create_test_user(&pool, "admin@test.com", "Admin User", "user").await;
@@ -59,11 +66,87 @@ async fn test_list_admin_posts() {
     cleanup_test_db(&pool).await;
 }
 
+#[tokio::test]
+#[serial]
+async fn test_list_all_domains_posts_covers_accessible_domains_only() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let second = create_test_domain(&pool, "second.testblog.com", "Second Blog").await;
+    let hidden = create_test_domain(&pool, "hidden.testblog.com", "Hidden Blog").await;
+    for (domain_id, title) in [
+        (domain.id, "First Domain Post"),
+        (second.id, "Second Domain Post"),
+        (hidden.id, "Hidden Domain Post"),
+    ] {
+        create_test_post(&pool, domain_id, title, "Content", "Admin", "published").await;
+    }
+
+    let mut user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+    create_test_permission(&pool, user.id, second.id, "editor").await;
+    user.domain_permissions = vec![
+        api::DomainPermission {
+            domain_id: domain.id,
+            role: "viewer".to_string(),
+        },
+        api::DomainPermission {
+            domain_id: second.id,
+            role: "editor".to_string(),
+        },
+    ];
+    let admin = create_test_user(&pool, "admin@test.com", "Admin", "platform_admin").await;
+
+    let titles = |body: Value| -> Vec<String> {
+        let mut titles: Vec<String> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["title"].as_str().unwrap().to_string())
+            .collect();
+        titles.sort();
+        titles
+    };
+
+    let server = TestServer::new(
+        create_admin_app(state.clone())
+            .layer(Extension(domain.clone()))
+            .layer(Extension(user)),
+    )
+    .unwrap();
+    let response = server.get("/posts?domain=all").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(
+        titles(response.json()),
+        ["First Domain Post", "Second Domain Post"]
+    );
+
+    let server = TestServer::new(
+        create_admin_app(state)
+            .layer(Extension(domain))
+            .layer(Extension(admin)),
+    )
+    .unwrap();
+    let response = server.get("/posts?domain=all").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(
+        titles(response.json()),
+        [
+            "First Domain Post",
+            "Hidden Domain Post",
+            "Second Domain Post"
+        ]
+    );
+
+    cleanup_test_db(&pool).await;
+}
+
 #[tokio::test]
 #[serial]
 async fn test_create_post() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
     let user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
@@ -99,6 +182,143 @@ async fn test_create_post() {
         "New Test Post"
     );
     assert_eq!(body.get("status").unwrap().as_str().unwrap(), "published");
+    assert_eq!(body["word_count"], 6);
+    assert_eq!(body["reading_time_minutes"], 1);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_post_excerpt_written_or_generated() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "editor".to_string(),
+    }];
+
+    let app = create_admin_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(user_with_permissions));
+    let server = TestServer::new(app).unwrap();
+
+    let long_content = format!(
+        "# Intro\n\nThis **post** has [a link](https://example.com). {}",
+        "Lorem ipsum dolor sit amet. ".repeat(20)
+    );
+
+    // A written excerpt is kept as is
+    let response = server
+        .post("/posts")
+        .json(&json!({
+            "title": "Written",
+            "content": long_content,
+            "category": "Technology",
+            "excerpt": "Hand-written summary",
+        }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(response.json::<Value>()["excerpt"], "Hand-written summary");
+
+    // Otherwise it is generated from the content as plain text
+    let response = server
+        .post("/posts")
+        .json(&json!({
+            "title": "Generated",
+            "content": long_content,
+            "category": "Technology",
+        }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    let excerpt = body["excerpt"].as_str().unwrap();
+    assert!(excerpt.starts_with("Intro This post has a link. Lorem ipsum"));
+    assert!(excerpt.ends_with('…'));
+    assert!(excerpt.chars().count() <= 201);
+    let last_word = excerpt.trim_end_matches('…').rsplit(' ').next().unwrap();
+    assert!(["Lorem", "ipsum", "dolor", "sit", "amet."].contains(&last_word));
+
+    // Updating without an excerpt regenerates it from the new content
+    let response = server
+        .put(&format!("/posts/{}", body["id"]))
+        .json(&json!({
+            "title": "Generated",
+            "content": "Short *new* body",
+            "category": "Technology",
+        }))
+        .await;
+    assert_eq!(response.json::<Value>()["excerpt"], "Short new body");
+
+    let response = server
+        .post("/posts")
+        .json(&json!({
+            "title": "Too long",
+            "content": "Body",
+            "category": "Technology",
+            "excerpt": "x".repeat(501),
+        }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_create_and_update_post_reject_empty_title_identically() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "editor").await;
+    let post_id = create_test_post(
+        &pool,
+        domain.id,
+        "Existing Post",
+        "Existing content",
+        "Editor",
+        "draft",
+    )
+    .await;
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "editor".to_string(),
+    }];
+
+    let app = create_admin_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(user_with_permissions));
+
+    let server = TestServer::new(app).unwrap();
+
+    let invalid_post = json!({
+        "title": "",
+        "content": "Some content",
+        "category": "Technology"
+    });
+
+    let create = server.post("/posts").json(&invalid_post).await;
+    let update = server
+        .put(&format!("/posts/{post_id}"))
+        .json(&invalid_post)
+        .await;
+
+    assert_eq!(create.status_code(), StatusCode::BAD_REQUEST);
+    assert_eq!(update.status_code(), StatusCode::BAD_REQUEST);
+
+    let create_body: Value = create.json();
+    let update_body: Value = update.json();
+    assert_eq!(create_body, update_body);
+    assert_eq!(create_body["error"], "validation_error");
+    assert!(create_body["field_errors"]["title"].is_array());
 
     cleanup_test_db(&pool).await;
 }
@@ -107,7 +327,7 @@ async fn test_create_post() {
 #[serial]
 async fn test_create_post_insufficient_permissions() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
     let user = create_test_user(&pool, "viewer@test.com", "Viewer User", "user").await;
@@ -142,7 +362,7 @@ async fn test_create_post_insufficient_permissions() {
 #[serial]
 async fn test_get_admin_post() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
     let user = create_test_user(&pool, "admin@test.com", "Admin User", "user").await;
@@ -186,7 +406,7 @@ async fn test_get_admin_post() {
 #[serial]
 async fn test_update_post() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
     let user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
@@ -234,34 +454,235 @@ async fn test_update_post() {
         "Updated Title"
     );
     assert_eq!(body.get("status").unwrap().as_str().unwrap(), "published");
+    assert_eq!(body["word_count"], 2);
 
     cleanup_test_db(&pool).await;
 }
 
 #[tokio::test]
 #[serial]
-async fn test_delete_post() {
+async fn test_changing_slug_redirects_old_url() {
+    use api::handlers::{HandlerModule, blog::BlogModule};
+
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
-    let user = create_test_user(&pool, "admin@test.com", "Admin User", "user").await;
-    create_test_permission(&pool, user.id, domain.id, "admin").await; // Admin permission needed for delete
-
+    let mut user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "editor").await;
+    user.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "editor".to_string(),
+    }];
     let post_id = create_test_post(
         &pool,
         domain.id,
-        "Post to Delete",
-        "This will be deleted",
-        "Author",
+        "Original Title",
+        "Some content",
+        "Editor",
         "published",
     )
     .await;
 
+    let server = TestServer::new(
+        create_admin_app(state.clone())
+            .layer(Extension(domain.clone()))
+            .layer(Extension(user)),
+    )
+    .unwrap();
+    let rename = |slug: &str| {
+        server.put(&format!("/posts/{post_id}")).json(&json!({
+            "title": "Original Title",
+            "content": "Some content",
+            "category": "Technology",
+            "slug": slug,
+        }))
+    };
+
+    let response = rename("new-title").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(response.json::<Value>()["slug"], "new-title");
+    let redirect: Option<i32> = sqlx::query_scalar(
+        "SELECT post_id FROM post_slug_redirects WHERE domain_id = $1 AND old_slug = 'original-title'",
+    )
+    .bind(domain.id)
+    .fetch_optional(&pool)
+    .await
+    .unwrap();
+    assert_eq!(redirect, Some(post_id));
+
+    // Renamed twice, both old slugs lead to the current one
+    assert_eq!(rename("newest-title").await.status_code(), StatusCode::OK);
+
+    let blog = TestServer::new(
+        BlogModule::routes()
+            .with_state(state)
+            .layer(Extension(domain))
+            .layer(Extension(api::AnalyticsContext {
+                ip_address: "127.0.0.1".to_string(),
+                user_agent: "Mozilla/5.0".to_string(),
+                referrer: None,
+            })),
+    )
+    .unwrap();
+    let response = blog.get("/posts/original-title").await;
+    assert_eq!(response.status_code(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(response.header("location"), "/posts/newest-title");
+    let response = blog.get("/posts/new-title?locale=fr").await;
+    assert_eq!(response.status_code(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(response.header("location"), "/posts/newest-title?locale=fr");
+    assert_eq!(
+        blog.get("/posts/newest-title").await.status_code(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        blog.get("/posts/never-existed").await.status_code(),
+        StatusCode::NOT_FOUND
+    );
+
+    // Taking an old slug back serves the post there again
+    assert_eq!(rename("original-title").await.status_code(), StatusCode::OK);
+    assert_eq!(
+        blog.get("/posts/original-title").await.status_code(),
+        StatusCode::OK
+    );
+    let response = blog.get("/posts/newest-title").await;
+    assert_eq!(response.status_code(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(response.header("location"), "/posts/original-title");
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_post_authors_are_credited_by_current_name() {
+    use api::handlers::{HandlerModule, blog::BlogModule};
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let mut user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "editor").await;
+    user.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "editor".to_string(),
+    }];
+    let editor_id = user.id;
+    let coauthor = create_test_user(&pool, "coauthor@test.com", "Co Author", "user").await;
+    create_test_permission(&pool, coauthor.id, domain.id, "viewer").await;
+    let outsider = create_test_user(&pool, "outsider@test.com", "Outsider", "user").await;
+
+    let server = TestServer::new(
+        create_admin_app(state.clone())
+            .layer(Extension(domain.clone()))
+            .layer(Extension(user)),
+    )
+    .unwrap();
+    let response = server
+        .post("/posts")
+        .json(&json!({
+            "title": "Written Together",
+            "content": "Two people wrote this",
+            "category": "Technology",
+            "slug": "written-together",
+            "status": "published"
+        }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let post_id = response.json::<Value>()["id"].as_i64().unwrap();
+
+    // The creator is credited as soon as the post exists
+    let authors: Value = server
+        .get(&format!("/posts/{post_id}/authors"))
+        .await
+        .json();
+    assert_eq!(authors.as_array().unwrap().len(), 1);
+    assert_eq!(authors[0]["user_id"], editor_id);
+
+    let response = server
+        .put(&format!("/posts/{post_id}/authors/{}", coauthor.id))
+        .json(&json!({}))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(response.json::<Value>().as_array().unwrap().len(), 2);
+
+    // Only users with access to the domain can be credited
+    let response = server
+        .put(&format!("/posts/{post_id}/authors/{}", outsider.id))
+        .json(&json!({ "role": "contributor" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let blog = TestServer::new(
+        BlogModule::routes()
+            .with_state(state)
+            .layer(Extension(domain))
+            .layer(Extension(api::AnalyticsContext {
+                ip_address: "127.0.0.1".to_string(),
+                user_agent: "Mozilla/5.0".to_string(),
+                referrer: None,
+            })),
+    )
+    .unwrap();
+    let body: Value = blog.get("/posts/written-together").await.json();
+    assert_eq!(body["author"], "Editor User, Co Author");
+    let names: Vec<&str> = body["authors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["Editor User", "Co Author"]);
+
+    // A rename shows up on posts that already exist
+    sqlx::query("UPDATE users SET name = 'Co Writer' WHERE id = $1")
+        .bind(coauthor.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let body: Value = blog.get("/posts/written-together").await.json();
+    assert_eq!(body["author"], "Editor User, Co Writer");
+    assert_eq!(body["authors"][1]["name"], "Co Writer");
+    let body: Value = blog.get("/posts").await.json();
+    assert_eq!(body["posts"][0]["author"], "Editor User, Co Writer");
+
+    assert_eq!(
+        server
+            .delete(&format!("/posts/{post_id}/authors/{}", coauthor.id))
+            .await
+            .status_code(),
+        StatusCode::NO_CONTENT
+    );
+    let body: Value = blog.get("/posts/written-together").await.json();
+    assert_eq!(body["author"], "Editor User");
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_update_post_status_follows_workflow() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "editor").await;
+    let post_id = create_test_post(
+        &pool,
+        domain.id,
+        "Workflow Post",
+        "Workflow content",
+        "Editor",
+        "draft",
+    )
+    .await;
+
     let mut user_with_permissions = user.clone();
     user_with_permissions.domain_permissions = vec![api::DomainPermission {
         domain_id: domain.id,
-        role: "admin".to_string(),
+        role: "editor".to_string(),
     }];
 
     let app = create_admin_app(state)
@@ -269,34 +690,89 @@ async fn test_delete_post() {
         .layer(Extension(user_with_permissions));
 
     let server = TestServer::new(app).unwrap();
-    let response = server.delete(&format!("/posts/{}", post_id)).await;
+    let put_status = |status: Option<&str>| {
+        let mut body = json!({
+            "title": "Workflow Post",
+            "content": "Workflow content",
+            "category": "Technology",
+        });
+        if let Some(status) = status {
+            body["status"] = json!(status);
+        }
+        server.put(&format!("/posts/{post_id}")).json(&body)
+    };
+
+    let response = server.get(&format!("/posts/{post_id}")).await;
+    assert_eq!(
+        response.json::<Value>()["next_statuses"],
+        json!(["review", "scheduled", "published"])
+    );
 
-    assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+    // A draft can't be archived without being published first
+    let response = put_status(Some("archived")).await;
+    assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = put_status(Some("banana")).await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    for (status, next) in [
+        ("review", json!(["draft", "scheduled", "published"])),
+        ("published", json!(["draft", "archived"])),
+        ("archived", json!(["draft", "published"])),
+    ] {
+        let response = put_status(Some(status)).await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let body: Value = response.json();
+        assert_eq!(body["status"], status);
+        assert_eq!(body["next_statuses"], next);
+    }
+
+    // Leaving the status out keeps the current one
+    let response = put_status(None).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(response.json::<Value>()["status"], "archived");
+
+    let response = put_status(Some("scheduled")).await;
+    assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
 
     cleanup_test_db(&pool).await;
 }
 
 #[tokio::test]
 #[serial]
-async fn test_analytics_summary() {
+async fn test_autosave_keeps_published_post_and_returns_newest_draft() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
-    let user = create_test_user(&pool, "admin@test.com", "Admin User", "user").await;
-    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+    let user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "editor").await;
 
-    // Create some analytics events
-    sqlx::query!(
-        r#"
-        INSERT INTO analytics_events (domain_id, event_type, path, ip_address, user_agent)
-        VALUES 
-            ($1, 'page_view', '/', '127.0.0.1', 'test-agent'),
-            ($1, 'post_view', '/posts/test', '127.0.0.1', 'test-agent'),
-            ($1, 'search', '/search', '127.0.0.2', 'test-agent')
-        "#,
-        domain.id
+    let post_id = create_test_post(
+        &pool,
+        domain.id,
+        "Published Title",
+        "Published content",
+        "Author",
+        "published",
+    )
+    .await;
+    let other_post_id = create_test_post(
+        &pool,
+        domain.id,
+        "Other",
+        "Other content",
+        "Author",
+        "draft",
+    )
+    .await;
+
+    // An autosave last touched eight days ago is due for pruning
+    sqlx::query(
+        "INSERT INTO post_drafts (post_id, user_id, title, content, saved_at) VALUES ($1, $2, 'Stale', 'Stale', NOW() - INTERVAL '8 days')",
     )
+    .bind(other_post_id)
+    .bind(user.id)
     .execute(&pool)
     .await
     .unwrap();
@@ -304,7 +780,7 @@ async fn test_analytics_summary() {
     let mut user_with_permissions = user.clone();
     user_with_permissions.domain_permissions = vec![api::DomainPermission {
         domain_id: domain.id,
-        role: "viewer".to_string(),
+        role: "editor".to_string(),
     }];
 
     let app = create_admin_app(state)
@@ -312,15 +788,2532 @@ async fn test_analytics_summary() {
         .layer(Extension(user_with_permissions));
 
     let server = TestServer::new(app).unwrap();
-    let response = server.get("/analytics").await;
 
+    let response = server.get(&format!("/posts/{}/autosave", post_id)).await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    for content in ["First draft", "Second draft"] {
+        let response = server
+            .put(&format!("/posts/{}/autosave", post_id))
+            .json(&json!({ "title": "Work in progress", "content": content }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+    }
+
+    let response = server.get(&format!("/posts/{}/autosave", post_id)).await;
     assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["title"], "Work in progress");
+    assert_eq!(body["content"], "Second draft");
 
+    // The live post is untouched
+    let response = server.get(&format!("/posts/{}", post_id)).await;
     let body: Value = response.json();
-    let last_30_days = body.get("last_30_days").unwrap();
-    assert_eq!(last_30_days.get("page_views").unwrap().as_i64().unwrap(), 1);
-    assert_eq!(last_30_days.get("post_views").unwrap().as_i64().unwrap(), 1);
-    assert_eq!(last_30_days.get("searches").unwrap().as_i64().unwrap(), 1);
+    assert_eq!(body["title"], "Published Title");
+    assert_eq!(body["content"], "Published content");
+    assert_eq!(body["status"], "published");
+
+    let drafts: Vec<(i32, String)> =
+        sqlx::query_as("SELECT post_id, content FROM post_drafts ORDER BY post_id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(drafts, [(post_id, "Second draft".to_string())]);
+
+    let response = server
+        .put("/posts/999999/autosave")
+        .json(&json!({ "title": "", "content": "" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_post_translations() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "editor").await;
+
+    let post_id = create_test_post(
+        &pool,
+        domain.id,
+        "Hello",
+        "English content",
+        "Author",
+        "published",
+    )
+    .await;
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "editor".to_string(),
+    }];
+
+    let app = create_admin_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(user_with_permissions));
+
+    let server = TestServer::new(app).unwrap();
+
+    for title in ["Bonjour", "Salut"] {
+        let response = server
+            .put(&format!("/posts/{}/translations/FR", post_id))
+            .json(&json!({ "title": title, "content": "Contenu" }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let body: Value = response.json();
+        assert_eq!(body["locale"], "fr");
+    }
+
+    let response = server
+        .get(&format!("/posts/{}/translations", post_id))
+        .await;
+    let body: Value = response.json();
+    let translations = body.as_array().unwrap();
+    assert_eq!(translations.len(), 1);
+    assert_eq!(translations[0]["title"], "Salut");
+
+    // The post itself keeps its default language
+    let body: Value = server.get(&format!("/posts/{}", post_id)).await.json();
+    assert_eq!(body["title"], "Hello");
+
+    let response = server
+        .put(&format!("/posts/{}/translations/not-a-locale!", post_id))
+        .json(&json!({ "title": "Hallo", "content": "Inhalt" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    let response = server
+        .put("/posts/999999/translations/de")
+        .json(&json!({ "title": "Hallo", "content": "Inhalt" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    let response = server
+        .delete(&format!("/posts/{}/translations/fr", post_id))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_delete_post() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let user = create_test_user(&pool, "admin@test.com", "Admin User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "admin").await; // Admin permission needed for delete
+
+    let post_id = create_test_post(
+        &pool,
+        domain.id,
+        "Post to Delete",
+        "This will be deleted",
+        "Author",
+        "published",
+    )
+    .await;
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "admin".to_string(),
+    }];
+
+    let app = create_admin_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(user_with_permissions));
+
+    let server = TestServer::new(app).unwrap();
+    let response = server.delete(&format!("/posts/{}", post_id)).await;
+
+    assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_view_count_resync_corrects_drift() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let other = create_test_domain(&pool, "other.testblog.com", "Other Blog").await;
+    let post_id = create_test_post(
+        &pool,
+        domain.id,
+        "Counted",
+        "Content",
+        "Author",
+        "published",
+    )
+    .await;
+    let other_post_id = create_test_post(
+        &pool,
+        other.id,
+        "Elsewhere",
+        "Content",
+        "Author",
+        "published",
+    )
+    .await;
+    for _ in 0..3 {
+        sqlx::query(
+            "INSERT INTO analytics_events (domain_id, post_id, event_type) VALUES ($1, $2, 'post_view')",
+        )
+        .bind(domain.id)
+        .bind(post_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    sqlx::query("UPDATE posts SET view_count = 42")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let user = create_test_user(&pool, "admin@test.com", "Admin User", "user").await;
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "admin".to_string(),
+    }];
+    let app = create_admin_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(user_with_permissions));
+    let server = TestServer::new(app).unwrap();
+
+    let response = server.post("/posts/view-counts/sync").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(response.json::<Value>()["corrected"], 1);
+
+    let view_count = |id: i32| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, i64>("SELECT view_count FROM posts WHERE id = $1")
+                .bind(id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        }
+    };
+    assert_eq!(view_count(post_id).await, 3);
+    // Other domains are left alone
+    assert_eq!(view_count(other_post_id).await, 42);
+
+    // Nothing left to correct
+    let response = server.post("/posts/view-counts/sync").await;
+    assert_eq!(response.json::<Value>()["corrected"], 0);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_analytics_summary() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let user = create_test_user(&pool, "admin@test.com", "Admin User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+
+    // Create some analytics events
+    sqlx::query!(
+        r#"
+        INSERT INTO analytics_events (domain_id, event_type, path, ip_address, user_agent)
+        VALUES 
+            ($1, 'page_view', '/', '127.0.0.1', 'test-agent'),
+            ($1, 'post_view', '/posts/test', '127.0.0.1', 'test-agent'),
+            ($1, 'search', '/search', '127.0.0.2', 'test-agent')
+        "#,
+        domain.id
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+
+    let app = create_admin_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(user_with_permissions));
+
+    let server = TestServer::new(app).unwrap();
+    let response = server.get("/analytics").await;
+
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    let body: Value = response.json();
+    // Views are page and post views; searches don't count
+    assert_eq!(body["total_views"], 2);
+    assert_eq!(body["monthly_views"], 2);
+    assert_eq!(body["total_users"], 2);
+    assert_eq!(body["active_domains"], 1);
+    let domain_specific = body.get("domain_specific").unwrap();
+    assert_eq!(domain_specific["posts"], 0);
+    assert_eq!(domain_specific["views"], 2);
+    assert_eq!(domain_specific["visitors"], 2);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_analytics_summary_totals_only_cover_visible_domains() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let other = create_test_domain(&pool, "other.testblog.com", "Other Blog").await;
+    let viewer = create_test_user(&pool, "viewer@test.com", "Viewer User", "user").await;
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+    create_test_permission(&pool, viewer.id, domain.id, "viewer").await;
+
+    for (domain_id, posts) in [(domain.id, 1), (other.id, 2)] {
+        for i in 0..posts {
+            create_test_post(
+                &pool,
+                domain_id,
+                &format!("Post {} {}", domain_id, i),
+                "Content",
+                "Author",
+                "published",
+            )
+            .await;
+        }
+        sqlx::query(
+            "INSERT INTO analytics_events (domain_id, event_type, path, ip_address, user_agent) \
+             VALUES ($1, 'page_view', '/', '127.0.0.1', 'test-agent')",
+        )
+        .bind(domain_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let mut viewer_with_permissions = viewer.clone();
+    viewer_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+
+    let server = TestServer::new(
+        create_admin_app(state.clone())
+            .layer(Extension(domain.clone()))
+            .layer(Extension(viewer_with_permissions)),
+    )
+    .unwrap();
+    let response = server.get("/analytics").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["total_posts"], 1);
+    assert_eq!(body["total_views"], 1);
+    assert_eq!(body["active_domains"], 1);
+
+    let server = TestServer::new(
+        create_admin_app(state)
+            .layer(Extension(domain))
+            .layer(Extension(admin)),
+    )
+    .unwrap();
+    let response = server.get("/analytics").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["total_posts"], 3);
+    assert_eq!(body["total_views"], 2);
+    assert_eq!(body["active_domains"], 2);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_domain_menu_round_trip() {
+    use api::handlers::{HandlerModule, blog::BlogModule};
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let mut domain_admin = create_test_user(&pool, "admin@test.com", "Domain Admin", "user").await;
+    create_test_permission(&pool, domain_admin.id, domain.id, "admin").await;
+    domain_admin.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "admin".to_string(),
+    }];
+    let mut viewer = create_test_user(&pool, "viewer@test.com", "Viewer", "user").await;
+    create_test_permission(&pool, viewer.id, domain.id, "viewer").await;
+    viewer.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+
+    let server = TestServer::new(
+        create_admin_app(state.clone())
+            .layer(Extension(domain.clone()))
+            .layer(Extension(domain_admin)),
+    )
+    .unwrap();
+
+    let response = server.get("/domain/menu").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(response.json::<Value>(), json!({ "items": [] }));
+
+    let menu = json!({
+        "items": [
+            { "id": "home", "label": "Home", "url": "/" },
+            {
+                "id": "topics",
+                "label": "Topics",
+                "children": [
+                    { "label": "Rust", "slug": "rust-tips" },
+                    { "label": "GitHub", "url": "https://github.com/okakura" }
+                ]
+            }
+        ]
+    });
+    let response = server.put("/domain/menu").json(&menu).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(server.get("/domain/menu").await.json::<Value>(), menu);
+
+    // Cycles and over-deep menus are rejected and leave the menu alone
+    let response = server
+        .put("/domain/menu")
+        .json(&json!({
+            "items": [{ "id": "a", "label": "A", "children": [
+                { "id": "b", "label": "B", "children": [{ "id": "a", "label": "A", "url": "/a" }] }
+            ] }]
+        }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    let response = server
+        .put("/domain/menu")
+        .json(&json!({
+            "items": [{ "label": "1", "children": [{ "label": "2", "children": [
+                { "label": "3", "children": [{ "label": "4", "url": "/deep" }] }
+            ] }] }]
+        }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    assert_eq!(server.get("/domain/menu").await.json::<Value>(), menu);
+
+    // Viewers can read the menu but not change it
+    let viewer_server = TestServer::new(
+        create_admin_app(state.clone())
+            .layer(Extension(domain.clone()))
+            .layer(Extension(viewer)),
+    )
+    .unwrap();
+    assert_eq!(
+        viewer_server.get("/domain/menu").await.status_code(),
+        StatusCode::OK
+    );
+    let response = viewer_server.put("/domain/menu").json(&menu).await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+    // The public blog serves the same menu
+    let blog = TestServer::new(
+        BlogModule::routes()
+            .with_state(state)
+            .layer(Extension(domain)),
+    )
+    .unwrap();
+    let response = blog.get("/menu").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(response.json::<Value>(), menu);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_media_upload_and_delete() {
+    let pool = create_test_db().await;
+    let media_dir = tempfile::tempdir().unwrap();
+    let state = Arc::new(AppState {
+        storage: Arc::new(api::services::media::LocalStorage::new(
+            media_dir.path(),
+            "/media",
+        )),
+        ..AppState::new(pool.clone())
+    });
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "editor").await;
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "editor".to_string(),
+    }];
+
+    let app = create_admin_app(state)
+        .layer(Extension(domain.clone()))
+        .layer(Extension(user_with_permissions));
+
+    let server = TestServer::new(app).unwrap();
+
+    // Unsupported type is rejected before anything is stored
+    let form = MultipartForm::new().add_part(
+        "file",
+        Part::bytes(b"plain text".to_vec())
+            .file_name("notes.txt")
+            .mime_type("text/plain"),
+    );
+    let response = server.post("/media").multipart(form).await;
+    assert_eq!(response.status_code(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    // A real PNG header is accepted and stored under the domain
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+    let form = MultipartForm::new().add_part(
+        "file",
+        Part::bytes(png.clone())
+            .file_name("cover.png")
+            .mime_type("image/png"),
+    );
+    let response = server.post("/media").multipart(form).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    let body: Value = response.json();
+    let url = body.get("url").unwrap().as_str().unwrap();
+    assert!(url.starts_with(&format!("/media/{}/", domain.id)));
+    assert_eq!(body.get("filename").unwrap().as_str().unwrap(), "cover.png");
+
+    let stored = media_dir.path().join(url.trim_start_matches("/media/"));
+    assert_eq!(std::fs::read(&stored).unwrap(), png);
+
+    let listing: Value = server.get("/media").await.json();
+    assert_eq!(listing.as_array().unwrap().len(), 1);
+
+    let id = body.get("id").unwrap().as_i64().unwrap();
+    let response = server.delete(&format!("/media/{}", id)).await;
+    assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+    assert!(!stored.exists());
+
+    cleanup_test_db(&pool).await;
+}
+
+async fn insert_event_days_ago(pool: &sqlx::PgPool, domain_id: i32, days: i32, ip: &str) {
+    sqlx::query(
+        r#"
+        INSERT INTO analytics_events (domain_id, event_type, path, ip_address, created_at)
+        VALUES ($1, 'page_view', '/', $2::inet, NOW() - $3 * INTERVAL '1 day')
+        "#,
+    )
+    .bind(domain_id)
+    .bind(ip)
+    .bind(days)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_delete_events_before_respects_cutoff() {
+    let pool = create_test_db().await;
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    for _ in 0..5 {
+        insert_event_days_ago(&pool, domain.id, 40, "10.0.0.1").await;
+    }
+    for _ in 0..3 {
+        insert_event_days_ago(&pool, domain.id, 5, "10.0.0.1").await;
+    }
+
+    // Batches smaller than the backlog still delete everything past the cutoff
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(30);
+    let deleted = api::services::retention::delete_events_before(&pool, cutoff, 2)
+        .await
+        .unwrap();
+    assert_eq!(deleted, 5);
+
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM analytics_events")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 3);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_prune_analytics_endpoint() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    insert_event_days_ago(&pool, domain.id, 400, "10.0.0.1").await;
+    insert_event_days_ago(&pool, domain.id, 400, "10.0.0.2").await;
+    insert_event_days_ago(&pool, domain.id, 10, "10.0.0.1").await;
+
+    // Domain-level users cannot prune
+    let editor = create_test_user(&pool, "editor@test.com", "Editor", "user").await;
+    let server = TestServer::new(create_admin_app(state.clone()).layer(Extension(editor))).unwrap();
+    let response = server.post("/analytics/prune").await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+    let server = TestServer::new(create_admin_app(state).layer(Extension(admin))).unwrap();
+    let response = server.post("/analytics/prune").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    let body: Value = response.json();
+    assert_eq!(body["deleted_events"].as_u64().unwrap(), 2);
+
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM analytics_events")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 1);
+
+    // The pruned day survives as a rollup
+    let (events, unique_visitors): (i64, i64) = sqlx::query_as(
+        "SELECT events, unique_visitors FROM analytics_daily_summary WHERE domain_id = $1",
+    )
+    .bind(domain.id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!((events, unique_visitors), (2, 2));
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_update_domain_features() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let user = create_test_user(&pool, "admin@test.com", "Admin User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "admin").await;
+
+    let mut domain_admin = user.clone();
+    domain_admin.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "admin".to_string(),
+    }];
+
+    let app = create_admin_app(state)
+        .layer(Extension(domain.clone()))
+        .layer(Extension(domain_admin));
+    let server = TestServer::new(app).unwrap();
+
+    let response = server.get("/domain/features").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["search"], true);
+
+    let response = server
+        .put("/domain/features")
+        .json(&json!({ "search": false }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["search"], false);
+    assert_eq!(body["comments"], true);
+
+    let stored: Value = sqlx::query_scalar("SELECT features FROM domains WHERE id = $1")
+        .bind(domain.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(stored, json!({ "search": false }));
+
+    // Unknown features are rejected
+    let response = server
+        .put("/domain/features")
+        .json(&json!({ "teleport": true }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_impersonation_token_acts_as_target() {
+    if std::env::var("JWT_SECRET").is_err() {
+        // SAFETY: tests in this file run serially
+        unsafe { std::env::set_var("JWT_SECRET", "test-secret") };
+    }
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let post_id = create_test_post(
+        &pool,
+        domain.id,
+        "Support Post",
+        "Content",
+        "Author",
+        "published",
+    )
+    .await;
+    let viewer = create_test_user(&pool, "viewer@test.com", "Viewer", "user").await;
+    create_test_permission(&pool, viewer.id, domain.id, "viewer").await;
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+
+    let server =
+        TestServer::new(create_admin_app(state.clone()).layer(Extension(admin.clone()))).unwrap();
+    let response = server.post(&format!("/impersonate/{}", viewer.id)).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["impersonator_id"], admin.id);
+    let token = body["token"].as_str().unwrap().to_string();
+
+    // Admins cannot impersonate themselves
+    let response = server.post(&format!("/impersonate/{}", admin.id)).await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    // The token authenticates as the viewer, with the viewer's permissions
+    let app = create_admin_app(state.clone())
+        .layer(middleware::from_fn_with_state(state, auth_middleware))
+        .layer(Extension(domain));
+    let server = TestServer::new(app).unwrap();
+    let auth = HeaderValue::from_str(&format!("Bearer {}", token)).unwrap();
+
+    let response = server
+        .get("/posts")
+        .add_header("authorization", auth.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    let response = server
+        .delete(&format!("/posts/{}", post_id))
+        .add_header("authorization", auth)
+        .await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+    // Both the start and every impersonated request are audited
+    let entries: Vec<(Option<i32>, Option<i32>, String)> =
+        sqlx::query_as("SELECT actor_id, impersonator_id, action FROM audit_log ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(
+        entries[0],
+        (Some(admin.id), None, "impersonation.start".to_string())
+    );
+    assert!(entries[1..].iter().all(
+        |(actor, impersonator, _)| *actor == Some(viewer.id) && *impersonator == Some(admin.id)
+    ));
+    assert_eq!(entries[2].2, format!("DELETE /posts/{}", post_id));
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_admin_referrer_breakdown_reflects_events() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let first = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let second = create_test_domain(&pool, "other.testblog.com", "Other Blog").await;
+
+    let events = [
+        (first.id, None),
+        (first.id, Some("https://www.google.com/search?q=rust")),
+        (second.id, Some("https://duckduckgo.com/")),
+        (second.id, Some("https://m.facebook.com/story")),
+        (second.id, Some("https://example.com/links")),
+        (second.id, Some("https://example.com/links")),
+    ];
+    for (domain_id, referrer) in events {
+        sqlx::query(
+            r#"
+            INSERT INTO analytics_events (domain_id, event_type, path, ip_address, referrer)
+            VALUES ($1, 'page_view', '/', '10.0.0.1'::inet, $2)
+            "#,
+        )
+        .bind(domain_id)
+        .bind(referrer)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+    let server = TestServer::new(create_admin_app(state).layer(Extension(admin))).unwrap();
+    let response = server.get("/analytics/referrers").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    let body: Value = response.json();
+    assert_eq!(
+        body["referrer_types"],
+        json!({
+            "direct": 1,
+            "search_engines": 2,
+            "social_media": 1,
+            "other_websites": 2
+        })
+    );
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_domain_export_archive() {
+    use api::services::domain_export::{
+        DOMAIN_MEMBER, EVENTS_MEMBER, MANIFEST_MEMBER, Manifest, PERMISSIONS_MEMBER, POSTS_MEMBER,
+        SCHEMA_VERSION,
+    };
+    use std::io::Read;
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "export.testblog.com", "Export Test Blog").await;
+    let other = create_test_domain(&pool, "other.testblog.com", "Other Blog").await;
+    for title in ["First, Post", "Second Post"] {
+        create_test_post(&pool, domain.id, title, "Content", "Author", "published").await;
+    }
+    create_test_post(
+        &pool,
+        other.id,
+        "Elsewhere",
+        "Content",
+        "Author",
+        "published",
+    )
+    .await;
+    for path in ["/", "/posts/first", "/search"] {
+        sqlx::query(
+            "INSERT INTO analytics_events (domain_id, event_type, path, ip_address) VALUES ($1, 'page_view', $2, '127.0.0.1')",
+        )
+        .bind(domain.id)
+        .bind(path)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    let editor = create_test_user(&pool, "editor@test.com", "Editor", "user").await;
+    create_test_permission(&pool, editor.id, domain.id, "editor").await;
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+
+    let server = TestServer::new(create_admin_app(state).layer(Extension(admin))).unwrap();
+    let response = server.get(&format!("/domains/{}/export", domain.id)).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(response.header("content-type"), "application/zip");
+
+    let mut archive =
+        zip::ZipArchive::new(std::io::Cursor::new(response.as_bytes().to_vec())).unwrap();
+    let names = archive.file_names().map(String::from).collect::<Vec<_>>();
+    for member in [
+        DOMAIN_MEMBER,
+        POSTS_MEMBER,
+        EVENTS_MEMBER,
+        PERMISSIONS_MEMBER,
+        MANIFEST_MEMBER,
+    ] {
+        assert!(names.iter().any(|n| n == member), "missing {member}");
+    }
+
+    let mut read_member = |name: &str| {
+        let mut contents = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    };
+
+    let manifest: Manifest = serde_json::from_str(&read_member(MANIFEST_MEMBER)).unwrap();
+    assert_eq!(manifest.schema_version, SCHEMA_VERSION);
+    assert_eq!(manifest.domain_id, domain.id);
+    assert_eq!(manifest.counts.posts, 2);
+    assert_eq!(manifest.counts.analytics_events, 3);
+    assert_eq!(manifest.counts.permissions, 1);
+
+    let posts: Vec<Value> = serde_json::from_str(&read_member(POSTS_MEMBER)).unwrap();
+    assert_eq!(posts.len(), manifest.counts.posts);
+    assert_eq!(posts[0]["title"], "First, Post");
+
+    // Header plus one line per event
+    let events = read_member(EVENTS_MEMBER);
+    assert_eq!(events.lines().count(), manifest.counts.analytics_events + 1);
+
+    let permissions: Vec<Value> = serde_json::from_str(&read_member(PERMISSIONS_MEMBER)).unwrap();
+    assert_eq!(permissions.len(), manifest.counts.permissions);
+    assert_eq!(permissions[0]["role"], "editor");
+
+    let settings: Value = serde_json::from_str(&read_member(DOMAIN_MEMBER)).unwrap();
+    assert_eq!(settings["hostname"], "export.testblog.com");
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_import_posts() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let user = create_test_user(&pool, "admin@test.com", "Domain Admin", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "admin").await;
+    // create_test_post derives the slug "existing-post" from the title
+    create_test_post(
+        &pool,
+        domain.id,
+        "Existing Post",
+        "Content",
+        "Author",
+        "published",
+    )
+    .await;
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "admin".to_string(),
+    }];
+    let app = create_admin_app(state)
+        .layer(Extension(domain.clone()))
+        .layer(Extension(user_with_permissions));
+    let server = TestServer::new(app).unwrap();
+
+    let response = server
+        .post("/posts/import")
+        .json(&json!([
+            {
+                "title": "Imported Post",
+                "content": "Migrated from another platform",
+                "category": "Technology",
+                "slug": "existing-post",
+                "status": "published",
+                "created_at": "2020-05-01T12:00:00Z"
+            },
+            {
+                "title": "",
+                "content": "No title",
+                "category": "Technology"
+            },
+            {
+                "title": "Fresh Post",
+                "content": "Brand new",
+                "category": "Technology"
+            }
+        ]))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    let body: Value = response.json();
+    assert_eq!(body["imported"], 2);
+    assert_eq!(body["failed"], 1);
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results[0]["slug"], "existing-post-2");
+    assert!(results[1]["error"].is_string());
+    assert!(results[1].get("id").is_none());
+    assert_eq!(results[2]["slug"], "fresh-post");
+
+    let created_at: chrono::DateTime<chrono::Utc> =
+        sqlx::query_scalar("SELECT created_at FROM posts WHERE domain_id = $1 AND slug = $2")
+            .bind(domain.id)
+            .bind("existing-post-2")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(created_at.to_rfc3339(), "2020-05-01T12:00:00+00:00");
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_list_users_pagination() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+    for i in 0..3 {
+        create_test_user(&pool, &format!("user{i}@test.com"), "User", "user").await;
+    }
+
+    let server = TestServer::new(create_admin_app(state).layer(Extension(admin))).unwrap();
+
+    let response = server.get("/users?page=2&per_page=3").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["total"], 4);
+    assert_eq!(body["page"], 2);
+    assert_eq!(body["users"].as_array().unwrap().len(), 1);
+
+    // Oversized pages are clamped, nonsense is rejected
+    let response = server.get("/users?per_page=1000").await;
+    assert_eq!(response.json::<Value>()["per_page"], 100);
+    let response = server.get("/users?page=0").await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    let response = server.get("/users?per_page=abc").await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_soft_deleted_user_is_hidden_until_restored() {
+    if std::env::var("JWT_SECRET").is_err() {
+        // SAFETY: tests in this file run serially
+        unsafe { std::env::set_var("JWT_SECRET", "test-secret") };
+    }
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+    let user = create_test_user(&pool, "leaver@test.com", "Leaver", "user").await;
+    sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+        .bind(bcrypt::hash("password123", 4).unwrap())
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let server =
+        TestServer::new(create_admin_app(state.clone()).layer(Extension(admin.clone()))).unwrap();
+    let auth = TestServer::new(
+        Router::new()
+            .nest("/auth", api::handlers::auth::auth_router())
+            .with_state(state.clone()),
+    )
+    .unwrap();
+    let credentials = json!({ "email": "leaver@test.com", "password": "password123" });
+    let token = auth.post("/auth/login").json(&credentials).await.json::<Value>()["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let old_bearer = HeaderValue::from_str(&format!("Bearer {token}")).unwrap();
+
+    // Admins still cannot delete themselves
+    let response = server.delete(&format!("/users/{}", admin.id)).await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    let response = server.delete(&format!("/users/{}", user.id)).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let response = server.delete(&format!("/users/{}", user.id)).await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    let body: Value = server.get("/users").await.json();
+    assert_eq!(body["total"], 1);
+    let body: Value = server.get("/users?include_deleted=true").await.json();
+    assert_eq!(body["total"], 2);
+
+    let response = auth.post("/auth/login").json(&credentials).await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    // Restoring is platform admin only
+    let viewer =
+        TestServer::new(create_admin_app(state.clone()).layer(Extension(user.clone()))).unwrap();
+    let response = viewer.post(&format!("/users/{}/restore", user.id)).await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+    let response = server.post(&format!("/users/{}/restore", user.id)).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert!(response.json::<Value>()["deleted_at"].is_null());
+
+    let body: Value = server.get("/users").await.json();
+    assert_eq!(body["total"], 2);
+    let response = auth.post("/auth/login").json(&credentials).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    // Tokens from before the delete stay revoked
+    let response = auth
+        .get("/auth/verify")
+        .add_header("authorization", old_bearer)
+        .await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_password_reuse_and_forced_change() {
+    if std::env::var("JWT_SECRET").is_err() {
+        // SAFETY: tests in this file run serially
+        unsafe { std::env::set_var("JWT_SECRET", "test-secret") };
+    }
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+
+    let server =
+        TestServer::new(create_admin_app(state.clone()).layer(Extension(admin.clone()))).unwrap();
+    let auth = TestServer::new(
+        Router::new()
+            .nest("/auth", api::handlers::auth::auth_router())
+            .with_state(state.clone()),
+    )
+    .unwrap();
+    let guarded = TestServer::new(
+        create_admin_app(state.clone())
+            .layer(middleware::from_fn_with_state(state, auth_middleware))
+            .layer(Extension(domain.clone())),
+    )
+    .unwrap();
+
+    let response = server
+        .post("/users")
+        .json(&json!({
+            "email": "newhire@test.com",
+            "name": "New Hire",
+            "password": "Initial!Pass1",
+            "role": "domain_user",
+            "domain_permissions": [{ "domain_id": domain.id, "role": "viewer" }],
+            "must_change_password": true
+        }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["must_change_password"], true);
+    let user_id = body["id"].as_i64().unwrap();
+
+    // Neither the current password nor a recent one can be set again
+    let response = server
+        .put(&format!("/users/{}", user_id))
+        .json(&json!({ "password": "Initial!Pass1" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    let response = server
+        .put(&format!("/users/{}", user_id))
+        .json(&json!({ "password": "Second!Pass2" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let response = server
+        .put(&format!("/users/{}", user_id))
+        .json(&json!({ "password": "Initial!Pass1" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    let response = auth
+        .post("/auth/login")
+        .json(&json!({ "email": "newhire@test.com", "password": "Second!Pass2" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["user"]["must_change_password"], true);
+    let bearer =
+        HeaderValue::from_str(&format!("Bearer {}", body["token"].as_str().unwrap())).unwrap();
+
+    // Normal routes stay closed until the password is changed
+    let response = guarded
+        .get("/posts")
+        .add_header("authorization", bearer.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    assert_eq!(
+        response.json::<Value>()["error"],
+        "password_change_required"
+    );
+
+    let response = auth
+        .post("/auth/change-password")
+        .add_header("authorization", bearer.clone())
+        .json(&json!({ "current_password": "Second!Pass2", "new_password": "Initial!Pass1" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.json::<Value>()["error"], "password_reused");
+
+    let response = auth
+        .post("/auth/change-password")
+        .add_header("authorization", bearer.clone())
+        .json(&json!({ "current_password": "Second!Pass2", "new_password": "Third!Pass3" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    let response = guarded
+        .get("/posts")
+        .add_header("authorization", bearer)
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_bulk_domain_permissions() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let other = create_test_domain(&pool, "other.testblog.com", "Other Blog").await;
+    let mut domain_admin = create_test_user(&pool, "admin@test.com", "Domain Admin", "user").await;
+    create_test_permission(&pool, domain_admin.id, domain.id, "admin").await;
+    domain_admin.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "admin".to_string(),
+    }];
+    let newcomer = create_test_user(&pool, "new@test.com", "Newcomer", "user").await;
+    let leaver = create_test_user(&pool, "leaver@test.com", "Leaver", "user").await;
+    create_test_permission(&pool, leaver.id, domain.id, "editor").await;
+    let promoted = create_test_user(&pool, "promoted@test.com", "Promoted", "user").await;
+    create_test_permission(&pool, promoted.id, domain.id, "viewer").await;
+
+    let server =
+        TestServer::new(create_admin_app(state).layer(Extension(domain_admin.clone()))).unwrap();
+
+    // One bad role rejects the whole batch
+    let response = server
+        .post(&format!("/domains/{}/permissions/bulk", domain.id))
+        .json(&json!({ "permissions": [
+            { "user_id": newcomer.id, "role": "editor" },
+            { "user_id": leaver.id, "role": "owner" },
+        ]}))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    let count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM user_domain_permissions WHERE user_id = $1")
+            .bind(newcomer.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(count, 0);
+
+    // Adds, removals and changes together
+    let response = server
+        .post(&format!("/domains/{}/permissions/bulk", domain.id))
+        .json(&json!({ "permissions": [
+            { "user_id": newcomer.id, "role": "editor" },
+            { "user_id": leaver.id, "role": "none" },
+            { "user_id": promoted.id, "role": "admin" },
+        ]}))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    let roles: Vec<(i64, &str)> = body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| (p["user_id"].as_i64().unwrap(), p["role"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        roles,
+        vec![
+            (domain_admin.id as i64, "admin"),
+            (newcomer.id as i64, "editor"),
+            (promoted.id as i64, "admin"),
+        ]
+    );
+
+    // Admin of one domain cannot manage another
+    let response = server
+        .post(&format!("/domains/{}/permissions/bulk", other.id))
+        .json(&json!({ "permissions": [{ "user_id": newcomer.id, "role": "viewer" }] }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_super_admin_passes_same_extractors_as_platform_admin() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+
+    for (role, expected) in [
+        ("platform_admin", StatusCode::OK),
+        ("super_admin", StatusCode::OK),
+        ("user", StatusCode::FORBIDDEN),
+    ] {
+        let user = create_test_user(&pool, &format!("{role}@test.com"), "Admin", role).await;
+        let app = create_admin_app(state.clone())
+            .layer(Extension(domain.clone()))
+            .layer(Extension(user));
+        let server = TestServer::new(app).unwrap();
+
+        // RequireDomainAdmin without any domain permission rows
+        let response = server
+            .put("/domain/features")
+            .json(&json!({ "search": true }))
+            .await;
+        assert_eq!(response.status_code(), expected, "{role}");
+
+        // RequirePlatformAdmin
+        let response = server.get("/domains").await;
+        assert_eq!(response.status_code(), expected, "{role}");
+        let response = server.get("/users").await;
+        assert_eq!(response.status_code(), expected, "{role}");
+    }
+
+    cleanup_test_db(&pool).await;
+}
+
+#[derive(Default)]
+struct RecordingNotifier {
+    sent: std::sync::Mutex<Vec<api::services::alerts::AlertNotification>>,
+}
+
+impl api::services::alerts::AlertNotifier for RecordingNotifier {
+    async fn notify(
+        &self,
+        _alert: &api::services::alerts::Alert,
+        notification: &api::services::alerts::AlertNotification,
+    ) -> Result<(), String> {
+        self.sent.lock().unwrap().push(notification.clone());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_analytics_alerts_fire_once_per_crossing() {
+    use api::services::alerts::run_alerts;
+    use chrono::{Duration, Utc};
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let mut domain_admin = create_test_user(&pool, "admin@test.com", "Domain Admin", "user").await;
+    create_test_permission(&pool, domain_admin.id, domain.id, "admin").await;
+    domain_admin.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "admin".to_string(),
+    }];
+
+    let server = TestServer::new(
+        create_admin_app(state)
+            .layer(Extension(domain.clone()))
+            .layer(Extension(domain_admin)),
+    )
+    .unwrap();
+
+    let response = server
+        .post("/alerts")
+        .json(&json!({
+            "metric": "page_views",
+            "comparison": "above",
+            "threshold": 2,
+            "window_minutes": 60,
+            "cooldown_minutes": 30,
+            "webhook_url": "https://hooks.example.com/traffic",
+        }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let alert: Value = response.json();
+    assert_eq!(alert["is_firing"], false);
+
+    let response = server
+        .post("/alerts")
+        .json(&json!({ "metric": "bounces", "comparison": "above", "threshold": 2, "email": "a@b.com" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    assert!(response.json::<Value>()["field_errors"]["metric"].is_array());
+
+    let domain_id = domain.id;
+    let add_page_views = |count: usize| {
+        let pool = pool.clone();
+        async move {
+            for _ in 0..count {
+                sqlx::query(
+                    "INSERT INTO analytics_events (domain_id, event_type, path, ip_address, user_agent) \
+                     VALUES ($1, 'page_view', '/', '127.0.0.1', 'test-agent')",
+                )
+                .bind(domain_id)
+                .execute(&pool)
+                .await
+                .unwrap();
+            }
+        }
+    };
+
+    let notifier = RecordingNotifier::default();
+    let now = Utc::now() + Duration::seconds(1);
+
+    // Two views reach the threshold without crossing it
+    add_page_views(2).await;
+    assert_eq!(run_alerts(&pool, &notifier, now).await.unwrap(), 0);
+
+    // The third crosses it and notifies once, later checks stay quiet
+    add_page_views(1).await;
+    assert_eq!(run_alerts(&pool, &notifier, now).await.unwrap(), 1);
+    assert_eq!(run_alerts(&pool, &notifier, now).await.unwrap(), 0);
+    {
+        let sent = notifier.sent.lock().unwrap();
+        assert_eq!(sent[0].value, 3);
+        assert_eq!(sent[0].hostname, "admin.testblog.com");
+    }
+
+    // Dropping back under and crossing again within the cooldown is suppressed
+    sqlx::query("DELETE FROM analytics_events WHERE domain_id = $1")
+        .bind(domain.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(run_alerts(&pool, &notifier, now).await.unwrap(), 0);
+    add_page_views(3).await;
+    assert_eq!(run_alerts(&pool, &notifier, now).await.unwrap(), 0);
+
+    let response = server.get(&format!("/alerts/{}", alert["id"])).await;
+    assert_eq!(response.json::<Value>()["is_firing"], true);
+
+    let response = server.delete(&format!("/alerts/{}", alert["id"])).await;
+    assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+    assert_eq!(server.get("/alerts").await.json::<Value>(), json!([]));
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_support_can_reset_password_and_revoke_sessions() {
+    if std::env::var("JWT_SECRET").is_err() {
+        // SAFETY: tests in this file run serially
+        unsafe { std::env::set_var("JWT_SECRET", "test-secret") };
+    }
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+    let user = create_test_user(&pool, "locked@test.com", "Locked Out", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "admin").await;
+    sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+        .bind(bcrypt::hash("password123", 4).unwrap())
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let server =
+        TestServer::new(create_admin_app(state.clone()).layer(Extension(admin.clone()))).unwrap();
+    let auth = TestServer::new(
+        Router::new()
+            .nest("/auth", api::handlers::auth::auth_router())
+            .with_state(state.clone()),
+    )
+    .unwrap();
+    let guarded = TestServer::new(
+        create_admin_app(state.clone())
+            .layer(middleware::from_fn_with_state(state, auth_middleware))
+            .layer(Extension(domain)),
+    )
+    .unwrap();
+
+    let response = auth
+        .post("/auth/login")
+        .json(&json!({ "email": "locked@test.com", "password": "password123" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let token = response.json::<Value>()["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let bearer = HeaderValue::from_str(&format!("Bearer {}", token)).unwrap();
+    let response = guarded
+        .get("/posts")
+        .add_header("authorization", bearer.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    // Revoking sessions turns the existing token away everywhere
+    let response = server
+        .post(&format!("/users/{}/revoke-sessions", user.id))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+    let response = guarded
+        .get("/posts")
+        .add_header("authorization", bearer.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+    let response = auth
+        .get("/auth/verify")
+        .add_header("authorization", bearer)
+        .await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    assert_eq!(
+        server
+            .post("/users/999999/revoke-sessions")
+            .await
+            .status_code(),
+        StatusCode::NOT_FOUND
+    );
+
+    // Without ?email=true the reset token comes back for support to pass on
+    let response = server
+        .post(&format!("/users/{}/reset-password", user.id))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["emailed"], false);
+    let reset_token = body["token"].as_str().unwrap().to_string();
+
+    let response = auth
+        .post("/auth/reset-password")
+        .json(&json!({ "token": reset_token, "new_password": "Recovered!Pass1" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    // Tokens work once
+    let response = auth
+        .post("/auth/reset-password")
+        .json(&json!({ "token": reset_token, "new_password": "Another!Pass2" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    let response = auth
+        .post("/auth/login")
+        .json(&json!({ "email": "locked@test.com", "password": "Recovered!Pass1" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    let actions: Vec<(Option<i32>, String)> =
+        sqlx::query_as("SELECT actor_id, action FROM audit_log ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert!(actions.contains(&(Some(admin.id), "user.sessions_revoked".to_string())));
+    assert!(actions.contains(&(Some(admin.id), "user.password_reset_issued".to_string())));
+    assert!(actions.contains(&(Some(user.id), "user.password_reset".to_string())));
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_post_calendar_groups_scheduled_and_published_posts_by_day() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let other = create_test_domain(&pool, "other.testblog.com", "Other Blog").await;
+    let user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "editor").await;
+
+    let published = create_test_post(
+        &pool,
+        domain.id,
+        "Published Post",
+        "Content",
+        "Editor",
+        "published",
+    )
+    .await;
+    let elsewhere = create_test_post(
+        &pool,
+        other.id,
+        "Other Domain Post",
+        "Content",
+        "Editor",
+        "published",
+    )
+    .await;
+    create_test_post(&pool, domain.id, "Draft Post", "Content", "Editor", "draft").await;
+    sqlx::query("UPDATE posts SET created_at = '2030-05-03T10:00:00Z' WHERE id = ANY($1) OR status = 'draft'")
+        .bind(vec![published, elsewhere])
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "editor".to_string(),
+    }];
+    let server = TestServer::new(
+        create_admin_app(state)
+            .layer(Extension(domain))
+            .layer(Extension(user_with_permissions)),
+    )
+    .unwrap();
+
+    // Scheduling needs a publish time
+    let mut scheduled = json!({
+        "title": "Scheduled Post",
+        "content": "Content",
+        "category": "Technology",
+        "status": "scheduled",
+    });
+    let response = server.post("/posts").json(&scheduled).await;
+    assert_eq!(response.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+    scheduled["publish_at"] = json!("2030-05-20T09:00:00Z");
+    let response = server.post("/posts").json(&scheduled).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(
+        response.json::<Value>()["publish_at"],
+        "2030-05-20T09:00:00Z"
+    );
+
+    let response = server.get("/posts/calendar?month=2030-05").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["month"], "2030-05");
+    assert_eq!(body["total"], 2);
+    let days = body["days"].as_array().unwrap();
+    assert_eq!(days.len(), 31);
+
+    // The published post sits on its creation day, the scheduled one on its publish day
+    assert_eq!(days[2]["date"], "2030-05-03");
+    assert_eq!(days[2]["count"], 1);
+    assert_eq!(days[2]["posts"][0]["title"], "Published Post");
+    assert_eq!(days[19]["date"], "2030-05-20");
+    assert_eq!(days[19]["count"], 1);
+    assert_eq!(days[19]["posts"][0]["title"], "Scheduled Post");
+    assert_eq!(days[19]["posts"][0]["status"], "scheduled");
+    assert_eq!(days[0]["count"], 0);
+
+    let body: Value = server.get("/posts/calendar?month=2030-06").await.json();
+    assert_eq!(body["total"], 0);
+
+    assert_eq!(
+        server.get("/posts/calendar?month=May").await.status_code(),
+        StatusCode::BAD_REQUEST
+    );
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_html_content_is_sanitized_on_save() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let mut domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "editor").await;
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "editor".to_string(),
+    }];
+
+    let unsafe_html =
+        r#"<p onmouseover="steal()">Hello <em>world</em></p><script>alert(1)</script>"#;
+    let post = |title: &str, content: &str| {
+        json!({
+            "title": title,
+            "content": content,
+            "category": "Technology",
+            "status": "draft"
+        })
+    };
+
+    // Markdown domains (the default) store content exactly as written
+    let markdown = "> Quote\n\n<script>alert(1)</script>";
+    let server = TestServer::new(
+        create_admin_app(state.clone())
+            .layer(Extension(domain.clone()))
+            .layer(Extension(user_with_permissions.clone())),
+    )
+    .unwrap();
+    let response = server
+        .post("/posts")
+        .json(&post("Markdown Post", markdown))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(response.json::<Value>()["content"], markdown);
+
+    // HTML domains lose scripts and event handlers, keeping the formatting
+    domain.theme_config = json!({ "content_config": { "format": "html" } });
+    let server = TestServer::new(
+        create_admin_app(state)
+            .layer(Extension(domain))
+            .layer(Extension(user_with_permissions)),
+    )
+    .unwrap();
+    let response = server
+        .post("/posts")
+        .json(&post("Formatted Post", unsafe_html))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["content"], "<p>Hello <em>world</em></p>");
+
+    let response = server
+        .put(&format!("/posts/{}", body["id"]))
+        .json(&post("Formatted Post", unsafe_html))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    let stored: String = sqlx::query_scalar("SELECT content FROM posts WHERE id = $1")
+        .bind(body["id"].as_i64().unwrap() as i32)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(!stored.contains("script"));
+    assert!(!stored.contains("onmouseover"));
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_get_admin_post_outside_current_domain() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let sister = create_test_domain(&pool, "sister.testblog.com", "Sister Blog").await;
+    let stranger = create_test_domain(&pool, "stranger.testblog.com", "Stranger Blog").await;
+    let user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "editor").await;
+    create_test_permission(&pool, user.id, sister.id, "viewer").await;
+
+    let own = create_test_post(&pool, domain.id, "Own", "Own content", "Editor", "draft").await;
+    let sister_post = create_test_post(
+        &pool,
+        sister.id,
+        "Sister",
+        "Sister content",
+        "Editor",
+        "draft",
+    )
+    .await;
+    let stranger_post = create_test_post(
+        &pool,
+        stranger.id,
+        "Stranger",
+        "Stranger content",
+        "Someone",
+        "draft",
+    )
+    .await;
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![
+        api::DomainPermission {
+            domain_id: domain.id,
+            role: "editor".to_string(),
+        },
+        api::DomainPermission {
+            domain_id: sister.id,
+            role: "viewer".to_string(),
+        },
+    ];
+
+    let app = create_admin_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(user_with_permissions));
+    let server = TestServer::new(app).unwrap();
+
+    let response = server.get(&format!("/posts/{own}")).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    // In a domain the user can also work in: tell them it's elsewhere
+    let response = server.get(&format!("/posts/{sister_post}")).await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+    // In a domain they can't see: indistinguishable from a missing post
+    let response = server.get(&format!("/posts/{stranger_post}")).await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    let response = server
+        .get(&format!("/posts/{}", stranger_post + 1000))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_admin_post_responses_share_one_shape() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "editor").await;
+    let post_id = create_test_post(&pool, domain.id, "Shape", "Body", "Editor", "draft").await;
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "editor".to_string(),
+    }];
+    let app = create_admin_app(state)
+        .layer(Extension(domain.clone()))
+        .layer(Extension(user_with_permissions));
+    let server = TestServer::new(app).unwrap();
+
+    let listed = server.get("/posts").await;
+    assert_eq!(listed.status_code(), StatusCode::OK);
+    let listed = listed.json::<Value>()[0].clone();
+
+    let single = server.get(&format!("/posts/{post_id}")).await;
+    assert_eq!(single.status_code(), StatusCode::OK);
+    let single: Value = single.json();
+
+    let created = server
+        .post("/posts")
+        .json(&json!({
+            "title": "Created",
+            "content": "Created content",
+            "category": "Technology",
+            "slug": "created",
+            "status": "draft"
+        }))
+        .await;
+    assert_eq!(created.status_code(), StatusCode::OK);
+    let created: Value = created.json();
+
+    // Fields every former consumer read, including `domain_name`
+    for post in [&listed, &single, &created] {
+        for field in [
+            "id",
+            "title",
+            "content",
+            "author",
+            "category",
+            "slug",
+            "status",
+            "domain_id",
+            "domain_name",
+            "created_at",
+            "updated_at",
+            "next_statuses",
+        ] {
+            assert!(post.get(field).is_some(), "missing {field} in {post}");
+        }
+        assert_eq!(post["domain_id"], domain.id);
+        assert_eq!(post["domain_name"], "Admin Test Blog");
+    }
+    assert_eq!(listed["id"], post_id);
+    assert_eq!(single["id"], post_id);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_clone_post_creates_separate_draft() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let user = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "editor").await;
+    let original = create_test_post(
+        &pool,
+        domain.id,
+        "Launch Notes",
+        "What shipped this week",
+        "Author",
+        "published",
+    )
+    .await;
+    sqlx::query("UPDATE posts SET view_count = 42 WHERE id = $1")
+        .bind(original)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "editor".to_string(),
+    }];
+    let app = create_admin_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(user_with_permissions));
+    let server = TestServer::new(app).unwrap();
+
+    let first = server.post(&format!("/posts/{original}/clone")).await;
+    assert_eq!(first.status_code(), StatusCode::OK);
+    let first: Value = first.json();
+    assert_ne!(first["id"], original);
+    assert_eq!(first["title"], "Copy of Launch Notes");
+    assert_eq!(first["content"], "What shipped this week");
+    assert_eq!(first["category"], "Technology");
+    assert_eq!(first["status"], "draft");
+    assert_eq!(first["slug"], "copy-of-launch-notes");
+
+    // A second copy of the same post gets its own slug
+    let second: Value = server
+        .post(&format!("/posts/{original}/clone"))
+        .await
+        .json();
+    assert_eq!(second["slug"], "copy-of-launch-notes-2");
+
+    let view_count: i64 = sqlx::query_scalar("SELECT view_count FROM posts WHERE id = $1")
+        .bind(first["id"].as_i64().unwrap() as i32)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(view_count, 0);
+
+    // The original is untouched
+    let response = server.get(&format!("/posts/{original}")).await;
+    let body: Value = response.json();
+    assert_eq!(body["title"], "Launch Notes");
+    assert_eq!(body["slug"], "launch-notes");
+    assert_eq!(body["status"], "published");
+
+    let response = server.post("/posts/999999/clone").await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_post_quota_and_usage() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let editor = create_test_user(&pool, "editor@test.com", "Editor User", "user").await;
+    create_test_permission(&pool, editor.id, domain.id, "editor").await;
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+    create_test_post(&pool, domain.id, "First", "Content", "Editor", "draft").await;
+    sqlx::query(
+        "INSERT INTO media (domain_id, storage_key, filename, content_type, size_bytes)
+         VALUES ($1, 'a.png', 'a.png', 'image/png', 1000), ($1, 'b.png', 'b.png', 'image/png', 500)",
+    )
+    .bind(domain.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let platform =
+        TestServer::new(create_admin_app(state.clone()).layer(Extension(admin))).unwrap();
+    let response = platform
+        .put(&format!("/domains/{}/quota", domain.id))
+        .json(&json!({ "max_posts": 2, "max_media_bytes": 10000 }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let response = platform
+        .put(&format!("/domains/{}/quota", domain.id))
+        .json(&json!({ "max_posts": -1 }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    let mut user_with_permissions = editor.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "editor".to_string(),
+    }];
+    let app = create_admin_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(user_with_permissions));
+    let server = TestServer::new(app).unwrap();
+
+    let new_post = |title: &str| {
+        json!({
+            "title": title,
+            "content": "Content",
+            "category": "Technology",
+        })
+    };
+    let response = server.post("/posts").json(&new_post("Second")).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    // The third post is over the quota of two
+    let response = server.post("/posts").json(&new_post("Third")).await;
+    assert_eq!(response.status_code(), StatusCode::CONFLICT);
+    let body: Value = response.json();
+    assert_eq!(body["error"], "quota_exceeded");
+    assert_eq!(body["resource"], "posts");
+    assert_eq!(body["limit"], 2);
+    assert!(body["message"].as_str().unwrap().contains("2 posts"));
+
+    let response = server.get("/domain/usage").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["usage"]["posts"], 2);
+    assert_eq!(body["usage"]["media_bytes"], 1500);
+    assert_eq!(body["quota"]["max_posts"], 2);
+    assert_eq!(body["quota"]["max_media_bytes"], 10000);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_get_user_with_null_columns() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+    let user = create_test_user(&pool, "legacy@test.com", "Legacy User", "user").await;
+    // A row from before the columns had defaults
+    sqlx::query("UPDATE users SET role = NULL, updated_at = NULL WHERE id = $1")
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let server = TestServer::new(create_admin_app(state).layer(Extension(admin))).unwrap();
+    let response = server.get(&format!("/users/{}", user.id)).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["role"], "domain_user");
+    assert!(body["created_at"].is_string());
+    assert!(body["updated_at"].is_null());
+
+    cleanup_test_db(&pool).await;
+}
+
+/// Answers every TXT lookup with `txt`, or fails with `error`
+struct MockDns {
+    txt: Vec<String>,
+    error: Option<api::services::domain_verification::DnsError>,
+}
+
+impl api::services::domain_verification::DnsResolver for MockDns {
+    fn txt<'a>(
+        &'a self,
+        _name: &'a str,
+    ) -> api::services::domain_verification::DnsFuture<'a, Vec<String>> {
+        Box::pin(async move {
+            match &self.error {
+                Some(error) => Err(error.clone()),
+                None => Ok(self.txt.clone()),
+            }
+        })
+    }
+
+    fn cname<'a>(
+        &'a self,
+        _name: &'a str,
+    ) -> api::services::domain_verification::DnsFuture<'a, Vec<String>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_verify_domain_with_mock_resolver() {
+    use api::services::domain_verification::DnsError;
+
+    let pool = create_test_db().await;
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+    let domain = create_test_domain(&pool, "custom.testblog.com", "Custom Blog").await;
+    let token: String = sqlx::query_scalar("SELECT verification_token FROM domains WHERE id = $1")
+        .bind(domain.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    let server_with = |dns: MockDns| {
+        let state = Arc::new(AppState {
+            dns: Arc::new(dns),
+            ..AppState::new(pool.clone())
+        });
+        TestServer::new(create_admin_app(state).layer(Extension(admin.clone()))).unwrap()
+    };
+    let path = format!("/domains/{}/verify", domain.id);
+
+    // No record yet: the response says what to publish
+    let server = server_with(MockDns {
+        txt: vec!["unrelated".to_string()],
+        error: None,
+    });
+    let response = server.post(&path).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["verified"], false);
+    assert_eq!(body["records_found"], false);
+    assert_eq!(
+        body["expected"]["txt_name"],
+        "_multi-blog-verification.custom.testblog.com"
+    );
+    assert_eq!(body["expected"]["txt_value"], token.as_str());
+
+    // Lookups that time out leave the domain as it was
+    let server = server_with(MockDns {
+        txt: Vec::new(),
+        error: Some(DnsError::Timeout),
+    });
+    let response = server.post(&path).await;
+    assert_eq!(response.status_code(), StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(response.json::<Value>()["error"], "dns_timeout");
+
+    let server = server_with(MockDns {
+        txt: vec![token.clone()],
+        error: None,
+    });
+    let response = server.post(&path).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["verified"], true);
+    assert_eq!(body["records_found"], true);
+    let verified: bool =
+        sqlx::query_scalar("SELECT verified_at IS NOT NULL FROM domains WHERE id = $1")
+            .bind(domain.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(verified);
+
+    // Only platform admins may verify
+    let user = create_test_user(&pool, "owner@test.com", "Owner", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "admin").await;
+    let state = Arc::new(AppState::new(pool.clone()));
+    let server = TestServer::new(create_admin_app(state).layer(Extension(user))).unwrap();
+    let response = server.post(&path).await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_login_returns_tokens_and_user_context() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "login.testblog.com", "Login Blog").await;
+    let user = create_test_user(&pool, "writer@test.com", "Writer", "domain_user").await;
+    create_test_permission(&pool, user.id, domain.id, "editor").await;
+    sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+        .bind(bcrypt::hash("password123", 4).unwrap())
+        .bind(user.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let auth = TestServer::new(
+        Router::new()
+            .nest("/auth", api::handlers::auth::auth_router())
+            .with_state(state.clone()),
+    )
+    .unwrap();
+    let response = auth
+        .post("/auth/login")
+        .json(&json!({ "email": "writer@test.com", "password": "password123" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert!(body["access_token"].is_string());
+    assert_eq!(body["token"], body["access_token"]);
+    assert_ne!(body["refresh_token"], body["access_token"]);
+    assert_eq!(body["user"]["id"], user.id);
+    assert_eq!(body["user"]["role"], "domain_user");
+    assert_eq!(
+        body["user"]["domain_permissions"],
+        json!([{ "domain_id": domain.id, "role": "editor" }])
+    );
+
+    // The access token opens admin routes
+    let guarded = TestServer::new(
+        create_admin_app(state.clone())
+            .layer(middleware::from_fn_with_state(state, auth_middleware))
+            .layer(Extension(domain)),
+    )
+    .unwrap();
+    let bearer = |token: &Value| {
+        HeaderValue::from_str(&format!("Bearer {}", token.as_str().unwrap())).unwrap()
+    };
+    let response = guarded
+        .get("/posts")
+        .add_header("authorization", bearer(&body["access_token"]))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    // The refresh token doesn't, but buys a new pair
+    let response = guarded
+        .get("/posts")
+        .add_header("authorization", bearer(&body["refresh_token"]))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+    let response = auth
+        .post("/auth/refresh")
+        .json(&json!({ "refresh_token": body["refresh_token"] }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let refreshed: Value = response.json();
+    assert!(refreshed["access_token"].is_string());
+    assert_eq!(refreshed["user"]["domain_permissions"][0]["role"], "editor");
+
+    let response = auth
+        .post("/auth/refresh")
+        .json(&json!({ "refresh_token": body["access_token"] }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_list_and_export_subscribers() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "admin.testblog.com", "Admin Test Blog").await;
+    let other = create_test_domain(&pool, "other.com", "Other Blog").await;
+    for (domain_id, email, confirmed) in [
+        (domain.id, "pending@example.com", false),
+        (domain.id, "confirmed@example.com", true),
+        (other.id, "elsewhere@example.com", true),
+    ] {
+        sqlx::query(
+            r#"
+            INSERT INTO subscribers (domain_id, email, confirmation_sent_at, confirmed_at)
+            VALUES ($1, $2, NOW(), CASE WHEN $3 THEN NOW() END)
+            "#,
+        )
+        .bind(domain_id)
+        .bind(email)
+        .bind(confirmed)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let mut user = create_test_user(&pool, "viewer@test.com", "Viewer", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+    user.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+    let app = create_admin_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(user));
+    let server = TestServer::new(app).unwrap();
+
+    let response = server.get("/subscribers").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["total"], 2);
+    let emails: Vec<&str> = body["subscribers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["email"].as_str().unwrap())
+        .collect();
+    assert!(emails.contains(&"pending@example.com"));
+    assert!(emails.contains(&"confirmed@example.com"));
+
+    let response = server
+        .get("/subscribers")
+        .add_query_param("status", "confirmed")
+        .await;
+    let body: Value = response.json();
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["subscribers"][0]["email"], "confirmed@example.com");
+    assert_eq!(body["subscribers"][0]["status"], "confirmed");
+
+    let response = server.get("/subscribers/export").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert!(
+        response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/csv")
+    );
+    let csv = response.text();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("email,status,created_at,confirmed_at"));
+    assert_eq!(lines.count(), 2);
+    assert!(csv.contains("pending@example.com,pending,"));
+    assert!(!csv.contains("elsewhere@example.com"));
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_clone_domain_copies_settings_independently() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let source = create_test_domain(&pool, "template.testblog.com", "Template Blog").await;
+    sqlx::query(
+        r##"UPDATE domains SET theme_config = '{"colors": {"primary": "#0f766e"}}',
+                              categories = '["News", "Guides"]',
+                              features = '{"comments": false}', timezone = 'Europe/Paris',
+                              cors_origins = '["https://template.testblog.com"]'
+           WHERE id = $1"##,
+    )
+    .bind(source.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"INSERT INTO domain_menus (domain_id, items) VALUES ($1, '[{"label": "Home", "url": "/"}]')"#,
+    )
+    .bind(source.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO email_templates (domain_id, kind, subject, body) VALUES ($1, 'subscription_confirmation', 'Join {{blog}}', '{{confirm_url}}')",
+    )
+    .bind(source.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let published = create_test_post(
+        &pool,
+        source.id,
+        "Welcome",
+        "Content",
+        "Author",
+        "published",
+    )
+    .await;
+    create_test_post(&pool, source.id, "Upcoming", "Content", "Author", "draft").await;
+    sqlx::query(
+        "INSERT INTO analytics_events (domain_id, event_type, path, post_id) VALUES ($1, 'post_view', '/posts/welcome', $2)",
+    )
+    .bind(source.id)
+    .bind(published)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+    let editor = create_test_user(&pool, "editor@test.com", "Editor", "user").await;
+    create_test_permission(&pool, editor.id, source.id, "admin").await;
+    let platform =
+        TestServer::new(create_admin_app(state.clone()).layer(Extension(admin))).unwrap();
+    let clone_path = format!("/domains/{}/clone", source.id);
+
+    let response = platform
+        .post(&clone_path)
+        .json(&json!({
+            "hostname": "Copy.TestBlog.com",
+            "name": "Copy Blog",
+            "include_posts": true,
+        }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    let clone_id = body["domain"]["id"].as_i64().unwrap() as i32;
+    assert_ne!(clone_id, source.id);
+    assert_eq!(body["domain"]["hostname"], "copy.testblog.com");
+    assert_eq!(body["domain"]["name"], "Copy Blog");
+    assert_eq!(
+        body["domain"]["theme_config"]["colors"]["primary"],
+        "#0f766e"
+    );
+    assert_eq!(body["domain"]["posts_count"], 2);
+    assert_eq!(body["cloned"]["source_id"], source.id);
+    assert_eq!(body["cloned"]["categories"], 2);
+    assert_eq!(body["cloned"]["menu"], true);
+    assert_eq!(body["cloned"]["search_settings"], false);
+    assert_eq!(body["cloned"]["email_templates"], 1);
+    assert_eq!(body["cloned"]["posts"], 2);
+
+    // Settings came along; address-bound settings and analytics did not
+    let (features, timezone, cors_origins): (Value, String, Value) =
+        sqlx::query_as("SELECT features, timezone, cors_origins FROM domains WHERE id = $1")
+            .bind(clone_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(features, json!({ "comments": false }));
+    assert_eq!(timezone, "Europe/Paris");
+    assert_eq!(cors_origins, json!([]));
+    let statuses: Vec<String> =
+        sqlx::query_scalar("SELECT status FROM posts WHERE domain_id = $1 ORDER BY slug")
+            .bind(clone_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(statuses, vec!["draft", "draft"]);
+    let events: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM analytics_events WHERE domain_id = $1")
+            .bind(clone_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(events, 0);
+    let audited: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM audit_log WHERE action = 'domain.clone'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(audited, 1);
+
+    // Editing the clone leaves the source alone
+    let response = platform
+        .put(&format!("/domains/{clone_id}"))
+        .json(&json!({ "name": "Renamed Copy" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    sqlx::query(
+        r##"UPDATE domains SET theme_config = '{"colors": {"primary": "#b91c1c"}}',
+                              categories = '["Recipes"]'
+           WHERE id = $1"##,
+    )
+    .bind(clone_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query("UPDATE domain_menus SET items = '[]' WHERE domain_id = $1")
+        .bind(clone_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE posts SET title = 'Changed' WHERE domain_id = $1")
+        .bind(clone_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let (name, theme_config, categories): (String, Value, Value) =
+        sqlx::query_as("SELECT name, theme_config, categories FROM domains WHERE id = $1")
+            .bind(source.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(name, "Template Blog");
+    assert_eq!(theme_config["colors"]["primary"], "#0f766e");
+    assert_eq!(categories, json!(["News", "Guides"]));
+    let menu: Value = sqlx::query_scalar("SELECT items FROM domain_menus WHERE domain_id = $1")
+        .bind(source.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(menu[0]["label"], "Home");
+    let title: String = sqlx::query_scalar("SELECT title FROM posts WHERE id = $1")
+        .bind(published)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(title, "Welcome");
+
+    // Posts only when asked for
+    let response = platform
+        .post(&clone_path)
+        .json(&json!({ "hostname": "bare.testblog.com", "name": "Bare Blog" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(response.json::<Value>()["cloned"]["posts"], 0);
+
+    let response = platform
+        .post(&clone_path)
+        .json(&json!({ "hostname": "copy.testblog.com", "name": "Again" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::CONFLICT);
+    let response = platform
+        .post("/domains/999999/clone")
+        .json(&json!({ "hostname": "missing.testblog.com", "name": "Missing" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    // Domain admins can't clone, even their own domain
+    let mut domain_admin = editor.clone();
+    domain_admin.domain_permissions = vec![api::DomainPermission {
+        domain_id: source.id,
+        role: "admin".to_string(),
+    }];
+    let server = TestServer::new(
+        create_admin_app(state)
+            .layer(Extension(source))
+            .layer(Extension(domain_admin)),
+    )
+    .unwrap();
+    let response = server
+        .post(&clone_path)
+        .json(&json!({ "hostname": "other.testblog.com", "name": "Other" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
 
     cleanup_test_db(&pool).await;
 }
@@ -1,8 +1,9 @@
 // tests/analytics_tests.rs
 use api::{
-    AppState, DomainContext, UserContext, handlers::analytics::AnalyticsModule, test_utils::*,
+    AppState, DomainContext, UserContext, auth_middleware, domain_middleware,
+    handlers::HandlerModule, handlers::analytics::AnalyticsModule, test_utils::*,
 };
-use axum::{Extension, Router};
+use axum::{Extension, Router, http::HeaderValue, middleware};
 use axum_test::TestServer;
 use chrono::Utc;
 use serde_json::Value;
@@ -37,7 +38,7 @@ async fn create_test_analytics_data(pool: &sqlx::PgPool, domain_id: i32, post_id
         sqlx::query!(
             r#"
             INSERT INTO analytics_events (domain_id, event_type, path, ip_address, user_agent, referrer, post_id, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            VALUES ($1, $2, $3, $4::text::inet, $5, $6, $7, $8)
             "#,
             domain_id,
             event_type,
@@ -58,7 +59,7 @@ async fn create_test_analytics_data(pool: &sqlx::PgPool, domain_id: i32, post_id
 #[serial]
 async fn test_analytics_overview() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
     let user = create_test_user(&pool, "analytics@test.com", "Analytics User", "user").await;
@@ -87,21 +88,23 @@ async fn test_analytics_overview() {
         .layer(Extension(user_with_permissions));
 
     let server = TestServer::new(app).unwrap();
-    let response = server.get("/overview").await;
+    let response = server.get("/dashboard").await;
 
     assert_eq!(response.status_code(), axum::http::StatusCode::OK);
 
     let body: Value = response.json();
 
-    assert!(body.get("current_period").is_some());
-    assert!(body.get("previous_period").is_some());
-    assert!(body.get("change_percent").is_some());
+    assert!(body.get("overview").is_some());
     assert!(body.get("top_posts").is_some());
     assert!(body.get("top_categories").is_some());
 
-    let current_period = body.get("current_period").unwrap();
-    assert!(current_period.get("page_views").unwrap().as_i64().unwrap() > 0);
-    assert!(current_period.get("post_views").unwrap().as_i64().unwrap() > 0);
+    let overview = body.get("overview").unwrap();
+    assert!(overview.get("previous_period").is_some());
+    assert!(overview.get("change_percent").is_some());
+    assert!(overview.get("total_page_views").unwrap().as_i64().unwrap() > 0);
+
+    let top_posts = body.get("top_posts").unwrap().as_array().unwrap();
+    assert!(top_posts[0].get("views").unwrap().as_i64().unwrap() > 0);
 
     cleanup_test_db(&pool).await;
 }
@@ -110,7 +113,7 @@ async fn test_analytics_overview() {
 #[serial]
 async fn test_traffic_stats() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
     let user = create_test_user(&pool, "analytics@test.com", "Analytics User", "user").await;
@@ -151,7 +154,7 @@ async fn test_traffic_stats() {
 #[serial]
 async fn test_search_analytics() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
     let user = create_test_user(&pool, "analytics@test.com", "Analytics User", "user").await;
@@ -210,7 +213,7 @@ async fn test_search_analytics() {
 #[serial]
 async fn test_referrer_stats() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
     let user = create_test_user(&pool, "analytics@test.com", "Analytics User", "user").await;
@@ -228,7 +231,7 @@ async fn test_referrer_stats() {
         sqlx::query!(
             r#"
             INSERT INTO analytics_events (domain_id, event_type, path, ip_address, user_agent, referrer, created_at)
-            VALUES ($1, 'page_view', '/', $2, 'Mozilla/5.0', $3, $4)
+            VALUES ($1, 'page_view', '/', $2::text::inet, 'Mozilla/5.0', $3, $4)
             "#,
             domain.id,
             ip,
@@ -293,7 +296,7 @@ async fn test_referrer_stats() {
 #[serial]
 async fn test_realtime_stats() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
     let user = create_test_user(&pool, "analytics@test.com", "Analytics User", "user").await;
@@ -345,7 +348,7 @@ async fn test_realtime_stats() {
 #[serial]
 async fn test_post_analytics() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
     let user = create_test_user(&pool, "analytics@test.com", "Analytics User", "user").await;
@@ -361,6 +364,8 @@ async fn test_post_analytics() {
     )
     .await;
 
+    create_test_analytics_data(&pool, domain.id, Some(post_id)).await;
+
     let mut user_with_permissions = user.clone();
     user_with_permissions.domain_permissions = vec![api::DomainPermission {
         domain_id: domain.id,
@@ -377,7 +382,7 @@ async fn test_post_analytics() {
     assert_eq!(response.status_code(), axum::http::StatusCode::OK);
 
     let body: Value = response.json();
-    let posts = body.as_array().unwrap();
+    let posts = body.get("posts").unwrap().as_array().unwrap();
 
     assert!(!posts.is_empty());
     let post = &posts[0];
@@ -395,7 +400,7 @@ async fn test_post_analytics() {
 #[serial]
 async fn test_unauthorized_access() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
     let user = create_test_user(&pool, "noaccess@test.com", "No Access User", "user").await;
@@ -408,9 +413,2091 @@ async fn test_unauthorized_access() {
         .layer(Extension(user_without_permissions));
 
     let server = TestServer::new(app).unwrap();
-    let response = server.get("/overview").await;
+    let response = server.get("/dashboard").await;
 
     assert_eq!(response.status_code(), axum::http::StatusCode::FORBIDDEN);
 
     cleanup_test_db(&pool).await;
 }
+
+#[tokio::test]
+#[serial]
+async fn test_domain_scoping_is_the_same_on_every_report() {
+    use axum::http::StatusCode;
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
+    let other = create_test_domain(&pool, "other.testblog.com", "Other Blog").await;
+    let mut viewer = create_test_user(&pool, "viewer@test.com", "Viewer", "user").await;
+    create_test_permission(&pool, viewer.id, domain.id, "viewer").await;
+    viewer.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+    let outsider = create_test_user(&pool, "outsider@test.com", "Outsider", "user").await;
+    let admin = create_test_user(&pool, "admin@test.com", "Admin", "platform_admin").await;
+
+    let server_for = |user: UserContext| {
+        TestServer::new(
+            create_analytics_app(state.clone())
+                .layer(Extension(domain.clone()))
+                .layer(Extension(user)),
+        )
+        .unwrap()
+    };
+    let viewer = server_for(viewer);
+    let outsider = server_for(outsider);
+    let admin = server_for(admin);
+
+    let reports = [
+        "/dashboard",
+        "/traffic",
+        "/posts",
+        "/search-terms",
+        "/referrers",
+        "/real-time",
+        "/visitors",
+        "/custom-events",
+        "/pages",
+    ];
+    for report in reports {
+        let own = format!("{report}?domain_id={}", domain.id);
+        let foreign = format!("{report}?domain_id={}", other.id);
+
+        assert_eq!(
+            viewer.get(report).await.status_code(),
+            StatusCode::OK,
+            "{report}"
+        );
+        assert_eq!(
+            viewer.get(&own).await.status_code(),
+            StatusCode::OK,
+            "{own}"
+        );
+        assert_eq!(
+            viewer.get(&foreign).await.status_code(),
+            StatusCode::FORBIDDEN,
+            "{foreign}"
+        );
+
+        assert_eq!(
+            outsider.get(report).await.status_code(),
+            StatusCode::FORBIDDEN,
+            "{report}"
+        );
+        assert_eq!(
+            outsider.get(&own).await.status_code(),
+            StatusCode::FORBIDDEN,
+            "{own}"
+        );
+
+        assert_eq!(
+            admin.get(report).await.status_code(),
+            StatusCode::OK,
+            "{report}"
+        );
+        assert_eq!(
+            admin.get(&foreign).await.status_code(),
+            StatusCode::OK,
+            "{foreign}"
+        );
+    }
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_content_engagement() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
+    let user = create_test_user(&pool, "analytics@test.com", "Analytics User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+
+    // One session on this domain with two reads: one finished, one abandoned
+    sqlx::query(
+        r#"
+        WITH s AS (
+            INSERT INTO user_sessions (id, session_id, domain_name)
+            VALUES ('11111111-1111-1111-1111-111111111111', '11111111-1111-1111-1111-111111111111', $1)
+            RETURNING session_id
+        )
+        INSERT INTO content_metrics (session_id, content_id, content_type, title, reading_time, scroll_percentage)
+        SELECT s.session_id, v.content_id, 'post', v.title, v.reading_time, v.scroll_percentage
+        FROM s, (VALUES
+            ('1', 'Finished', 120, 95.0),
+            ('2', 'Abandoned', 60, 30.0)
+        ) AS v(content_id, title, reading_time, scroll_percentage)
+        "#,
+    )
+    .bind(&domain.hostname)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+
+    let app = create_analytics_app(state)
+        .layer(Extension(domain.clone()))
+        .layer(Extension(user_with_permissions));
+
+    let server = TestServer::new(app).unwrap();
+    let response = server
+        .get(&format!("/content?domain_id={}", domain.id))
+        .await;
+
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+
+    let body: Value = response.json();
+    assert_eq!(
+        body.get("total_content_views").unwrap().as_i64().unwrap(),
+        2
+    );
+    assert_eq!(body.get("avg_reading_time").unwrap().as_i64().unwrap(), 90);
+    assert_eq!(
+        body.get("content_completion_rate")
+            .unwrap()
+            .as_f64()
+            .unwrap(),
+        50.0
+    );
+
+    // domain_id is required for the single-domain view
+    let response = server.get("/content").await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::BAD_REQUEST);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_time_on_page_distribution() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "engagement.testblog.com", "Engagement Blog").await;
+    let other = create_test_domain(&pool, "other.testblog.com", "Other Blog").await;
+    let user = create_test_user(&pool, "engagement@test.com", "Engagement User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+
+    for (session, hostname, times) in [
+        (
+            "22222222-2222-2222-2222-222222222222",
+            &domain.hostname,
+            vec![5, 9, 10, 25, 45, 59, 60, 120, 179, 180, 600],
+        ),
+        // Not visible to the user, so must not skew the stats
+        (
+            "33333333-3333-3333-3333-333333333333",
+            &other.hostname,
+            vec![1000, 2000],
+        ),
+    ] {
+        sqlx::query(
+            r#"
+            WITH s AS (
+                INSERT INTO user_sessions (id, session_id, domain_name)
+                VALUES ($1::uuid, $1::uuid, $2)
+                RETURNING session_id
+            )
+            INSERT INTO content_metrics (session_id, content_id, content_type, title, time_on_page)
+            SELECT s.session_id, t::text, 'post', 'Post', t
+            FROM s, unnest($3::int[]) AS t
+            "#,
+        )
+        .bind(session)
+        .bind(hostname)
+        .bind(&times)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+
+    let app = create_analytics_app(state)
+        .layer(Extension(domain.clone()))
+        .layer(Extension(user_with_permissions));
+
+    let server = TestServer::new(app).unwrap();
+    let response = server.get("/engagement").await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+
+    let body: Value = response.json();
+    assert_eq!(body["total_views"], 11);
+
+    let buckets: Vec<(String, i64)> = body["distribution"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| {
+            (
+                b["label"].as_str().unwrap().to_string(),
+                b["count"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        buckets,
+        [
+            ("0-10s".to_string(), 2),
+            ("10-30s".to_string(), 2),
+            ("30-60s".to_string(), 2),
+            ("1-3m".to_string(), 3),
+            ("3m+".to_string(), 2),
+        ]
+    );
+    assert_eq!(body["distribution"][4]["max_seconds"], Value::Null);
+    assert_eq!(body["median_seconds"].as_f64().unwrap(), 59.0);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_funnel_analytics() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
+    let user = create_test_user(&pool, "analytics@test.com", "Analytics User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+
+    // a: completes the funnel, b: drops off after a post,
+    // c: never lands, d: hits the steps out of order
+    let journeys: Vec<(&str, Vec<&str>)> = vec![
+        (
+            "aaaaaaaa-0000-0000-0000-000000000000",
+            vec!["/", "/posts/one", "/signup"],
+        ),
+        (
+            "bbbbbbbb-0000-0000-0000-000000000000",
+            vec!["/", "/about", "/posts/two"],
+        ),
+        (
+            "cccccccc-0000-0000-0000-000000000000",
+            vec!["/posts/three", "/signup"],
+        ),
+        ("dddddddd-0000-0000-0000-000000000000", vec!["/signup", "/"]),
+    ];
+
+    for (session, paths) in journeys {
+        sqlx::query("INSERT INTO user_sessions (id, session_id, domain_name) VALUES ($1::uuid, $1::uuid, $2)")
+            .bind(session)
+            .bind(&domain.hostname)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        for (i, path) in paths.into_iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO analytics_events (session_id, domain_id, event_type, path, created_at)
+                VALUES ($1::uuid, $2, 'page_view', $3, NOW() - INTERVAL '1 hour' + $4 * INTERVAL '1 minute')
+                "#,
+            )
+            .bind(session)
+            .bind(domain.id)
+            .bind(path)
+            .bind(i as i32)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+
+    let app = create_analytics_app(state)
+        .layer(Extension(domain.clone()))
+        .layer(Extension(user_with_permissions));
+
+    let server = TestServer::new(app).unwrap();
+    let response = server
+        .post("/funnel")
+        .json(&serde_json::json!({
+            "steps": ["/", "/posts/*", "/signup"],
+            "range": "24h",
+            "domain_id": domain.id
+        }))
+        .await;
+
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+
+    let body: Value = response.json();
+    assert_eq!(body.get("total_sessions").unwrap().as_i64().unwrap(), 3);
+
+    let steps = body.get("steps").unwrap().as_array().unwrap();
+    let sessions: Vec<i64> = steps
+        .iter()
+        .map(|s| s.get("sessions").unwrap().as_i64().unwrap())
+        .collect();
+    assert_eq!(sessions, vec![3, 2, 1]);
+
+    assert_eq!(
+        steps[1]
+            .get("conversion_from_previous")
+            .unwrap()
+            .as_f64()
+            .unwrap()
+            .round(),
+        67.0
+    );
+    assert_eq!(
+        steps[2]
+            .get("conversion_from_previous")
+            .unwrap()
+            .as_f64()
+            .unwrap(),
+        50.0
+    );
+
+    // A single step is not a funnel
+    let response = server
+        .post("/funnel")
+        .json(&serde_json::json!({ "steps": ["/"] }))
+        .await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::BAD_REQUEST);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_landing_and_exit_pages_follow_session_order() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
+    let other = create_test_domain(&pool, "other.testblog.com", "Other Blog").await;
+    let user = create_test_user(&pool, "analytics@test.com", "Analytics User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+
+    // Paths are inserted out of order; minutes decide the order in a session
+    type Journey = (&'static str, i32, Vec<(&'static str, i32)>);
+    let journeys: Vec<Journey> = vec![
+        (
+            "aaaaaaaa-0000-0000-0000-000000000000",
+            domain.id,
+            vec![("/posts/one", 2), ("/", 0), ("/about", 1)],
+        ),
+        (
+            "bbbbbbbb-0000-0000-0000-000000000000",
+            domain.id,
+            vec![("/", 0), ("/posts/two", 5)],
+        ),
+        (
+            "cccccccc-0000-0000-0000-000000000000",
+            domain.id,
+            vec![("/posts/one", 3)],
+        ),
+        (
+            "dddddddd-0000-0000-0000-000000000000",
+            other.id,
+            vec![("/secret", 0), ("/", 1)],
+        ),
+    ];
+
+    for (session, domain_id, views) in journeys {
+        let hostname = if domain_id == domain.id {
+            &domain.hostname
+        } else {
+            &other.hostname
+        };
+        sqlx::query("INSERT INTO user_sessions (id, session_id, domain_name) VALUES ($1::uuid, $1::uuid, $2)")
+            .bind(session)
+            .bind(hostname)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        for (path, minute) in views {
+            sqlx::query(
+                r#"
+                INSERT INTO analytics_events (session_id, domain_id, event_type, path, created_at)
+                VALUES ($1::uuid, $2, 'page_view', $3, NOW() - INTERVAL '1 hour' + $4 * INTERVAL '1 minute')
+                "#,
+            )
+            .bind(session)
+            .bind(domain_id)
+            .bind(path)
+            .bind(minute)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    // Only page views mark where a session starts or ends
+    sqlx::query(
+        r#"
+        INSERT INTO analytics_events (session_id, domain_id, event_type, path, created_at)
+        VALUES ('aaaaaaaa-0000-0000-0000-000000000000'::uuid, $1, 'custom', '/checkout', NOW())
+        "#,
+    )
+    .bind(domain.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+
+    let app = create_analytics_app(state)
+        .layer(Extension(domain.clone()))
+        .layer(Extension(user_with_permissions));
+    let server = TestServer::new(app).unwrap();
+
+    let response = server.get("/pages?range=24h").await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+    let body: Value = response.json();
+
+    let domains = body["domains"].as_array().unwrap();
+    assert_eq!(domains.len(), 1);
+    assert_eq!(domains[0]["domain_id"], domain.id);
+
+    let pages = |kind: &str| -> Vec<(String, i64)> {
+        domains[0][kind]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| {
+                (
+                    p["path"].as_str().unwrap().to_string(),
+                    p["sessions"].as_i64().unwrap(),
+                )
+            })
+            .collect()
+    };
+    assert_eq!(
+        pages("landing_pages"),
+        vec![("/".to_string(), 2), ("/posts/one".to_string(), 1)]
+    );
+    assert_eq!(
+        pages("exit_pages"),
+        vec![("/posts/one".to_string(), 2), ("/posts/two".to_string(), 1)]
+    );
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_batch_event_ingestion() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
+    let session_id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO user_sessions (session_id, domain_name) VALUES (gen_random_uuid(), $1) RETURNING id",
+    )
+    .bind(&domain.hostname)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let unknown_session = uuid::Uuid::new_v4();
+    let timestamp = Utc::now().to_rfc3339();
+
+    let count = |table: &'static str| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM {table}"))
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        }
+    };
+
+    let app = create_analytics_app(state);
+    let server = TestServer::new(app).unwrap();
+
+    // Valid events are stored, invalid ones are reported by index
+    let response = server
+        .post("/events/batch")
+        .json(&serde_json::json!([
+            {"type": "behavior", "event_type": "click", "element": "nav-menu", "timestamp": timestamp, "session_id": session_id},
+            {"type": "search", "query": "rust", "results_count": 3, "timestamp": timestamp, "session_id": session_id},
+            {"type": "search", "query": "  ", "results_count": 0, "timestamp": timestamp, "session_id": session_id},
+            {"type": "content_metrics", "content_id": "1", "content_type": "blog_post", "title": "Post",
+             "reading_time": 120, "scroll_percentage": 80.0, "time_on_page": 100, "bounce": false,
+             "engagement_events": 2, "timestamp": timestamp, "session_id": unknown_session},
+            {"type": "page_view", "timestamp": timestamp, "session_id": session_id}
+        ]))
+        .await;
+
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+
+    let body: Value = response.json();
+    assert_eq!(body.get("accepted").unwrap().as_i64().unwrap(), 2);
+    let failed: Vec<i64> = body
+        .get("failed")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f.get("index").unwrap().as_i64().unwrap())
+        .collect();
+    assert_eq!(failed, vec![2, 3, 4]);
+    assert_eq!(count("behavior_events").await, 1);
+    assert_eq!(count("search_events").await, 1);
+    assert_eq!(count("content_metrics").await, 0);
+
+    // Atomic mode stores nothing when any event fails
+    let response = server
+        .post("/events/batch")
+        .add_query_param("atomic", "true")
+        .json(&serde_json::json!([
+            {"type": "behavior", "event_type": "scroll", "scroll_depth": 50.0, "timestamp": timestamp, "session_id": session_id},
+            {"type": "search_click", "query": "rust", "clicked_result": "Rust Guide", "position_clicked": 1,
+             "timestamp": timestamp, "session_id": unknown_session}
+        ]))
+        .await;
+
+    assert_eq!(
+        response.status_code(),
+        axum::http::StatusCode::UNPROCESSABLE_ENTITY
+    );
+    let body: Value = response.json();
+    assert_eq!(body.get("accepted").unwrap().as_i64().unwrap(), 0);
+    assert_eq!(body["failed"][0].get("index").unwrap().as_i64().unwrap(), 1);
+    assert_eq!(count("behavior_events").await, 1);
+    assert_eq!(count("search_click_events").await, 0);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_anonymous_visitor_can_track_but_not_report() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+    create_test_domain(&pool, "tracking.testblog.com", "Tracking Blog").await;
+
+    let session_id = uuid::Uuid::new_v4();
+    sqlx::query("INSERT INTO user_sessions (id, session_id, domain_name) VALUES ($1, $1, $2)")
+        .bind(session_id)
+        .bind("tracking.testblog.com")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Same layering as the /analytics group in main.rs
+    let app = Router::new()
+        .route(
+            "/dashboard",
+            axum::routing::get(api::handlers::analytics::get_analytics_dashboard),
+        )
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .merge(
+            AnalyticsModule::tracking_routes().layer(middleware::from_fn_with_state(
+                state.clone(),
+                domain_middleware,
+            )),
+        )
+        .with_state(state);
+
+    let server = TestServer::new(app).unwrap();
+    let host = HeaderValue::from_static("tracking.testblog.com");
+
+    let response = server
+        .post("/behavior")
+        .add_header("host", host.clone())
+        .json(&serde_json::json!({
+            "event_type": "click",
+            "element": "subscribe-button",
+            "timestamp": Utc::now().to_rfc3339(),
+            "session_id": session_id,
+        }))
+        .await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+
+    let response = server.get("/dashboard").add_header("host", host).await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::UNAUTHORIZED);
+
+    // Tracking still needs a known blog domain
+    let response = server
+        .post("/behavior")
+        .add_header("host", HeaderValue::from_static("unknown.example.com"))
+        .json(&serde_json::json!({
+            "event_type": "click",
+            "timestamp": Utc::now().to_rfc3339(),
+            "session_id": session_id,
+        }))
+        .await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::NOT_FOUND);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_session_config_defines_bounces_and_timeouts() {
+    use api::services::session_tracking::{SessionConfig, SessionTracker};
+
+    let pool = create_test_db().await;
+
+    // Two quick page views that ended after 5 seconds, and an open session
+    // idle since 10 minutes after it started
+    sqlx::query(
+        r#"
+        INSERT INTO user_sessions (domain_name, started_at, last_activity_at, ended_at, duration_seconds, page_views)
+        VALUES
+            ('bounce.testblog.com', NOW() - INTERVAL '90 minutes', NOW() - INTERVAL '90 minutes' + INTERVAL '5 seconds',
+             NOW() - INTERVAL '90 minutes' + INTERVAL '5 seconds', 5, 2),
+            ('bounce.testblog.com', NOW() - INTERVAL '60 minutes', NOW() - INTERVAL '50 minutes', NULL, NULL, 3)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let lenient = SessionConfig {
+        inactivity_timeout_minutes: 30,
+        bounce_max_page_views: 1,
+        bounce_max_seconds: None,
+    };
+    let strict = SessionConfig {
+        bounce_max_seconds: Some(10),
+        ..lenient.clone()
+    };
+    let long_timeout = SessionConfig {
+        inactivity_timeout_minutes: 120,
+        ..lenient.clone()
+    };
+
+    let end = Utc::now();
+    let start = end - chrono::Duration::hours(2);
+    let domain = Some("bounce.testblog.com");
+
+    let bounce_rate = |config: SessionConfig| {
+        let pool = pool.clone();
+        async move {
+            SessionTracker::get_bounce_rate(&pool, start, end, domain, &config)
+                .await
+                .unwrap()
+        }
+    };
+    let avg_duration = |config: SessionConfig| {
+        let pool = pool.clone();
+        async move {
+            SessionTracker::get_average_session_duration(&pool, start, end, domain, &config)
+                .await
+                .unwrap()
+        }
+    };
+
+    // Two page views aren't a bounce by page count, but 5s is under 10s
+    assert_eq!(bounce_rate(lenient.clone()).await, Some(0.0));
+    assert_eq!(bounce_rate(strict).await, Some(0.5));
+
+    // The idle session has timed out after 30 minutes and lasted 600s;
+    // with a two hour timeout it is still ongoing and left out
+    assert_eq!(avg_duration(lenient).await, Some(302.5));
+    assert_eq!(avg_duration(long_timeout).await, Some(5.0));
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_sampled_page_views_are_weighted_in_totals() {
+    use api::services::daily_stats;
+
+    let pool = create_test_db().await;
+    let domain = create_test_domain(&pool, "sampled.testblog.com", "Sampled Blog").await;
+
+    // Three page views kept at a 25% sample rate, plus unsampled events
+    for (event_type, weight) in [
+        ("page_view", 4.0),
+        ("page_view", 4.0),
+        ("page_view", 4.0),
+        ("search", 1.0),
+        ("post_view", 1.0),
+    ] {
+        sqlx::query(
+            r#"
+            INSERT INTO analytics_events (domain_id, event_type, path, ip_address, sample_weight)
+            VALUES ($1, $2, '/', '10.0.0.1'::inet, $3)
+            "#,
+        )
+        .bind(domain.id)
+        .bind(event_type)
+        .bind(weight)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let now = Utc::now();
+    let totals = daily_stats::raw_totals(
+        &pool,
+        &[domain.id],
+        now - chrono::Duration::hours(1),
+        now + chrono::Duration::hours(1),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(totals.page_views, 12);
+    assert_eq!(totals.searches, 1);
+    assert_eq!(totals.post_views, 1);
+
+    // The real-time view weighs them the same way
+    let user = create_test_user(&pool, "sampled@test.com", "Sampled User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+    let mut viewer = user.clone();
+    viewer.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+    let app = create_analytics_app(Arc::new(AppState::new(pool.clone()))).layer(Extension(viewer));
+    let server = TestServer::new(app).unwrap();
+    let body: Value = server.get("/real-time").await.json();
+    assert_eq!(body["page_views_last_hour"], 12);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_daily_rollup_matches_raw_totals() {
+    use api::services::daily_stats;
+
+    let pool = create_test_db().await;
+    let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
+
+    // Ten days of traffic, one distinct set of visitors per day
+    for days_ago in 0..10 {
+        for (i, event_type) in ["page_view", "page_view", "post_view", "search"]
+            .iter()
+            .cycle()
+            .take(days_ago + 3)
+            .enumerate()
+        {
+            sqlx::query(
+                r#"
+                INSERT INTO analytics_events (domain_id, event_type, path, ip_address, created_at)
+                VALUES ($1, $2, '/', $3::inet, NOW() - $4 * INTERVAL '1 day')
+                "#,
+            )
+            .bind(domain.id)
+            .bind(event_type)
+            .bind(format!("10.0.{days_ago}.{}", i % 2))
+            .bind(days_ago as i32)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    let now = Utc::now();
+    let today = now.date_naive();
+    daily_stats::backfill(
+        &pool,
+        today - chrono::Duration::days(11),
+        today - chrono::Duration::days(1),
+    )
+    .await
+    .unwrap();
+
+    let domain_ids = [domain.id];
+    let today_start = today.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let start = today_start - chrono::Duration::days(7);
+    let end = now + chrono::Duration::seconds(1);
+
+    let direct = daily_stats::raw_totals(&pool, &domain_ids, start, end)
+        .await
+        .unwrap();
+    let rolled = daily_stats::period_totals(&pool, &domain_ids, start, end, now)
+        .await
+        .unwrap();
+    assert_eq!(rolled.page_views, direct.page_views);
+    assert_eq!(rolled.post_views, direct.post_views);
+    assert_eq!(rolled.searches, direct.searches);
+    // Visitors never repeat across days in this dataset, so daily sums are exact
+    assert_eq!(rolled.unique_visitors, direct.unique_visitors);
+
+    let daily = daily_stats::daily_totals(&pool, &domain_ids, start, end, now, "UTC")
+        .await
+        .unwrap();
+    assert_eq!(
+        daily.iter().map(|d| d.totals.page_views).sum::<i64>(),
+        direct.page_views
+    );
+    assert!(daily.windows(2).all(|w| w[0].day < w[1].day));
+
+    // Past days are served from the rollup even once raw events are gone
+    let today_only = daily_stats::raw_totals(&pool, &domain_ids, today_start, end)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM analytics_events WHERE created_at < $1")
+        .bind(today_start)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let after_prune = daily_stats::period_totals(&pool, &domain_ids, start, end, now)
+        .await
+        .unwrap();
+    assert_eq!(after_prune.page_views, rolled.page_views);
+    assert!(after_prune.page_views > today_only.page_views);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_hourly_distribution_uses_integer_hours() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
+    let user = create_test_user(&pool, "analytics@test.com", "Analytics User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+
+    // One page view in each of the last 24 hours lands in every hour bucket
+    for hours_ago in 0..24 {
+        sqlx::query(
+            r#"
+            INSERT INTO analytics_events (domain_id, event_type, path, ip_address, created_at)
+            VALUES ($1, 'page_view', '/', '10.0.0.1'::inet, NOW() - $2 * INTERVAL '1 hour')
+            "#,
+        )
+        .bind(domain.id)
+        .bind(hours_ago)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+
+    let app = create_analytics_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(user_with_permissions));
+    let server = TestServer::new(app).unwrap();
+    let response = server.get("/traffic?range=7d").await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+
+    let body: Value = response.json();
+    let hours: Vec<i64> = body["hourly_distribution"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|h| {
+            assert_eq!(h["page_views"], 1);
+            h["hour"].as_i64().expect("hour should be an integer")
+        })
+        .collect();
+    assert_eq!(hours, (0..24).collect::<Vec<_>>());
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_day_and_hour_buckets_follow_domain_timezone() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let utc = create_test_domain(&pool, "utc.testblog.com", "UTC Blog").await;
+    let new_york = create_test_domain(&pool, "ny.testblog.com", "New York Blog").await;
+    sqlx::query("UPDATE domains SET timezone = 'America/New_York' WHERE id = $1")
+        .bind(new_york.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let admin = create_test_user(&pool, "admin@test.com", "Admin", "platform_admin").await;
+
+    // 03:30 UTC is still the previous evening in New York
+    for domain_id in [utc.id, new_york.id] {
+        for at in ["2024-01-15T03:30:00Z", "2024-01-15T12:00:00Z"] {
+            sqlx::query(
+                r#"
+                INSERT INTO analytics_events (domain_id, event_type, path, ip_address, created_at)
+                VALUES ($1, 'page_view', '/', '10.0.0.1'::inet, $2::timestamptz)
+                "#,
+            )
+            .bind(domain_id)
+            .bind(at)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+    }
+    let jan = |d| chrono::NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+    api::services::daily_stats::backfill(&pool, jan(14), jan(15))
+        .await
+        .unwrap();
+
+    let server = TestServer::new(create_analytics_app(state).layer(Extension(admin))).unwrap();
+    let traffic = |domain_id: i32, tz: &str| {
+        format!(
+            "/traffic?start_date=2024-01-14T00:00:00Z&end_date=2024-01-16T00:00:00Z&domain_id={domain_id}{tz}"
+        )
+    };
+    let buckets = |body: &Value| {
+        let days: Vec<(String, i64)> = body["daily_stats"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| {
+                (
+                    d["date"].as_str().unwrap().to_string(),
+                    d["page_views"].as_i64().unwrap(),
+                )
+            })
+            .collect();
+        let hours: Vec<i64> = body["hourly_distribution"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|h| h["hour"].as_i64().unwrap())
+            .collect();
+        (days, hours)
+    };
+
+    let body: Value = server.get(&traffic(utc.id, "")).await.json();
+    assert_eq!(
+        buckets(&body),
+        (vec![("2024-01-15".to_string(), 2)], vec![3, 12])
+    );
+
+    let body: Value = server.get(&traffic(new_york.id, "")).await.json();
+    assert_eq!(
+        buckets(&body),
+        (
+            vec![("2024-01-14".to_string(), 1), ("2024-01-15".to_string(), 1)],
+            vec![7, 22]
+        )
+    );
+
+    // ?tz= overrides the domain's zone, and unknown zones are rejected
+    let body: Value = server.get(&traffic(new_york.id, "&tz=UTC")).await.json();
+    assert_eq!(
+        buckets(&body),
+        (vec![("2024-01-15".to_string(), 2)], vec![3, 12])
+    );
+    let response = server.get(&traffic(utc.id, "&tz=Mars/Olympus_Mons")).await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::BAD_REQUEST);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_device_breakdown_scoped_to_domains() {
+    use api::services::session_tracking::SessionTracker;
+
+    let pool = create_test_db().await;
+    let first = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
+    let second = create_test_domain(&pool, "other.testblog.com", "Other Blog").await;
+    let third = create_test_domain(&pool, "third.testblog.com", "Third Blog").await;
+
+    // (domain, stored device type, user agent, is_bot); a missing device type
+    // falls back to the user agent, and bots are ignored
+    let sessions = [
+        (&first, Some("mobile"), None, false),
+        (&first, Some("desktop"), None, false),
+        (&first, None, Some("Mozilla/5.0 (iPad; CPU OS 17_0)"), false),
+        (&second, Some("mobile"), None, false),
+        (&second, Some("desktop"), None, true),
+        (&third, Some("desktop"), None, false),
+    ];
+    for (domain, device_type, user_agent, is_bot) in sessions {
+        sqlx::query(
+            r#"
+            INSERT INTO user_sessions (session_id, domain_name, device_type, user_agent, is_bot)
+            VALUES (gen_random_uuid(), $1, $2, $3, $4)
+            "#,
+        )
+        .bind(&domain.hostname)
+        .bind(device_type)
+        .bind(user_agent)
+        .bind(is_bot)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let start = Utc::now() - chrono::Duration::hours(1);
+    let end = Utc::now() + chrono::Duration::hours(1);
+
+    let scoped =
+        SessionTracker::get_device_breakdown(&pool, start, end, Some(&[first.id, second.id][..]))
+            .await
+            .unwrap();
+    assert_eq!(scoped, (2, 1, 1, 0));
+
+    let everywhere = SessionTracker::get_device_breakdown(&pool, start, end, None)
+        .await
+        .unwrap();
+    assert_eq!(everywhere, (2, 2, 1, 0));
+
+    // Without sessions, visitors are classified from analytics events
+    sqlx::query("DELETE FROM user_sessions")
+        .execute(&pool)
+        .await
+        .unwrap();
+    for (ip, user_agent) in [
+        (
+            "10.0.0.1",
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0) Mobile",
+        ),
+        ("10.0.0.2", "Mozilla/5.0 (Windows NT 10.0) Chrome/120.0"),
+    ] {
+        sqlx::query(
+            r#"
+            INSERT INTO analytics_events (domain_id, event_type, path, ip_address, user_agent)
+            VALUES ($1, 'page_view', '/', $2::inet, $3)
+            "#,
+        )
+        .bind(first.id)
+        .bind(ip)
+        .bind(user_agent)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    let fallback = SessionTracker::get_device_breakdown(&pool, start, end, Some(&[first.id][..]))
+        .await
+        .unwrap();
+    assert_eq!(fallback, (1, 1, 0, 0));
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_search_without_results_is_reported() {
+    use api::{AnalyticsContext, handlers::HandlerModule, handlers::blog::BlogModule};
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
+    let user = create_test_user(&pool, "analytics@test.com", "Analytics User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+    create_test_post(
+        &pool,
+        domain.id,
+        "Rust Programming Guide",
+        "Learn Rust",
+        "Developer",
+        "published",
+    )
+    .await;
+
+    // Run real searches through the blog so the logged metadata is exercised
+    let blog = TestServer::new(
+        BlogModule::routes()
+            .with_state(state.clone())
+            .layer(Extension(domain.clone()))
+            .layer(Extension(AnalyticsContext {
+                ip_address: "127.0.0.1".to_string(),
+                user_agent: "Mozilla/5.0".to_string(),
+                referrer: None,
+            })),
+    )
+    .unwrap();
+    blog.get("/search?q=rust").await;
+    blog.get("/search?q=rust").await;
+    blog.get("/search?q=haskell").await;
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+    let server = TestServer::new(
+        create_analytics_app(state)
+            .layer(Extension(domain))
+            .layer(Extension(user_with_permissions)),
+    )
+    .unwrap();
+    let response = server.get("/search-terms").await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+
+    let body: Value = response.json();
+    let popular = body["popular_terms"].as_array().unwrap();
+    assert_eq!(popular[0]["query"], "rust");
+    assert_eq!(popular[0]["count"], 2);
+    assert_eq!(popular[0]["results_found"], true);
+
+    let no_results = body["no_results_queries"].as_array().unwrap();
+    assert_eq!(no_results.len(), 1);
+    assert_eq!(no_results[0]["query"], "haskell");
+    assert_eq!(no_results[0]["results_found"], false);
+
+    let rate = body["no_results_rate"].as_f64().unwrap();
+    assert!((rate - 1.0 / 3.0).abs() < 1e-9);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_live_updates_stream_page_views() {
+    use api::{AnalyticsContext, handlers::HandlerModule, handlers::blog::BlogModule};
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
+    let other = create_test_domain(&pool, "other.testblog.com", "Other Blog").await;
+    let user = create_test_user(&pool, "analytics@test.com", "Analytics User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+    let server = TestServer::builder()
+        .http_transport()
+        .build(create_analytics_app(state.clone()).layer(Extension(user_with_permissions)))
+        .unwrap();
+    let mut socket = server.get_websocket("/ws").await.into_websocket().await;
+
+    let blog_for = |domain: DomainContext| {
+        TestServer::new(
+            BlogModule::routes()
+                .with_state(state.clone())
+                .layer(Extension(domain))
+                .layer(Extension(AnalyticsContext {
+                    ip_address: "127.0.0.1".to_string(),
+                    user_agent: "Mozilla/5.0".to_string(),
+                    referrer: None,
+                })),
+        )
+        .unwrap()
+    };
+
+    // A view on a domain the user can't see must not be delivered
+    blog_for(other).get("/posts").await;
+    blog_for(domain.clone()).get("/").await;
+
+    let event: Value = socket.receive_json().await;
+    assert_eq!(event["type"], "page_view");
+    assert_eq!(event["domain_id"], domain.id);
+    assert_eq!(event["path"], "/");
+
+    socket.close().await;
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_export_formats() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
+    let user = create_test_user(&pool, "analytics@test.com", "Analytics User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+    create_test_analytics_data(&pool, domain.id, None).await;
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+    let server =
+        TestServer::new(create_analytics_app(state).layer(Extension(user_with_permissions)))
+            .unwrap();
+
+    let csv = server.get("/export").await;
+    assert_eq!(csv.status_code(), axum::http::StatusCode::OK);
+    assert_eq!(csv.text().lines().count(), 5);
+
+    let parquet = server.get("/export?format=parquet").await;
+    assert_eq!(parquet.status_code(), axum::http::StatusCode::OK);
+    assert_eq!(
+        parquet.header("content-type"),
+        "application/vnd.apache.parquet"
+    );
+    assert!(parquet.as_bytes().starts_with(b"PAR1"));
+
+    let unknown = server.get("/export?format=xlsx").await;
+    assert_eq!(unknown.status_code(), axum::http::StatusCode::BAD_REQUEST);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_super_admin_analytics_access_matches_platform_admin() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
+    create_test_analytics_data(&pool, domain.id, None).await;
+
+    for (role, expected) in [
+        ("platform_admin", axum::http::StatusCode::OK),
+        ("super_admin", axum::http::StatusCode::OK),
+        ("user", axum::http::StatusCode::FORBIDDEN),
+    ] {
+        let user = create_test_user(&pool, &format!("{role}@test.com"), "Admin", role).await;
+        let app = create_analytics_app(state.clone())
+            .layer(Extension(domain.clone()))
+            .layer(Extension(user));
+        let server = TestServer::new(app).unwrap();
+
+        let response = server.get("/dashboard").await;
+        assert_eq!(response.status_code(), expected, "{role}");
+        let response = server
+            .get(&format!("/dashboard?domain_id={}", domain.id))
+            .await;
+        assert_eq!(response.status_code(), expected, "{role}");
+    }
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_tracking_rejects_invalid_events() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "tracking.testblog.com", "Tracking Blog").await;
+    let session_id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO user_sessions (session_id, domain_name) VALUES (gen_random_uuid(), $1) RETURNING id",
+    )
+    .bind(&domain.hostname)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    let timestamp = Utc::now().to_rfc3339();
+
+    let server = TestServer::new(create_analytics_app(state)).unwrap();
+
+    // Out-of-range scroll depth
+    let response = server
+        .post("/behavior")
+        .json(&serde_json::json!({
+            "event_type": "scroll", "scroll_depth": 150.0,
+            "timestamp": timestamp, "session_id": session_id
+        }))
+        .await;
+    assert_eq!(
+        response.status_code(),
+        axum::http::StatusCode::UNPROCESSABLE_ENTITY
+    );
+    let body: Value = response.json();
+    assert!(body["field_errors"]["scroll_depth"].is_array());
+
+    // Missing and blank queries
+    let response = server
+        .post("/search")
+        .json(&serde_json::json!({
+            "results_count": 3, "timestamp": timestamp, "session_id": session_id
+        }))
+        .await;
+    assert_eq!(
+        response.status_code(),
+        axum::http::StatusCode::UNPROCESSABLE_ENTITY
+    );
+
+    let response = server
+        .post("/search")
+        .json(&serde_json::json!({
+            "query": " ", "results_count": -2, "timestamp": timestamp, "session_id": session_id
+        }))
+        .await;
+    assert_eq!(
+        response.status_code(),
+        axum::http::StatusCode::UNPROCESSABLE_ENTITY
+    );
+    let body: Value = response.json();
+    assert!(body["field_errors"]["query"].is_array());
+    assert!(body["field_errors"]["results_count"].is_array());
+
+    let stored: i64 = sqlx::query_scalar(
+        "SELECT (SELECT COUNT(*) FROM behavior_events) + (SELECT COUNT(*) FROM search_events)",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(stored, 0);
+
+    // Valid events still go through
+    let response = server
+        .post("/behavior")
+        .json(&serde_json::json!({
+            "event_type": "scroll", "scroll_depth": 75.5,
+            "timestamp": timestamp, "session_id": session_id
+        }))
+        .await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_session_timeline_is_ordered_and_domain_scoped() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "timeline.testblog.com", "Timeline Blog").await;
+    let other = create_test_domain(&pool, "other.testblog.com", "Other Blog").await;
+    let user = create_test_user(&pool, "support@test.com", "Support User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+
+    let new_session = |hostname: &'static str| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, uuid::Uuid>(
+                "INSERT INTO user_sessions (session_id, domain_name, ip_address, started_at)
+                 VALUES (gen_random_uuid(), $1, '203.0.113.42', NOW() - INTERVAL '1 hour')
+                 RETURNING id",
+            )
+            .bind(hostname)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        }
+    };
+    let session_id = new_session("timeline.testblog.com").await;
+    let other_session = new_session("other.testblog.com").await;
+
+    // Inserted out of order on purpose
+    sqlx::query(
+        "INSERT INTO search_events (session_id, query, results_count, created_at)
+         VALUES ($1, 'rust', 2, NOW() - INTERVAL '10 minutes')",
+    )
+    .bind(session_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO behavior_events (session_id, event_type, element, created_at)
+         VALUES ($1, 'click', 'nav-menu', NOW() - INTERVAL '20 minutes')",
+    )
+    .bind(session_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO analytics_events (session_id, domain_id, event_type, path, created_at)
+         VALUES ($1, $2, 'page_view', '/', NOW() - INTERVAL '30 minutes')",
+    )
+    .bind(session_id)
+    .bind(domain.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO behavior_events (session_id, event_type, scroll_depth, created_at)
+         VALUES ($1, 'scroll', 80, NOW() - INTERVAL '5 minutes')",
+    )
+    .bind(session_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO analytics_events (session_id, domain_id, event_type, path)
+         VALUES ($1, $2, 'page_view', '/secret')",
+    )
+    .bind(other_session)
+    .bind(other.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let mut viewer = user.clone();
+    viewer.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+    let app = create_analytics_app(state).layer(Extension(viewer));
+    let server = TestServer::new(app).unwrap();
+
+    let response = server.get(&format!("/sessions/{session_id}")).await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+
+    let body: Value = response.json();
+    assert_eq!(body["domain_id"], domain.id);
+    assert_eq!(body["ip_address"], "203.0.113.0");
+    let events: Vec<(String, String)> = body["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| {
+            (
+                e["source"].as_str().unwrap().to_string(),
+                e["event_type"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            ("analytics".to_string(), "page_view".to_string()),
+            ("behavior".to_string(), "click".to_string()),
+            ("search".to_string(), "search".to_string()),
+            ("behavior".to_string(), "scroll".to_string()),
+        ]
+    );
+    assert_eq!(body["events"][2]["details"]["query"], "rust");
+    assert_eq!(body["truncated"], false);
+
+    // Another domain's session is not visible, nor is an unknown one
+    let response = server.get(&format!("/sessions/{other_session}")).await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::NOT_FOUND);
+    let response = server
+        .get(&format!("/sessions/{}", uuid::Uuid::new_v4()))
+        .await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::NOT_FOUND);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_visitors_split_new_and_returning() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "visitors.testblog.com", "Visitors Blog").await;
+    let user = create_test_user(&pool, "owner@test.com", "Owner", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+
+    // 198.51.100.1 read the blog last month and came back; 198.51.100.2 is new
+    for (ip, age) in [
+        ("198.51.100.1", "40 days"),
+        ("198.51.100.1", "0 days"),
+        ("198.51.100.2", "0 days"),
+        ("198.51.100.2", "0 days"),
+    ] {
+        sqlx::query(
+            "INSERT INTO analytics_events (domain_id, event_type, path, ip_address, created_at)
+             VALUES ($1, 'page_view', '/', $2::inet, NOW() - $3::interval)",
+        )
+        .bind(domain.id)
+        .bind(ip)
+        .bind(age)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+
+    let mut viewer = user.clone();
+    viewer.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+    let app = create_analytics_app(state).layer(Extension(viewer));
+    let server = TestServer::new(app).unwrap();
+
+    let response = server.get("/visitors?days=7").await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+    let body: Value = response.json();
+
+    let domains = body["domains"].as_array().unwrap();
+    assert_eq!(domains.len(), 1);
+    assert_eq!(domains[0]["domain_id"], domain.id);
+    assert_eq!(domains[0]["unique_visitors"], 2);
+    assert_eq!(domains[0]["new_visitors"], 1);
+    assert_eq!(domains[0]["returning_visitors"], 1);
+
+    let trend = body["daily_trend"].as_array().unwrap();
+    assert_eq!(trend.len(), 1);
+    assert_eq!(trend[0]["date"], Utc::now().date_naive().to_string());
+    assert_eq!(trend[0]["new_visitors"], 1);
+    assert_eq!(trend[0]["returning_visitors"], 1);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_dashboard_flags_missing_session_data() {
+    use api::services::session_tracking::is_missing_table;
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "sessions.testblog.com", "Sessions Blog").await;
+    let user = create_test_user(&pool, "owner@test.com", "Owner", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+    create_test_analytics_data(&pool, domain.id, None).await;
+
+    let mut viewer = user.clone();
+    viewer.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+    let app = create_analytics_app(state).layer(Extension(viewer));
+    let server = TestServer::new(app).unwrap();
+
+    // Page views but no sessions: no made-up session metrics
+    let response = server.get("/dashboard?days=7").await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+    let overview = &response.json::<Value>()["overview"];
+    assert_eq!(overview["session_data_available"], false);
+    assert!(overview["avg_session_duration"].is_null());
+    assert!(overview["bounce_rate"].is_null());
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_sessions (domain_name, started_at, last_activity_at, ended_at, duration_seconds, page_views)
+        VALUES ('sessions.testblog.com', NOW() - INTERVAL '1 hour', NOW() - INTERVAL '50 minutes',
+                NOW() - INTERVAL '50 minutes', 600, 3)
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // Skip the response cached by the first call
+    let response = server.get("/dashboard?days=7&nocache=true").await;
+    let overview = &response.json::<Value>()["overview"];
+    assert_eq!(overview["session_data_available"], true);
+    assert_eq!(overview["avg_session_duration"], 600.0);
+    assert_eq!(overview["bounce_rate"], 0.0);
+
+    // What a database without the session migrations reports
+    let error = sqlx::query("SELECT 1 FROM no_such_sessions_table")
+        .execute(&pool)
+        .await
+        .unwrap_err();
+    assert!(is_missing_table(&error));
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_dashboard_is_cached_until_bypassed() {
+    use api::services::response_cache::ResponseCache;
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState {
+        dashboard_cache: ResponseCache::new(std::time::Duration::from_secs(60)),
+        ..AppState::new(pool.clone())
+    });
+
+    let domain = create_test_domain(&pool, "cached.testblog.com", "Cached Blog").await;
+    let user = create_test_user(&pool, "owner@test.com", "Owner", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+    create_test_analytics_data(&pool, domain.id, None).await;
+
+    let mut viewer = user.clone();
+    viewer.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+    let app = create_analytics_app(state).layer(Extension(viewer));
+    let server = TestServer::new(app).unwrap();
+
+    let response = server.get("/dashboard?days=7").await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+    assert_eq!(response.header("age"), "0");
+    let page_views = response.json::<Value>()["overview"]["total_page_views"].clone();
+
+    create_test_analytics_data(&pool, domain.id, None).await;
+
+    // Within the TTL the new events aren't counted yet
+    let response = server.get("/dashboard?days=7").await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+    assert!(
+        response
+            .header("cache-control")
+            .to_str()
+            .unwrap()
+            .starts_with("private, max-age=")
+    );
+    assert!(
+        response
+            .header("age")
+            .to_str()
+            .unwrap()
+            .parse::<u64>()
+            .is_ok()
+    );
+    assert_eq!(
+        response.json::<Value>()["overview"]["total_page_views"],
+        page_views
+    );
+
+    // A different window is computed separately
+    let response = server.get("/dashboard?days=8").await;
+    assert_ne!(
+        response.json::<Value>()["overview"]["total_page_views"],
+        page_views
+    );
+
+    let response = server.get("/dashboard?days=7&nocache=true").await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+    assert_eq!(response.header("age"), "0");
+    let recomputed = response.json::<Value>()["overview"]["total_page_views"].clone();
+    assert!(recomputed.as_i64().unwrap() > page_views.as_i64().unwrap());
+
+    // The bypass refreshed the cached response
+    let response = server.get("/dashboard?days=7").await;
+    assert_eq!(
+        response.json::<Value>()["overview"]["total_page_views"],
+        recomputed
+    );
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_custom_events_require_registration() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let mut domain = create_test_domain(&pool, "custom.testblog.com", "Custom Blog").await;
+    domain.theme_config = serde_json::json!({
+        "analytics_config": { "custom_events": ["newsletter_signup"] }
+    });
+    let user = create_test_user(&pool, "owner@test.com", "Owner", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+
+    let tracking = TestServer::new(
+        AnalyticsModule::tracking_routes()
+            .layer(Extension(domain.clone()))
+            .with_state(state.clone()),
+    )
+    .unwrap();
+
+    let response = tracking
+        .post("/custom-event")
+        .json(&serde_json::json!({
+            "name": "newsletter_signup",
+            "properties": { "placement": "footer" },
+            "path": "/posts/hello",
+        }))
+        .await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+
+    let response = tracking
+        .post("/custom-event")
+        .json(&serde_json::json!({ "name": "coupon_redeemed" }))
+        .await;
+    assert_eq!(
+        response.status_code(),
+        axum::http::StatusCode::UNPROCESSABLE_ENTITY
+    );
+    let body: Value = response.json();
+    assert!(body["field_errors"]["name"].is_array());
+
+    let (event_type, metadata): (String, Value) =
+        sqlx::query_as("SELECT event_type, metadata FROM analytics_events WHERE domain_id = $1")
+            .bind(domain.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(event_type, "custom");
+    assert_eq!(metadata["name"], "newsletter_signup");
+    assert_eq!(metadata["properties"]["placement"], "footer");
+
+    let mut viewer = user.clone();
+    viewer.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+    let server = TestServer::new(create_analytics_app(state).layer(Extension(viewer))).unwrap();
+
+    let response = server.get("/custom-events?days=7").await;
+    assert_eq!(response.status_code(), axum::http::StatusCode::OK);
+    let body: Value = response.json();
+    let events = body["events"].as_array().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["domain_id"], domain.id);
+    assert_eq!(events[0]["name"], "newsletter_signup");
+    assert_eq!(events[0]["count"], 1);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_export_field_selection_and_privacy() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
+    let user = create_test_user(&pool, "analytics@test.com", "Analytics User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+    create_test_analytics_data(&pool, domain.id, None).await;
+
+    let mut user_with_permissions = user.clone();
+    user_with_permissions.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+    let server =
+        TestServer::new(create_analytics_app(state).layer(Extension(user_with_permissions)))
+            .unwrap();
+
+    let csv = server
+        .get("/export?fields=event_type,user_agent,created_at")
+        .await;
+    assert_eq!(csv.status_code(), axum::http::StatusCode::OK);
+    let text = csv.text();
+    let mut lines = text.lines();
+    assert_eq!(lines.next(), Some("Event Type,User Agent,Timestamp"));
+    assert!(lines.all(|line| line.split(',').count() == 3));
+
+    let unknown = server.get("/export?fields=event_type,email").await;
+    assert_eq!(unknown.status_code(), axum::http::StatusCode::BAD_REQUEST);
+
+    // The domain keeps user agents out of exports, even when asked for
+    sqlx::query(
+        r#"UPDATE domains SET theme_config = '{"analytics_config": {"export_exclude": ["user_agent"]}}' WHERE id = $1"#,
+    )
+    .bind(domain.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let csv = server
+        .get("/export?fields=event_type,user_agent,created_at")
+        .await;
+    assert_eq!(csv.status_code(), axum::http::StatusCode::OK);
+    let text = csv.text();
+    assert_eq!(text.lines().next(), Some("Event Type,Timestamp"));
+    assert!(!text.contains("Mozilla"));
+
+    let csv = server.get("/export").await;
+    assert!(!csv.text().lines().next().unwrap().contains("User Agent"));
+
+    cleanup_test_db(&pool).await;
+}
+
+async fn count_events(pool: &sqlx::PgPool, domain_id: i32) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM analytics_events WHERE domain_id = $1")
+        .bind(domain_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+#[serial]
+async fn test_buffered_events_are_eventually_written() {
+    use api::services::event_buffer::{AnalyticsEvent, EventBuffer, EventBufferConfig};
+    use std::time::Duration;
+
+    let pool = create_test_db().await;
+    let domain = create_test_domain(&pool, "buffered.testblog.com", "Buffered Blog").await;
+
+    // Batches larger than the events recorded, so only the timer writes them
+    let events = EventBuffer::spawn(
+        pool.clone(),
+        EventBufferConfig {
+            batch_size: 100,
+            flush_interval: Duration::from_millis(50),
+            capacity: 100,
+        },
+    );
+    for i in 0..5 {
+        events
+            .record(AnalyticsEvent {
+                path: Some(format!("/page-{i}")),
+                ..AnalyticsEvent::new(domain.id, "page_view", Utc::now())
+            })
+            .await
+            .unwrap();
+    }
+
+    let mut written = 0;
+    for _ in 0..50 {
+        written = count_events(&pool, domain.id).await;
+        if written == 5 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(written, 5);
+
+    events.shutdown().await;
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_event_buffer_shutdown_writes_queued_events() {
+    use api::services::event_buffer::{AnalyticsEvent, EventBuffer, EventBufferConfig};
+    use std::time::Duration;
+
+    let pool = create_test_db().await;
+    let domain = create_test_domain(&pool, "buffered.testblog.com", "Buffered Blog").await;
+
+    // Neither the batch size nor the timer is reached before shutdown
+    let events = EventBuffer::spawn(
+        pool.clone(),
+        EventBufferConfig {
+            batch_size: 1000,
+            flush_interval: Duration::from_secs(3600),
+            capacity: 1000,
+        },
+    );
+    for _ in 0..10 {
+        events
+            .record(AnalyticsEvent::new(domain.id, "search", Utc::now()))
+            .await
+            .unwrap();
+    }
+    assert_eq!(count_events(&pool, domain.id).await, 0);
+
+    events.shutdown().await;
+    assert_eq!(count_events(&pool, domain.id).await, 10);
+
+    // Events recorded after shutdown are written straight away
+    events
+        .record(AnalyticsEvent::new(domain.id, "search", Utc::now()))
+        .await
+        .unwrap();
+    assert_eq!(count_events(&pool, domain.id).await, 11);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_dashboard_delta_waits_for_buffered_events() {
+    use api::services::clock::{Clock, FrozenClock};
+    use api::services::event_buffer::{AnalyticsEvent, EventBuffer, EventBufferConfig};
+    use chrono::{Duration, SecondsFormat};
+
+    let pool = create_test_db().await;
+    let domain = create_test_domain(&pool, "buffered.testblog.com", "Buffered Blog").await;
+    let user = create_test_user(&pool, "owner@test.com", "Owner", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+
+    // Only explicit flushes write, so the test decides when events land
+    let events = EventBuffer::spawn(
+        pool.clone(),
+        EventBufferConfig {
+            batch_size: 100,
+            flush_interval: std::time::Duration::from_secs(60),
+            capacity: 100,
+        },
+    );
+    let clock = FrozenClock::new(Utc::now());
+    let state = Arc::new(AppState {
+        events: events.clone(),
+        clock: Arc::new(clock.clone()),
+        ..AppState::new(pool.clone())
+    });
+    let write_delay = Duration::from_std(events.write_delay()).unwrap();
+
+    let mut viewer = user.clone();
+    viewer.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+    let server = TestServer::new(create_analytics_app(state).layer(Extension(viewer))).unwrap();
+    let poll = |since: String| server.get("/dashboard").add_query_param("since", since);
+
+    // A page view recorded after the last poll is still in the buffer when
+    // the next poll comes
+    let since = clock.now().to_rfc3339_opts(SecondsFormat::Micros, true);
+    clock.advance(Duration::seconds(1));
+    events
+        .record(AnalyticsEvent::new(domain.id, "page_view", clock.now()))
+        .await
+        .unwrap();
+    clock.advance(Duration::seconds(1));
+    assert_eq!(count_events(&pool, domain.id).await, 0);
+
+    let delta: Value = poll(since).await.json();
+    assert_eq!(delta["page_views"], 0);
+
+    // Once the buffer has had time to write it, the following poll counts it
+    events.flush().await;
+    clock.advance(write_delay);
+    let delta: Value = poll(delta["until"].as_str().unwrap().to_string())
+        .await
+        .json();
+    assert_eq!(delta["page_views"], 1);
+
+    let delta: Value = poll(delta["until"].as_str().unwrap().to_string())
+        .await
+        .json();
+    assert_eq!(delta["page_views"], 0);
+
+    events.shutdown().await;
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_referrers_are_stored_anonymized() {
+    use api::analytics_middleware;
+    use api::handlers::{HandlerModule, blog::BlogModule};
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "analytics.testblog.com", "Analytics Test Blog").await;
+    let user = create_test_user(&pool, "analytics@test.com", "Analytics User", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+
+    let blog = TestServer::new(
+        BlogModule::routes()
+            .with_state(state.clone())
+            .layer(Extension(domain.clone()))
+            .layer(middleware::from_fn(analytics_middleware)),
+    )
+    .unwrap();
+    for referrer in [
+        "https://News.example.com/story?token=secret",
+        "https://news.example.com/story/?utm_source=mail#comments",
+        "https://news.example.com:443/story",
+    ] {
+        blog.get("/")
+            .add_header("referer", HeaderValue::from_static(referrer))
+            .await;
+    }
+
+    let stored: Vec<String> = sqlx::query_scalar(
+        "SELECT referrer FROM analytics_events WHERE domain_id = $1 AND referrer IS NOT NULL",
+    )
+    .bind(domain.id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(stored.len(), 3);
+    assert!(stored.iter().all(|r| r == "https://news.example.com/story"));
+
+    let mut viewer = user.clone();
+    viewer.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+    let server = TestServer::new(create_analytics_app(state).layer(Extension(viewer))).unwrap();
+    let body: Value = server.get("/referrers").await.json();
+    let top = body["top_referrers"].as_array().unwrap();
+    assert_eq!(top.len(), 1);
+    assert_eq!(top[0]["referrer"], "https://news.example.com/story");
+    assert_eq!(top[0]["visits"], 3);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_dashboard_delta_counts_only_new_events() {
+    use api::services::clock::{Clock, FrozenClock};
+    use axum::http::StatusCode;
+    use chrono::{Duration, SecondsFormat};
+
+    let pool = create_test_db().await;
+
+    let domain = create_test_domain(&pool, "delta.testblog.com", "Delta Blog").await;
+    let other = create_test_domain(&pool, "other.testblog.com", "Other Blog").await;
+    let user = create_test_user(&pool, "owner@test.com", "Owner", "user").await;
+    create_test_permission(&pool, user.id, domain.id, "viewer").await;
+    let post_id = create_test_post(
+        &pool,
+        domain.id,
+        "Delta Post",
+        "Content",
+        "Author",
+        "published",
+    )
+    .await;
+    // Recorded before the first poll
+    create_test_analytics_data(&pool, domain.id, Some(post_id)).await;
+
+    let clock = FrozenClock::new(Utc::now());
+    let state = Arc::new(AppState {
+        clock: Arc::new(clock.clone()),
+        ..AppState::new(pool.clone())
+    });
+    let write_delay = Duration::from_std(state.events.write_delay()).unwrap();
+
+    let mut viewer = user.clone();
+    viewer.domain_permissions = vec![api::DomainPermission {
+        domain_id: domain.id,
+        role: "viewer".to_string(),
+    }];
+    let server = TestServer::new(create_analytics_app(state).layer(Extension(viewer))).unwrap();
+
+    // Nothing new since the last poll
+    let since = clock.now().to_rfc3339_opts(SecondsFormat::Micros, true);
+    let response = server.get(&format!("/dashboard?since={since}")).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let delta: Value = response.json();
+    assert_eq!(delta["page_views"], 0);
+    assert_eq!(delta["post_views"], 0);
+    assert_eq!(delta["searches"], 0);
+    assert_eq!(delta["posts"], serde_json::json!([]));
+    assert!(delta.get("overview").is_none());
+
+    // New events, in the user's domain and in another one
+    create_test_analytics_data(&pool, domain.id, Some(post_id)).await;
+    create_test_analytics_data(&pool, other.id, None).await;
+    clock.advance(write_delay + Duration::minutes(1));
+
+    let until = delta["until"].as_str().unwrap().to_string();
+    let delta: Value = server
+        .get(&format!("/dashboard?since={until}"))
+        .await
+        .json();
+    assert_eq!(delta["since"], until);
+    assert_eq!(delta["page_views"], 2);
+    assert_eq!(delta["post_views"], 1);
+    assert_eq!(delta["searches"], 1);
+    assert_eq!(delta["posts"][0]["id"], post_id);
+    assert_eq!(delta["posts"][0]["slug"], "delta-post");
+    assert_eq!(delta["posts"][0]["views"], 1);
+
+    // Polling again from the latest `until` finds nothing
+    let until = delta["until"].as_str().unwrap().to_string();
+    let delta: Value = server
+        .get(&format!("/dashboard?since={until}"))
+        .await
+        .json();
+    assert_eq!(delta["page_views"], 0);
+    assert_eq!(delta["posts"], serde_json::json!([]));
+
+    // Unparseable or too old a `since` asks for a full reload
+    assert_eq!(
+        server.get("/dashboard?since=yesterday").await.status_code(),
+        StatusCode::BAD_REQUEST
+    );
+    let stale = (clock.now() - Duration::days(2)).to_rfc3339_opts(SecondsFormat::Secs, true);
+    assert_eq!(
+        server
+            .get(&format!("/dashboard?since={stale}"))
+            .await
+            .status_code(),
+        StatusCode::BAD_REQUEST
+    );
+
+    cleanup_test_db(&pool).await;
+}
@@ -1,24 +1,39 @@
 // tests/blog_tests.rs
-use api::{AppState, DomainContext, handlers::blog::BlogModule, test_utils::*};
+use api::{
+    AnalyticsContext, AppState, DomainContext,
+    handlers::{HandlerModule, blog::BlogModule},
+    test_utils::*,
+};
 use axum::{
     Extension, Router,
-    body::Body,
-    http::{Request, StatusCode},
+    http::{HeaderName, HeaderValue, StatusCode},
 };
 use axum_test::TestServer;
 use serde_json::Value;
 use serial_test::serial;
 use std::sync::Arc;
 
+fn session_header() -> HeaderName {
+    HeaderName::from_static(api::handlers::blog::SESSION_HEADER)
+}
+
 fn create_blog_app(state: Arc<AppState>) -> Router {
-    BlogModule::routes().with_state(state)
+    // The analytics middleware supplies this in the full app
+    let analytics = AnalyticsContext {
+        ip_address: "127.0.0.1".to_string(),
+        user_agent: "Mozilla/5.0".to_string(),
+        referrer: None,
+    };
+    BlogModule::routes()
+        .with_state(state)
+        .layer(Extension(analytics))
 }
 
 #[tokio::test]
 #[serial]
 async fn test_home_endpoint() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     // Create test domain and posts
     let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
@@ -40,7 +55,8 @@ async fn test_home_endpoint() {
     assert_eq!(response.status_code(), StatusCode::OK);
 
     let body: Value = response.json();
-    assert!(body.get("posts").is_some());
+    assert_eq!(body["domain"], "Test Blog");
+    assert_eq!(body["recent_posts"].as_array().unwrap().len(), 1);
 
     cleanup_test_db(&pool).await;
 }
@@ -49,7 +65,7 @@ async fn test_home_endpoint() {
 #[serial]
 async fn test_list_posts() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
 
@@ -84,7 +100,7 @@ async fn test_list_posts() {
 #[serial]
 async fn test_get_post_by_slug() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
     let _post_id = create_test_post(
@@ -114,11 +130,97 @@ async fn test_get_post_by_slug() {
     cleanup_test_db(&pool).await;
 }
 
+#[tokio::test]
+#[serial]
+async fn test_get_post_translation_with_locale_fallback() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    let post_id = create_test_post(
+        &pool,
+        domain.id,
+        "Hello World",
+        "English content",
+        "John Doe",
+        "published",
+    )
+    .await;
+    sqlx::query(
+        "INSERT INTO post_translations (post_id, locale, title, content) VALUES ($1, 'fr', 'Bonjour le monde', 'Contenu en français'), ($1, 'pt-br', 'Olá mundo', 'Conteúdo')",
+    )
+    .bind(post_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let analytics = AnalyticsContext {
+        ip_address: "127.0.0.1".to_string(),
+        user_agent: "Mozilla/5.0".to_string(),
+        referrer: None,
+    };
+    let app = create_blog_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(analytics));
+    let server = TestServer::new(app).unwrap();
+
+    let translated = |locale: &str| {
+        let request = server.get(&format!("/posts/hello-world?locale={locale}"));
+        async move {
+            let response = request.await;
+            assert_eq!(response.status_code(), StatusCode::OK);
+            response.json::<Value>()
+        }
+    };
+
+    let body = translated("fr").await;
+    assert_eq!(body["title"], "Bonjour le monde");
+    assert_eq!(body["content"], "Contenu en français");
+    assert_eq!(body["locale"], "fr");
+
+    // Regional variants fall back to the language, case-insensitively
+    let body = translated("fr-CA").await;
+    assert_eq!(body["locale"], "fr");
+    let body = translated("pt_BR").await;
+    assert_eq!(body["title"], "Olá mundo");
+
+    // Unknown or malformed locales get the default language
+    for locale in ["de", "english"] {
+        let body = translated(locale).await;
+        assert_eq!(body["title"], "Hello World");
+        assert_eq!(body["content"], "English content");
+        assert!(body.get("locale").is_none());
+    }
+
+    let body: Value = server.get("/posts/hello-world").await.json();
+    assert_eq!(body["title"], "Hello World");
+
+    // Each translation has its own ETag
+    let default_etag = server.get("/posts/hello-world").await.headers()["etag"].clone();
+    let french_etag = server.get("/posts/hello-world?locale=fr").await.headers()["etag"].clone();
+    assert_ne!(default_etag, french_etag);
+
+    // The feed links translated posts with hreflang alternates
+    let feed = server.get("/feed.xml").await.text();
+    assert!(
+        feed.contains(r#"hreflang="fr" href="https://testblog.com/posts/hello-world?locale=fr""#)
+    );
+    assert!(feed.contains(r#"hreflang="x-default""#));
+
+    // and so does the sitemap
+    let sitemap = server.get("/sitemap.xml").await.text();
+    assert!(sitemap.contains(
+        r#"<xhtml:link rel="alternate" hreflang="pt-br" href="https://testblog.com/posts/hello-world?locale=pt-br"/>"#
+    ));
+
+    cleanup_test_db(&pool).await;
+}
+
 #[tokio::test]
 #[serial]
 async fn test_get_nonexistent_post() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
 
@@ -132,11 +234,83 @@ async fn test_get_nonexistent_post() {
     cleanup_test_db(&pool).await;
 }
 
+#[tokio::test]
+#[serial]
+async fn test_post_by_id_redirects_to_slug() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    let other = create_test_domain(&pool, "other.com", "Other Blog").await;
+    let published = create_test_post(
+        &pool,
+        domain.id,
+        "Published Post",
+        "Content",
+        "Test Author",
+        "published",
+    )
+    .await;
+    let draft = create_test_post(
+        &pool,
+        domain.id,
+        "Draft Post",
+        "Content",
+        "Test Author",
+        "draft",
+    )
+    .await;
+    let private = create_test_post(
+        &pool,
+        domain.id,
+        "Private Post",
+        "Content",
+        "Test Author",
+        "published",
+    )
+    .await;
+    sqlx::query("UPDATE posts SET visibility = 'private' WHERE id = $1")
+        .bind(private)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let elsewhere = create_test_post(
+        &pool,
+        other.id,
+        "Other Post",
+        "Content",
+        "Test Author",
+        "published",
+    )
+    .await;
+
+    let app = create_blog_app(state).layer(Extension(domain));
+    let server = TestServer::new(app).unwrap();
+
+    let response = server.get(&format!("/posts/id/{published}")).await;
+    assert_eq!(response.status_code(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(response.header("location"), "/posts/published-post");
+    let response = server
+        .get(&format!("/posts/id/{published}?locale=fr"))
+        .await;
+    assert_eq!(
+        response.header("location"),
+        "/posts/published-post?locale=fr"
+    );
+
+    for id in [draft, private, elsewhere, 999_999] {
+        let response = server.get(&format!("/posts/id/{id}")).await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND, "{id}");
+    }
+
+    cleanup_test_db(&pool).await;
+}
+
 #[tokio::test]
 #[serial]
 async fn test_search_posts() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
 
@@ -184,11 +358,132 @@ async fn test_search_posts() {
     cleanup_test_db(&pool).await;
 }
 
+#[tokio::test]
+#[serial]
+async fn test_search_ranks_title_match_above_body_match() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+
+    // The title match is older, so it only comes first on rank
+    create_test_post(
+        &pool,
+        domain.id,
+        "Ownership in Rust",
+        "Borrowing and lifetimes explained",
+        "Developer",
+        "published",
+    )
+    .await;
+    create_test_post(
+        &pool,
+        domain.id,
+        "Async Patterns",
+        "Futures and executors, with examples written in Rust",
+        "Developer",
+        "published",
+    )
+    .await;
+
+    let analytics = AnalyticsContext {
+        ip_address: "127.0.0.1".to_string(),
+        user_agent: "Mozilla/5.0".to_string(),
+        referrer: None,
+    };
+    let app = create_blog_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(analytics));
+    let server = TestServer::new(app).unwrap();
+
+    let body: Value = server.get("/search?q=rust").await.json();
+    let titles: Vec<&str> = body["posts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|post| post["title"].as_str().unwrap())
+        .collect();
+    assert_eq!(titles, vec!["Ownership in Rust", "Async Patterns"]);
+    assert!(body["posts"][0].get("highlight").is_none());
+
+    let body: Value = server.get("/search?q=rust&highlight=true").await.json();
+    let highlight = body["posts"][1]["highlight"].as_str().unwrap();
+    assert!(highlight.contains("<mark>Rust</mark>"));
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_search_synonyms_and_stopwords_per_domain() {
+    use api::services::search::{self, SearchSettings};
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    let other = create_test_domain(&pool, "other.com", "Other Blog").await;
+    for domain_id in [domain.id, other.id] {
+        create_test_post(
+            &pool,
+            domain_id,
+            "JavaScript Tips",
+            "Useful browser programming guide",
+            "Developer",
+            "published",
+        )
+        .await;
+    }
+
+    search::save(
+        &pool,
+        domain.id,
+        &SearchSettings {
+            synonyms: vec![vec!["js".to_string(), "javascript".to_string()]],
+            stopwords: vec!["guide".to_string()],
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let analytics = AnalyticsContext {
+        ip_address: "127.0.0.1".to_string(),
+        user_agent: "Mozilla/5.0".to_string(),
+        referrer: None,
+    };
+    let app = create_blog_app(state.clone())
+        .layer(Extension(domain))
+        .layer(Extension(analytics.clone()));
+    let server = TestServer::new(app).unwrap();
+    let body: Value = server.get("/search?q=js").await.json();
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["posts"][0]["slug"], "javascript-tips");
+
+    // A stopword is left out, so it neither matches nor narrows the search
+    let body: Value = server.get("/search?q=guide").await.json();
+    assert_eq!(body["total"], 0);
+    let body: Value = server.get("/search?q=browser+guide").await.json();
+    assert_eq!(body["total"], 1);
+
+    // The other domain has no synonyms configured
+    let app = create_blog_app(state)
+        .layer(Extension(other))
+        .layer(Extension(analytics));
+    let server = TestServer::new(app).unwrap();
+    let body: Value = server.get("/search?q=js").await.json();
+    assert_eq!(body["total"], 0);
+    let body: Value = server.get("/search?q=guide").await.json();
+    assert_eq!(body["total"], 1);
+
+    cleanup_test_db(&pool).await;
+}
+
 #[tokio::test]
 #[serial]
 async fn test_get_category_posts() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
 
@@ -227,7 +522,7 @@ async fn test_get_category_posts() {
 #[serial]
 async fn test_rss_feed() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
     create_test_post(
@@ -261,3 +556,1426 @@ async fn test_rss_feed() {
 
     cleanup_test_db(&pool).await;
 }
+
+#[tokio::test]
+#[serial]
+async fn test_category_feed_only_has_matching_published_posts() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    let other = create_test_domain(&pool, "other.com", "Other Blog").await;
+    create_test_post(
+        &pool,
+        domain.id,
+        "Rust Tips",
+        "Content",
+        "Author",
+        "published",
+    )
+    .await;
+    create_test_post(&pool, domain.id, "Draft Idea", "Content", "Author", "draft").await;
+    let go_tips = create_test_post(
+        &pool,
+        domain.id,
+        "Go Tips",
+        "Content",
+        "Author",
+        "published",
+    )
+    .await;
+    sqlx::query("UPDATE posts SET category = 'Programming' WHERE id = $1")
+        .bind(go_tips)
+        .execute(&pool)
+        .await
+        .unwrap();
+    create_test_post(
+        &pool,
+        other.id,
+        "Elsewhere",
+        "Content",
+        "Author",
+        "published",
+    )
+    .await;
+
+    let app = create_blog_app(state).layer(Extension(domain.clone()));
+    let server = TestServer::new(app).unwrap();
+
+    let response = server.get("/feed/category/Technology.xml").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert!(
+        response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .contains("application/rss+xml")
+    );
+    let feed = response.text();
+    assert!(feed.contains("<title>Test Blog - Technology</title>"));
+    assert!(feed.contains("Rust Tips"));
+    assert!(!feed.contains("Draft Idea"));
+    assert!(!feed.contains("Go Tips"));
+    assert!(!feed.contains("Elsewhere"));
+
+    let feed = server.get("/feed/category/Programming.xml").await.text();
+    assert!(feed.contains("Go Tips"));
+    assert!(!feed.contains("Rust Tips"));
+
+    // Titles and categories are escaped rather than read as markup
+    let spicy = create_test_post(
+        &pool,
+        domain.id,
+        "Salt & <Pepper>",
+        "Content",
+        "Author",
+        "published",
+    )
+    .await;
+    sqlx::query("UPDATE posts SET category = 'Food & Drink' WHERE id = $1")
+        .bind(spicy)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let feed = server
+        .get("/feed/category/Food%20%26%20Drink.xml")
+        .await
+        .text();
+    assert!(feed.contains("<title>Test Blog - Food &amp; Drink</title>"));
+    assert!(feed.contains("<title>Salt &amp; &lt;Pepper&gt;</title>"));
+    assert!(!feed.contains("<Pepper>"));
+
+    // Categories the domain doesn't have
+    for path in ["/feed/category/Cooking.xml", "/feed/category/Technology"] {
+        assert_eq!(
+            server.get(path).await.status_code(),
+            StatusCode::NOT_FOUND,
+            "{path}"
+        );
+    }
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_post_views_deduplicated_within_window() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    let post_id = create_test_post(
+        &pool,
+        domain.id,
+        "Popular Post",
+        "Everyone refreshes this one",
+        "John Doe",
+        "published",
+    )
+    .await;
+
+    let session_id = "22222222-2222-2222-2222-222222222222";
+    sqlx::query("INSERT INTO user_sessions (session_id, domain_name) VALUES ($1::uuid, $2)")
+        .bind(session_id)
+        .bind(&domain.hostname)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let analytics = AnalyticsContext {
+        ip_address: "127.0.0.1".to_string(),
+        user_agent: "Mozilla/5.0".to_string(),
+        referrer: None,
+    };
+    let app = create_blog_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(analytics));
+    let server = TestServer::new(app).unwrap();
+
+    let count_views = || async {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM analytics_events WHERE event_type = 'post_view' AND post_id = $1",
+        )
+        .bind(post_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+    };
+
+    // Refreshing within the window counts once
+    for _ in 0..3 {
+        let response = server
+            .get("/posts/popular-post")
+            .add_header(session_header(), HeaderValue::from_static(session_id))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+    }
+    assert_eq!(count_views().await, 1);
+
+    // Once the earlier view falls outside the window, it counts again
+    sqlx::query(
+        "UPDATE analytics_events SET created_at = NOW() - INTERVAL '31 minutes' WHERE post_id = $1",
+    )
+    .bind(post_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    server
+        .get("/posts/popular-post")
+        .add_header(session_header(), HeaderValue::from_static(session_id))
+        .await;
+    assert_eq!(count_views().await, 2);
+
+    // A different reader is counted separately
+    server
+        .get("/posts/popular-post")
+        .add_header(
+            session_header(),
+            HeaderValue::from_static("33333333-3333-3333-3333-333333333333"),
+        )
+        .await;
+    assert_eq!(count_views().await, 3);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_post_view_increments_view_count() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    let quiet_id = create_test_post(
+        &pool,
+        domain.id,
+        "Quiet Post",
+        "Nobody reads this",
+        "John Doe",
+        "published",
+    )
+    .await;
+    let popular_id = create_test_post(
+        &pool,
+        domain.id,
+        "Popular Post",
+        "Everyone reads this",
+        "John Doe",
+        "published",
+    )
+    .await;
+    // Newest first by default, so the quiet post is older
+    sqlx::query("UPDATE posts SET created_at = NOW() - INTERVAL '1 day' WHERE id = $1")
+        .bind(popular_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let analytics = AnalyticsContext {
+        ip_address: "127.0.0.1".to_string(),
+        user_agent: "Mozilla/5.0".to_string(),
+        referrer: None,
+    };
+    let app = create_blog_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(analytics));
+    let server = TestServer::new(app).unwrap();
+
+    let view_count = |id: i32| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, i64>("SELECT view_count FROM posts WHERE id = $1")
+                .bind(id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        }
+    };
+
+    // A deduplicated refresh doesn't count twice
+    server.get("/posts/popular-post").await;
+    server.get("/posts/popular-post").await;
+    assert_eq!(view_count(popular_id).await, 1);
+    assert_eq!(view_count(quiet_id).await, 0);
+
+    let body: Value = server.get("/posts").await.json();
+    assert_eq!(body["posts"][0]["id"], quiet_id);
+    let body: Value = server.get("/posts?sort=popular").await.json();
+    assert_eq!(body["posts"][0]["id"], popular_id);
+    let response = server.get("/posts?sort=random").await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    cleanup_test_db(&pool).await;
+}
+
+/// Three published posts, created a day apart with the first one oldest
+async fn create_dated_posts(pool: &sqlx::PgPool, domain_id: i32) -> Vec<i32> {
+    let mut ids = Vec::new();
+    for (days_ago, title) in [(3, "Old Post"), (2, "Middle Post"), (1, "New Post")] {
+        let id = create_test_post(pool, domain_id, title, "Content", "John Doe", "published").await;
+        sqlx::query(
+            "UPDATE posts SET created_at = NOW() - make_interval(days => $2) WHERE id = $1",
+        )
+        .bind(id)
+        .bind(days_ago)
+        .execute(pool)
+        .await
+        .unwrap();
+        ids.push(id);
+    }
+    ids
+}
+
+fn listed_ids(body: &Value) -> Vec<i64> {
+    body["posts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|post| post["id"].as_i64().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+#[serial]
+async fn test_list_sort_modes() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    let ids = create_dated_posts(&pool, domain.id).await;
+    let (old, middle, new) = (ids[0] as i64, ids[1] as i64, ids[2] as i64);
+    sqlx::query("UPDATE posts SET view_count = $2 WHERE id = $1")
+        .bind(middle as i32)
+        .bind(10_i64)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let analytics = AnalyticsContext {
+        ip_address: "127.0.0.1".to_string(),
+        user_agent: "Mozilla/5.0".to_string(),
+        referrer: None,
+    };
+    let app = create_blog_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(analytics));
+    let server = TestServer::new(app).unwrap();
+
+    let body: Value = server.get("/posts").await.json();
+    assert_eq!(listed_ids(&body), vec![new, middle, old]);
+    let body: Value = server.get("/posts?sort=recent").await.json();
+    assert_eq!(listed_ids(&body), vec![new, middle, old]);
+    let body: Value = server.get("/posts?sort=oldest").await.json();
+    assert_eq!(listed_ids(&body), vec![old, middle, new]);
+    let body: Value = server.get("/posts?sort=popular").await.json();
+    assert_eq!(listed_ids(&body), vec![middle, new, old]);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_pinned_posts_come_first() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    let ids = create_dated_posts(&pool, domain.id).await;
+    let (old, middle, new) = (ids[0] as i64, ids[1] as i64, ids[2] as i64);
+    sqlx::query("UPDATE posts SET pinned = TRUE WHERE id = $1")
+        .bind(old as i32)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // A pinned post on another domain doesn't change this one's listing
+    let other = create_test_domain(&pool, "other.testblog.com", "Other Blog").await;
+    let other_id = create_test_post(
+        &pool,
+        other.id,
+        "Other Pinned",
+        "Content",
+        "John Doe",
+        "published",
+    )
+    .await;
+    sqlx::query("UPDATE posts SET pinned = TRUE WHERE id = $1")
+        .bind(other_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let analytics = AnalyticsContext {
+        ip_address: "127.0.0.1".to_string(),
+        user_agent: "Mozilla/5.0".to_string(),
+        referrer: None,
+    };
+    let app = create_blog_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(analytics));
+    let server = TestServer::new(app).unwrap();
+
+    let body: Value = server.get("/posts").await.json();
+    assert_eq!(listed_ids(&body), vec![old, new, middle]);
+    assert_eq!(body["posts"][0]["pinned"], true);
+    assert_eq!(body["posts"][1]["pinned"], false);
+
+    let body: Value = server.get("/posts?sort=oldest").await.json();
+    assert_eq!(listed_ids(&body), vec![old, middle, new]);
+    let body: Value = server.get("/posts?sort=popular").await.json();
+    assert_eq!(listed_ids(&body)[0], old);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_list_views_include_content_only_when_full() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    create_test_post(
+        &pool,
+        domain.id,
+        "Open Post",
+        "Full text of the open post",
+        "John Doe",
+        "published",
+    )
+    .await;
+    let members_id = create_test_post(
+        &pool,
+        domain.id,
+        "Members Post",
+        "Full text of the members post",
+        "John Doe",
+        "published",
+    )
+    .await;
+    sqlx::query("UPDATE posts SET visibility = 'members' WHERE id = $1")
+        .bind(members_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let analytics = AnalyticsContext {
+        ip_address: "127.0.0.1".to_string(),
+        user_agent: "Mozilla/5.0".to_string(),
+        referrer: None,
+    };
+    let app = create_blog_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(analytics));
+    let server = TestServer::new(app).unwrap();
+
+    let post_titled = |body: &Value, title: &str| {
+        body["posts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|post| post["title"] == title)
+            .unwrap()
+            .clone()
+    };
+
+    for path in ["/posts", "/posts?view=summary"] {
+        let body: Value = server.get(path).await.json();
+        let post = post_titled(&body, "Open Post");
+        assert!(post.get("content").is_none());
+        assert_eq!(post["slug"], "open-post");
+        assert!(post["excerpt"].is_string());
+    }
+
+    let body: Value = server.get("/posts?view=full").await.json();
+    assert_eq!(
+        post_titled(&body, "Open Post")["content"],
+        "Full text of the open post"
+    );
+    // Members-only content isn't listed, even in full
+    assert!(post_titled(&body, "Members Post").get("content").is_none());
+
+    let response = server.get("/posts?view=everything").await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_full_sample_rate_records_every_page_view() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let mut domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    domain.theme_config = serde_json::json!({
+        "analytics_config": { "page_view_sample_rate": 1.0 }
+    });
+    create_test_post(
+        &pool,
+        domain.id,
+        "Sampled Post",
+        "Content",
+        "John Doe",
+        "published",
+    )
+    .await;
+
+    let analytics = AnalyticsContext {
+        ip_address: "127.0.0.1".to_string(),
+        user_agent: "Mozilla/5.0".to_string(),
+        referrer: None,
+    };
+    let app = create_blog_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(analytics));
+    let server = TestServer::new(app).unwrap();
+
+    for _ in 0..5 {
+        let response = server.get("/posts/sampled-post").await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+    }
+
+    let (rows, weight): (i64, f64) = sqlx::query_as(
+        "SELECT COUNT(*), SUM(sample_weight) FROM analytics_events WHERE event_type = 'page_view'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(rows, 5);
+    assert_eq!(weight, 5.0);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_conditional_get_with_etag() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    let post_id = create_test_post(
+        &pool,
+        domain.id,
+        "Cached Post",
+        "Original content",
+        "John Doe",
+        "published",
+    )
+    .await;
+
+    let analytics = AnalyticsContext {
+        ip_address: "127.0.0.1".to_string(),
+        user_agent: "Mozilla/5.0".to_string(),
+        referrer: None,
+    };
+    let app = create_blog_app(state)
+        .layer(Extension(domain))
+        .layer(Extension(analytics));
+    let server = TestServer::new(app).unwrap();
+
+    let response = server.get("/posts/cached-post").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert!(
+        response
+            .headers()
+            .get("cache-control")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .contains("max-age=")
+    );
+    let etag = response.headers().get("etag").unwrap().clone();
+
+    // Unchanged post: 304 with no body
+    let response = server
+        .get("/posts/cached-post")
+        .add_header(HeaderName::from_static("if-none-match"), etag.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_MODIFIED);
+    assert!(response.text().is_empty());
+
+    // Editing the post changes its ETag
+    sqlx::query(
+        "UPDATE posts SET content = 'Edited content', updated_at = NOW() + INTERVAL '1 second' WHERE id = $1",
+    )
+    .bind(post_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let response = server
+        .get("/posts/cached-post")
+        .add_header(HeaderName::from_static("if-none-match"), etag.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_ne!(response.headers().get("etag").unwrap(), &etag);
+    assert_eq!(
+        response.json::<Value>()["content"].as_str().unwrap(),
+        "Edited content"
+    );
+
+    // Listings are tagged by their content
+    let response = server.get("/posts").await;
+    let list_etag = response.headers().get("etag").unwrap().clone();
+    let response = server
+        .get("/posts")
+        .add_header(HeaderName::from_static("if-none-match"), list_etag)
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_MODIFIED);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_theme_css() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let mut domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    domain.theme_config = serde_json::json!({
+        "primary": "#112233",
+        "accent": "#ec4899",
+        "fonts": { "body": "Inter, sans-serif" }
+    });
+
+    let app = create_blog_app(state).layer(Extension(domain));
+    let server = TestServer::new(app).unwrap();
+    let response = server.get("/theme.css").await;
+
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert!(
+        response
+            .headers()
+            .get("content-type")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("text/css")
+    );
+    assert!(response.headers().get("cache-control").is_some());
+
+    let css = response.text();
+    assert!(css.contains("--color-primary: #112233;"));
+    assert!(css.contains("--color-accent: #ec4899;"));
+    assert!(css.contains("--font-body: Inter, sans-serif;"));
+    // Keys the domain didn't set use the defaults
+    assert!(css.contains("--color-background: #ffffff;"));
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_theme_inherits_platform_default() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let mut domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    domain.theme_config = serde_json::json!({ "colors": { "primary": "#112233" } });
+
+    unsafe {
+        std::env::set_var(
+            "DEFAULT_THEME",
+            r##"{"colors": {"primary": "#0f766e", "accent": "#f59e0b"}, "logo": "/media/logo.svg"}"##,
+        )
+    };
+
+    let app = create_blog_app(state).layer(Extension(domain));
+    let server = TestServer::new(app).unwrap();
+
+    let response = server.get("/theme").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let theme: serde_json::Value = response.json();
+    // The one color the domain overrides, the rest from the platform default
+    // and the built-in theme
+    assert_eq!(theme["colors"]["primary"], "#112233");
+    assert_eq!(theme["colors"]["accent"], "#f59e0b");
+    assert_eq!(theme["colors"]["text"], "#111827");
+    assert_eq!(theme["logo"], "/media/logo.svg");
+    assert!(theme["fonts"]["body"].is_string());
+
+    let css = server.get("/theme.css").await.text();
+    assert!(css.contains("--color-primary: #112233;"));
+    assert!(css.contains("--color-accent: #f59e0b;"));
+
+    unsafe { std::env::remove_var("DEFAULT_THEME") };
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_disabled_features_are_unavailable() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let enabled = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    let mut disabled = create_test_domain(&pool, "quiet.testblog.com", "Quiet Blog").await;
+    disabled.features = serde_json::json!({ "search": false, "rss": false });
+
+    let analytics = AnalyticsContext {
+        ip_address: "127.0.0.1".to_string(),
+        user_agent: "Mozilla/5.0".to_string(),
+        referrer: None,
+    };
+
+    let server = TestServer::new(
+        create_blog_app(state.clone())
+            .layer(Extension(enabled))
+            .layer(Extension(analytics.clone())),
+    )
+    .unwrap();
+    assert_eq!(
+        server.get("/search?q=rust").await.status_code(),
+        StatusCode::OK
+    );
+    assert_eq!(server.get("/feed.xml").await.status_code(), StatusCode::OK);
+
+    let server = TestServer::new(
+        create_blog_app(state)
+            .layer(Extension(disabled))
+            .layer(Extension(analytics)),
+    )
+    .unwrap();
+    assert_eq!(
+        server.get("/search?q=rust").await.status_code(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        server.get("/feed.xml").await.status_code(),
+        StatusCode::NOT_FOUND
+    );
+    // Other routes are unaffected
+    assert_eq!(server.get("/posts").await.status_code(), StatusCode::OK);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_post_social_meta() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let mut domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    domain.theme_config = serde_json::json!({
+        "seo_config": {
+            "site_name": "The Test Blog",
+            "meta_description": "Notes from the test blog",
+            "social_image": "/images/default-og.png"
+        },
+        "social_config": { "twitter_handle": "@testblog" }
+    });
+    let with_image = create_test_post(
+        &pool,
+        domain.id,
+        "Shared Post",
+        "Content",
+        "Test Author",
+        "published",
+    )
+    .await;
+    sqlx::query("UPDATE posts SET excerpt = 'A post worth sharing', image_url = 'https://cdn.testblog.com/cover.png' WHERE id = $1")
+        .bind(with_image)
+        .execute(&pool)
+        .await
+        .unwrap();
+    create_test_post(
+        &pool,
+        domain.id,
+        "Plain Post",
+        "Content",
+        "Test Author",
+        "published",
+    )
+    .await;
+    sqlx::query("UPDATE posts SET excerpt = '' WHERE slug = 'plain-post'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    create_test_post(
+        &pool,
+        domain.id,
+        "Draft Post",
+        "Content",
+        "Test Author",
+        "draft",
+    )
+    .await;
+
+    let server = TestServer::new(create_blog_app(state).layer(Extension(domain))).unwrap();
+
+    let response = server.get("/posts/shared-post/meta").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["description"], "A post worth sharing");
+    assert_eq!(
+        body["canonical_url"],
+        "https://testblog.com/posts/shared-post"
+    );
+    assert_eq!(
+        body["open_graph"]["og:image"],
+        "https://cdn.testblog.com/cover.png"
+    );
+    assert_eq!(body["open_graph"]["og:site_name"], "The Test Blog");
+    assert_eq!(body["open_graph"]["article:author"], "Test Author");
+    assert_eq!(body["twitter"]["twitter:card"], "summary_large_image");
+    assert_eq!(body["twitter"]["twitter:site"], "@testblog");
+
+    // Fields the post leaves empty come from the domain's SEO settings
+    let body: Value = server.get("/posts/plain-post/meta").await.json();
+    assert_eq!(body["description"], "Notes from the test blog");
+    assert_eq!(body["image"], "https://testblog.com/images/default-og.png");
+
+    assert_eq!(
+        server.get("/posts/draft-post/meta").await.status_code(),
+        StatusCode::NOT_FOUND
+    );
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_post_seo_overrides_and_sitemap() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let mut domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    domain.theme_config = serde_json::json!({
+        "seo_config": { "meta_description": "Notes from the test blog" }
+    });
+    let tuned = create_test_post(
+        &pool,
+        domain.id,
+        "Tuned Post",
+        "Content",
+        "Test Author",
+        "published",
+    )
+    .await;
+    sqlx::query(
+        "UPDATE posts SET excerpt = '', meta_title = 'A Better Title', meta_description = 'Written for search' WHERE id = $1",
+    )
+    .bind(tuned)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let hidden = create_test_post(
+        &pool,
+        domain.id,
+        "Hidden Post",
+        "Content",
+        "Test Author",
+        "published",
+    )
+    .await;
+    sqlx::query("UPDATE posts SET noindex = TRUE WHERE id = $1")
+        .bind(hidden)
+        .execute(&pool)
+        .await
+        .unwrap();
+    // A slug saved before slugs were restricted
+    let legacy = create_test_post(
+        &pool,
+        domain.id,
+        "Fish and Chips",
+        "Content",
+        "Test Author",
+        "published",
+    )
+    .await;
+    sqlx::query("UPDATE posts SET slug = 'fish-&-chips' WHERE id = $1")
+        .bind(legacy)
+        .execute(&pool)
+        .await
+        .unwrap();
+    create_test_post(
+        &pool,
+        domain.id,
+        "Draft Post",
+        "Content",
+        "Test Author",
+        "draft",
+    )
+    .await;
+
+    let server = TestServer::new(create_blog_app(state).layer(Extension(domain))).unwrap();
+
+    // The post's own meta wins over its title and the domain default
+    let body: Value = server.get("/posts/tuned-post/meta").await.json();
+    assert_eq!(body["title"], "A Better Title");
+    assert_eq!(body["description"], "Written for search");
+    assert_eq!(body["open_graph"]["og:title"], "A Better Title");
+    assert!(body.get("robots").is_none());
+
+    // noindex posts stay readable but tell crawlers to skip them
+    let body: Value = server.get("/posts/hidden-post/meta").await.json();
+    assert_eq!(body["robots"], "noindex");
+    assert_eq!(body["description"], "Notes from the test blog");
+
+    let response = server.get("/sitemap.xml").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(
+        response.header("content-type"),
+        "application/xml; charset=utf-8"
+    );
+    let sitemap = response.text();
+    assert!(sitemap.contains("<loc>https://testblog.com/posts/tuned-post</loc>"));
+    assert!(sitemap.contains("<loc>https://testblog.com/posts/fish-&amp;-chips</loc>"));
+    assert!(!sitemap.contains("hidden-post"));
+    assert!(!sitemap.contains("draft-post"));
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_post_visibility_for_anonymous_and_member_readers() {
+    use api::handlers::auth::{create_access_token, create_member_token};
+    use api::handlers::members::members_router;
+
+    // SAFETY: tests in this file run serially
+    unsafe { std::env::set_var("JWT_SECRET", "test-secret") };
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    for (title, visibility) in [
+        ("Open Post", "public"),
+        ("Members Post", "members"),
+        ("Hidden Post", "private"),
+    ] {
+        let id = create_test_post(
+            &pool,
+            domain.id,
+            title,
+            &format!("Full text of the {visibility} post"),
+            "John Doe",
+            "published",
+        )
+        .await;
+        sqlx::query("UPDATE posts SET visibility = $2, excerpt = $3 WHERE id = $1")
+            .bind(id)
+            .bind(visibility)
+            .bind(format!("Teaser of the {visibility} post"))
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+    api::services::members::create(
+        &pool,
+        domain.id,
+        "reader@test.com",
+        "Reader",
+        &bcrypt::hash("Reader-pass-2024", 4).unwrap(),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    let analytics = AnalyticsContext {
+        ip_address: "127.0.0.1".to_string(),
+        user_agent: "Mozilla/5.0".to_string(),
+        referrer: None,
+    };
+    let app = BlogModule::routes()
+        .nest("/members", members_router())
+        .with_state(state.clone())
+        .layer(Extension(domain.clone()))
+        .layer(Extension(analytics));
+    let server = TestServer::new(app).unwrap();
+
+    // Anonymous readers: members-only posts are teased, private ones hidden
+    let response = server.get("/posts/open-post").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(
+        response.json::<Value>()["content"],
+        "Full text of the public post"
+    );
+
+    let response = server.get("/posts/members-post").await;
+    assert_eq!(response.status_code(), StatusCode::PAYMENT_REQUIRED);
+    let body: Value = response.json();
+    assert_eq!(body["error"], "members_only");
+    assert_eq!(body["teaser"]["title"], "Members Post");
+    assert_eq!(body["teaser"]["excerpt"], "Teaser of the members post");
+    assert!(!response.text().contains("Full text"));
+
+    let response = server.get("/posts/hidden-post").await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    let body: Value = server.get("/posts").await.json();
+    assert_eq!(body["total"], 2);
+    let visibilities: Vec<&str> = body["posts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|post| post["visibility"].as_str().unwrap())
+        .collect();
+    assert!(visibilities.contains(&"members"));
+    assert!(!visibilities.contains(&"private"));
+
+    // Members sign in and read everything but private posts
+    let response = server
+        .post("/members/login")
+        .json(&serde_json::json!({ "email": "reader@test.com", "password": "wrong-pass" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    let response = server
+        .post("/members/login")
+        .json(&serde_json::json!({ "email": "reader@test.com", "password": "Reader-pass-2024" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let token = response.json::<Value>()["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let member = HeaderValue::from_str(&format!("Bearer {token}")).unwrap();
+
+    let response = server
+        .get("/posts/open-post")
+        .add_header("authorization", member.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    let response = server
+        .get("/posts/members-post")
+        .add_header("authorization", member.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(
+        response.json::<Value>()["content"],
+        "Full text of the members post"
+    );
+    assert_eq!(response.header("cache-control"), "private, no-cache");
+
+    let response = server
+        .get("/posts/hidden-post")
+        .add_header("authorization", member)
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    // Tokens that aren't this domain's member tokens are turned away
+    let config = state.jwt.clone();
+    let other_domain = create_member_token(
+        &config,
+        "reader@test.com",
+        1,
+        domain.id + 1,
+        chrono::Duration::hours(1),
+    )
+    .unwrap();
+    let admin = create_access_token(&config, "admin@test.com", 1, "platform_admin").unwrap();
+    for token in [other_domain, admin, "garbage".to_string()] {
+        let response = server
+            .get("/posts/members-post")
+            .add_header(
+                "authorization",
+                HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
+            )
+            .await;
+        assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+        assert_eq!(response.json::<Value>()["teaser"]["title"], "Members Post");
+    }
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_dated_permalinks() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let mut domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    domain.theme_config =
+        serde_json::json!({ "seo_config": { "permalink_format": "/%year%/%month%/%slug%" } });
+    let post_id = create_test_post(
+        &pool,
+        domain.id,
+        "My Post",
+        "Dated permalink content",
+        "Test Author",
+        "published",
+    )
+    .await;
+    sqlx::query("UPDATE posts SET created_at = '2024-03-05T12:00:00Z' WHERE id = $1")
+        .bind(post_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let app = create_blog_app(state).layer(Extension(domain));
+    let server = TestServer::new(app).unwrap();
+
+    // Canonical URLs follow the format
+    let feed = server.get("/feed.xml").await.text();
+    assert!(feed.contains("<link>https://testblog.com/2024/03/my-post</link>"));
+    let meta: Value = server.get("/posts/my-post/meta").await.json();
+    assert_eq!(
+        meta["canonical_url"],
+        "https://testblog.com/2024/03/my-post"
+    );
+
+    // ... and resolve back to the post
+    let response = server
+        .get("/permalinks/resolve")
+        .add_query_param("path", "/2024/03/my-post")
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let resolved: Value = response.json();
+    assert_eq!(resolved["id"], post_id);
+    assert_eq!(resolved["slug"], "my-post");
+
+    // A path with the wrong date or shape names nothing
+    for path in ["/2024/04/my-post", "/posts/my-post", "/2024/03/other-post"] {
+        let response = server
+            .get("/permalinks/resolve")
+            .add_query_param("path", path)
+            .await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND, "{path}");
+    }
+
+    cleanup_test_db(&pool).await;
+}
+
+/// Keeps every email instead of sending it
+#[derive(Clone, Default)]
+struct RecordingMailer(Arc<std::sync::Mutex<Vec<api::services::digest::EmailMessage>>>);
+
+impl api::services::digest::Mailer for RecordingMailer {
+    async fn send(
+        &self,
+        message: api::services::digest::EmailMessage,
+    ) -> Result<(), api::services::digest::MailError> {
+        self.0.lock().unwrap().push(message);
+        Ok(())
+    }
+}
+
+impl RecordingMailer {
+    /// The confirmation token in the latest email
+    fn last_token(&self) -> String {
+        let sent = self.0.lock().unwrap();
+        let body = &sent.last().expect("no email sent").body;
+        let start = body.find("token=").unwrap() + "token=".len();
+        body[start..].lines().next().unwrap().to_string()
+    }
+
+    fn count(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
+    fn last(&self) -> api::services::digest::EmailMessage {
+        self.0
+            .lock()
+            .unwrap()
+            .last()
+            .expect("no email sent")
+            .clone()
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_subscribe_confirm_flow_and_duplicate_subscribe() {
+    use api::handlers::subscribers::subscribers_router;
+    use api::services::subscribers;
+    use serde_json::json;
+
+    let pool = create_test_db().await;
+    let mailer = RecordingMailer::default();
+    let state = Arc::new(AppState {
+        mailer: Some(Arc::new(mailer.clone())),
+        ..AppState::new(pool.clone())
+    });
+
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    let other = create_test_domain(&pool, "other.com", "Other Blog").await;
+    let app = Router::new()
+        .nest("/subscribe", subscribers_router())
+        .with_state(state)
+        .layer(Extension(domain.clone()));
+    let server = TestServer::new(app).unwrap();
+
+    let response = server
+        .post("/subscribe")
+        .json(&json!({ "email": "not-an-email" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+
+    let response = server
+        .post("/subscribe")
+        .json(&json!({ "email": "Reader@Example.com" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::ACCEPTED);
+    assert_eq!(mailer.count(), 1);
+    let first_token = mailer.last_token();
+
+    // Pending until the link is followed
+    let (listed, total) = subscribers::list(&pool, domain.id, None, 20, 0)
+        .await
+        .unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(listed[0].email, "reader@example.com");
+    assert_eq!(listed[0].status, "pending");
+    assert!(listed[0].confirmed_at.is_none());
+
+    // Subscribing again while pending sends a new link and voids the old one
+    let response = server
+        .post("/subscribe")
+        .json(&json!({ "email": "reader@example.com" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::ACCEPTED);
+    assert_eq!(mailer.count(), 2);
+    let token = mailer.last_token();
+    assert_ne!(token, first_token);
+
+    let response = server
+        .get("/subscribe/confirm")
+        .add_query_param("token", &first_token)
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    let response = server
+        .get("/subscribe/confirm")
+        .add_query_param("token", &token)
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    assert_eq!(body["email"], "reader@example.com");
+    let confirmed_at = body["confirmed_at"].clone();
+    assert!(confirmed_at.is_string());
+
+    // Following the link twice is harmless
+    let response = server
+        .get("/subscribe/confirm")
+        .add_query_param("token", &token)
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(response.json::<Value>()["confirmed_at"], confirmed_at);
+
+    // Subscribing once confirmed answers the same, without another email
+    let response = server
+        .post("/subscribe")
+        .json(&json!({ "email": "READER@example.com" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::ACCEPTED);
+    assert_eq!(mailer.count(), 2);
+
+    let (listed, total) = subscribers::list(&pool, domain.id, None, 20, 0)
+        .await
+        .unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(listed[0].status, "confirmed");
+
+    // The token only confirms on the domain it was issued for
+    let app = Router::new()
+        .nest("/subscribe", subscribers_router())
+        .with_state(Arc::new(AppState::new(pool.clone())))
+        .layer(Extension(other));
+    let response = TestServer::new(app)
+        .unwrap()
+        .get("/subscribe/confirm")
+        .add_query_param("token", &token)
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_subscription_link_expires_by_the_state_clock() {
+    use api::handlers::subscribers::subscribers_router;
+    use api::services::clock::{Clock, FrozenClock};
+    use api::services::subscribers::confirmation_ttl;
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use serde_json::json;
+
+    let pool = create_test_db().await;
+    let mailer = RecordingMailer::default();
+    let sent_at = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+    let clock = FrozenClock::new(sent_at);
+    let state = Arc::new(AppState {
+        mailer: Some(Arc::new(mailer.clone())),
+        clock: Arc::new(clock.clone()),
+        ..AppState::new(pool.clone())
+    });
+
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    let app = Router::new()
+        .nest("/subscribe", subscribers_router())
+        .with_state(state)
+        .layer(Extension(domain));
+    let server = TestServer::new(app).unwrap();
+    let subscribe = || {
+        server
+            .post("/subscribe")
+            .json(&json!({ "email": "reader@example.com" }))
+    };
+
+    assert_eq!(subscribe().await.status_code(), StatusCode::ACCEPTED);
+    clock.advance(confirmation_ttl() + Duration::seconds(1));
+    let response = server
+        .get("/subscribe/confirm")
+        .add_query_param("token", mailer.last_token())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    // A fresh link is dated from the clock's current time
+    assert_eq!(subscribe().await.status_code(), StatusCode::ACCEPTED);
+    let confirmed_at = clock.now() + confirmation_ttl() - Duration::seconds(1);
+    clock.set(confirmed_at);
+    let response = server
+        .get("/subscribe/confirm")
+        .add_query_param("token", mailer.last_token())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: Value = response.json();
+    let stamped: DateTime<Utc> = body["confirmed_at"].as_str().unwrap().parse().unwrap();
+    assert_eq!(stamped, confirmed_at);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_confirmation_email_uses_domain_template() {
+    use api::handlers::subscribers::subscribers_router;
+    use api::services::email_templates::{self, EmailKind, EmailTemplate};
+    use serde_json::json;
+
+    let pool = create_test_db().await;
+    let mailer = RecordingMailer::default();
+    let state = Arc::new(AppState {
+        mailer: Some(Arc::new(mailer.clone())),
+        ..AppState::new(pool.clone())
+    });
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    let other = create_test_domain(&pool, "other.com", "Other Blog").await;
+    let subscribe = |domain: DomainContext, email: &'static str| {
+        let app = Router::new()
+            .nest("/subscribe", subscribers_router())
+            .with_state(state.clone())
+            .layer(Extension(domain));
+        async move {
+            TestServer::new(app)
+                .unwrap()
+                .post("/subscribe")
+                .json(&json!({ "email": email }))
+                .await
+                .status_code()
+        }
+    };
+
+    email_templates::save(
+        &pool,
+        None,
+        EmailKind::SubscriptionConfirmation,
+        &EmailTemplate {
+            subject: "Welcome to {{blog}}".to_string(),
+            body: "Confirm at {{confirm_url}}\n".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    // A placeholder this email doesn't have and a stray brace are sent as written
+    email_templates::save(
+        &pool,
+        Some(domain.id),
+        EmailKind::SubscriptionConfirmation,
+        &EmailTemplate {
+            subject: "{{blog}} wants to hear from {{ email }}".to_string(),
+            body: "Dear {{first_name}}, follow {{confirm_url}} {{ before {{expires_at".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        subscribe(domain.clone(), "reader@example.com").await,
+        StatusCode::ACCEPTED
+    );
+    let email = mailer.last();
+    assert_eq!(
+        email.subject,
+        "Test Blog wants to hear from reader@example.com"
+    );
+    assert!(
+        email.body.starts_with(
+            "Dear {{first_name}}, follow https://testblog.com/subscribe/confirm?token="
+        )
+    );
+    assert!(email.body.ends_with(" {{ before {{expires_at"));
+
+    // Domains without their own template get the platform's
+    assert_eq!(
+        subscribe(other.clone(), "reader@example.com").await,
+        StatusCode::ACCEPTED
+    );
+    let email = mailer.last();
+    assert_eq!(email.subject, "Welcome to Other Blog");
+    assert!(
+        email
+            .body
+            .starts_with("Confirm at https://other.com/subscribe/confirm?token=")
+    );
+
+    // And the built-in one once the platform's is gone
+    assert!(
+        email_templates::delete(&pool, None, EmailKind::SubscriptionConfirmation)
+            .await
+            .unwrap()
+    );
+    assert_eq!(
+        subscribe(other, "second@example.com").await,
+        StatusCode::ACCEPTED
+    );
+    assert_eq!(
+        mailer.last().subject,
+        "Confirm your subscription to Other Blog"
+    );
+    assert_eq!(mailer.count(), 3);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_subscribe_needs_email_delivery() {
+    use api::handlers::subscribers::subscribers_router;
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState {
+        mailer: None,
+        ..AppState::new(pool.clone())
+    });
+    let domain = create_test_domain(&pool, "testblog.com", "Test Blog").await;
+    let app = Router::new()
+        .nest("/subscribe", subscribers_router())
+        .with_state(state)
+        .layer(Extension(domain.clone()));
+    let server = TestServer::new(app).unwrap();
+
+    let response = server
+        .post("/subscribe")
+        .json(&serde_json::json!({ "email": "reader@example.com" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.json::<Value>()["error"], "email_unavailable");
+
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM subscribers")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(stored, 0);
+
+    cleanup_test_db(&pool).await;
+}
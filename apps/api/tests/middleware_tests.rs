@@ -1,15 +1,15 @@
 // tests/middleware_tests.rs
 use api::{
     AppState, DomainContext, analytics_middleware, auth_middleware, domain_middleware,
+    middleware::{REQUEST_ID_HEADER, request_id_middleware},
     test_utils::*,
+    validation::extractors::ValidatedJson,
 };
 use axum::{
     Extension, Router,
-    body::Body,
     extract::State,
-    http::{HeaderMap, HeaderValue, Request, StatusCode},
+    http::{HeaderValue, StatusCode},
     middleware,
-    response::Response,
     routing::get,
 };
 use axum_test::TestServer;
@@ -31,7 +31,7 @@ async fn test_analytics_handler(Extension(analytics): Extension<api::AnalyticsCo
 #[serial]
 async fn test_domain_middleware_success() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     // Create test domain
     create_test_domain(&pool, "testdomain.com", "Test Domain").await;
@@ -63,7 +63,7 @@ async fn test_domain_middleware_success() {
 #[serial]
 async fn test_domain_middleware_unknown_domain() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let app = Router::new()
         .route("/test", get(test_handler))
@@ -81,6 +81,50 @@ async fn test_domain_middleware_unknown_domain() {
         .await;
 
     assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["error"], "domain_not_found");
+    assert_eq!(body["hostname"], "unknowndomain.com");
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_domain_middleware_redirects_unknown_domain() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    let app = Router::new()
+        .route("/test", get(test_handler).post(test_handler))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            domain_middleware,
+        ))
+        .with_state(state);
+
+    let server = TestServer::new(app).unwrap();
+
+    unsafe { std::env::set_var("UNKNOWN_DOMAIN_REDIRECT", "https://blogs.example.com/") };
+
+    let response = server
+        .get("/test")
+        .add_query_param("page", "2")
+        .add_header("host", HeaderValue::from_static("new.example.com"))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::FOUND);
+    assert_eq!(
+        response.headers()["location"],
+        "https://blogs.example.com/test?page=2"
+    );
+
+    // Writes can't follow a redirect meaningfully
+    let response = server
+        .post("/test")
+        .add_header("host", HeaderValue::from_static("new.example.com"))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    unsafe { std::env::remove_var("UNKNOWN_DOMAIN_REDIRECT") };
 
     cleanup_test_db(&pool).await;
 }
@@ -89,7 +133,7 @@ async fn test_domain_middleware_unknown_domain() {
 #[serial]
 async fn test_domain_middleware_with_port() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     // Create test domain
     create_test_domain(&pool, "testdomain.com", "Test Domain").await;
@@ -117,6 +161,49 @@ async fn test_domain_middleware_with_port() {
     cleanup_test_db(&pool).await;
 }
 
+#[tokio::test]
+#[serial]
+async fn test_domain_middleware_ignores_x_domain_in_production() {
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    create_test_domain(&pool, "testdomain.com", "Test Domain").await;
+    create_test_domain(&pool, "victim.com", "Victim Domain").await;
+
+    let app = Router::new()
+        .route("/test", get(test_handler))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            domain_middleware,
+        ))
+        .with_state(state);
+    let server = TestServer::new(app).unwrap();
+
+    unsafe {
+        std::env::set_var("ENVIRONMENT", "production");
+        std::env::remove_var("ALLOW_DOMAIN_HEADER_OVERRIDE");
+    }
+    let spoofed = server
+        .get("/test")
+        .add_header("host", HeaderValue::from_static("testdomain.com"))
+        .add_header("x-domain", HeaderValue::from_static("victim.com"))
+        .await;
+    unsafe { std::env::remove_var("ENVIRONMENT") };
+
+    assert_eq!(spoofed.status_code(), StatusCode::OK);
+    assert!(spoofed.text().contains("Test Domain"));
+
+    // Development keeps the override for local multi-blog testing
+    let overridden = server
+        .get("/test")
+        .add_header("host", HeaderValue::from_static("testdomain.com"))
+        .add_header("x-domain", HeaderValue::from_static("victim.com"))
+        .await;
+    assert!(overridden.text().contains("Victim Domain"));
+
+    cleanup_test_db(&pool).await;
+}
+
 #[tokio::test]
 async fn test_analytics_middleware() {
     let app = Router::new()
@@ -163,7 +250,7 @@ async fn test_auth_handler(Extension(user): Extension<api::UserContext>) -> Stri
 #[serial]
 async fn test_auth_middleware_missing_token() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let app = Router::new()
         .route("/test", get(test_auth_handler))
@@ -185,8 +272,12 @@ async fn test_auth_middleware_missing_token() {
 #[tokio::test]
 #[serial]
 async fn test_auth_middleware_with_token() {
+    use api::handlers::auth::create_access_token;
+
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
+    let user = create_test_user(&pool, "admin@test.com", "Admin User", "platform_admin").await;
+    let token = create_access_token(&state.jwt_config(), &user.email, user.id, &user.role).unwrap();
 
     let app = Router::new()
         .route("/test", get(test_auth_handler))
@@ -202,7 +293,7 @@ async fn test_auth_middleware_with_token() {
         .get("/test")
         .add_header(
             "authorization",
-            HeaderValue::from_static("Bearer valid_token"),
+            HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
         )
         .await;
 
@@ -217,7 +308,7 @@ async fn test_auth_middleware_with_token() {
 #[serial]
 async fn test_auth_middleware_invalid_format() {
     let pool = create_test_db().await;
-    let state = Arc::new(AppState { db: pool.clone() });
+    let state = Arc::new(AppState::new(pool.clone()));
 
     let app = Router::new()
         .route("/test", get(test_auth_handler))
@@ -241,3 +332,824 @@ async fn test_auth_middleware_invalid_format() {
 
     cleanup_test_db(&pool).await;
 }
+
+#[tokio::test]
+#[serial]
+async fn test_token_expires_by_the_state_clock() {
+    use api::handlers::auth::create_access_token;
+    use api::services::clock::FrozenClock;
+    use chrono::{Duration, TimeZone, Utc};
+
+    if std::env::var("JWT_SECRET").is_err() {
+        // SAFETY: tests in this file run serially
+        unsafe { std::env::set_var("JWT_SECRET", "test-secret") };
+    }
+
+    let pool = create_test_db().await;
+    let issued_at = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+    let clock = FrozenClock::new(issued_at);
+    let state = Arc::new(AppState {
+        clock: Arc::new(clock.clone()),
+        ..AppState::new(pool.clone())
+    });
+    let user = create_test_user(&pool, "clock@test.com", "Clock User", "user").await;
+
+    let config = state.jwt_config();
+    let token = create_access_token(&config, &user.email, user.id, &user.role).unwrap();
+    let last_valid = issued_at + config.access_ttl + Duration::seconds(config.leeway as i64);
+
+    let app = Router::new()
+        .route("/test", get(test_auth_handler))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .with_state(state);
+    let server = TestServer::new(app).unwrap();
+    let auth = HeaderValue::from_str(&format!("Bearer {token}")).unwrap();
+
+    let response = server
+        .get("/test")
+        .add_header("authorization", auth.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    clock.set(last_valid);
+    let response = server
+        .get("/test")
+        .add_header("authorization", auth.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    clock.advance(Duration::seconds(1));
+    let response = server.get("/test").add_header("authorization", auth).await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[derive(serde::Deserialize, validator::Validate)]
+struct NamedRequest {
+    #[validate(length(min = 1, message = "Name cannot be empty"))]
+    name: String,
+}
+
+async fn test_validated_handler(ValidatedJson(request): ValidatedJson<NamedRequest>) -> String {
+    request.name
+}
+
+fn request_id_app() -> Router {
+    Router::new()
+        .route("/test", get(|| async { "ok" }))
+        .route("/validated", axum::routing::post(test_validated_handler))
+        .layer(middleware::from_fn(request_id_middleware))
+}
+
+#[tokio::test]
+async fn test_request_id_generated_and_preserved() {
+    let server = TestServer::new(request_id_app()).unwrap();
+
+    // Without an inbound id one is generated
+    let response = server.get("/test").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let generated = response.header(REQUEST_ID_HEADER);
+    assert!(uuid::Uuid::parse_str(generated.to_str().unwrap()).is_ok());
+
+    // A client-supplied id is echoed back unchanged
+    let response = server
+        .get("/test")
+        .add_header(REQUEST_ID_HEADER, HeaderValue::from_static("client-req-42"))
+        .await;
+    assert_eq!(response.header(REQUEST_ID_HEADER), "client-req-42");
+
+    // An unusable id is replaced
+    let response = server
+        .get("/test")
+        .add_header(
+            REQUEST_ID_HEADER,
+            HeaderValue::from_static("has spaces in it"),
+        )
+        .await;
+    assert_ne!(response.header(REQUEST_ID_HEADER), "has spaces in it");
+}
+
+#[tokio::test]
+async fn test_validation_error_includes_request_id() {
+    let server = TestServer::new(request_id_app()).unwrap();
+
+    let response = server
+        .post("/validated")
+        .add_header(REQUEST_ID_HEADER, HeaderValue::from_static("client-req-43"))
+        .json(&serde_json::json!({ "name": "" }))
+        .await;
+
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.header(REQUEST_ID_HEADER), "client-req-43");
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["request_id"], "client-req-43");
+    assert_eq!(body["field_errors"]["name"][0], "Name cannot be empty");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_maintenance_mode_blocks_only_public_routes() {
+    use api::handlers::{HandlerModule, admin::AdminModule, auth::auth_router, blog::BlogModule};
+    use api::middleware::maintenance_middleware;
+    use serde_json::{Value, json};
+
+    if std::env::var("JWT_SECRET").is_err() {
+        // SAFETY: tests in this file run serially
+        unsafe { std::env::set_var("JWT_SECRET", "test-secret") };
+    }
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    create_test_domain(&pool, "testdomain.com", "Test Domain").await;
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+    sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+        .bind(bcrypt::hash("password123", 4).unwrap())
+        .bind(admin.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Mirrors create_app: only the public groups carry the maintenance layer
+    let app = Router::new()
+        .route("/health", get(|| async { "ok" }))
+        .nest("/auth", auth_router())
+        .nest(
+            "/admin",
+            AdminModule::routes().layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            )),
+        )
+        .merge(
+            BlogModule::routes()
+                .layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    domain_middleware,
+                ))
+                .layer(middleware::from_fn(analytics_middleware))
+                .layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    maintenance_middleware,
+                )),
+        )
+        .with_state(state);
+    let server = TestServer::new(app).unwrap();
+    let host = HeaderValue::from_static("testdomain.com");
+
+    let response = server.get("/posts").add_header("host", host.clone()).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    // A platform admin logs in and switches maintenance on
+    let response = server
+        .post("/auth/login")
+        .json(&json!({ "email": "platform@test.com", "password": "password123" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let token = response.json::<Value>()["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let auth = HeaderValue::from_str(&format!("Bearer {token}")).unwrap();
+
+    let response = server
+        .put("/admin/maintenance")
+        .add_header("authorization", auth.clone())
+        .json(&json!({ "enabled": true }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(response.json::<Value>()["enabled"], true);
+
+    let response = server.get("/posts").add_header("host", host.clone()).await;
+    assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.json::<Value>()["error"], "maintenance");
+
+    // Health, login and the admin panel keep working
+    assert_eq!(server.get("/health").await.status_code(), StatusCode::OK);
+    let response = server
+        .post("/auth/login")
+        .json(&json!({ "email": "platform@test.com", "password": "password123" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    let response = server
+        .put("/admin/maintenance")
+        .add_header("authorization", auth)
+        .json(&json!({ "enabled": false }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    let response = server.get("/posts").add_header("host", host).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    cleanup_test_db(&pool).await;
+}
+
+async fn slow_query_handler(State(pool): State<sqlx::PgPool>) -> StatusCode {
+    match sqlx::query("SELECT pg_sleep(1)").execute(&pool).await {
+        Ok(_) => StatusCode::OK,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_slow_queries_abort_within_budget() {
+    use api::services::query_timeout;
+    use std::time::{Duration, Instant};
+
+    unsafe {
+        std::env::set_var("DB_STATEMENT_TIMEOUT_MS", "200");
+        std::env::set_var("DB_EXPORT_STATEMENT_TIMEOUT_MS", "5000");
+    }
+
+    // Postgres cancels the statement itself
+    let pool =
+        sqlx::PgPool::connect_with(query_timeout::connect_options(&test_database_url()).unwrap())
+            .await
+            .unwrap();
+    let started = Instant::now();
+    let error = sqlx::query("SELECT pg_sleep(5)")
+        .execute(&pool)
+        .await
+        .unwrap_err();
+    assert!(query_timeout::is_timeout(&error));
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    // The request gets a 504, while export routes have the longer budget
+    let app = Router::new()
+        .route("/slow", get(slow_query_handler))
+        .route("/analytics/export", get(slow_query_handler))
+        .layer(middleware::from_fn_with_state(
+            query_timeout::RequestBudgets::from_env(),
+            api::middleware::query_timeout_middleware,
+        ))
+        .with_state(create_test_db().await);
+    let server = TestServer::new(app).unwrap();
+
+    let started = Instant::now();
+    let response = server.get("/slow").await;
+    assert_eq!(response.status_code(), StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(response.json::<serde_json::Value>()["error"], "timeout");
+    assert!(started.elapsed() < Duration::from_millis(900));
+
+    let response = server.get("/analytics/export").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    unsafe {
+        std::env::remove_var("DB_STATEMENT_TIMEOUT_MS");
+        std::env::remove_var("DB_EXPORT_STATEMENT_TIMEOUT_MS");
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_slow_handler_times_out_while_fast_one_succeeds() {
+    use api::services::query_timeout;
+    use std::time::Duration;
+
+    async fn slow_handler() -> &'static str {
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        "done"
+    }
+
+    // SAFETY: tests in this file run serially
+    unsafe {
+        std::env::set_var("DB_STATEMENT_TIMEOUT_MS", "200");
+        std::env::set_var("REQUEST_TIMEOUT_OVERRIDES", "/reports=2000");
+    }
+
+    let app = Router::new()
+        .route("/fast", get(|| async { "done" }))
+        .route("/slow", get(slow_handler))
+        .route("/reports/yearly", get(slow_handler))
+        .layer(middleware::from_fn_with_state(
+            query_timeout::RequestBudgets::from_env(),
+            api::middleware::query_timeout_middleware,
+        ));
+    let server = TestServer::new(app).unwrap();
+
+    assert_eq!(server.get("/fast").await.status_code(), StatusCode::OK);
+
+    let response = server.get("/slow").await;
+    assert_eq!(response.status_code(), StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(response.json::<serde_json::Value>()["error"], "timeout");
+
+    // Routes with an override get their own budget
+    let response = server.get("/reports/yearly").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    unsafe {
+        std::env::remove_var("DB_STATEMENT_TIMEOUT_MS");
+        std::env::remove_var("REQUEST_TIMEOUT_OVERRIDES");
+    }
+}
+
+#[tokio::test]
+async fn test_requests_beyond_the_limit_are_shed() {
+    use api::middleware::{ConcurrencyLimit, load_shed_middleware};
+    use tokio::sync::Notify;
+
+    let started = Arc::new(Notify::new());
+    let release = Arc::new(Notify::new());
+    let app = Router::new()
+        .route(
+            "/slow",
+            get({
+                let (started, release) = (started.clone(), release.clone());
+                move || async move {
+                    started.notify_one();
+                    release.notified().await;
+                    "done"
+                }
+            }),
+        )
+        .route("/fast", get(|| async { "done" }))
+        .route("/health", get(|| async { "ok" }))
+        .layer(middleware::from_fn_with_state(
+            ConcurrencyLimit::new(1),
+            load_shed_middleware,
+        ));
+    let server = TestServer::new(app).unwrap();
+
+    // While /slow holds the only slot, everything else but /health is shed
+    let (slow, ()) = tokio::join!(server.get("/slow"), async {
+        started.notified().await;
+
+        let response = server.get("/fast").await;
+        assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.header("retry-after"), "5");
+        assert_eq!(response.json::<serde_json::Value>()["error"], "overloaded");
+        assert_eq!(server.get("/health").await.status_code(), StatusCode::OK);
+
+        release.notify_one();
+    });
+    assert_eq!(slow.status_code(), StatusCode::OK);
+
+    // The slot is free again once the slow request is done
+    assert_eq!(server.get("/fast").await.status_code(), StatusCode::OK);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_domain_cors_allows_own_origin_only() {
+    use api::handlers::{HandlerModule, blog::BlogModule};
+    use api::middleware::domain_cors_middleware;
+    use axum::http::Method;
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+
+    for (hostname, name, origin) in [
+        ("blog-a.com", "Blog A", "https://www.blog-a.com"),
+        ("blog-b.com", "Blog B", "https://www.blog-b.com"),
+    ] {
+        let domain = create_test_domain(&pool, hostname, name).await;
+        sqlx::query("UPDATE domains SET cors_origins = $2 WHERE id = $1")
+            .bind(domain.id)
+            .bind(serde_json::json!([origin]))
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    // Mirrors create_app: CORS is resolved after the domain
+    let app = BlogModule::routes()
+        .layer(middleware::from_fn(domain_cors_middleware))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            domain_middleware,
+        ))
+        .layer(middleware::from_fn(analytics_middleware))
+        .with_state(state);
+    let server = TestServer::new(app).unwrap();
+    let host = HeaderValue::from_static("blog-a.com");
+    let own_origin = HeaderValue::from_static("https://www.blog-a.com");
+
+    let response = server
+        .get("/posts")
+        .add_header("host", host.clone())
+        .add_header("origin", own_origin.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert_eq!(
+        response.header("access-control-allow-origin"),
+        "https://www.blog-a.com"
+    );
+    assert_eq!(response.header("access-control-allow-credentials"), "true");
+
+    // Preflights from the domain's origin are answered without the handler
+    let response = server
+        .method(Method::OPTIONS, "/posts")
+        .add_header("host", host.clone())
+        .add_header("origin", own_origin)
+        .add_header(
+            "access-control-request-method",
+            HeaderValue::from_static("GET"),
+        )
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+    assert_eq!(
+        response.header("access-control-allow-origin"),
+        "https://www.blog-a.com"
+    );
+    assert!(
+        response
+            .header("access-control-allow-methods")
+            .to_str()
+            .unwrap()
+            .contains("GET")
+    );
+
+    // Another domain's origin is foreign here, preflight or not
+    let foreign = HeaderValue::from_static("https://www.blog-b.com");
+    let response = server
+        .get("/posts")
+        .add_header("host", host.clone())
+        .add_header("origin", foreign.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    assert!(
+        response
+            .headers()
+            .get("access-control-allow-origin")
+            .is_none()
+    );
+    assert_eq!(
+        response.json::<serde_json::Value>()["error"],
+        "origin_not_allowed"
+    );
+
+    let response = server
+        .method(Method::OPTIONS, "/posts")
+        .add_header("host", host.clone())
+        .add_header("origin", foreign)
+        .add_header(
+            "access-control-request-method",
+            HeaderValue::from_static("GET"),
+        )
+        .await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+
+    // Same-origin requests carry no Origin and are untouched
+    let response = server.get("/posts").add_header("host", host).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert!(
+        response
+            .headers()
+            .get("access-control-allow-origin")
+            .is_none()
+    );
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_metrics_scrape_requires_configured_token() {
+    let app = Router::new().route("/metrics", get(api::telemetry::metrics_handler));
+    let server = TestServer::new(app).unwrap();
+
+    unsafe { std::env::set_var("METRICS_TOKEN", "scrape-secret") };
+
+    let response = server.get("/metrics").await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["www-authenticate"], "Bearer");
+
+    let response = server
+        .get("/metrics")
+        .add_header("authorization", HeaderValue::from_static("Bearer wrong"))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+
+    let response = server
+        .get("/metrics")
+        .add_header(
+            "authorization",
+            HeaderValue::from_static("Bearer scrape-secret"),
+        )
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    assert!(
+        response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain")
+    );
+
+    // Without a token the endpoint stays open for local development
+    unsafe { std::env::remove_var("METRICS_TOKEN") };
+    let response = server.get("/metrics").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_rate_limit_update_applies_to_next_request() {
+    use api::handlers::{HandlerModule, admin::AdminModule};
+    use api::middleware::{ClientIp, RateLimitMiddleware};
+    use api::services::rate_limits;
+    use serde_json::json;
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState::new(pool.clone()));
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+
+    // Mirrors create_app: the limiter follows state.rate_limits
+    let limiter = RateLimitMiddleware::shared(state.rate_limits.read_only.clone());
+    let app = Router::new()
+        .route(
+            "/posts",
+            get(|| async { "ok" }).layer(middleware::from_fn(move |ip: ClientIp, req, next| {
+                let limiter = limiter.clone();
+                async move { limiter.apply(ip, req, next).await }
+            })),
+        )
+        .nest("/admin", AdminModule::routes().layer(Extension(admin)))
+        .with_state(state.clone());
+    let server = TestServer::new(app).unwrap();
+    let client = HeaderValue::from_static("203.0.113.7");
+
+    for _ in 0..3 {
+        let response = server
+            .get("/posts")
+            .add_header("x-forwarded-for", client.clone())
+            .await;
+        assert_eq!(response.status_code(), StatusCode::OK);
+    }
+
+    let mut settings = serde_json::to_value(state.rate_limits.current()).unwrap();
+    settings["read_only"] = json!({ "max_requests": 2, "window_seconds": 60 });
+    let response = server.put("/admin/rate-limits").json(&settings).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    // The lower limit applies from the next request, without a restart
+    for expected in [
+        StatusCode::OK,
+        StatusCode::OK,
+        StatusCode::TOO_MANY_REQUESTS,
+    ] {
+        let response = server
+            .get("/posts")
+            .add_header("x-forwarded-for", client.clone())
+            .await;
+        assert_eq!(response.status_code(), expected);
+    }
+
+    // Saved for the next startup
+    let saved = rate_limits::load(&pool).await.unwrap().unwrap();
+    assert_eq!(saved.read_only.max_requests.get(), 2);
+    assert_eq!(saved, state.rate_limits.current());
+
+    // A zero-length window is refused and leaves the limits alone
+    settings["read_only"] = json!({ "max_requests": 2, "window_seconds": 0 });
+    let response = server.put("/admin/rate-limits").json(&settings).await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    assert_eq!(state.rate_limits.current().read_only.window_seconds, 60);
+
+    cleanup_test_db(&pool).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn test_abusive_ip_is_banned_until_cooldown() {
+    use api::handlers::{HandlerModule, admin::AdminModule, auth::auth_router};
+    use api::middleware::abuse_middleware;
+    use api::services::abuse::{AbuseConfig, AbuseTracker};
+    use axum::extract::ConnectInfo;
+    use serde_json::{Value, json};
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    if std::env::var("JWT_SECRET").is_err() {
+        // SAFETY: tests in this file run serially
+        unsafe { std::env::set_var("JWT_SECRET", "test-secret") };
+    }
+
+    let pool = create_test_db().await;
+    let state = Arc::new(AppState {
+        abuse: AbuseTracker::new(AbuseConfig {
+            window: Duration::from_secs(60),
+            not_found_threshold: 3,
+            auth_failure_threshold: 3,
+            ban_duration: Duration::from_secs(1),
+        }),
+        ..AppState::new(pool.clone())
+    });
+
+    let admin = create_test_user(
+        &pool,
+        "platform@test.com",
+        "Platform Admin",
+        "platform_admin",
+    )
+    .await;
+    sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+        .bind(bcrypt::hash("password123", 4).unwrap())
+        .bind(admin.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // One server per client address, sharing the same tracker
+    let server_for = |addr: SocketAddr| {
+        let app = Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .nest("/auth", auth_router())
+            .nest(
+                "/admin",
+                AdminModule::routes().layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    auth_middleware,
+                )),
+            )
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                abuse_middleware,
+            ))
+            .layer(Extension(ConnectInfo(addr)))
+            .with_state(state.clone());
+        TestServer::new(app).unwrap()
+    };
+    let attacker_addr: SocketAddr = "203.0.113.7:4000".parse().unwrap();
+    let attacker = server_for(attacker_addr);
+    let admin_server = server_for("198.51.100.1:4000".parse().unwrap());
+
+    // Failed logins below the threshold don't ban
+    for _ in 0..3 {
+        assert_eq!(attacker.get("/health").await.status_code(), StatusCode::OK);
+        let response = attacker
+            .post("/auth/login")
+            .json(&json!({ "email": "platform@test.com", "password": "wrong-password" }))
+            .await;
+        assert_eq!(response.status_code(), StatusCode::UNAUTHORIZED);
+    }
+
+    // The third failure crossed the threshold
+    let response = attacker.get("/health").await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    assert!(response.headers().contains_key("retry-after"));
+    let body = response.json::<Value>();
+    assert_eq!(body["error"], "ip_banned");
+    assert_eq!(body["reason"], "auth_failure");
+
+    // Other clients are unaffected, and platform admins see the ban
+    let response = admin_server
+        .post("/auth/login")
+        .json(&json!({ "email": "platform@test.com", "password": "password123" }))
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let token = response.json::<Value>()["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let auth = HeaderValue::from_str(&format!("Bearer {token}")).unwrap();
+
+    let response = admin_server
+        .get("/admin/bans")
+        .add_header("authorization", auth.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let bans = response.json::<Value>();
+    assert_eq!(bans.as_array().unwrap().len(), 1);
+    assert_eq!(bans[0]["ip"], "203.0.113.7");
+    assert_eq!(bans[0]["reason"], "auth_failure");
+    assert_eq!(bans[0]["strikes"], 3);
+
+    // The ban lifts once the cooldown has passed
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(attacker.get("/health").await.status_code(), StatusCode::OK);
+
+    // Probing for missing pages bans too, until an admin clears it
+    for _ in 0..3 {
+        let response = attacker.get("/wp-login.php").await;
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    }
+    let response = attacker.get("/health").await;
+    assert_eq!(response.status_code(), StatusCode::FORBIDDEN);
+    assert_eq!(response.json::<Value>()["reason"], "not_found");
+
+    let response = admin_server
+        .delete(&format!("/admin/bans/{}", attacker_addr.ip()))
+        .add_header("authorization", auth.clone())
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+    assert_eq!(attacker.get("/health").await.status_code(), StatusCode::OK);
+
+    let response = admin_server
+        .delete(&format!("/admin/bans/{}", attacker_addr.ip()))
+        .add_header("authorization", auth)
+        .await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+
+    let audited: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM audit_log WHERE action = 'abuse.ban_clear'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(audited, 1);
+
+    cleanup_test_db(&pool).await;
+}
+
+/// A readiness probe with a fixed outcome
+struct FixedProbe {
+    name: &'static str,
+    critical: bool,
+    healthy: bool,
+}
+
+impl api::services::health::HealthCheck for FixedProbe {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn critical(&self) -> bool {
+        self.critical
+    }
+
+    fn check(&self) -> api::services::health::ProbeFuture<'_> {
+        Box::pin(async move {
+            if self.healthy {
+                Ok(())
+            } else {
+                Err(format!("{} unreachable", self.name))
+            }
+        })
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_readiness_aggregates_registered_probes() {
+    use api::services::health::{DatabaseCheck, HealthRegistry, readiness_handler};
+    use std::time::Duration;
+
+    let pool = create_test_db().await;
+    let ready = |registry: HealthRegistry| {
+        TestServer::new(
+            Router::new().route("/health/ready", get(readiness_handler).with_state(registry)),
+        )
+        .unwrap()
+    };
+    let registry = HealthRegistry::new(Duration::from_secs(2))
+        .with(DatabaseCheck(pool.clone()))
+        .with(FixedProbe {
+            name: "redis",
+            critical: false,
+            healthy: true,
+        });
+
+    // Everything up
+    let response = ready(registry.clone()).get("/health/ready").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["components"]["database"]["status"], "ok");
+    assert_eq!(body["components"]["redis"]["status"], "ok");
+
+    // An optional service down still takes traffic
+    let registry = registry.with(FixedProbe {
+        name: "smtp",
+        critical: false,
+        healthy: false,
+    });
+    let response = ready(registry.clone()).get("/health/ready").await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["status"], "degraded");
+    assert_eq!(body["components"]["smtp"]["status"], "error");
+    assert_eq!(body["components"]["smtp"]["error"], "smtp unreachable");
+    assert_eq!(body["components"]["smtp"]["critical"], false);
+
+    // A critical one down does not
+    let registry = registry.with(FixedProbe {
+        name: "geoip",
+        critical: true,
+        healthy: false,
+    });
+    let response = ready(registry).get("/health/ready").await;
+    assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["status"], "down");
+    assert_eq!(body["components"]["database"]["status"], "ok");
+    assert_eq!(body["components"]["geoip"]["critical"], true);
+
+    cleanup_test_db(&pool).await;
+}
@@ -45,7 +45,12 @@ async fn test_app_state_creation() {
         .await
         .expect("Failed to connect to database");
 
-    let app_state = Arc::new(AppState { db: pool });
+    // `AppState::new` loads the JWT settings
+    if std::env::var("JWT_SECRET").is_err() {
+        // SAFETY: no other test in this file reads the environment concurrently
+        unsafe { std::env::set_var("JWT_SECRET", "test-secret") };
+    }
+    let app_state = Arc::new(AppState::new(pool));
 
     // Test that we can use the app state
     let result = sqlx::query("SELECT COUNT(*) as count FROM domains")
@@ -0,0 +1,15 @@
+-- Migration: 002_create_media.sql
+-- Uploaded media (images) scoped by domain
+
+CREATE TABLE media (
+    id SERIAL PRIMARY KEY,
+    domain_id INTEGER NOT NULL REFERENCES domains(id) ON DELETE CASCADE,
+    storage_key VARCHAR(500) NOT NULL UNIQUE, -- path within the storage backend
+    filename VARCHAR(255) NOT NULL, -- original upload filename
+    content_type VARCHAR(100) NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    uploaded_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_media_domain_created ON media(domain_id, created_at DESC);
@@ -0,0 +1,15 @@
+-- Migration: 003_create_analytics_daily_summary.sql
+-- Per-day event counts kept after raw analytics_events rows are pruned
+
+CREATE TABLE analytics_daily_summary (
+    domain_id INTEGER NOT NULL REFERENCES domains(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    event_type VARCHAR(50) NOT NULL,
+    events BIGINT NOT NULL,
+    unique_visitors BIGINT NOT NULL, -- distinct IP addresses that day
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (domain_id, day, event_type)
+);
+
+-- Pruning deletes by age
+CREATE INDEX IF NOT EXISTS idx_analytics_events_created_at ON analytics_events(created_at);
@@ -0,0 +1,16 @@
+-- Migration: 004_create_daily_domain_stats.sql
+-- Nightly per-domain rollup of analytics_events read by the dashboards
+
+CREATE TABLE daily_domain_stats (
+    domain_id INTEGER NOT NULL REFERENCES domains(id) ON DELETE CASCADE,
+    day DATE NOT NULL, -- UTC day
+    page_views BIGINT NOT NULL DEFAULT 0,
+    post_views BIGINT NOT NULL DEFAULT 0,
+    searches BIGINT NOT NULL DEFAULT 0,
+    unique_visitors BIGINT NOT NULL DEFAULT 0, -- distinct IP addresses that day
+    sessions BIGINT NOT NULL DEFAULT 0, -- distinct sessions that day
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (domain_id, day)
+);
+
+CREATE INDEX idx_daily_domain_stats_day ON daily_domain_stats(day);
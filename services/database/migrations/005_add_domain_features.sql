@@ -0,0 +1,4 @@
+-- Migration: 005_add_domain_features.sql
+-- Per-domain feature flags, e.g. {"search": false}; unset features are enabled
+
+ALTER TABLE domains ADD COLUMN features JSONB NOT NULL DEFAULT '{}';
@@ -0,0 +1,14 @@
+-- Migration: 006_create_audit_log.sql
+-- Record of sensitive actions, including everything done under impersonation
+
+CREATE TABLE audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    actor_id INTEGER REFERENCES users(id) ON DELETE SET NULL, -- user the action ran as
+    impersonator_id INTEGER REFERENCES users(id) ON DELETE SET NULL, -- platform admin behind it, if any
+    action VARCHAR(255) NOT NULL,
+    details JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_log_created_at ON audit_log(created_at);
+CREATE INDEX idx_audit_log_impersonator ON audit_log(impersonator_id) WHERE impersonator_id IS NOT NULL;
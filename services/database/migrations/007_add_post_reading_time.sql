@@ -0,0 +1,28 @@
+-- Migration: 007_add_post_reading_time.sql
+-- Server-side word count and reading time, recomputed by the API on every post save
+
+ALTER TABLE posts
+    ADD COLUMN word_count INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN reading_time_minutes INTEGER NOT NULL DEFAULT 0;
+
+-- Rough backfill (HTML tags stripped, whitespace-separated words at 200 wpm);
+-- posts get the exact figures the next time they are saved
+UPDATE posts
+SET word_count = COALESCE(
+    array_length(
+        regexp_split_to_array(btrim(regexp_replace(content, '<[^>]*>', ' ', 'g')), '\s+'),
+        1
+    ),
+    0
+)
+WHERE btrim(regexp_replace(content, '<[^>]*>', ' ', 'g')) <> '';
+
+UPDATE posts SET reading_time_minutes = CEIL(word_count / 200.0)::INTEGER;
+
+-- posts.read_time (001) is superseded; keep a hand-set figure where the
+-- content gave no words, then drop it
+UPDATE posts
+SET reading_time_minutes = read_time
+WHERE reading_time_minutes = 0 AND read_time > 0;
+
+ALTER TABLE posts DROP COLUMN read_time;
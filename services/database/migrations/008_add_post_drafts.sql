@@ -0,0 +1,15 @@
+-- Migration: 008_add_post_drafts.sql
+-- Editor autosaves, one per post and user, kept apart from the live post
+
+CREATE TABLE post_drafts (
+    post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    category VARCHAR(100),
+    saved_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (post_id, user_id)
+);
+
+-- Pruning of stale autosaves
+CREATE INDEX idx_post_drafts_saved_at ON post_drafts(saved_at);
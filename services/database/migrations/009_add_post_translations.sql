@@ -0,0 +1,12 @@
+-- Migration: 009_add_post_translations.sql
+-- Per-locale title and content for posts; the post itself is the default language
+
+CREATE TABLE post_translations (
+    post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    locale VARCHAR(35) NOT NULL, -- lowercase BCP 47 tag, e.g. fr or pt-br
+    title VARCHAR(255) NOT NULL,
+    content TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (post_id, locale)
+);
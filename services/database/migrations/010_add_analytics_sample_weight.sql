@@ -0,0 +1,4 @@
+-- Migration: 010_add_analytics_sample_weight.sql
+-- Events each row stands for; page views on sampled domains store 1 / sample rate
+
+ALTER TABLE analytics_events ADD COLUMN sample_weight DOUBLE PRECISION NOT NULL DEFAULT 1.0;
@@ -0,0 +1,11 @@
+-- Migration: 011_add_user_soft_delete.sql
+-- Deleted users keep their row (and audit trail) until restored
+
+ALTER TABLE users ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
+
+-- Tokens issued at or before this are refused, so a restored user's old
+-- tokens stay dead
+ALTER TABLE users ADD COLUMN sessions_revoked_at TIMESTAMP WITH TIME ZONE;
+
+-- Listings and logins only look at live users
+CREATE INDEX idx_users_active ON users(id) WHERE deleted_at IS NULL;
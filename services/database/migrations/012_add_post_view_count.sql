@@ -0,0 +1,16 @@
+-- Migration: 012_add_post_view_count.sql
+-- Running total of post_view events, bumped as views are recorded
+
+ALTER TABLE posts ADD COLUMN view_count BIGINT NOT NULL DEFAULT 0;
+
+UPDATE posts p SET view_count = v.views
+FROM (
+    SELECT post_id, COUNT(*) AS views
+    FROM analytics_events
+    WHERE event_type = 'post_view' AND post_id IS NOT NULL
+    GROUP BY post_id
+) v
+WHERE v.post_id = p.id;
+
+-- "Most popular" listings
+CREATE INDEX idx_posts_domain_view_count ON posts(domain_id, view_count DESC);
@@ -0,0 +1,8 @@
+-- Migration: 013_create_settings.sql
+-- Platform-wide runtime settings that admins can change without a redeploy
+
+CREATE TABLE settings (
+    key VARCHAR(100) PRIMARY KEY,
+    value JSONB NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
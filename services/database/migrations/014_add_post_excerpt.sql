@@ -0,0 +1,24 @@
+-- Migration: 014_add_post_excerpt.sql
+-- Plain-text summary for listings and feeds, written by the editor or generated on save.
+-- `posts.excerpt` already exists (001) but is nullable and was never filled in
+
+-- Rough backfill for posts without an excerpt (HTML tags, link targets and
+-- markdown symbols stripped, cut at a word boundary near 200 characters);
+-- posts get the exact excerpt the next time they are saved. Excerpts that
+-- already exist are left alone
+UPDATE posts
+SET excerpt = COALESCE(
+    substring(
+        btrim(regexp_replace(
+            regexp_replace(content, '<[^>]*>|\]\([^)]*\)|[#*`>\[\]~]', ' ', 'g'),
+            '\s+', ' ', 'g'
+        ))
+        FROM '^.{0,200}(?=\s|$)'
+    ),
+    ''
+)
+WHERE excerpt IS NULL;
+
+ALTER TABLE posts
+    ALTER COLUMN excerpt SET DEFAULT '',
+    ALTER COLUMN excerpt SET NOT NULL;
@@ -0,0 +1,17 @@
+-- Migration: 015_add_password_policy.sql
+-- Forced password changes and the history used to stop password reuse
+
+ALTER TABLE users ADD COLUMN must_change_password BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE password_history (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    password_hash VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_password_history_user ON password_history(user_id, created_at DESC);
+
+-- Current passwords count as the most recent entry
+INSERT INTO password_history (user_id, password_hash)
+SELECT id, password_hash FROM users;
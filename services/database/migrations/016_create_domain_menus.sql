@@ -0,0 +1,8 @@
+-- Migration: 016_create_domain_menus.sql
+-- Navigation menu per domain, an ordered tree of items kept as JSON
+
+CREATE TABLE domain_menus (
+    domain_id INTEGER PRIMARY KEY REFERENCES domains(id) ON DELETE CASCADE,
+    items JSONB NOT NULL DEFAULT '[]'::jsonb,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
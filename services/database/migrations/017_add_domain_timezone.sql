@@ -0,0 +1,4 @@
+-- Migration: 017_add_domain_timezone.sql
+-- IANA time zone analytics days and hours are bucketed in, per domain
+
+ALTER TABLE domains ADD COLUMN timezone VARCHAR(64) NOT NULL DEFAULT 'UTC';
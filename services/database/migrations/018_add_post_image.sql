@@ -0,0 +1,4 @@
+-- Migration: 018_add_post_image.sql
+-- Optional image shown when a post is shared; the domain's social_image otherwise
+
+ALTER TABLE posts ADD COLUMN image_url TEXT;
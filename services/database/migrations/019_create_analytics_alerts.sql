@@ -0,0 +1,24 @@
+-- Migration: 019_create_analytics_alerts.sql
+-- Traffic alerts per domain, checked periodically against recent analytics
+
+CREATE TABLE analytics_alerts (
+    id SERIAL PRIMARY KEY,
+    domain_id INTEGER NOT NULL REFERENCES domains(id) ON DELETE CASCADE,
+    metric VARCHAR(32) NOT NULL,       -- page_views, post_views, unique_visitors, searches, sessions
+    comparison VARCHAR(8) NOT NULL,    -- above, below
+    threshold BIGINT NOT NULL,
+    window_minutes INTEGER NOT NULL DEFAULT 60,
+    cooldown_minutes INTEGER NOT NULL DEFAULT 60,
+    webhook_url TEXT,
+    email VARCHAR(255),
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Whether the threshold was crossed at the last check; alerts fire on the
+    -- transition, not on every check while it stays crossed
+    is_firing BOOLEAN NOT NULL DEFAULT FALSE,
+    last_triggered_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CHECK (webhook_url IS NOT NULL OR email IS NOT NULL)
+);
+
+CREATE INDEX idx_analytics_alerts_domain ON analytics_alerts(domain_id);
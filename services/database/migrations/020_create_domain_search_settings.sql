@@ -0,0 +1,16 @@
+-- Migration: 020_create_domain_search_settings.sql
+-- Full-text post search: per-domain field weights, synonyms and stopwords,
+-- and an index on the weighted document searches match against
+
+CREATE TABLE domain_search_settings (
+    domain_id INTEGER PRIMARY KEY REFERENCES domains(id) ON DELETE CASCADE,
+    settings JSONB NOT NULL DEFAULT '{}'::jsonb,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Must match services::search::SEARCH_DOCUMENT exactly to be used
+CREATE INDEX idx_posts_search ON posts USING GIN ((
+    setweight(to_tsvector('english', title), 'A')
+    || setweight(to_tsvector('english', excerpt), 'B')
+    || setweight(to_tsvector('english', content), 'C')
+));
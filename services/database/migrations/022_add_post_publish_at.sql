@@ -0,0 +1,6 @@
+-- Migration: 022_add_post_publish_at.sql
+-- When a scheduled post is due to go out, shown on the admin content calendar
+
+ALTER TABLE posts ADD COLUMN publish_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX idx_posts_domain_publish_at ON posts(domain_id, publish_at) WHERE publish_at IS NOT NULL;
@@ -0,0 +1,5 @@
+-- Migration: 023_add_domain_cors_origins.sql
+-- Origins allowed to call a domain's blog and session routes cross-origin,
+-- e.g. ["https://blog.example.com"]; empty falls back to CORS_ORIGINS
+
+ALTER TABLE domains ADD COLUMN cors_origins JSONB NOT NULL DEFAULT '[]';
@@ -0,0 +1,4 @@
+-- Migration: 025_add_analytics_visitor_index.sql
+-- Lets /analytics/visitors look up a visitor's earlier events per domain
+
+CREATE INDEX idx_analytics_domain_ip_created ON analytics_events(domain_id, ip_address, created_at);
@@ -0,0 +1,5 @@
+-- Migration: 026_add_domain_quota.sql
+-- Storage limits for a domain, e.g. {"max_posts": 500, "max_media_bytes": 1073741824};
+-- a missing or null limit means unlimited
+
+ALTER TABLE domains ADD COLUMN quota JSONB NOT NULL DEFAULT '{}';
@@ -0,0 +1,6 @@
+-- Migration: 027_add_post_pinned.sql
+-- Pinned posts come first in the public post listing, whatever the sort
+
+ALTER TABLE posts ADD COLUMN pinned BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX idx_posts_domain_pinned ON posts(domain_id) WHERE pinned;
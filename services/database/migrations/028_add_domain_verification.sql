@@ -0,0 +1,6 @@
+-- Migration: 028_add_domain_verification.sql
+-- DNS ownership checks for custom domains: the token a domain publishes in a
+-- TXT record, and when its DNS was last found pointing at the platform
+
+ALTER TABLE domains ADD COLUMN verification_token VARCHAR(64) NOT NULL DEFAULT md5(random()::text);
+ALTER TABLE domains ADD COLUMN verified_at TIMESTAMP WITH TIME ZONE;
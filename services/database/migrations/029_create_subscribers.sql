@@ -0,0 +1,15 @@
+-- Migration: 029_create_subscribers.sql
+-- Newsletter subscribers per domain, confirmed through a double opt-in email.
+-- The pending confirmation token is kept as a bcrypt hash, like reset tokens
+
+CREATE TABLE subscribers (
+    id SERIAL PRIMARY KEY,
+    domain_id INTEGER NOT NULL REFERENCES domains(id) ON DELETE CASCADE,
+    email VARCHAR(255) NOT NULL,
+    token_hash VARCHAR(255),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    confirmation_sent_at TIMESTAMP WITH TIME ZONE,
+    confirmed_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE UNIQUE INDEX idx_subscribers_domain_email ON subscribers(domain_id, lower(email));
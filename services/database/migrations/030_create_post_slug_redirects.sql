@@ -0,0 +1,13 @@
+-- Migration: 030_create_post_slug_redirects.sql
+-- Slugs a post was published under before it was renamed, so old links can
+-- be redirected to wherever the post lives now
+
+CREATE TABLE post_slug_redirects (
+    domain_id INTEGER NOT NULL REFERENCES domains(id) ON DELETE CASCADE,
+    old_slug VARCHAR(255) NOT NULL,
+    post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (domain_id, old_slug)
+);
+
+CREATE INDEX idx_post_slug_redirects_post ON post_slug_redirects(post_id);
@@ -0,0 +1,23 @@
+-- Migration: 031_create_post_authors.sql
+-- Users credited on a post, as author or contributor. Names are read from
+-- users at request time, so a rename shows up on every post. posts.author
+-- stays as the byline of posts nobody is linked to
+
+CREATE TABLE post_authors (
+    post_id INTEGER NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role VARCHAR(20) NOT NULL DEFAULT 'author' CHECK (role IN ('author', 'contributor')),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (post_id, user_id)
+);
+
+CREATE INDEX idx_post_authors_user ON post_authors(user_id);
+
+-- Link existing posts to the user whose name they carry, where exactly one
+-- user has that name
+INSERT INTO post_authors (post_id, user_id, role, created_at)
+SELECT p.id, MIN(u.id), 'author', p.created_at
+FROM posts p
+JOIN users u ON u.name = p.author AND u.deleted_at IS NULL
+GROUP BY p.id, p.created_at
+HAVING COUNT(u.id) = 1;
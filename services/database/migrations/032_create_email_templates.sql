@@ -0,0 +1,17 @@
+-- Migration: 032_create_email_templates.sql
+-- Subject and body overrides for the emails the platform sends. A row with
+-- no domain_id is the platform-wide template; a domain's own row wins over it
+
+CREATE TABLE email_templates (
+    id SERIAL PRIMARY KEY,
+    domain_id INTEGER REFERENCES domains(id) ON DELETE CASCADE,
+    kind VARCHAR(50) NOT NULL,
+    subject VARCHAR(200) NOT NULL,
+    body TEXT NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_email_templates_domain_kind ON email_templates(domain_id, kind)
+    WHERE domain_id IS NOT NULL;
+CREATE UNIQUE INDEX idx_email_templates_platform_kind ON email_templates(kind)
+    WHERE domain_id IS NULL;
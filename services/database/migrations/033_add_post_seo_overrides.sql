@@ -0,0 +1,8 @@
+-- Migration: 033_add_post_seo_overrides.sql
+-- Per-post search and sharing overrides; unset fields fall back to the post
+-- title and excerpt, then the domain's seo_config. noindex posts stay
+-- readable but are hidden from search engines and the sitemap
+
+ALTER TABLE posts ADD COLUMN meta_title VARCHAR(200);
+ALTER TABLE posts ADD COLUMN meta_description VARCHAR(500);
+ALTER TABLE posts ADD COLUMN noindex BOOLEAN NOT NULL DEFAULT FALSE;
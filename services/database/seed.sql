@@ -57,7 +57,7 @@ INSERT INTO user_domain_permissions (user_id, domain_id, role) VALUES
 (5, 3, 'editor');    -- Business Blog: editor
 
 -- Sample posts
-INSERT INTO posts (domain_id, title, slug, content, excerpt, author, category, status, read_time, published_at) VALUES
+INSERT INTO posts (domain_id, title, slug, content, excerpt, author, category, status, reading_time_minutes, published_at) VALUES
 -- Tech Blog Posts
 (1, 'The Future of AI in 2024', 'future-of-ai-2024', 
  'Artificial Intelligence continues to evolve at an unprecedented pace. From large language models to computer vision breakthroughs, 2024 has been a landmark year for AI development. In this post, we explore the key trends shaping the AI landscape and what developers need to know to stay ahead of the curve.',
@@ -121,7 +121,7 @@ SELECT
     p.id,
     'post_view',
     '/posts/' || p.slug,
-    json_build_object('read_time', p.read_time, 'category', p.category)
+    json_build_object('read_time', p.reading_time_minutes, 'category', p.category)
 FROM user_sessions us
 CROSS JOIN domains d
 CROSS JOIN posts p